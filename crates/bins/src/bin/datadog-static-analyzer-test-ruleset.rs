@@ -1,14 +1,19 @@
 use cli::datadog_utils::get_ruleset;
+use cli::local_rules::get_rulesets_from_directory;
 use kernel::analysis::analyze::analyze;
 use kernel::model::analysis::AnalysisOptions;
+use kernel::model::common::ColumnUnit;
 use kernel::model::rule::Rule;
+use kernel::model::ruleset::RuleSet;
 
 use anyhow::{Error, Result};
 use getopts::Options;
 use kernel::arguments::ArgumentProvider;
 use kernel::model::rule_test::RuleTest;
 use kernel::utils::decode_base64_string;
+use std::collections::HashSet;
 use std::env;
+use std::path::Path;
 use std::process::exit;
 
 fn print_usage(program: &str, opts: Options) {
@@ -23,6 +28,11 @@ fn test_rule(rule: &Rule, test: &RuleTest) -> Result<String> {
         log_output: true,
         use_debug: true,
         ignore_generated_files: false,
+        report_parse_errors: false,
+        column_unit: ColumnUnit::Bytes,
+        generated_files_rules: HashSet::new(),
+        profile_rules: false,
+        nondeterministic_rules: false,
     };
     let rules = vec![rule_internal];
     let analyze_result = analyze(
@@ -47,7 +57,7 @@ fn test_rule(rule: &Rule, test: &RuleTest) -> Result<String> {
                 test.annotation_count,
                 first_results.violations.len(),
                 first_results.execution_error.clone().unwrap_or("none".to_string()),
-                first_results.output.clone().unwrap_or("none".to_string()),
+                first_results.output_text().unwrap_or("none".to_string()),
                 first_results.errors.join(",")
             );
             Err(Error::msg(error))
@@ -63,6 +73,12 @@ fn main() {
     let mut opts = Options::new();
 
     opts.optmulti("r", "ruleset", "rules to test", "python-security");
+    opts.optopt(
+        "l",
+        "local",
+        "test the rules of a directory of local rules",
+        "/path/to/rules",
+    );
     opts.optflag("h", "help", "print this help");
     opts.optflag("s", "staging", "use staging");
     opts.optflag("t", "include-testing-rules", "include testing rules");
@@ -84,29 +100,46 @@ fn main() {
     let mut num_failures = 0;
     for ruleset in rulesets {
         match get_ruleset(ruleset.as_str(), use_staging) {
-            Ok(r) => {
-                println!("Testing ruleset {}", r.name);
-                for rule in r.rules.clone() {
-                    println!("   rule {} ... ", rule.name);
-                    let c = rule.clone();
-                    for t in rule.tests {
-                        match test_rule(&c, &t) {
-                            Ok(_) => {
-                                println!("      test {} passed", t.filename);
-                            }
-                            Err(e) => {
-                                println!("      test {} FAILED ({})", t.filename, e);
-                                num_failures += 1;
-                            }
-                        }
-                    }
+            Ok(r) => num_failures += test_ruleset(&r),
+            Err(e) => {
+                eprintln!("error when trying to fetch the ruleset: {}", e);
+            }
+        }
+    }
+    if let Some(directory) = matches.opt_str("l") {
+        match get_rulesets_from_directory(Path::new(&directory)) {
+            Ok(local_rulesets) => {
+                for r in &local_rulesets {
+                    num_failures += test_ruleset(r);
                 }
             }
             Err(e) => {
-                eprintln!("error when trying to fetch the ruleset: {}", e);
+                eprintln!("error when reading the local rules: {:#}", e);
+                num_failures += 1;
             }
         }
     }
 
     exit(num_failures)
 }
+
+/// Runs the tests of the rules of a ruleset, and returns the number of failed tests.
+fn test_ruleset(ruleset: &RuleSet) -> i32 {
+    let mut num_failures = 0;
+    println!("Testing ruleset {}", ruleset.name);
+    for rule in &ruleset.rules {
+        println!("   rule {} ... ", rule.name);
+        for t in &rule.tests {
+            match test_rule(rule, t) {
+                Ok(_) => {
+                    println!("      test {} passed", t.filename);
+                }
+                Err(e) => {
+                    println!("      test {} FAILED ({})", t.filename, e);
+                    num_failures += 1;
+                }
+            }
+        }
+    }
+    num_failures
+}
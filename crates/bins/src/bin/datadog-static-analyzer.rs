@@ -1,46 +1,90 @@
+use cli::blame::Blamer;
+use cli::codeowners::CodeOwners;
 use cli::config_file::read_config_file;
 use cli::datadog_utils::{
-    get_all_default_rulesets, get_diff_aware_information, get_rules_from_rulesets,
+    get_all_default_rulesets, get_datadog_variable_value, get_diff_aware_information,
+    UploadMetadata,
 };
 use cli::file_utils::{
-    are_subdirectories_safe, filter_files_by_diff_aware_info, filter_files_by_size,
-    filter_files_for_language, get_files, read_files_from_gitignore,
+    are_subdirectories_safe, filter_files_by_diff_aware_info, filter_files_for_language, get_files,
+    read_editorconfig, read_file_content, select_files, FilePolicy,
 };
 use cli::rule_utils::{
-    count_violations_by_severities, get_languages_for_rules, get_rulesets_from_file,
+    count_violations_by_severities, get_deprecated_rules_warnings, get_languages_for_rules,
+    get_rules_from_config_file, get_rulesets_from_file,
 };
 use itertools::Itertools;
-use kernel::analysis::analyze::analyze;
+use kernel::analysis::analyze::analyze_with;
+use kernel::analysis::cancellation::CancellationToken;
+use kernel::analysis::ddsa_lib::JsRuntimePool;
+use kernel::analysis::parser_pool::parser_pool_stats;
+use kernel::analysis::sfc::{analyze_sfc_with, is_sfc_file};
+use kernel::analysis::tree_sitter::get_grammar_version;
+use kernel::api::{analyze_file, AnalysisRequest};
 use kernel::constants::{CARGO_VERSION, VERSION};
 use kernel::model::analysis::{AnalysisOptions, ERROR_RULE_TIMEOUT};
-use kernel::model::common::{Language, OutputFormat};
-use kernel::model::rule::{Rule, RuleInternal, RuleResult, RuleSeverity};
+use kernel::model::common::{ColumnUnit, Interner, Language, OutputFormat, ALL_LANGUAGES};
+use kernel::model::rule::{Rule, RuleInternal, RuleProfile, RuleResult, RuleSeverity};
 
 use anyhow::{Context, Result};
 use cli::constants::DEFAULT_MAX_FILE_SIZE_KB;
-use cli::csv;
-use cli::model::cli_configuration::CliConfiguration;
+use cli::dedup::merge_duplicate_violations;
+use cli::fixes::{apply_fixes, introduced_violations, FixSummary};
+use cli::git_utils::get_git_metadata;
+use cli::gitignore::GitIgnore;
+use cli::journal::ScanJournal;
+use cli::model::cli_configuration::{CliConfiguration, NonUtf8Behavior, SymlinkBehavior};
 use cli::model::datadog_api::DiffAwareData;
-use cli::sarif::sarif_utils::{
-    generate_sarif_report, SarifReportMetadata, SarifRule, SarifRuleResult,
+use cli::model::skipped_file::{SkipReason, SkippedFile};
+use cli::output::{
+    output_format_from_name, write_json_results, AnalysisReport, FileSink, OutputSink, OutputSpec,
+    PublisherSink, UploadSink, STDOUT,
 };
-use cli::secrets::{SecretResult, SecretRule};
+use cli::plugins::load_plugin;
+use cli::policies::evaluate_policies;
+use cli::progress::{Progress, ProgressMode};
+use cli::publishers::publisher_from_env;
+use cli::result_cache::{store_from_location, ResultCache};
+use cli::rule_errors::RuleErrorSummary;
+use cli::sarif::sarif_utils::SarifReportMetadata;
+use cli::schema::get_output_schema;
+use cli::secrets::{group_identical_secrets, SecretResult, SecretRule};
+use cli::severity_remaps::apply_severity_remaps;
+use cli::shard::Shard;
+use cli::snippets::add_snippets;
+use cli::spill::{AggregatedResults, ResultSpill};
 use cli::violations_table;
 use getopts::Options;
-use indicatif::ProgressBar;
 use kernel::arguments::ArgumentProvider;
-use kernel::model::config_file::{ConfigFile, PathConfig};
+use kernel::model::config_file::{
+    ConfigFile, FileLanguagesConfig, PathConfig, PolicyConfig, SeverityRemapConfig,
+};
 use kernel::path_restrictions::PathRestrictions;
+use kernel::plugin::PluginRegistry;
 use kernel::rule_overrides::RuleOverrides;
 use rayon::prelude::*;
-use std::collections::HashMap;
-use std::io::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::process::exit;
+use std::sync::Arc;
 use std::time::{Instant, SystemTime};
 use std::{env, fs};
 
+mod datadog_static_analyzer_ast;
+mod datadog_static_analyzer_explain;
+mod datadog_static_analyzer_init_rule;
+mod datadog_static_analyzer_lint_rules;
+mod datadog_static_analyzer_merge_results;
+mod datadog_static_analyzer_query;
+mod datadog_static_analyzer_rules;
+#[cfg(feature = "secrets")]
+mod datadog_static_analyzer_secrets;
+mod datadog_static_analyzer_server;
+
 fn print_usage(program: &str, opts: Options) {
-    let brief = format!("Usage: {} FILE [options]", program);
+    let brief = format!(
+        "Usage: {} FILE [options]\n       {} server [options]\n       {} secrets --staged [options]\n       {} list-rules|describe-rule [options]\n       {} explain <file>:<line> --rule <name> [options]\n       {} init-rule --language <language> --name <ruleset>/<rule> [options]\n       {} query --language <language> --query <pattern> [options] <file>\n       {} ast [--json] [--at <line>:<col>] <file>\n       {} lint-rules [-r /path/to/rules] [options]\n       {} merge-results -o <output> [options] <report>...",
+        program, program, program, program, program, program, program, program, program, program
+    );
     print!("{}", opts.usage(&brief));
 }
 
@@ -51,12 +95,6 @@ fn print_configuration(configuration: &CliConfiguration) {
         "rule file"
     };
 
-    let output_format_str = match configuration.output_format {
-        OutputFormat::Csv => "csv",
-        OutputFormat::Sarif => "sarif",
-        OutputFormat::Json => "json",
-    };
-
     let languages = get_languages_for_rules(&configuration.rules);
     let languages_string: Vec<String> = languages.iter().map(|l| l.to_string()).collect();
     let ignore_paths_str = if configuration.path_config.ignore.is_empty() {
@@ -87,8 +125,10 @@ fn print_configuration(configuration: &CliConfiguration) {
     #[cfg(feature = "secrets")]
     println!("validate secrets    : {}", configuration.validate_secrets);
 
-    println!("output file         : {}", configuration.output_file);
-    println!("output format       : {}", output_format_str);
+    println!(
+        "outputs             : {}",
+        configuration.outputs.iter().join(",")
+    );
     println!("ignore paths        : {}", ignore_paths_str);
     println!("only paths          : {}", only_paths_str);
     println!("ignore gitignore    : {}", configuration.ignore_gitignore);
@@ -103,10 +143,27 @@ fn print_configuration(configuration: &CliConfiguration) {
         configuration.ignore_generated_files
     );
     println!("rules languages     : {}", languages_string.join(","));
+    if configuration.plugins.checker_names().next().is_some() {
+        println!(
+            "plugin checkers     : {}",
+            configuration.plugins.checker_names().join(",")
+        );
+    }
     println!(
         "max file size       : {} kb",
         configuration.max_file_size_kb
     );
+    println!(
+        "follow symlinks     : {}",
+        configuration.symlink_behavior == SymlinkBehavior::Follow
+    );
+    println!(
+        "non-UTF-8 files     : {}",
+        match configuration.non_utf8_behavior {
+            NonUtf8Behavior::Skip => "skip",
+            NonUtf8Behavior::Lossy => "lossy",
+        }
+    );
 }
 
 /// Utility function to convert rules to rules internal.
@@ -151,8 +208,97 @@ fn convert_rules_to_rules_internal(
     rules
 }
 
+/// Analyzes files again (e.g. after their fixes were applied), with the rules and the plugins of
+/// the analysis. The files are relative to `directory`.
+fn reanalyze_files(
+    configuration: &CliConfiguration,
+    directory: &std::path::Path,
+    files: &[String],
+    analysis_options: &AnalysisOptions,
+) -> Result<Vec<RuleResult>> {
+    let paths = files.iter().map(|f| directory.join(f)).collect::<Vec<_>>();
+    let mut languages = get_languages_for_rules(&configuration.rules);
+    for language in configuration.plugins.languages() {
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+    let mut results = vec![];
+    for language in &languages {
+        let rules = convert_rules_to_rules_internal(configuration, language)?;
+        for path in filter_files_for_language(&paths, language, &configuration.file_languages) {
+            let Some(relative_path) = path.strip_prefix(directory).ok().and_then(|p| p.to_str())
+            else {
+                continue;
+            };
+            let Ok(content) = read_file_content(&path, configuration.non_utf8_behavior) else {
+                continue;
+            };
+            let request = AnalysisRequest::new(relative_path, Arc::clone(&content))
+                .language(*language)
+                .rules(rules.iter().filter(|r| {
+                    configuration
+                        .path_restrictions
+                        .rule_applies(&r.name, relative_path)
+                }))
+                .argument_provider(&configuration.argument_provider)
+                .options(analysis_options.clone());
+            results.extend(analyze_file(&request)?.rule_results);
+            results.extend(configuration.plugins.check_file(
+                *language,
+                relative_path,
+                &content,
+                |name| {
+                    configuration
+                        .path_restrictions
+                        .rule_applies(name, relative_path)
+                },
+            ));
+        }
+    }
+    Ok(results)
+}
+
 fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
+    // `datadog-static-analyzer server` starts the analysis server, with its own options.
+    if args.get(1).map(String::as_str) == Some("server") {
+        rocket::execute(datadog_static_analyzer_server::start());
+        return Ok(());
+    }
+    // `datadog-static-analyzer secrets` scans the changes of a repository, for git hooks.
+    #[cfg(feature = "secrets")]
+    if args.get(1).map(String::as_str) == Some("secrets") {
+        return datadog_static_analyzer_secrets::run(&args[0], &args[2..]);
+    }
+    // `datadog-static-analyzer list-rules|describe-rule` prints the rules used for a directory.
+    if let Some(command @ ("list-rules" | "describe-rule")) = args.get(1).map(String::as_str) {
+        return datadog_static_analyzer_rules::run(&args[0], command, &args[2..]);
+    }
+    // `datadog-static-analyzer explain` traces the execution of a rule on a line of a file.
+    if args.get(1).map(String::as_str) == Some("explain") {
+        return datadog_static_analyzer_explain::run(&args[0], &args[2..]);
+    }
+    // `datadog-static-analyzer init-rule` creates the directory of a new local rule.
+    if args.get(1).map(String::as_str) == Some("init-rule") {
+        return datadog_static_analyzer_init_rule::run(&args[0], &args[2..]);
+    }
+    // `datadog-static-analyzer query` prints the captures of a tree-sitter query on a file.
+    if args.get(1).map(String::as_str) == Some("query") {
+        return datadog_static_analyzer_query::run(&args[0], &args[2..]);
+    }
+    // `datadog-static-analyzer ast` prints the tree-sitter tree of a file.
+    if args.get(1).map(String::as_str) == Some("ast") {
+        return datadog_static_analyzer_ast::run(&args[0], &args[2..]);
+    }
+    // `datadog-static-analyzer lint-rules` checks the definitions of rules.
+    if args.get(1).map(String::as_str) == Some("lint-rules") {
+        return datadog_static_analyzer_lint_rules::run(&args[0], &args[2..]);
+    }
+    // `datadog-static-analyzer merge-results` merges the reports of the shards of an analysis.
+    if args.get(1).map(String::as_str) == Some("merge-results") {
+        return datadog_static_analyzer_merge_results::run(&args[0], &args[2..]);
+    }
     let program = args[0].clone();
     let mut opts = Options::new();
     #[allow(unused_assignments)]
@@ -176,23 +322,74 @@ fn main() -> Result<()> {
     opts.optopt(
         "r",
         "rules",
-        "rules to use (json file)",
+        "rules to use (json file, or directory of local rules)",
         "/path/to/rules.json",
     );
     opts.optopt("d", "debug", "use debug mode", "yes/no");
-    opts.optopt("f", "format", "format of the output file", "json/sarif/csv");
-    opts.optopt("o", "output", "output file name", "output.json");
+    opts.optopt(
+        "f",
+        "format",
+        "format of the output files that don't specify one",
+        "json/sarif/csv/html/pretty",
+    );
+    opts.optmulti(
+        "o",
+        "output",
+        "output file name, optionally prefixed with its format; can be repeated to write several reports, and - writes to stdout (default: a readable report on stdout)",
+        "[sarif=]output.sarif",
+    );
     opts.optflag(
         "",
         "print-violations",
         "print a list with all the violations that were found",
     );
+    opts.optflagopt(
+        "",
+        "include-snippets",
+        "embed the code of each violation in the report, with the given number of lines of context (default: 0)",
+        "context-lines",
+    );
+    opts.optflagopt(
+        "",
+        "blame",
+        "add the last commit that changed the lines of each violation (author, sha and date) to the report, for at most N files (default: 1000)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "shard",
+        "only analyze the i-th of N parts of the files, to split an analysis across N jobs (see merge-results)",
+        "i/N",
+    );
+    opts.optopt(
+        "",
+        "group-by",
+        "print a summary of the violations grouped by the owners of their files (from CODEOWNERS)",
+        "owner",
+    );
+    opts.optflagopt(
+        "",
+        "summary",
+        "print only the number of violations by severity, with the N noisiest rules and directories (default: 10)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "progress",
+        "how to report the progress of the analysis: a progress bar in a terminal, a JSON line every few seconds on stderr, or nothing (default: bar)",
+        "bar|json|none",
+    );
     opts.optopt(
         "",
         "fail-on-any-violation",
         "exit a non-zero return code if there is one violation",
         "error,warning,notice,none",
     );
+    opts.optflag(
+        "",
+        "fail-on-rule-error",
+        "exit with the code 3 if a rule timed out or failed on a file, as the results are then incomplete",
+    );
     opts.optopt(
         "c",
         "cpus",
@@ -222,6 +419,16 @@ fn main() -> Result<()> {
         "performance-statistics",
         "enable performance statistics",
     );
+    opts.optflag(
+        "",
+        "profile-rules",
+        "report the resources used by each rule execution (v8 heap, CPU and wall time, captures)",
+    );
+    opts.optflag(
+        "",
+        "nondeterministic-rules",
+        "let the rules use the real Math.random and Date instead of seeded random numbers and a frozen time (disables --cache)",
+    );
     opts.optflag("s", "staging", "use staging");
     opts.optflag("t", "include-testing-rules", "include testing rules");
     opts.optflag(
@@ -229,16 +436,223 @@ fn main() -> Result<()> {
         "add-git-info",
         "add Git information to the SARIF report",
     );
+    opts.optflag(
+        "",
+        "no-global-gitignore",
+        "do not ignore the files listed in the global excludes file of git (core.excludesFile)",
+    );
+    opts.optflag(
+        "",
+        "follow-symlinks",
+        "follow symbolic links that resolve inside the repository",
+    );
+    opts.optopt(
+        "",
+        "non-utf8-files",
+        "how to handle files that are not valid UTF-8 (default: skip)",
+        "skip/lossy",
+    );
+    opts.optflag(
+        "",
+        "report-parse-errors",
+        "report the syntax errors of the analyzed files as violations of a `parse-error` rule",
+    );
+    opts.optopt(
+        "",
+        "column-unit",
+        "unit of the columns of the reported positions (default: bytes)",
+        "bytes/chars/utf16",
+    );
+    opts.optflag(
+        "",
+        "merge-duplicate-violations",
+        "merge identical violations reported by different rules",
+    );
+    opts.optflag(
+        "",
+        "fix",
+        "apply the automatic fixes of the violations to the analyzed files",
+    );
+    opts.optflag(
+        "",
+        "fix-unsafe",
+        "also apply the suggested fixes, which may change the behavior of the code (implies --fix)",
+    );
+    opts.optopt(
+        "",
+        "fix-iterations",
+        "re-analyze the fixed files and apply their fixes again, up to N times in total, until no fix applies (default: 1)",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "print-schema",
+        "print the JSON Schema of the report for an output format and exit",
+        "json,sarif",
+    );
+    opts.optflag(
+        "",
+        "list-files",
+        "list the files that would be analyzed (with their languages and rulesets) and why the other files are excluded, without analyzing them",
+    );
+    opts.optopt(
+        "",
+        "skip-report",
+        "write the list of files that were skipped (and why) to a JSON file",
+        "skipped.json",
+    );
+    opts.optopt(
+        "",
+        "max-buffered-violations",
+        "keep at most N violations in memory during the analysis: the other results are spilled to temporary files, and the JSON reports are written from these files",
+        "N",
+    );
+    opts.optopt(
+        "",
+        "resume",
+        "record the results of each file in a journal, and reuse the results of the files that an interrupted analysis with the same configuration already analyzed",
+        "journal.jsonl",
+    );
+    opts.optopt(
+        "",
+        "cache",
+        "reuse the results of the files that didn't change since an analysis with the same configuration, from a directory or an HTTP cache (e.g. an S3 compatible bucket)",
+        "/path/to/cache|https://...",
+    );
+    opts.optmulti(
+        "",
+        "plugin",
+        "load a native plugin, whose checkers analyze the files like rules (accepts multiple)",
+        "/path/to/libplugin.so",
+    );
+    opts.optflag(
+        "",
+        "allow-plugins",
+        "load the plugins listed in the configuration file",
+    );
+    opts.optopt(
+        "",
+        "rule-runtime",
+        "the runtime that executes the rules: v8 (default) or wasm (experimental, requires --wasm-engine)",
+        "v8|wasm",
+    );
+    opts.optopt(
+        "",
+        "wasm-engine",
+        "the WebAssembly module that executes the rules with --rule-runtime wasm",
+        "/path/to/engine.wasm",
+    );
+    opts.optflag(
+        "",
+        "cache-compression",
+        "compress the entries written to the cache with gzip",
+    );
+    opts.optopt(
+        "",
+        "policy-report",
+        "write the result of each policy of the configuration file to a JSON file",
+        "policies.json",
+    );
+    opts.optmulti(
+        "",
+        "publish",
+        "publish the violations to a code hosting platform, configured from the environment of its CI",
+        "bitbucket/azure-devops",
+    );
+    opts.optflag(
+        "",
+        "upload",
+        "upload the results to Datadog once the analysis is done (requires DD_API_KEY, and a git repository)",
+    );
+    opts.optopt(
+        "",
+        "upload-service",
+        "the service of the uploaded results (default: DD_SERVICE, or the name of the analyzed directory)",
+        "service",
+    );
+    opts.optopt(
+        "",
+        "upload-env",
+        "the environment of the uploaded results (default: DD_ENV, or ci)",
+        "env",
+    );
     #[cfg(feature = "secrets")]
     {
         opts.optflag("", "secrets-scan", "run the secret scanner");
         opts.optflag("", "secrets-validate", "attempt to validate secrets");
+        opts.optflag(
+            "",
+            "secrets-no-validate",
+            "never validate secrets: report the candidates without any network request (overrides --secrets-validate)",
+        );
         opts.optopt(
             "",
             "secrets-rules",
             "path to a YAML file containing secrets scanner rules",
             "/path/to/secrets-rules.yml",
         );
+        opts.optflag(
+            "",
+            "secrets-scan-archives",
+            "scan the text files contained in archives (zip, jar, tar, tar.gz) for secrets",
+        );
+        opts.optopt(
+            "",
+            "secrets-archive-depth",
+            "maximum nesting level of archives to scan (default: 2)",
+            "2",
+        );
+        opts.optopt(
+            "",
+            "secrets-archive-max-size-mb",
+            "maximum number of uncompressed megabytes read from a single archive (default: 50)",
+            "50",
+        );
+        opts.optflag(
+            "",
+            "secrets-structured-content",
+            "use the keys of JSON, YAML and .env files as the context of the secrets they contain",
+        );
+        opts.optflag(
+            "",
+            "secrets-markup-code-only",
+            "only scan the code blocks and inline code of Markdown and AsciiDoc files for secrets",
+        );
+        opts.optopt(
+            "",
+            "secrets-suppression-report",
+            "write the secrets that were ignored by the allowlists to a JSON file",
+            "suppressed-secrets.json",
+        );
+        opts.optflag(
+            "",
+            "secrets-group-identical",
+            "report the secrets of a rule with the same value as one finding with all their locations",
+        );
+        opts.optopt(
+            "",
+            "secrets-context-lines",
+            "embed the lines of each secret in the report, redacted, with the given number of lines of context",
+            "context-lines",
+        );
+        opts.optopt(
+            "",
+            "secrets-validation-proxy",
+            "send the secrets validation requests through a proxy (default: the HTTPS_PROXY or HTTP_PROXY environment variable)",
+            "http://proxy:3128",
+        );
+        opts.optopt(
+            "",
+            "secrets-validation-max-per-host",
+            "maximum number of concurrent secrets validation requests to a single host",
+            "4",
+        );
+        opts.optopt(
+            "",
+            "secrets-validation-qps",
+            "maximum number of secrets validation requests per second, across all the rules",
+            "20",
+        );
     }
 
     let matches = match opts.parse(&args[1..]) {
@@ -250,6 +664,14 @@ fn main() -> Result<()> {
 
     if matches.opt_present("v") {
         println!("Version: {}, revision: {}", CARGO_VERSION, VERSION);
+        println!("Grammars:");
+        for language in ALL_LANGUAGES {
+            println!(
+                "  {:<12} {}",
+                language.to_string(),
+                get_grammar_version(language)
+            );
+        }
         exit(0);
     }
 
@@ -258,19 +680,104 @@ fn main() -> Result<()> {
         exit(0);
     }
 
-    let diff_aware_requested = matches.opt_present("w");
-
-    if !matches.opt_present("o") {
-        eprintln!("output file not specified");
-        print_usage(&program, opts);
-        exit(1);
+    if let Some(format) = matches.opt_str("print-schema") {
+        let output_format = match format.as_str() {
+            "json" => OutputFormat::Json,
+            "sarif" => OutputFormat::Sarif,
+            _ => {
+                eprintln!(
+                    "invalid value for --print-schema: {} (expected json or sarif)",
+                    format
+                );
+                exit(1);
+            }
+        };
+        println!("{}", get_output_schema(&output_format)?);
+        exit(0);
     }
 
+    let diff_aware_requested = matches.opt_present("w");
+    let list_files = matches.opt_present("list-files");
+
     let should_verify_checksum = !matches.opt_present("b");
     let use_staging = matches.opt_present("s");
-    let add_git_info = matches.opt_present("g");
+    // the uploaded SARIF report needs the git information of the violations
+    let add_git_info = matches.opt_present("g") || matches.opt_present("upload");
     let enable_performance_statistics = matches.opt_present("x");
+    let profile_rules = matches.opt_present("profile-rules");
+    let fail_on_rule_error = matches.opt_present("fail-on-rule-error");
+    let nondeterministic_rules = matches.opt_present("nondeterministic-rules");
     let print_violations = matches.opt_present("print-violations");
+    let snippet_context_lines = if matches.opt_present("include-snippets") {
+        match matches
+            .opt_str("include-snippets")
+            .map(|n| n.parse::<u32>())
+        {
+            None => Some(0),
+            Some(Ok(n)) => Some(n),
+            Some(Err(_)) => {
+                eprintln!("invalid value for --include-snippets (expected a number of lines)");
+                exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    // the rules are executed by v8, unless the experimental WebAssembly runtime is selected
+    let wasm_engine = match matches.opt_str("rule-runtime").as_deref() {
+        None | Some("v8") => None,
+        Some("wasm") => match matches.opt_str("wasm-engine") {
+            Some(engine) => Some(std::path::PathBuf::from(engine)),
+            None => {
+                eprintln!("--rule-runtime wasm requires --wasm-engine");
+                exit(1);
+            }
+        },
+        Some(_) => {
+            eprintln!("invalid value for --rule-runtime (expected v8 or wasm)");
+            exit(1);
+        }
+    };
+    let blame_max_files = if matches.opt_present("blame") {
+        match matches.opt_str("blame").map(|n| n.parse::<usize>()) {
+            None => Some(DEFAULT_BLAME_MAX_FILES),
+            Some(Ok(n)) => Some(n),
+            Some(Err(_)) => {
+                eprintln!("invalid value for --blame (expected a number of files)");
+                exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    let shard = match matches.opt_str("shard").map(|s| s.parse::<Shard>()) {
+        Some(Ok(shard)) => Some(shard),
+        Some(Err(err)) => {
+            eprintln!("invalid value for --shard: {err}");
+            exit(1);
+        }
+        None => None,
+    };
+    let group_by_owner = match matches.opt_str("group-by").as_deref() {
+        Some("owner") => true,
+        Some(g) => {
+            eprintln!("invalid value for --group-by: {} (expected owner)", g);
+            exit(1);
+        }
+        None => false,
+    };
+    let summary_top = if matches.opt_present("summary") {
+        match matches.opt_str("summary").map(|n| n.parse::<usize>()) {
+            None => Some(10),
+            Some(Ok(n)) => Some(n),
+            Some(Err(_)) => {
+                eprintln!("--summary must be a number");
+                exit(1);
+            }
+        }
+    } else {
+        None
+    };
     // if --fail-on-any-violation is specified, get the list of severities to exit with a non-zero code
     let fail_any_violation_severities = match matches.opt_str("fail-on-any-violation") {
         Some(f) => f
@@ -282,7 +789,9 @@ fn main() -> Result<()> {
         }
     };
     let scan_for_secrets = cfg!(feature = "secrets") && matches.opt_present("secrets-scan");
-    let validate_secrets = cfg!(feature = "secrets") && matches.opt_present("secrets-validate");
+    let validate_secrets = cfg!(feature = "secrets")
+        && matches.opt_present("secrets-validate")
+        && !matches.opt_present("secrets-no-validate");
     let secrets_rule_file = if cfg!(feature = "secrets") {
         use std::path::PathBuf;
         matches.opt_str("secrets-rules").map(PathBuf::from)
@@ -290,22 +799,176 @@ fn main() -> Result<()> {
         None
     };
 
-    let output_format = match matches.opt_str("f") {
+    #[cfg(feature = "secrets")]
+    let secrets_archive_config = if matches.opt_present("secrets-scan-archives") {
+        let default = secrets::archive::ArchiveConfig::default();
+        let max_depth = matches
+            .opt_str("secrets-archive-depth")
+            .map(|val| {
+                val.parse::<usize>()
+                    .context("unable to parse `secrets-archive-depth` flag as integer")
+            })
+            .transpose()?
+            .unwrap_or(default.max_depth);
+        let max_total_size = matches
+            .opt_str("secrets-archive-max-size-mb")
+            .map(|val| {
+                val.parse::<u64>()
+                    .context("unable to parse `secrets-archive-max-size-mb` flag as integer")
+            })
+            .transpose()?
+            .map_or(default.max_total_size, |mb| mb * 1024 * 1024);
+        Some(secrets::archive::ArchiveConfig {
+            max_depth,
+            max_total_size,
+        })
+    } else {
+        None
+    };
+
+    #[cfg(feature = "secrets")]
+    let secrets_structured_content = matches.opt_present("secrets-structured-content");
+    let secrets_markup_code_only = matches.opt_present("secrets-markup-code-only");
+    #[cfg(feature = "secrets")]
+    let secrets_suppression_report_file = matches.opt_str("secrets-suppression-report");
+    let secrets_group_identical =
+        cfg!(feature = "secrets") && matches.opt_present("secrets-group-identical");
+    #[cfg(feature = "secrets")]
+    let secrets_context_lines = matches
+        .opt_str("secrets-context-lines")
+        .map(|val| {
+            val.parse::<u32>()
+                .context("unable to parse `secrets-context-lines` flag as integer")
+        })
+        .transpose()?;
+    #[cfg(feature = "secrets")]
+    let mut secrets_http_client = secrets::core::validator::http::HttpClientConfig {
+        proxy: matches.opt_str("secrets-validation-proxy"),
+        max_concurrency_per_host: matches
+            .opt_str("secrets-validation-max-per-host")
+            .map(|val| {
+                val.parse::<std::num::NonZeroUsize>().context(
+                    "unable to parse `secrets-validation-max-per-host` flag as a positive integer",
+                )
+            })
+            .transpose()?,
+        max_requests_per_second: matches
+            .opt_str("secrets-validation-qps")
+            .map(|val| {
+                val.parse::<std::num::NonZeroU32>()
+                    .context("unable to parse `secrets-validation-qps` flag as a positive integer")
+            })
+            .transpose()?,
+        allowed_hosts: None,
+    };
+
+    let symlink_behavior = if matches.opt_present("follow-symlinks") {
+        SymlinkBehavior::Follow
+    } else {
+        SymlinkBehavior::Skip
+    };
+    let non_utf8_behavior = match matches.opt_str("non-utf8-files") {
         Some(f) => match f.as_str() {
-            "csv" => OutputFormat::Csv,
-            "sarif" => OutputFormat::Sarif,
-            _ => OutputFormat::Json,
+            "lossy" => NonUtf8Behavior::Lossy,
+            "skip" => NonUtf8Behavior::Skip,
+            _ => {
+                eprintln!(
+                    "invalid value for --non-utf8-files: {} (expected skip or lossy)",
+                    f
+                );
+                exit(1);
+            }
         },
-        None => OutputFormat::Json,
+        None => NonUtf8Behavior::Skip,
+    };
+    let column_unit = match matches.opt_str("column-unit") {
+        Some(u) => match u.as_str() {
+            "bytes" => ColumnUnit::Bytes,
+            "chars" => ColumnUnit::Chars,
+            "utf16" => ColumnUnit::Utf16,
+            _ => {
+                eprintln!(
+                    "invalid value for --column-unit: {} (expected bytes, chars or utf16)",
+                    u
+                );
+                exit(1);
+            }
+        },
+        None => ColumnUnit::Bytes,
+    };
+    let skip_report_file = matches.opt_str("skip-report");
+    let max_buffered_violations = match matches
+        .opt_str("max-buffered-violations")
+        .map(|n| n.parse::<usize>())
+    {
+        None => None,
+        Some(Ok(n)) => Some(n),
+        Some(Err(_)) => {
+            eprintln!("--max-buffered-violations must be a number");
+            exit(1);
+        }
     };
+    let journal_file = matches.opt_str("resume");
+    let cache_location = matches.opt_str("cache");
+    let policy_report_file = matches.opt_str("policy-report");
+    let publishers = matches
+        .opt_strs("publish")
+        .iter()
+        .map(|name| publisher_from_env(name).map(PublisherSink))
+        .collect::<Result<Vec<_>>>()
+        .context("cannot configure the publishers")?;
+    let merge_duplicates = matches.opt_present("merge-duplicate-violations");
+    let fix_unsafe = matches.opt_present("fix-unsafe");
+    let fix = fix_unsafe || matches.opt_present("fix") || matches.opt_present("fix-iterations");
+    let fix_iterations = match matches
+        .opt_str("fix-iterations")
+        .map(|n| n.parse::<usize>())
+    {
+        None => 1,
+        Some(Ok(n)) if n > 0 => n,
+        Some(_) => {
+            eprintln!("invalid value for --fix-iterations (expected a positive number)");
+            exit(1);
+        }
+    };
+
+    let output_format = matches
+        .opt_str("f")
+        .and_then(|f| output_format_from_name(&f))
+        .unwrap_or(OutputFormat::Json);
+    let mut outputs = matches
+        .opt_strs("o")
+        .iter()
+        .map(|value| OutputSpec::parse(value, &output_format))
+        .collect::<Result<Vec<_>>>()?;
+    // without any output file, the report is written to the terminal (in a readable format unless
+    // a format is requested), except when only a summary is requested.
+    if outputs.is_empty() && summary_top.is_none() {
+        outputs.push(OutputSpec {
+            format: matches
+                .opt_str("f")
+                .and_then(|f| output_format_from_name(&f))
+                .unwrap_or(OutputFormat::Pretty),
+            path: STDOUT.to_string(),
+        });
+    }
 
     let use_debug = *matches
         .opt_str("d")
         .map(|value| value == "yes" || value == "true")
         .get_or_insert(env::var_os("DD_SA_DEBUG").is_some());
-    let output_file = matches
-        .opt_str("o")
-        .context("output file must be specified")?;
+    // the progress bar is disabled in debug mode, as it would be mixed with too much information.
+    let progress_mode = match matches.opt_str("progress") {
+        Some(mode) => ProgressMode::try_from(mode.as_str()).unwrap_or_else(|_| {
+            eprintln!(
+                "invalid value for --progress: {} (expected bar, json or none)",
+                mode
+            );
+            exit(1);
+        }),
+        None if use_debug => ProgressMode::None,
+        None => ProgressMode::Bar,
+    };
 
     let mut path_config = PathConfig {
         ignore: Vec::new(),
@@ -336,6 +999,37 @@ fn main() -> Result<()> {
         exit(1)
     }
 
+    // the upload is configured before the analysis, so that a missing key or repository fails
+    // without analyzing anything.
+    let upload = if matches.opt_present("upload") {
+        if get_datadog_variable_value("API_KEY").is_err() {
+            eprintln!("--upload requires an API key: set DD_API_KEY");
+            exit(1);
+        }
+        let git = get_git_metadata(&directory_to_analyze, use_debug)
+            .context("cannot get the git metadata of the results to upload")?;
+        let service = matches
+            .opt_str("upload-service")
+            .or_else(|| get_datadog_variable_value("SERVICE").ok())
+            .or_else(|| {
+                fs::canonicalize(directory_path)
+                    .ok()?
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+            .unwrap_or_else(|| directory_to_analyze.clone());
+        let env = matches
+            .opt_str("upload-env")
+            .or_else(|| get_datadog_variable_value("ENV").ok())
+            .unwrap_or_else(|| DEFAULT_UPLOAD_ENV.to_string());
+        Some(UploadSink {
+            metadata: UploadMetadata { service, env, git },
+            use_staging,
+        })
+    } else {
+        None
+    };
+
     let configuration_file: Option<ConfigFile> =
         match read_config_file(directory_to_analyze.as_str()) {
             Ok(cfg) => cfg,
@@ -350,6 +1044,14 @@ fn main() -> Result<()> {
     let mut rules: Vec<Rule> = Vec::new();
     let mut path_restrictions = PathRestrictions::default();
     let mut argument_provider = ArgumentProvider::new();
+    let mut policies: Vec<PolicyConfig> = Vec::new();
+    let mut severity_remaps: Vec<SeverityRemapConfig> = Vec::new();
+    let mut generated_files_rules: HashSet<String> = HashSet::new();
+    let mut file_languages = FileLanguagesConfig::default();
+    let mut config_plugins: Vec<String> = Vec::new();
+    #[cfg(feature = "secrets")]
+    let mut secrets_allowlist_config =
+        kernel::model::config_file::SecretsAllowlistConfig::default();
 
     // if there is a configuration file, we load the rules from it. But it means
     // we cannot have the rule parameter given.
@@ -361,18 +1063,21 @@ fn main() -> Result<()> {
             exit(1);
         }
 
-        let overrides = RuleOverrides::from_config_file(&conf);
-
-        let rulesets = conf.rulesets.keys().cloned().collect_vec();
-        let rules_from_api = get_rules_from_rulesets(&rulesets, use_staging)
-            .context("error when reading rules from API")?;
-        rules.extend(rules_from_api.into_iter().map(|rule| Rule {
-            severity: overrides.severity(&rule.name, rule.severity),
-            category: overrides.category(&rule.name, rule.category),
-            ..rule
-        }));
+        rules.extend(
+            get_rules_from_config_file(&conf, use_staging)
+                .context("error when reading rules from API")?,
+        );
+        for warning in get_deprecated_rules_warnings(&rules) {
+            eprintln!("WARNING: {warning} (enabled by the configuration file)");
+        }
         path_restrictions = PathRestrictions::from_ruleset_configs(&conf.rulesets);
         argument_provider = ArgumentProvider::from(&conf);
+        let overrides = RuleOverrides::from_config_file(&conf);
+        generated_files_rules = rules
+            .iter()
+            .filter(|rule| overrides.analyzes_generated_files(&rule.name))
+            .map(|rule| rule.name.clone())
+            .collect();
 
         // copy the only and ignore paths from the configuration file
         path_config.ignore.extend(conf.paths.ignore);
@@ -381,6 +1086,18 @@ fn main() -> Result<()> {
         // Get the max file size from the configuration or default to the default constant.
         max_file_size_kb = conf.max_file_size_kb.unwrap_or(DEFAULT_MAX_FILE_SIZE_KB);
         ignore_generated_files = conf.ignore_generated_files.unwrap_or(true);
+        policies = conf.policies;
+        severity_remaps = conf.severity_remaps;
+        file_languages = conf.file_languages;
+        config_plugins = conf.plugins;
+        #[cfg(feature = "secrets")]
+        {
+            secrets_allowlist_config = conf.secrets_allowlist;
+            secrets_http_client.allowed_hosts = conf
+                .secrets_validation
+                .allowed_hosts
+                .map(secrets::core::validator::http::HostAllowlist::new);
+        }
     } else {
         use_configuration_file = false;
         // if there is no config file, we take the default rules from our APIs.
@@ -411,23 +1128,76 @@ fn main() -> Result<()> {
         .ignore
         .extend(ignore_paths_from_options.iter().map(|p| p.clone().into()));
 
-    // ignore all directories that are in gitignore
-    if !ignore_gitignore {
-        let paths_from_gitignore = read_files_from_gitignore(directory_to_analyze.as_str())
-            .expect("error when reading gitignore file");
-        path_config
-            .ignore
-            .extend(paths_from_gitignore.iter().map(|p| p.clone().into()));
+    // ignore all the files that are ignored by git
+    let gitignore = if ignore_gitignore {
+        GitIgnore::default()
+    } else {
+        GitIgnore::from_repository(directory_path, !matches.opt_present("no-global-gitignore"))
+            .context("error when reading the gitignore files")?
+    };
+
+    // the rules with queries that don't compile with the grammars of this version of the analyzer
+    // are reported when they are loaded, and not used.
+    rules.retain(|rule| {
+        let incompatible_queries = rule.get_incompatible_queries();
+        for err in &incompatible_queries {
+            eprintln!("ERROR: rule {} is not used: {}", rule.name, err);
+        }
+        incompatible_queries.is_empty()
+    });
+
+    // the plugins of the command line are always loaded, and the plugins of the configuration file
+    // only when they are allowed, as a plugin runs native code.
+    let mut plugin_paths = matches
+        .opt_strs("plugin")
+        .into_iter()
+        .map(std::path::PathBuf::from)
+        .collect::<Vec<_>>();
+    if matches.opt_present("allow-plugins") {
+        plugin_paths.extend(config_plugins.iter().map(|p| directory_path.join(p)));
+    } else if !config_plugins.is_empty() {
+        eprintln!(
+            "WARNING: the plugins of the configuration file are not loaded without --allow-plugins: {}",
+            config_plugins.join(", ")
+        );
+    }
+    let mut plugins = PluginRegistry::new();
+    for path in &plugin_paths {
+        let name = load_plugin(path, &mut plugins)?;
+        if use_debug {
+            println!("Loaded the plugin {} from {}", name, path.display());
+        }
     }
 
-    let languages = get_languages_for_rules(&rules);
+    let mut languages = get_languages_for_rules(&rules);
+    for language in plugins.languages() {
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
 
-    let files_in_repository = get_files(
+    let walked_files = get_files(
         directory_to_analyze.as_str(),
         subdirectories_to_analyze.clone(),
         &path_config,
+        &gitignore,
+        symlink_behavior,
     )
     .expect("unable to get the list of files to analyze");
+    let mut files_in_repository = walked_files.files;
+    let mut skipped_files: Vec<SkippedFile> = walked_files.skipped;
+    if let Some(shard) = shard {
+        // the files are assigned to a shard by their path relative to the analyzed directory, so
+        // that all the jobs agree on the shards whatever their checkout directory.
+        files_in_repository.retain(|path| {
+            path.strip_prefix(directory_path)
+                .is_ok_and(|relative_path| shard.contains(&relative_path.to_string_lossy()))
+        });
+        skipped_files.retain(|skipped| shard.contains(&skipped.path));
+    }
+
+    // the `.editorconfig` settings are given to the rules, and followed by the fixes
+    argument_provider.set_editorconfig(read_editorconfig(directory_path, &files_in_repository));
 
     let num_cores_requested = matches
         .opt_str("c")
@@ -448,12 +1218,11 @@ fn main() -> Result<()> {
         source_subdirectories: subdirectories_to_analyze.clone(),
         path_config,
         rules_file,
-        output_format,
+        outputs,
         num_cpus,
         rules,
         path_restrictions,
         argument_provider,
-        output_file,
         max_file_size_kb,
         use_staging,
         show_performance_statistics: enable_performance_statistics,
@@ -461,18 +1230,87 @@ fn main() -> Result<()> {
         validate_secrets,
         secrets_rule_file: secrets_rule_file.clone(),
         ignore_generated_files,
+        generated_files_rules,
+        symlink_behavior,
+        non_utf8_behavior,
+        file_languages,
+        plugins,
     };
 
+    if list_files {
+        let selections =
+            select_files(&configuration, &gitignore).context("unable to select the files")?;
+        for selection in &selections {
+            println!("{selection}");
+        }
+        let analyzed = selections.iter().filter(|s| s.exclusion.is_none()).count();
+        println!(
+            "{} files analyzed, {} files excluded",
+            analyzed,
+            selections.len() - analyzed
+        );
+        return Ok(());
+    }
+
     print_configuration(&configuration);
+    if let Some(shard) = shard {
+        println!(
+            "shard               : {} ({} files)",
+            shard,
+            files_in_repository.len()
+        );
+    }
 
-    let mut all_rule_results = vec![];
+    let mut rule_spill = ResultSpill::new(max_buffered_violations);
+    // the errors of the rules are counted as the results are produced, as they may be spilled
+    let mut rule_errors = RuleErrorSummary::new();
 
     let analysis_options = AnalysisOptions {
         log_output: true,
         use_debug,
         ignore_generated_files,
+        report_parse_errors: matches.opt_present("report-parse-errors"),
+        column_unit,
+        generated_files_rules: configuration.generated_files_rules.clone(),
+        profile_rules,
+        nondeterministic_rules,
     };
 
+    // the results of a file can only be reused with the same rules and the options that change
+    // the results.
+    let results_configuration = format!(
+        "{}:{}:{}:{}:{}:{}:{}:{}:{}",
+        VERSION,
+        configuration.generate_diff_aware_digest(),
+        configuration.argument_provider.digest(),
+        configuration.path_restrictions.digest(),
+        configuration.argument_provider.editorconfig().digest(),
+        analysis_options.ignore_generated_files,
+        analysis_options.report_parse_errors,
+        analysis_options.column_unit,
+        analysis_options.nondeterministic_rules,
+    );
+    let journal = journal_file
+        .as_ref()
+        .map(|path| ScanJournal::open(std::path::Path::new(path), &results_configuration))
+        .transpose()?;
+    // the results of the rules that aren't executed deterministically can't be reused
+    if nondeterministic_rules && cache_location.is_some() {
+        eprintln!("WARNING: the cache is not used with --nondeterministic-rules");
+    }
+    let result_cache = cache_location
+        .as_deref()
+        .filter(|_| !nondeterministic_rules)
+        .map(store_from_location)
+        .transpose()?
+        .map(|store| {
+            ResultCache::new(
+                store,
+                &results_configuration,
+                matches.opt_present("cache-compression"),
+            )
+        });
+
     // verify rule checksum
     if should_verify_checksum {
         if configuration.use_debug {
@@ -558,6 +1396,34 @@ fn main() -> Result<()> {
     rayon::ThreadPoolBuilder::new()
         .num_threads(num_threads)
         .build_global()?;
+    match &wasm_engine {
+        Some(engine) => select_wasm_rule_runtime(
+            engine,
+            configuration.use_debug || enable_performance_statistics,
+        )?,
+        // the rules are executed by a pool of JavaScript runtimes, warmed up before the analysis
+        // starts
+        None => {
+            JsRuntimePool::init_global(num_threads)
+                .context("failed to initialize the JavaScript runtimes")?;
+        }
+    }
+
+    // The first SIGINT (Ctrl-C) or SIGTERM stops the analysis: no new file is analyzed, the files
+    // being analyzed are finished (the rules are bounded by their timeout), and the partial
+    // results are reported. The second one exits immediately.
+    let cancellation = CancellationToken::new();
+    {
+        let cancellation = cancellation.clone();
+        ctrlc::set_handler(move || {
+            if cancellation.is_cancelled() {
+                exit(EXIT_CODE_INTERRUPTED);
+            }
+            eprintln!("Interrupted, stopping the analysis (press Ctrl-C again to exit now)");
+            cancellation.cancel();
+        })
+        .context("failed to set the Ctrl-C handler")?;
+    }
 
     let mut total_files_analyzed: usize = 0;
     let start_timestamp = SystemTime::now()
@@ -565,13 +1431,26 @@ fn main() -> Result<()> {
         .unwrap()
         .as_secs();
 
-    let files_filtered_by_size = filter_files_by_size(&files_in_repository, &configuration);
+    let file_policy = FilePolicy::new(configuration.max_file_size_kb)
+        .with_file_languages(&configuration.file_languages);
+    let (eligible_files, ineligible_files) = file_policy.partition(&files_in_repository);
+    for (path, reason) in &ineligible_files {
+        if configuration.use_debug {
+            eprintln!("skipping file {}: {}", path.display(), reason);
+        }
+        // Files with an unsupported extension are expected and not worth reporting.
+        if *reason != SkipReason::UnsupportedExtension {
+            if let Ok(relative_path) = path.strip_prefix(directory_path) {
+                skipped_files.push(SkippedFile::new(relative_path.to_string_lossy(), *reason));
+            }
+        }
+    }
 
     // if diff-aware is enabled, we filter the files and keep only the files we want to analyze from diff-aware
     let files_to_analyze = if let Some(dap) = &diff_aware_parameters {
-        filter_files_by_diff_aware_info(&files_filtered_by_size, directory_path, dap)
+        filter_files_by_diff_aware_info(&eligible_files, directory_path, dap)
     } else {
-        files_filtered_by_size
+        eligible_files
     };
 
     if configuration.use_debug && diff_aware_parameters.is_some() {
@@ -594,36 +1473,92 @@ fn main() -> Result<()> {
     let mut secrets_rules = Vec::<SecretRule>::new();
     #[cfg(feature = "secrets")]
     if scan_for_secrets && secrets_rule_file.is_some() {
-        use cli::secrets::{as_position, ValidationStatus};
+        use cli::file_utils::partition_secrets_files;
+        use cli::secrets::{redact_secrets, ValidationStatus};
+        use cli::secrets_allowlist::{SecretsAllowlist, SuppressedSecret};
+        use secrets::archive::is_archive;
         use secrets::core::validator::Candidate;
+        use secrets::severity::Finding;
         use secrets::ScannerBuilder;
+        use std::path::PathBuf;
         use std::sync::atomic::{AtomicBool, Ordering};
-        use std::sync::Arc;
         use std::time::Duration;
 
         let rule_file = secrets_rule_file.expect("should have been checked");
-        let scanner = ScannerBuilder::new()
+        let mut scanner_builder = ScannerBuilder::new()
             .yaml_file_multi_rule(rule_file)
+            .structured_content(secrets_structured_content)
+            .markup_code_only(secrets_markup_code_only)
+            .http_client(secrets_http_client)
+            .cancellation_flag(Arc::clone(cancellation.flag()));
+        if let Some(archive_config) = secrets_archive_config {
+            scanner_builder = scanner_builder.archives(archive_config);
+        }
+        let scanner = scanner_builder
             .try_build()
             .context("failed to initialize secrets scanner")?;
+        let allowlist = SecretsAllowlist::new(&secrets_allowlist_config)?;
+
+        // Archives are bounded by their own size budget, so they aren't subject to the file policy.
+        let archives = if secrets_archive_config.is_some() {
+            files_in_repository
+                .iter()
+                .filter(|path| is_archive(path))
+                .cloned()
+                .collect::<Vec<_>>()
+        } else {
+            vec![]
+        };
+        // Files that are too large are only scanned by the rules that opt into them.
+        let (secrets_files, overridable_files) =
+            partition_secrets_files(&files_to_analyze, &ineligible_files);
+        let filter_diff_aware = |files: Vec<PathBuf>| match &diff_aware_parameters {
+            Some(dap) => filter_files_by_diff_aware_info(&files, directory_path, dap),
+            None => files,
+        };
+        let archives = filter_diff_aware(archives);
+        let secrets_files = filter_diff_aware(secrets_files);
+        let overridable_files = filter_diff_aware(overridable_files);
+        let files_to_scan = secrets_files
+            .iter()
+            .filter(|path| !is_archive(path))
+            .chain(&archives)
+            .map(|path| (path, true))
+            .chain(
+                overridable_files
+                    .iter()
+                    .filter(|path| !is_archive(path))
+                    .map(|path| (path, false)),
+            )
+            .collect::<Vec<_>>();
 
         for rule_info in scanner.rules() {
             secrets_rules.push(rule_info.clone().into());
         }
 
         let scanner = Arc::new(scanner);
-        println!("scanning {} files for secrets", files_to_analyze.len());
-        let progress_bar =
-            (!configuration.use_debug).then(|| ProgressBar::new(files_to_analyze.len() as u64));
+        println!("scanning {} files for secrets", files_to_scan.len());
+        let progress = Progress::new(progress_mode, "secrets", files_to_scan.len() as u64);
 
         let start_timestamp = Instant::now();
-        let candidates = files_to_analyze
+        let candidates = files_to_scan
             .par_iter()
-            .filter_map(|path| {
-                let scan_result = scanner.scan_file(path);
-                if let Some(pb) = &progress_bar {
-                    pb.inc(1);
+            .filter_map(|(path, is_eligible)| {
+                if cancellation.is_cancelled() {
+                    progress.finish_file(0);
+                    return None;
                 }
+                progress.start_file(&path.to_string_lossy());
+                let scan_result = if *is_eligible {
+                    scanner.scan_file(path)
+                } else {
+                    scanner.scan_ineligible_file(path)
+                };
+                progress.finish_file(if *is_eligible {
+                    scanner.rule_count() as u64
+                } else {
+                    0
+                });
                 // Silently drop files that can't be read, or that caused scan errors.
                 scan_result.map_err(drop).ok()
             })
@@ -634,7 +1569,7 @@ fn main() -> Result<()> {
         println!(
             "Secrets scan found {} candidates in {} file(s) in {:.1}s",
             candidates.len(),
-            files_to_analyze.len(),
+            files_to_scan.len(),
             elapsed.as_secs_f32()
         );
 
@@ -645,9 +1580,7 @@ fn main() -> Result<()> {
         if validate_secrets && !candidates.is_empty() {
             let num_validations = candidates.len();
             println!("Starting validation for {} candidates", num_validations);
-            if let Some(pb) = &progress_bar {
-                pb.inc_length(num_validations as u64)
-            }
+            progress.inc_length(num_validations as u64);
 
             let timed_out = Arc::new(AtomicBool::new(false));
             let timed_out_clone = Arc::clone(&timed_out);
@@ -662,14 +1595,13 @@ fn main() -> Result<()> {
             let val_results = candidates
                 .into_par_iter()
                 .map(|candidate| {
-                    let attempt = if timed_out.load(Ordering::Relaxed) {
-                        None
-                    } else {
-                        Some(scanner.validate_candidate(&candidate))
-                    };
-                    if let Some(pb) = &progress_bar {
-                        pb.inc(1);
-                    }
+                    let attempt =
+                        if timed_out.load(Ordering::Relaxed) || cancellation.is_cancelled() {
+                            None
+                        } else {
+                            Some(scanner.validate_candidate(&candidate))
+                        };
+                    progress.finish_file(0);
                     // If we either timed out, or the attempt resulted in an Err, mark candidate as Unvalidated.
                     let status = attempt.map_or(ValidationStatus::Unvalidated, |res| {
                         res.map_or(ValidationStatus::Unvalidated, |vr| vr.category().into())
@@ -687,56 +1619,167 @@ fn main() -> Result<()> {
         }
 
         let mut valid_count = 0;
+        let mut suppressed_secrets = Vec::<SuppressedSecret>::new();
+        let mut contents = HashMap::<std::path::PathBuf, Option<String>>::new();
+        // all the secrets of a file are redacted in the context of each of them
+        let mut redacted_contents = HashMap::<std::path::PathBuf, String>::new();
+        let mut secret_ranges = HashMap::<std::path::PathBuf, Vec<std::ops::Range<usize>>>::new();
+        for (candidate, _) in &final_results {
+            secret_ranges
+                .entry(candidate.source.clone())
+                .or_default()
+                .push(candidate.rule_match.matched.byte_span.as_range());
+        }
         for (candidate, status) in final_results {
-            if matches!(status, ValidationStatus::Valid(_)) {
-                valid_count += 1;
-            }
             let rel_path = candidate
                 .source
                 .strip_prefix(directory_path)
                 .expect("should be child path");
-            detected_secrets.push(SecretResult::new(
+            let mut secret = SecretResult::new(
                 candidate.rule_match.rule_id.to_string(),
                 rel_path.to_string_lossy(),
                 status,
-                as_position(candidate.rule_match.matched.point_span.start()),
-                as_position(candidate.rule_match.matched.point_span.end()),
-            ));
+                candidate.rule_match.matched.point_span.start().into(),
+                candidate.rule_match.matched.point_span.end().into(),
+            );
+            // The entries of archives can't be read, so they only have the path-based suppressions.
+            let content = contents
+                .entry(candidate.source.clone())
+                .or_insert_with(|| fs::read_to_string(&candidate.source).ok())
+                .as_deref();
+            if let Some(content) = content {
+                // the position of the candidate is in graphemes: it's located again from its bytes,
+                // with the columns in the unit of the violations of the rules
+                let range = candidate.rule_match.matched.byte_span.as_range();
+                if content.get(range.clone()) == Some(candidate.rule_match.matched.as_str()) {
+                    secret.locate(content, range, analysis_options.column_unit);
+                }
+                secret.set_fingerprint(content);
+                if let Some(context_lines) = secrets_context_lines {
+                    let redacted = redacted_contents
+                        .entry(candidate.source.clone())
+                        .or_insert_with(|| {
+                            redact_secrets(
+                                content,
+                                secret_ranges
+                                    .get(&candidate.source)
+                                    .map_or(&[][..], Vec::as_slice),
+                            )
+                        });
+                    secret.set_redacted_context(redacted, context_lines);
+                }
+            }
+            secret.set_value_hash(candidate.rule_match.matched.as_str());
+            let rule_info = scanner.rule(&secret.rule_id);
+            if let Some(rule_info) = rule_info {
+                let matched = candidate.rule_match.matched.as_str();
+                let range = candidate.rule_match.matched.byte_span.as_range();
+                let finding = Finding {
+                    status: status.into(),
+                    path: rel_path,
+                    secret: matched,
+                    content: content
+                        .filter(|content| content.get(range.clone()) == Some(matched))
+                        .map(|content| (content, range)),
+                };
+                if let Some(severity) = rule_info.severity_rules.severity(&finding) {
+                    secret.set_severity(severity.into());
+                }
+            }
+            let ignored_by_rule = rule_info.is_some_and(|rule| rule.ignores_path(rel_path));
+            match allowlist.suppression_reason(
+                &secret,
+                candidate.rule_match.matched.as_str(),
+                content,
+                ignored_by_rule,
+            ) {
+                Some(reason) => suppressed_secrets.push(SuppressedSecret::new(secret, reason)),
+                None => {
+                    if matches!(status, ValidationStatus::Valid(_)) {
+                        valid_count += 1;
+                    }
+                    detected_secrets.push(secret);
+                }
+            }
+        }
+
+        if !suppressed_secrets.is_empty() {
+            println!(
+                "Ignored {} secret(s) from the allowlists",
+                suppressed_secrets.len()
+            );
+        }
+        if let Some(report_file) = &secrets_suppression_report_file {
+            let report = serde_json::to_string(&suppressed_secrets)
+                .context("cannot serialize the secrets suppression report")?;
+            fs::write(report_file, report)
+                .context("error when writing the secrets suppression report")?;
         }
 
         if validate_secrets {
             println!(
                 "Secrets validation detected {} valid secret(s) in {} file(s) using {} rule(s) in {:.1}s",
                 valid_count,
-                files_to_analyze.len(),
+                files_to_scan.len(),
                 scanner.rule_count(),
                 start_timestamp.elapsed().as_secs_f32()
             );
         }
 
-        if let Some(pb) = &progress_bar {
-            pb.finish();
-        }
+        progress.finish();
     }
     ////////////////////////////////////////////////////////////////////////////////////////
     ////////////////////////////////////////////////////////////////////////////////////////
 
+    // the results of each file are prepared for the reports as they are aggregated, as they may
+    // be spilled to the disk before the end of the analysis.
+    let codeowners = CodeOwners::from_repository(directory_path)?;
+    let mut blamer = blame_max_files
+        .map(|max_files| Blamer::open(directory_path, max_files))
+        .transpose()
+        .context("cannot blame the violations")?;
+    let mut merged_violations = 0;
+    let mut interner = Interner::default();
+    let mut prepare_results = |results: &mut [RuleResult]| {
+        // the rule names, filenames and messages repeated across the results are stored once
+        for result in results.iter_mut() {
+            result.intern(&mut interner);
+        }
+        // the severities are remapped first, so that the policies and the reports use them
+        apply_severity_remaps(&severity_remaps, results);
+        if merge_duplicates {
+            merged_violations += merge_duplicate_violations(results, directory_path);
+        }
+        // attach the owners of each file, if the repository has a CODEOWNERS file
+        if let Some(codeowners) = &codeowners {
+            codeowners.assign(results, &mut []);
+        }
+        if let Some(context_lines) = snippet_context_lines {
+            add_snippets(results, directory_path, context_lines);
+        }
+        if let Some(blamer) = &mut blamer {
+            blamer.annotate(results);
+        }
+    };
+
     let mut number_of_rules_used = 0;
     // Finally run the analysis
     for language in &languages {
-        let files_for_language = filter_files_for_language(&files_to_analyze, language);
+        if cancellation.is_cancelled() {
+            break;
+        }
+        let files_for_language =
+            filter_files_for_language(&files_to_analyze, language, &configuration.file_languages);
 
         if files_for_language.is_empty() {
             continue;
         }
 
-        // we only use the progress bar when the debug mode is not active, otherwise, it puts
-        // too much information on the screen.
-        let progress_bar = if !configuration.use_debug {
-            Some(ProgressBar::new(files_for_language.len() as u64))
-        } else {
-            None
-        };
+        let progress = Progress::new(
+            progress_mode,
+            language.to_string(),
+            files_for_language.len() as u64,
+        );
         total_files_analyzed += files_for_language.len();
 
         let rules_for_language: Vec<RuleInternal> =
@@ -759,61 +1802,212 @@ fn main() -> Result<()> {
             )
         }
 
-        // take the relative path for the analysis
-        let rule_results: Vec<RuleResult> = files_for_language
-            .into_par_iter()
-            .flat_map(|path| {
-                let relative_path = path
-                    .strip_prefix(directory_path)
-                    .unwrap()
-                    .to_str()
-                    .expect("path contains non-Unicode characters");
-                let mut selected_rules = rules_for_language
-                    .iter()
-                    .filter(|r| {
-                        configuration
-                            .path_restrictions
-                            .rule_applies(&r.name, relative_path)
-                    })
-                    .peekable();
-                let res = if selected_rules.peek().is_none() {
-                    vec![]
-                } else if let Ok(file_content) = fs::read_to_string(&path) {
-                    analyze(
-                        language,
-                        selected_rules,
-                        relative_path,
-                        &file_content,
-                        &configuration.argument_provider,
-                        &analysis_options,
-                    )
-                } else {
-                    eprintln!("error when getting content of path {}", &path.display());
-                    vec![]
-                };
+        // the files are analyzed in batches, so that the results of a batch can be spilled
+        // before the next batch is analyzed
+        for batch in files_for_language.chunks(ANALYSIS_BATCH_SIZE) {
+            // take the relative path for the analysis
+            let (rule_results, skipped): (Vec<Vec<RuleResult>>, Vec<Option<SkippedFile>>) = batch
+                .par_iter()
+                .map(|path| {
+                    let relative_path = path
+                        .strip_prefix(directory_path)
+                        .unwrap()
+                        .to_str()
+                        .expect("path contains non-Unicode characters");
+                    if cancellation.is_cancelled() {
+                        progress.finish_file(0);
+                        return (vec![], None);
+                    }
+                    progress.start_file(relative_path);
+                    let mut selected_rules = rules_for_language
+                        .iter()
+                        .filter(|r| {
+                            configuration
+                                .path_restrictions
+                                .rule_applies(&r.name, relative_path)
+                        })
+                        .peekable();
+                    let has_rules = selected_rules.peek().is_some();
+                    let res = if !has_rules && !configuration.plugins.has_checkers(*language) {
+                        (vec![], None)
+                    } else {
+                        match read_file_content(&path, configuration.non_utf8_behavior) {
+                            Ok(file_content) => {
+                                let journaled = journal.as_ref().and_then(|j| {
+                                    j.completed(*language, relative_path, &file_content)
+                                });
+                                let cached = || {
+                                    result_cache.as_ref().and_then(|c| {
+                                        c.get(*language, relative_path, &file_content)
+                                    })
+                                };
+                                if let Some(results) = journaled.or_else(cached) {
+                                    (results, None)
+                                } else {
+                                    let mut results = if !has_rules {
+                                        vec![]
+                                    } else if is_sfc_file(relative_path) {
+                                        analyze_sfc_with(
+                                            language,
+                                            selected_rules,
+                                            relative_path,
+                                            &file_content,
+                                            &configuration.argument_provider,
+                                            &analysis_options,
+                                            &cancellation,
+                                        )
+                                    } else {
+                                        analyze_with(
+                                            language,
+                                            selected_rules,
+                                            relative_path,
+                                            Arc::clone(&file_content),
+                                            &configuration.argument_provider,
+                                            &analysis_options,
+                                            &cancellation,
+                                        )
+                                    };
+                                    // Once the analysis is cancelled, the plugins are not run:
+                                    // the results are partial and are neither journaled nor cached.
+                                    if !cancellation.is_cancelled() {
+                                        results.extend(configuration.plugins.check_file(
+                                            *language,
+                                            relative_path,
+                                            &file_content,
+                                            |name| {
+                                                configuration
+                                                    .path_restrictions
+                                                    .rule_applies(name, relative_path)
+                                            },
+                                        ));
+                                        if let Some(journal) = &journal {
+                                            if let Err(err) = journal.record(
+                                                *language,
+                                                relative_path,
+                                                &file_content,
+                                                &results,
+                                            ) {
+                                                eprintln!("{:#}", err);
+                                            }
+                                        }
+                                        if let Some(cache) = &result_cache {
+                                            cache.put(
+                                                *language,
+                                                relative_path,
+                                                &file_content,
+                                                &results,
+                                            );
+                                        }
+                                    }
+                                    (results, None)
+                                }
+                            }
+                            Err(reason) => {
+                                if configuration.use_debug {
+                                    eprintln!("skipping file {}: {}", &path.display(), reason);
+                                }
+                                (vec![], Some(SkippedFile::new(relative_path, reason)))
+                            }
+                        }
+                    };
 
-                if let Some(pb) = &progress_bar {
-                    pb.inc(1);
-                }
-                res
-            })
-            .collect();
-        all_rule_results.append(rule_results.clone().as_mut());
+                    progress.finish_file(res.0.len() as u64);
+                    res
+                })
+                .unzip();
+            rule_spill.extend(
+                rule_results
+                    .into_iter()
+                    .flatten()
+                    .inspect(|result| rule_errors.record(result)),
+                &mut prepare_results,
+            )?;
+            skipped_files.extend(skipped.into_iter().flatten());
+        }
+
+        progress.finish();
+    }
 
-        if let Some(pb) = &progress_bar {
-            pb.finish();
+    if let Some(journal) = &journal {
+        if journal.reused() > 0 {
+            println!(
+                "Reused the results of {} file(s) from the journal",
+                journal.reused()
+            );
         }
     }
+    if let Some(cache) = &result_cache {
+        println!(
+            "Reused the results of {} file(s) from the cache {} ({} file(s) analyzed, {} error(s))",
+            cache.hits(),
+            cache.name(),
+            cache.misses(),
+            cache.errors()
+        );
+    }
+
+    // the results are sorted so that the reports of two analyses of the same code are identical
+    let (mut all_rule_results, spilled_results) = match rule_spill.finish(&mut prepare_results)? {
+        AggregatedResults::InMemory(results) => (results, None),
+        AggregatedResults::Spilled(spilled) => (vec![], Some(spilled)),
+    };
+    if merge_duplicates && configuration.use_debug {
+        println!("Merged {} duplicate violation(s)", merged_violations);
+    }
+    if let Some(blamer) = &blamer {
+        if blamer.skipped_files() > 0 {
+            eprintln!(
+                "The violations of {} file(s) were not blamed: --blame is limited to {} file(s)",
+                blamer.skipped_files(),
+                blamer.blamed_files()
+            );
+        }
+    }
+    let incomplete = cancellation.is_cancelled();
+
+    detected_secrets.sort_by(|a, b| {
+        (
+            &a.file_path,
+            a.violation.start.line,
+            a.violation.start.col,
+            &a.rule_id,
+        )
+            .cmp(&(
+                &b.file_path,
+                b.violation.start.line,
+                b.violation.start.col,
+                &b.rule_id,
+            ))
+    });
+
+    if secrets_group_identical {
+        let found = detected_secrets.len();
+        detected_secrets = group_identical_secrets(detected_secrets);
+        if detected_secrets.len() < found {
+            println!(
+                "Grouped {} secret(s) into {} finding(s) with identical values",
+                found,
+                detected_secrets.len()
+            );
+        }
+    }
+
+    if let Some(codeowners) = &codeowners {
+        codeowners.assign(&mut [], &mut detected_secrets);
+    }
 
     let end_timestamp = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
         .as_secs();
 
-    let nb_violations: u32 = all_rule_results
-        .iter()
-        .map(|x| x.violations.len() as u32)
-        .sum();
+    let nb_violations: u32 = match &spilled_results {
+        Some(spilled) => spilled.violations() as u32,
+        None => all_rule_results
+            .iter()
+            .map(|x| x.violations.len() as u32)
+            .sum(),
+    };
 
     let execution_time_secs = end_timestamp - start_timestamp;
 
@@ -822,6 +2016,36 @@ fn main() -> Result<()> {
         nb_violations, total_files_analyzed, number_of_rules_used, execution_time_secs
     );
 
+    // the JSON reports of spilled results are written while the spilled files are merged, and
+    // the results are only read back in memory if anything else needs them.
+    if let Some(spilled) = &spilled_results {
+        if configuration.use_debug {
+            println!("Spilled the results to {} file(s)", spilled.files());
+        }
+        for output in &configuration.outputs {
+            if output.format == OutputFormat::Json {
+                write_json_results(&output.path, spilled.iter()?, incomplete)?;
+            }
+        }
+        let needs_results = configuration
+            .outputs
+            .iter()
+            .any(|o| o.format != OutputFormat::Json)
+            || enable_performance_statistics
+            || profile_rules
+            || print_violations
+            || group_by_owner
+            || summary_top.is_some()
+            || fix
+            || !publishers.is_empty()
+            || upload.is_some()
+            || !policies.is_empty()
+            || !fail_any_violation_severities.is_empty();
+        if needs_results {
+            all_rule_results = spilled.load()?;
+        }
+    }
+
     // If the performance statistics are enabled, we show the total execution time per rule
     // and the rule that timed-out.
     if enable_performance_statistics {
@@ -830,11 +2054,11 @@ fn main() -> Result<()> {
         // first, get the rule execution time
         for rule_result in &all_rule_results {
             let current_value = rules_execution_time_ms
-                .get(&rule_result.rule_name)
+                .get(rule_result.rule_name.as_str())
                 .unwrap_or(&0u128);
             let new_value =
                 current_value + rule_result.execution_time_ms + rule_result.query_node_time_ms;
-            rules_execution_time_ms.insert(rule_result.rule_name.clone(), new_value);
+            rules_execution_time_ms.insert(rule_result.rule_name.to_string(), new_value);
         }
 
         println!("All rules execution time");
@@ -882,8 +2106,11 @@ fn main() -> Result<()> {
 
         // organize the map to have the parsing time by file
         for rule_result in &all_rule_results {
-            if !parsing_time_ms.contains_key(&rule_result.filename) {
-                parsing_time_ms.insert(rule_result.filename.clone(), rule_result.parsing_time_ms);
+            if !parsing_time_ms.contains_key(rule_result.filename.as_str()) {
+                parsing_time_ms.insert(
+                    rule_result.filename.to_string(),
+                    rule_result.parsing_time_ms,
+                );
             }
         }
 
@@ -895,6 +2122,14 @@ fn main() -> Result<()> {
             println!("file {:?}, parsing time {:?} ms", v.0, v.1);
         }
 
+        let parsers = parser_pool_stats();
+        println!("Parsers");
+        println!("-------");
+        println!(
+            "{} parser(s) created, {} parse(s) with a reused parser",
+            parsers.created, parsers.reused
+        );
+
         // show the rules that timed out
         println!("Rule timed out");
         println!("--------------");
@@ -911,63 +2146,226 @@ fn main() -> Result<()> {
         }
     }
 
+    // If the rules are profiled, we show the resources used by each rule, by decreasing CPU time.
+    if profile_rules {
+        let mut rule_profiles: HashMap<&str, RuleProfile> = HashMap::new();
+        for rule_result in &all_rule_results {
+            let Some(profile) = &rule_result.profile else {
+                continue;
+            };
+            let total = rule_profiles
+                .entry(rule_result.rule_name.as_str())
+                .or_default();
+            total.heap_allocated_bytes += profile.heap_allocated_bytes;
+            total.cpu_time_ms += profile.cpu_time_ms;
+            total.wall_time_ms += profile.wall_time_ms;
+            total.captures += profile.captures;
+        }
+
+        println!("Top 100 most expensive rules");
+        println!("----------------------------");
+        for (rule_name, profile) in rule_profiles
+            .iter()
+            .sorted_by(|a, b| Ord::cmp(&b.1.cpu_time_ms, &a.1.cpu_time_ms))
+            .take(100)
+        {
+            println!(
+                "rule {:?}, cpu time {} ms, wall time {} ms, heap allocated {} bytes, captures {}",
+                rule_name,
+                profile.cpu_time_ms,
+                profile.wall_time_ms,
+                profile.heap_allocated_bytes,
+                profile.captures
+            );
+        }
+    }
+
     if print_violations && nb_violations > 0 {
         violations_table::print_violations_table(&all_rule_results);
     }
 
-    let value = match configuration.output_format {
-        OutputFormat::Csv => csv::generate_csv_results(&all_rule_results),
-        OutputFormat::Json => {
-            serde_json::to_string(&all_rule_results).expect("error when getting the JSON report")
-        }
-        OutputFormat::Sarif => {
-            let mut rules: Vec<SarifRule> = configuration
-                .rules
+    if group_by_owner {
+        violations_table::print_owners_table(
+            all_rule_results
                 .iter()
-                .cloned()
-                .map(|r| r.into())
-                .collect();
-            let mut results = all_rule_results
+                .flat_map(|r| &r.violations)
+                .chain(detected_secrets.iter().map(|s| &s.violation)),
+        );
+    }
+
+    if let Some(top) = summary_top {
+        let summary = violations_table::summarize_violations(
+            all_rule_results
                 .iter()
-                .cloned()
-                .map(SarifRuleResult::try_from)
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(anyhow::Error::msg)?;
-
-            rules.extend(secrets_rules.into_iter().map(SarifRule::from));
-            let detected_secrets = detected_secrets
-                .into_iter()
-                .map(SarifRuleResult::try_from)
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(anyhow::Error::msg)?;
-            results.extend(detected_secrets);
-
-            match generate_sarif_report(
-                &rules,
-                &results,
-                &directory_to_analyze,
-                SarifReportMetadata {
-                    add_git_info,
-                    debug: configuration.use_debug,
-                    config_digest: configuration.generate_diff_aware_digest(),
-                    diff_aware_parameters,
-                    execution_time_secs,
-                },
-            ) {
-                Ok(report) => {
-                    serde_json::to_string(&report).expect("error when getting the SARIF report")
-                }
-                Err(_) => {
-                    panic!("Error when generating the sarif report");
+                .flat_map(|r| {
+                    r.violations
+                        .iter()
+                        .map(|v| (r.rule_name.as_str(), r.filename.as_str(), v))
+                })
+                .chain(
+                    detected_secrets
+                        .iter()
+                        .map(|s| (s.rule_id.as_str(), s.file_path.as_str(), &s.violation)),
+                ),
+        );
+        violations_table::print_summary(&summary, top);
+    }
+
+    // write the reports, even the partial ones of an interrupted analysis
+    let report = AnalysisReport {
+        rule_results: &all_rule_results,
+        rules: &configuration.rules,
+        secrets: &detected_secrets,
+        secret_rules: &secrets_rules,
+        directory: &directory_to_analyze,
+        column_unit: analysis_options.column_unit,
+        sarif_metadata: SarifReportMetadata {
+            add_git_info,
+            debug: configuration.use_debug,
+            config_digest: configuration.generate_diff_aware_digest(),
+            diff_aware_parameters,
+            execution_time_secs,
+            incomplete,
+        },
+        incomplete,
+    };
+    for output in &configuration.outputs {
+        // the JSON reports of spilled results were already written
+        if spilled_results.is_some() && output.format == OutputFormat::Json {
+            continue;
+        }
+        FileSink(output.clone()).write(&report)?;
+    }
+    // the spilled files are removed now, as `exit` doesn't run the destructors
+    drop(spilled_results);
+
+    if fix {
+        // the fixes of the fixed files are applied again on their new content, until no fix
+        // applies: a fix may enable another one (e.g. a fix skipped because of a conflict)
+        let mut summary = FixSummary::default();
+        let mut fixed_results: Option<Vec<RuleResult>> = None;
+        for iteration in 0..fix_iterations {
+            let pass = apply_fixes(
+                directory_path,
+                fixed_results.as_deref().unwrap_or(&all_rule_results),
+                fix_unsafe,
+                analysis_options.column_unit,
+                configuration.argument_provider.editorconfig(),
+            )?;
+            if pass.files.is_empty() {
+                break;
+            }
+            if configuration.use_debug {
+                println!(
+                    "Fix iteration {}: applied {} fix(es) to {} file(s)",
+                    iteration + 1,
+                    pass.fixes,
+                    pass.files.len()
+                );
+            }
+            let mut results = reanalyze_files(
+                &configuration,
+                directory_path,
+                &pass.files,
+                &analysis_options,
+            )?;
+            apply_severity_remaps(&severity_remaps, &mut results);
+            fixed_results = Some(results);
+            summary.merge(pass);
+        }
+        println!(
+            "Applied {} fix(es) to {} file(s)",
+            summary.fixes,
+            summary.files.len()
+        );
+        if summary.skipped > 0 {
+            println!("Skipped {} conflicting or invalid fix(es)", summary.skipped);
+        }
+        // the fixed files are analyzed one last time, to report the violations the fixes introduced
+        if !summary.files.is_empty() {
+            let mut results = reanalyze_files(
+                &configuration,
+                directory_path,
+                &summary.files,
+                &analysis_options,
+            )?;
+            apply_severity_remaps(&severity_remaps, &mut results);
+            let introduced = introduced_violations(&all_rule_results, &results);
+            if !introduced.is_empty() {
+                eprintln!(
+                    "WARNING: the fixes introduced {} new violation(s):",
+                    introduced.len()
+                );
+                for (result, violation) in introduced {
+                    eprintln!(
+                        "  {}:{}:{} {} ({})",
+                        result.filename,
+                        violation.start.line,
+                        violation.start.col,
+                        violation.message,
+                        result.rule_name
+                    );
                 }
             }
         }
-    };
+    }
 
-    // write the reports
-    let mut file = fs::File::create(configuration.output_file).context("cannot create file")?;
-    file.write_all(value.as_bytes())
-        .context("error when writing results")?;
+    if let Some(skip_report_file) = skip_report_file {
+        // a file may be skipped once per language that matches it
+        skipped_files.sort();
+        skipped_files.dedup();
+        let report =
+            serde_json::to_string(&skipped_files).context("cannot serialize skip report")?;
+        fs::write(skip_report_file, report).context("error when writing the skip report")?;
+    }
+
+    // the results of an interrupted analysis are incomplete: the rules that were not executed
+    // are reported with the `analysis-cancelled` error, and the files that were not analyzed
+    // are not reported.
+    if incomplete {
+        eprintln!("The analysis was interrupted, the results are incomplete");
+        exit(EXIT_CODE_INTERRUPTED);
+    }
+
+    // the journal is only needed to resume an interrupted analysis
+    if let (Some(journal), Some(journal_file)) = (journal, &journal_file) {
+        journal.remove(std::path::Path::new(journal_file))?;
+    }
+
+    // an interrupted analysis is not published nor uploaded, as its results are incomplete
+    for publisher in &publishers {
+        publisher.write(&report)?;
+    }
+    if let Some(upload) = &upload {
+        upload.write(&report)?;
+    }
+
+    // policies are evaluated on the results of the whole analysis, and any failed policy
+    // fails the analysis
+    let policy_results = evaluate_policies(&policies, &all_rule_results);
+    for policy in policy_results.iter().filter(|p| !p.passed) {
+        eprintln!(
+            "Policy {} failed: {} violation(s) found (maximum: {})",
+            policy.name, policy.violations, policy.max_violations
+        );
+    }
+    if let Some(policy_report_file) = policy_report_file {
+        let report =
+            serde_json::to_string(&policy_results).context("cannot serialize policy report")?;
+        fs::write(policy_report_file, report).context("error when writing the policy report")?;
+    }
+
+    // the errors of the rules take precedence over the violations: the code may only look clean
+    // because the rules failed
+    if !rule_errors.is_empty() {
+        eprintln!("{}", rule_errors.report(MAX_REPORTED_RULE_ERRORS));
+        if fail_on_rule_error {
+            exit(EXIT_CODE_RULE_ERRORS);
+        }
+    }
+    if policy_results.iter().any(|p| !p.passed) {
+        exit(1);
+    }
 
     // if there is any violation at all and --fail-on-any-violation is passed, we exit 1
     if !fail_any_violation_severities.is_empty()
@@ -981,6 +2379,49 @@ fn main() -> Result<()> {
 
 const DEFAULT_MAX_CPUS: usize = 8;
 
+/// The number of files of a language analyzed before their results are aggregated.
+const ANALYSIS_BATCH_SIZE: usize = 10_000;
+
+/// The exit code of an interrupted analysis, as a shell reports a process killed by SIGINT.
+const EXIT_CODE_INTERRUPTED: i32 = 130;
+
+/// The exit code of an analysis where rules timed out or failed, with `--fail-on-rule-error`.
+const EXIT_CODE_RULE_ERRORS: i32 = 3;
+
+/// The maximum number of rules listed in the summary of the errors of the rules.
+const MAX_REPORTED_RULE_ERRORS: usize = 20;
+
+/// The maximum number of files blamed by `--blame`, when no number is given.
+const DEFAULT_BLAME_MAX_FILES: usize = 1000;
+
+/// The environment of the uploaded results, when neither `--upload-env` nor `DD_ENV` is set.
+const DEFAULT_UPLOAD_ENV: &str = "ci";
+
+/// Executes the rules with the WebAssembly runtime, and the engine module at `engine`.
+#[cfg(feature = "wasm-runtime")]
+fn select_wasm_rule_runtime(engine: &std::path::Path, show_startup: bool) -> Result<()> {
+    use kernel::analysis::rule_runtime::set_rule_runtime;
+    use kernel::analysis::wasm_runtime::WasmRuleRuntime;
+
+    let runtime = WasmRuleRuntime::from_file(engine)
+        .with_context(|| format!("cannot compile the WebAssembly engine {}", engine.display()))?;
+    if show_startup {
+        println!(
+            "rule runtime: wasm, engine compiled in {} ms",
+            runtime.compile_time().as_millis()
+        );
+    }
+    if set_rule_runtime(Box::new(runtime)).is_err() {
+        anyhow::bail!("the runtime of the rules is already selected");
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "wasm-runtime"))]
+fn select_wasm_rule_runtime(_engine: &std::path::Path, _show_startup: bool) -> Result<()> {
+    anyhow::bail!("the analyzer is built without the WebAssembly runtime (feature wasm-runtime)")
+}
+
 /// Returns the user's requested core count, clamped to the number of logical cores on the system.
 /// If unspecified, up to [DEFAULT_MAX_CPUS] CPUs will be used.
 fn choose_cpu_count(user_input: Option<usize>) -> usize {
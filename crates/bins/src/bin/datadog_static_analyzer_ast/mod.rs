@@ -0,0 +1,228 @@
+//! `datadog-static-analyzer ast <file>`: prints the tree-sitter tree of a file, as parsed by the
+//! grammars of the analyzer, with the kind, field name and position of each node.
+
+use anyhow::{anyhow, Context, Result};
+use cli::file_utils::filter_files_for_language;
+use getopts::Options;
+use kernel::analysis::tree_sitter::{get_tree, map_node};
+use kernel::model::analysis::TreeSitterNode;
+use kernel::model::common::{Language, LineIndex, Position, ALL_LANGUAGES};
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::process::exit;
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!("Usage: {program} ast [options] <file>");
+    print!("{}", opts.usage(&brief));
+}
+
+/// Runs the `ast` subcommand with its arguments.
+pub fn run(program: &str, args: &[String]) -> Result<()> {
+    let mut opts = Options::new();
+    opts.optopt(
+        "l",
+        "language",
+        "language of the file (default: the language of its extension)",
+        "python",
+    );
+    opts.optopt(
+        "",
+        "at",
+        "only print the path from the root to the node at a position",
+        "line:col",
+    );
+    opts.optflag("", "json", "print the tree as JSON");
+    opts.optflag("h", "help", "print this help");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("error when parsing arguments: {}", f);
+            exit(1);
+        }
+    };
+    if matches.opt_present("h") {
+        print_usage(program, opts);
+        exit(0);
+    }
+    let [filename] = matches.free.as_slice() else {
+        print_usage(program, opts);
+        exit(1);
+    };
+    let language = match matches.opt_str("l") {
+        Some(language) => {
+            Language::from_name(&language).ok_or_else(|| anyhow!("unknown language {language}"))?
+        }
+        None => detect_language(filename)
+            .ok_or_else(|| anyhow!("cannot detect the language of {filename}, use --language"))?,
+    };
+    let position = matches
+        .opt_str("at")
+        .as_deref()
+        .map(parse_position)
+        .transpose()?;
+    let code =
+        fs::read_to_string(filename).with_context(|| format!("cannot read file {filename}"))?;
+
+    let tree = get_tree(&code, &language)
+        .ok_or_else(|| anyhow!("the file cannot be parsed as {language}"))?;
+    let root = map_node(tree.root_node()).ok_or_else(|| anyhow!("the tree is empty"))?;
+    if tree.root_node().has_error() {
+        eprintln!("the file has syntax errors: the tree contains ERROR or MISSING nodes");
+    }
+    let json = matches.opt_present("json");
+    match position {
+        Some(position) => {
+            let path = node_path(&root, &position);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&path)?);
+            } else {
+                print!("{}", format_nodes(&path, &code, false));
+            }
+        }
+        None if json => println!("{}", serde_json::to_string_pretty(&root)?),
+        None => print!("{}", format_nodes(std::slice::from_ref(&root), &code, true)),
+    }
+    Ok(())
+}
+
+/// Returns the language of a file, from its name.
+fn detect_language(filename: &str) -> Option<Language> {
+    let path = PathBuf::from(filename);
+    ALL_LANGUAGES.iter().copied().find(|language| {
+        !filter_files_for_language(std::slice::from_ref(&path), language, &Default::default())
+            .is_empty()
+    })
+}
+
+/// Parses a position of the form `<line>:<col>`, where lines and columns start at 1.
+fn parse_position(position: &str) -> Result<Position> {
+    let parse = |n: &str| n.parse::<u32>().ok().filter(|n| *n > 0);
+    position
+        .split_once(':')
+        .and_then(|(line, col)| {
+            Some(Position {
+                line: parse(line)?,
+                col: parse(col)?,
+            })
+        })
+        .ok_or_else(|| anyhow!("invalid position {position}, expecting <line>:<col>"))
+}
+
+/// Returns the nodes from the root to the deepest named node at the position, without their
+/// children.
+fn node_path(root: &TreeSitterNode, position: &Position) -> Vec<TreeSitterNode> {
+    let contains = |node: &TreeSitterNode| {
+        (node.start.line, node.start.col) <= (position.line, position.col)
+            && (position.line, position.col) < (node.end.line, node.end.col)
+    };
+    let mut path = vec![];
+    let mut node = Some(root);
+    while let Some(current) = node {
+        path.push(TreeSitterNode {
+            children: vec![],
+            ..current.clone()
+        });
+        node = current.children.iter().find(|child| contains(child));
+    }
+    path
+}
+
+/// Formats the nodes, one per line: `[field: ]kind start-end`, followed by the text of the nodes
+/// without children. The children are formatted below their parent, indented, if `recursive`.
+fn format_nodes(nodes: &[TreeSitterNode], code: &str, recursive: bool) -> String {
+    fn format_node(
+        output: &mut String,
+        node: &TreeSitterNode,
+        depth: usize,
+        line_index: &LineIndex,
+        code: &str,
+        recursive: bool,
+    ) {
+        let _ = write!(
+            output,
+            "{:indent$}{}{} {}:{}-{}:{}",
+            "",
+            node.field_name
+                .as_ref()
+                .map(|field| format!("{field}: "))
+                .unwrap_or_default(),
+            node.ast_type,
+            node.start.line,
+            node.start.col,
+            node.end.line,
+            node.end.col,
+            indent = depth * 2
+        );
+        if node.children.is_empty() {
+            if let Some(offsets) = line_index.offsets(&node.start, &node.end) {
+                let _ = write!(output, " {:?}", &code[offsets.start_byte..offsets.end_byte]);
+            }
+        }
+        output.push('\n');
+        if recursive {
+            for child in &node.children {
+                format_node(output, child, depth + 1, line_index, code, recursive);
+            }
+        }
+    }
+
+    let line_index = LineIndex::new(code);
+    let mut output = String::new();
+    for (index, node) in nodes.iter().enumerate() {
+        // the nodes of a path are indented as if they were printed with their ancestors
+        let depth = if recursive { 0 } else { index };
+        format_node(&mut output, node, depth, &line_index, code, recursive);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = "x = foo(1)\n";
+
+    fn python_tree() -> TreeSitterNode {
+        map_node(get_tree(CODE, &Language::Python).unwrap().root_node()).unwrap()
+    }
+
+    #[test]
+    fn format_tree() {
+        assert_eq!(
+            format_nodes(&[python_tree()], CODE, true),
+            "module 1:1-2:1
+  expression_statement 1:1-1:11
+    assignment 1:1-1:11
+      left: identifier 1:1-1:2 \"x\"
+      right: call 1:5-1:11
+        function: identifier 1:5-1:8 \"foo\"
+        arguments: argument_list 1:8-1:11
+          integer 1:9-1:10 \"1\"
+"
+        );
+    }
+
+    #[test]
+    fn path_at_position() {
+        let path = node_path(&python_tree(), &parse_position("1:6").unwrap());
+        assert_eq!(
+            format_nodes(&path, CODE, false),
+            "module 1:1-2:1
+  expression_statement 1:1-1:11
+    assignment 1:1-1:11
+      right: call 1:5-1:11
+        function: identifier 1:5-1:8 \"foo\"
+"
+        );
+        assert!(parse_position("1").is_err());
+        assert!(parse_position("0:1").is_err());
+    }
+
+    #[test]
+    fn detect_languages() {
+        assert_eq!(detect_language("src/main.go"), Some(Language::Go));
+        assert_eq!(detect_language("Dockerfile"), Some(Language::Dockerfile));
+        assert_eq!(detect_language("README"), None);
+    }
+}
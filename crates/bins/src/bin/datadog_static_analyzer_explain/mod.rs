@@ -0,0 +1,285 @@
+//! `datadog-static-analyzer explain <file>:<line> --rule <name>`: re-runs a rule on a file and
+//! traces why a violation is, or isn't, reported on a line.
+
+use crate::datadog_static_analyzer_rules::load_rules;
+use anyhow::{anyhow, Context, Result};
+use cli::file_utils::check_file_selection;
+use getopts::Options;
+use kernel::analysis::analyze::{analyze, get_lines_to_ignore_with_tree, get_rule_matches};
+use kernel::analysis::tree_sitter::get_tree;
+use kernel::arguments::ArgumentProvider;
+use kernel::model::analysis::{AnalysisOptions, TreeSitterNode};
+use kernel::model::common::{ColumnUnit, LineIndex};
+use kernel::model::config_file::split_path;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+
+/// The number of lines shown before and after the lines of a match.
+const CONTEXT_LINES: u32 = 2;
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!("Usage: {program} explain <file>:<line> --rule <name> [options]");
+    print!("{}", opts.usage(&brief));
+}
+
+/// Runs the `explain` subcommand with its arguments.
+pub fn run(program: &str, args: &[String]) -> Result<()> {
+    let mut opts = Options::new();
+    opts.optopt("", "rule", "name of the rule to explain", "ruleset/rule");
+    opts.optopt(
+        "i",
+        "directory",
+        "directory of the repository (default: the current directory)",
+        "/path/to/repository",
+    );
+    opts.optopt(
+        "r",
+        "rules",
+        "rules to use (json file), if the repository has no configuration file",
+        "/path/to/rules.json",
+    );
+    opts.optflag("s", "staging", "use staging");
+    opts.optflag("h", "help", "print this help");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("error when parsing arguments: {}", f);
+            exit(1);
+        }
+    };
+    if matches.opt_present("h") {
+        print_usage(program, opts);
+        exit(0);
+    }
+    let (Some(rule_name), [location]) = (matches.opt_str("rule"), matches.free.as_slice()) else {
+        print_usage(program, opts);
+        exit(1);
+    };
+    let (filename, line) = parse_location(location)?;
+    let directory = matches.opt_str("i").unwrap_or_else(|| ".".to_string());
+
+    let (rules, config) = load_rules(
+        &directory,
+        matches.opt_str("r").as_deref(),
+        matches.opt_present("s"),
+    )?;
+    let rule = rules
+        .iter()
+        .find(|rule| rule.name == rule_name)
+        .ok_or_else(|| anyhow!("rule {rule_name} is not enabled for {directory}"))?;
+    let path = Path::new(&directory).join(filename);
+    let code = fs::read_to_string(&path)
+        .with_context(|| format!("cannot read file {}", path.display()))?;
+
+    println!("Rule {} on {}:{}", rule.name, filename, line);
+    println!("\nFile selection");
+    let checks = check_file_selection(
+        Path::new(&directory),
+        filename,
+        &code,
+        rule,
+        config.as_ref(),
+    )?;
+    for check in &checks {
+        let mark = if check.passed { "x" } else { " " };
+        println!("  [{}] {}", mark, check.description);
+    }
+    if checks.iter().any(|check| !check.passed) {
+        println!("  the file is not analyzed with this rule");
+    }
+
+    let rule_internal = rule.to_rule_internal()?;
+    let argument_provider = config
+        .as_ref()
+        .map(ArgumentProvider::from)
+        .unwrap_or_default();
+    let arguments = argument_provider.get_arguments(&split_path(filename), &rule.name);
+    let Some(tree) = get_tree(&code, &rule.language) else {
+        println!("\nthe file cannot be parsed as {}", rule.language);
+        return Ok(());
+    };
+    if tree.root_node().has_error() {
+        println!("\nthe file has syntax errors, the matches may be incomplete");
+    }
+
+    let line_index = LineIndex::new(&code);
+    let nodes = get_rule_matches(&tree, &rule_internal, filename, code.as_str(), &arguments);
+    println!("\nMatches ({} in the file)", nodes.len());
+    for node in &nodes {
+        let mut captures = node
+            .captures
+            .iter()
+            .map(|(name, capture)| (name, vec![capture]))
+            .chain(
+                node.captures_list
+                    .iter()
+                    .map(|(name, captures)| (name, captures.iter().collect())),
+            )
+            .filter(|(_, captures)| captures.iter().any(|c| spans_line(c, line)))
+            .collect::<Vec<_>>();
+        if captures.is_empty() {
+            continue;
+        }
+        captures.sort_by_key(|(name, _)| *name);
+        match &node.query_name {
+            Some(query_name) => println!("  match of the query `{query_name}`"),
+            None => println!("  match"),
+        }
+        let captured = captures.iter().flat_map(|(_, captures)| captures);
+        let start = captured.clone().map(|c| c.start.line).min().unwrap_or(line);
+        let end = captured.map(|c| c.end.line).max().unwrap_or(line);
+        print!("{}", code_lines(&code, start, end, line));
+        for (name, captures) in captures {
+            for capture in captures {
+                println!(
+                    "    @{} {} {}:{}-{}:{} {:?}",
+                    name,
+                    capture.ast_type,
+                    capture.start.line,
+                    capture.start.col,
+                    capture.end.line,
+                    capture.end.col,
+                    node_text(&line_index, &code, capture)
+                );
+            }
+        }
+    }
+    let mut arguments = arguments.into_iter().collect::<Vec<_>>();
+    arguments.sort();
+    println!("\nContext passed to the rule");
+    println!("  filename : {}", filename);
+    println!("  arguments: {:?}", arguments);
+
+    println!("\nExecution");
+    let results = analyze(
+        &rule.language,
+        [&rule_internal],
+        filename,
+        &code,
+        &argument_provider,
+        &AnalysisOptions {
+            log_output: true,
+            use_debug: true,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        },
+    );
+    let Some(result) = results.first() else {
+        println!("  the rule was not executed");
+        return Ok(());
+    };
+    if let Some(records) = result.output.as_ref().filter(|r| !r.is_empty()) {
+        println!("  console output:");
+        for record in records {
+            println!("    [{:?}] {}", record.level, record.message);
+        }
+    }
+    if let Some(error) = &result.execution_error {
+        println!("  execution error: {error}");
+    }
+    for error in &result.errors {
+        println!("  error: {error}");
+    }
+    let violations = result
+        .violations
+        .iter()
+        .filter(|v| v.start.line <= line && line <= v.end.line)
+        .collect::<Vec<_>>();
+    for violation in &violations {
+        println!(
+            "  violation {}:{}-{}:{}: {}",
+            violation.start.line,
+            violation.start.col,
+            violation.end.line,
+            violation.end.col,
+            violation.message
+        );
+    }
+
+    println!("\nSuppressions");
+    let lines_to_ignore = get_lines_to_ignore_with_tree(&code, &rule.language, Some(&tree));
+    if lines_to_ignore.should_filter_rule(&rule.name, line) {
+        println!("  a violation on line {line} is suppressed by a `no-dd-sa` comment");
+    } else {
+        println!("  no `no-dd-sa` comment applies to line {line}");
+    }
+    if violations.is_empty() {
+        println!("\nNo violation of {} is reported on line {line}", rule.name);
+    }
+    Ok(())
+}
+
+/// Parses a location of the form `<file>:<line>`.
+fn parse_location(location: &str) -> Result<(&str, u32)> {
+    let (filename, line) = location
+        .rsplit_once(':')
+        .ok_or_else(|| anyhow!("invalid location {location}, expecting <file>:<line>"))?;
+    let line = line
+        .parse::<u32>()
+        .ok()
+        .filter(|line| *line > 0)
+        .ok_or_else(|| anyhow!("invalid line in {location}"))?;
+    Ok((filename, line))
+}
+
+/// Returns the lines from `start` to `end` with some context and their numbers, with `line`
+/// highlighted.
+fn code_lines(code: &str, start: u32, end: u32, line: u32) -> String {
+    let first = start.saturating_sub(CONTEXT_LINES).max(1);
+    let last = end.saturating_add(CONTEXT_LINES);
+    code.lines()
+        .zip(1..)
+        .filter(|(_, number)| (first..=last).contains(number))
+        .map(|(text, number)| {
+            let marker = if number == line { '>' } else { ' ' };
+            format!("   {marker}{number:>5} | {text}\n")
+        })
+        .collect()
+}
+
+fn spans_line(node: &TreeSitterNode, line: u32) -> bool {
+    node.start.line <= line && line <= node.end.line
+}
+
+fn node_text<'a>(line_index: &LineIndex, code: &'a str, node: &TreeSitterNode) -> &'a str {
+    match line_index.offsets(&node.start, &node.end) {
+        Some(offsets) => &code[offsets.start_byte..offsets.end_byte],
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations() {
+        assert_eq!(
+            parse_location("src/main.py:12").unwrap(),
+            ("src/main.py", 12)
+        );
+        assert_eq!(parse_location("c:/a.py:3").unwrap(), ("c:/a.py", 3));
+        assert!(parse_location("src/main.py").is_err());
+        assert!(parse_location("src/main.py:0").is_err());
+        assert!(parse_location("src/main.py:abc").is_err());
+    }
+
+    #[test]
+    fn lines_with_context() {
+        let code = "a\nb\nc\nd\ne\nf\ng\n";
+        assert_eq!(
+            code_lines(code, 4, 4, 4),
+            "        2 | b\n        3 | c\n   >    4 | d\n        5 | e\n        6 | f\n"
+        );
+        assert_eq!(
+            code_lines(code, 1, 2, 1),
+            "   >    1 | a\n        2 | b\n        3 | c\n        4 | d\n"
+        );
+    }
+}
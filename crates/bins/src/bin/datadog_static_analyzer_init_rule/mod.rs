@@ -0,0 +1,58 @@
+//! `datadog-static-analyzer init-rule --language <language> --name <ruleset>/<rule>`: creates the
+//! directory of a new local rule, with an example query, code and test files.
+
+use anyhow::{anyhow, Result};
+use cli::local_rules::scaffold_rule;
+use getopts::Options;
+use kernel::model::common::Language;
+use std::path::Path;
+use std::process::exit;
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!(
+        "Usage: {program} init-rule --language <language> --name <ruleset>/<rule> [options]"
+    );
+    print!("{}", opts.usage(&brief));
+}
+
+/// Runs the `init-rule` subcommand with its arguments.
+pub fn run(program: &str, args: &[String]) -> Result<()> {
+    let mut opts = Options::new();
+    opts.optopt("l", "language", "language of the rule", "python");
+    opts.optopt("n", "name", "name of the rule", "my-ruleset/my-rule");
+    opts.optopt(
+        "d",
+        "directory",
+        "directory of the local rules (default: rules)",
+        "/path/to/rules",
+    );
+    opts.optflag("h", "help", "print this help");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("error when parsing arguments: {}", f);
+            exit(1);
+        }
+    };
+    if matches.opt_present("h") {
+        print_usage(program, opts);
+        exit(0);
+    }
+    let (Some(language), Some(name)) = (matches.opt_str("l"), matches.opt_str("n")) else {
+        print_usage(program, opts);
+        exit(1);
+    };
+    let language =
+        Language::from_name(&language).ok_or_else(|| anyhow!("unknown language {language}"))?;
+    let directory = matches.opt_str("d").unwrap_or_else(|| "rules".to_string());
+
+    let files = scaffold_rule(Path::new(&directory), language, &name)?;
+    println!("Created the rule {name}:");
+    for file in files {
+        println!("  {}", file.display());
+    }
+    println!(
+        "\nTest the rule with `datadog-static-analyzer-test-ruleset --local {directory}`, and use it with `datadog-static-analyzer --rules {directory}`."
+    );
+    Ok(())
+}
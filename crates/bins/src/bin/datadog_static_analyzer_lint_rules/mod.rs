@@ -0,0 +1,75 @@
+//! `datadog-static-analyzer lint-rules`: checks the definitions of rules for common problems,
+//! without analyzing any code.
+
+use crate::datadog_static_analyzer_rules::load_rules;
+use anyhow::{Context, Result};
+use cli::rule_lint::{lint_rule, LintLevel};
+use cli::rule_utils::get_rulesets_from_file;
+use getopts::Options;
+use std::process::exit;
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!("Usage: {program} lint-rules [-r /path/to/rules] [options]");
+    print!("{}", opts.usage(&brief));
+}
+
+/// Runs the `lint-rules` subcommand with its arguments.
+pub fn run(program: &str, args: &[String]) -> Result<()> {
+    let mut opts = Options::new();
+    opts.optopt(
+        "r",
+        "rules",
+        "rules to check (json file, or directory of local rules)",
+        "/path/to/rules",
+    );
+    opts.optopt(
+        "i",
+        "directory",
+        "without -r, check the rules used to analyze this directory (default: the current directory)",
+        "/path/to/directory",
+    );
+    opts.optflag("s", "staging", "use staging");
+    opts.optflag("h", "help", "print this help");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("error when parsing arguments: {}", f);
+            exit(1);
+        }
+    };
+    if matches.opt_present("h") {
+        print_usage(program, opts);
+        exit(0);
+    }
+
+    let rules = match matches.opt_str("r") {
+        Some(rules_file) => get_rulesets_from_file(&rules_file)
+            .with_context(|| format!("cannot read the rules of {rules_file}"))?
+            .into_iter()
+            .flat_map(|r| r.rules)
+            .collect(),
+        None => {
+            let directory = matches.opt_str("i").unwrap_or_else(|| ".".to_string());
+            load_rules(&directory, None, matches.opt_present("s"))?.0
+        }
+    };
+
+    let problems = rules.iter().flat_map(lint_rule).collect::<Vec<_>>();
+    for problem in &problems {
+        println!("{:<7} {}: {}", problem.level, problem.rule, problem.message);
+    }
+    let errors = problems
+        .iter()
+        .filter(|p| p.level == LintLevel::Error)
+        .count();
+    println!(
+        "{} rule(s) checked: {} error(s), {} warning(s)",
+        rules.len(),
+        errors,
+        problems.len() - errors
+    );
+    if errors > 0 {
+        exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! `datadog-static-analyzer merge-results`: merges the reports of the shards of an analysis
+//! (`--shard i/N`) into a single report.
+
+use anyhow::{anyhow, Context, Result};
+use cli::output::{
+    output_format_from_name, AnalysisReport, FileSink, OutputSink, OutputSpec, STDOUT,
+};
+use cli::sarif::sarif_utils::SarifReportMetadata;
+use cli::shard::{count_sarif_files, merge_json_reports, merge_sarif_reports};
+use getopts::Options;
+use kernel::model::common::{ColumnUnit, OutputFormat};
+use std::collections::HashSet;
+use std::fs;
+use std::process::exit;
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!("Usage: {program} merge-results -o <output> [options] <report>...");
+    print!("{}", opts.usage(&brief));
+}
+
+/// Returns whether the report is a SARIF report, instead of a JSON report.
+fn is_sarif_report(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content)
+        .is_ok_and(|report| report.get("runs").is_some())
+}
+
+/// Runs the `merge-results` subcommand with its arguments.
+pub fn run(program: &str, args: &[String]) -> Result<()> {
+    let mut opts = Options::new();
+    opts.optopt(
+        "o",
+        "output",
+        "output file, or - for the standard output (prefix with <format>= to set the format)",
+        "[sarif=]output.sarif",
+    );
+    opts.optopt(
+        "f",
+        "format",
+        "format of the output file: json, sarif (the format of the reports), csv, html or pretty",
+        "json|sarif|csv|html|pretty",
+    );
+    opts.optopt(
+        "i",
+        "directory",
+        "directory that was analyzed, to read the code of the violations in the html and pretty formats (default: the current directory)",
+        "/path/to/directory",
+    );
+    opts.optflag("h", "help", "print this help");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("error when parsing arguments: {}", f);
+            exit(1);
+        }
+    };
+    if matches.opt_present("h") {
+        print_usage(program, opts);
+        exit(0);
+    }
+    let Some(output) = matches.opt_str("o") else {
+        eprintln!("no output specified (use -o)");
+        print_usage(program, opts);
+        exit(1);
+    };
+    if matches.free.is_empty() {
+        eprintln!("no report to merge");
+        print_usage(program, opts);
+        exit(1);
+    }
+
+    let reports = matches
+        .free
+        .iter()
+        .map(|path| fs::read_to_string(path).with_context(|| format!("cannot read {path}")))
+        .collect::<Result<Vec<_>>>()?;
+    let sarif_reports = reports.iter().filter(|r| is_sarif_report(r)).count();
+    if sarif_reports != 0 && sarif_reports != reports.len() {
+        return Err(anyhow!(
+            "the reports to merge are not all in the same format (json or sarif)"
+        ));
+    }
+    let input_format = if sarif_reports == 0 {
+        OutputFormat::Json
+    } else {
+        OutputFormat::Sarif
+    };
+    let default_format = match matches.opt_str("f") {
+        Some(name) => output_format_from_name(&name).ok_or_else(|| {
+            anyhow!("invalid format {name} (expected json, sarif, csv, html or pretty)")
+        })?,
+        None => input_format.clone(),
+    };
+    let output = OutputSpec::parse(&output, &default_format)?;
+    let to_stdout = output.path == STDOUT;
+
+    let (violations, files) = if input_format == OutputFormat::Sarif {
+        // the rules and the properties of SARIF results are not in the JSON reports, so a SARIF
+        // report can only be written from SARIF reports, and the other way around.
+        if output.format != OutputFormat::Sarif {
+            return Err(anyhow!(
+                "SARIF reports can only be merged into a SARIF report"
+            ));
+        }
+        let (report, violations) = merge_sarif_reports(&reports)?;
+        let content =
+            serde_json::to_string(&report).context("error when getting the SARIF report")?;
+        if to_stdout {
+            println!("{content}");
+        } else {
+            fs::write(&output.path, content)
+                .with_context(|| format!("error when writing results to {}", output.path))?;
+        }
+        (violations, count_sarif_files(&report))
+    } else {
+        if output.format == OutputFormat::Sarif {
+            return Err(anyhow!(
+                "JSON reports cannot be merged into a SARIF report: use the sarif format for the reports of the shards"
+            ));
+        }
+        let merged = merge_json_reports(&reports)?;
+        if merged.duplicates > 0 {
+            eprintln!(
+                "WARNING: {} result(s) were in more than one report and were merged: were the shards of the same analysis?",
+                merged.duplicates
+            );
+        }
+        let directory = matches.opt_str("i").unwrap_or_else(|| ".".to_string());
+        let report = AnalysisReport {
+            rule_results: &merged.results,
+            rules: &[],
+            secrets: &[],
+            secret_rules: &[],
+            directory: &directory,
+            column_unit: ColumnUnit::default(),
+            sarif_metadata: SarifReportMetadata {
+                add_git_info: false,
+                debug: false,
+                config_digest: String::new(),
+                diff_aware_parameters: None,
+                execution_time_secs: 0,
+                incomplete: merged.incomplete,
+            },
+            incomplete: merged.incomplete,
+        };
+        FileSink(output).write(&report)?;
+        if merged.incomplete {
+            eprintln!("WARNING: the analysis of at least one shard was interrupted: the results are incomplete");
+        }
+        (
+            merged.results.iter().map(|r| r.violations.len()).sum(),
+            merged
+                .results
+                .iter()
+                .filter(|r| !r.violations.is_empty())
+                .map(|r| r.filename.as_str())
+                .collect::<HashSet<_>>()
+                .len(),
+        )
+    };
+    // the summary goes to the standard error when the report is written to the standard output
+    let summary = format!(
+        "Found {} violation(s) in {} file(s) from {} shard(s)",
+        violations,
+        files,
+        reports.len()
+    );
+    if to_stdout {
+        eprintln!("{summary}");
+    } else {
+        println!("{summary}");
+    }
+    Ok(())
+}
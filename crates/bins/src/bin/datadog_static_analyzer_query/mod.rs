@@ -0,0 +1,136 @@
+//! `datadog-static-analyzer query --language <language> --query <pattern> <file>`: prints the
+//! captures of a tree-sitter query on a file, to write the query of a rule without writing the
+//! rule itself.
+
+use anyhow::{anyhow, Context, Result};
+use getopts::Options;
+use kernel::analysis::tree_sitter::{get_query, get_tree, TSCaptureContent};
+use kernel::model::common::Language;
+use std::fmt::Write;
+use std::fs;
+use std::process::exit;
+
+fn print_usage(program: &str, opts: Options) {
+    let brief =
+        format!("Usage: {program} query --language <language> --query <pattern> [options] <file>");
+    print!("{}", opts.usage(&brief));
+}
+
+/// Runs the `query` subcommand with its arguments.
+pub fn run(program: &str, args: &[String]) -> Result<()> {
+    let mut opts = Options::new();
+    opts.optopt("l", "language", "language of the file", "go");
+    opts.optopt("q", "query", "tree-sitter query", "(identifier) @name");
+    opts.optopt(
+        "",
+        "query-file",
+        "file with the tree-sitter query (e.g. the query.scm of a local rule)",
+        "query.scm",
+    );
+    opts.optflag("", "sexp", "print the S-expression of the tree of the file");
+    opts.optflag("h", "help", "print this help");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("error when parsing arguments: {}", f);
+            exit(1);
+        }
+    };
+    if matches.opt_present("h") {
+        print_usage(program, opts);
+        exit(0);
+    }
+    let query = match (matches.opt_str("q"), matches.opt_str("query-file")) {
+        (Some(query), None) => query,
+        (None, Some(query_file)) => fs::read_to_string(&query_file)
+            .with_context(|| format!("cannot read the query file {query_file}"))?,
+        _ => {
+            print_usage(program, opts);
+            exit(1);
+        }
+    };
+    let (Some(language), [filename]) = (matches.opt_str("l"), matches.free.as_slice()) else {
+        print_usage(program, opts);
+        exit(1);
+    };
+    let language =
+        Language::from_name(&language).ok_or_else(|| anyhow!("unknown language {language}"))?;
+    let code =
+        fs::read_to_string(filename).with_context(|| format!("cannot read file {filename}"))?;
+
+    print!(
+        "{}",
+        format_query_matches(&code, language, &query, matches.opt_present("sexp"))?
+    );
+    Ok(())
+}
+
+/// Returns the captures of each match of the query on the code, with their positions (lines and
+/// columns start at 1, as in the violations) and their text.
+fn format_query_matches(code: &str, language: Language, query: &str, sexp: bool) -> Result<String> {
+    let query = get_query(query, &language).context("invalid query")?;
+    let tree = get_tree(code, &language)
+        .ok_or_else(|| anyhow!("the file cannot be parsed as {language}"))?;
+    let mut output = String::new();
+    if sexp {
+        writeln!(output, "{}\n", tree.root_node().to_sexp())?;
+    }
+    if tree.root_node().has_error() {
+        writeln!(
+            output,
+            "the file has syntax errors, the matches may be incomplete\n"
+        )?;
+    }
+
+    let mut count = 0;
+    for query_match in query.cursor().matches(tree.root_node(), code) {
+        count += 1;
+        writeln!(output, "match {count}")?;
+        for capture in query_match {
+            let nodes = match capture.contents {
+                TSCaptureContent::Single(node) => vec![node],
+                TSCaptureContent::Multi(nodes) => nodes,
+            };
+            for node in nodes {
+                let (start, end) = (node.start_position(), node.end_position());
+                writeln!(
+                    output,
+                    "  @{} {} {}:{}-{}:{} {:?}",
+                    capture.name,
+                    node.kind(),
+                    start.row + 1,
+                    start.column + 1,
+                    end.row + 1,
+                    end.column + 1,
+                    node.utf8_text(code.as_bytes()).unwrap_or_default()
+                )?;
+            }
+        }
+    }
+    writeln!(output, "{count} match(es)")?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_matches() {
+        let code = "package main\n\nfunc main() {\n\tpanic(\"a\")\n\tprintln(\"b\")\n}\n";
+        let output = format_query_matches(
+            code,
+            Language::Go,
+            "(call_expression function: (identifier) @name (#eq? @name \"panic\")) @call",
+            false,
+        )
+        .unwrap();
+        assert!(output.starts_with("match 1\n"));
+        assert!(output.contains("  @name identifier 4:2-4:7 \"panic\"\n"));
+        assert!(output.contains("  @call call_expression 4:2-4:12 \"panic(\\\"a\\\")\"\n"));
+        assert!(output.ends_with("1 match(es)\n"));
+        let output = format_query_matches(code, Language::Go, "(package_clause) @p", true).unwrap();
+        assert!(output.starts_with("(source_file (package_clause"));
+        assert!(format_query_matches(code, Language::Go, "(call_expression", false).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! `datadog-static-analyzer list-rules` and `datadog-static-analyzer describe-rule`: print the
+//! rules that an analysis of a directory would use, once its configuration file is resolved.
+
+use anyhow::{anyhow, Context, Result};
+use cli::config_file::read_config_file;
+use cli::datadog_utils::get_all_default_rulesets;
+use cli::rule_utils::{
+    describe_rule, format_rules_list, get_rules_from_config_file, get_rulesets_from_file,
+};
+use getopts::Options;
+use kernel::arguments::ArgumentProvider;
+use kernel::model::config_file::ConfigFile;
+use kernel::model::rule::Rule;
+use std::process::exit;
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!(
+        "Usage: {program} list-rules -i /path/to/directory [options]\n       {program} describe-rule -i /path/to/directory [options] RULE_NAME"
+    );
+    print!("{}", opts.usage(&brief));
+}
+
+/// Runs the `list-rules` or `describe-rule` subcommand with its arguments.
+pub fn run(program: &str, command: &str, args: &[String]) -> Result<()> {
+    let mut opts = Options::new();
+    opts.optopt(
+        "i",
+        "directory",
+        "directory whose configuration file selects the rules",
+        "/path/to/directory",
+    );
+    opts.optopt(
+        "r",
+        "rules",
+        "rules to use (json file), if the directory has no configuration file",
+        "/path/to/rules.json",
+    );
+    opts.optflag("s", "staging", "use staging");
+    opts.optflag("h", "help", "print this help");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("error when parsing arguments: {}", f);
+            exit(1);
+        }
+    };
+    if matches.opt_present("h") {
+        print_usage(program, opts);
+        exit(0);
+    }
+    let Some(directory) = matches.opt_str("i") else {
+        eprintln!("no directory passed, specify a directory with option -i");
+        print_usage(program, opts);
+        exit(1);
+    };
+
+    let (rules, config) = load_rules(
+        &directory,
+        matches.opt_str("r").as_deref(),
+        matches.opt_present("s"),
+    )?;
+    let argument_provider = config
+        .as_ref()
+        .map(ArgumentProvider::from)
+        .unwrap_or_default();
+    if command == "list-rules" {
+        print!("{}", format_rules_list(&rules, &argument_provider));
+        return Ok(());
+    }
+    let [rule_name] = matches.free.as_slice() else {
+        print_usage(program, opts);
+        exit(1);
+    };
+    let rule = rules
+        .iter()
+        .find(|rule| rule.name == *rule_name)
+        .ok_or_else(|| anyhow!("rule {rule_name} is not enabled for {directory}"))?;
+    print!("{}", describe_rule(rule, &argument_provider));
+    Ok(())
+}
+
+/// Returns the rules used to analyze a directory, along with its configuration file: the rules of
+/// the configuration file, the rules of the rules file, or the default rules.
+pub(crate) fn load_rules(
+    directory: &str,
+    rules_file: Option<&str>,
+    use_staging: bool,
+) -> Result<(Vec<Rule>, Option<ConfigFile>)> {
+    let config = read_config_file(directory)
+        .with_context(|| format!("cannot read the configuration file of {directory}"))?;
+    let rulesets = match (config, rules_file) {
+        (Some(config), None) => {
+            let rules = get_rules_from_config_file(&config, use_staging)
+                .context("error when reading rules from API")?;
+            return Ok((rules, Some(config)));
+        }
+        (Some(_), Some(_)) => {
+            return Err(anyhow!(
+                "a rule file cannot be specified when a configuration file is present."
+            ))
+        }
+        (None, Some(rules_file)) => {
+            get_rulesets_from_file(rules_file).context("cannot read ruleset from file")?
+        }
+        (None, None) => {
+            get_all_default_rulesets(use_staging).context("cannot get default rules")?
+        }
+    };
+    let rules = rulesets.into_iter().flat_map(|r| r.rules).collect();
+    Ok((rules, None))
+}
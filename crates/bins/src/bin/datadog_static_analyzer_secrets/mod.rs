@@ -0,0 +1,241 @@
+//! `datadog-static-analyzer secrets`: scans the changes of a repository for secrets before they
+//! are committed or pushed, to be used in git hooks.
+
+mod update_rules;
+
+use anyhow::{anyhow, Context, Result};
+use cli::changed_files::{
+    get_files_changed_since, get_hooks_directory, get_staged_files, ChangedFile,
+};
+use cli::config_file::read_config_file;
+use cli::secrets::{SecretResult, ValidationStatus};
+use cli::secrets_allowlist::SecretsAllowlist;
+use getopts::Options;
+use rayon::prelude::*;
+use secrets::ScannerBuilder;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+/// The revision that the commits of a push are compared to.
+const PUSH_BASE: &str = "@{upstream}";
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!(
+        "Usage: {program} secrets --staged --rules /path/to/secrets-rules.yml [options]\n       {program} secrets --install-hook pre-commit|pre-push --rules /path/to/secrets-rules.yml\n       {program} secrets update-rules [--rules-version VERSION]"
+    );
+    print!("{}", opts.usage(&brief));
+}
+
+/// Runs the `secrets` subcommand with its arguments. Exits with 1 if a secret is found.
+pub fn run(program: &str, args: &[String]) -> Result<()> {
+    if args.first().map(String::as_str) == Some("update-rules") {
+        return update_rules::run(program, &args[1..]);
+    }
+    let mut opts = Options::new();
+    opts.optflag("", "staged", "scan the lines staged in the git index");
+    opts.optopt(
+        "",
+        "since",
+        "scan the lines added since a revision (e.g. @{upstream})",
+        "REVISION",
+    );
+    opts.optopt(
+        "r",
+        "rules",
+        "path to a YAML file containing secrets scanner rules",
+        "/path/to/secrets-rules.yml",
+    );
+    opts.optopt(
+        "i",
+        "directory",
+        "directory of the git repository (default: the current directory)",
+        "/path/to/repository",
+    );
+    opts.optflagopt(
+        "",
+        "install-hook",
+        "install a git hook that runs the scan (default: pre-commit)",
+        "pre-commit|pre-push",
+    );
+    opts.optflag("h", "help", "print this help");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("error when parsing arguments: {}", f);
+            exit(1);
+        }
+    };
+    if matches.opt_present("h") {
+        print_usage(program, opts);
+        exit(0);
+    }
+
+    let directory = PathBuf::from(matches.opt_str("i").unwrap_or_else(|| ".".to_string()));
+    let Some(rules) = matches.opt_str("r") else {
+        eprintln!("no secrets rules passed, specify them with option --rules");
+        exit(1);
+    };
+
+    if matches.opt_present("install-hook") {
+        let hook = matches
+            .opt_str("install-hook")
+            .unwrap_or_else(|| "pre-commit".to_string());
+        let path = install_hook(program, &directory, &hook, Path::new(&rules))?;
+        println!("Installed the {hook} hook in {}", path.display());
+        return Ok(());
+    }
+
+    let files = match (matches.opt_present("staged"), matches.opt_str("since")) {
+        (true, None) => get_staged_files(&directory)?,
+        (false, Some(base)) => get_files_changed_since(&directory, &base)?,
+        _ => {
+            eprintln!("specify either --staged or --since");
+            print_usage(program, opts);
+            exit(1);
+        }
+    };
+
+    let scanner = ScannerBuilder::new()
+        .yaml_file_multi_rule(rules)
+        .try_build()
+        .context("failed to initialize secrets scanner")?;
+    let allowlist = match read_config_file(&directory.to_string_lossy())
+        .context("cannot read the configuration file")?
+    {
+        Some(config) => SecretsAllowlist::new(&config.secrets_allowlist)?,
+        None => SecretsAllowlist::default(),
+    };
+    let (mut findings, suppressed): (Vec<_>, Vec<_>) = files
+        .par_iter()
+        .map(|file| scan_file(&scanner, &allowlist, file))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .partition(|finding| !finding.suppressed);
+    findings.sort();
+
+    if !suppressed.is_empty() {
+        eprintln!(
+            "{} potential secret(s) ignored by the allowlists",
+            suppressed.len()
+        );
+    }
+    if findings.is_empty() {
+        return Ok(());
+    }
+    for finding in &findings {
+        eprintln!(
+            "{}:{}:{}: potential secret ({})",
+            finding.path.display(),
+            finding.line,
+            finding.col,
+            finding.rule_id
+        );
+    }
+    eprintln!(
+        "\n{} potential secret(s) found in the changes. Remove them, or bypass this check with --no-verify.",
+        findings.len()
+    );
+    exit(1);
+}
+
+/// A secret found in an added line.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Finding {
+    path: PathBuf,
+    line: u32,
+    col: u32,
+    rule_id: String,
+    /// Whether the secret is ignored by an allowlist.
+    suppressed: bool,
+}
+
+/// Scans the new version of a file, and keeps the secrets that start in an added line.
+fn scan_file(
+    scanner: &secrets::Scanner,
+    allowlist: &SecretsAllowlist,
+    file: &ChangedFile,
+) -> Result<Vec<Finding>> {
+    let candidates = scanner
+        .scan_contents(&file.path, &file.contents)
+        .map_err(|e| anyhow!("cannot scan {}: {e}", file.path.display()))?;
+    let content = String::from_utf8_lossy(&file.contents);
+    Ok(candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let mut secret = SecretResult::new(
+                candidate.rule_match.rule_id.to_string(),
+                file.path.to_string_lossy(),
+                ValidationStatus::Unvalidated,
+                candidate.rule_match.matched.point_span.start().into(),
+                candidate.rule_match.matched.point_span.end().into(),
+            );
+            if !file.added_lines.contains(&secret.violation.start.line) {
+                return None;
+            }
+            secret.set_fingerprint(&content);
+            let ignored_by_rule = scanner
+                .rule(&secret.rule_id)
+                .is_some_and(|rule| rule.ignores_path(&file.path));
+            let suppressed = allowlist
+                .suppression_reason(
+                    &secret,
+                    candidate.rule_match.matched.as_str(),
+                    Some(&content),
+                    ignored_by_rule,
+                )
+                .is_some();
+            Some(Finding {
+                path: file.path.clone(),
+                line: secret.violation.start.line,
+                col: secret.violation.start.col,
+                rule_id: secret.rule_id,
+                suppressed,
+            })
+        })
+        .collect())
+}
+
+/// Writes a git hook that runs the scan, and returns its path.
+fn install_hook(program: &str, directory: &Path, hook: &str, rules: &Path) -> Result<PathBuf> {
+    let scan = match hook {
+        "pre-commit" => "--staged".to_string(),
+        "pre-push" => format!("--since '{PUSH_BASE}'"),
+        _ => {
+            return Err(anyhow!(
+                "unknown hook {hook} (expected pre-commit or pre-push)"
+            ))
+        }
+    };
+    let rules = fs::canonicalize(rules)
+        .with_context(|| format!("cannot find the rules file {}", rules.display()))?;
+    let program = fs::canonicalize(program).unwrap_or_else(|_| PathBuf::from(program));
+
+    let hooks = get_hooks_directory(directory)?;
+    fs::create_dir_all(&hooks)?;
+    let path = hooks.join(hook);
+    if path.exists() {
+        return Err(anyhow!(
+            "a {hook} hook already exists at {}, add the scan to it manually",
+            path.display()
+        ));
+    }
+    let script = format!(
+        "#!/bin/sh\n# Installed by datadog-static-analyzer: blocks the changes that add a secret.\nexec {} secrets {scan} --rules {}\n",
+        shell_quote(&program.to_string_lossy()),
+        shell_quote(&rules.to_string_lossy())
+    );
+    fs::write(&path, script)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(path)
+}
+
+/// Quotes a word for the shell: the word is single-quoted, with its single quotes escaped.
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
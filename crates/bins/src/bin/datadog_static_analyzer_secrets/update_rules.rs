@@ -0,0 +1,211 @@
+//! `datadog-static-analyzer secrets update-rules`: downloads the secrets rules from Datadog to a
+//! local cache, optionally at a pinned version.
+
+use anyhow::{anyhow, Context, Result};
+use cli::datadog_utils::get_secrets_rules;
+use cli::model::datadog_api::ApiSecretRulesAttributes;
+use getopts::Options;
+use secrets::ScannerBuilder;
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+/// The file of the cache that contains the rules of the latest version that was downloaded.
+const LATEST_RULES_FILE: &str = "secrets-rules.yml";
+
+fn print_usage(program: &str, opts: Options) {
+    let brief = format!("Usage: {program} secrets update-rules [options]");
+    print!("{}", opts.usage(&brief));
+}
+
+/// Runs the `secrets update-rules` subcommand with its arguments.
+pub fn run(program: &str, args: &[String]) -> Result<()> {
+    let mut opts = Options::new();
+    opts.optopt(
+        "",
+        "rules-version",
+        "download this version of the rules instead of the latest one",
+        "VERSION",
+    );
+    opts.optopt(
+        "",
+        "cache-dir",
+        "directory of the cache (default: $XDG_CACHE_HOME/datadog-static-analyzer/secrets)",
+        "/path/to/cache",
+    );
+    opts.optflag("s", "staging", "use staging");
+    opts.optflag("h", "help", "print this help");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            eprintln!("error when parsing arguments: {}", f);
+            exit(1);
+        }
+    };
+    if matches.opt_present("h") {
+        print_usage(program, opts);
+        exit(0);
+    }
+
+    let cache_directory = match matches.opt_str("cache-dir") {
+        Some(directory) => PathBuf::from(directory),
+        None => default_cache_directory()?,
+    };
+    let pinned_version = matches.opt_str("rules-version");
+    if let Some(version) = &pinned_version {
+        // A version of the rules never changes, so a pinned version is only downloaded once.
+        let path = cache_directory.join(versioned_rules_file(version)?);
+        if path.exists() {
+            println!(
+                "Secrets rules version {version} already in the cache: {}",
+                path.display()
+            );
+            return Ok(());
+        }
+    }
+
+    let release = get_secrets_rules(pinned_version.as_deref(), matches.opt_present("s"))
+        .context("cannot download the secrets rules")?;
+    if let Some(version) = pinned_version.as_ref().filter(|v| **v != release.version) {
+        return Err(anyhow!(
+            "requested the secrets rules version {version}, received {}",
+            release.version
+        ));
+    }
+    let path = cache_directory.join(versioned_rules_file(&release.version)?);
+    write_rules_file(&path, &rules_file_contents(&release)?)?;
+    if pinned_version.is_none() {
+        fs::copy(&path, cache_directory.join(LATEST_RULES_FILE))?;
+    }
+    println!(
+        "Downloaded {} secrets rules (version {}) to {}",
+        release.rules.len(),
+        release.version,
+        path.display()
+    );
+    Ok(())
+}
+
+/// Returns the default directory of the cache: `$XDG_CACHE_HOME/datadog-static-analyzer/secrets`,
+/// or `$HOME/.cache/datadog-static-analyzer/secrets`.
+fn default_cache_directory() -> Result<PathBuf> {
+    let cache_home = match env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+        Some(directory) => PathBuf::from(directory),
+        None => env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".cache"))
+            .ok_or_else(|| {
+                anyhow!("cannot find the cache directory, specify it with --cache-dir")
+            })?,
+    };
+    Ok(cache_home.join("datadog-static-analyzer").join("secrets"))
+}
+
+/// Returns the name of the cache file of a version of the rules.
+fn versioned_rules_file(version: &str) -> Result<String> {
+    let is_valid = !version.is_empty()
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if !is_valid {
+        return Err(anyhow!("invalid secrets rules version: {version}"));
+    }
+    Ok(format!("secrets-rules-{version}.yml"))
+}
+
+/// Returns the content of a file containing multiple YAML-defined rules, made of the rules of a release.
+fn rules_file_contents(release: &ApiSecretRulesAttributes) -> Result<String> {
+    let mut ids = HashSet::new();
+    let mut rules = serde_yaml::Mapping::new();
+    for rule in &release.rules {
+        if !ids.insert(rule.id.as_str()) {
+            return Err(anyhow!("duplicate secrets rule {}", rule.id));
+        }
+        let definition = serde_yaml::from_str::<serde_yaml::Value>(&rule.definition)
+            .with_context(|| format!("invalid definition of the secrets rule {}", rule.id))?;
+        if definition.get("id").and_then(|id| id.as_str()) != Some(rule.id.as_str()) {
+            return Err(anyhow!(
+                "the definition of the secrets rule {} has a different id",
+                rule.id
+            ));
+        }
+        rules.insert(rule.id.clone().into(), definition);
+    }
+    Ok(format!(
+        "# Secrets rules version {}, downloaded with `datadog-static-analyzer secrets update-rules`\n{}",
+        release.version,
+        serde_yaml::to_string(&rules)?
+    ))
+}
+
+/// Writes the rules to the cache, once the scanner has validated them. The file is replaced
+/// atomically, so that a scan never reads a partial file.
+fn write_rules_file(path: &Path, contents: &str) -> Result<()> {
+    let directory = path
+        .parent()
+        .ok_or_else(|| anyhow!("invalid cache file {}", path.display()))?;
+    fs::create_dir_all(directory)
+        .with_context(|| format!("cannot create the cache directory {}", directory.display()))?;
+    let temporary = path.with_extension("yml.tmp");
+    fs::write(&temporary, contents)?;
+    if let Err(err) = ScannerBuilder::new()
+        .yaml_file_multi_rule(&temporary)
+        .try_build()
+    {
+        let _ = fs::remove_file(&temporary);
+        return Err(anyhow!("the downloaded secrets rules are invalid: {err}"));
+    }
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cli::model::datadog_api::ApiSecretRule;
+
+    fn release(rules: &[(&str, &str)]) -> ApiSecretRulesAttributes {
+        ApiSecretRulesAttributes {
+            version: "1.2.0".to_string(),
+            rules: rules
+                .iter()
+                .map(|(id, definition)| ApiSecretRule {
+                    id: id.to_string(),
+                    definition: definition.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn versioned_file_names() {
+        assert_eq!(
+            versioned_rules_file("1.2.0").unwrap(),
+            "secrets-rules-1.2.0.yml"
+        );
+        assert!(versioned_rules_file("../1.2.0").is_err());
+        assert!(versioned_rules_file("").is_err());
+    }
+
+    /// The rules of a release are written as a multi-rule file, keyed by their id.
+    #[test]
+    fn multi_rule_file() {
+        let contents = rules_file_contents(&release(&[
+            ("rule-one", "schema-version: v1\nid: rule-one\n"),
+            ("rule-two", "schema-version: v1\nid: rule-two\n"),
+        ]))
+        .unwrap();
+        assert!(contents.starts_with("# Secrets rules version 1.2.0"));
+        let parsed = serde_yaml::from_str::<serde_yaml::Mapping>(&contents).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed["rule-two"]["id"].as_str(), Some("rule-two"));
+
+        assert!(rules_file_contents(&release(&[("rule-one", "id: rule-two\n")])).is_err());
+        assert!(rules_file_contents(&release(&[
+            ("rule-one", "id: rule-one\n"),
+            ("rule-one", "id: rule-one\n"),
+        ]))
+        .is_err());
+    }
+}
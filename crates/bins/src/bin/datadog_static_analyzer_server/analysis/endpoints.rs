@@ -0,0 +1,435 @@
+use super::error::AnalysisError;
+use super::models::{AnalysisResults, FileAnalysisRequest};
+use super::rule_cache::RuleCache;
+use crate::datadog_static_analyzer_server::state::ServerState;
+use cli::constants::DEFAULT_MAX_FILE_SIZE_KB;
+use cli::file_utils::filter_files_for_language;
+use flate2::read::GzDecoder;
+use kernel::analysis::cancellation::CancellationToken;
+use kernel::api::{analyze_file, AnalysisRequest};
+use kernel::arguments::ArgumentProvider;
+use kernel::model::analysis::AnalysisOptions;
+use kernel::model::common::{ColumnUnit, Language, ALL_LANGUAGES};
+use kernel::model::config_file::FileLanguagesConfig;
+use kernel::model::rule::{sort_rule_results, RuleInternal, RuleResult};
+use kernel::utils::decode_base64_string;
+use rocket::data::{Data, ToByteUnit};
+use rocket::serde::json::Json;
+use rocket::State;
+use server::metrics::metrics;
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::instrument;
+
+/// The maximum size of an archive submitted for analysis.
+const MAX_ARCHIVE_SIZE_MB: u64 = 50;
+
+/// The limits of the content of an archive, once decompressed: a small compressed archive can
+/// expand to a much larger size, or to a large number of entries.
+struct ArchiveLimits {
+    /// The maximum total size of the decompressed archive.
+    max_content_size_mb: u64,
+    /// The maximum number of entries (files, directories, links...) of the archive.
+    max_entries: usize,
+}
+
+const ARCHIVE_LIMITS: ArchiveLimits = ArchiveLimits {
+    max_content_size_mb: 500,
+    max_entries: 100_000,
+};
+
+/// Analyzes a single file with the rules of the given rulesets.
+///
+/// The rulesets are fetched and compiled the first time they are used, and are then kept in memory
+/// for the following requests.
+#[instrument(skip_all)]
+#[rocket::post("/v1/analysis/file", format = "application/json", data = "<request>")]
+pub async fn analyze_file(
+    state: &State<ServerState>,
+    request: Json<FileAnalysisRequest>,
+) -> Result<Json<AnalysisResults>, AnalysisError> {
+    let FileAnalysisRequest {
+        filename,
+        code_base64,
+        rulesets,
+    } = request.into_inner();
+    tracing::debug!(filename = %filename, rulesets = ?&rulesets);
+    let rule_cache = Arc::clone(&state.rule_cache);
+    run_blocking(move || {
+        let code = decode_base64_string(code_base64).map_err(|_| AnalysisError::Decoder)?;
+        let rules = get_rules(&rule_cache, &rulesets)?;
+        let results = analyze_file_content(&rules, &filename, &code, &CancellationToken::new())
+            .ok_or(AnalysisError::UnsupportedFile(filename))?;
+        Ok(AnalysisResults {
+            results,
+            skipped_files: vec![],
+        })
+    })
+    .await
+}
+
+/// Analyzes all the files of a tarball (optionally compressed with gzip) with the rules of the given rulesets.
+/// Files whose language is not supported are ignored.
+#[instrument(skip_all)]
+#[rocket::post("/v1/analysis/tarball?<rulesets>", data = "<archive>")]
+pub async fn analyze_tarball(
+    state: &State<ServerState>,
+    rulesets: Vec<String>,
+    archive: Data<'_>,
+) -> Result<Json<AnalysisResults>, AnalysisError> {
+    tracing::debug!(rulesets = ?&rulesets);
+    let archive = archive
+        .open(MAX_ARCHIVE_SIZE_MB.mebibytes())
+        .into_bytes()
+        .await?;
+    if !archive.is_complete() {
+        return Err(AnalysisError::ArchiveTooLarge(MAX_ARCHIVE_SIZE_MB));
+    }
+    let archive = archive.into_inner();
+    let rule_cache = Arc::clone(&state.rule_cache);
+    run_blocking(move || {
+        let rules = get_rules(&rule_cache, &rulesets)?;
+        analyze_archive(&rules, &archive)
+    })
+    .await
+}
+
+/// Runs an analysis on the blocking thread pool, as fetching rulesets and executing rules are blocking operations.
+async fn run_blocking<F>(f: F) -> Result<Json<AnalysisResults>, AnalysisError>
+where
+    F: FnOnce() -> Result<AnalysisResults, AnalysisError> + Send + 'static,
+{
+    let start = Instant::now();
+    let result = rocket::tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|_| AnalysisError::Internal)?;
+    let metrics = metrics();
+    metrics.analysis_requests.inc();
+    metrics.analysis_duration.observe(start.elapsed());
+    if result.is_err() {
+        metrics.analysis_request_errors.inc();
+    }
+    result.map(Json)
+}
+
+pub fn get_rules(
+    rule_cache: &RuleCache,
+    rulesets: &[String],
+) -> Result<Vec<Arc<Vec<RuleInternal>>>, AnalysisError> {
+    if rulesets.is_empty() {
+        return Err(AnalysisError::NoRulesets);
+    }
+    rulesets
+        .iter()
+        .map(|name| {
+            rule_cache
+                .get(name)
+                .map_err(|source| AnalysisError::Ruleset {
+                    name: name.clone(),
+                    source,
+                })
+        })
+        .collect()
+}
+
+/// Returns the languages a file should be analyzed as, based on its name.
+fn get_languages_for_file(filename: &str) -> Vec<Language> {
+    let path = [PathBuf::from(filename)];
+    ALL_LANGUAGES
+        .iter()
+        .filter(|language| {
+            !filter_files_for_language(&path, language, &FileLanguagesConfig::default()).is_empty()
+        })
+        .copied()
+        .collect()
+}
+
+/// Analyzes a file with the rules that match its language(s), until the token is cancelled.
+/// Returns `None` if the language of the file is not supported.
+pub fn analyze_file_content(
+    rulesets: &[Arc<Vec<RuleInternal>>],
+    filename: &str,
+    code: &str,
+    cancellation: &CancellationToken,
+) -> Option<Vec<RuleResult>> {
+    let languages = get_languages_for_file(filename);
+    if languages.is_empty() {
+        return None;
+    }
+    let argument_provider = ArgumentProvider::new();
+    let analysis_options = AnalysisOptions {
+        log_output: false,
+        use_debug: false,
+        ignore_generated_files: false,
+        report_parse_errors: false,
+        column_unit: ColumnUnit::Bytes,
+        generated_files_rules: HashSet::new(),
+        profile_rules: false,
+        nondeterministic_rules: false,
+    };
+    let results = languages
+        .iter()
+        .flat_map(|language| {
+            let rules = rulesets
+                .iter()
+                .flat_map(|rules| rules.iter())
+                .filter(|rule| rule.language == *language);
+            let request = AnalysisRequest::new(filename, code)
+                .language(*language)
+                .rules(rules)
+                .options(analysis_options.clone())
+                .argument_provider(&argument_provider)
+                .cancellation(cancellation);
+            analyze_file(&request)
+                .expect("the language of the request is set")
+                .rule_results
+        })
+        .collect::<Vec<_>>();
+
+    let metrics = metrics();
+    metrics.files_analyzed.inc();
+    for result in &results {
+        metrics.record_rule_execution(
+            result.execution_time_ms,
+            &result.errors,
+            result.execution_error.is_some(),
+        );
+    }
+    Some(results)
+}
+
+/// Analyzes the files of a tar archive, which may be compressed with gzip.
+fn analyze_archive(
+    rulesets: &[Arc<Vec<RuleInternal>>],
+    archive: &[u8],
+) -> Result<AnalysisResults, AnalysisError> {
+    let mut analysis_results = AnalysisResults::default();
+    let cancellation = CancellationToken::new();
+    for_each_archive_file(archive, |filename, code| match code {
+        Some(code) => {
+            if let Some(results) = analyze_file_content(rulesets, &filename, &code, &cancellation) {
+                analysis_results.results.extend(results);
+            }
+        }
+        None => analysis_results.skipped_files.push(filename),
+    })?;
+    sort_rule_results(&mut analysis_results.results);
+    Ok(analysis_results)
+}
+
+/// Calls `f` with the name and the content of each file of a tar archive (which may be compressed
+/// with gzip) whose language is supported. The content is `None` if the file is not valid UTF-8,
+/// or if it's larger than the maximum size of the files analyzed by the CLI: the content of a file
+/// is never read past this size.
+///
+/// The archive is rejected once its decompressed content or its number of entries exceeds
+/// [`ARCHIVE_LIMITS`].
+pub fn for_each_archive_file<F>(archive: &[u8], f: F) -> Result<(), AnalysisError>
+where
+    F: FnMut(String, Option<String>),
+{
+    for_each_archive_file_with_limits(archive, &ARCHIVE_LIMITS, f)
+}
+
+fn for_each_archive_file_with_limits<F>(
+    archive: &[u8],
+    limits: &ArchiveLimits,
+    mut f: F,
+) -> Result<(), AnalysisError>
+where
+    F: FnMut(String, Option<String>),
+{
+    let max_content_size = limits.max_content_size_mb * 1024 * 1024;
+    let content_size = Cell::new(0);
+    let reader: Box<dyn Read> = if archive.starts_with(&[0x1f, 0x8b]) {
+        Box::new(GzDecoder::new(archive))
+    } else {
+        Box::new(archive)
+    };
+    let reader = SizeLimitedReader {
+        inner: reader,
+        size: &content_size,
+        max_size: max_content_size,
+    };
+    let read_entries = || -> Result<(), AnalysisError> {
+        let mut archive = tar::Archive::new(reader);
+        for (index, entry) in archive.entries()?.enumerate() {
+            if index >= limits.max_entries {
+                return Err(AnalysisError::TooManyArchiveEntries(limits.max_entries));
+            }
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()?.to_string_lossy().to_string();
+            let filename = path.trim_start_matches("./").to_string();
+            if get_languages_for_file(&filename).is_empty() {
+                continue;
+            }
+            let max_file_size = DEFAULT_MAX_FILE_SIZE_KB * 1024;
+            if entry.size() > max_file_size {
+                f(filename, None);
+                continue;
+            }
+            // the size of the header is not trusted: one more byte is read to detect a larger file
+            let mut code = String::new();
+            match entry
+                .by_ref()
+                .take(max_file_size + 1)
+                .read_to_string(&mut code)
+            {
+                Ok(size) if size as u64 <= max_file_size => f(filename, Some(code)),
+                Err(err) if content_size.get() > max_content_size => return Err(err.into()),
+                _ => f(filename, None),
+            }
+        }
+        Ok(())
+    };
+    let result = read_entries();
+    // reading past the maximum size fails the reading of the archive with an I/O error
+    if content_size.get() > max_content_size {
+        return Err(AnalysisError::ArchiveContentTooLarge(
+            limits.max_content_size_mb,
+        ));
+    }
+    result
+}
+
+/// Counts the bytes read from a reader, and fails once more than `max_size` bytes were read.
+struct SizeLimitedReader<'a, R> {
+    inner: R,
+    size: &'a Cell<u64>,
+    max_size: u64,
+}
+
+impl<R: Read> Read for SizeLimitedReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.size.get() > self.max_size {
+            return Err(io::Error::other("the archive exceeds its maximum size"));
+        }
+        let len = self.inner.read(buf)?;
+        self.size.set(self.size.get() + len as u64);
+        if self.size.get() > self.max_size {
+            return Err(io::Error::other("the archive exceeds its maximum size"));
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::analysis::tree_sitter::get_query;
+    use kernel::model::rule::{RuleCategory, RuleSeverity};
+
+    fn python_rule() -> RuleInternal {
+        RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::BestPractices,
+            severity: RuleSeverity::Warning,
+            language: Language::Python,
+            code: r#"
+function visit(node, filename, code) {
+    const functionName = node.captures["name"];
+    addError(buildError(functionName.start.line, functionName.start.col, functionName.end.line, functionName.end.col, "invalid name"));
+}"#
+            .to_string(),
+            tree_sitter_query: get_query(
+                "(function_definition name: (identifier) @name)",
+                &Language::Python,
+            )
+            .unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        }
+    }
+
+    #[test]
+    fn languages_for_file() {
+        assert_eq!(get_languages_for_file("a/b.py"), vec![Language::Python]);
+        assert_eq!(get_languages_for_file("a/b.unknown"), vec![]);
+    }
+
+    #[test]
+    fn analyze_tar_archive() {
+        let mut builder = tar::Builder::new(vec![]);
+        let large = vec![b'#'; DEFAULT_MAX_FILE_SIZE_KB as usize * 1024 + 1];
+        for (path, content) in [
+            ("./src/a.py", "def foo():\n    pass\n".as_bytes()),
+            ("src/README.md", "# readme".as_bytes()),
+            ("src/b.py", &[0xff, 0xfe]),
+            ("src/c.py", large.as_slice()),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, content).unwrap();
+        }
+        let archive = builder.into_inner().unwrap();
+
+        let rules = vec![Arc::new(vec![python_rule()])];
+        let results = analyze_archive(&rules, &archive).unwrap();
+        assert_eq!(results.results.len(), 1);
+        assert_eq!(results.results[0].filename, "src/a.py");
+        assert_eq!(results.results[0].violations.len(), 1);
+        assert_eq!(
+            results.skipped_files,
+            vec!["src/b.py".to_string(), "src/c.py".to_string()]
+        );
+    }
+
+    /// An archive whose decompressed content or number of entries exceeds the limits is rejected
+    /// with a 413 or a 400, before its files are analyzed.
+    #[test]
+    fn oversized_archive() {
+        let archive = |files: &[(&str, usize)]| {
+            let mut builder = tar::Builder::new(vec![]);
+            for (path, size) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(*size as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder
+                    .append_data(&mut header, path, vec![b'#'; *size].as_slice())
+                    .unwrap();
+            }
+            let tar = builder.into_inner().unwrap();
+            let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::best());
+            io::Write::write_all(&mut encoder, &tar).unwrap();
+            encoder.finish().unwrap()
+        };
+        let limits = ArchiveLimits {
+            max_content_size_mb: 1,
+            max_entries: 2,
+        };
+        let read_files = |archive: &[u8]| {
+            let mut files = vec![];
+            for_each_archive_file_with_limits(archive, &limits, |filename, _| files.push(filename))
+                .map(|_| files)
+        };
+
+        assert_eq!(
+            read_files(&archive(&[("a.py", 10), ("b.py", 10)])).unwrap(),
+            vec!["a.py".to_string(), "b.py".to_string()]
+        );
+
+        // the files are skipped, but the archive still expands past its maximum size
+        let large = archive(&[("a.txt", 800 * 1024), ("b.txt", 800 * 1024)]);
+        assert!(large.len() < 1024 * 1024);
+        let err = read_files(&large).unwrap_err();
+        assert!(matches!(err, AnalysisError::ArchiveContentTooLarge(1)));
+        assert_eq!(err.status(), rocket::http::Status::PayloadTooLarge);
+
+        let err = read_files(&archive(&[("a.py", 1), ("b.py", 1), ("c.py", 1)])).unwrap_err();
+        assert!(matches!(err, AnalysisError::TooManyArchiveEntries(2)));
+        assert_eq!(err.status(), rocket::http::Status::BadRequest);
+    }
+}
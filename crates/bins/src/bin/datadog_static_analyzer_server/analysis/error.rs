@@ -0,0 +1,66 @@
+use rocket::{
+    http::{ContentType, Status},
+    response::Responder,
+    Response,
+};
+use serde_json::json;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AnalysisError {
+    #[error("No ruleset was specified")]
+    NoRulesets,
+    #[error("Error getting ruleset {name}: {source}")]
+    Ruleset { name: String, source: anyhow::Error },
+    #[error("Error decoding base64 string")]
+    Decoder,
+    #[error("Unsupported file: {0}")]
+    UnsupportedFile(String),
+    #[error("The archive exceeds the maximum size of {0} MiB")]
+    ArchiveTooLarge(u64),
+    #[error("The content of the archive exceeds the maximum size of {0} MiB")]
+    ArchiveContentTooLarge(u64),
+    #[error("The archive exceeds the maximum number of {0} entries")]
+    TooManyArchiveEntries(usize),
+    #[error("Error reading the archive")]
+    Archive {
+        #[from]
+        source: std::io::Error,
+    },
+    #[error("The analysis failed unexpectedly")]
+    Internal,
+}
+
+impl<'r> Responder<'r, 'static> for AnalysisError {
+    fn respond_to(self, request: &'r rocket::Request<'_>) -> rocket::response::Result<'static> {
+        let json = json!({"error": self.to_string(), "code": self.code()});
+        Response::build_from(json.respond_to(request)?)
+            .status(self.status())
+            .header(ContentType::JSON)
+            .ok()
+    }
+}
+
+impl AnalysisError {
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::NoRulesets => 1,
+            Self::Ruleset { .. } => 2,
+            Self::Decoder => 3,
+            Self::UnsupportedFile(_) => 4,
+            Self::ArchiveTooLarge(_) => 5,
+            Self::Archive { .. } => 6,
+            Self::Internal => 7,
+            Self::ArchiveContentTooLarge(_) => 8,
+            Self::TooManyArchiveEntries(_) => 9,
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        match self {
+            Self::ArchiveTooLarge(_) | Self::ArchiveContentTooLarge(_) => Status::PayloadTooLarge,
+            Self::Internal => Status::InternalServerError,
+            _ => Status::BadRequest,
+        }
+    }
+}
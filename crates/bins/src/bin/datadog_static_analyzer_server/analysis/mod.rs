@@ -0,0 +1,9 @@
+pub mod endpoints;
+pub mod error;
+mod models;
+pub mod rule_cache;
+use rocket::Route;
+
+pub fn analysis_routes() -> Vec<Route> {
+    rocket::routes![endpoints::analyze_file, endpoints::analyze_tarball]
+}
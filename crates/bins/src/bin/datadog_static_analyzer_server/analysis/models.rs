@@ -0,0 +1,18 @@
+use kernel::model::rule::RuleResult;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct FileAnalysisRequest {
+    pub filename: String,
+    #[serde(rename = "code")]
+    pub code_base64: String,
+    pub rulesets: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct AnalysisResults {
+    pub results: Vec<RuleResult>,
+    /// The files of an archive that could not be analyzed because they are not valid UTF-8.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_files: Vec<String>,
+}
@@ -0,0 +1,68 @@
+use anyhow::{anyhow, Result};
+use cli::datadog_utils::get_ruleset;
+use kernel::model::rule::RuleInternal;
+use kernel::model::ruleset::RuleSet;
+use server::metrics::metrics;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// A cache of the compiled rules of the rulesets used by the analysis endpoints, so that a ruleset
+/// is only fetched and compiled once for the lifetime of the server.
+#[derive(Debug, Default)]
+pub struct RuleCache {
+    rulesets: RwLock<HashMap<String, Arc<Vec<RuleInternal>>>>,
+    use_staging: bool,
+}
+
+impl RuleCache {
+    pub fn new(use_staging: bool) -> Self {
+        Self {
+            rulesets: RwLock::default(),
+            use_staging,
+        }
+    }
+
+    /// Compiles the rules of a ruleset and adds them to the cache (e.g. for rulesets read from a file).
+    pub fn insert(&self, ruleset: &RuleSet) -> Result<Arc<Vec<RuleInternal>>> {
+        let rules = Arc::new(compile_ruleset(ruleset)?);
+        self.rulesets
+            .write()
+            .map_err(|_| anyhow!("rule cache is poisoned"))?
+            .insert(ruleset.name.clone(), Arc::clone(&rules));
+        Ok(rules)
+    }
+
+    /// Returns the compiled rules of a ruleset, fetching the ruleset from Datadog if it isn't cached.
+    pub fn get(&self, name: &str) -> Result<Arc<Vec<RuleInternal>>> {
+        let cached = self
+            .rulesets
+            .read()
+            .map_err(|_| anyhow!("rule cache is poisoned"))?
+            .get(name)
+            .cloned();
+        if let Some(rules) = cached {
+            metrics().rule_cache_hits.inc();
+            return Ok(rules);
+        }
+        metrics().rule_cache_misses.inc();
+        let ruleset = get_ruleset(name, self.use_staging)?;
+        self.insert(&RuleSet {
+            name: name.to_string(),
+            ..ruleset
+        })
+    }
+}
+
+/// Compiles the rules of a ruleset, failing if a rule is invalid or its checksum doesn't match.
+fn compile_ruleset(ruleset: &RuleSet) -> Result<Vec<RuleInternal>> {
+    ruleset
+        .rules
+        .iter()
+        .map(|rule| {
+            if !rule.verify_checksum() {
+                return Err(anyhow!("rule {} has an invalid checksum", rule.name));
+            }
+            rule.to_rule_internal()
+        })
+        .collect()
+}
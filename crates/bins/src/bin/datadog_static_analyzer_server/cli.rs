@@ -1,10 +1,14 @@
+use cli::rule_utils::get_rulesets_from_file;
 use getopts::Options;
 use kernel::constants::{CARGO_VERSION, VERSION};
 use rocket::{Build, Rocket, Shutdown};
+use std::net::SocketAddr;
 use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
 use std::time::Duration;
 use std::{env, process, thread};
 
+use super::analysis::rule_cache::RuleCache;
 use super::state::ServerState;
 use super::utils::get_current_timestamp_ms;
 
@@ -30,6 +34,30 @@ fn get_opts() -> Options {
         "90",
     );
     opts.optflag("e", "enable-shutdown", "enables the shutdown endpoint");
+    opts.optopt(
+        "r",
+        "rules",
+        "rules to preload for the analysis endpoints",
+        "/path/to/rules.json",
+    );
+    opts.optflag(
+        "",
+        "staging",
+        "fetch the rulesets of the analysis endpoints from staging",
+    );
+    opts.optopt(
+        "",
+        "grpc-port",
+        "port to run the gRPC server on (disabled by default)",
+        "50051",
+    );
+    #[cfg(feature = "secrets")]
+    opts.optopt(
+        "",
+        "secrets-rules",
+        "path to a YAML file containing secrets scanner rules, for the gRPC server",
+        "/path/to/secrets-rules.yml",
+    );
     opts.optflag("h", "help", "print this help");
     opts.optflag("v", "version", "shows the tool version");
     opts
@@ -67,6 +95,21 @@ pub fn prepare_rocket() -> (Rocket<Build>, ServerState, Sender<Shutdown>) {
     let mut server_state = ServerState::new(matches.opt_str("s"), matches.opt_present("e"));
     let mut rocket_configuration = rocket::config::Config::default();
 
+    // compiled rules are kept between requests of the analysis endpoints
+    server_state.rule_cache = Arc::new(RuleCache::new(matches.opt_present("staging")));
+    if let Some(rules_file) = matches.opt_str("r") {
+        let rulesets = get_rulesets_from_file(&rules_file).unwrap_or_else(|e| {
+            eprintln!("Error when reading rules from {}: {}", rules_file, e);
+            process::exit(1);
+        });
+        for ruleset in &rulesets {
+            if let Err(e) = server_state.rule_cache.insert(ruleset) {
+                eprintln!("Error when compiling ruleset {}: {}", ruleset.name, e);
+                process::exit(1);
+            }
+        }
+    }
+
     // Set up the port in rocket configuration if --port is passed
     if matches.opt_present("p") {
         let port_opt = matches.opt_str("p");
@@ -87,6 +130,26 @@ pub fn prepare_rocket() -> (Rocket<Build>, ServerState, Sender<Shutdown>) {
         }
     }
 
+    if let Some(grpc_port) = matches.opt_str("grpc-port") {
+        let Ok(grpc_port) = grpc_port.parse::<u16>() else {
+            eprintln!("Invalid gRPC port argument");
+            process::exit(1)
+        };
+        server_state.grpc_address = Some(SocketAddr::new(rocket_configuration.address, grpc_port));
+    }
+
+    #[cfg(feature = "secrets")]
+    if let Some(rules_file) = matches.opt_str("secrets-rules") {
+        let scanner = secrets::ScannerBuilder::new()
+            .yaml_file_multi_rule(rules_file)
+            .try_build()
+            .unwrap_or_else(|e| {
+                eprintln!("Error when initializing the secrets scanner: {}", e);
+                process::exit(1);
+            });
+        server_state.secrets_scanner = Some(Arc::new(scanner));
+    }
+
     // channel used to send the shutdown handler so that we can exit the server gracefully
     let (tx, rx) = channel();
 
@@ -2,10 +2,11 @@ use crate::datadog_static_analyzer_server::fairings::TraceSpan;
 use rocket::{
     fs::NamedFile,
     futures::FutureExt,
-    http::Status,
+    http::{ContentType, Status},
     serde::json::{json, Json, Value},
     Build, Error, Rocket, Shutdown, State,
 };
+use server::metrics::metrics;
 use server::model::{
     analysis_request::AnalysisRequest, tree_sitter_tree_request::TreeSitterRequest,
 };
@@ -15,7 +16,7 @@ use std::{path::Path, process::exit, sync::mpsc::Sender};
 
 use crate::datadog_static_analyzer_server::state::ServerState;
 
-use super::{ide::ide_routes, utils};
+use super::{analysis::analysis_routes, ide::ide_routes, utils};
 
 /// The shutdown endpoint, when a GET request is received, will return a 204 code if the shutdown mechanism is enabled.
 /// It will return a 403 code otherwise.
@@ -160,6 +161,14 @@ fn ping() -> String {
     "pong".to_string()
 }
 
+/// Exposes the metrics of the server (e.g. the number of files analyzed and the rule execution latency)
+/// in the Prometheus text format.
+#[rocket::get("/metrics")]
+fn get_metrics() -> (ContentType, String) {
+    let content_type = ContentType::new("text", "plain").with_params(("version", "0.0.4"));
+    (content_type, metrics().render())
+}
+
 fn mount_endpoints(rocket: Rocket<Build>) -> Rocket<Build> {
     rocket
         .mount(
@@ -170,6 +179,7 @@ fn mount_endpoints(rocket: Rocket<Build>) -> Rocket<Build> {
                 get_version,
                 get_revision,
                 ping,
+                get_metrics,
                 get_options,
                 serve_static,
                 languages,
@@ -177,6 +187,8 @@ fn mount_endpoints(rocket: Rocket<Build>) -> Rocket<Build> {
                 shutdown_post
             ],
         )
+        // analysis of files and archives
+        .mount("/", analysis_routes())
         // IDE owned routes
         .mount("/ide", ide_routes())
 }
@@ -0,0 +1,272 @@
+use super::analysis::endpoints::{analyze_file_content, for_each_archive_file, get_rules};
+use super::analysis::error::AnalysisError;
+use super::analysis::rule_cache::RuleCache;
+use super::state::ServerState;
+use grpc::v1::analyzer_server::{Analyzer, AnalyzerServer};
+use grpc::v1::{
+    self, AnalyzeFileRequest, AnalyzeFileResponse, AnalyzeTreeRequest, FileResult,
+    ListRulesRequest, ListRulesResponse, SecretCandidate, ValidateSecretCandidatesRequest,
+};
+use kernel::analysis::cancellation::CancellationToken;
+use rocket::tokio::sync::mpsc;
+use rocket::tokio::task::spawn_blocking;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+/// The number of results buffered by a streaming call before the analysis waits for the client.
+const STREAM_BUFFER_SIZE: usize = 16;
+
+impl From<AnalysisError> for Status {
+    fn from(value: AnalysisError) -> Self {
+        match value {
+            AnalysisError::ArchiveTooLarge(_) | AnalysisError::ArchiveContentTooLarge(_) => {
+                Status::resource_exhausted(value.to_string())
+            }
+            AnalysisError::Internal => Status::internal(value.to_string()),
+            _ => Status::invalid_argument(value.to_string()),
+        }
+    }
+}
+
+/// The implementation of the `Analyzer` gRPC service. It shares the compiled rules with the
+/// REST endpoints.
+pub struct AnalyzerService {
+    rule_cache: Arc<RuleCache>,
+    #[cfg(feature = "secrets")]
+    secrets_scanner: Option<Arc<secrets::Scanner>>,
+}
+
+/// Cancels a token when dropped. Tonic drops the future of a call when the client cancels it, so
+/// this is used to stop the analysis of a call that was cancelled.
+struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+impl AnalyzerService {
+    pub fn new(state: &ServerState) -> Self {
+        Self {
+            rule_cache: Arc::clone(&state.rule_cache),
+            #[cfg(feature = "secrets")]
+            secrets_scanner: state.secrets_scanner.clone(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Analyzer for AnalyzerService {
+    async fn analyze_file(
+        &self,
+        request: Request<AnalyzeFileRequest>,
+    ) -> Result<Response<AnalyzeFileResponse>, Status> {
+        let AnalyzeFileRequest {
+            filename,
+            code,
+            rulesets,
+        } = request.into_inner();
+        let rule_cache = Arc::clone(&self.rule_cache);
+        let cancellation = CancellationToken::new();
+        let _cancel_on_drop = CancelOnDrop(cancellation.clone());
+        let results = spawn_blocking(move || {
+            let rules = get_rules(&rule_cache, &rulesets)?;
+            analyze_file_content(&rules, &filename, &code, &cancellation)
+                .ok_or(AnalysisError::UnsupportedFile(filename))
+        })
+        .await
+        .map_err(|_| AnalysisError::Internal)??;
+        Ok(Response::new(AnalyzeFileResponse {
+            results: results.into_iter().map(v1::RuleResult::from).collect(),
+        }))
+    }
+
+    type AnalyzeTreeStream = ReceiverStream<Result<FileResult, Status>>;
+
+    async fn analyze_tree(
+        &self,
+        request: Request<AnalyzeTreeRequest>,
+    ) -> Result<Response<Self::AnalyzeTreeStream>, Status> {
+        let AnalyzeTreeRequest { archive, rulesets } = request.into_inner();
+        let rule_cache = Arc::clone(&self.rule_cache);
+        // Get the rules before streaming so that an invalid ruleset fails the call.
+        let rules = spawn_blocking(move || get_rules(&rule_cache, &rulesets))
+            .await
+            .map_err(|_| AnalysisError::Internal)??;
+
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+        // Stop the analysis of the current file as soon as the client is gone.
+        let cancellation = CancellationToken::new();
+        let closed_tx = tx.clone();
+        let closed_cancellation = cancellation.clone();
+        rocket::tokio::spawn(async move {
+            closed_tx.closed().await;
+            closed_cancellation.cancel();
+        });
+        spawn_blocking(move || {
+            let result = for_each_archive_file(&archive, |filename, code| {
+                // The client is gone, there is no need to analyze the remaining files.
+                if tx.is_closed() {
+                    return;
+                }
+                let file_result = match code {
+                    Some(code) => {
+                        let results = analyze_file_content(&rules, &filename, &code, &cancellation)
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(v1::RuleResult::from)
+                            .collect();
+                        FileResult {
+                            filename,
+                            results,
+                            skipped: false,
+                        }
+                    }
+                    None => FileResult {
+                        filename,
+                        results: vec![],
+                        skipped: true,
+                    },
+                };
+                let _ = tx.blocking_send(Ok(file_result));
+            });
+            if let Err(e) = result {
+                let _ = tx.blocking_send(Err(e.into()));
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn list_rules(
+        &self,
+        request: Request<ListRulesRequest>,
+    ) -> Result<Response<ListRulesResponse>, Status> {
+        let ListRulesRequest { rulesets } = request.into_inner();
+        let rule_cache = Arc::clone(&self.rule_cache);
+        let rules = spawn_blocking(move || {
+            let rules = get_rules(&rule_cache, &rulesets)?;
+            Ok::<_, AnalysisError>(
+                rulesets
+                    .iter()
+                    .zip(rules)
+                    .flat_map(|(ruleset, rules)| {
+                        rules
+                            .iter()
+                            .map(|rule| v1::Rule {
+                                name: rule.name.clone(),
+                                ruleset: ruleset.clone(),
+                                language: rule.language.to_string(),
+                                category: v1::Category::from(rule.category).into(),
+                                severity: v1::Severity::from(rule.severity).into(),
+                                short_description: rule.short_description.clone(),
+                                description: rule.description.clone(),
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect(),
+            )
+        })
+        .await
+        .map_err(|_| AnalysisError::Internal)??;
+        Ok(Response::new(ListRulesResponse { rules }))
+    }
+
+    type ValidateSecretCandidatesStream = ReceiverStream<Result<SecretCandidate, Status>>;
+
+    #[cfg(feature = "secrets")]
+    async fn validate_secret_candidates(
+        &self,
+        request: Request<ValidateSecretCandidatesRequest>,
+    ) -> Result<Response<Self::ValidateSecretCandidatesStream>, Status> {
+        use cli::secrets::ValidationStatus;
+        use std::path::Path;
+
+        let Some(scanner) = self.secrets_scanner.clone() else {
+            return Err(Status::failed_precondition(
+                "the server was started without secrets rules",
+            ));
+        };
+        let ValidateSecretCandidatesRequest { files } = request.into_inner();
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER_SIZE);
+        spawn_blocking(move || {
+            for file in files {
+                let candidates =
+                    match scanner.scan_contents(Path::new(&file.filename), &file.content) {
+                        Ok(candidates) => candidates,
+                        Err(e) => {
+                            let _ = tx.blocking_send(Err(Status::internal(e.to_string())));
+                            return;
+                        }
+                    };
+                for candidate in candidates {
+                    if tx.is_closed() {
+                        return;
+                    }
+                    let status = scanner
+                        .validate_candidate(&candidate)
+                        .map_or(ValidationStatus::Unvalidated, |result| {
+                            result.category().into()
+                        });
+                    let _ =
+                        tx.blocking_send(Ok(secret_candidate(&file.filename, &candidate, status)));
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    #[cfg(not(feature = "secrets"))]
+    async fn validate_secret_candidates(
+        &self,
+        _request: Request<ValidateSecretCandidatesRequest>,
+    ) -> Result<Response<Self::ValidateSecretCandidatesStream>, Status> {
+        Err(Status::unimplemented(
+            "the server was built without secrets scanning",
+        ))
+    }
+}
+
+#[cfg(feature = "secrets")]
+fn secret_candidate(
+    filename: &str,
+    candidate: &secrets::core::validator::Candidate,
+    status: cli::secrets::ValidationStatus,
+) -> SecretCandidate {
+    use cli::secrets::ValidationStatus;
+    use kernel::model::common::Position;
+
+    let (status, severity) = match status {
+        ValidationStatus::Valid(severity) => (v1::ValidationStatus::Valid, Some(severity)),
+        ValidationStatus::Invalid(severity) => (v1::ValidationStatus::Invalid, Some(severity)),
+        ValidationStatus::Inconclusive(severity) => {
+            (v1::ValidationStatus::Inconclusive, Some(severity))
+        }
+        ValidationStatus::Unvalidated => (v1::ValidationStatus::Unvalidated, None),
+    };
+    let span = &candidate.rule_match.matched.point_span;
+    SecretCandidate {
+        rule_id: candidate.rule_match.rule_id.to_string(),
+        filename: filename.to_string(),
+        start: Some(Position::from(span.start()).into()),
+        end: Some(Position::from(span.end()).into()),
+        status: status.into(),
+        severity: severity
+            .map_or(v1::Severity::Unspecified, v1::Severity::from)
+            .into(),
+    }
+}
+
+/// Serves the `Analyzer` gRPC service on the given address until the process exits.
+pub async fn serve(address: SocketAddr, service: AnalyzerService) {
+    tracing::info!("gRPC server listening on {address}");
+    if let Err(e) = tonic::transport::Server::builder()
+        .add_service(AnalyzerServer::new(service))
+        .serve(address)
+        .await
+    {
+        eprintln!("gRPC server error: {e}");
+    }
+}
@@ -1,8 +1,10 @@
 use tracing_subscriber::EnvFilter;
 
+mod analysis;
 mod cli;
 mod endpoints;
 mod fairings;
+mod grpc;
 mod ide;
 mod state;
 mod utils;
@@ -23,6 +25,10 @@ pub async fn start() {
     if state.is_keepalive_enabled {
         rocket = rocket.attach(fairings::KeepAlive);
     }
+    // the gRPC server runs alongside the rocket and shares its compiled rules
+    if let Some(address) = state.grpc_address {
+        rocket::tokio::spawn(grpc::serve(address, grpc::AnalyzerService::new(&state)));
+    }
     // launch the rocket
     if let Err(e) = endpoints::launch_rocket_with_endpoints(rocket, tx_shutdown).await {
         panic!("Something went wrong while trying to ignite the rocket {e:?}");
@@ -1,4 +1,6 @@
+use super::analysis::rule_cache::RuleCache;
 use super::utils::get_current_timestamp_ms;
+use std::net::SocketAddr;
 use std::sync::{Arc, RwLock};
 
 #[derive(Clone)]
@@ -7,6 +9,10 @@ pub struct ServerState {
     pub static_directory: Option<String>,
     pub is_shutdown_enabled: bool,
     pub is_keepalive_enabled: bool,
+    pub rule_cache: Arc<RuleCache>,
+    pub grpc_address: Option<SocketAddr>,
+    #[cfg(feature = "secrets")]
+    pub secrets_scanner: Option<Arc<secrets::Scanner>>,
 }
 
 impl ServerState {
@@ -16,6 +22,10 @@ impl ServerState {
             static_directory,
             is_shutdown_enabled,
             is_keepalive_enabled: false,
+            rule_cache: Arc::new(RuleCache::new(false)),
+            grpc_address: None,
+            #[cfg(feature = "secrets")]
+            secrets_scanner: None,
         }
     }
 }
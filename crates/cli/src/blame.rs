@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use git2::{Blame, Repository};
+use kernel::model::common::SharedStr;
+use kernel::model::rule::RuleResult;
+use kernel::model::violation::{LastCommit, Violation};
+
+/// Annotates the violations with the last commit that changed their lines, so that the violations
+/// of new code can be told apart from the violations of old code.
+///
+/// `git blame` is slow on large files with a long history, so each file is blamed at most once,
+/// and at most `max_files` files are blamed: the violations of the other files are not annotated.
+pub struct Blamer {
+    repository: Repository,
+    max_files: usize,
+    blamed_files: usize,
+    skipped_files: usize,
+}
+
+impl Blamer {
+    /// Opens the git repository at the root of the analyzed directory.
+    pub fn open(directory: &Path, max_files: usize) -> Result<Self> {
+        let repository = Repository::open(directory)
+            .with_context(|| format!("{} is not a git repository", directory.display()))?;
+        Ok(Self {
+            repository,
+            max_files,
+            blamed_files: 0,
+            skipped_files: 0,
+        })
+    }
+
+    /// Returns the number of files with violations that were blamed.
+    pub fn blamed_files(&self) -> usize {
+        self.blamed_files
+    }
+
+    /// Returns the number of files with violations that were not blamed, as `max_files` files
+    /// already were.
+    pub fn skipped_files(&self) -> usize {
+        self.skipped_files
+    }
+
+    /// Sets the last commit of the violations of the results. The lines are blamed as of `HEAD`:
+    /// the files that are not committed and the lines after the end of the committed file are
+    /// not annotated.
+    pub fn annotate(&mut self, rule_results: &mut [RuleResult]) {
+        let mut blames: HashMap<SharedStr, Option<Blame>> = HashMap::new();
+        for result in rule_results {
+            if result.violations.is_empty() {
+                continue;
+            }
+            if !blames.contains_key(&result.filename) {
+                let blame = if self.blamed_files < self.max_files {
+                    self.blamed_files += 1;
+                    self.repository
+                        .blame_file(Path::new(&result.filename), None)
+                        .ok()
+                } else {
+                    self.skipped_files += 1;
+                    None
+                };
+                blames.insert(result.filename.clone(), blame);
+            }
+            let Some(blame) = &blames[&result.filename] else {
+                continue;
+            };
+            for violation in &mut result.violations {
+                violation.last_commit = get_last_commit(blame, violation);
+            }
+        }
+    }
+}
+
+/// Returns the most recent commit of the lines of the violation.
+fn get_last_commit(blame: &Blame, violation: &Violation) -> Option<LastCommit> {
+    if violation.file_level || violation.start.line == 0 {
+        return None;
+    }
+    let end_line = violation.end.line.max(violation.start.line);
+    (violation.start.line..=end_line)
+        .filter_map(|line| blame.get_line(line as usize))
+        .max_by_key(|hunk| hunk.final_signature().when().seconds())
+        .map(|hunk| {
+            let signature = hunk.final_signature();
+            LastCommit {
+                sha: hunk.final_commit_id().to_string(),
+                author: signature.name().unwrap_or_default().to_string(),
+                author_email: signature.email().unwrap_or_default().to_string(),
+                date: signature.when().seconds(),
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rule_result, violation};
+    use git2::{Signature, Time};
+    use kernel::model::common::Position;
+    use kernel::model::rule::{RuleCategory, RuleSeverity};
+    use std::fs;
+
+    fn commit(repository: &Repository, filename: &str, content: &str, author: &str, date: i64) {
+        fs::write(
+            repository.workdir().unwrap().join(filename),
+            content.as_bytes(),
+        )
+        .unwrap();
+        let mut index = repository.index().unwrap();
+        index.add_path(Path::new(filename)).unwrap();
+        index.write().unwrap();
+        let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::new(
+            author,
+            &format!("{}@example.com", author.to_lowercase()),
+            &Time::new(date, 0),
+        )
+        .unwrap();
+        let parent = repository
+            .head()
+            .ok()
+            .map(|head| head.peel_to_commit().unwrap());
+        repository
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "commit",
+                &tree,
+                &parent.iter().collect::<Vec<_>>(),
+            )
+            .unwrap();
+    }
+
+    fn result(filename: &str, lines: &[(u32, u32)]) -> RuleResult {
+        let violations = lines
+            .iter()
+            .map(|(start, end)| {
+                violation(
+                    Position {
+                        line: *start,
+                        col: 1,
+                    },
+                    Position { line: *end, col: 2 },
+                    RuleSeverity::Error,
+                    RuleCategory::BestPractices,
+                )
+            })
+            .collect();
+        rule_result("myrule", filename, violations)
+    }
+
+    fn authors(result: &RuleResult) -> Vec<Option<&str>> {
+        result
+            .violations
+            .iter()
+            .map(|v| v.last_commit.as_ref().map(|c| c.author.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn annotate_violations() {
+        let directory = tempfile::tempdir().unwrap();
+        let repository = Repository::init(directory.path()).unwrap();
+        commit(
+            &repository,
+            "a.py",
+            "a = 1\nb = 2\n",
+            "Alice",
+            1_600_000_000,
+        );
+        commit(&repository, "a.py", "a = 1\nb = 3\n", "Bob", 1_700_000_000);
+        commit(&repository, "b.py", "c = 1\n", "Alice", 1_600_000_000);
+        // a line that is not committed yet
+        fs::write(directory.path().join("a.py"), "a = 1\nb = 3\nc = 4\n").unwrap();
+
+        let mut results = [
+            result("a.py", &[(1, 1), (1, 2), (3, 3)]),
+            result("b.py", &[(1, 1)]),
+        ];
+        let mut blamer = Blamer::open(directory.path(), 10).unwrap();
+        blamer.annotate(&mut results);
+        // the most recent commit of the lines of a violation is used
+        assert_eq!(authors(&results[0]), vec![Some("Alice"), Some("Bob"), None]);
+        let last_commit = results[0].violations[1].last_commit.as_ref().unwrap();
+        assert_eq!(last_commit.author_email, "bob@example.com");
+        assert_eq!(last_commit.date, 1_700_000_000);
+        assert_eq!(authors(&results[1]), vec![Some("Alice")]);
+        assert_eq!((blamer.blamed_files(), blamer.skipped_files()), (2, 0));
+
+        // past the maximum number of files, the violations are not annotated
+        let mut results = [result("a.py", &[(1, 1)]), result("b.py", &[(1, 1)])];
+        let mut blamer = Blamer::open(directory.path(), 1).unwrap();
+        blamer.annotate(&mut results);
+        assert_eq!(authors(&results[0]), vec![Some("Alice")]);
+        assert_eq!(authors(&results[1]), vec![None]);
+        assert_eq!((blamer.blamed_files(), blamer.skipped_files()), (1, 1));
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use git2::{Delta, DiffOptions, Patch, Repository, Tree};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// A file changed in a repository, with the content of its new version and the lines that were added.
+#[derive(Debug)]
+pub struct ChangedFile {
+    /// The path of the file, relative to the root of the repository.
+    pub path: PathBuf,
+    pub contents: Vec<u8>,
+    /// The line numbers of the lines that were added or modified.
+    pub added_lines: BTreeSet<u32>,
+}
+
+/// Returns the files staged in the index of the repository that contains `directory`,
+/// compared to `HEAD`. In a repository without commits, all the staged files are returned.
+pub fn get_staged_files(directory: &Path) -> Result<Vec<ChangedFile>> {
+    let repository = Repository::discover(directory).context("cannot open the git repository")?;
+    let head = repository.head().ok().and_then(|h| h.peel_to_tree().ok());
+    let index = repository.index()?;
+    let diff = repository.diff_tree_to_index(head.as_ref(), Some(&index), Some(&mut options()))?;
+    get_changed_files(&repository, &diff)
+}
+
+/// Returns the files changed between `base` (a revision such as `@{upstream}`) and `HEAD`.
+/// If `base` can't be resolved, for example for a branch that was never pushed, all the files
+/// of `HEAD` are returned.
+pub fn get_files_changed_since(directory: &Path, base: &str) -> Result<Vec<ChangedFile>> {
+    let repository = Repository::discover(directory).context("cannot open the git repository")?;
+    let head = repository.head()?.peel_to_tree()?;
+    let base: Option<Tree> = repository
+        .revparse_single(base)
+        .ok()
+        .and_then(|object| object.peel_to_tree().ok());
+    let diff = repository.diff_tree_to_tree(base.as_ref(), Some(&head), Some(&mut options()))?;
+    get_changed_files(&repository, &diff)
+}
+
+/// Returns the directory of the git hooks of the repository that contains `directory`.
+pub fn get_hooks_directory(directory: &Path) -> Result<PathBuf> {
+    let repository = Repository::discover(directory).context("cannot open the git repository")?;
+    // `core.hooksPath` is relative to the root of the working tree.
+    if let Ok(hooks_path) = repository.config()?.get_path("core.hooksPath") {
+        let root = repository.workdir().unwrap_or_else(|| repository.path());
+        return Ok(root.join(hooks_path));
+    }
+    Ok(repository.path().join("hooks"))
+}
+
+fn options() -> DiffOptions {
+    let mut options = DiffOptions::new();
+    options.context_lines(0);
+    options
+}
+
+fn get_changed_files(repository: &Repository, diff: &git2::Diff) -> Result<Vec<ChangedFile>> {
+    let mut files = vec![];
+    for (idx, delta) in diff.deltas().enumerate() {
+        if delta.status() == Delta::Deleted {
+            continue;
+        }
+        let new_file = delta.new_file();
+        let Some(path) = new_file.path() else {
+            continue;
+        };
+        let blob = repository.find_blob(new_file.id())?;
+        if blob.is_binary() {
+            continue;
+        }
+        // A patch is `None` when the file didn't change (e.g. a change of mode).
+        let Some(patch) = Patch::from_diff(diff, idx)? else {
+            continue;
+        };
+        let mut added_lines = BTreeSet::new();
+        for hunk in 0..patch.num_hunks() {
+            for line in 0..patch.num_lines_in_hunk(hunk)? {
+                let line = patch.line_in_hunk(hunk, line)?;
+                if line.origin() == '+' {
+                    added_lines.extend(line.new_lineno());
+                }
+            }
+        }
+        if !added_lines.is_empty() {
+            files.push(ChangedFile {
+                path: path.to_path_buf(),
+                contents: blob.content().to_vec(),
+                added_lines,
+            });
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn staged_files() {
+        let directory = tempfile::tempdir().unwrap();
+        let repository = Repository::init(directory.path()).unwrap();
+        let mut index = repository.index().unwrap();
+
+        fs::write(directory.path().join("committed.txt"), "a\nb\n").unwrap();
+        index.add_path(Path::new("committed.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+        repository
+            .commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[])
+            .unwrap();
+
+        // only the staged version of a file is returned, and only its added lines
+        fs::write(directory.path().join("committed.txt"), "a\nnew\nb\n").unwrap();
+        index.add_path(Path::new("committed.txt")).unwrap();
+        fs::write(directory.path().join("staged.txt"), "x\n").unwrap();
+        index.add_path(Path::new("staged.txt")).unwrap();
+        index.write().unwrap();
+        fs::write(directory.path().join("staged.txt"), "x\nunstaged\n").unwrap();
+        fs::write(directory.path().join("untracked.txt"), "y\n").unwrap();
+
+        let files = get_staged_files(directory.path()).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, PathBuf::from("committed.txt"));
+        assert_eq!(files[0].contents, b"a\nnew\nb\n");
+        assert_eq!(files[0].added_lines, BTreeSet::from([2]));
+        assert_eq!(files[1].path, PathBuf::from("staged.txt"));
+        assert_eq!(files[1].contents, b"x\n");
+        assert_eq!(files[1].added_lines, BTreeSet::from([1]));
+
+        // without a base, all the files of HEAD are returned
+        let files = get_files_changed_since(directory.path(), "@{upstream}").unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].added_lines, BTreeSet::from([1, 2]));
+    }
+}
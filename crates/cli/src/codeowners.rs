@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use kernel::model::rule::RuleResult;
+use std::fs;
+use std::path::Path;
+
+use crate::secrets::SecretResult;
+
+/// The locations of the `CODEOWNERS` file in a repository, in the order they are looked up.
+const CODEOWNERS_LOCATIONS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// A line of a `CODEOWNERS` file: the files matched by a pattern, and their owners.
+#[derive(Debug)]
+struct Entry {
+    globs: GlobSet,
+    owners: Vec<String>,
+}
+
+/// The owners of the files of a repository, as listed in its `CODEOWNERS` file.
+/// See https://docs.github.com/en/repositories/managing-your-repositorys-settings-and-features/customizing-your-repository/about-code-owners
+#[derive(Debug, Default)]
+pub struct CodeOwners {
+    entries: Vec<Entry>,
+}
+
+impl CodeOwners {
+    /// Parses the content of a `CODEOWNERS` file. Lines with an invalid pattern are ignored,
+    /// as they are by GitHub.
+    pub fn parse(content: &str) -> Self {
+        let entries = content
+            .lines()
+            .map(str::trim)
+            // GitLab sections (`[Section]` or `^[Section]`) are not supported: their entries are
+            // read as if they were not in a section.
+            .filter(|line| !line.is_empty() && !line.starts_with(['#', '[', '^']))
+            .filter_map(|line| {
+                let mut tokens = line.split_whitespace();
+                let globs = pattern_globs(tokens.next()?)?;
+                let owners = tokens
+                    .take_while(|token| !token.starts_with('#'))
+                    .map(str::to_string)
+                    .collect();
+                Some(Entry { globs, owners })
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Reads the `CODEOWNERS` file of a repository, if there is one.
+    pub fn from_repository(directory: &Path) -> Result<Option<Self>> {
+        for location in CODEOWNERS_LOCATIONS {
+            let path = directory.join(location);
+            if path.is_file() {
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("cannot read {}", path.display()))?;
+                return Ok(Some(Self::parse(&content)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the owners of a file, given its path relative to the root of the repository.
+    /// The last entry that matches the file takes precedence.
+    pub fn owners(&self, path: &str) -> &[String] {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.globs.is_match(path))
+            .map(|entry| entry.owners.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Sets the owners of the violations of the results and of the secrets.
+    pub fn assign(&self, rule_results: &mut [RuleResult], secrets: &mut [SecretResult]) {
+        for result in rule_results {
+            let owners = self.owners(&result.filename);
+            for violation in &mut result.violations {
+                violation.owners = owners.to_vec();
+            }
+        }
+        for secret in secrets {
+            secret.violation.owners = self.owners(&secret.file_path).to_vec();
+        }
+    }
+}
+
+/// Compiles a pattern of a `CODEOWNERS` file, which follows the rules of `.gitignore` files.
+fn pattern_globs(pattern: &str) -> Option<GlobSet> {
+    let anchored = pattern.starts_with('/');
+    let directory = pattern.ends_with('/');
+    let pattern = pattern.trim_matches('/');
+    // A pattern without a slash (other than a trailing one) matches at any depth.
+    let base = if anchored || pattern.contains('/') {
+        pattern.to_string()
+    } else {
+        format!("**/{pattern}")
+    };
+    let last_component = pattern.rsplit('/').next().unwrap_or_default();
+    let globs = if last_component.contains('*') {
+        // A pattern that ends with a wildcard only matches the files of a directory, not those
+        // of its subdirectories.
+        vec![base]
+    } else if directory {
+        vec![format!("{base}/**")]
+    } else {
+        // The pattern matches either a file, or a directory and everything it contains.
+        vec![format!("{base}/**"), base]
+    };
+
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(
+            GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .ok()?,
+        );
+    }
+    builder.build().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODEOWNERS: &str = r#"
+# the default owners
+*       @org/everyone
+*.js    @org/frontend # inline comment
+/build/logs/ @org/build
+docs/*  docs@example.com
+apps/   @org/apps
+/scripts/deploy.sh @org/ops @alice
+/scripts/generated.sh
+"#;
+
+    #[test]
+    fn owners() {
+        let codeowners = CodeOwners::parse(CODEOWNERS);
+        assert_eq!(codeowners.owners("README.md"), ["@org/everyone"]);
+        assert_eq!(codeowners.owners("src/lib/index.js"), ["@org/frontend"]);
+        assert_eq!(codeowners.owners("build/logs/out.txt"), ["@org/build"]);
+        assert_eq!(codeowners.owners("build/logs/a/b.txt"), ["@org/build"]);
+        assert_eq!(codeowners.owners("src/build/logs/a.txt"), ["@org/everyone"]);
+        assert_eq!(codeowners.owners("docs/index.md"), ["docs@example.com"]);
+        assert_eq!(codeowners.owners("docs/api/index.md"), ["@org/everyone"]);
+        assert_eq!(codeowners.owners("apps/web/main.py"), ["@org/apps"]);
+        assert_eq!(codeowners.owners("src/apps/main.py"), ["@org/apps"]);
+        assert_eq!(
+            codeowners.owners("scripts/deploy.sh"),
+            ["@org/ops", "@alice"]
+        );
+        // an entry without owners removes the owners of the file
+        assert!(codeowners.owners("scripts/generated.sh").is_empty());
+    }
+
+    #[test]
+    fn no_entries() {
+        let codeowners = CodeOwners::parse("# no owners\n\n[Section]\n");
+        assert!(codeowners.owners("README.md").is_empty());
+    }
+}
@@ -3,6 +3,8 @@ pub static DATADOG_CONFIG_FILE_WITHOUT_PREFIX: &str = "static-analysis.datadog";
 pub static DATADOG_HEADER_APP_KEY: &str = "dd-application-key";
 pub static DATADOG_HEADER_API_KEY: &str = "dd-api-key";
 pub static DATADOG_HEADER_JWT_TOKEN: &str = "dd-auth-jwt";
+pub static DATADOG_HEADER_EVP_ORIGIN: &str = "dd-evp-origin";
+pub static DATADOG_HEADER_EVP_ORIGIN_VERSION: &str = "dd-evp-origin-version";
 pub static HEADER_CONTENT_TYPE: &str = "Content-Type";
 pub static HEADER_CONTENT_TYPE_APPLICATION_JSON: &str = "application/json";
 pub static SARIF_PROPERTY_DATADOG_FINGERPRINT: &str = "DATADOG_FINGERPRINT";
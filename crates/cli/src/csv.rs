@@ -1,7 +1,7 @@
 use csv::Writer;
 use kernel::model::rule::RuleResult;
 
-pub fn generate_csv_results(rule_results: &Vec<RuleResult>) -> String {
+pub fn generate_csv_results(rule_results: &[RuleResult]) -> String {
     let mut wtr = Writer::from_writer(vec![]);
     wtr.write_record([
         "filename",
@@ -47,28 +47,41 @@ mod tests {
     // execution time must be more than 0
     #[test]
     fn test_export_csv() {
-        let res_no_result = generate_csv_results(&vec![]);
+        let res_no_result = generate_csv_results(&[]);
         assert_eq!(
             res_no_result,
             "filename,rule,category,severity,message,start_line,start_col,end_line,end_col\n"
         );
-        let res_with_result = generate_csv_results(&vec![RuleResult {
-            rule_name: "myrule".to_string(),
-            filename: "filename".to_string(),
+        let res_with_result = generate_csv_results(&[RuleResult {
+            rule_name: "myrule".into(),
+            filename: "filename".into(),
             violations: vec![Violation {
                 start: Position { line: 10, col: 12 },
                 end: Position { line: 12, col: 10 },
-                message: "message".to_string(),
+                message: "message".into(),
                 severity: RuleSeverity::Error,
                 category: RuleCategory::Performance,
                 fixes: vec![],
+                merged_rules: vec![],
+                tags: vec![],
+                cwe: vec![],
+                documentation_url: None,
+                related_locations: vec![],
+                file_level: false,
+                fingerprint: None,
+                owners: vec![],
+                snippet: None,
+                offsets: None,
+                last_commit: None,
             }],
             errors: vec![],
             execution_error: None,
             output: None,
             execution_time_ms: 10,
             query_node_time_ms: 0,
+            degraded: false,
             parsing_time_ms: 0,
+            profile: None,
         }]);
         assert_eq!(res_with_result, "filename,rule,category,severity,message,start_line,start_col,end_line,end_col\nfilename,myrule,performance,error,message,10,12,12,10\n");
     }
@@ -1,26 +1,45 @@
 use std::env;
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
 
 use crate::{
     constants::{
-        DATADOG_HEADER_API_KEY, DATADOG_HEADER_APP_KEY, DATADOG_HEADER_JWT_TOKEN,
-        HEADER_CONTENT_TYPE, HEADER_CONTENT_TYPE_APPLICATION_JSON,
+        DATADOG_HEADER_API_KEY, DATADOG_HEADER_APP_KEY, DATADOG_HEADER_EVP_ORIGIN,
+        DATADOG_HEADER_EVP_ORIGIN_VERSION, DATADOG_HEADER_JWT_TOKEN, HEADER_CONTENT_TYPE,
+        HEADER_CONTENT_TYPE_APPLICATION_JSON,
     },
+    git_utils::GitMetadata,
     model::datadog_api::APIErrorResponse,
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use kernel::constants::CARGO_VERSION;
 use kernel::model::rule::Rule;
 use kernel::model::ruleset::RuleSet;
+use reqwest::blocking::multipart::{Form, Part};
 use reqwest::blocking::RequestBuilder;
+use serde_json::{json, Value};
 use uuid::Uuid;
 
 use crate::model::datadog_api::{
-    APIResponse, ApiResponseDefaultRuleset, DiffAwareData, DiffAwareRequest,
-    DiffAwareRequestArguments, DiffAwareRequestData, DiffAwareRequestDataAttributes,
-    DiffAwareResponse,
+    APIResponse, ApiResponseDefaultRuleset, ApiResponseSecretRules, ApiSecretRulesAttributes,
+    DiffAwareData, DiffAwareRequest, DiffAwareRequestArguments, DiffAwareRequestData,
+    DiffAwareRequestDataAttributes, DiffAwareResponse,
 };
 
 const STAGING_DATADOG_HOSTNAME: &str = "api.datad0g.com";
 const DEFAULT_DATADOG_HOSTNAME: &str = "api.datadoghq.com";
+const STAGING_DATADOG_SITE: &str = "datad0g.com";
+const DEFAULT_DATADOG_SITE: &str = "datadoghq.com";
+
+/// The origin of the reports uploaded to the intake.
+const UPLOAD_ORIGIN: &str = "datadog-static-analyzer";
+/// The number of times an upload is attempted before failing.
+const UPLOAD_ATTEMPTS: u32 = 3;
+/// The delay before the second attempt of an upload, doubled after each attempt.
+const UPLOAD_RETRY_DELAY: Duration = Duration::from_secs(2);
 
 const DEFAULT_RULESETS_LANGUAGES: &[&str] = &[
     "CSHARP",
@@ -76,6 +95,18 @@ fn get_datadog_hostname(use_staging: bool) -> String {
     }
 }
 
+// The site of the intake: the DD_SITE envvar, the staging site if use_staging is true, or the
+// default site.
+fn get_datadog_site(use_staging: bool) -> String {
+    if use_staging {
+        STAGING_DATADOG_SITE.to_string()
+    } else if let Ok(site) = get_datadog_variable_value("SITE") {
+        site
+    } else {
+        DEFAULT_DATADOG_SITE.to_string()
+    }
+}
+
 enum RequestMethod {
     Get,
     Post,
@@ -199,6 +230,37 @@ pub fn get_all_default_rulesets(use_staging: bool) -> Result<Vec<RuleSet>> {
     Ok(result)
 }
 
+/// Get the secrets rules from Datadog, at the given version, or at the latest version if
+/// `version` is `None`.
+pub fn get_secrets_rules(
+    version: Option<&str>,
+    use_staging: bool,
+) -> Result<ApiSecretRulesAttributes> {
+    let path = match version {
+        Some(version) => format!("secrets/rules/{version}"),
+        None => "secrets/rules/latest".to_string(),
+    };
+    let server_response = make_request(RequestMethod::Get, &path, use_staging, true)?.send()?;
+
+    let status_code = server_response.status();
+    let response_text = &server_response.text()?;
+
+    if !status_code.is_success() {
+        let error_msg = serde_json::from_str::<APIErrorResponse>(response_text)
+            .ok()
+            .and_then(|error| error.errors.into_iter().next())
+            .map_or_else(
+                || format!("Unknown error {status_code}"),
+                |e| format!("Error: {}", e.detail.unwrap_or(e.title)),
+            );
+        return Err(anyhow!(error_msg));
+    }
+
+    serde_json::from_str::<ApiResponseSecretRules>(response_text)
+        .map(|response| response.data.attributes)
+        .map_err(|e| anyhow!("error when parsing the secrets rules: {e:?}"))
+}
+
 /// Get diff-aware data from Datadog. In order to be able to perform this,
 /// we need to ensure that
 ///   1. We are scanning a Git Repository
@@ -255,6 +317,108 @@ pub fn get_diff_aware_information(arguments: &DiffAwareRequestArguments) -> Resu
     }
 }
 
+/// The metadata of a report uploaded to Datadog: the service and environment it's reported
+/// under, and the analyzed commit.
+pub struct UploadMetadata {
+    pub service: String,
+    pub env: String,
+    pub git: GitMetadata,
+}
+
+/// Returns the event that describes an uploaded report.
+fn upload_event(metadata: &UploadMetadata) -> Value {
+    let mut event = json!({
+        "type": "sarif",
+        "service": metadata.service,
+        "env": metadata.env,
+        "tool": UPLOAD_ORIGIN,
+        "tool_version": CARGO_VERSION,
+        "git.repository_url": metadata.git.repository_url,
+        "git.commit.sha": metadata.git.commit_sha,
+    });
+    if let Some(branch) = &metadata.git.branch {
+        event["git.branch"] = json!(branch);
+    }
+    event
+}
+
+/// Uploads a SARIF report to the static analysis intake of Datadog, compressed with gzip. The
+/// upload is retried when the request fails or the intake is unavailable.
+///
+/// The upload requires an API key (`DD_API_KEY`), and sends the application key
+/// (`DD_APP_KEY`) when it's set.
+pub fn upload_sarif_report(
+    report: &str,
+    metadata: &UploadMetadata,
+    use_staging: bool,
+) -> Result<()> {
+    let api_key = get_datadog_variable_value("API_KEY")?;
+    let app_key = get_datadog_variable_value("APP_KEY").ok();
+    let url = format!(
+        "https://cicodescan-intake.{}/api/v2/cicodescan",
+        get_datadog_site(use_staging)
+    );
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(report.as_bytes())?;
+    let compressed = encoder
+        .finish()
+        .context("cannot compress the SARIF report")?;
+    let event = serde_json::to_vec(&upload_event(metadata))?;
+
+    let client = reqwest::blocking::Client::new();
+    let mut delay = UPLOAD_RETRY_DELAY;
+    for attempt in 1..=UPLOAD_ATTEMPTS {
+        // the form is consumed by the request, so it's built again for each attempt
+        let form = Form::new()
+            .part(
+                "event",
+                Part::bytes(event.clone())
+                    .file_name("event.json")
+                    .mime_str(HEADER_CONTENT_TYPE_APPLICATION_JSON)?,
+            )
+            .part(
+                "sarif_report_file",
+                Part::bytes(compressed.clone())
+                    .file_name("report.sarif.gz")
+                    .mime_str("application/gzip")?,
+            );
+        let mut request = client
+            .post(&url)
+            .header(DATADOG_HEADER_API_KEY, &api_key)
+            .header(DATADOG_HEADER_EVP_ORIGIN, UPLOAD_ORIGIN)
+            .header(DATADOG_HEADER_EVP_ORIGIN_VERSION, CARGO_VERSION)
+            .multipart(form);
+        if let Some(app_key) = &app_key {
+            request = request.header(DATADOG_HEADER_APP_KEY, app_key);
+        }
+
+        let error = match request.send() {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                let error = anyhow!(
+                    "the intake returned {status}: {}",
+                    response.text().unwrap_or_default()
+                );
+                // other client errors (e.g. an invalid API key) fail the same way when retried
+                if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    return Err(error);
+                }
+                error
+            }
+            Err(err) => anyhow!(err),
+        };
+        if attempt == UPLOAD_ATTEMPTS {
+            return Err(error.context(format!("the upload failed {UPLOAD_ATTEMPTS} times")));
+        }
+        eprintln!("Upload attempt {attempt} failed, retrying in {delay:?}: {error}");
+        thread::sleep(delay);
+        delay *= 2;
+    }
+    unreachable!("the last attempt returns")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -264,4 +428,30 @@ mod tests {
         assert_eq!(get_datadog_hostname(true), STAGING_DATADOG_HOSTNAME);
         assert_eq!(get_datadog_hostname(false), DEFAULT_DATADOG_HOSTNAME);
     }
+
+    #[test]
+    fn test_upload_event() {
+        let mut metadata = UploadMetadata {
+            service: "my-service".to_string(),
+            env: "ci".to_string(),
+            git: GitMetadata {
+                repository_url: "https://github.com/DataDog/datadog-static-analyzer.git"
+                    .to_string(),
+                commit_sha: "0123456789abcdef0123456789abcdef01234567".to_string(),
+                branch: Some("main".to_string()),
+            },
+        };
+        let event = upload_event(&metadata);
+        assert_eq!(event["service"], "my-service");
+        assert_eq!(event["env"], "ci");
+        assert_eq!(
+            event["git.commit.sha"],
+            "0123456789abcdef0123456789abcdef01234567"
+        );
+        assert_eq!(event["git.branch"], "main");
+
+        // a detached HEAD has no branch
+        metadata.git.branch = None;
+        assert!(upload_event(&metadata).get("git.branch").is_none());
+    }
 }
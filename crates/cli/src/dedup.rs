@@ -0,0 +1,207 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use kernel::model::common::SharedStr;
+use kernel::model::rule::RuleResult;
+use kernel::model::violation::compute_fingerprint;
+
+/// The keys under which a violation is considered a duplicate of another one.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum DuplicateKey {
+    /// (filename, start line, start col, end line, end col, message)
+    Location(SharedStr, u32, u32, u32, u32, SharedStr),
+    /// The fingerprint of the violation, computed with the name of the rule without its ruleset.
+    Fingerprint(String),
+}
+
+/// Merge the identical violations reported by different rules. Two violations are identical
+/// if they are in the same file, at the same location and have the same message (for example,
+/// when a ruleset and a copy of it are both used), or if they have the same fingerprint once
+/// the ruleset is left out of the rule name (for example, when a rule was copied in a custom
+/// ruleset and its messages reworded). The files are read from `directory` to compute these
+/// fingerprints.
+///
+/// The first violation found is kept and the names of the rules that reported the same violation
+/// are added to its `merged_rules`. The rule results themselves are kept, even if they no longer
+/// have violations, so that their errors and statistics are still reported.
+///
+/// Returns the number of violations that were merged.
+pub fn merge_duplicate_violations(rule_results: &mut [RuleResult], directory: &Path) -> usize {
+    let mut files: HashMap<SharedStr, Option<String>> = HashMap::new();
+    let mut seen: HashMap<DuplicateKey, (usize, usize)> = HashMap::new();
+    let mut merged: Vec<(usize, usize, String)> = vec![];
+
+    for (result_idx, rule_result) in rule_results.iter_mut().enumerate() {
+        let content = files
+            .entry(rule_result.filename.clone())
+            .or_insert_with(|| fs::read_to_string(directory.join(&rule_result.filename)).ok());
+        let short_name = rule_result
+            .rule_name
+            .rsplit_once('/')
+            .map_or(rule_result.rule_name.as_str(), |(_, name)| name);
+        let mut violation_idx = 0;
+        rule_result.violations.retain(|violation| {
+            let mut keys = vec![DuplicateKey::Location(
+                rule_result.filename.clone(),
+                violation.start.line,
+                violation.start.col,
+                violation.end.line,
+                violation.end.col,
+                violation.message.clone(),
+            )];
+            if !violation.file_level {
+                let line = content.as_deref().and_then(|content| {
+                    content
+                        .lines()
+                        .nth((violation.start.line as usize).checked_sub(1)?)
+                });
+                if let Some(line) = line {
+                    keys.push(DuplicateKey::Fingerprint(compute_fingerprint(
+                        short_name,
+                        &rule_result.filename,
+                        line,
+                    )));
+                }
+            }
+
+            // The same rule can't be merged with itself.
+            let duplicate_of = keys
+                .iter()
+                .filter_map(|key| seen.get(key))
+                .find(|(kept_result_idx, _)| *kept_result_idx != result_idx)
+                .copied();
+            if let Some((kept_result_idx, kept_violation_idx)) = duplicate_of {
+                merged.push((
+                    kept_result_idx,
+                    kept_violation_idx,
+                    rule_result.rule_name.to_string(),
+                ));
+                return false;
+            }
+            for key in keys {
+                if let Entry::Vacant(entry) = seen.entry(key) {
+                    entry.insert((result_idx, violation_idx));
+                }
+            }
+            violation_idx += 1;
+            true
+        });
+    }
+
+    let count = merged.len();
+    for (result_idx, violation_idx, rule_name) in merged {
+        let merged_rules = &mut rule_results[result_idx].violations[violation_idx].merged_rules;
+        if !merged_rules.contains(&rule_name) {
+            merged_rules.push(rule_name);
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{self, rule_result};
+    use kernel::model::common::Position;
+    use kernel::model::rule::{RuleCategory, RuleSeverity};
+    use kernel::model::violation::Violation;
+
+    fn violation(line: u32, message: &str) -> Violation {
+        Violation {
+            message: message.into(),
+            ..test_utils::violation(
+                Position { line, col: 1 },
+                Position { line, col: 10 },
+                RuleSeverity::Warning,
+                RuleCategory::Security,
+            )
+        }
+    }
+
+    #[test]
+    fn test_merge_duplicate_violations() {
+        let mut results = vec![
+            rule_result(
+                "python-security/no-eval",
+                "main.py",
+                vec![
+                    violation(1, "do not use eval"),
+                    violation(2, "do not use eval"),
+                ],
+            ),
+            rule_result(
+                "custom/no-eval",
+                "main.py",
+                vec![
+                    violation(1, "do not use eval"),
+                    violation(3, "do not use eval"),
+                ],
+            ),
+            // Same location, but a different file
+            rule_result(
+                "custom/no-eval",
+                "other.py",
+                vec![violation(1, "do not use eval")],
+            ),
+            // Same location and file, but a different message
+            rule_result(
+                "custom/no-exec",
+                "main.py",
+                vec![violation(1, "do not use exec")],
+            ),
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(merge_duplicate_violations(&mut results, dir.path()), 1);
+        assert_eq!(results[0].violations.len(), 2);
+        assert_eq!(
+            results[0].violations[0].merged_rules,
+            vec!["custom/no-eval".to_string()]
+        );
+        assert!(results[0].violations[1].merged_rules.is_empty());
+        assert_eq!(results[1].violations.len(), 1);
+        assert_eq!(results[1].violations[0].start.line, 3);
+        assert_eq!(results[2].violations.len(), 1);
+        assert_eq!(results[3].violations.len(), 1);
+    }
+
+    /// A copy of a rule in another ruleset, with reworded messages, reports the same violations.
+    #[test]
+    fn test_merge_duplicate_violations_by_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("main.py"), "eval(x)\neval(y)\n").unwrap();
+        let mut results = vec![
+            rule_result(
+                "python-security/no-eval",
+                "main.py",
+                vec![
+                    violation(1, "do not use eval"),
+                    violation(2, "do not use eval"),
+                ],
+            ),
+            rule_result(
+                "custom/no-eval",
+                "main.py",
+                vec![violation(1, "eval is forbidden here")],
+            ),
+            // Same line, but a different rule
+            rule_result(
+                "custom/no-exec",
+                "main.py",
+                vec![violation(2, "exec is forbidden here")],
+            ),
+        ];
+
+        assert_eq!(merge_duplicate_violations(&mut results, dir.path()), 1);
+        assert_eq!(results[0].violations.len(), 2);
+        assert_eq!(
+            results[0].violations[0].merged_rules,
+            vec!["custom/no-eval".to_string()]
+        );
+        assert!(results[0].violations[1].merged_rules.is_empty());
+        assert!(results[1].violations.is_empty());
+        assert_eq!(results[2].violations.len(), 1);
+    }
+}
@@ -1,43 +1,89 @@
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
 use std::fs;
 use std::fs::read_to_string;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::Result;
-use sha2::{Digest, Sha256};
+use memmap2::Mmap;
 use walkdir::WalkDir;
 
+use kernel::analysis::generated_content::is_generated_file;
+use kernel::analysis::sfc::SFC_EXTENSIONS;
+use kernel::editorconfig::EditorConfig;
 use kernel::model::common::Language;
-use kernel::model::config_file::PathConfig;
-use kernel::model::violation::Violation;
+use kernel::model::config_file::{ConfigFile, FileLanguagesConfig, PathConfig};
+use kernel::model::rule::Rule;
+use kernel::model::violation::{compute_fingerprint, Violation};
+use kernel::path_restrictions::PathRestrictions;
+use kernel::rule_overrides::RuleOverrides;
 
-use crate::model::cli_configuration::CliConfiguration;
+use crate::constants::DEFAULT_MAX_FILE_SIZE_KB;
+
+use crate::gitignore::GitIgnore;
+use crate::model::cli_configuration::{CliConfiguration, NonUtf8Behavior, SymlinkBehavior};
 use crate::model::datadog_api::DiffAwareData;
+use crate::model::skipped_file::{SkipReason, SkippedFile};
 
 static FILE_EXTENSIONS_PER_LANGUAGE_LIST: &[(Language, &[&str])] = &[
     (Language::Csharp, &["cs"]),
     (Language::Dockerfile, &["docker", "dockerfile"]),
     (Language::Go, &["go"]),
+    (Language::GraphQL, &["graphql", "gql"]),
+    (Language::Groovy, &["groovy", "gradle"]),
     (Language::Java, &["java"]),
     (Language::JavaScript, &["js", "jsx"]),
     (Language::Kotlin, &["kt", "kts"]),
+    (Language::ObjectiveC, &["m", "mm"]),
+    (Language::Proto, &["proto"]),
     (Language::Python, &["py", "py3"]),
     (Language::Ruby, &["rb"]),
     (Language::Rust, &["rs"]),
     (Language::Swift, &["swift"]),
-    (Language::Terraform, &["tf"]),
+    (Language::Terraform, &["tf", "tfvars", "hcl", "nomad"]),
     (Language::TypeScript, &["ts", "tsx"]),
+    (Language::Xml, &["xml"]),
     (Language::Yaml, &["yml", "yaml"]),
 ];
 
-static FILE_EXACT_MATCH_PER_LANGUAGE_LIST: &[(Language, &[&str])] =
-    &[(Language::Dockerfile, &["Dockerfile"])];
+static FILE_EXACT_MATCH_PER_LANGUAGE_LIST: &[(Language, &[&str])] = &[
+    (Language::Dockerfile, &["Dockerfile"]),
+    (Language::Groovy, &["Jenkinsfile"]),
+];
 
 static FILE_PREFIX_PER_LANGUAGE_LIST: &[(Language, &[&str])] =
     &[(Language::Dockerfile, &["Dockerfile"])];
 
+/// Extensions of text files that aren't analyzed as a language, but that are commonly found in
+/// repositories and are eligible to be scanned.
+static TEXT_FILE_EXTENSIONS: &[&str] = &[
+    "adoc",
+    "asciidoc",
+    "cfg",
+    "conf",
+    "config",
+    "htm",
+    "html",
+    "ini",
+    "json",
+    "markdown",
+    "md",
+    "mdx",
+    "properties",
+    "sh",
+    "sql",
+    "toml",
+    "txt",
+];
+
+/// The number of leading bytes inspected to determine if a file is binary.
+const BINARY_SNIFF_LEN: u64 = 8192;
+
 // get all extensions for a language.
-fn get_extensions_for_language(language: &Language) -> Option<Vec<String>> {
+pub(crate) fn get_extensions_for_language(language: &Language) -> Option<Vec<String>> {
     for fe in FILE_EXTENSIONS_PER_LANGUAGE_LIST {
         if fe.0 == *language {
             let extensions = fe.1.to_vec();
@@ -69,42 +115,65 @@ fn get_prefix_for_language(language: &Language) -> Option<Vec<String>> {
     None
 }
 
-// Read the .gitignore file in a directory and return the lines that are not commented
-// or empty.
-// We ignore pattern that start with # (comments) or contains ! (cause repositories
-// not being included and totally skipped).
-pub fn read_files_from_gitignore_internal(path: &PathBuf) -> Result<Vec<String>> {
-    if path.exists() {
-        let lines: Vec<String> = read_to_string(path)?
-            .lines()
-            .map(String::from)
-            .filter(|v| !v.starts_with('#'))
-            .filter(|v| !v.contains('!'))
-            .filter(|v| !v.is_empty())
-            .collect();
-        return Ok(lines);
-    }
-    Ok(vec![])
+/// The result of walking the repository: the files to analyze and the files that were
+/// skipped along with the reason why.
+#[derive(Debug, Default)]
+pub struct WalkedFiles {
+    pub files: Vec<PathBuf>,
+    pub skipped: Vec<SkippedFile>,
 }
 
-pub fn read_files_from_gitignore(source_directory: &str) -> Result<Vec<String>> {
-    let gitignore_path = Path::new(source_directory).join(".gitignore");
-    read_files_from_gitignore_internal(&gitignore_path)
+/// Reads the `.editorconfig` files of the directories of the files, and of their parent directories
+/// up to `directory`. The files that cannot be read are ignored.
+pub fn read_editorconfig(directory: &Path, files: &[PathBuf]) -> EditorConfig {
+    // the directories are sorted, so that the files are always added in the same order
+    let mut directories = BTreeSet::new();
+    for file in files {
+        for parent in file.ancestors().skip(1) {
+            if !parent.starts_with(directory) || !directories.insert(parent) {
+                break;
+            }
+        }
+    }
+    let mut editorconfig = EditorConfig::new();
+    for parent in directories {
+        let Ok(content) = read_to_string(parent.join(".editorconfig")) else {
+            continue;
+        };
+        if let Some(relative_path) = parent.strip_prefix(directory).ok().and_then(|p| p.to_str()) {
+            editorconfig.add_file(relative_path, &content);
+        }
+    }
+    editorconfig
 }
 
 /// get the files to analyze from the directory. This function walks the directory
 /// to analyze recursively and gets all the files.
 /// if passed, subdirectories_to_analyze are subdirectories within the directory.
+/// Symbolic links are followed or skipped depending on `symlink_behavior`. When they are followed,
+/// links that resolve outside the repository or that create a cycle are skipped.
+/// The files and directories ignored by `gitignore` are not walked.
 pub fn get_files(
     directory: &str,
     subdirectories_to_analyze: Vec<String>,
     path_config: &PathConfig,
-) -> Result<Vec<PathBuf>> {
-    let mut files_to_return: Vec<PathBuf> = vec![];
+    gitignore: &GitIgnore,
+    symlink_behavior: SymlinkBehavior,
+) -> Result<WalkedFiles> {
+    let mut walked = WalkedFiles::default();
 
     // This is the directory that contains the .git files, we do not need to keep them.
     let git_directory = format!("{}/.git", &directory);
 
+    // Links are resolved against the canonical path of the repository so that we never read
+    // content outside the repository (an attacker could add a symlink outside the repo and
+    // read content outside of the repo with a custom rule).
+    let canonical_directory = Path::new(directory).canonicalize()?;
+    let follow_links = symlink_behavior == SymlinkBehavior::Follow;
+    // Canonical paths of the files already found, so that a file reachable through a link
+    // is only analyzed once.
+    let mut seen_files: HashSet<PathBuf> = HashSet::new();
+
     let directories_to_walk: Vec<String> = if !subdirectories_to_analyze.is_empty() {
         subdirectories_to_analyze
             .iter()
@@ -119,37 +188,157 @@ pub fn get_files(
     };
 
     for directory_to_walk in directories_to_walk {
-        for entry in WalkDir::new(directory_to_walk.as_str()) {
-            let dir_entry = entry?;
+        // Links are pruned before walkdir descends into them: the closure cannot borrow `walked`
+        // mutably while we iterate, so the links are collected separately.
+        let skipped_links: RefCell<Vec<(PathBuf, SkipReason)>> = RefCell::new(vec![]);
+        let walker = WalkDir::new(directory_to_walk.as_str())
+            .follow_links(follow_links)
+            .into_iter()
+            .filter_entry(|e| {
+                if e.depth() > 0
+                    && e.file_type().is_dir()
+                    && relative_path_str(e.path(), directory)
+                        .is_some_and(|path| gitignore.is_ignored(path, true))
+                {
+                    return false;
+                }
+                if e.depth() == 0
+                    || !e.path_is_symlink()
+                    || e.path().starts_with(git_directory.as_str())
+                {
+                    return true;
+                }
+                let reason = match symlink_behavior {
+                    SymlinkBehavior::Skip => SkipReason::Symlink,
+                    SymlinkBehavior::Follow => {
+                        let inside_repository = e
+                            .path()
+                            .canonicalize()
+                            .map(|p| p.starts_with(&canonical_directory))
+                            .unwrap_or(false);
+                        if inside_repository {
+                            return true;
+                        }
+                        SkipReason::SymlinkOutsideRepository
+                    }
+                };
+                skipped_links
+                    .borrow_mut()
+                    .push((e.path().to_path_buf(), reason));
+                false
+            });
+
+        for entry in walker {
+            let dir_entry = match entry {
+                Ok(dir_entry) => dir_entry,
+                Err(err) => match (err.loop_ancestor(), err.path()) {
+                    // walkdir reports a link to one of its ancestors as an error.
+                    (Some(_), Some(path)) => {
+                        skipped_links
+                            .borrow_mut()
+                            .push((path.to_path_buf(), SkipReason::SymlinkCycle));
+                        continue;
+                    }
+                    _ => return Err(err.into()),
+                },
+            };
             let entry = dir_entry.path();
 
-            // we only include if this is a file and not a symlink
-            // we should NEVER follow symlink for security reason (an attacker could then
-            // attempt to add a symlink outside the repo and read content outside of the
-            // repo with a custom rule.
-            let mut should_include = entry.is_file() && !entry.is_symlink();
-            let path_buf = entry.to_path_buf();
-
-            let relative_path_str = path_buf
-                .strip_prefix(directory)
-                .ok()
-                .and_then(|p| p.to_str())
-                .ok_or_else(|| anyhow::Error::msg("should get the path"))?;
+            // do not include the git directory.
+            if !entry.is_file() || entry.starts_with(git_directory.as_str()) {
+                continue;
+            }
 
-            // check if the path is allowed by the configuration.
-            should_include = should_include && path_config.allows_file(relative_path_str);
+            let Some(relative_path_str) = relative_path_str(entry, directory) else {
+                walked.skipped.push(SkippedFile::new(
+                    entry.to_string_lossy(),
+                    SkipReason::NonUtf8Path,
+                ));
+                continue;
+            };
+
+            // check if the path is allowed by the configuration and not ignored by git.
+            if !path_config.allows_file(relative_path_str)
+                || gitignore.is_ignored(relative_path_str, false)
+            {
+                continue;
+            }
 
-            // do not include the git directory.
-            if entry.starts_with(git_directory.as_str()) {
-                should_include = false;
+            if follow_links && !seen_files.insert(entry.canonicalize()?) {
+                continue;
             }
 
-            if should_include {
-                files_to_return.push(entry.to_path_buf());
+            walked.files.push(entry.to_path_buf());
+        }
+
+        for (path, reason) in skipped_links.into_inner() {
+            let relative_path = path
+                .strip_prefix(directory)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+            if path_config.allows_file(&relative_path)
+                && !gitignore.is_ignored(&relative_path, false)
+            {
+                walked.skipped.push(SkippedFile::new(relative_path, reason));
             }
         }
     }
-    Ok(files_to_return)
+    Ok(walked)
+}
+
+// get the path of a file relative to the repository, if it can be represented as a string.
+fn relative_path_str<'a>(path: &'a Path, directory: &str) -> Option<&'a str> {
+    path.strip_prefix(directory).ok().and_then(|p| p.to_str())
+}
+
+/// The size from which the content of a file is memory-mapped instead of read: mapping a small
+/// file costs more than reading it.
+const MMAP_MIN_BYTES: u64 = 64 * 1024;
+
+/// Read the content of a file to analyze. The content of files that are not valid UTF-8 is
+/// either decoded lossily (with a warning) or the file is skipped, depending on `non_utf8_behavior`.
+///
+/// The content is returned as an [`Arc<str>`], so that it is shared with the kernel instead of
+/// being copied for each rule. Large files are memory-mapped and validated in place, so that their
+/// content is only copied once into the [`Arc<str>`]; small files and files that are not regular
+/// files (e.g. named pipes) are read.
+pub fn read_file_content(
+    path: &Path,
+    non_utf8_behavior: NonUtf8Behavior,
+) -> std::result::Result<Arc<str>, SkipReason> {
+    let mut file = fs::File::open(path).map_err(|_| SkipReason::Unreadable)?;
+    let metadata = file.metadata().map_err(|_| SkipReason::Unreadable)?;
+    if !metadata.is_file() || metadata.len() < MMAP_MIN_BYTES {
+        let mut bytes = Vec::with_capacity(metadata.len() as usize);
+        file.read_to_end(&mut bytes)
+            .map_err(|_| SkipReason::Unreadable)?;
+        return decode_file_content(path, &bytes, non_utf8_behavior);
+    }
+    // SAFETY: the mapping is only read, and dropped once the content is copied. The files are
+    // not expected to be modified while they are analyzed.
+    let mmap = unsafe { Mmap::map(&file) }.map_err(|_| SkipReason::Unreadable)?;
+    decode_file_content(path, &mmap, non_utf8_behavior)
+}
+
+fn decode_file_content(
+    path: &Path,
+    bytes: &[u8],
+    non_utf8_behavior: NonUtf8Behavior,
+) -> std::result::Result<Arc<str>, SkipReason> {
+    match std::str::from_utf8(bytes) {
+        Ok(content) => Ok(Arc::from(content)),
+        Err(_) => match non_utf8_behavior {
+            NonUtf8Behavior::Skip => Err(SkipReason::NonUtf8),
+            NonUtf8Behavior::Lossy => {
+                eprintln!(
+                    "WARNING: file {} is not valid UTF-8, invalid sequences are replaced",
+                    path.display()
+                );
+                Ok(Arc::from(String::from_utf8_lossy(bytes)))
+            }
+        },
+    }
 }
 
 /// try to find if one of the subdirectory used to scan a repository is going outside the
@@ -205,19 +394,156 @@ fn match_prefix_filename(path: &Path, prefixes_list: &[String]) -> bool {
     }
 }
 
+/// Decides which files are eligible to be analyzed. A file is eligible if:
+///  - it is not binary (its first bytes don't contain a NUL byte)
+///  - it is not larger than `max_file_size_kb`
+///  - it has no extension, or its extension is used by a language or a common text format
+///
+/// The secrets scanner only applies the first two conditions (see [`partition_secrets_files`]).
+#[derive(Debug, Clone)]
+pub struct FilePolicy {
+    max_file_size_bytes: u64,
+    extensions: HashSet<String>,
+    filenames: HashSet<String>,
+}
+
+impl FilePolicy {
+    pub fn new(max_file_size_kb: u64) -> Self {
+        let extensions = FILE_EXTENSIONS_PER_LANGUAGE_LIST
+            .iter()
+            .flat_map(|(_, extensions)| extensions.iter())
+            .chain(SFC_EXTENSIONS)
+            .chain(TEXT_FILE_EXTENSIONS)
+            .map(|e| e.to_string())
+            .collect();
+        Self {
+            max_file_size_bytes: max_file_size_kb * 1024,
+            extensions,
+            filenames: HashSet::new(),
+        }
+    }
+
+    /// Makes the files mapped to a language by the configuration file eligible.
+    pub fn with_file_languages(mut self, file_languages: &FileLanguagesConfig) -> Self {
+        self.extensions.extend(
+            file_languages
+                .extensions
+                .keys()
+                .map(|extension| extension.to_lowercase()),
+        );
+        self.filenames
+            .extend(file_languages.filenames.keys().cloned());
+        self
+    }
+
+    /// Returns `Ok` if the file is eligible, or the reason why it is not.
+    pub fn check(&self, path: &Path) -> std::result::Result<(), SkipReason> {
+        let metadata = fs::metadata(path).map_err(|_| SkipReason::Unreadable)?;
+        if is_binary_file(path).map_err(|_| SkipReason::Unreadable)? {
+            return Err(SkipReason::Binary);
+        }
+        if metadata.len() > self.max_file_size_bytes {
+            return Err(SkipReason::TooLarge);
+        }
+        if !self.has_eligible_extension(path) {
+            return Err(SkipReason::UnsupportedExtension);
+        }
+        Ok(())
+    }
+
+    /// Splits the files into the eligible files and the ineligible files, along with the reason
+    /// why they are not eligible.
+    pub fn partition(&self, files: &[PathBuf]) -> (Vec<PathBuf>, Vec<(PathBuf, SkipReason)>) {
+        let mut eligible = vec![];
+        let mut ineligible = vec![];
+        for file in files {
+            match self.check(file) {
+                Ok(()) => eligible.push(file.clone()),
+                Err(reason) => ineligible.push((file.clone(), reason)),
+            }
+        }
+        (eligible, ineligible)
+    }
+
+    fn has_eligible_extension(&self, path: &Path) -> bool {
+        let Some(file_name) = path.file_name().and_then(|f| f.to_str()) else {
+            return false;
+        };
+        let is_language_file = FILE_EXACT_MATCH_PER_LANGUAGE_LIST
+            .iter()
+            .chain(FILE_PREFIX_PER_LANGUAGE_LIST)
+            .flat_map(|(_, names)| names.iter())
+            .any(|name| file_name.starts_with(name))
+            || self.filenames.contains(file_name);
+        // Dotfiles like `.env` are treated as having an extension.
+        match file_name.rsplit_once('.') {
+            Some((_, extension)) => {
+                is_language_file || self.extensions.contains(&extension.to_lowercase())
+            }
+            None => true,
+        }
+    }
+}
+
+/// Splits the files to scan for secrets into the files scanned by all the rules and the files
+/// only scanned by the rules that opt into ineligible files, given the partition of the files by
+/// the [`FilePolicy`]. Secrets can be in any text file (e.g. `.env` or `.pem` files), so the files
+/// with an unsupported extension are scanned by all the rules.
+pub fn partition_secrets_files(
+    eligible: &[PathBuf],
+    ineligible: &[(PathBuf, SkipReason)],
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut files = eligible.to_vec();
+    let mut overridable = vec![];
+    for (path, reason) in ineligible {
+        if *reason == SkipReason::UnsupportedExtension {
+            files.push(path.clone());
+        } else if reason.is_overridable() {
+            overridable.push(path.clone());
+        }
+    }
+    (files, overridable)
+}
+
+/// Returns true if the first bytes of the file contain a NUL byte.
+fn is_binary_file(path: &Path) -> std::io::Result<bool> {
+    let mut buffer = Vec::new();
+    fs::File::open(path)?
+        .take(BINARY_SNIFF_LEN)
+        .read_to_end(&mut buffer)?;
+    Ok(buffer.contains(&0))
+}
+
 // filter files to analyze for a language. It will filter the files based on the prefix or suffix.
-pub fn filter_files_for_language(files: &[PathBuf], language: &Language) -> Vec<PathBuf> {
-    let extensions = get_extensions_for_language(language).unwrap_or_default();
+// Single-file components (Vue, Svelte) embed JavaScript or TypeScript and are analyzed for both languages.
+// A file mapped to a language by `file_languages` is only analyzed for that language.
+pub fn filter_files_for_language(
+    files: &[PathBuf],
+    language: &Language,
+    file_languages: &FileLanguagesConfig,
+) -> Vec<PathBuf> {
+    let mut extensions = get_extensions_for_language(language).unwrap_or_default();
+    if matches!(language, Language::JavaScript | Language::TypeScript) {
+        extensions.extend(SFC_EXTENSIONS.iter().map(|e| e.to_string()));
+    }
     let exact_matches = get_exact_filename_for_language(language).unwrap_or_default();
     let prefixes = get_prefix_for_language(language).unwrap_or_default();
 
-    if extensions.is_empty() && exact_matches.is_empty() && prefixes.is_empty() {
+    if extensions.is_empty()
+        && exact_matches.is_empty()
+        && prefixes.is_empty()
+        && !file_languages.extensions.values().any(|l| l == language)
+        && !file_languages.filenames.values().any(|l| l == language)
+    {
         return vec![];
     }
 
     let result = files
         .iter()
         .filter(|p| {
+            if let Some(mapped_language) = file_languages.language_for(p) {
+                return mapped_language == *language;
+            }
             let extension_match = match_extension(p, &extensions);
             let filename_match = match_exact_filename(p, &exact_matches);
             let prefix_match = match_prefix_filename(p, &prefixes);
@@ -229,33 +555,6 @@ pub fn filter_files_for_language(files: &[PathBuf], language: &Language) -> Vec<
     result
 }
 
-pub fn filter_files_by_size(files: &[PathBuf], configuration: &CliConfiguration) -> Vec<PathBuf> {
-    let max_len_bytes = configuration.max_file_size_kb * 1024;
-    return files
-        .iter()
-        .filter(|f| {
-            let metadata = fs::metadata(f);
-            let too_big = metadata
-                .as_ref()
-                .map(|x| x.len() > max_len_bytes)
-                .unwrap_or(false);
-
-            if configuration.use_debug && too_big {
-                eprintln!(
-                    "File {} too big (size {} bytes, max size {} kb ({} bytes))",
-                    f.display(),
-                    &metadata.map(|x| x.len()).unwrap_or(0),
-                    configuration.max_file_size_kb,
-                    max_len_bytes
-                )
-            }
-
-            f.is_file() && !too_big
-        })
-        .cloned()
-        .collect();
-}
-
 /// Filter the files to scan for diff-aware scanning.
 ///  - files is the list of files we should scan (full path on disk)
 ///  - directory_path is the path of the directory
@@ -286,9 +585,291 @@ pub fn filter_files_by_diff_aware_info(
         .collect();
 }
 
-/// Generate a fingerprint for a violation that will uniquely identify the violation. The fingerprint is calculated
-/// as is
-///  SHA2(<file-location-in-repository> - <characters-in-directory> - <content-of-code-line> - <number of characters in line>)
+/// Why a file of the repository is not analyzed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileExclusion {
+    /// The file was skipped when walking the repository or by the file policy.
+    Skipped(SkipReason),
+    /// The file is ignored by git (`.gitignore` files, `.git/info/exclude` or the global
+    /// excludes file).
+    Gitignore,
+    /// The file is not allowed by the `only` and `ignore` paths.
+    Paths,
+    /// No rule is enabled for the language of the file.
+    NoRules,
+    /// The file is excluded by the paths of all the rulesets for its language.
+    RulesetPaths,
+    /// The file is generated and generated files are ignored.
+    Generated,
+}
+
+impl fmt::Display for FileExclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Skipped(reason) => write!(f, "{reason}"),
+            Self::Gitignore => write!(f, "ignored by git"),
+            Self::Paths => write!(f, "excluded by the only/ignore paths"),
+            Self::NoRules => write!(f, "no rule for the language of the file"),
+            Self::RulesetPaths => write!(f, "excluded by the paths of the rulesets"),
+            Self::Generated => write!(f, "generated file"),
+        }
+    }
+}
+
+/// The outcome of the file selection for a file of the repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSelection {
+    /// The path of the file, relative to the repository.
+    pub path: String,
+    /// The languages the file is analyzed as, with the rulesets that apply to the file.
+    pub languages: Vec<(Language, Vec<String>)>,
+    /// Why the file is not analyzed, if it is not.
+    pub exclusion: Option<FileExclusion>,
+}
+
+impl fmt::Display for FileSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(exclusion) = &self.exclusion {
+            return write!(f, "{}: excluded ({})", self.path, exclusion);
+        }
+        let languages = self
+            .languages
+            .iter()
+            .map(|(language, rulesets)| format!("{} ({})", language, rulesets.join(", ")))
+            .collect::<Vec<_>>();
+        write!(f, "{}: {}", self.path, languages.join("; "))
+    }
+}
+
+/// Runs the discovery phase of the analysis without analyzing any file: returns every file of
+/// the repository with the languages and rulesets it would be analyzed with, or the reason why
+/// it would not be analyzed. The files are sorted by path.
+pub fn select_files(
+    configuration: &CliConfiguration,
+    gitignore: &GitIgnore,
+) -> Result<Vec<FileSelection>> {
+    let directory = configuration.source_directory.as_str();
+    // All the files are walked, so that the files that are ignored are also listed.
+    let walked = get_files(
+        directory,
+        configuration.source_subdirectories.clone(),
+        &PathConfig::default(),
+        &GitIgnore::default(),
+        configuration.symlink_behavior,
+    )?;
+    let file_policy = FilePolicy::new(configuration.max_file_size_kb)
+        .with_file_languages(&configuration.file_languages);
+
+    let mut selections: Vec<FileSelection> = walked
+        .skipped
+        .into_iter()
+        .map(|skipped| {
+            let exclusion = if gitignore.is_ignored(&skipped.path, false) {
+                FileExclusion::Gitignore
+            } else if !configuration.path_config.allows_file(&skipped.path) {
+                FileExclusion::Paths
+            } else {
+                FileExclusion::Skipped(skipped.reason)
+            };
+            FileSelection {
+                path: skipped.path,
+                languages: vec![],
+                exclusion: Some(exclusion),
+            }
+        })
+        .collect();
+    for file in walked.files {
+        let path = relative_path_str(&file, directory)
+            .unwrap_or_default()
+            .to_string();
+        let (languages, exclusion) =
+            select_file(configuration, &file, &path, gitignore, &file_policy);
+        selections.push(FileSelection {
+            path,
+            languages,
+            exclusion,
+        });
+    }
+    selections.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(selections)
+}
+
+type LanguageRulesets = Vec<(Language, Vec<String>)>;
+
+// select the languages and rulesets for a file, in the same order as the analysis filters files.
+fn select_file(
+    configuration: &CliConfiguration,
+    file: &Path,
+    path: &str,
+    gitignore: &GitIgnore,
+    file_policy: &FilePolicy,
+) -> (LanguageRulesets, Option<FileExclusion>) {
+    if gitignore.is_ignored(path, false) {
+        return (vec![], Some(FileExclusion::Gitignore));
+    }
+    if !configuration.path_config.allows_file(path) {
+        return (vec![], Some(FileExclusion::Paths));
+    }
+    if let Err(reason) = file_policy.check(file) {
+        return (vec![], Some(FileExclusion::Skipped(reason)));
+    }
+
+    let mut languages: Vec<Language> = configuration
+        .rules
+        .iter()
+        .map(|rule| rule.language)
+        .filter(|language| {
+            !filter_files_for_language(
+                &[file.to_path_buf()],
+                language,
+                &configuration.file_languages,
+            )
+            .is_empty()
+        })
+        .collect();
+    languages.sort_by_key(|language| language.to_string());
+    languages.dedup();
+    if languages.is_empty() {
+        return (vec![], Some(FileExclusion::NoRules));
+    }
+
+    let mut selected: Vec<(Language, Vec<&Rule>)> = languages
+        .into_iter()
+        .map(|language| {
+            let rules = configuration
+                .rules
+                .iter()
+                .filter(|rule| {
+                    rule.language == language
+                        && configuration
+                            .path_restrictions
+                            .rule_applies(&rule.name, path)
+                })
+                .collect::<Vec<_>>();
+            (language, rules)
+        })
+        .filter(|(_, rules)| !rules.is_empty())
+        .collect();
+    if selected.is_empty() {
+        return (vec![], Some(FileExclusion::RulesetPaths));
+    }
+
+    // a generated file is only analyzed by the rules that opt into generated files.
+    if configuration.ignore_generated_files {
+        if let Ok(code) = fs::read_to_string(file) {
+            for (language, rules) in &mut selected {
+                if is_generated_file(&code, language) {
+                    rules.retain(|rule| configuration.generated_files_rules.contains(&rule.name));
+                }
+            }
+            selected.retain(|(_, rules)| !rules.is_empty());
+        }
+        if selected.is_empty() {
+            return (vec![], Some(FileExclusion::Generated));
+        }
+    }
+
+    let selected = selected
+        .into_iter()
+        .map(|(language, rules)| {
+            let mut rulesets = rules
+                .into_iter()
+                .map(|rule| match rule.name.split_once('/') {
+                    Some((ruleset, _)) => ruleset.to_string(),
+                    None => rule.name.clone(),
+                })
+                .collect::<Vec<_>>();
+            rulesets.sort();
+            rulesets.dedup();
+            (language, rulesets)
+        })
+        .collect();
+    (selected, None)
+}
+
+/// A check of the file selection for a rule: the file is only analyzed with the rule if all the
+/// checks pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionCheck {
+    pub description: String,
+    pub passed: bool,
+}
+
+impl SelectionCheck {
+    fn new(description: impl Into<String>, passed: bool) -> Self {
+        Self {
+            description: description.into(),
+            passed,
+        }
+    }
+}
+
+/// Returns the checks that decide if a file of the repository in `directory` is analyzed with a
+/// rule, in the same order as the analysis filters files. `path` is relative to `directory`,
+/// `code` is the content of the file and `config` the configuration file of the repository.
+pub fn check_file_selection(
+    directory: &Path,
+    path: &str,
+    code: &str,
+    rule: &Rule,
+    config: Option<&ConfigFile>,
+) -> Result<Vec<SelectionCheck>> {
+    let file = directory.join(path);
+    let file_languages = config.map(|c| c.file_languages.clone()).unwrap_or_default();
+    let mut checks = vec![SelectionCheck::new(
+        format!("the file is a {} file", rule.language),
+        !filter_files_for_language(std::slice::from_ref(&file), &rule.language, &file_languages)
+            .is_empty(),
+    )];
+    let max_file_size_kb = config
+        .and_then(|c| c.max_file_size_kb)
+        .unwrap_or(DEFAULT_MAX_FILE_SIZE_KB);
+    let file_policy = FilePolicy::new(max_file_size_kb).with_file_languages(&file_languages);
+    checks.push(match file_policy.check(&file) {
+        Ok(()) => SelectionCheck::new("the file is eligible for analysis", true),
+        Err(reason) => SelectionCheck::new(
+            format!("the file is eligible for analysis ({reason})"),
+            false,
+        ),
+    });
+    if let Some(config) = config {
+        checks.push(SelectionCheck::new(
+            "the file is allowed by the `only` and `ignore` paths of the configuration file",
+            config.paths.allows_file(path),
+        ));
+        checks.push(SelectionCheck::new(
+            "the rule applies to the file (paths of its ruleset and rule configuration)",
+            PathRestrictions::from_ruleset_configs(&config.rulesets).rule_applies(&rule.name, path),
+        ));
+    }
+    if !config.and_then(|c| c.ignore_gitignore).unwrap_or(false) {
+        let gitignore = GitIgnore::from_repository(directory, true)?;
+        checks.push(SelectionCheck::new(
+            "the file is not ignored by git",
+            !gitignore.is_ignored(path, false),
+        ));
+    }
+    let analyzes_generated_files = config
+        .is_some_and(|c| RuleOverrides::from_config_file(c).analyzes_generated_files(&rule.name));
+    if analyzes_generated_files {
+        checks.push(SelectionCheck::new(
+            "the rule analyzes generated files",
+            true,
+        ));
+    } else if config
+        .and_then(|c| c.ignore_generated_files)
+        .unwrap_or(true)
+    {
+        checks.push(SelectionCheck::new(
+            "the file is not generated",
+            !is_generated_file(code, &rule.language),
+        ));
+    }
+    Ok(checks)
+}
+
+/// Generate a fingerprint for a violation that will uniquely identify the violation, from the content
+/// of the file on disk. See [`compute_fingerprint`] for the algorithm.
 pub fn get_fingerprint_for_violation(
     rule_name: String,
     violation: &Violation,
@@ -304,25 +885,10 @@ pub fn get_fingerprint_for_violation(
     let line = violation.start.line as usize;
 
     match read_to_string(&path) {
-        Ok(file_content) => match file_content.lines().nth(line - 1) {
-            Some(line_content) => {
-                let line_content_stripped = line_content
-                    .chars()
-                    .filter(|ch| !ch.is_whitespace())
-                    .collect::<String>();
-                let hash_content = format!(
-                    "{}|{}|{}|{}|{}",
-                    rule_name,
-                    filename,
-                    filename.len(),
-                    line_content_stripped,
-                    line_content_stripped.len()
-                );
-                let hash = format!("{:x}", Sha256::digest(hash_content.as_bytes()));
-                Some(hash)
-            }
-            None => None,
-        },
+        Ok(file_content) => file_content
+            .lines()
+            .nth(line - 1)
+            .map(|line_content| compute_fingerprint(&rule_name, filename, line_content)),
         Err(_) => {
             if use_debug {
                 eprintln!(
@@ -344,44 +910,35 @@ mod tests {
     use kernel::arguments::ArgumentProvider;
     use tempfile::{tempdir, TempDir};
 
-    use kernel::model::common::OutputFormat::Sarif;
     use kernel::model::common::Position;
-    use kernel::model::rule::{RuleCategory, RuleSeverity};
+    use kernel::model::config_file::RulesetConfig;
+    use kernel::model::rule::{RuleBuilder, RuleCategory, RuleSeverity, RuleType};
     use kernel::path_restrictions::PathRestrictions;
+    use kernel::plugin::PluginRegistry;
 
     use super::*;
 
-    #[test]
-    fn get_gitignore_exists() {
-        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        d.push("resources/test/gitignore/test1");
-        let file_list = read_files_from_gitignore_internal(&d);
-        assert!(file_list.is_ok());
-        let fl = file_list.unwrap();
-        assert_eq!(85, fl.len());
-        // check it contains the values of the file
-        assert!(fl.contains(&"ddtrace/appsec/_ddwaf.cpp".to_string()));
-        // make sure it does not contains lines with !
-        assert!(!fl.contains(&"!.env".to_string()));
-        assert_eq!(
-            0,
-            fl.iter()
-                .filter(|v| v.starts_with("#"))
-                .collect::<Vec<&String>>()
-                .len()
-        )
-    }
-
     #[test]
     fn get_fingerprint_for_violation_success() {
         let d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         let violation = Violation {
             start: Position { line: 10, col: 1 },
             end: Position { line: 12, col: 1 },
-            message: "something bad happened".to_string(),
+            message: "something bad happened".into(),
             severity: RuleSeverity::Notice,
             category: RuleCategory::Performance,
             fixes: vec![],
+            merged_rules: vec![],
+            tags: vec![],
+            cwe: vec![],
+            documentation_url: None,
+            related_locations: vec![],
+            file_level: false,
+            fingerprint: None,
+            owners: vec![],
+            snippet: None,
+            offsets: None,
+            last_commit: None,
         };
         let directory_string = d.into_os_string().into_string().unwrap();
         let fingerprint = get_fingerprint_for_violation(
@@ -417,10 +974,21 @@ mod tests {
         let violation = Violation {
             start: Position { line: 10, col: 1 },
             end: Position { line: 12, col: 1 },
-            message: "something bad happened".to_string(),
+            message: "something bad happened".into(),
             severity: RuleSeverity::Notice,
             category: RuleCategory::Performance,
             fixes: vec![],
+            merged_rules: vec![],
+            tags: vec![],
+            cwe: vec![],
+            documentation_url: None,
+            related_locations: vec![],
+            file_level: false,
+            fingerprint: None,
+            owners: vec![],
+            snippet: None,
+            offsets: None,
+            last_commit: None,
         };
         let directory_string = d.into_os_string().into_string().unwrap();
 
@@ -470,43 +1038,109 @@ mod tests {
         fs::remove_dir(plop_dir).expect("cannot remove dir")
     }
 
-    /// Filter files bigger than one kilobyte and make sure files
-    /// less than one kilobyte are not being filtered.
+    /// Binary files, large files and files with an unsupported extension are not eligible.
     #[test]
-    fn test_filter_files_by_size() {
-        let mut files1 = vec![];
-        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        d.push("resources/test/test_files_by_size/versions.json");
-        files1.push(d);
-        let cli_configuration = CliConfiguration {
-            use_debug: true,
-            use_configuration_file: true,
-            ignore_gitignore: true,
-            source_directory: "bla".to_string(),
-            source_subdirectories: vec![],
-            path_config: PathConfig::default(),
-            rules_file: None,
-            output_format: Sarif, // SARIF or JSON
-            output_file: "foo".to_string(),
-            num_cpus: 2, // of cpus to use for parallelism
-            rules: vec![],
-            path_restrictions: PathRestrictions::default(),
-            argument_provider: ArgumentProvider::new(),
-            max_file_size_kb: 1,
-            use_staging: false,
-            show_performance_statistics: false,
-            scan_for_secrets: false,
-            validate_secrets: false,
-            secrets_rule_file: None,
-            ignore_generated_files: false,
+    fn file_policy_check() {
+        let dir = tempdir().unwrap();
+        let write = |name: &str, content: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            path
         };
-        assert_eq!(0, filter_files_by_size(&files1, &cli_configuration).len());
+        let policy = FilePolicy::new(1);
 
-        let mut files2 = vec![];
-        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        d.push("resources/test/test_files_by_size/versions-empty.json");
-        files2.push(d);
-        assert_eq!(1, filter_files_by_size(&files2, &cli_configuration).len());
+        assert_eq!(policy.check(&write("main.py", b"print(1)")), Ok(()));
+        assert_eq!(policy.check(&write("Dockerfile.prod", b"FROM x")), Ok(()));
+        assert_eq!(policy.check(&write("Makefile", b"all:")), Ok(()));
+        assert_eq!(policy.check(&write("app.properties", b"a=b")), Ok(()));
+        assert_eq!(
+            policy.check(&write("image.py", b"\x89PNG\x00\x00")),
+            Err(SkipReason::Binary)
+        );
+        assert_eq!(
+            policy.check(&write("big.json", &[b' '; 2048])),
+            Err(SkipReason::TooLarge)
+        );
+        assert_eq!(
+            policy.check(&write("key.pem", b"-----BEGIN")),
+            Err(SkipReason::UnsupportedExtension)
+        );
+        assert_eq!(
+            policy.check(&write(".env", b"TOKEN=abc")),
+            Err(SkipReason::UnsupportedExtension)
+        );
+        assert_eq!(
+            policy.check(&dir.path().join("missing.py")),
+            Err(SkipReason::Unreadable)
+        );
+    }
+
+    /// The files with an unsupported extension are scanned for secrets by all the rules, and the
+    /// files that are too large only by the rules that opt into them.
+    #[test]
+    fn partition_secrets_files_extensions() {
+        let dir = tempdir().unwrap();
+        let write = |name: &str, content: &[u8]| {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            path
+        };
+        let files = vec![
+            write("main.py", b"print(1)"),
+            write(".env", b"TOKEN=abc_018cf028"),
+            write("key.pem", b"-----BEGIN abc_018cf028"),
+            write("big.json", &[b' '; 2048]),
+            write("image.png", b"\x89PNG\x00\x00"),
+        ];
+        let (eligible, ineligible) = FilePolicy::new(1).partition(&files);
+        assert_eq!(eligible, files[..1].to_vec());
+
+        let (secrets_files, overridable) = partition_secrets_files(&eligible, &ineligible);
+        assert_eq!(secrets_files, files[..3].to_vec());
+        assert_eq!(overridable, files[3..4].to_vec());
+    }
+
+    /// The secrets in `.env` and `.pem` files are detected, even though these files are not
+    /// eligible for the static analysis.
+    #[cfg(feature = "secrets")]
+    #[test]
+    fn secrets_detected_in_unsupported_extensions() {
+        let rule = "\
+schema-version: v1
+id: rule-one
+matcher:
+  hyperscan:
+    pattern: abc_[[:xdigit:]]{8}
+validator:
+  http:
+    extension: simple-request
+    config:
+      request:
+        url: http://localhost/
+        method: GET
+      response-handler:
+        handler-list:
+        default-result:
+          secret: INCONCLUSIVE
+          severity: NOTICE
+";
+        let scanner = secrets::ScannerBuilder::new()
+            .yaml_string(rule)
+            .try_build()
+            .unwrap();
+        let dir = tempdir().unwrap();
+        let env_file = dir.path().join(".env");
+        let pem_file = dir.path().join("key.pem");
+        fs::write(&env_file, "TOKEN=abc_018cf028\n").unwrap();
+        fs::write(&pem_file, "-----BEGIN abc_018cf028\n").unwrap();
+
+        let (eligible, ineligible) = FilePolicy::new(1).partition(&[env_file, pem_file]);
+        let (secrets_files, _) = partition_secrets_files(&eligible, &ineligible);
+        assert_eq!(secrets_files.len(), 2);
+        for file in &secrets_files {
+            let candidates = scanner.scan_file(file).unwrap();
+            assert_eq!(candidates.len(), 1, "{}", file.display());
+        }
     }
 
     /// Filter files based on diff-aware returned files
@@ -531,16 +1165,6 @@ mod tests {
         );
     }
 
-    #[test]
-    fn get_gitignore_do_not_exists() {
-        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-        d.push("resources/test/gitignore/test-do-not-exists");
-        let file_list = read_files_from_gitignore_internal(&d);
-        assert!(file_list.is_ok());
-        let fl = file_list.unwrap();
-        assert!(fl.is_empty());
-    }
-
     struct TestDir {
         dir: TempDir,
     }
@@ -563,6 +1187,15 @@ mod tests {
             }
             fs::File::create(full_path).unwrap();
         }
+
+        #[cfg(unix)]
+        fn add_symlink(&self, original: impl AsRef<Path>, link: &str) {
+            let full_path = self.dir.path().join(link);
+            if let Some(dir) = full_path.parent() {
+                fs::create_dir_all(dir).unwrap();
+            }
+            std::os::unix::fs::symlink(original, full_path).unwrap();
+        }
     }
 
     macro_rules! assert_contains_files {
@@ -608,7 +1241,15 @@ mod tests {
 
         // first, we get the list of files without any path to ignore
         let empty_config = PathConfig::default();
-        let files = get_files(&base_path, vec![], &empty_config).unwrap();
+        let files = get_files(
+            &base_path,
+            vec![],
+            &empty_config,
+            &GitIgnore::default(),
+            SymlinkBehavior::Skip,
+        )
+        .unwrap()
+        .files;
         assert_contains_files!(
             &base_path,
             files,
@@ -626,7 +1267,15 @@ mod tests {
             ignore: vec!["src/**/main.rs".to_string().into()],
             only: None,
         };
-        let files = get_files(&base_path, vec![], &path_config).unwrap();
+        let files = get_files(
+            &base_path,
+            vec![],
+            &path_config,
+            &GitIgnore::default(),
+            SymlinkBehavior::Skip,
+        )
+        .unwrap()
+        .files;
         assert_contains_files!(
             &base_path,
             files,
@@ -644,7 +1293,15 @@ mod tests {
             ignore: vec!["src/a".to_string().into()],
             only: None,
         };
-        let files = get_files(&base_path, vec![], &path_config).unwrap();
+        let files = get_files(
+            &base_path,
+            vec![],
+            &path_config,
+            &GitIgnore::default(),
+            SymlinkBehavior::Skip,
+        )
+        .unwrap()
+        .files;
         assert_contains_files!(&base_path, files, ["src/b/main.rs", "test/a/main.rs",]);
         assert_not_contains_files!(&base_path, files, ["src/a/main.rs", "src/a/other.rs"]);
 
@@ -653,7 +1310,15 @@ mod tests {
             ignore: vec![],
             only: Some(vec!["**/other.rs".to_string().into()]),
         };
-        let files = get_files(&base_path, vec![], &path_config).unwrap();
+        let files = get_files(
+            &base_path,
+            vec![],
+            &path_config,
+            &GitIgnore::default(),
+            SymlinkBehavior::Skip,
+        )
+        .unwrap()
+        .files;
         assert_contains_files!(&base_path, files, ["src/a/other.rs", "test/a/other.rs"]);
         assert_not_contains_files!(&base_path, files, ["src/a/main.rs", "test/a/main.rs"]);
 
@@ -662,7 +1327,15 @@ mod tests {
             ignore: vec![],
             only: Some(vec!["src/a".to_string().into()]),
         };
-        let files = get_files(&base_path, vec![], &path_config).unwrap();
+        let files = get_files(
+            &base_path,
+            vec![],
+            &path_config,
+            &GitIgnore::default(),
+            SymlinkBehavior::Skip,
+        )
+        .unwrap()
+        .files;
         assert_contains_files!(&base_path, files, ["src/a/main.rs", "src/a/other.rs"]);
         assert_not_contains_files!(&base_path, files, ["src/b/main.rs", "test/a/main.rs"]);
     }
@@ -677,9 +1350,115 @@ mod tests {
             current_path.display().to_string().as_str(),
             vec![subdirectory.into_os_string().into_string().unwrap()],
             &PathConfig::default(),
+            &GitIgnore::default(),
+            SymlinkBehavior::Skip,
         );
 
-        assert_eq!(2, files.unwrap().len());
+        assert_eq!(2, files.unwrap().files.len());
+    }
+
+    // symbolic links are not followed by default and are reported as skipped.
+    #[cfg(unix)]
+    #[test]
+    fn get_files_skip_symlinks() {
+        let test_dir = TestDir::new();
+        test_dir.add_file("src/main.rs");
+        test_dir.add_symlink(test_dir.dir.path().join("src"), "linked");
+        let base_path = test_dir.base_path();
+
+        let walked = get_files(
+            &base_path,
+            vec![],
+            &PathConfig::default(),
+            &GitIgnore::default(),
+            SymlinkBehavior::Skip,
+        )
+        .unwrap();
+        assert_contains_files!(&base_path, walked.files, ["src/main.rs"]);
+        assert_not_contains_files!(&base_path, walked.files, ["linked/main.rs"]);
+        assert_eq!(
+            walked.skipped,
+            vec![SkippedFile::new("linked", SkipReason::Symlink)]
+        );
+    }
+
+    // when following symbolic links, links outside the repository and cycles are skipped,
+    // and files reachable through several paths are only reported once.
+    #[cfg(unix)]
+    #[test]
+    fn get_files_follow_symlinks() {
+        let outside_dir = TestDir::new();
+        outside_dir.add_file("secret.rs");
+
+        let test_dir = TestDir::new();
+        test_dir.add_file("src/main.rs");
+        test_dir.add_file("other/lib.rs");
+        test_dir.add_symlink(test_dir.dir.path().join("other"), "src/other");
+        test_dir.add_symlink(outside_dir.dir.path(), "outside");
+        test_dir.add_symlink(test_dir.dir.path().join("src"), "src/cycle");
+        let base_path = test_dir.base_path();
+
+        let walked = get_files(
+            &base_path,
+            vec![],
+            &PathConfig::default(),
+            &GitIgnore::default(),
+            SymlinkBehavior::Follow,
+        )
+        .unwrap();
+        assert_contains_files!(&base_path, walked.files, ["src/main.rs"]);
+        assert_not_contains_files!(&base_path, walked.files, ["outside/secret.rs"]);
+        assert_eq!(2, walked.files.len());
+
+        let skipped: HashSet<SkippedFile> = HashSet::from_iter(walked.skipped);
+        assert!(skipped.contains(&SkippedFile::new(
+            "outside",
+            SkipReason::SymlinkOutsideRepository
+        )));
+        assert!(skipped.contains(&SkippedFile::new("src/cycle", SkipReason::SymlinkCycle)));
+    }
+
+    #[test]
+    fn read_file_content_non_utf8() {
+        let test_dir = TestDir::new();
+        let path = test_dir.dir.path().join("latin1.py");
+        fs::write(&path, b"name = 'caf\xe9'\n").unwrap();
+
+        assert_eq!(
+            read_file_content(&path, NonUtf8Behavior::Skip),
+            Err(SkipReason::NonUtf8)
+        );
+        assert_eq!(
+            read_file_content(&path, NonUtf8Behavior::Lossy),
+            Ok(Arc::from("name = 'caf\u{FFFD}'\n"))
+        );
+        // a large file is mapped instead of read
+        let large = test_dir.dir.path().join("large.py");
+        let mut content = "a = 1\n"
+            .repeat(MMAP_MIN_BYTES as usize / 6 + 1)
+            .into_bytes();
+        content.extend_from_slice(b"name = 'caf\xe9'\n");
+        fs::write(&large, &content).unwrap();
+        assert_eq!(
+            read_file_content(&large, NonUtf8Behavior::Skip),
+            Err(SkipReason::NonUtf8)
+        );
+        let lossy = read_file_content(&large, NonUtf8Behavior::Lossy).unwrap();
+        assert!(lossy.starts_with("a = 1\n"));
+        assert!(lossy.ends_with("name = 'caf\u{FFFD}'\n"));
+        let empty = test_dir.dir.path().join("empty.py");
+        fs::write(&empty, b"").unwrap();
+        assert_eq!(
+            read_file_content(&empty, NonUtf8Behavior::Skip),
+            Ok(Arc::from(""))
+        );
+        assert_eq!(
+            read_file_content(
+                &test_dir.dir.path().join("missing.py"),
+                NonUtf8Behavior::Lossy
+            ),
+            Err(SkipReason::Unreadable)
+        );
     }
 
     // check that we have the correct number of extensions for each language we support.
@@ -692,6 +1471,11 @@ mod tests {
         extensions_per_languages.insert(Language::TypeScript, 2);
         extensions_per_languages.insert(Language::Dockerfile, 2);
         extensions_per_languages.insert(Language::Yaml, 2);
+        extensions_per_languages.insert(Language::Groovy, 2);
+        extensions_per_languages.insert(Language::ObjectiveC, 2);
+        extensions_per_languages.insert(Language::Proto, 1);
+        extensions_per_languages.insert(Language::GraphQL, 2);
+        extensions_per_languages.insert(Language::Xml, 1);
 
         for (l, e) in extensions_per_languages {
             assert_eq!(
@@ -711,19 +1495,31 @@ mod tests {
             current_path.display().to_string().as_str(),
             vec![],
             &PathConfig::default(),
+            &GitIgnore::default(),
+            SymlinkBehavior::Skip,
         );
         assert!(files.is_ok());
-        let files = &files.unwrap();
+        let files = &files.unwrap().files;
         assert_eq!(
             0,
-            filter_files_for_language(files, &Language::TypeScript).len()
+            filter_files_for_language(
+                files,
+                &Language::TypeScript,
+                &FileLanguagesConfig::default()
+            )
+            .len()
+        );
+        assert_ne!(
+            0,
+            filter_files_for_language(files, &Language::Rust, &FileLanguagesConfig::default())
+                .len()
         );
-        assert_ne!(0, filter_files_for_language(files, &Language::Rust).len());
         assert_eq!(
             1,
             filter_files_for_language(
                 &[PathBuf::from("path").join(PathBuf::from("foobar.Dockerfile"))],
-                &Language::Dockerfile
+                &Language::Dockerfile,
+                &FileLanguagesConfig::default()
             )
             .len()
         );
@@ -735,7 +1531,8 @@ mod tests {
             1,
             filter_files_for_language(
                 &[PathBuf::from("path").join(PathBuf::from("Dockerfile.foobar"))],
-                &Language::Dockerfile
+                &Language::Dockerfile,
+                &FileLanguagesConfig::default()
             )
             .len()
         );
@@ -743,7 +1540,8 @@ mod tests {
             0,
             filter_files_for_language(
                 &[PathBuf::from("path").join(PathBuf::from("Dock3rfile.foobar"))],
-                &Language::Dockerfile
+                &Language::Dockerfile,
+                &FileLanguagesConfig::default()
             )
             .len()
         );
@@ -755,7 +1553,8 @@ mod tests {
             1,
             filter_files_for_language(
                 &[PathBuf::from("path").join(PathBuf::from("Dockerfile"))],
-                &Language::Dockerfile
+                &Language::Dockerfile,
+                &FileLanguagesConfig::default()
             )
             .len()
         );
@@ -763,9 +1562,257 @@ mod tests {
             0,
             filter_files_for_language(
                 &[PathBuf::from("path").join(PathBuf::from("Dock3rfile"))],
-                &Language::Dockerfile
+                &Language::Dockerfile,
+                &FileLanguagesConfig::default()
             )
             .len()
         );
     }
+
+    #[test]
+    fn test_filter_files_for_language_gradle() {
+        let files = [
+            PathBuf::from("build.gradle"),
+            PathBuf::from("settings.gradle.kts"),
+            PathBuf::from("src/main/groovy/App.groovy"),
+            PathBuf::from("Jenkinsfile"),
+        ];
+        assert_eq!(
+            filter_files_for_language(&files, &Language::Groovy, &FileLanguagesConfig::default()),
+            vec![
+                PathBuf::from("build.gradle"),
+                PathBuf::from("src/main/groovy/App.groovy"),
+                PathBuf::from("Jenkinsfile"),
+            ]
+        );
+        assert_eq!(
+            filter_files_for_language(&files, &Language::Kotlin, &FileLanguagesConfig::default()),
+            vec![PathBuf::from("settings.gradle.kts")]
+        );
+    }
+
+    #[test]
+    fn test_filter_files_for_language_sfc() {
+        let files = [
+            PathBuf::from("src/App.vue"),
+            PathBuf::from("src/routes/+page.svelte"),
+            PathBuf::from("src/main.ts"),
+        ];
+        assert_eq!(
+            filter_files_for_language(
+                &files,
+                &Language::TypeScript,
+                &FileLanguagesConfig::default()
+            ),
+            files.to_vec()
+        );
+        assert_eq!(
+            filter_files_for_language(
+                &files,
+                &Language::JavaScript,
+                &FileLanguagesConfig::default()
+            ),
+            files[..2].to_vec()
+        );
+        assert!(filter_files_for_language(
+            &files,
+            &Language::Python,
+            &FileLanguagesConfig::default()
+        )
+        .is_empty());
+    }
+
+    // the file names and extensions of the configuration file extend and override the
+    // built-in ones.
+    #[test]
+    fn test_filter_files_for_language_with_file_languages() {
+        let file_languages = FileLanguagesConfig {
+            extensions: [
+                ("bzl".to_string(), Language::Python),
+                ("jsx".to_string(), Language::TypeScript),
+            ]
+            .into(),
+            filenames: [("BUILD".to_string(), Language::Python)].into(),
+        };
+        let files = [
+            PathBuf::from("tools/defs.bzl"),
+            PathBuf::from("src/BUILD"),
+            PathBuf::from("src/main.py"),
+            PathBuf::from("src/App.jsx"),
+        ];
+        assert_eq!(
+            filter_files_for_language(&files, &Language::Python, &file_languages),
+            files[..3].to_vec()
+        );
+        assert_eq!(
+            filter_files_for_language(&files, &Language::TypeScript, &file_languages),
+            files[3..].to_vec()
+        );
+        assert!(
+            filter_files_for_language(&files, &Language::JavaScript, &file_languages).is_empty()
+        );
+
+        let dir = tempdir().unwrap();
+        let build_file = dir.path().join("BUILD.bzl");
+        fs::write(&build_file, "load(\"//:defs.bzl\", \"rule\")").unwrap();
+        assert_eq!(
+            FilePolicy::new(1).check(&build_file),
+            Err(SkipReason::UnsupportedExtension)
+        );
+        assert_eq!(
+            FilePolicy::new(1)
+                .with_file_languages(&file_languages)
+                .check(&build_file),
+            Ok(())
+        );
+    }
+
+    /// The file selection reports the languages and rulesets of the analyzed files, and the
+    /// reason why the other files are excluded.
+    #[test]
+    fn select_files_reasons() {
+        let test_dir = TestDir::new();
+        test_dir.add_file("src/main.py");
+        test_dir.add_file("src/app.js");
+        test_dir.add_file("build/out.py");
+        test_dir.add_file("vendor/lib.py");
+        test_dir.add_file("tests/test_main.py");
+        test_dir.add_file("README.md");
+        test_dir.add_file("logo.png");
+        fs::write(test_dir.dir.path().join(".gitignore"), "build\n").unwrap();
+        fs::write(
+            test_dir.dir.path().join("src/api_pb2.py"),
+            "# Generated protocol buffer code\n",
+        )
+        .unwrap();
+
+        let rule = |name: &str, language: Language| {
+            RuleBuilder::default()
+                .name(name.to_string())
+                .description_base64(None)
+                .language(language)
+                .checksum(String::new())
+                .pattern(None)
+                .tree_sitter_query_base64(None)
+                .category(RuleCategory::BestPractices)
+                .code_base64(String::new())
+                .short_description_base64(None)
+                .entity_checked(None)
+                .rule_type(RuleType::TreeSitterQuery)
+                .severity(RuleSeverity::Error)
+                .cwe(None)
+                .arguments(vec![])
+                .tests(vec![])
+                .is_testing(false)
+                .build()
+                .unwrap()
+        };
+        let rulesets = [(
+            "python-security".to_string(),
+            RulesetConfig {
+                paths: PathConfig {
+                    ignore: vec!["tests".to_string().into()],
+                    only: None,
+                },
+                rules: Default::default(),
+                arguments: Default::default(),
+                analyze_generated_files: None,
+            },
+        )]
+        .into_iter()
+        .collect();
+        let mut configuration = CliConfiguration {
+            use_debug: false,
+            use_configuration_file: true,
+            ignore_gitignore: false,
+            source_directory: test_dir.base_path(),
+            source_subdirectories: vec![],
+            path_config: PathConfig {
+                ignore: vec!["vendor".to_string().into()],
+                only: None,
+            },
+            rules_file: None,
+            outputs: vec![],
+            num_cpus: 1,
+            rules: vec![
+                rule("python-security/no-eval", Language::Python),
+                rule("python-best-practices/no-print", Language::Python),
+            ],
+            path_restrictions: PathRestrictions::from_ruleset_configs(&rulesets),
+            argument_provider: ArgumentProvider::new(),
+            max_file_size_kb: 100,
+            use_staging: false,
+            show_performance_statistics: false,
+            scan_for_secrets: false,
+            validate_secrets: false,
+            secrets_rule_file: None,
+            ignore_generated_files: true,
+            generated_files_rules: HashSet::new(),
+            symlink_behavior: SymlinkBehavior::Skip,
+            non_utf8_behavior: NonUtf8Behavior::Skip,
+            file_languages: FileLanguagesConfig::default(),
+            plugins: PluginRegistry::default(),
+        };
+
+        let gitignore = GitIgnore::from_repository(test_dir.dir.path(), false).unwrap();
+        let selections = select_files(&configuration, &gitignore)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            selections,
+            vec![
+                ".gitignore: excluded (unsupported file extension)",
+                "README.md: excluded (no rule for the language of the file)",
+                "build/out.py: excluded (ignored by git)",
+                "logo.png: excluded (unsupported file extension)",
+                "src/api_pb2.py: excluded (generated file)",
+                "src/app.js: excluded (no rule for the language of the file)",
+                "src/main.py: python (python-best-practices, python-security)",
+                "tests/test_main.py: python (python-best-practices)",
+                "vendor/lib.py: excluded (excluded by the only/ignore paths)",
+            ]
+        );
+
+        // generated files are analyzed by the rules that opt into them
+        configuration
+            .generated_files_rules
+            .insert("python-security/no-eval".to_string());
+        let selections = select_files(&configuration, &gitignore).unwrap();
+        let generated = selections
+            .iter()
+            .find(|s| s.path == "src/api_pb2.py")
+            .unwrap();
+        assert_eq!(
+            generated.to_string(),
+            "src/api_pb2.py: python (python-security)"
+        );
+
+        // the checks of a file for a rule, as explained to the user
+        let failed_checks = |path: &str| {
+            let code = fs::read_to_string(test_dir.dir.path().join(path)).unwrap();
+            check_file_selection(
+                test_dir.dir.path(),
+                path,
+                &code,
+                &configuration.rules[1],
+                None,
+            )
+            .unwrap()
+            .into_iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.description)
+            .collect::<Vec<_>>()
+        };
+        assert!(failed_checks("src/main.py").is_empty());
+        assert_eq!(
+            failed_checks("src/api_pb2.py"),
+            vec!["the file is not generated"]
+        );
+        assert_eq!(
+            failed_checks("build/out.py"),
+            vec!["the file is not ignored by git"]
+        );
+    }
 }
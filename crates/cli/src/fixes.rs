@@ -0,0 +1,609 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::text_style::TextStyle;
+use anyhow::{Context, Result};
+use kernel::editorconfig::EditorConfig;
+use kernel::model::common::{ColumnUnit, LineIndex};
+use kernel::model::rule::{RuleResult, RuleSeverity};
+use kernel::model::violation::{Edit, EditType, Fix, FixApplicability, Violation};
+
+/// The number of fixes that were applied, and the files they were applied to.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct FixSummary {
+    pub fixes: usize,
+    /// The number of fixes that were not applied because they overlap a fix with a higher
+    /// priority, because their edits are invalid, or because their file cannot be read.
+    pub skipped: usize,
+    /// The files that were fixed, relative to the directory of the analysis.
+    pub files: Vec<String>,
+}
+
+impl FixSummary {
+    /// Adds the fixes of another pass of [`apply_fixes`].
+    pub fn merge(&mut self, other: FixSummary) {
+        self.fixes += other.fixes;
+        self.skipped += other.skipped;
+        for file in other.files {
+            if !self.files.contains(&file) {
+                self.files.push(file);
+            }
+        }
+    }
+}
+
+/// The priority of the fixes of a severity: the fixes of the most severe violations are applied
+/// first, so that they win the conflicts with the fixes of less severe violations.
+fn severity_priority(severity: RuleSeverity) -> u8 {
+    match severity {
+        RuleSeverity::Error => 0,
+        RuleSeverity::Warning => 1,
+        RuleSeverity::Notice => 2,
+        RuleSeverity::None => 3,
+    }
+}
+
+/// Returns true if the fix can be applied. `Automatic` fixes are always applied, `Suggested` fixes
+/// are only applied if `allow_unsafe` is true, and `Manual` fixes are never applied.
+pub fn is_fix_applicable(fix: &Fix, allow_unsafe: bool) -> bool {
+    match fix.applicability {
+        FixApplicability::Automatic => true,
+        FixApplicability::Suggested => allow_unsafe,
+        FixApplicability::Manual => false,
+    }
+}
+
+/// Applies the fixes of the violations to the files they were reported in (relative to `directory`).
+///
+/// For each violation, the first applicable fix (see [`is_fix_applicable`]) is applied. When the
+/// fixes of a file conflict, the fix of the most severe violation wins, and then the fix of the
+/// rule with the first name (and then the first violation in the file), so that the same fixes are
+/// applied whatever the order of the results. A fix is skipped if one of its edits is out of bounds
+/// or overlaps an edit of a fix with a higher priority. Files that are not valid UTF-8 (or that
+/// cannot be read) are left untouched, and their fixes are skipped. The columns of the edits are
+/// in `column_unit`, the unit the analysis reported them in.
+/// The code inserted by the fixes follows the `.editorconfig` settings of the files, and the style
+/// detected in the files otherwise.
+pub fn apply_fixes(
+    directory: &Path,
+    rule_results: &[RuleResult],
+    allow_unsafe: bool,
+    column_unit: ColumnUnit,
+    editorconfig: &EditorConfig,
+) -> Result<FixSummary> {
+    let mut fixes_by_file: BTreeMap<&str, Vec<(&str, &Violation, &Fix)>> = BTreeMap::new();
+    for rule_result in rule_results {
+        for violation in &rule_result.violations {
+            if let Some(fix) = violation
+                .fixes
+                .iter()
+                .find(|fix| is_fix_applicable(fix, allow_unsafe))
+            {
+                fixes_by_file
+                    .entry(rule_result.filename.as_str())
+                    .or_default()
+                    .push((rule_result.rule_name.as_str(), violation, fix));
+            }
+        }
+    }
+
+    let mut summary = FixSummary::default();
+    for (filename, mut fixes) in fixes_by_file {
+        fixes.sort_by(|(rule_a, violation_a, _), (rule_b, violation_b, _)| {
+            severity_priority(violation_a.severity)
+                .cmp(&severity_priority(violation_b.severity))
+                .then(rule_a.cmp(rule_b))
+                .then(
+                    (violation_a.start.line, violation_a.start.col)
+                        .cmp(&(violation_b.start.line, violation_b.start.col)),
+                )
+        });
+        let fixes = fixes.into_iter().map(|(_, _, fix)| fix).collect::<Vec<_>>();
+        let path = directory.join(filename);
+        let Ok(content) = fs::read_to_string(&path) else {
+            summary.skipped += fixes.len();
+            continue;
+        };
+        let style =
+            TextStyle::detect(&content).with_editorconfig(&editorconfig.properties(filename));
+        let (fixed_content, applied) =
+            apply_fixes_with_style(&content, &fixes, column_unit, &style);
+        summary.skipped += fixes.len() - applied;
+        if applied > 0 {
+            fs::write(&path, fixed_content)
+                .with_context(|| format!("cannot write fixes to {}", path.display()))?;
+            summary.fixes += applied;
+            summary.files.push(filename.to_string());
+        }
+    }
+    Ok(summary)
+}
+
+/// Returns the violations of `after` that are not in `before`: the violations introduced by the
+/// fixes of the files, when `after` are the results of the fixed files. The violations are compared
+/// by rule, file and fingerprint (or message, without fingerprint), as their positions change with
+/// the fixes.
+pub fn introduced_violations<'a>(
+    before: &[RuleResult],
+    after: &'a [RuleResult],
+) -> Vec<(&'a RuleResult, &'a Violation)> {
+    let key = |result: &RuleResult, violation: &Violation| {
+        (
+            result.rule_name.clone(),
+            result.filename.clone(),
+            violation
+                .fingerprint
+                .clone()
+                .unwrap_or_else(|| violation.message.to_string()),
+        )
+    };
+    let existing = before
+        .iter()
+        .flat_map(|r| r.violations.iter().map(move |v| key(r, v)))
+        .collect::<HashSet<_>>();
+    after
+        .iter()
+        .flat_map(|r| r.violations.iter().map(move |v| (r, v)))
+        .filter(|(r, v)| !existing.contains(&key(r, v)))
+        .collect()
+}
+
+/// Applies the fixes to the content of a file, and returns the new content with the number
+/// of fixes that were applied. Fixes are applied in order, skipping any fix that is invalid or that
+/// overlaps a previously applied fix. The code inserted by the fixes is converted to the
+/// indentation and the line endings of the file (see [`TextStyle::adapt`]).
+pub fn apply_fixes_to_content(
+    content: &str,
+    fixes: &[&Fix],
+    column_unit: ColumnUnit,
+) -> (String, usize) {
+    apply_fixes_with_style(content, fixes, column_unit, &TextStyle::detect(content))
+}
+
+/// Applies the fixes to the content of a file as [`apply_fixes_to_content`], with the code inserted
+/// by the fixes converted to `style`.
+fn apply_fixes_with_style(
+    content: &str,
+    fixes: &[&Fix],
+    column_unit: ColumnUnit,
+    style: &TextStyle,
+) -> (String, usize) {
+    let line_index = LineIndex::new(content);
+    let mut replacements: Vec<(Range<usize>, String)> = vec![];
+    let mut applied = 0;
+
+    for fix in fixes {
+        let Some(edits) = fix
+            .edits
+            .iter()
+            .map(|edit| edit_to_replacement(content, &line_index, style, column_unit, edit))
+            .collect::<Option<Vec<_>>>()
+        else {
+            continue;
+        };
+        let overlaps = edits.iter().enumerate().any(|(idx, (range, _))| {
+            edits[..idx]
+                .iter()
+                .chain(replacements.iter())
+                .any(|(other, _)| ranges_overlap(range, other))
+        });
+        if !overlaps {
+            replacements.extend(edits);
+            applied += 1;
+        }
+    }
+
+    // Apply the replacements from the end of the content so that the offsets stay valid. A
+    // replacement is applied before an insertion at its start, which would otherwise be replaced.
+    replacements
+        .sort_by_key(|(range, _)| (std::cmp::Reverse(range.start), std::cmp::Reverse(range.end)));
+    let mut fixed = content.to_string();
+    for (range, text) in replacements {
+        fixed.replace_range(range, &text);
+    }
+    (fixed, applied)
+}
+
+/// The lines changed by a fix: the lines of the content it replaces, and the new lines.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FixPreview {
+    pub removed: Vec<String>,
+    pub added: Vec<String>,
+}
+
+/// Returns the lines changed by a fix, or `None` if the fix can't be applied to the content.
+pub fn preview_fix(content: &str, fix: &Fix, column_unit: ColumnUnit) -> Option<FixPreview> {
+    let (fixed, applied) = apply_fixes_to_content(content, &[fix], column_unit);
+    if applied == 0 {
+        return None;
+    }
+    let before = content.lines().collect::<Vec<_>>();
+    let after = fixed.lines().collect::<Vec<_>>();
+    let prefix = before
+        .iter()
+        .zip(&after)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let changed = |lines: &[&str]| {
+        lines[prefix..lines.len() - suffix]
+            .iter()
+            .map(|line| line.to_string())
+            .collect()
+    };
+    Some(FixPreview {
+        removed: changed(&before),
+        added: changed(&after),
+    })
+}
+
+/// Converts an edit into the byte range it replaces and the replacement text, in the style of the
+/// content. Returns `None` if the edit is invalid for the content.
+fn edit_to_replacement(
+    content: &str,
+    line_index: &LineIndex,
+    style: &TextStyle,
+    column_unit: ColumnUnit,
+    edit: &Edit,
+) -> Option<(Range<usize>, String)> {
+    let start = line_index.byte_offset_in(&edit.start, column_unit)?;
+    let end = match edit.edit_type {
+        EditType::Add => start,
+        EditType::Remove | EditType::Update => {
+            line_index.byte_offset_in(edit.end.as_ref()?, column_unit)?
+        }
+    };
+    if start > end {
+        return None;
+    }
+    let text = match edit.edit_type {
+        EditType::Remove => "",
+        EditType::Add | EditType::Update => edit.content.as_deref().unwrap_or_default(),
+    };
+    let at_line_start = start == 0 || content.as_bytes()[start - 1] == b'\n';
+    Some((start..end, style.adapt(text, at_line_start)))
+}
+
+/// Two ranges overlap if they share at least one byte, or if they are both insertions at the same offset
+/// (as the order of the insertions would be ambiguous).
+fn ranges_overlap(a: &Range<usize>, b: &Range<usize>) -> bool {
+    if a.is_empty() && b.is_empty() {
+        return a.start == b.start;
+    }
+    a.start < b.end && b.start < a.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rule_result;
+    use kernel::model::common::Position;
+    use kernel::model::rule::RuleCategory;
+    use kernel::model::violation::ViolationBuilder;
+
+    fn position(line: u32, col: u32) -> Position {
+        Position { line, col }
+    }
+
+    fn fix(edits: Vec<Edit>, applicability: FixApplicability) -> Fix {
+        Fix {
+            description: "fix".to_string(),
+            edits,
+            applicability,
+        }
+    }
+
+    fn update(start: Position, end: Position, content: &str) -> Edit {
+        Edit {
+            start,
+            end: Some(end),
+            edit_type: EditType::Update,
+            content: Some(content.to_string()),
+        }
+    }
+
+    fn violation(line: u32, severity: RuleSeverity, fixes: Vec<Fix>) -> Violation {
+        ViolationBuilder::default()
+            .start(position(line, 1))
+            .end(position(line, 4))
+            .message(format!("violation at line {line}"))
+            .severity(severity)
+            .category(RuleCategory::BestPractices)
+            .fixes(fixes)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn applicability() {
+        let automatic = fix(vec![], FixApplicability::Automatic);
+        let suggested = fix(vec![], FixApplicability::Suggested);
+        let manual = fix(vec![], FixApplicability::Manual);
+        assert!(is_fix_applicable(&automatic, false));
+        assert!(!is_fix_applicable(&suggested, false));
+        assert!(is_fix_applicable(&suggested, true));
+        assert!(!is_fix_applicable(&manual, true));
+    }
+
+    #[test]
+    fn apply_edits() {
+        let content = "def foo():\n    bar(1)\n";
+        let rename = fix(
+            vec![
+                update(position(1, 5), position(1, 8), "baz"),
+                Edit {
+                    start: position(2, 10),
+                    end: None,
+                    edit_type: EditType::Add,
+                    content: Some(", 2".to_string()),
+                },
+            ],
+            FixApplicability::Automatic,
+        );
+        let remove = fix(
+            vec![Edit {
+                start: position(2, 1),
+                end: Some(position(2, 5)),
+                edit_type: EditType::Remove,
+                content: None,
+            }],
+            FixApplicability::Automatic,
+        );
+        assert_eq!(
+            apply_fixes_to_content(content, &[&rename, &remove], ColumnUnit::Bytes),
+            ("def baz():\nbar(1, 2)\n".to_string(), 2)
+        );
+    }
+
+    /// An insertion at the start of a replacement is kept before the replaced content, whatever
+    /// the order of the fixes.
+    #[test]
+    fn insertion_at_replacement_start() {
+        let content = "abcdef";
+        let insert = fix(
+            vec![Edit {
+                start: position(1, 3),
+                end: None,
+                edit_type: EditType::Add,
+                content: Some("é".to_string()),
+            }],
+            FixApplicability::Automatic,
+        );
+        let replace = fix(
+            vec![update(position(1, 3), position(1, 5), "Y")],
+            FixApplicability::Automatic,
+        );
+        for fixes in [[&insert, &replace], [&replace, &insert]] {
+            assert_eq!(
+                apply_fixes_to_content(content, &fixes, ColumnUnit::Bytes),
+                ("abéYef".to_string(), 2)
+            );
+        }
+    }
+
+    /// Fixes that overlap a previous fix, or whose edits are out of bounds, are skipped.
+    #[test]
+    fn skip_invalid_fixes() {
+        let content = "abcdef\né\n";
+        let first = fix(
+            vec![update(position(1, 1), position(1, 4), "x")],
+            FixApplicability::Automatic,
+        );
+        let overlapping = fix(
+            vec![update(position(1, 3), position(1, 6), "y")],
+            FixApplicability::Automatic,
+        );
+        let out_of_bounds = fix(
+            vec![update(position(5, 1), position(5, 2), "z")],
+            FixApplicability::Automatic,
+        );
+        let not_char_boundary = fix(
+            vec![update(position(2, 2), position(2, 3), "z")],
+            FixApplicability::Automatic,
+        );
+        assert_eq!(
+            apply_fixes_to_content(
+                content,
+                &[&first, &overlapping, &out_of_bounds, &not_char_boundary],
+                ColumnUnit::Bytes
+            ),
+            ("xdef\né\n".to_string(), 1)
+        );
+    }
+
+    /// The fix of the most severe violation wins a conflict, and then the fix of the rule with the
+    /// first name, whatever the order of the results.
+    #[test]
+    fn resolve_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("file.py"), "foo = 1\nbar = 2\n").unwrap();
+        let replace = |line: u32, content: &str| {
+            fix(
+                vec![update(position(line, 1), position(line, 4), content)],
+                FixApplicability::Automatic,
+            )
+        };
+        let results = vec![
+            rule_result(
+                "ruleset/b-notice",
+                "file.py",
+                vec![violation(1, RuleSeverity::Notice, vec![replace(1, "aaa")])],
+            ),
+            rule_result(
+                "ruleset/c-error",
+                "file.py",
+                vec![violation(1, RuleSeverity::Error, vec![replace(1, "bbb")])],
+            ),
+            rule_result(
+                "ruleset/z-warning",
+                "file.py",
+                vec![violation(2, RuleSeverity::Warning, vec![replace(2, "ccc")])],
+            ),
+            rule_result(
+                "ruleset/a-warning",
+                "file.py",
+                vec![violation(2, RuleSeverity::Warning, vec![replace(2, "ddd")])],
+            ),
+        ];
+        let summary = apply_fixes(
+            dir.path(),
+            &results,
+            false,
+            ColumnUnit::Bytes,
+            &EditorConfig::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            summary,
+            FixSummary {
+                fixes: 2,
+                skipped: 2,
+                files: vec!["file.py".to_string()],
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("file.py")).unwrap(),
+            "bbb = 1\nddd = 2\n"
+        );
+    }
+
+    /// The fixes of a file that cannot be read are skipped.
+    #[test]
+    fn unreadable_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("latin1.py"), b"name = 'caf\xe9'\n").unwrap();
+        let fixes = vec![fix(
+            vec![update(position(1, 1), position(1, 5), "title")],
+            FixApplicability::Automatic,
+        )];
+        let results = vec![
+            rule_result(
+                "ruleset/rule",
+                "latin1.py",
+                vec![violation(1, RuleSeverity::Warning, fixes.clone())],
+            ),
+            rule_result(
+                "ruleset/rule",
+                "missing.py",
+                vec![violation(1, RuleSeverity::Warning, fixes)],
+            ),
+        ];
+        let summary = apply_fixes(
+            dir.path(),
+            &results,
+            false,
+            ColumnUnit::Bytes,
+            &EditorConfig::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            summary,
+            FixSummary {
+                fixes: 0,
+                skipped: 2,
+                files: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn find_introduced_violations() {
+        let before = vec![rule_result(
+            "ruleset/rule",
+            "file.py",
+            vec![violation(1, RuleSeverity::Warning, vec![])],
+        )];
+        // the violation of the first line moved to the second line, with the same message
+        let mut moved = violation(1, RuleSeverity::Warning, vec![]);
+        moved.start = position(2, 1);
+        let after = vec![
+            rule_result(
+                "ruleset/rule",
+                "file.py",
+                vec![moved, violation(3, RuleSeverity::Warning, vec![])],
+            ),
+            rule_result(
+                "ruleset/other",
+                "file.py",
+                vec![violation(1, RuleSeverity::Warning, vec![])],
+            ),
+        ];
+        let introduced = introduced_violations(&before, &after)
+            .into_iter()
+            .map(|(r, v)| (r.rule_name.as_str(), v.start.line))
+            .collect::<Vec<_>>();
+        assert_eq!(introduced, vec![("ruleset/rule", 3), ("ruleset/other", 1)]);
+    }
+
+    /// The code inserted in a file with tabs and CRLF line endings uses tabs and CRLF.
+    #[test]
+    fn preserve_text_style() {
+        let content = "if x:\r\n\tfoo()\r\n";
+        let add_else = fix(
+            vec![Edit {
+                start: position(3, 1),
+                end: None,
+                edit_type: EditType::Add,
+                content: Some("else:\n    bar()\n".to_string()),
+            }],
+            FixApplicability::Automatic,
+        );
+        assert_eq!(
+            apply_fixes_to_content(content, &[&add_else], ColumnUnit::Bytes),
+            ("if x:\r\n\tfoo()\r\nelse:\r\n\tbar()\r\n".to_string(), 1)
+        );
+    }
+
+    /// The columns of the edits are read in the unit of the analysis.
+    #[test]
+    fn column_units() {
+        let content = "s = \"😀\" + foo\n";
+        let rename = fix(
+            vec![update(position(1, 11), position(1, 14), "bar")],
+            FixApplicability::Automatic,
+        );
+        assert_eq!(
+            apply_fixes_to_content(content, &[&rename], ColumnUnit::Chars),
+            ("s = \"😀\" + bar\n".to_string(), 1)
+        );
+        let rename = fix(
+            vec![update(position(1, 12), position(1, 15), "bar")],
+            FixApplicability::Automatic,
+        );
+        assert_eq!(
+            apply_fixes_to_content(content, &[&rename], ColumnUnit::Utf16),
+            ("s = \"😀\" + bar\n".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn fix_preview() {
+        let content = "a = 1\nb = eval(foo)\nc = 3\n";
+        let literal_eval = fix(
+            vec![update(position(2, 5), position(2, 9), "literal_eval")],
+            FixApplicability::Suggested,
+        );
+        assert_eq!(
+            preview_fix(content, &literal_eval, ColumnUnit::Bytes),
+            Some(FixPreview {
+                removed: vec!["b = eval(foo)".to_string()],
+                added: vec!["b = literal_eval(foo)".to_string()],
+            })
+        );
+        let out_of_bounds = fix(
+            vec![update(position(10, 1), position(10, 2), "x")],
+            FixApplicability::Suggested,
+        );
+        assert_eq!(
+            preview_fix(content, &out_of_bounds, ColumnUnit::Bytes),
+            None
+        );
+    }
+}
@@ -1,7 +1,42 @@
 use crate::constants::{GITLAB_ENVIRONMENT_VARIABLE_COMMIT_BRANCH, GIT_HEAD};
+use anyhow::{anyhow, Context, Result};
 use git2::Repository;
 use std::env;
 
+/// The git metadata of the analyzed commit, sent with the results uploaded to Datadog.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GitMetadata {
+    pub repository_url: String,
+    pub commit_sha: String,
+    /// The branch, if the repository is not on a detached HEAD (see [`get_branch`]).
+    pub branch: Option<String>,
+}
+
+/// Returns the git metadata of the repository at the root of the directory: the URL of the
+/// `origin` remote, the commit of `HEAD` and the branch.
+pub fn get_git_metadata(directory: &str, use_debug: bool) -> Result<GitMetadata> {
+    let repository = Repository::open(directory)
+        .with_context(|| format!("{directory} is not a git repository"))?;
+    let repository_url = repository
+        .find_remote("origin")
+        .context("cannot find the origin remote of the repository")?
+        .url()
+        .ok_or(anyhow!("cannot get the repository origin URL"))?
+        .to_string();
+    let commit_sha = repository
+        .head()
+        .context("cannot get the HEAD of the repository")?
+        .peel_to_commit()
+        .context("HEAD is not a commit")?
+        .id()
+        .to_string();
+    Ok(GitMetadata {
+        repository_url,
+        commit_sha,
+        branch: get_branch(&repository, use_debug),
+    })
+}
+
 /// Try to get the branch running. We first try to get the branch from the repository. When
 /// it fails, we attempt to get the branch from the CI provider when the analyzer
 /// runs in a CI provider.
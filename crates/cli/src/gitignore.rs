@@ -0,0 +1,322 @@
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the files that list the ignored files of a directory.
+const GITIGNORE_FILE: &str = ".gitignore";
+
+/// A pattern of a gitignore file.
+#[derive(Debug)]
+struct Pattern {
+    /// The directory of the gitignore file, relative to the root of the repository. The pattern
+    /// only applies to the paths in this directory.
+    base: String,
+    glob: GlobMatcher,
+    /// The pattern starts with `!`: the paths it matches are not ignored.
+    negated: bool,
+    /// The pattern ends with `/`: it only matches directories.
+    directory_only: bool,
+}
+
+impl Pattern {
+    /// Parses a line of a gitignore file. Returns `None` for empty lines, comments and
+    /// invalid patterns.
+    fn parse(line: &str, base: &str) -> Option<Self> {
+        let line = trim_trailing_spaces(line);
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, pattern) = match line.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            // `\#` and `\!` match a path that starts with `#` or `!`.
+            None if line.starts_with("\\#") || line.starts_with("\\!") => (false, &line[1..]),
+            None => (false, line),
+        };
+        let directory_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        // A pattern with a slash at the beginning or in the middle is relative to the directory
+        // of the gitignore file, otherwise it matches at any depth.
+        let glob = if pattern.contains('/') {
+            pattern.trim_start_matches('/').to_string()
+        } else {
+            format!("**/{pattern}")
+        };
+        let glob = GlobBuilder::new(&glob)
+            .literal_separator(true)
+            .backslash_escape(true)
+            .build()
+            .ok()?
+            .compile_matcher();
+        Some(Self {
+            base: base.to_string(),
+            glob,
+            negated,
+            directory_only,
+        })
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.directory_only && !is_dir {
+            return false;
+        }
+        let relative_path = if self.base.is_empty() {
+            Some(path)
+        } else {
+            path.strip_prefix(self.base.as_str())
+                .and_then(|p| p.strip_prefix('/'))
+        };
+        relative_path.is_some_and(|p| self.glob.is_match(p))
+    }
+}
+
+/// The files of a repository that are ignored by git, following the rules of
+/// https://git-scm.com/docs/gitignore. The patterns are read from, by increasing precedence:
+///  - the excludes file of the git configuration (`core.excludesFile` of the repository or of the
+///    user, or `$XDG_CONFIG_HOME/git/ignore`)
+///  - the `.git/info/exclude` file of the repository
+///  - the `.gitignore` files of the repository, where a file takes precedence over the files of
+///    the parent directories
+///
+/// The last pattern that matches a path decides if it is ignored.
+#[derive(Debug, Default)]
+pub struct GitIgnore {
+    patterns: Vec<Pattern>,
+}
+
+impl GitIgnore {
+    /// Reads the ignored files of the repository in `directory`. The global excludes file is only
+    /// read if `use_global_excludes` is true.
+    pub fn from_repository(directory: &Path, use_global_excludes: bool) -> Result<Self> {
+        let mut gitignore = Self::default();
+        if use_global_excludes {
+            if let Some(path) = global_excludes_file(directory) {
+                gitignore.add_file(&path, "")?;
+            }
+        }
+        gitignore.add_file(&directory.join(".git").join("info").join("exclude"), "")?;
+        gitignore.add_directory(directory, "")?;
+        Ok(gitignore)
+    }
+
+    /// Returns true if a path, relative to the root of the repository, is ignored. As with git,
+    /// a path in an ignored directory is ignored even if a pattern re-includes it.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        path.match_indices('/')
+            .any(|(index, _)| self.is_excluded(&path[..index], true))
+            || self.is_excluded(path, is_dir)
+    }
+
+    fn is_excluded(&self, path: &str, is_dir: bool) -> bool {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| pattern.matches(path, is_dir))
+            .is_some_and(|pattern| !pattern.negated)
+    }
+
+    fn add_patterns(&mut self, content: &str, base: &str) {
+        self.patterns.extend(
+            content
+                .lines()
+                .filter_map(|line| Pattern::parse(line, base)),
+        );
+    }
+
+    fn add_file(&mut self, path: &Path, base: &str) -> Result<()> {
+        if path.is_file() {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("cannot read {}", path.display()))?;
+            self.add_patterns(&content, base);
+        }
+        Ok(())
+    }
+
+    // read the .gitignore file of a directory, then the ones of its subdirectories that are not
+    // ignored (git does not read the files of an ignored directory). A directory that can't be
+    // read is skipped, like its files are skipped by the analysis.
+    fn add_directory(&mut self, root: &Path, base: &str) -> Result<()> {
+        let directory = root.join(base);
+        self.add_file(&directory.join(GITIGNORE_FILE), base)?;
+
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(err) => {
+                eprintln!(
+                    "Cannot read the directory {} for its .gitignore files: {err}",
+                    directory.display()
+                );
+                return Ok(());
+            }
+        };
+        let mut subdirectories = vec![];
+        for entry in entries.flatten() {
+            // symbolic links are not followed, as with git.
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            if !is_dir || entry.file_name() == ".git" {
+                continue;
+            }
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            let path = if base.is_empty() {
+                name
+            } else {
+                format!("{base}/{name}")
+            };
+            if !self.is_excluded(&path, true) {
+                subdirectories.push(path);
+            }
+        }
+        subdirectories.sort();
+        for subdirectory in subdirectories {
+            self.add_directory(root, &subdirectory)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the path of the global excludes file: the `core.excludesFile` of the git
+/// configuration of the repository in `directory` (which includes the configuration of the user),
+/// or `$XDG_CONFIG_HOME/git/ignore` by default.
+fn global_excludes_file(directory: &Path) -> Option<PathBuf> {
+    let config = git2::Repository::open(directory)
+        .and_then(|repository| repository.config())
+        .or_else(|_| git2::Config::open_default());
+    if let Ok(path) = config.and_then(|config| config.get_path("core.excludesFile")) {
+        return Some(path);
+    }
+    match env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        Some(config_home) => Some(PathBuf::from(config_home).join("git").join("ignore")),
+        None => env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join(".config")
+                .join("git")
+                .join("ignore")
+        }),
+    }
+}
+
+// remove the trailing spaces of a line, unless they are escaped with a backslash.
+fn trim_trailing_spaces(line: &str) -> &str {
+    let mut end = line.len();
+    while line[..end].ends_with(' ') && !line[..end - 1].ends_with('\\') {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn gitignore(content: &str) -> GitIgnore {
+        let mut gitignore = GitIgnore::default();
+        gitignore.add_patterns(content, "");
+        gitignore
+    }
+
+    #[test]
+    fn patterns() {
+        let gitignore = gitignore(
+            r#"
+# comment
+*.log
+!important.log
+/build
+docs/*.html
+out/
+\#notes
+"#,
+        );
+        assert!(gitignore.is_ignored("app.log", false));
+        assert!(gitignore.is_ignored("logs/app.log", false));
+        assert!(!gitignore.is_ignored("logs/important.log", false));
+        assert!(gitignore.is_ignored("build/main.o", false));
+        assert!(!gitignore.is_ignored("src/build/main.o", false));
+        assert!(gitignore.is_ignored("docs/index.html", false));
+        assert!(!gitignore.is_ignored("docs/api/index.html", false));
+        assert!(gitignore.is_ignored("src/out/main.js", false));
+        // `out/` only matches directories
+        assert!(!gitignore.is_ignored("src/out", false));
+        assert!(gitignore.is_ignored("#notes", false));
+        assert!(!gitignore.is_ignored("# comment", false));
+    }
+
+    #[test]
+    fn trailing_spaces() {
+        assert_eq!(trim_trailing_spaces("*.log  "), "*.log");
+        assert_eq!(trim_trailing_spaces("name\\ "), "name\\ ");
+        assert_eq!(trim_trailing_spaces("   "), "");
+    }
+
+    // a file cannot be re-included if its directory is ignored.
+    #[test]
+    fn negation_in_ignored_directory() {
+        let gitignore = gitignore("dist/\n!dist/keep.js\ntmp/*\n!tmp/keep.js\n");
+        assert!(gitignore.is_ignored("dist/keep.js", false));
+        assert!(!gitignore.is_ignored("tmp/keep.js", false));
+        assert!(gitignore.is_ignored("tmp/other.js", false));
+    }
+
+    #[test]
+    fn gitignore_file() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/test/gitignore/test1");
+        let gitignore = gitignore(&fs::read_to_string(d).unwrap());
+        assert!(gitignore.is_ignored("ddtrace/appsec/_ddwaf.cpp", false));
+        assert!(gitignore.is_ignored("ddtrace/__pycache__/main.pyc", false));
+        assert!(gitignore.is_ignored("venv/lib/site.py", false));
+        assert!(!gitignore.is_ignored(".env", false));
+        assert!(!gitignore.is_ignored("ddtrace/main.py", false));
+    }
+
+    // nested .gitignore files and .git/info/exclude are read, and the deepest file takes
+    // precedence.
+    #[test]
+    fn from_repository() {
+        let dir = tempdir().unwrap();
+        let write = |path: &str, content: &str| {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(".gitignore", "*.tmp\nignored/\n");
+        write(".git/info/exclude", "local.txt\n");
+        write("src/.gitignore", "/generated\n!keep.tmp\n");
+        write("ignored/.gitignore", "!*\n");
+
+        let gitignore = GitIgnore::from_repository(dir.path(), false).unwrap();
+        assert!(gitignore.is_ignored("a.tmp", false));
+        assert!(gitignore.is_ignored("src/a.tmp", false));
+        assert!(!gitignore.is_ignored("src/keep.tmp", false));
+        assert!(gitignore.is_ignored("keep.tmp", false));
+        assert!(gitignore.is_ignored("src/generated/api.py", false));
+        assert!(!gitignore.is_ignored("generated/api.py", false));
+        assert!(gitignore.is_ignored("local.txt", false));
+        assert!(gitignore.is_ignored("src/local.txt", false));
+        assert!(gitignore.is_ignored("ignored/main.py", false));
+        assert!(!gitignore.is_ignored("src/main.py", false));
+    }
+
+    // the excludes file of the configuration of the repository is read
+    #[test]
+    fn repository_excludes_file() {
+        let dir = tempdir().unwrap();
+        let repository = git2::Repository::init(dir.path()).unwrap();
+        let excludes = dir.path().join("excludes");
+        fs::write(&excludes, "*.secret\n").unwrap();
+        repository
+            .config()
+            .unwrap()
+            .set_str("core.excludesFile", excludes.to_str().unwrap())
+            .unwrap();
+
+        let gitignore = GitIgnore::from_repository(dir.path(), true).unwrap();
+        assert!(gitignore.is_ignored("src/key.secret", false));
+        let gitignore = GitIgnore::from_repository(dir.path(), false).unwrap();
+        assert!(!gitignore.is_ignored("src/key.secret", false));
+    }
+}
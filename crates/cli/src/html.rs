@@ -0,0 +1,311 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use kernel::model::common::ColumnUnit;
+use kernel::model::rule::RuleResult;
+use kernel::model::violation::{Fix, FixApplicability, Violation};
+
+use crate::fixes::preview_fix;
+
+/// The number of lines shown before and after the lines of a violation.
+const CONTEXT_LINES: usize = 2;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2em; color: #1f2328; }
+h1 { font-size: 1.5em; }
+.filters { display: flex; gap: 1em; margin-bottom: 1.5em; position: sticky; top: 0; background: #fff; padding: 0.5em 0; }
+.violation { border: 1px solid #d0d7de; border-radius: 6px; margin-bottom: 1em; padding: 0.5em 1em; }
+.violation header { display: flex; gap: 1em; align-items: baseline; flex-wrap: wrap; }
+.severity { font-weight: bold; text-transform: uppercase; font-size: 0.8em; padding: 0.1em 0.5em; border-radius: 4px; color: #fff; background: #6e7781; }
+.severity-error { background: #cf222e; }
+.severity-warning { background: #bf8700; }
+.severity-notice { background: #0969da; }
+.rule, .location { font-family: monospace; }
+pre { background: #f6f8fa; padding: 0.5em; overflow-x: auto; }
+.line-number { color: #6e7781; user-select: none; display: inline-block; width: 4em; }
+mark { background: #ffd8b5; }
+.removed { background: #ffebe9; }
+.added { background: #dafbe1; }
+"#;
+
+const SCRIPT: &str = r#"
+function applyFilters() {
+  const severity = document.getElementById("filter-severity").value;
+  const rule = document.getElementById("filter-rule").value;
+  const path = document.getElementById("filter-path").value;
+  let shown = 0;
+  for (const element of document.querySelectorAll(".violation")) {
+    const visible = (!severity || element.dataset.severity === severity)
+      && (!rule || element.dataset.rule === rule)
+      && element.dataset.path.includes(path);
+    element.hidden = !visible;
+    shown += visible ? 1 : 0;
+  }
+  document.getElementById("count").textContent = shown;
+}
+for (const id of ["filter-severity", "filter-rule", "filter-path"]) {
+  document.getElementById(id).addEventListener("input", applyFilters);
+}
+"#;
+
+/// Generates a standalone HTML report of the violations, with a snippet of the code of each
+/// violation and a preview of its fixes. `directory` is the directory the filenames of the
+/// results are relative to, and `column_unit` the unit of the columns of the violations.
+pub fn generate_html_report(
+    rule_results: &[RuleResult],
+    directory: &Path,
+    column_unit: ColumnUnit,
+) -> String {
+    let mut files: HashMap<&str, Option<String>> = HashMap::new();
+    let mut rules = BTreeSet::new();
+    let mut violations = String::new();
+    let mut count = 0;
+
+    for result in rule_results {
+        let content = files
+            .entry(result.filename.as_str())
+            .or_insert_with(|| fs::read_to_string(directory.join(&result.filename)).ok());
+        for violation in &result.violations {
+            rules.insert(result.rule_name.as_str());
+            count += 1;
+            write_violation(
+                &mut violations,
+                &result.rule_name,
+                &result.filename,
+                violation,
+                content.as_deref(),
+                column_unit,
+            );
+        }
+    }
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Datadog Static Analysis Report</title>\n");
+    let _ = writeln!(html, "<style>{STYLE}</style>\n</head>\n<body>");
+    let _ = writeln!(
+        html,
+        "<h1>Datadog Static Analysis Report</h1>\n<p><span id=\"count\">{count}</span> violation(s)</p>"
+    );
+    html.push_str("<div class=\"filters\">\n<select id=\"filter-severity\"><option value=\"\">All severities</option>");
+    for severity in ["error", "warning", "notice", "none"] {
+        let _ = write!(html, "<option value=\"{severity}\">{severity}</option>");
+    }
+    html.push_str("</select>\n<select id=\"filter-rule\"><option value=\"\">All rules</option>");
+    for rule in rules {
+        let rule = escape(rule);
+        let _ = write!(html, "<option value=\"{rule}\">{rule}</option>");
+    }
+    html.push_str("</select>\n<input id=\"filter-path\" type=\"search\" placeholder=\"Filter by path\">\n</div>\n");
+    html.push_str(&violations);
+    let _ = writeln!(html, "<script>{SCRIPT}</script>\n</body>\n</html>");
+    html
+}
+
+fn write_violation(
+    out: &mut String,
+    rule_name: &str,
+    filename: &str,
+    violation: &Violation,
+    content: Option<&str>,
+    column_unit: ColumnUnit,
+) {
+    let severity = violation.severity.to_string();
+    let _ = writeln!(
+        out,
+        "<section class=\"violation\" data-severity=\"{severity}\" data-rule=\"{}\" data-path=\"{}\">",
+        escape(rule_name),
+        escape(filename)
+    );
+    let _ = writeln!(
+        out,
+        "<header><span class=\"severity severity-{severity}\">{severity}</span><span class=\"rule\">{}</span><span class=\"location\">{}:{}:{}</span></header>",
+        escape(rule_name),
+        escape(filename),
+        violation.start.line,
+        violation.start.col
+    );
+    let _ = writeln!(out, "<p>{}</p>", escape(&violation.message));
+    if let Some(content) = content {
+        write_snippet(out, content, violation);
+        for fix in &violation.fixes {
+            write_fix_preview(out, content, fix, column_unit);
+        }
+    }
+    out.push_str("</section>\n");
+}
+
+/// Writes the lines of the violation with some context, highlighting its range.
+fn write_snippet(out: &mut String, content: &str, violation: &Violation) {
+    let lines = content.lines().collect::<Vec<_>>();
+    let start_line = violation.start.line as usize;
+    let end_line = (violation.end.line as usize).max(start_line);
+    if start_line == 0 || start_line > lines.len() {
+        return;
+    }
+    let first = start_line.saturating_sub(CONTEXT_LINES).max(1);
+    let last = (end_line + CONTEXT_LINES).min(lines.len());
+
+    out.push_str("<pre><code>");
+    for number in first..=last {
+        let line = lines[number - 1];
+        let _ = write!(out, "<span class=\"line-number\">{number}</span>");
+        if violation.file_level || number < start_line || number > end_line {
+            out.push_str(&escape(line));
+        } else {
+            // The columns are 1-based byte offsets: the highlighted range starts at the start column
+            // on the first line of the violation, and ends at the end column on its last line.
+            let from = if number == start_line {
+                (violation.start.col as usize).saturating_sub(1)
+            } else {
+                0
+            };
+            let to = if number == end_line {
+                (violation.end.col as usize).saturating_sub(1)
+            } else {
+                line.len()
+            };
+            match (line.get(..from), line.get(from..to), line.get(to..)) {
+                (Some(before), Some(highlighted), Some(after)) => {
+                    let _ = write!(
+                        out,
+                        "{}<mark>{}</mark>{}",
+                        escape(before),
+                        escape(highlighted),
+                        escape(after)
+                    );
+                }
+                _ => out.push_str(&escape(line)),
+            }
+        }
+        out.push('\n');
+    }
+    out.push_str("</code></pre>\n");
+}
+
+/// Writes the lines changed by a fix, as a diff.
+fn write_fix_preview(out: &mut String, content: &str, fix: &Fix, column_unit: ColumnUnit) {
+    let Some(preview) = preview_fix(content, fix, column_unit) else {
+        return;
+    };
+    let applicability = match fix.applicability {
+        FixApplicability::Automatic => "automatic",
+        FixApplicability::Suggested => "suggested",
+        FixApplicability::Manual => "manual",
+    };
+    let _ = writeln!(
+        out,
+        "<p>Fix ({applicability}): {}</p>",
+        escape(&fix.description)
+    );
+    out.push_str("<pre><code>");
+    for line in &preview.removed {
+        let _ = writeln!(out, "<span class=\"removed\">- {}</span>", escape(line));
+    }
+    for line in &preview.added {
+        let _ = writeln!(out, "<span class=\"added\">+ {}</span>", escape(line));
+    }
+    out.push_str("</code></pre>\n");
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::model::common::Position;
+    use kernel::model::rule::{RuleCategory, RuleSeverity};
+    use kernel::model::violation::{Edit, EditType};
+
+    fn violation() -> Violation {
+        Violation {
+            start: Position { line: 2, col: 5 },
+            end: Position { line: 2, col: 14 },
+            message: "do not use <eval>".into(),
+            severity: RuleSeverity::Error,
+            category: RuleCategory::Security,
+            fixes: vec![Fix {
+                description: "use literal_eval".to_string(),
+                edits: vec![Edit {
+                    start: Position { line: 2, col: 5 },
+                    end: Some(Position { line: 2, col: 9 }),
+                    edit_type: EditType::Update,
+                    content: Some("literal_eval".to_string()),
+                }],
+                applicability: FixApplicability::Suggested,
+            }],
+            merged_rules: vec![],
+            tags: vec![],
+            cwe: vec![],
+            documentation_url: None,
+            related_locations: vec![],
+            file_level: false,
+            fingerprint: None,
+            owners: vec![],
+            snippet: None,
+            offsets: None,
+            last_commit: None,
+        }
+    }
+
+    #[test]
+    fn snippet() {
+        let mut out = String::new();
+        write_snippet(&mut out, "a = 1\nb = eval(foo)\nc = 3\n", &violation());
+        assert_eq!(
+            out,
+            "<pre><code><span class=\"line-number\">1</span>a = 1\n<span class=\"line-number\">2</span>b = <mark>eval(foo)</mark>\n<span class=\"line-number\">3</span>c = 3\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn fix_preview() {
+        let mut out = String::new();
+        let violation = violation();
+        write_fix_preview(
+            &mut out,
+            "a = 1\nb = eval(foo)\nc = 3\n",
+            &violation.fixes[0],
+            ColumnUnit::Bytes,
+        );
+        assert!(out.contains("<span class=\"removed\">- b = eval(foo)</span>\n"));
+        assert!(out.contains("<span class=\"added\">+ b = literal_eval(foo)</span>\n"));
+        assert!(!out.contains("a = 1"));
+    }
+
+    #[test]
+    fn report_is_escaped() {
+        let results = vec![RuleResult {
+            rule_name: "python-security/no-eval".into(),
+            filename: "missing.py".into(),
+            violations: vec![violation()],
+            errors: vec![],
+            execution_error: None,
+            output: None,
+            execution_time_ms: 0,
+            query_node_time_ms: 0,
+            degraded: false,
+            parsing_time_ms: 0,
+            profile: None,
+        }];
+        let html = generate_html_report(&results, Path::new("/nonexistent"), ColumnUnit::Bytes);
+        assert!(html.contains("<p>do not use &lt;eval&gt;</p>"));
+        assert!(html.contains("<option value=\"python-security/no-eval\">"));
+        assert!(html.contains("<span id=\"count\">1</span>"));
+    }
+}
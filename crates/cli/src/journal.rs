@@ -0,0 +1,217 @@
+//! The journal of an analysis: the results of each analyzed file, appended as soon as the file is
+//! analyzed, so that an interrupted analysis can be resumed without analyzing the same files again.
+//!
+//! The journal is a JSON Lines file. Its first line identifies the configuration of the analysis,
+//! and each following line holds the results of a file, with the hash of its content: a file is
+//! only reused if it didn't change since it was analyzed.
+
+use anyhow::{Context, Result};
+use kernel::model::analysis::ERROR_RULE_TIMEOUT;
+use kernel::model::common::Language;
+use kernel::model::rule::RuleResult;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The version of the format of the journal. A journal with another version is discarded.
+const JOURNAL_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, PartialEq)]
+struct JournalHeader {
+    version: u32,
+    /// The hash of the configuration of the analysis.
+    configuration: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JournalEntry {
+    language: Language,
+    path: String,
+    /// The hash of the content of the file when it was analyzed.
+    hash: String,
+    results: Vec<RuleResult>,
+}
+
+pub struct ScanJournal {
+    entries: HashMap<(Language, String), JournalEntry>,
+    writer: Mutex<BufWriter<File>>,
+    reused: AtomicUsize,
+}
+
+impl ScanJournal {
+    /// Opens the journal at `path`, and keeps the results it contains if they were produced with
+    /// the same `configuration` (the rules and the options that change the results). Otherwise,
+    /// the journal is started over.
+    pub fn open(path: &Path, configuration: &str) -> Result<Self> {
+        let header = JournalHeader {
+            version: JOURNAL_VERSION,
+            configuration: hash(configuration),
+        };
+        let mut entries = HashMap::new();
+        if let Ok(file) = File::open(path) {
+            let mut lines = BufReader::new(file).lines();
+            let same_configuration = lines
+                .next()
+                .and_then(|line| line.ok())
+                .and_then(|line| serde_json::from_str::<JournalHeader>(&line).ok())
+                .is_some_and(|h| h == header);
+            if same_configuration {
+                // the last line of the journal of a killed analysis may be truncated: the lines
+                // that can't be read are ignored, and the most recent entry of a file is kept.
+                for line in lines.map_while(|line| line.ok()) {
+                    if let Ok(entry) = serde_json::from_str::<JournalEntry>(&line) {
+                        entries.insert((entry.language, entry.path.clone()), entry);
+                    }
+                }
+            }
+        }
+
+        // the journal is rewritten with the entries that were kept, so that new entries are
+        // never appended to a truncated line.
+        let file = File::create(path)
+            .with_context(|| format!("cannot create the journal {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}", serde_json::to_string(&header)?)?;
+        for entry in entries.values() {
+            writeln!(writer, "{}", serde_json::to_string(entry)?)?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("cannot write the journal {}", path.display()))?;
+
+        Ok(Self {
+            entries,
+            writer: Mutex::new(writer),
+            reused: AtomicUsize::new(0),
+        })
+    }
+
+    /// Returns the results of a file that was already analyzed, if its content didn't change.
+    pub fn completed(
+        &self,
+        language: Language,
+        path: &str,
+        content: &str,
+    ) -> Option<Vec<RuleResult>> {
+        let entry = self.entries.get(&(language, path.to_string()))?;
+        if entry.hash != hash(content) {
+            return None;
+        }
+        self.reused.fetch_add(1, Ordering::Relaxed);
+        Some(entry.results.clone())
+    }
+
+    /// Appends the results of a file to the journal. The results of a file whose analysis was
+    /// cancelled, or where a rule timed out, are incomplete and are not recorded: the file is
+    /// analyzed again when the analysis is resumed.
+    pub fn record(
+        &self,
+        language: Language,
+        path: &str,
+        content: &str,
+        results: &[RuleResult],
+    ) -> Result<()> {
+        if results
+            .iter()
+            .any(|r| r.is_cancelled() || r.errors.iter().any(|e| e == ERROR_RULE_TIMEOUT))
+        {
+            return Ok(());
+        }
+        let line = serde_json::to_string(&JournalEntry {
+            language,
+            path: path.to_string(),
+            hash: hash(content),
+            results: results.to_vec(),
+        })?;
+        let mut writer = self.writer.lock().unwrap();
+        writeln!(writer, "{}", line)?;
+        // the entry must be on the disk before the analysis can be killed
+        writer.flush().context("cannot write the journal")
+    }
+
+    /// Returns the number of files whose results were reused from the journal.
+    pub fn reused(&self) -> usize {
+        self.reused.load(Ordering::Relaxed)
+    }
+
+    /// Removes the journal once the analysis is complete.
+    pub fn remove(self, path: &Path) -> Result<()> {
+        drop(self.writer);
+        fs::remove_file(path)
+            .with_context(|| format!("cannot remove the journal {}", path.display()))
+    }
+}
+
+fn hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rule_result;
+
+    fn result(filename: &str) -> RuleResult {
+        rule_result("ruleset/rule", filename, vec![])
+    }
+
+    #[test]
+    fn resume_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.jsonl");
+
+        let journal = ScanJournal::open(&path, "config").unwrap();
+        journal
+            .record(Language::Python, "a.py", "a = 1", &[result("a.py")])
+            .unwrap();
+        journal
+            .record(
+                Language::Python,
+                "b.py",
+                "b = 1",
+                &[RuleResult::cancelled("ruleset/rule", "b.py")],
+            )
+            .unwrap();
+        let mut timed_out = result("c.py");
+        timed_out.errors = vec![ERROR_RULE_TIMEOUT.to_string()];
+        journal
+            .record(Language::Python, "c.py", "c = 1", &[timed_out])
+            .unwrap();
+        drop(journal);
+        // the analysis was killed while writing an entry
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{{\"language\":\"PYTHON\",\"pa").unwrap();
+        drop(file);
+
+        let journal = ScanJournal::open(&path, "config").unwrap();
+        let results = journal
+            .completed(Language::Python, "a.py", "a = 1")
+            .unwrap();
+        assert_eq!(results[0].filename, "a.py");
+        // the content of the file changed
+        assert!(journal
+            .completed(Language::Python, "a.py", "a = 2")
+            .is_none());
+        // the analysis of the file was cancelled
+        assert!(journal
+            .completed(Language::Python, "b.py", "b = 1")
+            .is_none());
+        // a rule timed out on the file
+        assert!(journal
+            .completed(Language::Python, "c.py", "c = 1")
+            .is_none());
+        assert_eq!(journal.reused(), 1);
+        drop(journal);
+
+        // the results of another configuration are discarded
+        let journal = ScanJournal::open(&path, "other config").unwrap();
+        assert!(journal
+            .completed(Language::Python, "a.py", "a = 1")
+            .is_none());
+    }
+}
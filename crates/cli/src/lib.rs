@@ -1,11 +1,38 @@
+pub mod blame;
+pub mod changed_files;
+pub mod codeowners;
 pub mod config_file;
 pub mod constants;
 pub mod csv;
 pub mod datadog_utils;
+pub mod dedup;
 pub mod file_utils;
-mod git_utils;
+pub mod fixes;
+pub mod git_utils;
+pub mod gitignore;
+pub mod html;
+pub mod journal;
+pub mod local_rules;
 pub mod model;
+pub mod output;
+pub mod plugins;
+pub mod policies;
+pub mod pretty;
+pub mod progress;
+pub mod publishers;
+pub mod result_cache;
+pub mod rule_errors;
+pub mod rule_lint;
 pub mod rule_utils;
 pub mod sarif;
+pub mod schema;
 pub mod secrets;
+pub mod secrets_allowlist;
+pub mod severity_remaps;
+pub mod shard;
+pub mod snippets;
+pub mod spill;
+#[cfg(test)]
+mod test_utils;
+pub mod text_style;
 pub mod violations_table;
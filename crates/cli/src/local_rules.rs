@@ -0,0 +1,337 @@
+//! Rules written in a local directory, before they are published: each ruleset is a directory
+//! with one directory per rule, which holds the metadata of the rule (`rule.yaml`), its
+//! tree-sitter query (`query.scm`), its code (`rule.js`) and its test files (`tests/`).
+//!
+//! ```text
+//! rules/
+//! └── my-ruleset/
+//!     └── my-rule/
+//!         ├── rule.yaml
+//!         ├── query.scm
+//!         ├── rule.js
+//!         └── tests/
+//!             ├── compliant.py
+//!             └── noncompliant.py
+//! ```
+
+use crate::file_utils::get_extensions_for_language;
+use anyhow::{anyhow, Context, Result};
+use kernel::model::common::Language;
+use kernel::model::rule::{Prerequisite, Rule, RuleCategory, RuleSeverity, RuleType};
+use kernel::model::rule_test::RuleTest;
+use kernel::model::ruleset::RuleSet;
+use kernel::utils::encode_base64_string;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const METADATA_FILE: &str = "rule.yaml";
+const QUERY_FILE: &str = "query.scm";
+const CODE_FILE: &str = "rule.js";
+const TESTS_DIRECTORY: &str = "tests";
+
+/// The metadata of a rule, in `rule.yaml`. The name of the rule is the name of its directory.
+#[derive(Serialize, Deserialize)]
+struct RuleMetadata {
+    short_description: String,
+    description: String,
+    category: RuleCategory,
+    severity: RuleSeverity,
+    language: Language,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cwe: Option<String>,
+    /// The files of the `tests` directory, with the number of violations expected in each.
+    #[serde(default)]
+    tests: Vec<TestMetadata>,
+    /// The text the files must contain for the rule to be executed on them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    prerequisites: Vec<Prerequisite>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TestMetadata {
+    filename: String,
+    annotation_count: u32,
+}
+
+/// The files of a new rule: an example rule for the languages with one, and an empty rule that
+/// matches every node otherwise.
+struct RuleTemplate {
+    query: &'static str,
+    code: &'static str,
+    /// The content of the test files that have no violation and one violation.
+    compliant: &'static str,
+    noncompliant: Option<&'static str>,
+}
+
+const EMPTY_RULE_CODE: &str = r#"function visit(node, filename, code) {
+  const captured = node.captures["node"];
+  // TODO: report a violation on the captured node, if it's not compliant:
+  // addError(buildError(captured.start.line, captured.start.col, captured.end.line, captured.end.col, "message"));
+}
+"#;
+
+const CALL_RULE_CODE: &str = r#"function visit(node, filename, code) {
+  const call = node.captures["call"];
+  if (call) {
+    // TODO: describe the violation, and how to fix it
+    const error = buildError(
+      call.start.line, call.start.col, call.end.line, call.end.col,
+      "do not call " + getCodeForNode(node.captures["name"], code),
+    );
+    addError(error);
+  }
+}
+"#;
+
+fn template(language: Language) -> RuleTemplate {
+    match language {
+        Language::Python => RuleTemplate {
+            query: "(call function: (identifier) @name (#eq? @name \"eval\")) @call\n",
+            code: CALL_RULE_CODE,
+            compliant: "import ast\n\nast.literal_eval(value)\n",
+            noncompliant: Some("eval(value)\n"),
+        },
+        Language::JavaScript | Language::TypeScript => RuleTemplate {
+            query: "(call_expression function: (identifier) @name (#eq? @name \"eval\")) @call\n",
+            code: CALL_RULE_CODE,
+            compliant: "JSON.parse(value);\n",
+            noncompliant: Some("eval(value);\n"),
+        },
+        Language::Go => RuleTemplate {
+            query: "(call_expression function: (identifier) @name (#eq? @name \"panic\")) @call\n",
+            code: CALL_RULE_CODE,
+            compliant:
+                "package main\n\nimport \"log\"\n\nfunc main() {\n\tlog.Fatal(\"error\")\n}\n",
+            noncompliant: Some("package main\n\nfunc main() {\n\tpanic(\"error\")\n}\n"),
+        },
+        Language::Java => RuleTemplate {
+            query: "(method_invocation name: (identifier) @name (#eq? @name \"exit\")) @call\n",
+            code: CALL_RULE_CODE,
+            compliant: "class Main {\n    void stop() {\n        return;\n    }\n}\n",
+            noncompliant: Some(
+                "class Main {\n    void stop() {\n        System.exit(1);\n    }\n}\n",
+            ),
+        },
+        _ => RuleTemplate {
+            query: "(_) @node\n",
+            code: EMPTY_RULE_CODE,
+            compliant: "",
+            noncompliant: None,
+        },
+    }
+}
+
+/// Splits the name of a rule (`ruleset/rule`) into the names of its ruleset and of the rule,
+/// which are the names of their directories.
+fn split_rule_name(name: &str) -> Result<(&str, &str)> {
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    match name.split_once('/') {
+        Some((ruleset, rule)) if valid(ruleset) && valid(rule) => Ok((ruleset, rule)),
+        _ => Err(anyhow!(
+            "invalid rule name {name}: expected <ruleset>/<rule>, with letters, digits, - and _"
+        )),
+    }
+}
+
+/// Creates the directory of a new rule (`ruleset/rule`) in `directory`, with an example query,
+/// code and test files. Returns the files that were created.
+pub fn scaffold_rule(directory: &Path, language: Language, name: &str) -> Result<Vec<PathBuf>> {
+    let (ruleset, rule) = split_rule_name(name)?;
+    let rule_directory = directory.join(ruleset).join(rule);
+    if rule_directory.exists() {
+        return Err(anyhow!(
+            "the rule {name} already exists in {}",
+            rule_directory.display()
+        ));
+    }
+    let tests_directory = rule_directory.join(TESTS_DIRECTORY);
+    fs::create_dir_all(&tests_directory)
+        .with_context(|| format!("cannot create {}", tests_directory.display()))?;
+
+    let template = template(language);
+    let extension = get_extensions_for_language(&language)
+        .and_then(|extensions| extensions.into_iter().next())
+        .unwrap_or_else(|| language.to_string());
+    let mut test_files = vec![(format!("compliant.{extension}"), template.compliant, 0)];
+    if let Some(noncompliant) = template.noncompliant {
+        test_files.push((format!("noncompliant.{extension}"), noncompliant, 1));
+    }
+    let metadata = RuleMetadata {
+        short_description: format!("TODO: a short description of {name}"),
+        description: "TODO: what the rule detects, why it matters and how to fix it.".to_string(),
+        category: RuleCategory::BestPractices,
+        severity: RuleSeverity::Warning,
+        language,
+        cwe: None,
+        tests: test_files
+            .iter()
+            .map(|(filename, _, annotation_count)| TestMetadata {
+                filename: filename.clone(),
+                annotation_count: *annotation_count,
+            })
+            .collect(),
+        prerequisites: vec![],
+    };
+
+    let mut files = vec![
+        (
+            rule_directory.join(METADATA_FILE),
+            serde_yaml::to_string(&metadata)?,
+        ),
+        (rule_directory.join(QUERY_FILE), template.query.to_string()),
+        (rule_directory.join(CODE_FILE), template.code.to_string()),
+    ];
+    for (filename, content, _) in test_files {
+        files.push((tests_directory.join(filename), content.to_string()));
+    }
+    for (path, content) in &files {
+        fs::write(path, content).with_context(|| format!("cannot write {}", path.display()))?;
+    }
+    Ok(files.into_iter().map(|(path, _)| path).collect())
+}
+
+/// Returns the rulesets of a directory of local rules, sorted by name.
+pub fn get_rulesets_from_directory(directory: &Path) -> Result<Vec<RuleSet>> {
+    let mut rulesets = vec![];
+    for ruleset_directory in sorted_subdirectories(directory)? {
+        let ruleset_name = file_name(&ruleset_directory);
+        let mut rules = vec![];
+        for rule_directory in sorted_subdirectories(&ruleset_directory)? {
+            // the other directories of a ruleset (e.g. shared documentation) are not rules
+            if rule_directory.join(METADATA_FILE).is_file() {
+                let name = format!("{}/{}", ruleset_name, file_name(&rule_directory));
+                rules.push(
+                    read_rule(&rule_directory, name.clone())
+                        .with_context(|| format!("cannot read the rule {name}"))?,
+                );
+            }
+        }
+        if !rules.is_empty() {
+            rulesets.push(RuleSet {
+                name: ruleset_name,
+                description: None,
+                rules,
+            });
+        }
+    }
+    Ok(rulesets)
+}
+
+fn read_rule(directory: &Path, name: String) -> Result<Rule> {
+    let read = |path: PathBuf| {
+        fs::read_to_string(&path).with_context(|| format!("cannot read {}", path.display()))
+    };
+    let metadata: RuleMetadata = serde_yaml::from_str(&read(directory.join(METADATA_FILE))?)?;
+    let tests = metadata
+        .tests
+        .iter()
+        .map(|test| {
+            Ok(RuleTest {
+                annotation_count: test.annotation_count,
+                filename: test.filename.clone(),
+                code_base64: encode_base64_string(read(
+                    directory.join(TESTS_DIRECTORY).join(&test.filename),
+                )?),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut rule = Rule {
+        name,
+        short_description_base64: Some(encode_base64_string(metadata.short_description)),
+        description_base64: Some(encode_base64_string(metadata.description)),
+        category: metadata.category,
+        severity: metadata.severity,
+        language: metadata.language,
+        rule_type: RuleType::TreeSitterQuery,
+        entity_checked: None,
+        code_base64: encode_base64_string(read(directory.join(CODE_FILE))?),
+        cwe: metadata.cwe,
+        checksum: String::new(),
+        pattern: None,
+        tree_sitter_query_base64: Some(encode_base64_string(read(directory.join(QUERY_FILE))?)),
+        tree_sitter_queries: vec![],
+        arguments: vec![],
+        tests,
+        is_testing: false,
+        deprecated: false,
+        replaced_by: None,
+        allow_side_effects: false,
+        prerequisites: metadata.prerequisites,
+    };
+    rule.checksum = rule.compute_checksum();
+    Ok(rule)
+}
+
+fn sorted_subdirectories(directory: &Path) -> Result<Vec<PathBuf>> {
+    let mut subdirectories = fs::read_dir(directory)
+        .with_context(|| format!("cannot read the directory {}", directory.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+    subdirectories.sort();
+    Ok(subdirectories)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::utils::decode_base64_string;
+
+    #[test]
+    fn scaffold_and_load_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = scaffold_rule(dir.path(), Language::Python, "my-ruleset/my-rule").unwrap();
+        assert_eq!(files.len(), 5);
+        assert!(dir
+            .path()
+            .join("my-ruleset/my-rule/tests/noncompliant.py")
+            .is_file());
+        // an existing rule is not overwritten
+        assert!(scaffold_rule(dir.path(), Language::Python, "my-ruleset/my-rule").is_err());
+        scaffold_rule(dir.path(), Language::Yaml, "my-ruleset/other-rule").unwrap();
+
+        let rulesets = get_rulesets_from_directory(dir.path()).unwrap();
+        assert_eq!(rulesets.len(), 1);
+        assert_eq!(rulesets[0].name, "my-ruleset");
+        let rules = &rulesets[0].rules;
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].name, "my-ruleset/my-rule");
+        assert_eq!(rules[0].language, Language::Python);
+        assert!(rules[0].verify_checksum());
+        assert!(rules[0].to_rule_internal().is_ok());
+        assert_eq!(rules[0].tests.len(), 2);
+        assert_eq!(rules[0].tests[1].filename, "noncompliant.py");
+        assert_eq!(rules[0].tests[1].annotation_count, 1);
+        assert_eq!(
+            decode_base64_string(rules[0].tests[1].code_base64.clone()).unwrap(),
+            "eval(value)\n"
+        );
+        // a language without an example rule gets an empty rule
+        assert_eq!(rules[1].name, "my-ruleset/other-rule");
+        assert_eq!(rules[1].tests.len(), 1);
+        assert!(rules[1].to_rule_internal().is_ok());
+    }
+
+    #[test]
+    fn invalid_rule_names() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["my-rule", "/my-rule", "my-ruleset/", "a/b/c", "../my-rule"] {
+            assert!(
+                scaffold_rule(dir.path(), Language::Python, name).is_err(),
+                "{name}"
+            );
+        }
+    }
+}
@@ -1,16 +1,39 @@
 use anyhow::anyhow;
 use git2::Repository;
 use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 
 use crate::git_utils::get_branch;
+use crate::output::OutputSpec;
 use kernel::arguments::ArgumentProvider;
-use kernel::model::common::OutputFormat;
-use kernel::model::config_file::PathConfig;
+use kernel::model::config_file::{FileLanguagesConfig, PathConfig};
 use kernel::model::rule::Rule;
 use kernel::path_restrictions::PathRestrictions;
+use kernel::plugin::PluginRegistry;
 
 use crate::model::datadog_api::DiffAwareRequestArguments;
 
+/// How the file walker handles symbolic links.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SymlinkBehavior {
+    /// Symbolic links are never followed and are reported as skipped.
+    #[default]
+    Skip,
+    /// Symbolic links are followed when they resolve inside the repository.
+    /// Links that create a cycle are reported as skipped.
+    Follow,
+}
+
+/// How files whose content is not valid UTF-8 are handled.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum NonUtf8Behavior {
+    /// The file is not analyzed and is reported as skipped.
+    #[default]
+    Skip,
+    /// Invalid sequences are replaced with U+FFFD and a warning is printed.
+    Lossy,
+}
+
 /// represents the CLI configuration
 #[derive(Clone)]
 pub struct CliConfiguration {
@@ -21,8 +44,8 @@ pub struct CliConfiguration {
     pub source_subdirectories: Vec<String>,
     pub path_config: PathConfig,
     pub rules_file: Option<String>,
-    pub output_format: OutputFormat, // SARIF or JSON
-    pub output_file: String,
+    /// The reports written at the end of the analysis.
+    pub outputs: Vec<OutputSpec>,
     pub num_cpus: usize, // of cpus to use for parallelism
     pub rules: Vec<Rule>,
     pub path_restrictions: PathRestrictions,
@@ -34,6 +57,14 @@ pub struct CliConfiguration {
     pub validate_secrets: bool,
     pub secrets_rule_file: Option<std::path::PathBuf>,
     pub ignore_generated_files: bool,
+    /// The rules that analyze generated files, even if `ignore_generated_files` is true.
+    pub generated_files_rules: HashSet<String>,
+    pub symlink_behavior: SymlinkBehavior,
+    pub non_utf8_behavior: NonUtf8Behavior,
+    /// The file names and extensions mapped to a language by the configuration file.
+    pub file_languages: FileLanguagesConfig,
+    /// The checkers of the loaded plugins.
+    pub plugins: PluginRegistry,
 }
 
 impl CliConfiguration {
@@ -53,7 +84,7 @@ impl CliConfiguration {
         rules_string.sort();
 
         // println!("rules string: {}", rules_string.join("|"));
-        let full_config_string = format!(
+        let mut full_config_string = format!(
             "{}:{}:{}:{}::{}:{}",
             self.path_config.ignore.join(","),
             self.path_config
@@ -65,6 +96,31 @@ impl CliConfiguration {
             self.max_file_size_kb,
             self.source_subdirectories.join(",")
         );
+        // The mapping of the languages is only part of the digest when it is configured, so that
+        // the digest of the other configurations doesn't change.
+        if !self.file_languages.is_empty() {
+            full_config_string.push_str(&format!(":{:?}", self.file_languages));
+        }
+        // Same for the rules that are executed on generated files.
+        if !self.generated_files_rules.is_empty() {
+            let mut rules = self
+                .generated_files_rules
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            rules.sort_unstable();
+            full_config_string.push_str(&format!(":generated={}", rules.join(",")));
+        }
+        // Same for the checkers of the plugins, which are identified by the digest of their plugin.
+        let mut checkers = self
+            .plugins
+            .checker_digests()
+            .map(|(name, digest)| format!("{name}@{digest}"))
+            .collect::<Vec<_>>();
+        if !checkers.is_empty() {
+            checkers.sort_unstable();
+            full_config_string.push_str(&format!(":{}", checkers.join(",")));
+        }
         // compute the hash using sha2
         format!("{:x}", Sha256::digest(full_config_string.as_bytes()))
     }
@@ -122,14 +178,14 @@ impl CliConfiguration {
 #[cfg(test)]
 mod tests {
     use kernel::model::common::Language;
-    use kernel::model::common::OutputFormat::Sarif;
     use kernel::model::rule::{RuleCategory, RuleSeverity, RuleType};
 
     use super::*;
+    use crate::result_cache::{DirectoryStore, ResultCache};
+    use crate::test_utils::rule_result;
 
-    #[test]
-    fn test_generate_diff_aware_hash() {
-        let cli_configuration = CliConfiguration {
+    fn configuration() -> CliConfiguration {
+        CliConfiguration {
             use_debug: true,
             use_configuration_file: true,
             ignore_gitignore: true,
@@ -137,8 +193,7 @@ mod tests {
             source_subdirectories: vec![],
             path_config: PathConfig::default(),
             rules_file: None,
-            output_format: Sarif, // SARIF or JSON
-            output_file: "foo".to_string(),
+            outputs: vec![],
             num_cpus: 2, // of cpus to use for parallelism
             rules: vec![Rule {
                 name: "myrule".to_string(),
@@ -154,9 +209,14 @@ mod tests {
                 pattern: None,
                 cwe: None,
                 tree_sitter_query_base64: None,
+                tree_sitter_queries: vec![],
                 arguments: vec![],
                 tests: vec![],
                 is_testing: false,
+                deprecated: false,
+                replaced_by: None,
+                allow_side_effects: false,
+                prerequisites: vec![],
             }],
             path_restrictions: PathRestrictions::default(),
             argument_provider: ArgumentProvider::new(),
@@ -167,10 +227,50 @@ mod tests {
             validate_secrets: false,
             secrets_rule_file: None,
             ignore_generated_files: false,
-        };
+            generated_files_rules: HashSet::new(),
+            symlink_behavior: SymlinkBehavior::Skip,
+            non_utf8_behavior: NonUtf8Behavior::Skip,
+            file_languages: FileLanguagesConfig::default(),
+            plugins: PluginRegistry::default(),
+        }
+    }
+
+    #[test]
+    fn test_generate_diff_aware_hash() {
         assert_eq!(
-            cli_configuration.generate_diff_aware_digest(),
+            configuration().generate_diff_aware_digest(),
             "aadc07afa2ab7afb253e52a9be80bf7a756f953ce1f6de80f8717f0fa9584360"
         );
     }
+
+    /// The rules executed on generated files and the rules allowed to have side effects change
+    /// the results, so the results cached with another configuration are not reused.
+    #[test]
+    fn digest_changes_results() {
+        let default = configuration();
+        let mut generated = configuration();
+        generated.generated_files_rules.insert("myrule".to_string());
+        let mut side_effects = configuration();
+        side_effects.rules[0].allow_side_effects = true;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = |configuration: &CliConfiguration| {
+            ResultCache::new(
+                Box::new(DirectoryStore::new(dir.path())),
+                &configuration.generate_diff_aware_digest(),
+                false,
+            )
+        };
+        let result = rule_result("myrule", "a.py", vec![]);
+        cache(&default).put(Language::Python, "a.py", "a = 1", &[result]);
+        assert!(cache(&default)
+            .get(Language::Python, "a.py", "a = 1")
+            .is_some());
+        assert!(cache(&generated)
+            .get(Language::Python, "a.py", "a = 1")
+            .is_none());
+        assert!(cache(&side_effects)
+            .get(Language::Python, "a.py", "a = 1")
+            .is_none());
+    }
 }
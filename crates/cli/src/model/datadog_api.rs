@@ -1,5 +1,7 @@
 use kernel::model::common::Language;
-use kernel::model::rule::{Argument, EntityChecked, Rule, RuleCategory, RuleSeverity, RuleType};
+use kernel::model::rule::{
+    Argument, EntityChecked, NamedQuery, Prerequisite, Rule, RuleCategory, RuleSeverity, RuleType,
+};
 use kernel::model::rule_test::RuleTest;
 use kernel::model::ruleset::RuleSet;
 use serde::{Deserialize, Serialize};
@@ -92,6 +94,32 @@ pub struct ApiResponseDefaultRuleset {
     pub data: ApiDefaultRuleset,
 }
 
+// Data for the secrets rules
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiSecretRule {
+    pub id: String,
+    /// The YAML definition of the rule, in the format of a secrets rule file.
+    pub definition: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiSecretRulesAttributes {
+    pub version: String,
+    pub rules: Vec<ApiSecretRule>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiSecretRules {
+    pub id: String,
+    pub attributes: ApiSecretRulesAttributes,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiResponseSecretRules {
+    pub data: ApiSecretRules,
+}
+
 // Data for the rules
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -110,6 +138,8 @@ pub struct ApiResponseRule {
     pub code: String,
     pub language: Language,
     pub tree_sitter_query: Option<String>,
+    #[serde(default)]
+    pub tree_sitter_queries: Vec<ApiResponseNamedQuery>,
     #[serde(rename = "type")]
     pub rule_type: RuleType,
     pub entity_checked: Option<EntityChecked>,
@@ -122,6 +152,14 @@ pub struct ApiResponseRule {
     pub category: RuleCategory,
     pub tests: Vec<ApiResponseRuleTest>,
     pub is_testing: bool,
+    #[serde(default)]
+    pub deprecated: bool,
+    #[serde(default)]
+    pub replaced_by: Option<String>,
+    #[serde(default)]
+    pub allow_side_effects: bool,
+    #[serde(default)]
+    pub prerequisites: Vec<Prerequisite>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -130,6 +168,12 @@ pub struct ApiResponseArgument {
     pub description: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiResponseNamedQuery {
+    pub name: String,
+    pub query: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ApiResponseRulesetAttributes {
     pub name: String,
@@ -163,6 +207,14 @@ impl APIResponseRuleset {
                     severity: rule_from_api.severity,
                     pattern: rule_from_api.pattern,
                     tree_sitter_query_base64: rule_from_api.tree_sitter_query,
+                    tree_sitter_queries: rule_from_api
+                        .tree_sitter_queries
+                        .into_iter()
+                        .map(|q| NamedQuery {
+                            name: q.name,
+                            query_base64: q.query,
+                        })
+                        .collect(),
                     arguments: rule_from_api
                         .arguments
                         .unwrap_or_default()
@@ -182,6 +234,10 @@ impl APIResponseRuleset {
                         })
                         .collect(),
                     is_testing: rule_from_api.is_testing,
+                    deprecated: rule_from_api.deprecated,
+                    replaced_by: rule_from_api.replaced_by,
+                    allow_side_effects: rule_from_api.allow_side_effects,
+                    prerequisites: rule_from_api.prerequisites,
                 })
                 .collect(),
             None => Vec::new(),
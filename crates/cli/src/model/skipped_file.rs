@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The reason why a file found in the repository was not analyzed.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The file is a symbolic link and links are not followed.
+    Symlink,
+    /// The symbolic link points to one of its ancestors.
+    SymlinkCycle,
+    /// The symbolic link resolves to a path outside of the repository.
+    SymlinkOutsideRepository,
+    /// The path of the file is not valid UTF-8.
+    NonUtf8Path,
+    /// The content of the file is not valid UTF-8.
+    NonUtf8,
+    /// The file could not be read.
+    Unreadable,
+    /// The content of the file is binary.
+    Binary,
+    /// The file is larger than the maximum file size.
+    TooLarge,
+    /// The extension of the file is not one that is analyzed.
+    UnsupportedExtension,
+}
+
+impl SkipReason {
+    /// Returns true if the file was excluded by the file policy, but can still be scanned by
+    /// secrets rules that opt into scanning ineligible files.
+    pub fn is_overridable(&self) -> bool {
+        matches!(self, Self::TooLarge)
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Symlink => "symbolic link",
+            Self::SymlinkCycle => "symbolic link cycle",
+            Self::SymlinkOutsideRepository => "symbolic link outside of the repository",
+            Self::NonUtf8Path => "path is not valid UTF-8",
+            Self::NonUtf8 => "content is not valid UTF-8",
+            Self::Unreadable => "cannot read file",
+            Self::Binary => "binary file",
+            Self::TooLarge => "file too large",
+            Self::UnsupportedExtension => "unsupported file extension",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A file that was excluded from the analysis, along with the reason it was excluded.
+/// The path is relative to the root of the repository.
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: SkipReason,
+}
+
+impl SkippedFile {
+    pub fn new(path: impl Into<String>, reason: SkipReason) -> Self {
+        Self {
+            path: path.into(),
+            reason,
+        }
+    }
+}
@@ -0,0 +1,400 @@
+//! The outputs of an analysis: the reports written to a file (or to the standard output) in one of
+//! the supported formats, the platforms the violations are published to, and the upload to Datadog.
+
+use crate::csv;
+use crate::datadog_utils::{upload_sarif_report, UploadMetadata};
+use crate::html;
+use crate::pretty;
+use crate::publishers::Publisher;
+use crate::sarif::sarif_utils::{
+    generate_sarif_report, SarifReportMetadata, SarifRule, SarifRuleResult,
+};
+use crate::secrets::{SecretResult, SecretRule};
+use anyhow::{anyhow, Context, Result};
+use kernel::model::common::{ColumnUnit, OutputFormat};
+use kernel::model::rule::{Rule, RuleResult};
+use std::io::{BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::{env, fs};
+
+/// The path of an output that is written to the standard output.
+pub const STDOUT: &str = "-";
+
+/// The warning at the top of the readable report of an interrupted analysis.
+const INCOMPLETE_WARNING: &str =
+    "The analysis was interrupted: the files that were not analyzed are not reported.";
+
+/// A report written in a format to a file, as specified with `--output [<format>=]<path>`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputSpec {
+    pub format: OutputFormat,
+    /// The file the report is written to, or [`STDOUT`].
+    pub path: String,
+}
+
+impl OutputSpec {
+    /// Parses the value of an `--output` option: either `<format>=<path>`, or a path alone, which
+    /// is written in `default_format`.
+    pub fn parse(value: &str, default_format: &OutputFormat) -> Result<Self> {
+        let (format, path) = match value
+            .split_once('=')
+            .and_then(|(name, path)| Some((output_format_from_name(name)?, path)))
+        {
+            Some((format, path)) => (format, path),
+            None => (default_format.clone(), value),
+        };
+        if path.is_empty() {
+            return Err(anyhow!("no file specified for the output {value}"));
+        }
+        Ok(Self {
+            format,
+            path: path.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for OutputSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", output_format_name(&self.format), self.path)
+    }
+}
+
+/// Returns the output format with the name used on the command line (e.g. `sarif`).
+pub fn output_format_from_name(name: &str) -> Option<OutputFormat> {
+    match name {
+        "csv" => Some(OutputFormat::Csv),
+        "html" => Some(OutputFormat::Html),
+        "json" => Some(OutputFormat::Json),
+        "pretty" => Some(OutputFormat::Pretty),
+        "sarif" => Some(OutputFormat::Sarif),
+        _ => None,
+    }
+}
+
+/// Returns the name of an output format on the command line.
+pub fn output_format_name(format: &OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Csv => "csv",
+        OutputFormat::Html => "html",
+        OutputFormat::Json => "json",
+        OutputFormat::Pretty => "pretty",
+        OutputFormat::Sarif => "sarif",
+    }
+}
+
+/// The results of an analysis, with everything the outputs need to report them.
+pub struct AnalysisReport<'a> {
+    pub rule_results: &'a [RuleResult],
+    pub rules: &'a [Rule],
+    pub secrets: &'a [SecretResult],
+    pub secret_rules: &'a [SecretRule],
+    /// The directory that was analyzed.
+    pub directory: &'a str,
+    /// The unit of the columns of the violations.
+    pub column_unit: ColumnUnit,
+    pub sarif_metadata: SarifReportMetadata,
+    /// Whether the analysis was interrupted before all the files were analyzed.
+    pub incomplete: bool,
+}
+
+/// A destination of the results of an analysis.
+pub trait OutputSink {
+    /// The name of the destination, shown to the user.
+    fn name(&self) -> String;
+
+    /// Writes the results of the analysis to the destination.
+    fn write(&self, report: &AnalysisReport) -> Result<()>;
+}
+
+/// Writes the report to a file, or to the standard output.
+pub struct FileSink(pub OutputSpec);
+
+impl OutputSink for FileSink {
+    fn name(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn write(&self, report: &AnalysisReport) -> Result<()> {
+        // the pretty report is only colored in a terminal that doesn't disable colors.
+        let color = self.0.path == STDOUT
+            && std::io::stdout().is_terminal()
+            && env::var_os("NO_COLOR").is_none();
+        let content = render_report(&self.0.format, report, color)?;
+        if self.0.path == STDOUT {
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(content.as_bytes())
+                .and_then(|_| stdout.flush())
+                .context("error when writing results")
+        } else {
+            fs::write(&self.0.path, content)
+                .with_context(|| format!("error when writing results to {}", self.0.path))
+        }
+    }
+}
+
+/// Publishes the violations to a platform.
+pub struct PublisherSink(pub Box<dyn Publisher>);
+
+impl OutputSink for PublisherSink {
+    fn name(&self) -> String {
+        self.0.name().to_string()
+    }
+
+    fn write(&self, report: &AnalysisReport) -> Result<()> {
+        let count = self
+            .0
+            .publish(report.rule_results)
+            .with_context(|| format!("cannot publish the violations to {}", self.0.name()))?;
+        println!("Published {count} violation(s) to {}", self.0.name());
+        Ok(())
+    }
+}
+
+/// Uploads the SARIF report to Datadog.
+pub struct UploadSink {
+    pub metadata: UploadMetadata,
+    pub use_staging: bool,
+}
+
+impl OutputSink for UploadSink {
+    fn name(&self) -> String {
+        "datadog".to_string()
+    }
+
+    fn write(&self, report: &AnalysisReport) -> Result<()> {
+        let sarif = render_report(&OutputFormat::Sarif, report, false)?;
+        upload_sarif_report(&sarif, &self.metadata, self.use_staging)
+            .context("cannot upload the results to Datadog")?;
+        println!(
+            "Uploaded the results of {} to Datadog (service {}, env {})",
+            self.metadata.git.commit_sha, self.metadata.service, self.metadata.env
+        );
+        Ok(())
+    }
+}
+
+/// Writes the JSON report of results that are read one at a time (e.g. merged from the files they
+/// were spilled to), without holding all of them in memory. The report is identical to the one
+/// rendered by [`render_report`].
+pub fn write_json_results(
+    path: &str,
+    results: impl Iterator<Item = Result<RuleResult>>,
+    incomplete: bool,
+) -> Result<()> {
+    let destination: Box<dyn Write> = if path == STDOUT {
+        Box::new(std::io::stdout().lock())
+    } else {
+        Box::new(
+            fs::File::create(path)
+                .with_context(|| format!("error when writing results to {}", path))?,
+        )
+    };
+    // the results of an interrupted analysis are wrapped in an object, as in `render_report`
+    let (start, end): (&[u8], &[u8]) = if incomplete {
+        (b"{\"incomplete\":true,\"results\":[", b"]}")
+    } else {
+        (b"[", b"]")
+    };
+    let mut writer = BufWriter::new(destination);
+    writer.write_all(start)?;
+    for (index, result) in results.enumerate() {
+        if index > 0 {
+            writer.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut writer, &result?)
+            .context("error when getting the JSON report")?;
+    }
+    writer.write_all(end)?;
+    writer
+        .flush()
+        .with_context(|| format!("error when writing results to {}", path))
+}
+
+/// Renders the report of an analysis in a format. `color` only applies to the pretty format.
+pub fn render_report(
+    format: &OutputFormat,
+    report: &AnalysisReport,
+    color: bool,
+) -> Result<String> {
+    match format {
+        OutputFormat::Csv => Ok(csv::generate_csv_results(report.rule_results)),
+        OutputFormat::Html => Ok(html::generate_html_report(
+            report.rule_results,
+            Path::new(report.directory),
+            report.column_unit,
+        )),
+        OutputFormat::Pretty => {
+            let content = pretty::generate_pretty_report(
+                report.rule_results,
+                report.secrets,
+                Path::new(report.directory),
+                report.column_unit,
+                color,
+            );
+            if report.incomplete {
+                Ok(format!("{INCOMPLETE_WARNING}\n\n{content}"))
+            } else {
+                Ok(content)
+            }
+        }
+        // the results of an interrupted analysis are wrapped in an object, so that the tools that
+        // read a complete report don't silently read partial results.
+        OutputFormat::Json if report.incomplete => serde_json::to_string(&serde_json::json!({
+            "incomplete": true,
+            "results": report.rule_results,
+        }))
+        .context("error when getting the JSON report"),
+        OutputFormat::Json => {
+            serde_json::to_string(report.rule_results).context("error when getting the JSON report")
+        }
+        OutputFormat::Sarif => {
+            let rules = report
+                .rules
+                .iter()
+                .cloned()
+                .map(SarifRule::from)
+                .chain(report.secret_rules.iter().cloned().map(SarifRule::from))
+                .collect::<Vec<_>>();
+            let results = report
+                .rule_results
+                .iter()
+                .cloned()
+                .map(SarifRuleResult::try_from)
+                .chain(
+                    report
+                        .secrets
+                        .iter()
+                        .cloned()
+                        .map(SarifRuleResult::try_from),
+                )
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(anyhow::Error::msg)?;
+            let sarif = generate_sarif_report(
+                &rules,
+                &results,
+                &report.directory.to_string(),
+                report.sarif_metadata.clone(),
+            )
+            .context("error when generating the SARIF report")?;
+            serde_json::to_string(&sarif).context("error when getting the SARIF report")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_output_spec() {
+        assert_eq!(
+            OutputSpec::parse("sarif=out.sarif", &OutputFormat::Json).unwrap(),
+            OutputSpec {
+                format: OutputFormat::Sarif,
+                path: "out.sarif".to_string()
+            }
+        );
+        // without a format, the default format is used
+        assert_eq!(
+            OutputSpec::parse("out.csv", &OutputFormat::Csv).unwrap(),
+            OutputSpec {
+                format: OutputFormat::Csv,
+                path: "out.csv".to_string()
+            }
+        );
+        // a prefix that isn't a format is part of the path
+        assert_eq!(
+            OutputSpec::parse("a=b.json", &OutputFormat::Json).unwrap(),
+            OutputSpec {
+                format: OutputFormat::Json,
+                path: "a=b.json".to_string()
+            }
+        );
+        assert_eq!(
+            OutputSpec::parse("json=-", &OutputFormat::Sarif).unwrap(),
+            OutputSpec {
+                format: OutputFormat::Json,
+                path: STDOUT.to_string()
+            }
+        );
+        assert!(OutputSpec::parse("sarif=", &OutputFormat::Json).is_err());
+        assert_eq!(
+            OutputSpec::parse("html=report.html", &OutputFormat::Json)
+                .unwrap()
+                .to_string(),
+            "html=report.html"
+        );
+    }
+
+    #[test]
+    fn render_json_report() {
+        let report = AnalysisReport {
+            rule_results: &[],
+            rules: &[],
+            secrets: &[],
+            secret_rules: &[],
+            directory: ".",
+            column_unit: ColumnUnit::Bytes,
+            sarif_metadata: SarifReportMetadata {
+                add_git_info: false,
+                debug: false,
+                config_digest: String::new(),
+                diff_aware_parameters: None,
+                execution_time_secs: 0,
+                incomplete: false,
+            },
+            incomplete: false,
+        };
+        assert_eq!(
+            render_report(&OutputFormat::Json, &report, false).unwrap(),
+            "[]"
+        );
+        let sarif = render_report(&OutputFormat::Sarif, &report, false).unwrap();
+        assert!(sarif.contains("\"version\":\"2.1.0\""));
+
+        let report = AnalysisReport {
+            incomplete: true,
+            ..report
+        };
+        assert_eq!(
+            render_report(&OutputFormat::Json, &report, false).unwrap(),
+            r#"{"incomplete":true,"results":[]}"#
+        );
+    }
+
+    #[test]
+    fn write_streamed_json_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.json");
+        let path = path.to_str().unwrap();
+        let results = vec![
+            RuleResult::cancelled("rs/a", "a.py"),
+            RuleResult::cancelled("rs/b", "b.py"),
+        ];
+        for incomplete in [false, true] {
+            let report = AnalysisReport {
+                rule_results: &results,
+                rules: &[],
+                secrets: &[],
+                secret_rules: &[],
+                directory: ".",
+                column_unit: ColumnUnit::Bytes,
+                sarif_metadata: SarifReportMetadata {
+                    add_git_info: false,
+                    debug: false,
+                    config_digest: String::new(),
+                    diff_aware_parameters: None,
+                    execution_time_secs: 0,
+                    incomplete,
+                },
+                incomplete,
+            };
+            write_json_results(path, results.iter().cloned().map(Ok), incomplete).unwrap();
+            assert_eq!(
+                fs::read_to_string(path).unwrap(),
+                render_report(&OutputFormat::Json, &report, false).unwrap()
+            );
+        }
+    }
+}
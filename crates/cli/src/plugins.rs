@@ -0,0 +1,254 @@
+//! The native plugins: dynamic libraries that register checkers with the analyzer (see
+//! [`kernel::plugin`]).
+//!
+//! A plugin exports the C function `dd_static_analyzer_plugin_v1`, which returns a pointer to a
+//! [`PluginDescriptor`] that lives as long as the library:
+//!
+//! - `checkers` is a JSON list of the checkers of the plugin, with their name and the languages of
+//!   the files they check (e.g. `[{"name": "unused-type", "languages": ["PYTHON"]}]`). The rule
+//!   of the violations of a checker is `<plugin>/<checker>`.
+//! - `check` is called with the name of a checker, the language (as in `checkers`), the path and
+//!   the content of a file, and returns a JSON object with the violations of the file
+//!   (`{"violations": [{"start": {"line": 1, "col": 1}, "end": {...}, "message": "...",
+//!   "severity": "WARNING", "category": "BEST_PRACTICES"}]}`), or with an error
+//!   (`{"error": "..."}`). The string returned is released with `free_string`.
+//!
+//! The strings are UTF-8 and terminated by a NUL byte, except the content of the file. `check` is
+//! called from several threads at the same time.
+
+use anyhow::{anyhow, Context, Result};
+use kernel::model::common::{Language, Position};
+use kernel::model::rule::{RuleCategory, RuleSeverity};
+use kernel::model::violation::{Fix, Violation, ViolationBuilder};
+use kernel::plugin::{Checker, PluginRegistry};
+use libloading::Library;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::ffi::{c_char, CStr, CString};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The version of the ABI of the plugins, which is also the suffix of the entry point.
+const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// The function exported by the plugins.
+const PLUGIN_ENTRY_POINT: &[u8] = b"dd_static_analyzer_plugin_v1\0";
+
+type CheckFn = unsafe extern "C" fn(
+    checker: *const c_char,
+    language: *const c_char,
+    filename: *const c_char,
+    code: *const u8,
+    code_len: usize,
+) -> *mut c_char;
+
+type FreeStringFn = unsafe extern "C" fn(string: *mut c_char);
+
+/// The description of a plugin, returned by its entry point.
+#[repr(C)]
+pub struct PluginDescriptor {
+    /// The version of the ABI implemented by the plugin: [`PLUGIN_ABI_VERSION`].
+    pub abi_version: u32,
+    /// The name of the plugin, used as the ruleset of its checkers.
+    pub name: *const c_char,
+    /// The JSON list of the checkers of the plugin.
+    pub checkers: *const c_char,
+    pub check: CheckFn,
+    pub free_string: FreeStringFn,
+}
+
+#[derive(Deserialize)]
+struct CheckerDescription {
+    name: String,
+    languages: Vec<Language>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CheckOutput {
+    Violations { violations: Vec<PluginViolation> },
+    Error { error: String },
+}
+
+#[derive(Deserialize)]
+struct PluginViolation {
+    start: Position,
+    end: Position,
+    message: String,
+    severity: RuleSeverity,
+    category: RuleCategory,
+    #[serde(default)]
+    fixes: Vec<Fix>,
+}
+
+/// A checker of a native plugin.
+struct NativeChecker {
+    /// The name of the rule of the violations: `<plugin>/<checker>`.
+    name: String,
+    /// The name of the checker, given to the plugin.
+    checker: CString,
+    languages: Vec<Language>,
+    /// The SHA-256 of the library of the plugin.
+    digest: String,
+    check: CheckFn,
+    free_string: FreeStringFn,
+    /// The functions of the plugin are only valid while the library is loaded.
+    _library: Arc<Library>,
+}
+
+impl Checker for NativeChecker {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn languages(&self) -> &[Language] {
+        &self.languages
+    }
+
+    fn digest(&self) -> &str {
+        &self.digest
+    }
+
+    fn check(
+        &self,
+        language: Language,
+        filename: &str,
+        code: &str,
+    ) -> Result<Vec<Violation>, String> {
+        // the language has the name of the descriptions of the checkers
+        let language = serde_json::to_value(language)
+            .ok()
+            .and_then(|v| v.as_str().and_then(|l| CString::new(l).ok()))
+            .ok_or_else(|| "invalid language".to_string())?;
+        let filename = CString::new(filename).map_err(|e| e.to_string())?;
+        // SAFETY: the strings outlive the call, and the plugin returns a NUL-terminated string
+        // that it owns until it's released with `free_string`.
+        let output = unsafe {
+            let output = (self.check)(
+                self.checker.as_ptr(),
+                language.as_ptr(),
+                filename.as_ptr(),
+                code.as_ptr(),
+                code.len(),
+            );
+            if output.is_null() {
+                return Err("the plugin returned no output".to_string());
+            }
+            let text = CStr::from_ptr(output).to_string_lossy().into_owned();
+            (self.free_string)(output);
+            text
+        };
+        match serde_json::from_str::<CheckOutput>(&output) {
+            Ok(CheckOutput::Violations { violations }) => Ok(violations
+                .into_iter()
+                .map(|v| {
+                    ViolationBuilder::default()
+                        .start(v.start)
+                        .end(v.end)
+                        .message(v.message)
+                        .severity(v.severity)
+                        .category(v.category)
+                        .fixes(v.fixes)
+                        .build()
+                        .expect("all the fields of the violation are set")
+                })
+                .collect()),
+            Ok(CheckOutput::Error { error }) => Err(error),
+            Err(err) => Err(format!("invalid output of the plugin: {err}")),
+        }
+    }
+}
+
+/// Loads the plugin at `path`, and registers its checkers. Returns the name of the plugin.
+///
+/// A plugin runs with the permissions of the analyzer: only the plugins of trusted sources must be
+/// loaded.
+pub fn load_plugin(path: &Path, registry: &mut PluginRegistry) -> Result<String> {
+    // SAFETY: loading a library runs its initialization code, which the user trusts by loading it.
+    let library = unsafe { Library::new(path) }
+        .with_context(|| format!("cannot load the plugin {}", path.display()))?;
+    // SAFETY: the entry point has the signature of the ABI, and the descriptor lives as long as
+    // the library.
+    let descriptor = unsafe {
+        let entry_point = library
+            .get::<unsafe extern "C" fn() -> *const PluginDescriptor>(PLUGIN_ENTRY_POINT)
+            .with_context(|| {
+                format!(
+                    "{} is not a plugin of the analyzer (no `dd_static_analyzer_plugin_v1` function)",
+                    path.display()
+                )
+            })?;
+        entry_point()
+            .as_ref()
+            .ok_or_else(|| anyhow!("the plugin {} has no descriptor", path.display()))?
+    };
+    if descriptor.abi_version != PLUGIN_ABI_VERSION {
+        return Err(anyhow!(
+            "the plugin {} implements the version {} of the ABI instead of {}",
+            path.display(),
+            descriptor.abi_version,
+            PLUGIN_ABI_VERSION
+        ));
+    }
+    // SAFETY: the strings of the descriptor are NUL-terminated, and live as long as the library.
+    let (name, checkers) = unsafe {
+        (
+            CStr::from_ptr(descriptor.name).to_str()?.to_string(),
+            CStr::from_ptr(descriptor.checkers).to_str()?.to_string(),
+        )
+    };
+    if name.is_empty() || name.contains('/') {
+        return Err(anyhow!(
+            "invalid name of the plugin {}: {name}",
+            path.display()
+        ));
+    }
+    let checkers = serde_json::from_str::<Vec<CheckerDescription>>(&checkers)
+        .with_context(|| format!("invalid checkers of the plugin {name}"))?;
+    let (check, free_string) = (descriptor.check, descriptor.free_string);
+    // the checkers of another build of the plugin may report other violations
+    let digest = std::fs::read(path)
+        .map(|bytes| format!("{:x}", Sha256::digest(bytes)))
+        .with_context(|| format!("cannot read the plugin {}", path.display()))?;
+
+    let library = Arc::new(library);
+    for checker in checkers {
+        registry.register_checker(Arc::new(NativeChecker {
+            name: format!("{name}/{}", checker.name),
+            checker: CString::new(checker.name)?,
+            languages: checker.languages,
+            digest: digest.clone(),
+            check,
+            free_string,
+            _library: Arc::clone(&library),
+        }));
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_check_output() {
+        let output = r#"{"violations": [{"start": {"line": 1, "col": 1}, "end": {"line": 1, "col": 4}, "message": "unused type", "severity": "WARNING", "category": "BEST_PRACTICES"}]}"#;
+        let Ok(CheckOutput::Violations { violations }) = serde_json::from_str(output) else {
+            panic!("the violations are not parsed");
+        };
+        assert_eq!(violations[0].message, "unused type");
+        assert!(violations[0].fixes.is_empty());
+        assert!(matches!(
+            serde_json::from_str(r#"{"error": "no language server"}"#),
+            Ok(CheckOutput::Error { error }) if error == "no language server"
+        ));
+    }
+
+    #[test]
+    fn load_invalid_plugin() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("libplugin.so");
+        std::fs::write(&path, "not a library").unwrap();
+        assert!(load_plugin(&path, &mut PluginRegistry::new()).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use kernel::model::config_file::PolicyConfig;
+use kernel::model::rule::RuleResult;
+use serde::Serialize;
+
+/// The outcome of a policy of the configuration file, as written in the policy report.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PolicyResult {
+    pub name: String,
+    pub violations: u64,
+    pub max_violations: u64,
+    pub passed: bool,
+}
+
+/// Counts the violations of the results that each policy applies to.
+pub fn evaluate_policies(policies: &[PolicyConfig], results: &[RuleResult]) -> Vec<PolicyResult> {
+    policies
+        .iter()
+        .map(|policy| {
+            let violations = results
+                .iter()
+                .filter(|result| policy.paths.allows_file(&result.filename))
+                .flat_map(|result| &result.violations)
+                .filter(|v| policy.category.map_or(true, |c| v.category == c))
+                .filter(|v| policy.severity.map_or(true, |s| v.severity == s))
+                .count() as u64;
+            PolicyResult {
+                name: policy.name.clone(),
+                violations,
+                max_violations: policy.max_violations,
+                passed: violations <= policy.max_violations,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rule_result, violation};
+    use kernel::model::common::Position;
+    use kernel::model::config_file::PathConfig;
+    use kernel::model::rule::{RuleCategory, RuleSeverity};
+
+    fn result(filename: &str, violations: &[(RuleSeverity, RuleCategory)]) -> RuleResult {
+        let violations = violations
+            .iter()
+            .map(|(severity, category)| {
+                violation(
+                    Position { line: 1, col: 1 },
+                    Position { line: 1, col: 2 },
+                    *severity,
+                    *category,
+                )
+            })
+            .collect();
+        rule_result("myrule", filename, violations)
+    }
+
+    #[test]
+    fn evaluate() {
+        let results = vec![
+            result(
+                "src/legacy/a.py",
+                &[
+                    (RuleSeverity::Warning, RuleCategory::CodeStyle),
+                    (RuleSeverity::Warning, RuleCategory::Performance),
+                ],
+            ),
+            result(
+                "src/b.py",
+                &[
+                    (RuleSeverity::Error, RuleCategory::Security),
+                    (RuleSeverity::Warning, RuleCategory::Security),
+                ],
+            ),
+        ];
+        let policies = vec![
+            PolicyConfig {
+                name: "no-security-errors".to_string(),
+                category: Some(RuleCategory::Security),
+                severity: Some(RuleSeverity::Error),
+                paths: PathConfig::default(),
+                max_violations: 0,
+            },
+            PolicyConfig {
+                name: "few-legacy-warnings".to_string(),
+                category: None,
+                severity: Some(RuleSeverity::Warning),
+                paths: PathConfig {
+                    only: Some(vec!["src/legacy".to_string().into()]),
+                    ignore: vec![],
+                },
+                max_violations: 2,
+            },
+        ];
+        assert_eq!(
+            evaluate_policies(&policies, &results),
+            vec![
+                PolicyResult {
+                    name: "no-security-errors".to_string(),
+                    violations: 1,
+                    max_violations: 0,
+                    passed: false,
+                },
+                PolicyResult {
+                    name: "few-legacy-warnings".to_string(),
+                    violations: 2,
+                    max_violations: 2,
+                    passed: true,
+                },
+            ]
+        );
+    }
+}
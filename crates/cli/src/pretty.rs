@@ -0,0 +1,418 @@
+//! A report of the violations meant to be read in a terminal, in the style of the diagnostics of
+//! compilers: the location and the code of each violation, a preview of its fixes, and a summary.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use kernel::model::common::{ColumnUnit, LineIndex, Position};
+use kernel::model::rule::{RuleResult, RuleSeverity};
+use kernel::model::violation::{FixApplicability, Violation};
+use prettytable::{row, Table};
+
+use crate::fixes::preview_fix;
+use crate::secrets::{SecretLocation, SecretResult};
+use crate::violations_table::table_format;
+
+/// The maximum number of lines of a violation shown in its code frame.
+const MAX_FRAME_LINES: usize = 5;
+
+/// The severities, from the most to the least severe.
+const SEVERITIES: [RuleSeverity; 4] = [
+    RuleSeverity::Error,
+    RuleSeverity::Warning,
+    RuleSeverity::Notice,
+    RuleSeverity::None,
+];
+
+/// Colors the text with ANSI escape sequences, when enabled.
+#[derive(Copy, Clone)]
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn paint(self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{code}m{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn severity(self, severity: RuleSeverity) -> String {
+        let code = match severity {
+            RuleSeverity::Error => "1;31",
+            RuleSeverity::Warning => "1;33",
+            RuleSeverity::Notice => "1;36",
+            RuleSeverity::None => "1",
+        };
+        self.paint(code, &severity.to_string())
+    }
+
+    fn bold(self, text: &str) -> String {
+        self.paint("1", text)
+    }
+
+    fn gutter(self, text: &str) -> String {
+        self.paint("1;34", text)
+    }
+
+    fn removed(self, text: &str) -> String {
+        self.paint("31", text)
+    }
+
+    fn added(self, text: &str) -> String {
+        self.paint("32", text)
+    }
+}
+
+/// A violation, with the rule that reported it and its file.
+struct Entry<'a> {
+    rule_name: &'a str,
+    filename: &'a str,
+    violation: &'a Violation,
+    /// The other locations of a secret found in several places.
+    other_locations: &'a [SecretLocation],
+}
+
+/// Generates the report of the violations of the rules and of the secrets. `directory` is the
+/// directory the filenames are relative to, and `column_unit` the unit of the columns of the
+/// violations. The report is colored with ANSI escape sequences if `color` is true.
+pub fn generate_pretty_report(
+    rule_results: &[RuleResult],
+    secrets: &[SecretResult],
+    directory: &Path,
+    column_unit: ColumnUnit,
+    color: bool,
+) -> String {
+    let style = Style { color };
+    let entries = rule_results
+        .iter()
+        .flat_map(|result| {
+            result.violations.iter().map(|violation| Entry {
+                rule_name: &result.rule_name,
+                filename: &result.filename,
+                violation,
+                other_locations: &[],
+            })
+        })
+        .chain(secrets.iter().map(|secret| Entry {
+            rule_name: &secret.rule_id,
+            filename: &secret.file_path,
+            violation: &secret.violation,
+            other_locations: &secret.other_locations,
+        }))
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    let mut files: HashMap<&str, Option<String>> = HashMap::new();
+    for entry in &entries {
+        let content = files
+            .entry(entry.filename)
+            .or_insert_with(|| fs::read_to_string(directory.join(entry.filename)).ok());
+        write_violation(&mut out, style, entry, content.as_deref(), column_unit);
+    }
+    write_summary(&mut out, &entries);
+    out
+}
+
+fn write_violation(
+    out: &mut String,
+    style: Style,
+    entry: &Entry,
+    content: Option<&str>,
+    column_unit: ColumnUnit,
+) {
+    let violation = entry.violation;
+    let _ = writeln!(
+        out,
+        "{}{}: {}",
+        style.severity(violation.severity),
+        style.bold(&format!("[{}]", entry.rule_name)),
+        style.bold(&violation.message)
+    );
+
+    let frame = match content {
+        Some(content) if !violation.file_level => code_frame(content, violation, column_unit),
+        _ => vec![],
+    };
+    let width = frame
+        .iter()
+        .map(|(number, _, _)| number.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let margin = " ".repeat(width);
+    if violation.file_level {
+        let _ = writeln!(out, "{margin}{} {}", style.gutter("-->"), entry.filename);
+    } else {
+        let _ = writeln!(
+            out,
+            "{margin}{} {}:{}:{}",
+            style.gutter("-->"),
+            entry.filename,
+            violation.start.line,
+            violation.start.col
+        );
+    }
+
+    if !frame.is_empty() {
+        let _ = writeln!(out, "{margin} {}", style.gutter("|"));
+        for (number, line, carets) in &frame {
+            let _ = writeln!(
+                out,
+                "{} {} {line}",
+                style.gutter(&format!("{number:>width$}")),
+                style.gutter("|")
+            );
+            if let Some(carets) = carets {
+                let _ = writeln!(
+                    out,
+                    "{margin} {} {}",
+                    style.gutter("|"),
+                    style.paint("1;31", carets)
+                );
+            }
+        }
+    }
+
+    for other in entry.other_locations {
+        let _ = writeln!(
+            out,
+            "{margin} {} also in {}:{}:{}",
+            style.gutter("="),
+            other.file_path,
+            other.start.line,
+            other.start.col
+        );
+    }
+
+    if let Some(content) = content {
+        for fix in &violation.fixes {
+            let Some(preview) = preview_fix(content, fix, column_unit) else {
+                continue;
+            };
+            let applicability = match fix.applicability {
+                FixApplicability::Automatic => "automatic",
+                FixApplicability::Suggested => "suggested",
+                FixApplicability::Manual => "manual",
+            };
+            let _ = writeln!(
+                out,
+                "{margin} {} fix ({applicability}): {}",
+                style.gutter("="),
+                fix.description
+            );
+            for line in &preview.removed {
+                let _ = writeln!(out, "{margin} {}", style.removed(&format!("- {line}")));
+            }
+            for line in &preview.added {
+                let _ = writeln!(out, "{margin} {}", style.added(&format!("+ {line}")));
+            }
+        }
+    }
+    out.push('\n');
+}
+
+/// Returns the lines of a violation (at most [`MAX_FRAME_LINES`]) with their number, and the carets
+/// that underline the range of the violation on each line.
+fn code_frame<'a>(
+    content: &'a str,
+    violation: &Violation,
+    column_unit: ColumnUnit,
+) -> Vec<(usize, &'a str, Option<String>)> {
+    let line_index = LineIndex::new(content);
+    let lines = content.lines().collect::<Vec<_>>();
+    let start_line = violation.start.line as usize;
+    let end_line = (violation.end.line as usize).max(start_line);
+    if start_line == 0 || start_line > lines.len() {
+        return vec![];
+    }
+    // the byte offsets of the range of the violation within its first and last lines.
+    let offset_in_line = |position: &Position| {
+        let line_start = line_index.byte_offset(&Position {
+            line: position.line,
+            col: 1,
+        })?;
+        Some(line_index.byte_offset_in(position, column_unit)? - line_start)
+    };
+    let start_offset = offset_in_line(&violation.start);
+    let end_offset = offset_in_line(&violation.end);
+
+    (start_line..=end_line.min(lines.len()))
+        .take(MAX_FRAME_LINES)
+        .map(|number| {
+            let line = lines[number - 1].trim_end_matches('\r');
+            let from = if number == start_line {
+                start_offset
+            } else {
+                Some(0)
+            };
+            let to = if number == end_line {
+                end_offset
+            } else {
+                Some(line.len())
+            };
+            let carets = match (from, to) {
+                (Some(from), Some(to)) if from <= to => {
+                    match (line.get(..from), line.get(from..to)) {
+                        (Some(before), Some(highlighted)) => {
+                            // tabs are kept so that the carets are aligned with the code.
+                            let indent = before
+                                .chars()
+                                .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+                                .collect::<String>();
+                            Some(format!(
+                                "{indent}{}",
+                                "^".repeat(highlighted.chars().count().max(1))
+                            ))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            (number, line, carets)
+        })
+        .collect()
+}
+
+/// Writes the number of violations of each severity, and the number of files they are in.
+fn write_summary(out: &mut String, entries: &[Entry]) {
+    if entries.is_empty() {
+        out.push_str("No violations found\n");
+        return;
+    }
+    let mut table = Table::new();
+    table.set_format(table_format());
+    table.set_titles(row!["severity", "violations", "files"]);
+    for severity in SEVERITIES {
+        let entries = entries
+            .iter()
+            .filter(|entry| entry.violation.severity == severity)
+            .collect::<Vec<_>>();
+        if entries.is_empty() {
+            continue;
+        }
+        let files = entries
+            .iter()
+            .map(|entry| entry.filename)
+            .collect::<HashSet<_>>();
+        table.add_row(row![severity.to_string(), entries.len(), files.len()]);
+    }
+    let files = entries
+        .iter()
+        .map(|entry| entry.filename)
+        .collect::<HashSet<_>>();
+    table.add_row(row!["total", entries.len(), files.len()]);
+    out.push_str(&table.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::model::rule::RuleCategory;
+    use kernel::model::violation::{Edit, EditType, Fix};
+
+    fn violation() -> Violation {
+        Violation {
+            start: Position { line: 2, col: 5 },
+            end: Position { line: 2, col: 14 },
+            message: "do not use eval".into(),
+            severity: RuleSeverity::Error,
+            category: RuleCategory::Security,
+            fixes: vec![Fix {
+                description: "use literal_eval".to_string(),
+                edits: vec![Edit {
+                    start: Position { line: 2, col: 5 },
+                    end: Some(Position { line: 2, col: 9 }),
+                    edit_type: EditType::Update,
+                    content: Some("literal_eval".to_string()),
+                }],
+                applicability: FixApplicability::Suggested,
+            }],
+            merged_rules: vec![],
+            tags: vec![],
+            cwe: vec![],
+            documentation_url: None,
+            related_locations: vec![],
+            file_level: false,
+            fingerprint: None,
+            owners: vec![],
+            snippet: None,
+            offsets: None,
+            last_commit: None,
+        }
+    }
+
+    #[test]
+    fn violation_with_code_frame() {
+        let mut out = String::new();
+        let violation = violation();
+        let entry = Entry {
+            rule_name: "python-security/no-eval",
+            filename: "src/main.py",
+            violation: &violation,
+            other_locations: &[],
+        };
+        write_violation(
+            &mut out,
+            Style { color: false },
+            &entry,
+            Some("a = 1\nb = eval(foo)\nc = 3\n"),
+            ColumnUnit::Bytes,
+        );
+        assert_eq!(
+            out,
+            "\
+error[python-security/no-eval]: do not use eval
+ --> src/main.py:2:5
+  |
+2 | b = eval(foo)
+  |     ^^^^^^^^^
+  = fix (suggested): use literal_eval
+  - b = eval(foo)
+  + b = literal_eval(foo)
+
+"
+        );
+    }
+
+    #[test]
+    fn code_frame_in_utf16() {
+        let mut violation = violation();
+        // "é" is two bytes, but a single UTF-16 code unit.
+        violation.start = Position { line: 1, col: 3 };
+        violation.end = Position { line: 1, col: 6 };
+        let frame = code_frame("é = bar\n", &violation, ColumnUnit::Utf16);
+        assert_eq!(frame, vec![(1, "é = bar", Some("  ^^^".to_string()))]);
+    }
+
+    #[test]
+    fn summary() {
+        let violation = violation();
+        let entries = [
+            Entry {
+                rule_name: "a/b",
+                filename: "a.py",
+                violation: &violation,
+                other_locations: &[],
+            },
+            Entry {
+                rule_name: "a/c",
+                filename: "b.py",
+                violation: &violation,
+                other_locations: &[],
+            },
+        ];
+        let mut out = String::new();
+        write_summary(&mut out, &entries);
+        assert!(out.contains("error"));
+        assert!(out.contains("total"));
+
+        let mut out = String::new();
+        write_summary(&mut out, &[]);
+        assert_eq!(out, "No violations found\n");
+    }
+}
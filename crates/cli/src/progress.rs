@@ -0,0 +1,217 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The minimum time between two heartbeats of the JSON progress.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How the progress of an analysis is reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// A progress bar, only drawn when the standard error is a terminal.
+    Bar,
+    /// A JSON line on the standard error at regular intervals, for the logs of CI jobs.
+    Json,
+    /// No progress.
+    None,
+}
+
+impl TryFrom<&str> for ProgressMode {
+    type Error = &'static str;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "bar" => Ok(ProgressMode::Bar),
+            "json" => Ok(ProgressMode::Json),
+            "none" => Ok(ProgressMode::None),
+            _ => Err("unknown progress mode"),
+        }
+    }
+}
+
+/// A heartbeat of the JSON progress.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Heartbeat<'a> {
+    pub task: &'a str,
+    pub completed: u64,
+    pub total: u64,
+    pub current_file: Option<&'a str>,
+    pub rules_per_sec: f64,
+    pub elapsed_secs: u64,
+    /// The estimated number of seconds before the end of the task, once a file is completed.
+    pub eta_secs: Option<u64>,
+    pub done: bool,
+}
+
+/// The progress of a task on a list of files (e.g. the analysis of the files of a language),
+/// shared by the threads that process the files.
+pub struct Progress {
+    mode: ProgressMode,
+    task: String,
+    bar: Option<ProgressBar>,
+    total: AtomicU64,
+    completed: AtomicU64,
+    rules: AtomicU64,
+    current_file: Mutex<Option<String>>,
+    start: Instant,
+    last_heartbeat: Mutex<Instant>,
+}
+
+impl Progress {
+    pub fn new(mode: ProgressMode, task: impl Into<String>, total: u64) -> Self {
+        let bar = (mode == ProgressMode::Bar).then(|| {
+            let bar = ProgressBar::new(total);
+            bar.set_style(
+                ProgressStyle::with_template("{wide_bar} {pos}/{len} files, ETA {eta} {msg}")
+                    .expect("valid progress template"),
+            );
+            bar
+        });
+        let start = Instant::now();
+        Progress {
+            mode,
+            task: task.into(),
+            bar,
+            total: AtomicU64::new(total),
+            completed: AtomicU64::new(0),
+            rules: AtomicU64::new(0),
+            current_file: Mutex::new(None),
+            start,
+            last_heartbeat: Mutex::new(start),
+        }
+    }
+
+    /// Adds work to the task (e.g. the validation of the candidates of a secrets scan).
+    pub fn inc_length(&self, delta: u64) {
+        self.total.fetch_add(delta, Ordering::Relaxed);
+        if let Some(bar) = &self.bar {
+            bar.inc_length(delta);
+        }
+    }
+
+    /// Records the start of the work on a file.
+    pub fn start_file(&self, path: &str) {
+        if self.mode == ProgressMode::None {
+            return;
+        }
+        *self.current_file.lock().unwrap() = Some(path.to_string());
+        if let Some(bar) = &self.bar {
+            bar.set_message(format!("({:.0} rules/s) {}", self.rules_per_sec(), path));
+        }
+    }
+
+    /// Records the end of the work on a file, where `rules` were executed.
+    pub fn finish_file(&self, rules: u64) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        self.rules.fetch_add(rules, Ordering::Relaxed);
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+        if self.mode == ProgressMode::Json {
+            let mut last_heartbeat = self.last_heartbeat.lock().unwrap();
+            if last_heartbeat.elapsed() >= HEARTBEAT_INTERVAL {
+                *last_heartbeat = Instant::now();
+                self.print_heartbeat(false);
+            }
+        }
+    }
+
+    /// Ends the task.
+    pub fn finish(&self) {
+        match self.mode {
+            ProgressMode::Bar => {
+                if let Some(bar) = &self.bar {
+                    bar.finish_with_message(format!("({:.0} rules/s)", self.rules_per_sec()));
+                }
+            }
+            ProgressMode::Json => self.print_heartbeat(true),
+            ProgressMode::None => {}
+        }
+    }
+
+    fn rules_per_sec(&self) -> f64 {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.rules.load(Ordering::Relaxed) as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+
+    fn print_heartbeat(&self, done: bool) {
+        let current_file = self.current_file.lock().unwrap();
+        let completed = self.completed.load(Ordering::Relaxed);
+        let total = self.total.load(Ordering::Relaxed);
+        let elapsed = self.start.elapsed();
+        let heartbeat = Heartbeat {
+            task: &self.task,
+            completed,
+            total,
+            current_file: if done { None } else { current_file.as_deref() },
+            rules_per_sec: (self.rules_per_sec() * 10.0).round() / 10.0,
+            elapsed_secs: elapsed.as_secs(),
+            eta_secs: estimate_remaining(elapsed, completed, total).map(|eta| eta.as_secs()),
+            done,
+        };
+        if let Ok(line) = serde_json::to_string(&heartbeat) {
+            // the progress must never interrupt the analysis
+            let _ = writeln!(std::io::stderr().lock(), "{}", line);
+        }
+    }
+}
+
+/// Estimates the time to complete the `total` files, from the time spent on the `completed` ones.
+pub fn estimate_remaining(elapsed: Duration, completed: u64, total: u64) -> Option<Duration> {
+    if completed == 0 {
+        return None;
+    }
+    let remaining = total.saturating_sub(completed);
+    Some(elapsed.mul_f64(remaining as f64 / completed as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_mode() {
+        assert_eq!(ProgressMode::try_from("json"), Ok(ProgressMode::Json));
+        assert_eq!(ProgressMode::try_from("BAR"), Ok(ProgressMode::Bar));
+        assert_eq!(ProgressMode::try_from("none"), Ok(ProgressMode::None));
+        assert!(ProgressMode::try_from("spinner").is_err());
+    }
+
+    #[test]
+    fn test_estimate_remaining() {
+        assert_eq!(estimate_remaining(Duration::from_secs(10), 0, 100), None);
+        assert_eq!(
+            estimate_remaining(Duration::from_secs(10), 25, 100),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            estimate_remaining(Duration::from_secs(10), 100, 100),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_heartbeat_json() {
+        let heartbeat = Heartbeat {
+            task: "python",
+            completed: 3,
+            total: 10,
+            current_file: Some("src/main.py"),
+            rules_per_sec: 12.5,
+            elapsed_secs: 6,
+            eta_secs: Some(14),
+            done: false,
+        };
+        assert_eq!(
+            serde_json::to_string(&heartbeat).unwrap(),
+            r#"{"task":"python","completed":3,"total":10,"current_file":"src/main.py","rules_per_sec":12.5,"elapsed_secs":6,"eta_secs":14,"done":false}"#
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Publishers surface the violations of an analysis natively on a code hosting platform,
+//! for example as annotations of a commit or as comments on a pull request.
+
+pub mod azure_devops;
+pub mod bitbucket;
+
+use anyhow::{anyhow, Result};
+use kernel::model::rule::RuleResult;
+use kernel::model::violation::Violation;
+use std::env;
+
+/// A platform that the violations of an analysis are published to.
+pub trait Publisher {
+    /// The name of the platform, as passed to the `--publish` option.
+    fn name(&self) -> &'static str;
+
+    /// Publishes the violations of the results, and returns the number of violations published.
+    fn publish(&self, results: &[RuleResult]) -> Result<usize>;
+}
+
+/// Returns the publisher of a platform, configured from the environment variables of its CI.
+pub fn publisher_from_env(name: &str) -> Result<Box<dyn Publisher>> {
+    match name {
+        bitbucket::NAME => Ok(Box::new(bitbucket::BitbucketPublisher::from_env()?)),
+        azure_devops::NAME => Ok(Box::new(azure_devops::AzureDevOpsPublisher::from_env()?)),
+        _ => Err(anyhow!(
+            "unknown publisher {name} (expected {} or {})",
+            bitbucket::NAME,
+            azure_devops::NAME
+        )),
+    }
+}
+
+/// A violation of a rule in a file, as published to a platform.
+pub(crate) struct Annotation<'a> {
+    pub rule_name: &'a str,
+    pub filename: &'a str,
+    pub violation: &'a Violation,
+}
+
+impl Annotation<'_> {
+    /// A key that identifies the violation across analyses. The fingerprint is used when
+    /// available, as it doesn't change when lines are added above the violation.
+    pub fn key(&self) -> String {
+        self.violation.fingerprint.clone().unwrap_or_else(|| {
+            format!(
+                "{}:{}:{}:{}",
+                self.rule_name, self.filename, self.violation.start.line, self.violation.start.col
+            )
+        })
+    }
+
+    /// The text of the annotation: the message of the violation, followed by the rule.
+    pub fn summary(&self) -> String {
+        format!("{} ({})", self.violation.message, self.rule_name)
+    }
+}
+
+/// Returns the violations of the results, in the order of the results.
+pub(crate) fn annotations(results: &[RuleResult]) -> impl Iterator<Item = Annotation<'_>> {
+    results.iter().flat_map(|result| {
+        result.violations.iter().map(|violation| Annotation {
+            rule_name: &result.rule_name,
+            filename: &result.filename,
+            violation,
+        })
+    })
+}
+
+/// Returns the value of an environment variable that must be set (and not empty).
+pub(crate) fn required_env_var(name: &str) -> Result<String> {
+    match env::var(name) {
+        Ok(value) if !value.is_empty() => Ok(value),
+        _ => Err(anyhow!("environment variable {name} is not set")),
+    }
+}
+
+/// Truncates a string to at most `max_chars` characters, ending it with an ellipsis when truncated.
+pub(crate) fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    let mut truncated = s
+        .chars()
+        .take(max_chars.saturating_sub(1))
+        .collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_summary() {
+        assert_eq!(truncate("abc", 3), "abc");
+        assert_eq!(truncate("abcd", 3), "ab…");
+        assert_eq!(truncate("éèà", 2), "é…");
+    }
+
+    #[test]
+    fn unknown_publisher() {
+        assert!(publisher_from_env("gitea").is_err());
+    }
+}
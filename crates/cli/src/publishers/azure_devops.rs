@@ -0,0 +1,204 @@
+//! Publishes the violations to an Azure DevOps pull request: a status summarizes the analysis,
+//! and each violation is a comment thread on its line.
+//! See https://learn.microsoft.com/en-us/rest/api/azure/devops/git/pull-request-threads
+
+use super::{annotations, required_env_var, Annotation, Publisher};
+use anyhow::{anyhow, Context, Result};
+use kernel::model::rule::{RuleResult, RuleSeverity};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+pub const NAME: &str = "azure-devops";
+
+const API_VERSION: &str = "7.1";
+const STATUS_GENRE: &str = "datadog";
+const STATUS_NAME: &str = "datadog-static-analyzer";
+/// The number of threads created for an analysis, so that a pull request is not flooded
+/// with comments.
+const MAX_THREADS: usize = 100;
+/// A hidden marker added to the comments, used to not comment twice on the same violation when
+/// the pipeline runs again.
+const MARKER_PREFIX: &str = "<!-- datadog-static-analyzer:";
+const MARKER_SUFFIX: &str = " -->";
+
+pub struct AzureDevOpsPublisher {
+    client: Client,
+    token: String,
+    /// The URL of the pull request in the REST API.
+    pull_request_url: String,
+}
+
+impl AzureDevOpsPublisher {
+    /// Configures the publisher from the variables of an Azure Pipelines run triggered by a pull
+    /// request. `SYSTEM_ACCESSTOKEN` must be mapped explicitly in the pipeline.
+    pub fn from_env() -> Result<Self> {
+        let collection_uri = required_env_var("SYSTEM_COLLECTIONURI")?;
+        let project = required_env_var("SYSTEM_TEAMPROJECT")?;
+        let repository_id = required_env_var("BUILD_REPOSITORY_ID")?;
+        let pull_request_id = required_env_var("SYSTEM_PULLREQUEST_PULLREQUESTID")?;
+        let token = required_env_var("SYSTEM_ACCESSTOKEN")?;
+        Ok(Self {
+            client: Client::new(),
+            token,
+            pull_request_url: format!(
+                "{}/{project}/_apis/git/repositories/{repository_id}/pullRequests/{pull_request_id}",
+                collection_uri.trim_end_matches('/')
+            ),
+        })
+    }
+
+    fn send(&self, request: RequestBuilder) -> Result<Value> {
+        let response = request
+            .query(&[("api-version", API_VERSION)])
+            .bearer_auth(&self.token)
+            .send()?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Azure DevOps returned {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            ));
+        }
+        Ok(response.json()?)
+    }
+
+    /// Returns the keys of the violations that already have a thread on the pull request.
+    fn published_keys(&self) -> Result<HashSet<String>> {
+        let threads = self.send(
+            self.client
+                .get(format!("{}/threads", self.pull_request_url)),
+        )?;
+        Ok(threads["value"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|thread| thread["comments"].as_array().into_iter().flatten())
+            .filter_map(|comment| comment["content"].as_str().and_then(marker_key))
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+impl Publisher for AzureDevOpsPublisher {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn publish(&self, results: &[RuleResult]) -> Result<usize> {
+        let published = self
+            .published_keys()
+            .context("cannot get the threads of the pull request")?;
+        let mut count = 0;
+        for annotation in annotations(results)
+            .filter(|a| !published.contains(&a.key()))
+            .take(MAX_THREADS)
+        {
+            self.send(
+                self.client
+                    .post(format!("{}/threads", self.pull_request_url))
+                    .json(&thread_payload(&annotation)),
+            )
+            .context("cannot create a thread on the pull request")?;
+            count += 1;
+        }
+        self.send(
+            self.client
+                .post(format!("{}/statuses", self.pull_request_url))
+                .json(&status_payload(results)),
+        )
+        .context("cannot set the status of the pull request")?;
+        Ok(count)
+    }
+}
+
+/// Returns the key of the violation of a comment created by the publisher.
+fn marker_key(content: &str) -> Option<&str> {
+    let start = content.find(MARKER_PREFIX)? + MARKER_PREFIX.len();
+    let end = start + content[start..].find(MARKER_SUFFIX)?;
+    Some(content[start..end].trim())
+}
+
+/// The status fails when a violation has the error severity.
+fn status_payload(results: &[RuleResult]) -> Value {
+    let count = annotations(results).count();
+    let failed = annotations(results).any(|a| a.violation.severity == RuleSeverity::Error);
+    json!({
+        "state": if failed { "failed" } else { "succeeded" },
+        "description": format!("{count} violation(s) found"),
+        "context": {"genre": STATUS_GENRE, "name": STATUS_NAME},
+    })
+}
+
+fn thread_payload(annotation: &Annotation) -> Value {
+    let violation = annotation.violation;
+    let content = format!(
+        "**{}**: {}\n\n{MARKER_PREFIX} {}{MARKER_SUFFIX}",
+        violation.severity,
+        annotation.summary(),
+        annotation.key()
+    );
+    json!({
+        "comments": [{"parentCommentId": 0, "content": content, "commentType": "text"}],
+        "status": "active",
+        "threadContext": {
+            // Azure DevOps expects absolute paths from the root of the repository.
+            "filePath": format!("/{}", annotation.filename.trim_start_matches('/')),
+            "rightFileStart": {"line": violation.start.line, "offset": violation.start.col},
+            "rightFileEnd": {"line": violation.end.line, "offset": violation.end.col},
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rule_result, violation};
+    use kernel::model::common::Position;
+    use kernel::model::rule::RuleCategory;
+    use kernel::model::violation::Violation;
+
+    fn result() -> RuleResult {
+        let eval = Violation {
+            message: "do not use eval".into(),
+            fingerprint: Some("abc".to_string()),
+            ..violation(
+                Position { line: 3, col: 5 },
+                Position { line: 3, col: 15 },
+                RuleSeverity::Error,
+                RuleCategory::Security,
+            )
+        };
+        rule_result("python-security/no-eval", "src/main.py", vec![eval])
+    }
+
+    #[test]
+    fn thread() {
+        let results = [result()];
+        let thread = thread_payload(&annotations(&results).next().unwrap());
+        assert_eq!(thread["threadContext"]["filePath"], "/src/main.py");
+        assert_eq!(thread["threadContext"]["rightFileStart"]["line"], 3);
+        assert_eq!(thread["threadContext"]["rightFileEnd"]["offset"], 15);
+        let content = thread["comments"][0]["content"].as_str().unwrap();
+        assert!(content.starts_with("**error**: do not use eval (python-security/no-eval)"));
+        assert_eq!(marker_key(content), Some("abc"));
+    }
+
+    #[test]
+    fn marker() {
+        assert_eq!(marker_key("a comment"), None);
+        assert_eq!(
+            marker_key("text <!-- datadog-static-analyzer: rule:file:1:2 -->"),
+            Some("rule:file:1:2")
+        );
+    }
+
+    #[test]
+    fn status() {
+        assert_eq!(status_payload(&[])["state"], "succeeded");
+        let status = status_payload(&[result()]);
+        assert_eq!(status["state"], "failed");
+        assert_eq!(status["description"], "1 violation(s) found");
+        assert_eq!(status["context"]["name"], "datadog-static-analyzer");
+    }
+}
@@ -0,0 +1,201 @@
+//! Publishes the violations as a Bitbucket Code Insights report of the analyzed commit.
+//! See https://developer.atlassian.com/cloud/bitbucket/rest/api-group-reports/
+
+use super::{annotations, required_env_var, truncate, Annotation, Publisher};
+use anyhow::{anyhow, Context, Result};
+use kernel::model::rule::{RuleCategory, RuleResult, RuleSeverity};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::{json, Value};
+use std::env;
+
+pub const NAME: &str = "bitbucket";
+
+const REPORT_ID: &str = "datadog-static-analyzer";
+/// Bitbucket rejects the annotations of a report past this number.
+const MAX_ANNOTATIONS: usize = 1000;
+/// The maximum number of annotations created by a single request.
+const ANNOTATIONS_PER_REQUEST: usize = 100;
+const MAX_SUMMARY_LENGTH: usize = 450;
+
+const API_URL: &str = "https://api.bitbucket.org";
+/// In Bitbucket Pipelines, the requests sent through this proxy are authenticated automatically.
+/// The proxy only accepts plain HTTP requests.
+const PIPELINES_PROXY_URL: &str = "http://localhost:29418";
+const PIPELINES_API_URL: &str = "http://api.bitbucket.org";
+
+pub struct BitbucketPublisher {
+    client: Client,
+    api_url: &'static str,
+    /// Sent as a bearer token when set, otherwise the requests go through the Pipelines proxy.
+    token: Option<String>,
+    workspace: String,
+    repo_slug: String,
+    commit: String,
+}
+
+impl BitbucketPublisher {
+    /// Configures the publisher from the variables of Bitbucket Pipelines. `BITBUCKET_ACCESS_TOKEN`
+    /// can be set to publish from outside of Pipelines.
+    pub fn from_env() -> Result<Self> {
+        let workspace = required_env_var("BITBUCKET_WORKSPACE")?;
+        let repo_slug = required_env_var("BITBUCKET_REPO_SLUG")?;
+        let commit = required_env_var("BITBUCKET_COMMIT")?;
+        let token = env::var("BITBUCKET_ACCESS_TOKEN")
+            .ok()
+            .filter(|t| !t.is_empty());
+        let (client, api_url) = match token {
+            Some(_) => (Client::new(), API_URL),
+            None => (
+                Client::builder()
+                    .proxy(reqwest::Proxy::http(PIPELINES_PROXY_URL)?)
+                    .build()?,
+                PIPELINES_API_URL,
+            ),
+        };
+        Ok(Self {
+            client,
+            api_url,
+            token,
+            workspace,
+            repo_slug,
+            commit,
+        })
+    }
+
+    fn report_url(&self) -> String {
+        format!(
+            "{}/2.0/repositories/{}/{}/commit/{}/reports/{REPORT_ID}",
+            self.api_url, self.workspace, self.repo_slug, self.commit
+        )
+    }
+
+    fn send(&self, request: RequestBuilder, body: &Value) -> Result<()> {
+        let request = match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+        let response = request.json(body).send()?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Bitbucket returned {}: {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Publisher for BitbucketPublisher {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn publish(&self, results: &[RuleResult]) -> Result<usize> {
+        let annotations = annotations(results)
+            .take(MAX_ANNOTATIONS)
+            .enumerate()
+            .map(|(idx, annotation)| annotation_payload(idx, &annotation))
+            .collect::<Vec<_>>();
+
+        // Replacing the report also deletes the annotations of the previous analysis.
+        let report_url = self.report_url();
+        self.send(self.client.put(&report_url), &report_payload(results))
+            .context("cannot create the Bitbucket report")?;
+        for chunk in annotations.chunks(ANNOTATIONS_PER_REQUEST) {
+            self.send(
+                self.client.post(format!("{report_url}/annotations")),
+                &Value::from(chunk),
+            )
+            .context("cannot create the Bitbucket annotations")?;
+        }
+        Ok(annotations.len())
+    }
+}
+
+/// The report fails when a violation has the error severity.
+fn report_payload(results: &[RuleResult]) -> Value {
+    let count = annotations(results).count();
+    let failed = annotations(results).any(|a| a.violation.severity == RuleSeverity::Error);
+    json!({
+        "title": "Datadog Static Analysis",
+        "details": format!("{count} violation(s) found"),
+        "report_type": "BUG",
+        "reporter": "Datadog",
+        "result": if failed { "FAILED" } else { "PASSED" },
+        "data": [{"title": "Violations", "type": "NUMBER", "value": count}],
+    })
+}
+
+fn annotation_payload(idx: usize, annotation: &Annotation) -> Value {
+    let violation = annotation.violation;
+    let severity = match violation.severity {
+        RuleSeverity::Error => "HIGH",
+        RuleSeverity::Warning => "MEDIUM",
+        RuleSeverity::Notice | RuleSeverity::None => "LOW",
+    };
+    let annotation_type = match violation.category {
+        RuleCategory::Security => "VULNERABILITY",
+        RuleCategory::ErrorProne => "BUG",
+        _ => "CODE_SMELL",
+    };
+    json!({
+        "external_id": format!("{REPORT_ID}-{idx}"),
+        "annotation_type": annotation_type,
+        "summary": truncate(&annotation.summary(), MAX_SUMMARY_LENGTH),
+        "path": annotation.filename,
+        "line": violation.start.line,
+        "severity": severity,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rule_result, violation};
+    use kernel::model::common::Position;
+    use kernel::model::violation::Violation;
+
+    fn result(severity: RuleSeverity) -> RuleResult {
+        let eval = Violation {
+            message: "do not use eval".into(),
+            fingerprint: Some("abc".to_string()),
+            ..violation(
+                Position { line: 3, col: 5 },
+                Position { line: 3, col: 15 },
+                severity,
+                RuleCategory::Security,
+            )
+        };
+        rule_result("python-security/no-eval", "src/main.py", vec![eval])
+    }
+
+    #[test]
+    fn annotation() {
+        let results = [result(RuleSeverity::Warning)];
+        let annotation = annotations(&results).next().unwrap();
+        assert_eq!(
+            annotation_payload(4, &annotation),
+            json!({
+                "external_id": "datadog-static-analyzer-4",
+                "annotation_type": "VULNERABILITY",
+                "summary": "do not use eval (python-security/no-eval)",
+                "path": "src/main.py",
+                "line": 3,
+                "severity": "MEDIUM",
+            })
+        );
+    }
+
+    #[test]
+    fn report_result() {
+        assert_eq!(report_payload(&[])["result"], "PASSED");
+        assert_eq!(
+            report_payload(&[result(RuleSeverity::Warning)])["result"],
+            "PASSED"
+        );
+        let report = report_payload(&[result(RuleSeverity::Error)]);
+        assert_eq!(report["result"], "FAILED");
+        assert_eq!(report["data"][0]["value"], 1);
+    }
+}
@@ -0,0 +1,376 @@
+//! The cache of the results of the analyzed files, shared by the analyses of the same code with the
+//! same configuration (e.g. by CI runners on different machines), so that a file that didn't change
+//! is not analyzed again.
+//!
+//! The entries are addressed by the hash of the configuration of the analysis, the language, the
+//! path and the content of the file: an entry is never invalidated, it's just not read anymore once
+//! the file or the configuration changes. The entries are stored in a [`CacheStore`]: a local
+//! directory, or an HTTP server that accepts `GET` and `PUT` requests on `<url>/<key>` (e.g. an S3
+//! compatible bucket, or a build cache server).
+
+use anyhow::{anyhow, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use kernel::model::analysis::ERROR_RULE_TIMEOUT;
+use kernel::model::common::Language;
+use kernel::model::rule::RuleResult;
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// The version of the format of the entries. It's part of the keys, so that the entries of another
+/// version are never read.
+const CACHE_VERSION: u32 = 1;
+
+/// The first bytes of a gzip stream, to read the entries written with or without compression.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The environment variable with the token sent to an HTTP cache, as a bearer token.
+pub const CACHE_TOKEN_VARIABLE: &str = "DD_STATIC_ANALYZER_CACHE_TOKEN";
+
+/// The maximum duration of a request to an HTTP cache: a slow cache must not slow the analysis
+/// down more than analyzing the file would.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The storage of the entries of a [`ResultCache`].
+pub trait CacheStore: Send + Sync {
+    /// The location of the storage, shown to the user.
+    fn name(&self) -> String;
+
+    /// Returns the entry with this key, or `None` if there is none.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Stores the entry with this key, replacing the existing one.
+    fn put(&self, key: &str, value: &[u8]) -> Result<()>;
+}
+
+/// Stores the entries in a local directory (e.g. a directory restored by the cache of the CI).
+pub struct DirectoryStore {
+    directory: PathBuf,
+}
+
+impl DirectoryStore {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// The entries are spread in subdirectories by the first characters of their key, to keep
+    /// the directories small.
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(&key[..2]).join(key)
+    }
+}
+
+impl CacheStore for DirectoryStore {
+    fn name(&self) -> String {
+        self.directory.display().to_string()
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.path(key)) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(anyhow!(err).context(format!("cannot read the cache entry {key}"))),
+        }
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> Result<()> {
+        let path = self.path(key);
+        let directory = path.parent().expect("the entry is in a subdirectory");
+        fs::create_dir_all(directory).with_context(|| {
+            format!("cannot create the cache directory {}", directory.display())
+        })?;
+        // the entry is renamed once written, so that another analysis never reads a partial entry
+        let mut file = tempfile::NamedTempFile::new_in(directory)?;
+        file.write_all(value)?;
+        file.persist(&path)
+            .with_context(|| format!("cannot write the cache entry {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Stores the entries on an HTTP server, at `<url>/<key>`.
+pub struct HttpStore {
+    url: String,
+    token: Option<String>,
+    client: reqwest::blocking::Client,
+}
+
+impl HttpStore {
+    /// The token of [`CACHE_TOKEN_VARIABLE`], if set, is sent with each request.
+    pub fn new(url: &str) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .context("cannot create the HTTP client of the cache")?;
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            token: std::env::var(CACHE_TOKEN_VARIABLE)
+                .ok()
+                .filter(|t| !t.is_empty()),
+            client,
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, key: &str) -> reqwest::blocking::RequestBuilder {
+        let request = self.client.request(method, format!("{}/{}", self.url, key));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+impl CacheStore for HttpStore {
+    fn name(&self) -> String {
+        self.url.clone()
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let response = self.request(reqwest::Method::GET, key).send()?;
+        match response.status() {
+            // S3 returns 403 instead of 404 for a missing object when listing is not allowed
+            StatusCode::NOT_FOUND | StatusCode::FORBIDDEN => Ok(None),
+            status if status.is_success() => Ok(Some(response.bytes()?.to_vec())),
+            status => Err(anyhow!("the cache returned {status} for the entry {key}")),
+        }
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::PUT, key)
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .body(value.to_vec())
+            .send()?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "the cache returned {} when storing the entry {key}",
+                response.status()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the store of the location of `--cache`: an `http://` or `https://` URL, or a directory.
+pub fn store_from_location(location: &str) -> Result<Box<dyn CacheStore>> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        Ok(Box::new(HttpStore::new(location)?))
+    } else {
+        Ok(Box::new(DirectoryStore::new(location)))
+    }
+}
+
+pub struct ResultCache {
+    store: Box<dyn CacheStore>,
+    /// The hash of the configuration of the analysis.
+    configuration: String,
+    compress: bool,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    errors: AtomicUsize,
+}
+
+impl ResultCache {
+    /// Creates the cache of the results of the analyses with the same `configuration` (the rules
+    /// and the options that change the results). With `compress`, the entries are written with
+    /// gzip: the entries are read whether they are compressed or not.
+    pub fn new(store: Box<dyn CacheStore>, configuration: &str, compress: bool) -> Self {
+        Self {
+            store,
+            configuration: hash(configuration.as_bytes()),
+            compress,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            errors: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.store.name()
+    }
+
+    fn key(&self, language: Language, path: &str, content: &str) -> String {
+        let mut hasher = Sha256::new();
+        for part in [
+            CACHE_VERSION.to_string().as_str(),
+            &self.configuration,
+            &language.to_string(),
+            path,
+        ] {
+            hasher.update(part.as_bytes());
+            // the parts are separated, so that two different sets of parts never have the same key
+            hasher.update([0]);
+        }
+        hasher.update(content.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Returns the results of a file analyzed with the same configuration and content. An entry
+    /// that cannot be read is a miss: the cache never fails the analysis.
+    pub fn get(&self, language: Language, path: &str, content: &str) -> Option<Vec<RuleResult>> {
+        let key = self.key(language, path, content);
+        let results = match self.store.get(&key).and_then(|entry| match entry {
+            Some(entry) => decode(&entry).map(Some),
+            None => Ok(None),
+        }) {
+            Ok(results) => results,
+            Err(err) => {
+                self.report_error(err);
+                None
+            }
+        };
+        match &results {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        results
+    }
+
+    /// Stores the results of a file. The results of a file whose analysis was cancelled, or where
+    /// a rule timed out, depend on the machine and are not stored.
+    pub fn put(&self, language: Language, path: &str, content: &str, results: &[RuleResult]) {
+        if results
+            .iter()
+            .any(|r| r.is_cancelled() || r.errors.iter().any(|e| e == ERROR_RULE_TIMEOUT))
+        {
+            return;
+        }
+        let key = self.key(language, path, content);
+        if let Err(err) =
+            encode(results, self.compress).and_then(|entry| self.store.put(&key, &entry))
+        {
+            self.report_error(err);
+        }
+    }
+
+    /// Only the first error is shown, as a cache that is unavailable fails for every file.
+    fn report_error(&self, err: anyhow::Error) {
+        if self.errors.fetch_add(1, Ordering::Relaxed) == 0 {
+            eprintln!(
+                "WARNING: error with the cache {}: {:#}",
+                self.store.name(),
+                err
+            );
+        }
+    }
+
+    /// Returns the number of files whose results were read from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of files whose results were not in the cache.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of errors when reading or writing the cache.
+    pub fn errors(&self) -> usize {
+        self.errors.load(Ordering::Relaxed)
+    }
+}
+
+fn encode(results: &[RuleResult], compress: bool) -> Result<Vec<u8>> {
+    let json = serde_json::to_vec(results)?;
+    if !compress {
+        return Ok(json);
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&json)?;
+    encoder.finish().context("cannot compress the cache entry")
+}
+
+fn decode(entry: &[u8]) -> Result<Vec<RuleResult>> {
+    let results = if entry.starts_with(&GZIP_MAGIC) {
+        let mut json = vec![];
+        GzDecoder::new(entry)
+            .read_to_end(&mut json)
+            .context("cannot decompress the cache entry")?;
+        serde_json::from_slice(&json)
+    } else {
+        serde_json::from_slice(entry)
+    };
+    results.context("invalid cache entry")
+}
+
+fn hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rule_result;
+
+    fn result(filename: &str, errors: Vec<String>) -> RuleResult {
+        RuleResult {
+            errors,
+            ..rule_result("ruleset/rule", filename, vec![])
+        }
+    }
+
+    #[test]
+    fn reuse_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ResultCache::new(Box::new(DirectoryStore::new(dir.path())), "config", true);
+        assert!(cache.get(Language::Python, "a.py", "a = 1").is_none());
+        cache.put(Language::Python, "a.py", "a = 1", &[result("a.py", vec![])]);
+        cache.put(
+            Language::Python,
+            "b.py",
+            "b = 1",
+            &[result("b.py", vec![ERROR_RULE_TIMEOUT.to_string()])],
+        );
+
+        let results = cache.get(Language::Python, "a.py", "a = 1").unwrap();
+        assert_eq!(results[0].filename, "a.py");
+        // the content of the file changed
+        assert!(cache.get(Language::Python, "a.py", "a = 2").is_none());
+        // a rule timed out
+        assert!(cache.get(Language::Python, "b.py", "b = 1").is_none());
+        assert_eq!((cache.hits(), cache.misses(), cache.errors()), (1, 3, 0));
+
+        // the entries of another configuration are not read, and the entries are read whether
+        // they are compressed or not
+        let other = ResultCache::new(Box::new(DirectoryStore::new(dir.path())), "other", false);
+        assert!(other.get(Language::Python, "a.py", "a = 1").is_none());
+        let uncompressed =
+            ResultCache::new(Box::new(DirectoryStore::new(dir.path())), "config", false);
+        assert!(uncompressed
+            .get(Language::Python, "a.py", "a = 1")
+            .is_some());
+    }
+
+    #[test]
+    fn invalid_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DirectoryStore::new(dir.path());
+        let cache = ResultCache::new(Box::new(DirectoryStore::new(dir.path())), "config", false);
+        let key = cache.key(Language::Python, "a.py", "a = 1");
+        store.put(&key, b"[{\"rule_na").unwrap();
+        assert!(cache.get(Language::Python, "a.py", "a = 1").is_none());
+        assert_eq!(cache.errors(), 1);
+    }
+
+    #[test]
+    fn stores() {
+        assert_eq!(
+            store_from_location("https://cache.example.com/analyzer/")
+                .unwrap()
+                .name(),
+            "https://cache.example.com/analyzer"
+        );
+        assert_eq!(
+            store_from_location(".datadog-cache").unwrap().name(),
+            ".datadog-cache"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use kernel::model::analysis::{
+    ERROR_INVALID_QUERY, ERROR_RULE_EXECUTION, ERROR_RULE_SANDBOX, ERROR_RULE_TIMEOUT,
+};
+use kernel::model::rule::RuleResult;
+use std::collections::BTreeMap;
+
+/// The errors of a rule during an analysis, counted per file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RuleErrorCounts {
+    pub timeouts: u64,
+    pub execution_errors: u64,
+    pub sandbox_violations: u64,
+    pub invalid_queries: u64,
+}
+
+impl RuleErrorCounts {
+    pub fn total(&self) -> u64 {
+        self.timeouts + self.execution_errors + self.sandbox_violations + self.invalid_queries
+    }
+
+    fn add(&mut self, other: &RuleErrorCounts) {
+        self.timeouts += other.timeouts;
+        self.execution_errors += other.execution_errors;
+        self.sandbox_violations += other.sandbox_violations;
+        self.invalid_queries += other.invalid_queries;
+    }
+
+    /// Returns the counts as a sentence, e.g. `2 timeout(s), 1 execution error(s)`.
+    pub fn describe(&self) -> String {
+        [
+            (self.timeouts, "timeout(s)"),
+            (self.execution_errors, "execution error(s)"),
+            (self.sandbox_violations, "sandbox violation(s)"),
+            (self.invalid_queries, "invalid query(ies)"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, kind)| format!("{count} {kind}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// The errors of the rules during an analysis. A rule that times out or fails on a file reports
+/// no violation for it: an analysis with many errors looks clean while its results are degraded.
+///
+/// The cancelled rules of an interrupted analysis are not errors of the rules.
+#[derive(Debug, Default)]
+pub struct RuleErrorSummary {
+    rules: BTreeMap<String, RuleErrorCounts>,
+}
+
+impl RuleErrorSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the errors of the result of a rule on a file.
+    pub fn record(&mut self, result: &RuleResult) {
+        let mut counts = RuleErrorCounts::default();
+        for error in &result.errors {
+            match error.as_str() {
+                ERROR_RULE_TIMEOUT => counts.timeouts += 1,
+                ERROR_RULE_EXECUTION => counts.execution_errors += 1,
+                ERROR_RULE_SANDBOX => counts.sandbox_violations += 1,
+                ERROR_INVALID_QUERY => counts.invalid_queries += 1,
+                _ => {}
+            }
+        }
+        // the failure of a native plugin is only reported as its execution error
+        if result.errors.is_empty() && result.execution_error.is_some() {
+            counts.execution_errors += 1;
+        }
+        if counts.total() > 0 {
+            self.rules
+                .entry(result.rule_name.to_string())
+                .or_default()
+                .add(&counts);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the errors of all the rules.
+    pub fn total(&self) -> RuleErrorCounts {
+        let mut total = RuleErrorCounts::default();
+        for counts in self.rules.values() {
+            total.add(counts);
+        }
+        total
+    }
+
+    /// Returns the rules with errors, the rules with the most errors first (and then by name).
+    pub fn rules(&self) -> Vec<(&str, RuleErrorCounts)> {
+        let mut rules = self
+            .rules
+            .iter()
+            .map(|(name, counts)| (name.as_str(), *counts))
+            .collect::<Vec<_>>();
+        rules.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then(a.0.cmp(b.0)));
+        rules
+    }
+
+    /// Returns the summary shown at the end of the analysis, with at most `max_rules` rules.
+    pub fn report(&self, max_rules: usize) -> String {
+        let rules = self.rules();
+        let mut report = format!(
+            "The rules failed on some files, the results are incomplete: {} in {} rule(s)",
+            self.total().describe(),
+            rules.len()
+        );
+        for (name, counts) in rules.iter().take(max_rules) {
+            report.push_str(&format!("\n  {name}: {}", counts.describe()));
+        }
+        if rules.len() > max_rules {
+            report.push_str(&format!(
+                "\n  ... and {} more rule(s)",
+                rules.len() - max_rules
+            ));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rule_result;
+    use kernel::model::analysis::ERROR_ANALYSIS_CANCELLED;
+
+    fn result(rule_name: &str, errors: &[&str]) -> RuleResult {
+        RuleResult {
+            errors: errors.iter().map(|e| e.to_string()).collect(),
+            ..rule_result(rule_name, "file.py", vec![])
+        }
+    }
+
+    #[test]
+    fn summarize_rule_errors() {
+        let mut summary = RuleErrorSummary::new();
+        summary.record(&result("ruleset/clean", &[]));
+        summary.record(&result("ruleset/cancelled", &[ERROR_ANALYSIS_CANCELLED]));
+        assert!(summary.is_empty());
+
+        summary.record(&result("ruleset/slow", &[ERROR_RULE_TIMEOUT]));
+        summary.record(&result("ruleset/crash", &[ERROR_RULE_EXECUTION]));
+        summary.record(&result("ruleset/crash", &[ERROR_RULE_EXECUTION]));
+        summary.record(&result("ruleset/slow", &[ERROR_RULE_EXECUTION]));
+        summary.record(&result("ruleset/crash", &[ERROR_RULE_EXECUTION]));
+
+        assert_eq!(
+            summary.total(),
+            RuleErrorCounts {
+                timeouts: 1,
+                execution_errors: 4,
+                sandbox_violations: 0,
+                invalid_queries: 0,
+            }
+        );
+        let rules = summary.rules();
+        assert_eq!(rules[0].0, "ruleset/crash");
+        assert_eq!(rules[1].1.describe(), "1 timeout(s), 1 execution error(s)");
+        assert_eq!(
+            summary.report(1),
+            "The rules failed on some files, the results are incomplete: 1 timeout(s), 4 execution error(s) in 2 rule(s)\n  ruleset/crash: 3 execution error(s)\n  ... and 1 more rule(s)"
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! The static validation of the definitions of rules (`lint-rules`): the problems that make a rule
+//! fail, or never report anything, found without analyzing any code.
+
+use kernel::analysis::tree_sitter::{get_query, get_tree, IncompatibleQueryError};
+use kernel::model::rule::{Rule, RuleCategory, RuleSeverity};
+use kernel::utils::decode_base64_string;
+use std::fmt;
+
+/// The globals with side effects, which are only available to the rules that allow side effects
+/// (see `RESTRICTED_GLOBALS` in `sandbox.js`).
+const RESTRICTED_GLOBALS: &[&str] = &[
+    "Deno",
+    "__bootstrap",
+    "fetch",
+    "XMLHttpRequest",
+    "WebSocket",
+    "setTimeout",
+    "setInterval",
+    "setImmediate",
+    "queueMicrotask",
+    "WebAssembly",
+    "Atomics",
+];
+
+/// The globals that execute dynamic code, which rules should never use.
+const DYNAMIC_CODE_GLOBALS: &[&str] = &["eval", "Function", "require"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintLevel {
+    /// The rule fails, or never reports a violation.
+    Error,
+    /// The rule works, but is likely wrong or incomplete.
+    Warning,
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintLevel::Error => write!(f, "error"),
+            LintLevel::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LintProblem {
+    pub rule: String,
+    pub level: LintLevel,
+    pub message: String,
+}
+
+/// Returns the problems of the definition of a rule.
+pub fn lint_rule(rule: &Rule) -> Vec<LintProblem> {
+    let mut problems = vec![];
+    let mut report = |level: LintLevel, message: String| {
+        problems.push(LintProblem {
+            rule: rule.name.clone(),
+            level,
+            message,
+        })
+    };
+
+    let code = match decode_base64_string(rule.code_base64.clone()) {
+        Ok(code) => code,
+        Err(_) => {
+            report(LintLevel::Error, "the code is not valid base64".to_string());
+            String::new()
+        }
+    };
+    if !code.is_empty() && !contains_word(&code, "visit", |_| true) {
+        report(
+            LintLevel::Error,
+            "the code has no `visit` function".to_string(),
+        );
+    }
+
+    // the queries of the rule, with their names in the messages
+    let mut queries = vec![];
+    match &rule.tree_sitter_query_base64 {
+        Some(query) => queries.push((None, "the query".to_string(), query)),
+        None if rule.tree_sitter_queries.is_empty() => {
+            report(LintLevel::Error, "the rule has no query".to_string())
+        }
+        None => {}
+    }
+    for named_query in &rule.tree_sitter_queries {
+        queries.push((
+            Some(&named_query.name),
+            format!("the query `{}`", named_query.name),
+            &named_query.query_base64,
+        ));
+    }
+    for (name, query_name, query) in queries {
+        let Ok(query) = decode_base64_string(query.clone()) else {
+            report(
+                LintLevel::Error,
+                format!("{query_name} is not valid base64"),
+            );
+            continue;
+        };
+        let query = match get_query(&query, &rule.language) {
+            Ok(query) => query,
+            Err(err) => {
+                let message = match err.downcast::<IncompatibleQueryError>() {
+                    Ok(err) => IncompatibleQueryError {
+                        query_name: name.cloned(),
+                        ..err
+                    }
+                    .to_string(),
+                    Err(err) => format!("{query_name} does not compile: {err}"),
+                };
+                report(LintLevel::Error, message);
+                continue;
+            }
+        };
+        // the rule is only executed on the matches with at least one capture
+        if query.capture_names().is_empty() {
+            report(
+                LintLevel::Error,
+                format!("{query_name} has no captures: the rule is never executed"),
+            );
+        }
+        for capture in query.capture_names() {
+            if !code.is_empty() && !contains_word(&code, capture, |_| true) {
+                report(
+                    LintLevel::Warning,
+                    format!("the capture @{capture} of {query_name} is never read by the code"),
+                );
+            }
+        }
+    }
+
+    // a global is only reported when it's called or one of its members is used, to ignore the
+    // words of the messages of the violations.
+    let is_used = |rest: &str| rest.trim_start().starts_with(['(', '.']);
+    for global in DYNAMIC_CODE_GLOBALS {
+        if contains_word(&code, global, is_used) {
+            report(
+                LintLevel::Error,
+                format!("the code uses `{global}`, which executes dynamic code"),
+            );
+        }
+    }
+    if !rule.allow_side_effects {
+        for global in RESTRICTED_GLOBALS {
+            if contains_word(&code, global, is_used) {
+                report(
+                    LintLevel::Error,
+                    format!("the code uses `{global}`, which is only available to the rules that allow side effects"),
+                );
+            }
+        }
+    }
+
+    match (rule.category, rule.severity) {
+        (RuleCategory::Security, RuleSeverity::Notice | RuleSeverity::None) => report(
+            LintLevel::Warning,
+            format!("a security rule has the {:?} severity", rule.severity),
+        ),
+        (RuleCategory::CodeStyle, RuleSeverity::Error) => report(
+            LintLevel::Warning,
+            "a code style rule has the Error severity".to_string(),
+        ),
+        _ => {}
+    }
+    if rule.cwe.as_ref().is_some_and(|cwe| !cwe.is_empty())
+        && rule.category != RuleCategory::Security
+    {
+        report(
+            LintLevel::Warning,
+            format!(
+                "the rule has a CWE, but its category is {:?} instead of Security",
+                rule.category
+            ),
+        );
+    }
+
+    if rule.tests.is_empty() {
+        report(LintLevel::Warning, "the rule has no tests".to_string());
+    } else {
+        if rule.tests.iter().all(|t| t.annotation_count == 0) {
+            report(
+                LintLevel::Warning,
+                "no test expects a violation".to_string(),
+            );
+        }
+        if rule.tests.iter().all(|t| t.annotation_count > 0) {
+            report(
+                LintLevel::Warning,
+                "no test expects the absence of violations".to_string(),
+            );
+        }
+    }
+    for test in &rule.tests {
+        match decode_base64_string(test.code_base64.clone()) {
+            Ok(test_code) => {
+                let has_errors = get_tree(&test_code, &rule.language)
+                    .map_or(true, |tree| tree.root_node().has_error());
+                if has_errors {
+                    report(
+                        LintLevel::Warning,
+                        format!(
+                            "the test {} has syntax errors with the {} grammar",
+                            test.filename, rule.language
+                        ),
+                    );
+                }
+            }
+            Err(_) => report(
+                LintLevel::Error,
+                format!("the code of the test {} is not valid base64", test.filename),
+            ),
+        }
+    }
+    problems
+}
+
+/// Returns whether the code contains the word, where the text after the word satisfies `followed_by`.
+fn contains_word(code: &str, word: &str, followed_by: impl Fn(&str) -> bool) -> bool {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    code.match_indices(word).any(|(index, _)| {
+        let before = code[..index].chars().next_back();
+        let rest = &code[index + word.len()..];
+        !before.is_some_and(is_identifier)
+            && !rest.chars().next().is_some_and(is_identifier)
+            && followed_by(rest)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::model::common::Language;
+    use kernel::model::rule::RuleType;
+    use kernel::model::rule_test::RuleTest;
+    use kernel::utils::encode_base64_string;
+
+    fn rule(query: &str, code: &str, tests: &[(&str, u32)]) -> Rule {
+        Rule {
+            name: "ruleset/rule".to_string(),
+            short_description_base64: None,
+            description_base64: None,
+            category: RuleCategory::BestPractices,
+            severity: RuleSeverity::Warning,
+            language: Language::Python,
+            rule_type: RuleType::TreeSitterQuery,
+            entity_checked: None,
+            code_base64: encode_base64_string(code.to_string()),
+            checksum: String::new(),
+            pattern: None,
+            cwe: None,
+            tree_sitter_query_base64: Some(encode_base64_string(query.to_string())),
+            tree_sitter_queries: vec![],
+            arguments: vec![],
+            tests: tests
+                .iter()
+                .map(|(code, annotation_count)| RuleTest {
+                    annotation_count: *annotation_count,
+                    filename: "test.py".to_string(),
+                    code_base64: encode_base64_string(code.to_string()),
+                })
+                .collect(),
+            is_testing: false,
+            deprecated: false,
+            replaced_by: None,
+            allow_side_effects: false,
+            prerequisites: vec![],
+        }
+    }
+
+    fn messages(rule: &Rule) -> Vec<(LintLevel, String)> {
+        lint_rule(rule)
+            .into_iter()
+            .map(|p| (p.level, p.message))
+            .collect()
+    }
+
+    #[test]
+    fn valid_rule() {
+        let rule = rule(
+            "(call function: (identifier) @name) @call",
+            "function visit(node) { const call = node.captures[\"call\"]; console.log(node.captures.name); }",
+            &[("eval(x)\n", 1), ("x = 1\n", 0)],
+        );
+        assert_eq!(messages(&rule), vec![]);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let rule = rule(
+            "(call function: (identifier) @name) @call",
+            "function visit(node) { const call = node.captures[\"call\"]; fetch(\"https://example.com\"); }",
+            &[("eval(x\n", 1)],
+        );
+        assert_eq!(
+            messages(&rule),
+            vec![
+                (
+                    LintLevel::Warning,
+                    "the capture @name of the query is never read by the code".to_string()
+                ),
+                (
+                    LintLevel::Error,
+                    "the code uses `fetch`, which is only available to the rules that allow side effects".to_string()
+                ),
+                (
+                    LintLevel::Warning,
+                    "no test expects the absence of violations".to_string()
+                ),
+                (
+                    LintLevel::Warning,
+                    "the test test.py has syntax errors with the python grammar".to_string()
+                ),
+            ]
+        );
+
+        let rule = rule("(call)", "function visit(node) {}", &[]);
+        assert_eq!(
+            messages(&rule),
+            vec![
+                (
+                    LintLevel::Error,
+                    "the query has no captures: the rule is never executed".to_string()
+                ),
+                (LintLevel::Warning, "the rule has no tests".to_string()),
+            ]
+        );
+
+        let rule = rule("(unknown_node) @node", "function visit(node) {}", &[]);
+        assert!(lint_rule(&rule)[0]
+            .message
+            .starts_with("the query is incompatible with the python grammar"));
+    }
+
+    #[test]
+    fn words() {
+        assert!(contains_word("eval(x)", "eval", |_| true));
+        assert!(!contains_word("literal_eval(x)", "eval", |_| true));
+        assert!(!contains_word("evaluate(x)", "eval", |_| true));
+        // a word in a message is not a use of a global
+        assert!(!contains_word("\"do not use eval\"", "eval", |rest| rest
+            .trim_start()
+            .starts_with(['(', '.'])));
+    }
+}
@@ -1,11 +1,25 @@
 use anyhow::Result;
+use kernel::api::{analyze_file, AnalysisRequest};
+use kernel::arguments::ArgumentProvider;
 use kernel::model::common::Language;
+use kernel::model::config_file::{split_path, ConfigFile};
 use kernel::model::rule::{Rule, RuleResult, RuleSeverity};
 use kernel::model::ruleset::RuleSet;
+use kernel::rule_overrides::RuleOverrides;
+use kernel::utils::decode_base64_string;
 use std::collections::HashSet;
+use std::path::Path;
 use std::{fs::File, io::BufReader};
 
+use crate::datadog_utils::get_rules_from_rulesets;
+use crate::local_rules::get_rulesets_from_directory;
+
+/// Returns the rulesets of a JSON file, or of a directory of local rules (see
+/// [`get_rulesets_from_directory`]).
 pub fn get_rulesets_from_file(file_path: &str) -> Result<Vec<RuleSet>> {
+    if Path::new(file_path).is_dir() {
+        return get_rulesets_from_directory(Path::new(file_path));
+    }
     let file = File::open(file_path)?;
     let reader = BufReader::new(file);
     get_rulesets_from_reader(reader)
@@ -15,6 +29,22 @@ pub fn get_rulesets_from_reader<R: std::io::Read>(reader: R) -> Result<Vec<RuleS
     Ok(serde_json::from_reader(reader)?)
 }
 
+/// Returns the rules of the rulesets enabled by a configuration file, with the severities and
+/// categories overridden by the configuration.
+pub fn get_rules_from_config_file(config: &ConfigFile, use_staging: bool) -> Result<Vec<Rule>> {
+    let overrides = RuleOverrides::from_config_file(config);
+    let rulesets = config.rulesets.keys().cloned().collect::<Vec<_>>();
+    let rules = get_rules_from_rulesets(&rulesets, use_staging)?;
+    Ok(rules
+        .into_iter()
+        .map(|rule| Rule {
+            severity: overrides.severity(&rule.name, rule.severity),
+            category: overrides.category(&rule.name, rule.category),
+            ..rule
+        })
+        .collect())
+}
+
 pub fn get_languages_for_rules(rules: &[Rule]) -> Vec<Language> {
     let languages_set: HashSet<Language> = HashSet::from_iter(rules.iter().map(|r| r.language));
     Vec::from_iter(languages_set.iter().cloned())
@@ -36,6 +66,158 @@ pub fn count_violations_by_severities(
         .sum()
 }
 
+/// Returns a warning for each deprecated rule, that mentions the rule that replaces it.
+pub fn get_deprecated_rules_warnings(rules: &[Rule]) -> Vec<String> {
+    rules
+        .iter()
+        .filter(|rule| rule.deprecated)
+        .map(|rule| match &rule.replaced_by {
+            Some(replacement) => format!(
+                "rule {} is deprecated, use {} instead",
+                rule.name, replacement
+            ),
+            None => format!("rule {} is deprecated", rule.name),
+        })
+        .collect()
+}
+
+/// Returns true if the rule returns fixes when it's executed on the code of its tests. The fixes of
+/// a rule are only known by executing it: a rule without tests is reported without fixes.
+pub fn rule_has_fixes(rule: &Rule) -> bool {
+    let Ok(rule_internal) = rule.to_rule_internal() else {
+        return false;
+    };
+    rule.tests.iter().any(|test| {
+        let Ok(code) = decode_base64_string(test.code_base64.clone()) else {
+            return false;
+        };
+        let request = AnalysisRequest::new(&test.filename, code)
+            .language(rule.language)
+            .rules([&rule_internal]);
+        analyze_file(&request).is_ok_and(|report| report.violations().any(|v| !v.fixes.is_empty()))
+    })
+}
+
+/// Returns the arguments of a rule, with the value set by the configuration file for the root of
+/// the repository, if any. Arguments without a value use the default of the rule.
+pub fn get_rule_arguments(
+    rule: &Rule,
+    argument_provider: &ArgumentProvider,
+) -> Vec<(String, Option<String>)> {
+    let mut configured = argument_provider.get_arguments(&split_path(""), &rule.name);
+    rule.arguments
+        .iter()
+        .map(|argument| {
+            let name = decode_base64_string(argument.name_base64.clone())
+                .unwrap_or_else(|_| argument.name_base64.clone());
+            let value = configured.remove(&name);
+            (name, value)
+        })
+        .collect()
+}
+
+/// Returns the list of the rules, one line per rule sorted by name, with their language, severity,
+/// category, whether they have fixes, their arguments (see [`get_rule_arguments`]), and whether
+/// they're deprecated.
+pub fn format_rules_list(rules: &[Rule], argument_provider: &ArgumentProvider) -> String {
+    let mut rules = rules.iter().collect::<Vec<_>>();
+    rules.sort_by(|a, b| a.name.cmp(&b.name));
+    let name_width = rules.iter().map(|r| r.name.len()).max().unwrap_or(0).max(4);
+    let mut list = format!(
+        "{:name_width$}  {:10}  {:8}  {:14}  {:7}  ARGUMENTS\n",
+        "NAME", "LANGUAGE", "SEVERITY", "CATEGORY", "AUTOFIX"
+    );
+    for rule in rules {
+        let arguments = get_rule_arguments(rule, argument_provider)
+            .into_iter()
+            .map(|(name, value)| match value {
+                Some(value) => format!("{}={}", name, value),
+                None => name,
+            })
+            .collect::<Vec<_>>();
+        let mut line = format!(
+            "{:name_width$}  {:10}  {:8}  {:14}  {:7}  {}",
+            rule.name,
+            rule.language.to_string(),
+            rule.severity.to_string(),
+            rule.category.to_string(),
+            if rule_has_fixes(rule) { "yes" } else { "no" },
+            if arguments.is_empty() {
+                "-".to_string()
+            } else {
+                arguments.join(",")
+            }
+        );
+        if rule.deprecated {
+            line.push_str("  (deprecated");
+            if let Some(replacement) = &rule.replaced_by {
+                line.push_str(&format!(", replaced by {}", replacement));
+            }
+            line.push(')');
+        }
+        list.push_str(&line);
+        list.push('\n');
+    }
+    list
+}
+
+/// Returns the details of a rule: its metadata, arguments, tree-sitter queries and JavaScript code.
+pub fn describe_rule(rule: &Rule, argument_provider: &ArgumentProvider) -> String {
+    let decode = |value: &str| {
+        decode_base64_string(value.to_string()).unwrap_or_else(|_| "<invalid base64>".to_string())
+    };
+    let mut lines = vec![
+        format!("name          : {}", rule.name),
+        format!("language      : {}", rule.language),
+        format!("severity      : {}", rule.severity),
+        format!("category      : {}", rule.category),
+    ];
+    if let Some(cwe) = &rule.cwe {
+        lines.push(format!("cwe           : {}", cwe));
+    }
+    lines.push(format!(
+        "autofix       : {}",
+        if rule_has_fixes(rule) { "yes" } else { "no" }
+    ));
+    if rule.deprecated {
+        let replacement = rule
+            .replaced_by
+            .as_ref()
+            .map(|r| format!(", replaced by {}", r))
+            .unwrap_or_default();
+        lines.push(format!("deprecated    : yes{}", replacement));
+    }
+    lines.push(format!("documentation : {}", rule.get_url()));
+    if let Some(description) = &rule.short_description_base64 {
+        lines.push(format!("description   : {}", decode(description)));
+    }
+
+    let arguments = get_rule_arguments(rule, argument_provider);
+    if !arguments.is_empty() {
+        lines.push("\nArguments".to_string());
+        for (argument, (name, value)) in rule.arguments.iter().zip(arguments) {
+            lines.push(format!(
+                "  {}: {} - {}",
+                name,
+                value.unwrap_or_else(|| "(rule default)".to_string()),
+                decode(&argument.description_base64)
+            ));
+        }
+    }
+    if let Some(query) = &rule.tree_sitter_query_base64 {
+        lines.push(format!("\nQuery\n{}", decode(query).trim_end()));
+    }
+    for query in &rule.tree_sitter_queries {
+        lines.push(format!(
+            "\nQuery `{}`\n{}",
+            query.name,
+            decode(&query.query_base64).trim_end()
+        ));
+    }
+    lines.push(format!("\nCode\n{}", decode(&rule.code_base64).trim_end()));
+    lines.join("\n") + "\n"
+}
+
 #[cfg(test)]
 mod tests {
     use kernel::model::common::Position;
@@ -46,36 +228,72 @@ mod tests {
     };
 
     use super::*;
+    use kernel::model::rule::Argument;
+    use kernel::model::rule_test::RuleTest;
+    use kernel::utils::encode_base64_string;
 
     #[test]
     fn test_count_violations_by_severities() {
         let rr = RuleResult {
-            rule_name: "myrule".to_string(),
-            filename: "file.py".to_string(),
+            rule_name: "myrule".into(),
+            filename: "file.py".into(),
             violations: vec![
                 Violation {
                     start: Position { line: 10, col: 12 },
                     end: Position { line: 12, col: 10 },
-                    message: "message".to_string(),
+                    message: "message".into(),
                     severity: RuleSeverity::Error,
                     category: RuleCategory::Performance,
                     fixes: vec![],
+                    merged_rules: vec![],
+                    tags: vec![],
+                    cwe: vec![],
+                    documentation_url: None,
+                    related_locations: vec![],
+                    file_level: false,
+                    fingerprint: None,
+                    owners: vec![],
+                    snippet: None,
+                    offsets: None,
+                    last_commit: None,
                 },
                 Violation {
                     start: Position { line: 10, col: 12 },
                     end: Position { line: 12, col: 10 },
-                    message: "message".to_string(),
+                    message: "message".into(),
                     severity: RuleSeverity::Notice,
                     category: RuleCategory::Performance,
                     fixes: vec![],
+                    merged_rules: vec![],
+                    tags: vec![],
+                    cwe: vec![],
+                    documentation_url: None,
+                    related_locations: vec![],
+                    file_level: false,
+                    fingerprint: None,
+                    owners: vec![],
+                    snippet: None,
+                    offsets: None,
+                    last_commit: None,
                 },
                 Violation {
                     start: Position { line: 10, col: 12 },
                     end: Position { line: 12, col: 10 },
-                    message: "message".to_string(),
+                    message: "message".into(),
                     severity: RuleSeverity::Notice,
                     category: RuleCategory::Performance,
                     fixes: vec![],
+                    merged_rules: vec![],
+                    tags: vec![],
+                    cwe: vec![],
+                    documentation_url: None,
+                    related_locations: vec![],
+                    file_level: false,
+                    fingerprint: None,
+                    owners: vec![],
+                    snippet: None,
+                    offsets: None,
+                    last_commit: None,
                 },
             ],
             errors: vec![],
@@ -84,6 +302,8 @@ mod tests {
             execution_time_ms: 0,
             parsing_time_ms: 0,
             query_node_time_ms: 0,
+            degraded: false,
+            profile: None,
         };
 
         let rule_results = [rr];
@@ -156,4 +376,111 @@ mod tests {
         assert_eq!(rule.description_base64, Some("RW5zdXJlIHRoYXQgc29tZSB3b3JkcyBhcmUgbm90IHVzZWQgaW4gdGhlIGNvZGViYXNlIGFuZCBzdWdnZXN0IHJlcGxhY2VtZW50IHdoZW4gYXBwcm9wcmlhdGUuCgpFeGFtcGxlcyBvZiByZXBsYWNlbWVudCBzdWdnZXN0aW9uczoKIC0gYGJsYWNrbGlzdGAgd2l0aCBgZGVueWxpc3RgCiAtIGB3aGl0ZWxpc3RgIHdpdGggYGFsbG93bGlzdGAKIC0gYG1hc3RlcmAgd2l0aCBgcHJpbWFyeWAKIC0gYHNsYXZlYCB3aXRoIGBzZWNvbmRhcnlg".to_string()));
         assert_eq!(rule.code_base64, "LyoqCiAqIEEgdmlzaXQgZnVuY3Rpb24KICogQHBhcmFtIHthbnl9IG5vZGUgQW4gQVNUIGFueSBub2RlLgogKiBAcGFyYW0ge3N0cmluZ30gZmlsZW5hbWUgQSBmaWxlbmFtZSBwYXJhbS4KICogQHBhcmFtIHtzdHJpbmd9IGNvZGUgQSBjb2RlIHBhcmFtLgogKiBAcmV0dXJucwogKi8KZnVuY3Rpb24gdmlzaXQobm9kZSwgZmlsZW5hbWUsIGNvZGUpIHsKICBjb25zdCBGT1JCSURERU5fTkFNRVMgPSBuZXcgTWFwKCk7CgogIEZPUkJJRERFTl9OQU1FUy5zZXQoImJsYWNrbGlzdCIsICJkZW55bGlzdCIpOwogIEZPUkJJRERFTl9OQU1FUy5zZXQoIndoaXRlbGlzdCIsICJhbGxvd2xpc3QiKTsKICBGT1JCSURERU5fTkFNRVMuc2V0KCJtYXN0ZXIiLCAicHJpbWFyeSIpOwogIEZPUkJJRERFTl9OQU1FUy5zZXQoInNsYXZlIiwgInNlY29uZGFyeSIpOwoKICBmdW5jdGlvbiByZXBsYWNlKHRleHQsIHJlcGxhY2VtZW50LCBwb3NpdGlvbkluVGV4dCkgewogICAgdmFyIHJlc3VsdCA9IHRleHQuc3Vic3RyaW5nKDAsIHBvc2l0aW9uSW5UZXh0KTsKICAgIHZhciBwb3MgPSBwb3NpdGlvbkluVGV4dDsKICAgIGZvcih2YXIgaSA9IDA7IGkgPCByZXBsYWNlbWVudC5sZW5ndGg7IGkrKykgewogICAgICAgIHZhciBjID0gdGV4dC5jaGFyQXQocG9zKTsKICAgICAgICBpZihjID49IDY1ICYmIGMgPCA2NSArIDI2KSB7CiAgICAgICAgICAgIHJlc3VsdCArPSByZXBsYWNlbWVudC5jaGFyQXQoaSkudG9VcHBlckNhc2UoKTsKICAgICAgICB9IGVsc2UgewogICAgICAgICAgICByZXN1bHQgKz0gcmVwbGFjZW1lbnQuY2hhckF0KGkpLnRvTG93ZXJDYXNlKCk7CiAgICAgICAgfQogICAgICAgIHBvcyA9IHBvcyArIDE7CiAgICB9CiAgICByZXN1bHQgPSByZXN1bHQgKyB0ZXh0LnN1YnN0cmluZyhwb3MgKyAxLCB0ZXh0Lmxlbmd0aCk7CiAgICByZXR1cm4gcmVzdWx0OwogIH0KCiAgY29uc3QgaGFuZGxlcklkZW50aWZpZXIgPSAoaWRlbnRpZmllcikgPT4gewogICAgY29uc3QgYyA9IGdldENvZGUoaWRlbnRpZmllci5zdGFydCwgaWRlbnRpZmllci5lbmQsIGNvZGUpOwogICAgZm9yIChsZXQgW2tleSwgdmFsdWVdIG9mIEZPUkJJRERFTl9OQU1FUykgewogICAgICBjb25zdCBwb3MgPSBjLnRvTG93ZXJDYXNlKCkuaW5kZXhPZihrZXkpOwogICAgICBpZiAocG9zICE9PSAtMSkgewogICAgICAgIGNvbnN0IG5ld0NvZGUgPSByZXBsYWNlKGMsIHZhbHVlLCBwb3MpOwogICAgICAgIGNvbnN0IGVyciA9IGJ1aWxkRXJyb3IoCiAgICAgICAgICBpZGVudGlmaWVyLnN0YXJ0LmxpbmUsIGlkZW50aWZpZXIuc3RhcnQuY29sLAogICAgICAgICAgaWRlbnRpZmllci5lbmQubGluZSwgaWRlbnRpZmllci5lbmQuY29sLAogICAgICAgICAgYHN0cmluZyAke2tleX0gZGlzY291cmFnZWRgLAogICAgICAgICAgIldBUk5JTkciLAogICAgICAgICAgIkNPREVfU1RZTEUiCiAgICAgICAgKTsKICAgICAgICBjb25zdCBlID0gYnVpbGRFZGl0VXBkYXRlKAogICAgICAgICAgaWRlbnRpZmllci5zdGFydC5saW5lLCBpZGVudGlmaWVyLnN0YXJ0LmNvbCwKICAgICAgICAgIGlkZW50aWZpZXIuZW5kLmxpbmUsIGlkZW50aWZpZXIuZW5kLmNvbCwKICAgICAgICAgIG5ld0NvZGUKICAgICAgICApOwogICAgICAgIGNvbnN0IGYgPSBidWlsZEZpeChgdXNlICR7dmFsdWV9IGluc3RlYWRgLCBbZV0pOwogICAgICAgIGFkZEVycm9yKGVyci5hZGRGaXgoZikpOwogICAgICB9CiAgICB9CiAgfTsKCiAgaGFuZGxlcklkZW50aWZpZXIobm9kZS5jYXB0dXJlc1siZnVuY3Rpb25uYW1lIl0pOwogIGNvbnN0IHBhcmFtZXRlcnMgPSBub2RlLmNhcHR1cmVzWyJwYXJhbWV0ZXJzIl0uY2hpbGRyZW4uZmlsdGVyKGUgPT4gZS5hc3RUeXBlID09PSAiaWRlbnRpZmllciIpOwogIHBhcmFtZXRlcnMuZm9yRWFjaCgoZSkgPT4gewogICAgaGFuZGxlcklkZW50aWZpZXIoZSk7CiAgfSk7Cn0K".to_string());
     }
+
+    fn rule(name: &str, deprecated: bool, replaced_by: Option<&str>) -> Rule {
+        Rule {
+            name: name.to_string(),
+            short_description_base64: None,
+            description_base64: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Warning,
+            language: Language::Python,
+            rule_type: RuleType::TreeSitterQuery,
+            entity_checked: None,
+            code_base64: String::new(),
+            cwe: None,
+            checksum: String::new(),
+            pattern: None,
+            tree_sitter_query_base64: None,
+            tree_sitter_queries: vec![],
+            arguments: vec![],
+            tests: vec![],
+            is_testing: false,
+            deprecated,
+            replaced_by: replaced_by.map(ToString::to_string),
+            allow_side_effects: false,
+            prerequisites: vec![],
+        }
+    }
+
+    #[test]
+    fn test_deprecated_rules() {
+        let rules = [
+            rule(
+                "python-best-practices/old-rule",
+                true,
+                Some("python-best-practices/new-rule"),
+            ),
+            rule("python-best-practices/new-rule", false, None),
+            rule("python-code-style/abandoned", true, None),
+        ];
+        assert_eq!(
+            get_deprecated_rules_warnings(&rules),
+            vec![
+                "rule python-best-practices/old-rule is deprecated, use python-best-practices/new-rule instead",
+                "rule python-code-style/abandoned is deprecated",
+            ]
+        );
+    }
+
+    /// The list of rules shows the arguments configured at the root of the repository, and whether
+    /// the rules have fixes.
+    #[test]
+    fn test_format_rules_list() {
+        let mut new_rule = rule("python-best-practices/new-rule", false, None);
+        // function visit(node) { addError(buildError(...).addFix(buildFix("fix", []))); }
+        new_rule.code_base64 = "ZnVuY3Rpb24gdmlzaXQobm9kZSkgewogIGFkZEVycm9yKGJ1aWxkRXJyb3IoMSwgMSwgMSwgMiwgIngiKS5hZGRGaXgoYnVpbGRGaXgoImZpeCIsIFtdKSkpOwp9".to_string();
+        new_rule.tree_sitter_query_base64 =
+            Some(encode_base64_string("(module) @module".to_string()));
+        // the fixes are the ones the rule returns on the code of its tests
+        new_rule.tests = vec![RuleTest {
+            annotation_count: 1,
+            filename: "test.py".to_string(),
+            code_base64: encode_base64_string("x = 1\n".to_string()),
+        }];
+        new_rule.arguments = ["max-length", "min-length"]
+            .into_iter()
+            .map(|name| Argument {
+                name_base64: encode_base64_string(name.to_string()),
+                description_base64: encode_base64_string(format!("the {name}")),
+            })
+            .collect();
+        let rules = [
+            rule(
+                "python-best-practices/old-rule",
+                true,
+                Some("python-best-practices/new-rule"),
+            ),
+            new_rule,
+            rule("python-code-style/abandoned", true, None),
+        ];
+        let mut argument_provider = ArgumentProvider::new();
+        argument_provider.add_argument(
+            "python-best-practices/new-rule",
+            &split_path(""),
+            "max-length",
+            "80",
+        );
+        argument_provider.add_argument(
+            "python-best-practices/new-rule",
+            &split_path("tests"),
+            "min-length",
+            "2",
+        );
+        assert_eq!(
+            format_rules_list(&rules, &argument_provider),
+            "\
+NAME                            LANGUAGE    SEVERITY  CATEGORY        AUTOFIX  ARGUMENTS
+python-best-practices/new-rule  python      warning   code_style      yes      max-length=80,min-length
+python-best-practices/old-rule  python      warning   code_style      no       -  (deprecated, replaced by python-best-practices/new-rule)
+python-code-style/abandoned     python      warning   code_style      no       -  (deprecated)
+"
+        );
+
+        let details = describe_rule(&rules[1], &argument_provider);
+        assert!(details.contains("autofix       : yes\n"));
+        assert!(details.contains("  max-length: 80 - the max-length\n"));
+        assert!(details.contains("  min-length: (rule default) - the min-length\n"));
+        assert!(details.ends_with("\nCode\nfunction visit(node) {\n  addError(buildError(1, 1, 1, 2, \"x\").addFix(buildFix(\"fix\", [])));\n}\n"));
+    }
 }
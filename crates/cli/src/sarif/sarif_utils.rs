@@ -16,7 +16,7 @@ use serde_sarif::sarif::{
 
 use crate::constants::{SARIF_PROPERTY_DATADOG_FINGERPRINT, SARIF_PROPERTY_SHA};
 use kernel::model::rule::{RuleCategory, RuleSeverity};
-use kernel::model::violation::Violation;
+use kernel::model::violation::{Snippet, Violation};
 use kernel::model::{
     common::PositionBuilder,
     rule::{Rule, RuleResult},
@@ -25,7 +25,7 @@ use kernel::model::{
 
 use crate::file_utils::get_fingerprint_for_violation;
 use crate::model::datadog_api::DiffAwareData;
-use crate::secrets::{SecretResult, SecretRule};
+use crate::secrets::{SecretLocation, SecretResult, SecretRule};
 
 trait IntoSarif {
     type SarifType;
@@ -36,12 +36,15 @@ trait IntoSarif {
 /// The `SarifReportMetadata` structure contains all metadata being added to the sarif report.
 /// Those metadata is being added as property is being used to enhance the generation
 /// of the SARIF report.
+#[derive(Clone)]
 pub struct SarifReportMetadata {
     pub add_git_info: bool,
     pub debug: bool,
     pub config_digest: String,
     pub diff_aware_parameters: Option<DiffAwareData>,
     pub execution_time_secs: u64,
+    /// Whether the analysis was interrupted before all the files were analyzed.
+    pub incomplete: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -141,6 +144,14 @@ impl SarifRuleResult {
             SarifRuleResult::Secret(r) => r.rule_id.as_str(),
         }
     }
+
+    /// The other locations of the same secret, reported as additional locations of its result.
+    fn other_locations(&self) -> &[SecretLocation] {
+        match self {
+            SarifRuleResult::StaticAnalysis(_) => &[],
+            SarifRuleResult::Secret(r) => r.other_locations.as_slice(),
+        }
+    }
 }
 
 impl TryFrom<RuleResult> for SarifRuleResult {
@@ -179,6 +190,7 @@ pub struct SarifGenerationOptions {
     pub diff_aware_parameters: Option<DiffAwareData>,
     pub repository_directory: String,
     pub execution_time_secs: u64,
+    pub incomplete: bool,
 }
 
 impl IntoSarif for &Rule {
@@ -221,6 +233,12 @@ impl IntoSarif for &Rule {
         if self.is_testing {
             tags.push("DATADOG_TESTING:true".to_string());
         }
+        if self.deprecated {
+            tags.push("DATADOG_DEPRECATED:true".to_string());
+        }
+        if let Some(replaced_by) = self.replaced_by.as_ref() {
+            tags.push(format!("DATADOG_REPLACED_BY:{}", replaced_by));
+        }
         let props = PropertyBagBuilder::default().tags(tags).build().unwrap();
         builder.properties(props);
 
@@ -332,6 +350,14 @@ impl IntoSarif for &SecretRule {
             .tags(vec![SarifRule::rule_type_tag("SECRET")])
             .build()
             .unwrap();
+        if !self.remediation.is_empty() {
+            builder.help(
+                sarif::MultiformatMessageStringBuilder::default()
+                    .text(&self.remediation)
+                    .build()
+                    .unwrap(),
+            );
+        }
 
         builder
             .id(&self.rule_id)
@@ -369,6 +395,9 @@ fn generate_tool_section(rules: &[SarifRule], options: &SarifGenerationOptions)
     } else {
         tags.push("DATADOG_DIFF_AWARE_ENABLED:false".to_string());
     }
+    if options.incomplete {
+        tags.push("DATADOG_INCOMPLETE:true".to_string());
+    }
 
     let driver: ToolComponent = ToolComponentBuilder::default()
         .name("datadog-static-analyzer")
@@ -460,7 +489,48 @@ fn encode_filename(filename: String) -> String {
     return utf8_percent_encode(filename.as_str(), FRAGMENT).collect();
 }
 
+/// Returns the lines of the violation in its snippet, without the lines of context.
+fn snippet_lines(snippet: &Snippet, violation: &Violation) -> String {
+    let skip = violation.start.line.saturating_sub(snippet.start_line) as usize;
+    let take = (violation.end.line.max(violation.start.line) - violation.start.line) as usize + 1;
+    snippet
+        .text
+        .lines()
+        .skip(skip)
+        .take(take)
+        .map(|line| format!("{line}\n"))
+        .collect()
+}
+
 // Generate the tool section that reports all the rules being run
+/// Returns the tags for the metadata attached to a violation by its rule: the tags themselves,
+/// its CWEs (unless the rule already reports them), its documentation URL, the owners of its file
+/// and its last commit.
+fn get_violation_tags(violation: &Violation, rule_tags: &[String]) -> Vec<String> {
+    let mut tags = violation.tags.clone();
+    for cwe in &violation.cwe {
+        let tag = format!("CWE:{}", cwe);
+        if !rule_tags.contains(&tag) && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    if let Some(url) = &violation.documentation_url {
+        tags.push(format!("DATADOG_DOCUMENTATION_URL:{}", url));
+    }
+    for owner in &violation.owners {
+        tags.push(format!("DATADOG_OWNER:{}", owner));
+    }
+    if let Some(last_commit) = &violation.last_commit {
+        tags.push(format!("DATADOG_LAST_COMMIT_SHA:{}", last_commit.sha));
+        tags.push(format!(
+            "DATADOG_LAST_COMMIT_AUTHOR:{}",
+            last_commit.author_email
+        ));
+        tags.push(format!("DATADOG_LAST_COMMIT_DATE:{}", last_commit.date));
+    }
+    tags
+}
+
 fn generate_results(
     rules: &[SarifRule],
     rules_results: &[SarifRuleResult],
@@ -496,6 +566,10 @@ fn generate_results(
             }
 
             if let SarifRuleResult::Secret(detected) = rule_result {
+                // the severity of a secret depends on its validation and its context
+                if detected.violation.severity != RuleSeverity::None {
+                    result_builder.level(get_level_from_severity(detected.violation.severity));
+                }
                 tags.push(format!(
                     "DATADOG_VALIDATION_STATUS:{}",
                     detected.status.to_string().to_uppercase()
@@ -505,26 +579,110 @@ fn generate_results(
             let options = options_orig.clone();
             rule_result.violations().iter().map(move |violation| {
                 // if we find the rule for this violation, get the id, level and category
+                let mut physical_location = PhysicalLocationBuilder::default();
+                physical_location.artifact_location(
+                    ArtifactLocationBuilder::default()
+                        .uri(encode_filename(rule_result.file_path().to_string()))
+                        .build()
+                        .unwrap(),
+                );
+                // A file-level violation refers to the whole file, so it doesn't have a region.
+                if !violation.file_level {
+                    let mut region = RegionBuilder::default();
+                    region
+                        .start_line(violation.start.line)
+                        .start_column(violation.start.col)
+                        .end_line(violation.end.line)
+                        .end_column(violation.end.col);
+                    if let Some(offsets) = &violation.offsets {
+                        region
+                            .byte_offset(i64::try_from(offsets.start_byte)?)
+                            .byte_length(i64::try_from(
+                                offsets.end_byte.saturating_sub(offsets.start_byte),
+                            )?);
+                    }
+                    if let Some(snippet) = &violation.snippet {
+                        region.snippet(
+                            sarif::ArtifactContentBuilder::default()
+                                .text(snippet_lines(snippet, violation))
+                                .build()?,
+                        );
+                        physical_location.context_region(
+                            RegionBuilder::default()
+                                .start_line(snippet.start_line)
+                                .end_line(snippet.end_line)
+                                .snippet(
+                                    sarif::ArtifactContentBuilder::default()
+                                        .text(snippet.text.clone())
+                                        .build()?,
+                                )
+                                .build()?,
+                        );
+                    }
+                    physical_location.region(region.build()?);
+                }
                 let location = LocationBuilder::default()
-                    .physical_location(
-                        PhysicalLocationBuilder::default()
-                            .artifact_location(
-                                ArtifactLocationBuilder::default()
-                                    .uri(encode_filename(rule_result.file_path().to_string()))
-                                    .build()
-                                    .unwrap(),
-                            )
-                            .region(
-                                RegionBuilder::default()
-                                    .start_line(violation.start.line)
-                                    .start_column(violation.start.col)
-                                    .end_line(violation.end.line)
-                                    .end_column(violation.end.col)
+                    .physical_location(physical_location.build()?)
+                    .build()?;
+                let mut locations = vec![location];
+                for other in rule_result.other_locations() {
+                    locations.push(
+                        LocationBuilder::default()
+                            .physical_location(
+                                PhysicalLocationBuilder::default()
+                                    .artifact_location(
+                                        ArtifactLocationBuilder::default()
+                                            .uri(encode_filename(other.file_path.clone()))
+                                            .build()?,
+                                    )
+                                    .region(
+                                        RegionBuilder::default()
+                                            .start_line(other.start.line)
+                                            .start_column(other.start.col)
+                                            .end_line(other.end.line)
+                                            .end_column(other.end.col)
+                                            .build()?,
+                                    )
                                     .build()?,
                             )
                             .build()?,
-                    )
-                    .build()?;
+                    );
+                }
+
+                let related_locations = violation
+                    .related_locations
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, related)| {
+                        Ok(LocationBuilder::default()
+                            .id(i64::try_from(idx)?)
+                            .physical_location(
+                                PhysicalLocationBuilder::default()
+                                    .artifact_location(
+                                        ArtifactLocationBuilder::default()
+                                            .uri(encode_filename(
+                                                rule_result.file_path().to_string(),
+                                            ))
+                                            .build()?,
+                                    )
+                                    .region(
+                                        RegionBuilder::default()
+                                            .start_line(related.start.line)
+                                            .start_column(related.start.col)
+                                            .end_line(related.end.line)
+                                            .end_column(related.end.col)
+                                            .build()?,
+                                    )
+                                    .build()?,
+                            )
+                            .message(
+                                MessageBuilder::default()
+                                    .text(related.message.clone())
+                                    .build()?,
+                            )
+                            .build()?)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
 
                 let fixes: Vec<Fix> = violation
                     .fixes
@@ -562,13 +720,16 @@ fn generate_results(
                     None
                 };
 
-                let fingerprint_option = get_fingerprint_for_violation(
-                    rule_result.rule_name().to_string(),
-                    violation,
-                    Path::new(options.repository_directory.as_str()),
-                    Path::new(rule_result.file_path()),
-                    options.debug,
-                );
+                // The fingerprint is computed when the file is analyzed, which avoids reading it again.
+                let fingerprint_option = violation.fingerprint.clone().or_else(|| {
+                    get_fingerprint_for_violation(
+                        rule_result.rule_name().to_string(),
+                        violation,
+                        Path::new(options.repository_directory.as_str()),
+                        Path::new(rule_result.file_path()),
+                        options.debug,
+                    )
+                });
 
                 let partial_fingerprints: BTreeMap<String, String> =
                     match (sha_option, fingerprint_option) {
@@ -585,20 +746,35 @@ fn generate_results(
                         _ => BTreeMap::new(),
                     };
 
+                let mut result_builder = result_builder.clone();
+                if !related_locations.is_empty() {
+                    result_builder.related_locations(related_locations);
+                }
+
                 Ok(result_builder
-                    .clone()
                     .rule_id(rule_result.rule_name())
-                    .locations([location])
+                    .locations(locations)
                     .fixes(fixes)
                     .message(
                         MessageBuilder::default()
-                            .text(violation.message.clone())
+                            .text(violation.message.to_string())
                             .build()
                             .unwrap(),
                     )
                     .properties(
                         PropertyBagBuilder::default()
-                            .tags(tags.clone())
+                            .tags(
+                                tags.iter()
+                                    .cloned()
+                                    .chain(
+                                        violation
+                                            .merged_rules
+                                            .iter()
+                                            .map(|r| format!("DATADOG_MERGED_RULE:{}", r)),
+                                    )
+                                    .chain(get_violation_tags(violation, &tags))
+                                    .collect::<Vec<_>>(),
+                            )
                             .build()
                             .unwrap(),
                     )
@@ -639,6 +815,7 @@ pub fn generate_sarif_report(
         diff_aware_parameters: tool_information.diff_aware_parameters.clone(),
         repository_directory: directory.clone(),
         execution_time_secs: tool_information.execution_time_secs,
+        incomplete: tool_information.incomplete,
     };
 
     let run = RunBuilder::default()
@@ -662,7 +839,10 @@ mod tests {
     use kernel::model::{
         common::{Language, Position, PositionBuilder},
         rule::{RuleBuilder, RuleCategory, RuleResultBuilder, RuleSeverity, RuleType},
-        violation::{EditBuilder, EditType, FixBuilder as RosieFixBuilder, ViolationBuilder},
+        violation::{
+            EditBuilder, EditType, FixBuilder as RosieFixBuilder, LastCommit,
+            RelatedLocationBuilder, ViolationBuilder,
+        },
     };
 
     use super::*;
@@ -743,6 +923,7 @@ mod tests {
                 config_digest: "5d7273dec32b80788b4d3eac46c866f0".to_string(),
                 diff_aware_parameters: None,
                 execution_time_secs: 42,
+                incomplete: false,
             },
         )
         .expect("generate sarif report");
@@ -757,13 +938,210 @@ mod tests {
         assert!(validate_data(&sarif_report_to_string));
     }
 
+    /// The related locations of a violation are reported as the result's `relatedLocations`.
+    #[test]
+    fn test_generate_sarif_report_related_locations() {
+        let rule = RuleBuilder::default()
+            .name("my-rule".to_string())
+            .description_base64(None)
+            .language(Language::Python)
+            .checksum("blabla".to_string())
+            .pattern(None)
+            .tree_sitter_query_base64(None)
+            .category(RuleCategory::Security)
+            .code_base64("Zm9vYmFyYmF6".to_string())
+            .short_description_base64(None)
+            .entity_checked(None)
+            .rule_type(RuleType::TreeSitterQuery)
+            .severity(RuleSeverity::Error)
+            .cwe(None)
+            .arguments(vec![])
+            .tests(vec![])
+            .is_testing(false)
+            .build()
+            .unwrap();
+
+        let rule_result = RuleResultBuilder::default()
+            .rule_name("my-rule".to_string())
+            .filename("myfile".to_string())
+            .violations(vec![ViolationBuilder::default()
+                .start(PositionBuilder::default().line(5).col(1).build().unwrap())
+                .end(PositionBuilder::default().line(5).col(10).build().unwrap())
+                .message("violation message".to_string())
+                .severity(RuleSeverity::Error)
+                .category(RuleCategory::Security)
+                .fixes(vec![])
+                .related_locations(vec![RelatedLocationBuilder::default()
+                    .start(PositionBuilder::default().line(1).col(5).build().unwrap())
+                    .end(PositionBuilder::default().line(1).col(8).build().unwrap())
+                    .message("tainted source here".to_string())
+                    .build()
+                    .unwrap()])
+                .build()
+                .unwrap()])
+            .output(None)
+            .errors(vec![])
+            .execution_time_ms(42)
+            .parsing_time_ms(0)
+            .query_node_time_ms(0)
+            .execution_error(None)
+            .build()
+            .expect("building violation");
+
+        let sarif_report = generate_sarif_report(
+            &[rule.into()],
+            &[rule_result.try_into().unwrap()],
+            &"mydir".to_string(),
+            SarifReportMetadata {
+                add_git_info: false,
+                debug: false,
+                config_digest: "5d7273dec32b80788b4d3eac46c866f0".to_string(),
+                diff_aware_parameters: None,
+                execution_time_secs: 42,
+                incomplete: false,
+            },
+        )
+        .expect("generate sarif report");
+
+        let sarif_report_to_string = serde_json::to_value(sarif_report).unwrap();
+        assert_json_eq!(
+            sarif_report_to_string["runs"][0]["results"][0]["relatedLocations"],
+            serde_json::json!([{"id":0,"message":{"text":"tainted source here"},"physicalLocation":{"artifactLocation":{"uri":"myfile"},"region":{"endColumn":8,"endLine":1,"startColumn":5,"startLine":1}}}])
+        );
+        assert!(validate_data(&sarif_report_to_string));
+    }
+
+    /// A file-level violation is reported without a region.
+    #[test]
+    fn test_generate_sarif_report_file_level() {
+        let rule = RuleBuilder::default()
+            .name("my-rule".to_string())
+            .description_base64(None)
+            .language(Language::Python)
+            .checksum("blabla".to_string())
+            .pattern(None)
+            .tree_sitter_query_base64(None)
+            .category(RuleCategory::BestPractices)
+            .code_base64("Zm9vYmFyYmF6".to_string())
+            .short_description_base64(None)
+            .entity_checked(None)
+            .rule_type(RuleType::TreeSitterQuery)
+            .severity(RuleSeverity::Warning)
+            .cwe(None)
+            .arguments(vec![])
+            .tests(vec![])
+            .is_testing(false)
+            .build()
+            .unwrap();
+
+        let rule_result = RuleResultBuilder::default()
+            .rule_name("my-rule".to_string())
+            .filename("myfile".to_string())
+            .violations(vec![ViolationBuilder::default()
+                .start(PositionBuilder::default().line(1).col(1).build().unwrap())
+                .end(PositionBuilder::default().line(1).col(1).build().unwrap())
+                .message("missing license header".to_string())
+                .severity(RuleSeverity::Warning)
+                .category(RuleCategory::BestPractices)
+                .fixes(vec![])
+                .file_level(true)
+                .build()
+                .unwrap()])
+            .output(None)
+            .errors(vec![])
+            .execution_time_ms(42)
+            .parsing_time_ms(0)
+            .query_node_time_ms(0)
+            .execution_error(None)
+            .build()
+            .expect("building violation");
+
+        let sarif_report = generate_sarif_report(
+            &[rule.into()],
+            &[rule_result.try_into().unwrap()],
+            &"mydir".to_string(),
+            SarifReportMetadata {
+                add_git_info: false,
+                debug: false,
+                config_digest: "5d7273dec32b80788b4d3eac46c866f0".to_string(),
+                diff_aware_parameters: None,
+                execution_time_secs: 42,
+                incomplete: false,
+            },
+        )
+        .expect("generate sarif report");
+
+        let sarif_report_to_string = serde_json::to_value(sarif_report).unwrap();
+        assert_json_eq!(
+            sarif_report_to_string["runs"][0]["results"][0]["locations"],
+            serde_json::json!([{"physicalLocation":{"artifactLocation":{"uri":"myfile"}}}])
+        );
+        assert!(validate_data(&sarif_report_to_string));
+    }
+
+    /// The metadata attached to a violation is reported as tags, without repeating the rule's CWE.
+    #[test]
+    fn test_get_violation_tags() {
+        let violation = ViolationBuilder::default()
+            .start(PositionBuilder::default().line(1).col(2).build().unwrap())
+            .end(PositionBuilder::default().line(3).col(4).build().unwrap())
+            .message("violation message".to_string())
+            .severity(RuleSeverity::Error)
+            .category(RuleCategory::Security)
+            .fixes(vec![])
+            .tags(vec!["injection".to_string()])
+            .cwe(vec![1234, 79, 79])
+            .documentation_url(Some("https://example.com/docs".to_string()))
+            .last_commit(Some(LastCommit {
+                sha: "abcdef".to_string(),
+                author: "Jane Doe".to_string(),
+                author_email: "jane@example.com".to_string(),
+                date: 1_700_000_000,
+            }))
+            .build()
+            .unwrap();
+        let rule_tags = vec!["CWE:1234".to_string()];
+        assert_eq!(
+            get_violation_tags(&violation, &rule_tags),
+            vec![
+                "injection".to_string(),
+                "CWE:79".to_string(),
+                "DATADOG_DOCUMENTATION_URL:https://example.com/docs".to_string(),
+                "DATADOG_LAST_COMMIT_SHA:abcdef".to_string(),
+                "DATADOG_LAST_COMMIT_AUTHOR:jane@example.com".to_string(),
+                "DATADOG_LAST_COMMIT_DATE:1700000000".to_string(),
+            ]
+        );
+    }
+
+    /// The snippet of a region only contains the lines of the violation, not its context.
+    #[test]
+    fn test_snippet_lines() {
+        let violation = ViolationBuilder::default()
+            .start(PositionBuilder::default().line(3).col(1).build().unwrap())
+            .end(PositionBuilder::default().line(4).col(2).build().unwrap())
+            .message("violation message".to_string())
+            .severity(RuleSeverity::Error)
+            .category(RuleCategory::Security)
+            .fixes(vec![])
+            .build()
+            .unwrap();
+        let snippet = Snippet {
+            start_line: 2,
+            end_line: 5,
+            text: "line2\nline3\nline4\nline5\n".to_string(),
+        };
+        assert_eq!(snippet_lines(&snippet, &violation), "line3\nline4\n");
+    }
+
     #[test]
     fn test_generate_sarif_report_secret_happy_path() {
         let rule = SecretRule::new(
             "datadog-app-key",
             "Long description about detecting a Datadog secret...",
             "Short description",
-        );
+        )
+        .with_remediation("Revoke the key in Datadog, and create a new one.");
         let detected = SecretResult::new(
             "datadog-app-key",
             "folder/file.txt",
@@ -782,6 +1160,7 @@ mod tests {
                 config_digest: "5d7273dec32b80788b4d3eac46c866f0".to_string(),
                 diff_aware_parameters: None,
                 execution_time_secs: 42,
+                incomplete: false,
             },
         )
         .expect("sarif report should be able to be generated");
@@ -789,13 +1168,38 @@ mod tests {
         let sarif_report_to_string = serde_json::to_value(sarif_report).unwrap();
         assert_json_eq!(
             sarif_report_to_string,
-            serde_json::json!({"runs":[{"results":[{"fixes":[],"level":"note","locations":[{"physicalLocation":{"artifactLocation":{"uri":"folder/file.txt"},"region":{"endColumn":64,"endLine":1,"startColumn":24,"startLine":1}}}],"message":{"text":"A potential secret where validation was not attempted"},"partialFingerprints":{},"properties":{"tags":["DATADOG_CATEGORY:SECURITY","DATADOG_VALIDATION_STATUS:UNVALIDATED"]},"ruleId":"datadog-app-key","ruleIndex":0}],"tool":{"driver":{"informationUri":"https://www.datadoghq.com","name":"datadog-static-analyzer","version":CARGO_VERSION,"properties":{"tags":["DATADOG_DIFF_AWARE_CONFIG_DIGEST:5d7273dec32b80788b4d3eac46c866f0","DATADOG_EXECUTION_TIME_SECS:42","DATADOG_DIFF_AWARE_ENABLED:false"]},"rules":[{"fullDescription":{"text":"Long description about detecting a Datadog secret..."},"id":"datadog-app-key","properties":{"tags":["DATADOG_RULE_TYPE:SECRET"]},"shortDescription":{"text":"Short description"}}]}}}],"version":"2.1.0"}),
+            serde_json::json!({"runs":[{"results":[{"fixes":[],"level":"note","locations":[{"physicalLocation":{"artifactLocation":{"uri":"folder/file.txt"},"region":{"endColumn":64,"endLine":1,"startColumn":24,"startLine":1}}}],"message":{"text":"A potential secret where validation was not attempted"},"partialFingerprints":{},"properties":{"tags":["DATADOG_CATEGORY:SECURITY","DATADOG_VALIDATION_STATUS:UNVALIDATED"]},"ruleId":"datadog-app-key","ruleIndex":0}],"tool":{"driver":{"informationUri":"https://www.datadoghq.com","name":"datadog-static-analyzer","version":CARGO_VERSION,"properties":{"tags":["DATADOG_DIFF_AWARE_CONFIG_DIGEST:5d7273dec32b80788b4d3eac46c866f0","DATADOG_EXECUTION_TIME_SECS:42","DATADOG_DIFF_AWARE_ENABLED:false"]},"rules":[{"fullDescription":{"text":"Long description about detecting a Datadog secret..."},"help":{"text":"Revoke the key in Datadog, and create a new one."},"id":"datadog-app-key","properties":{"tags":["DATADOG_RULE_TYPE:SECRET"]},"shortDescription":{"text":"Short description"}}]}}}],"version":"2.1.0"}),
         );
 
         // validate the schema
         assert!(validate_data(&sarif_report_to_string));
     }
 
+    // The report of an interrupted analysis is marked as incomplete
+    #[test]
+    fn test_generate_sarif_incomplete() {
+        let sarif_report = generate_sarif_report(
+            &[],
+            &vec![],
+            &"mydir".to_string(),
+            SarifReportMetadata {
+                add_git_info: false,
+                debug: false,
+                config_digest: "5d7273dec32b80788b4d3eac46c866f0".to_string(),
+                diff_aware_parameters: None,
+                execution_time_secs: 42,
+                incomplete: true,
+            },
+        )
+        .expect("generate sarif report");
+
+        let sarif_report_to_string = serde_json::to_value(sarif_report).unwrap();
+        assert_json_eq!(
+            sarif_report_to_string,
+            serde_json::json!({"runs":[{"results":[],"tool":{"driver":{"informationUri":"https://www.datadoghq.com","name":"datadog-static-analyzer","version":CARGO_VERSION,"properties":{"tags":["DATADOG_DIFF_AWARE_CONFIG_DIGEST:5d7273dec32b80788b4d3eac46c866f0","DATADOG_EXECUTION_TIME_SECS:42","DATADOG_DIFF_AWARE_ENABLED:false","DATADOG_INCOMPLETE:true"]},"rules":[]}}}],"version":"2.1.0"})
+        );
+    }
+
     // Ensure that diff-aware scanning information are correctly surfaced
     #[test]
     fn test_generate_sarif_diff_aware_scanning() {
@@ -814,6 +1218,7 @@ mod tests {
                 config_digest: "5d7273dec32b80788b4d3eac46c866f0".to_string(),
                 diff_aware_parameters: Some(diff_aware_infos),
                 execution_time_secs: 42,
+                incomplete: false,
             },
         )
         .expect("generate sarif report");
@@ -893,6 +1298,7 @@ mod tests {
                 config_digest: "5d7273dec32b80788b4d3eac46c866f0".to_string(),
                 diff_aware_parameters: None,
                 execution_time_secs: 42,
+                incomplete: false,
             },
         )
         .expect("generate sarif report");
@@ -974,6 +1380,7 @@ mod tests {
                 config_digest: "5d7273dec32b80788b4d3eac46c866f0".to_string(),
                 diff_aware_parameters: None,
                 execution_time_secs: 42,
+                incomplete: false,
             },
         )
         .expect("generate sarif report");
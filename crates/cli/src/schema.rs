@@ -0,0 +1,76 @@
+use anyhow::{anyhow, Result};
+use kernel::model::common::OutputFormat;
+use kernel::model::rule::RuleResult;
+use serde_json::json;
+
+/// The SARIF 2.1.0 schema. Secrets findings are reported as regular SARIF results.
+const SARIF_SCHEMA: &str = include_str!("sarif/sarif-schema-2.1.0.json");
+
+/// Get the JSON Schema of the report generated for an output format.
+pub fn get_output_schema(output_format: &OutputFormat) -> Result<String> {
+    match output_format {
+        OutputFormat::Json => {
+            // The report is the list of results, unless the analysis was interrupted: the results
+            // are then wrapped in an object that marks them as incomplete.
+            let schema = serde_json::to_value(schemars::schema_for!(Vec<RuleResult>))?;
+            let results = json!({ "type": "array", "items": schema["items"] });
+            let schema = json!({
+                "$schema": schema["$schema"],
+                "title": "Report",
+                "oneOf": [
+                    results,
+                    {
+                        "type": "object",
+                        "required": ["incomplete", "results"],
+                        "properties": {
+                            "incomplete": { "const": true },
+                            "results": results,
+                        },
+                        "additionalProperties": false,
+                    },
+                ],
+                "definitions": schema["definitions"],
+            });
+            Ok(serde_json::to_string_pretty(&schema)?)
+        }
+        OutputFormat::Sarif => Ok(SARIF_SCHEMA.to_string()),
+        OutputFormat::Csv => Err(anyhow!("the csv output format does not have a JSON schema")),
+        OutputFormat::Html => Err(anyhow!(
+            "the html output format does not have a JSON schema"
+        )),
+        OutputFormat::Pretty => Err(anyhow!(
+            "the pretty output format does not have a JSON schema"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_json_output_schema() {
+        let schema = get_output_schema(&OutputFormat::Json).unwrap();
+        let schema: Value = serde_json::from_str(&schema).unwrap();
+        let reports = schema["oneOf"].as_array().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0]["type"], "array");
+        assert_eq!(reports[1]["type"], "object");
+        assert_eq!(reports[1]["properties"]["incomplete"]["const"], true);
+        assert_eq!(reports[1]["properties"]["results"], reports[0]);
+        assert_eq!(reports[0]["items"]["$ref"], "#/definitions/RuleResult");
+        let definitions = schema["definitions"].as_object().unwrap();
+        assert!(definitions.contains_key("RuleResult"));
+        assert!(definitions.contains_key("Violation"));
+        // The `Unknown` category is never serialized and must not be part of the schema.
+        assert!(!definitions["RuleCategory"].to_string().contains("Unknown"));
+    }
+
+    #[test]
+    fn test_output_schema_formats() {
+        let sarif = get_output_schema(&OutputFormat::Sarif).unwrap();
+        assert!(serde_json::from_str::<Value>(&sarif).is_ok());
+        assert!(get_output_schema(&OutputFormat::Csv).is_err());
+    }
+}
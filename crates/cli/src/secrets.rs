@@ -2,11 +2,18 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
-use kernel::model::common::Position;
+use crate::snippets::get_snippet;
+use kernel::model::common::{ColumnUnit, LineIndex, Position};
 use kernel::model::rule::{RuleCategory, RuleSeverity};
-use kernel::model::violation::Violation;
+use kernel::model::violation::{compute_fingerprint, Violation};
 use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+/// The character that replaces the characters of the secrets in their context.
+const REDACTION_CHAR: char = '*';
 
 /// A span of text that was detected as a potential secret, along with the validation result, if any.
 #[derive(Debug, Clone)]
@@ -15,6 +22,19 @@ pub struct SecretResult {
     pub file_path: String,
     pub status: ValidationStatus,
     pub violation: Violation,
+    /// The SHA-256 of the value of the secret, to find the same secret in other files.
+    pub value_hash: Option<String>,
+    /// The other locations of the same secret, once the identical secrets are grouped (see
+    /// [`group_identical_secrets`]).
+    pub other_locations: Vec<SecretLocation>,
+}
+
+/// A location of a secret.
+#[derive(Debug, Clone)]
+pub struct SecretLocation {
+    pub file_path: String,
+    pub start: Position,
+    pub end: Position,
 }
 
 impl SecretResult {
@@ -41,10 +61,21 @@ impl SecretResult {
         let violation = Violation {
             start,
             end,
-            message: message.to_string(),
+            message: message.into(),
             severity,
             category: RuleCategory::Security,
             fixes: vec![],
+            merged_rules: vec![],
+            tags: vec![],
+            cwe: vec![],
+            documentation_url: None,
+            related_locations: vec![],
+            file_level: false,
+            fingerprint: None,
+            owners: vec![],
+            snippet: None,
+            offsets: None,
+            last_commit: None,
         };
 
         Self {
@@ -52,8 +83,156 @@ impl SecretResult {
             file_path: file_path.into(),
             status,
             violation,
+            value_hash: None,
+            other_locations: vec![],
         }
     }
+
+    /// Sets the hash of the value of the secret, which identifies it across files.
+    pub fn set_value_hash(&mut self, value: &str) {
+        self.value_hash = Some(format!("{:x}", Sha256::digest(value.as_bytes())));
+    }
+
+    /// Sets the severity of the secret, as scored by the severity rules of its rule.
+    pub fn set_severity(&mut self, severity: RuleSeverity) {
+        self.violation.severity = severity;
+    }
+
+    /// Sets the position of the secret from its byte range in the content of its file: the columns
+    /// are in `column_unit`, as the columns of the violations of the rules, and the violation gets
+    /// the byte offsets of the secret. The position is left unchanged if the range is not in the
+    /// content (e.g. for the secrets of the entries of an archive).
+    pub fn locate(&mut self, content: &str, range: Range<usize>, column_unit: ColumnUnit) {
+        let (Some(start), Some(end)) = (
+            byte_position(content, range.start),
+            byte_position(content, range.end),
+        ) else {
+            return;
+        };
+        let line_index = LineIndex::new(content);
+        let (Some(start_col), Some(end_col)) = (
+            line_index.col_in(&start, column_unit),
+            line_index.col_in(&end, column_unit),
+        ) else {
+            return;
+        };
+        self.violation.offsets = line_index.offsets(&start, &end);
+        self.violation.start = Position {
+            line: start.line,
+            col: start_col,
+        };
+        self.violation.end = Position {
+            line: end.line,
+            col: end_col,
+        };
+    }
+
+    /// Embeds the lines of the secret in its violation, with `context_lines` lines around them.
+    /// `redacted_content` is the content of the file with its secrets redacted (see
+    /// [`redact_secrets`]), so that the report doesn't contain them.
+    pub fn set_redacted_context(&mut self, redacted_content: &str, context_lines: u32) {
+        self.violation.snippet = get_snippet(redacted_content, &self.violation, context_lines);
+    }
+
+    /// Sets the fingerprint of the secret from the content of its file. See [`compute_fingerprint`].
+    pub fn set_fingerprint(&mut self, content: &str) {
+        let line = self.violation.start.line as usize;
+        self.violation.fingerprint = line
+            .checked_sub(1)
+            .and_then(|index| content.lines().nth(index))
+            .map(|line_content| compute_fingerprint(&self.rule_id, &self.file_path, line_content));
+    }
+}
+
+/// Returns the position of a byte offset in the content (with the column in bytes), or `None` if
+/// the offset is not in the content or not at the boundary of a character.
+fn byte_position(content: &str, offset: usize) -> Option<Position> {
+    if !content.is_char_boundary(offset) {
+        return None;
+    }
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    Some(Position {
+        line: u32::try_from(before.matches('\n').count() + 1).ok()?,
+        col: u32::try_from(offset - line_start + 1).ok()?,
+    })
+}
+
+/// Returns the content of a file with the characters of its secrets (given by their byte range)
+/// replaced by `*`, keeping the line breaks so that the lines of the content don't change.
+pub fn redact_secrets(content: &str, secrets: &[Range<usize>]) -> String {
+    content
+        .char_indices()
+        .map(|(idx, ch)| {
+            if ch != '\n' && ch != '\r' && secrets.iter().any(|range| range.contains(&idx)) {
+                REDACTION_CHAR
+            } else {
+                ch
+            }
+        })
+        .collect()
+}
+
+/// Groups the secrets of a rule that have the same value into one finding: the first of them (in
+/// the order of the secrets) is kept, with the locations of the others. The secrets without a hash
+/// of their value are kept as they are. A finding has the highest severity of its secrets, and the
+/// strongest validation status (with its message): a secret that was validated as valid in any of
+/// its locations is reported as valid.
+pub fn group_identical_secrets(secrets: Vec<SecretResult>) -> Vec<SecretResult> {
+    let mut grouped = Vec::<SecretResult>::with_capacity(secrets.len());
+    let mut groups = HashMap::<(String, String), usize>::new();
+    for secret in secrets {
+        let Some(value_hash) = secret.value_hash.clone() else {
+            grouped.push(secret);
+            continue;
+        };
+        match groups.get(&(secret.rule_id.clone(), value_hash.clone())) {
+            Some(&idx) => {
+                let first = &mut grouped[idx];
+                if status_strength(secret.status) > status_strength(first.status) {
+                    first.status = secret.status;
+                    first.violation.message = secret.violation.message.clone();
+                }
+                if severity_rank(secret.violation.severity)
+                    > severity_rank(first.violation.severity)
+                {
+                    first.violation.severity = secret.violation.severity;
+                }
+                first.other_locations.push(SecretLocation {
+                    file_path: secret.file_path,
+                    start: secret.violation.start,
+                    end: secret.violation.end,
+                });
+                first.other_locations.extend(secret.other_locations);
+            }
+            None => {
+                groups.insert((secret.rule_id.clone(), value_hash), grouped.len());
+                grouped.push(secret);
+            }
+        }
+    }
+    grouped
+}
+
+/// The strength of a validation status when secrets are grouped: a secret confirmed valid is
+/// reported as such, and a result of a validation wins over no validation.
+fn status_strength(status: ValidationStatus) -> u8 {
+    match status {
+        ValidationStatus::Valid(_) => 3,
+        ValidationStatus::Inconclusive(_) => 2,
+        ValidationStatus::Invalid(_) => 1,
+        ValidationStatus::Unvalidated => 0,
+    }
+}
+
+/// The rank of a severity, from the least to the most severe.
+fn severity_rank(severity: RuleSeverity) -> u8 {
+    match severity {
+        RuleSeverity::None => 0,
+        RuleSeverity::Notice => 1,
+        RuleSeverity::Warning => 2,
+        RuleSeverity::Error => 3,
+    }
 }
 
 /// Metadata about a secret detection rule.
@@ -62,6 +241,8 @@ pub struct SecretRule {
     pub rule_id: String,
     pub description: String,
     pub short_description: String,
+    /// How to remediate a finding of the rule, empty if the rule doesn't say.
+    pub remediation: String,
 }
 
 impl SecretRule {
@@ -74,8 +255,14 @@ impl SecretRule {
             rule_id: rule_id.into(),
             description: description.into(),
             short_description: short_description.into(),
+            remediation: String::new(),
         }
     }
+
+    pub fn with_remediation(mut self, remediation: impl Into<String>) -> Self {
+        self.remediation = remediation.into();
+        self
+    }
 }
 
 #[cfg(feature = "secrets")]
@@ -85,6 +272,7 @@ impl From<secrets::scanner::RuleInfo> for SecretRule {
             rule_id: value.rule_id,
             description: value.description,
             short_description: value.short_description,
+            remediation: value.remediation,
         }
     }
 }
@@ -119,31 +307,138 @@ impl From<secrets::core::validator::SecretCategory> for ValidationStatus {
     fn from(value: secrets::core::validator::SecretCategory) -> Self {
         use secrets::core::validator::SecretCategory;
         match value {
-            SecretCategory::Valid(sev) => Self::Valid(as_rule_severity(sev)),
-            SecretCategory::Invalid(sev) => Self::Invalid(as_rule_severity(sev)),
-            SecretCategory::Inconclusive(sev) => Self::Inconclusive(as_rule_severity(sev)),
+            SecretCategory::Valid(sev) => Self::Valid(sev.into()),
+            SecretCategory::Invalid(sev) => Self::Invalid(sev.into()),
+            SecretCategory::Inconclusive(sev) => Self::Inconclusive(sev.into()),
         }
     }
 }
 
-// The following `as_*` functions work as pseudo "From" impls.
-// We use this strategy to avoid adding the `secrets`crate as a dependency of the static-analysis-kernel crate.
-
 #[cfg(feature = "secrets")]
-fn as_rule_severity(severity: secrets::core::validator::Severity) -> RuleSeverity {
-    use secrets::core::validator::Severity;
-    match severity {
-        Severity::Error => RuleSeverity::Error,
-        Severity::Warning => RuleSeverity::Warning,
-        Severity::Notice => RuleSeverity::Notice,
-        Severity::Info => RuleSeverity::None,
+impl From<ValidationStatus> for secrets::severity::FindingStatus {
+    fn from(value: ValidationStatus) -> Self {
+        match value {
+            ValidationStatus::Valid(_) => Self::Valid,
+            ValidationStatus::Invalid(_) => Self::Invalid,
+            ValidationStatus::Inconclusive(_) => Self::Inconclusive,
+            ValidationStatus::Unvalidated => Self::Unvalidated,
+        }
     }
 }
 
-#[cfg(feature = "secrets")]
-pub fn as_position(point: secrets::core::location::Point) -> Position {
-    Position {
-        line: point.line.get(),
-        col: point.col.get(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_secrets() {
+        let secret = |file_path: &str, value: Option<&str>, status| {
+            let mut secret = SecretResult::new(
+                "rule",
+                file_path,
+                status,
+                Position { line: 1, col: 1 },
+                Position { line: 1, col: 10 },
+            );
+            if let Some(value) = value {
+                secret.set_value_hash(value);
+            }
+            secret
+        };
+        let grouped = group_identical_secrets(vec![
+            secret("a.env", Some("token"), ValidationStatus::Unvalidated),
+            secret("b.env", Some("other"), ValidationStatus::Unvalidated),
+            secret("c.env", None, ValidationStatus::Unvalidated),
+            secret(
+                "d.env",
+                Some("token"),
+                ValidationStatus::Valid(RuleSeverity::Error),
+            ),
+            secret("e.env", Some("token"), ValidationStatus::Unvalidated),
+        ]);
+        let paths = grouped
+            .iter()
+            .map(|s| s.file_path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["a.env", "b.env", "c.env"]);
+        let others = grouped[0]
+            .other_locations
+            .iter()
+            .map(|l| l.file_path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(others, vec!["d.env", "e.env"]);
+        assert_eq!(
+            grouped[0].status,
+            ValidationStatus::Valid(RuleSeverity::Error)
+        );
+        assert!(grouped[1].other_locations.is_empty());
+
+        // the group has the highest severity and the strongest status of its secrets
+        let mut warning = secret("b.env", Some("token"), ValidationStatus::Unvalidated);
+        warning.violation.severity = RuleSeverity::Warning;
+        let grouped = group_identical_secrets(vec![
+            secret(
+                "a.env",
+                Some("token"),
+                ValidationStatus::Invalid(RuleSeverity::Notice),
+            ),
+            warning,
+            secret(
+                "c.env",
+                Some("token"),
+                ValidationStatus::Inconclusive(RuleSeverity::Notice),
+            ),
+        ]);
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(
+            grouped[0].status,
+            ValidationStatus::Inconclusive(RuleSeverity::Notice)
+        );
+        assert_eq!(grouped[0].violation.severity, RuleSeverity::Warning);
+    }
+
+    #[test]
+    fn locate_secret() {
+        let content = "const é = 1;\nlet key = \"sk_😀_abc123\"; // key\n";
+        let start = content.find("sk_").unwrap();
+        let end = content.find("\";").unwrap();
+        let mut secret = SecretResult::new(
+            "rule",
+            "file.js",
+            ValidationStatus::Unvalidated,
+            Position { line: 2, col: 1 },
+            Position { line: 2, col: 1 },
+        );
+        secret.locate(content, start..end, ColumnUnit::Chars);
+        let position = |p: &Position| (p.line, p.col);
+        assert_eq!(position(&secret.violation.start), (2, 12));
+        assert_eq!(position(&secret.violation.end), (2, 23));
+        let offsets = secret.violation.offsets.as_ref().unwrap();
+        assert_eq!(
+            &content[offsets.start_byte..offsets.end_byte],
+            "sk_😀_abc123"
+        );
+        assert_eq!(offsets.end_utf16_col, 23);
+
+        let redacted = redact_secrets(content, &[start..end]);
+        secret.set_redacted_context(&redacted, 1);
+        let snippet = secret.violation.snippet.unwrap();
+        assert_eq!(snippet.start_line, 1);
+        assert_eq!(
+            snippet.text,
+            "const é = 1;\nlet key = \"***********\"; // key\n"
+        );
+
+        // a range that is not in the content doesn't change the position
+        let mut secret = SecretResult::new(
+            "rule",
+            "archive.zip",
+            ValidationStatus::Unvalidated,
+            Position { line: 3, col: 5 },
+            Position { line: 3, col: 9 },
+        );
+        secret.locate(content, 1000..1004, ColumnUnit::Bytes);
+        assert_eq!(position(&secret.violation.start), (3, 5));
+        assert!(secret.violation.offsets.is_none());
     }
 }
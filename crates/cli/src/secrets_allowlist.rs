@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use kernel::model::common::Position;
+use kernel::model::config_file::{PathPattern, SecretsAllowlistConfig};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::secrets::SecretResult;
+
+/// The comment that ignores the secrets of its line, or of the line that follows it.
+pub const IGNORE_COMMENT: &str = "dd-secrets-ignore";
+
+/// Why a secret was not reported.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SuppressionReason {
+    /// The secret is on a line with a `dd-secrets-ignore` comment, or the line that follows it.
+    InlineComment,
+    /// The rule that found the secret ignores the file with its `ignore-paths`.
+    RuleIgnorePath,
+    /// The fingerprint of the secret is in the allowlist of the configuration file.
+    Fingerprint,
+    /// The file of the secret is in the allowlist of the configuration file.
+    Path,
+    /// The secret matches a pattern of the allowlist of the configuration file.
+    Pattern,
+}
+
+/// A secret that was found but not reported, as written in the suppression report.
+#[derive(Debug, Clone, Serialize)]
+pub struct SuppressedSecret {
+    pub rule_id: String,
+    pub file_path: String,
+    pub start: Position,
+    pub end: Position,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    pub reason: SuppressionReason,
+}
+
+impl SuppressedSecret {
+    pub fn new(secret: SecretResult, reason: SuppressionReason) -> Self {
+        Self {
+            rule_id: secret.rule_id,
+            file_path: secret.file_path,
+            start: secret.violation.start,
+            end: secret.violation.end,
+            fingerprint: secret.violation.fingerprint,
+            reason,
+        }
+    }
+}
+
+/// The known false positives of the secrets scanner.
+#[derive(Debug, Default)]
+pub struct SecretsAllowlist {
+    fingerprints: HashSet<String>,
+    paths: Vec<PathPattern>,
+    patterns: Vec<Regex>,
+}
+
+impl SecretsAllowlist {
+    /// Builds the allowlist from the configuration file. Returns an error if a pattern is not a
+    /// valid regular expression.
+    pub fn new(config: &SecretsAllowlistConfig) -> Result<Self> {
+        let patterns = config
+            .patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .with_context(|| format!("invalid secrets allowlist pattern: {pattern}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            fingerprints: config.fingerprints.iter().cloned().collect(),
+            paths: config.paths.clone(),
+            patterns,
+        })
+    }
+
+    /// Returns why a secret must not be reported, if it mustn't. `matched` is the text of the
+    /// secret, `content` is the content of its file (if it could be read), and `ignored_by_rule`
+    /// is true if the rule that found the secret ignores its file.
+    pub fn suppression_reason(
+        &self,
+        secret: &SecretResult,
+        matched: &str,
+        content: Option<&str>,
+        ignored_by_rule: bool,
+    ) -> Option<SuppressionReason> {
+        if content.is_some_and(|c| has_ignore_comment(c, secret.violation.start.line)) {
+            Some(SuppressionReason::InlineComment)
+        } else if ignored_by_rule {
+            Some(SuppressionReason::RuleIgnorePath)
+        } else if secret
+            .violation
+            .fingerprint
+            .as_ref()
+            .is_some_and(|f| self.fingerprints.contains(f))
+        {
+            Some(SuppressionReason::Fingerprint)
+        } else if self.paths.iter().any(|p| p.matches(&secret.file_path)) {
+            Some(SuppressionReason::Path)
+        } else if self.patterns.iter().any(|p| p.is_match(matched)) {
+            Some(SuppressionReason::Pattern)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns true if the line (1-based) or the line before it contains a `dd-secrets-ignore` comment.
+pub fn has_ignore_comment(content: &str, line: u32) -> bool {
+    if line == 0 {
+        return false;
+    }
+    let first = line.saturating_sub(2);
+    content
+        .lines()
+        .skip(first as usize)
+        .take((line - first) as usize)
+        .any(|l| l.contains(IGNORE_COMMENT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::ValidationStatus;
+
+    fn secret(file_path: &str, line: u32, fingerprint: Option<&str>) -> SecretResult {
+        let mut secret = SecretResult::new(
+            "rule",
+            file_path,
+            ValidationStatus::Unvalidated,
+            Position { line, col: 1 },
+            Position { line, col: 10 },
+        );
+        secret.violation.fingerprint = fingerprint.map(str::to_string);
+        secret
+    }
+
+    #[test]
+    fn ignore_comment() {
+        let content = "a = 1\nkey = 'abc' # dd-secrets-ignore\n// dd-secrets-ignore\nkey = 'def'\nkey = 'ghi'\n";
+        assert!(!has_ignore_comment(content, 1));
+        assert!(has_ignore_comment(content, 2));
+        assert!(has_ignore_comment(content, 4));
+        assert!(!has_ignore_comment(content, 5));
+        assert!(!has_ignore_comment(content, 0));
+        assert!(!has_ignore_comment(content, 10));
+    }
+
+    #[test]
+    fn suppression_reasons() {
+        let allowlist = SecretsAllowlist::new(&SecretsAllowlistConfig {
+            fingerprints: vec!["abc".to_string()],
+            paths: vec!["tests/**".to_string().into()],
+            patterns: vec!["EXAMPLE$".to_string()],
+        })
+        .unwrap();
+        let content = Some("key = 'AKIAEXAMPLE'\n");
+
+        let reason = |secret: &SecretResult, matched: &str, ignored_by_rule: bool| {
+            allowlist.suppression_reason(secret, matched, content, ignored_by_rule)
+        };
+        assert_eq!(
+            reason(&secret("src/a.py", 1, None), "AKIA1234", false),
+            None
+        );
+        assert_eq!(
+            reason(&secret("src/a.py", 1, None), "AKIA1234", true),
+            Some(SuppressionReason::RuleIgnorePath)
+        );
+        assert_eq!(
+            reason(&secret("src/a.py", 1, Some("abc")), "AKIA1234", false),
+            Some(SuppressionReason::Fingerprint)
+        );
+        assert_eq!(
+            reason(&secret("tests/a.py", 1, None), "AKIA1234", false),
+            Some(SuppressionReason::Path)
+        );
+        assert_eq!(
+            reason(&secret("src/a.py", 1, None), "AKIAEXAMPLE", false),
+            Some(SuppressionReason::Pattern)
+        );
+        assert_eq!(
+            allowlist.suppression_reason(
+                &secret("src/a.py", 1, None),
+                "AKIA1234",
+                Some("key = 'AKIA1234' # dd-secrets-ignore\n"),
+                false
+            ),
+            Some(SuppressionReason::InlineComment)
+        );
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let config = SecretsAllowlistConfig {
+            patterns: vec!["(".to_string()],
+            ..SecretsAllowlistConfig::default()
+        };
+        assert!(SecretsAllowlist::new(&config).is_err());
+    }
+}
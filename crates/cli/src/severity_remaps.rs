@@ -0,0 +1,111 @@
+use kernel::model::config_file::SeverityRemapConfig;
+use kernel::model::rule::RuleResult;
+
+/// Changes the severity of the violations of the results with the severity remaps of the
+/// configuration file: the first remap whose paths match the file of a result applies to all its
+/// violations.
+pub fn apply_severity_remaps(remaps: &[SeverityRemapConfig], results: &mut [RuleResult]) {
+    if remaps.is_empty() {
+        return;
+    }
+    for result in results {
+        let Some(remap) = remaps
+            .iter()
+            .find(|remap| remap.paths.allows_file(&result.filename))
+        else {
+            continue;
+        };
+        for violation in &mut result.violations {
+            violation.severity = remap.remap.apply(violation.severity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rule_result, violation};
+    use kernel::model::common::Position;
+    use kernel::model::config_file::{PathConfig, SeverityRemap};
+    use kernel::model::rule::{RuleCategory, RuleSeverity};
+
+    fn result(filename: &str, severities: &[RuleSeverity]) -> RuleResult {
+        let violations = severities
+            .iter()
+            .map(|severity| {
+                violation(
+                    Position { line: 1, col: 1 },
+                    Position { line: 1, col: 2 },
+                    *severity,
+                    RuleCategory::Security,
+                )
+            })
+            .collect();
+        rule_result("myrule", filename, violations)
+    }
+
+    fn severities(result: &RuleResult) -> Vec<RuleSeverity> {
+        result.violations.iter().map(|v| v.severity).collect()
+    }
+
+    #[test]
+    fn remap_severities() {
+        let remaps = vec![
+            SeverityRemapConfig {
+                paths: PathConfig {
+                    only: Some(vec!["experimental/**".to_string().into()]),
+                    ignore: vec![],
+                },
+                remap: SeverityRemap::Downgrade(1),
+            },
+            SeverityRemapConfig {
+                paths: PathConfig {
+                    only: Some(vec![
+                        "experimental/**".to_string().into(),
+                        "src".to_string().into(),
+                    ]),
+                    ignore: vec![],
+                },
+                remap: SeverityRemap::Upgrade(2),
+            },
+        ];
+        let all_severities = [
+            RuleSeverity::Error,
+            RuleSeverity::Warning,
+            RuleSeverity::Notice,
+            RuleSeverity::None,
+        ];
+        let mut results = vec![
+            result("experimental/a.py", &all_severities),
+            result("src/b.py", &all_severities),
+            result("tests/c.py", &all_severities),
+        ];
+        apply_severity_remaps(&remaps, &mut results);
+
+        // only the first remap that matches a file applies
+        assert_eq!(
+            severities(&results[0]),
+            vec![
+                RuleSeverity::Warning,
+                RuleSeverity::Notice,
+                RuleSeverity::Notice,
+                RuleSeverity::None
+            ]
+        );
+        assert_eq!(
+            severities(&results[1]),
+            vec![
+                RuleSeverity::Error,
+                RuleSeverity::Error,
+                RuleSeverity::Error,
+                RuleSeverity::None
+            ]
+        );
+        assert_eq!(severities(&results[2]), all_severities.to_vec());
+
+        assert_eq!(
+            SeverityRemap::Set(RuleSeverity::Notice).apply(RuleSeverity::None),
+            RuleSeverity::Notice
+        );
+    }
+}
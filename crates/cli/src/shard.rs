@@ -0,0 +1,346 @@
+//! The split of an analysis across parallel jobs (`--shard i/N`), and the merge of the reports of
+//! the jobs into one report (`merge-results`).
+
+use anyhow::{anyhow, Context, Result};
+use kernel::model::rule::{sort_rule_results, RuleResult};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// The tag of the SARIF run with the duration of the analysis.
+const SARIF_EXECUTION_TIME_TAG: &str = "DATADOG_EXECUTION_TIME_SECS:";
+
+/// The part of the files analyzed by one of `count` jobs, with `index` between 1 and `count`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    pub index: u64,
+    pub count: u64,
+}
+
+impl Shard {
+    /// Returns whether the file, with its path relative to the analyzed directory, is analyzed by
+    /// this shard. A file is always in the same shard of `count` shards, whatever the machine,
+    /// the order of the files and the other files of the repository.
+    pub fn contains(&self, relative_path: &str) -> bool {
+        // the separators are normalized, so that the shards are the same on Windows
+        let path = relative_path.replace('\\', "/");
+        let digest = Sha256::digest(path.as_bytes());
+        let hash = u64::from_be_bytes(digest[..8].try_into().expect("digest has 32 bytes"));
+        hash % self.count == self.index - 1
+    }
+}
+
+impl FromStr for Shard {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || anyhow!("invalid shard {s} (expected i/N, with i between 1 and N)");
+        let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+        let index = index.trim().parse::<u64>().map_err(|_| invalid())?;
+        let count = count.trim().parse::<u64>().map_err(|_| invalid())?;
+        if index == 0 || index > count {
+            return Err(invalid());
+        }
+        Ok(Self { index, count })
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// The results of the JSON reports of several shards.
+#[derive(Debug)]
+pub struct MergedResults {
+    /// The results, sorted with [`sort_rule_results`].
+    pub results: Vec<RuleResult>,
+    /// Whether the analysis of any shard was interrupted.
+    pub incomplete: bool,
+    /// The number of results of a file and rule reported by more than one report, which were
+    /// dropped: the shards overlap, or a report was merged twice.
+    pub duplicates: usize,
+}
+
+/// Merges the JSON reports of several shards, complete (a list of results) or not (an object with
+/// the `incomplete` flag and the results).
+pub fn merge_json_reports(reports: &[String]) -> Result<MergedResults> {
+    let mut results = vec![];
+    let mut incomplete = false;
+    for (index, report) in reports.iter().enumerate() {
+        let value: Value = serde_json::from_str(report)
+            .with_context(|| format!("report {} is not valid JSON", index + 1))?;
+        let report_results = match value {
+            Value::Object(mut object) => {
+                incomplete |= object.get("incomplete") == Some(&Value::Bool(true));
+                object.remove("results").unwrap_or(Value::Array(vec![]))
+            }
+            value => value,
+        };
+        results.extend(
+            serde_json::from_value::<Vec<RuleResult>>(report_results)
+                .with_context(|| format!("report {} is not a JSON report", index + 1))?,
+        );
+    }
+    // the sort is stable: the result of the first report is kept
+    sort_rule_results(&mut results);
+    let count = results.len();
+    results.dedup_by(|b, a| a.filename == b.filename && a.rule_name == b.rule_name);
+    Ok(MergedResults {
+        duplicates: count - results.len(),
+        results,
+        incomplete,
+    })
+}
+
+/// Merges the SARIF reports of several shards into the run of the first report: the rules of the
+/// runs are merged by id, and the results are sorted by file and rule. The run takes the longest
+/// execution time of the shards, as they run in parallel, and the tags of all the runs.
+///
+/// Returns the merged report and the number of its results.
+pub fn merge_sarif_reports(reports: &[String]) -> Result<(Value, usize)> {
+    let mut merged: Option<Value> = None;
+    let mut rule_indexes: HashMap<String, usize> = HashMap::new();
+    let mut results = vec![];
+    let mut tags: Vec<String> = vec![];
+    let mut execution_time_secs: Option<u64> = None;
+    for (index, report) in reports.iter().enumerate() {
+        let mut report: Value = serde_json::from_str(report)
+            .with_context(|| format!("report {} is not valid JSON", index + 1))?;
+        let run = report
+            .get_mut("runs")
+            .and_then(|runs| runs.get_mut(0))
+            .ok_or_else(|| anyhow!("report {} is not a SARIF report", index + 1))?;
+
+        // the rules of the run, and the index of each of them in the merged rules
+        let rules = run["tool"]["driver"]["rules"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let mut new_rules = vec![];
+        let mut index_map = vec![];
+        for rule in rules {
+            let id = rule["id"].as_str().unwrap_or_default().to_string();
+            let next_index = rule_indexes.len();
+            let merged_index = *rule_indexes.entry(id).or_insert_with(|| {
+                new_rules.push(rule.clone());
+                next_index
+            });
+            index_map.push(merged_index);
+        }
+        for mut result in run["results"].as_array().cloned().unwrap_or_default() {
+            if let Some(rule_index) = result["ruleIndex"].as_u64() {
+                let merged_index = index_map
+                    .get(rule_index as usize)
+                    .ok_or_else(|| anyhow!("invalid rule index in report {}", index + 1))?;
+                result["ruleIndex"] = Value::from(*merged_index);
+            }
+            results.push(result);
+        }
+
+        for tag in run["tool"]["driver"]["properties"]["tags"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if let Some(secs) = tag.strip_prefix(SARIF_EXECUTION_TIME_TAG) {
+                let secs = secs.parse::<u64>().unwrap_or_default();
+                execution_time_secs = Some(execution_time_secs.unwrap_or_default().max(secs));
+            } else if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+
+        match &mut merged {
+            None => {
+                run["tool"]["driver"]["rules"] = Value::Array(new_rules);
+                merged = Some(report);
+            }
+            Some(merged) => {
+                if let Some(merged_rules) =
+                    merged["runs"][0]["tool"]["driver"]["rules"].as_array_mut()
+                {
+                    merged_rules.extend(new_rules);
+                }
+            }
+        }
+    }
+    let mut merged = merged.ok_or_else(|| anyhow!("no report to merge"))?;
+
+    // the results of a file and rule are kept in the order of their report
+    let key = |result: &Value| {
+        (
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            result["ruleId"].as_str().unwrap_or_default().to_string(),
+        )
+    };
+    results.sort_by_cached_key(key);
+    let result_count = results.len();
+    let run = &mut merged["runs"][0];
+    run["results"] = Value::Array(results);
+    if let Some(secs) = execution_time_secs {
+        tags.push(format!("{SARIF_EXECUTION_TIME_TAG}{secs}"));
+    }
+    if run["tool"]["driver"]["properties"].is_object() {
+        run["tool"]["driver"]["properties"]["tags"] =
+            Value::Array(tags.into_iter().map(Value::from).collect());
+    }
+    Ok((merged, result_count))
+}
+
+/// Returns the number of distinct files of the results of a merged SARIF report.
+pub fn count_sarif_files(report: &Value) -> usize {
+    report["runs"][0]["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|result| {
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"].as_str()
+        })
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_shards() {
+        assert_eq!(
+            "2/3".parse::<Shard>().unwrap(),
+            Shard { index: 2, count: 3 }
+        );
+        assert!("0/3".parse::<Shard>().is_err());
+        assert!("4/3".parse::<Shard>().is_err());
+        assert!("3".parse::<Shard>().is_err());
+        assert!("a/b".parse::<Shard>().is_err());
+    }
+
+    #[test]
+    fn partition_files() {
+        let files = (0..1000)
+            .map(|i| format!("src/module{}/file{}.py", i % 7, i))
+            .collect::<Vec<_>>();
+        let shards = (1..=4).map(|index| Shard { index, count: 4 });
+        let mut sizes = vec![];
+        for shard in shards {
+            sizes.push(files.iter().filter(|f| shard.contains(f)).count());
+        }
+        // every file is in exactly one shard, and the shards are balanced
+        assert_eq!(sizes.iter().sum::<usize>(), files.len());
+        assert!(sizes.iter().all(|size| *size > 200 && *size < 300));
+        // the shard of a file doesn't depend on the separators
+        let shard = Shard { index: 1, count: 4 };
+        assert_eq!(shard.contains("a\\b.py"), shard.contains("a/b.py"));
+    }
+
+    fn json_result(filename: &str, rule_name: &str) -> Value {
+        json!({
+            "rule_name": rule_name,
+            "filename": filename,
+            "violations": [],
+            "errors": [],
+            "execution_error": null,
+            "output": null,
+            "execution_time_ms": 0,
+            "parsing_time_ms": 0,
+            "query_node_time_ms": 0,
+        })
+    }
+
+    #[test]
+    fn merge_json() {
+        let reports = [
+            json!([json_result("b.py", "rs/a"), json_result("a.py", "rs/a")]).to_string(),
+            json!({"incomplete": true, "results": [json_result("c.py", "rs/a"), json_result("a.py", "rs/a")]})
+                .to_string(),
+        ];
+        let merged = merge_json_reports(&reports).unwrap();
+        assert!(merged.incomplete);
+        assert_eq!(merged.duplicates, 1);
+        assert_eq!(
+            merged
+                .results
+                .iter()
+                .map(|r| r.filename.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a.py", "b.py", "c.py"]
+        );
+        assert!(merge_json_reports(&["{".to_string()]).is_err());
+    }
+
+    fn sarif_report(rules: &[&str], results: &[(&str, usize)], secs: u64) -> String {
+        json!({
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {"driver": {
+                    "name": "datadog-static-analyzer",
+                    "properties": {"tags": [format!("DATADOG_EXECUTION_TIME_SECS:{secs}"), "DATADOG_DIFF_AWARE_ENABLED:false"]},
+                    "rules": rules.iter().map(|id| json!({"id": id})).collect::<Vec<_>>(),
+                }},
+                "results": results.iter().map(|(uri, rule_index)| json!({
+                    "ruleId": rules[*rule_index],
+                    "ruleIndex": rule_index,
+                    "locations": [{"physicalLocation": {"artifactLocation": {"uri": uri}}}],
+                })).collect::<Vec<_>>(),
+            }],
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn merge_sarif() {
+        let reports = [
+            sarif_report(&["rs/a", "rs/b"], &[("b.py", 1), ("a.py", 0)], 10),
+            sarif_report(&["rs/b", "rs/c"], &[("c.py", 1), ("a.py", 0)], 20),
+        ];
+        let (merged, count) = merge_sarif_reports(&reports).unwrap();
+        assert_eq!(count, 4);
+        assert_eq!(count_sarif_files(&merged), 3);
+        let run = &merged["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"],
+            json!([{"id": "rs/a"}, {"id": "rs/b"}, {"id": "rs/c"}])
+        );
+        // the rule indexes refer to the merged rules
+        let results = run["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                (
+                    r["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]
+                        .as_str()
+                        .unwrap(),
+                    r["ruleId"].as_str().unwrap(),
+                    r["ruleIndex"].as_u64().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            vec![
+                ("a.py", "rs/a", 0),
+                ("a.py", "rs/b", 1),
+                ("b.py", "rs/b", 1),
+                ("c.py", "rs/c", 2)
+            ]
+        );
+        assert_eq!(
+            run["tool"]["driver"]["properties"]["tags"],
+            json!([
+                "DATADOG_DIFF_AWARE_ENABLED:false",
+                "DATADOG_EXECUTION_TIME_SECS:20"
+            ])
+        );
+    }
+}
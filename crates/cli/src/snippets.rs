@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use kernel::model::common::SharedStr;
+use kernel::model::rule::RuleResult;
+use kernel::model::violation::{Snippet, Violation};
+
+/// Embeds the source code of each violation in the results, with `context_lines` lines before and
+/// after it. File-level violations don't have a snippet, as they refer to the whole file.
+///
+/// Secrets are not embedded here, so that the reports never contain them: their context is only
+/// embedded redacted (see [`SecretResult::set_redacted_context`](crate::secrets::SecretResult::set_redacted_context)).
+pub fn add_snippets(rule_results: &mut [RuleResult], directory: &Path, context_lines: u32) {
+    let mut files: HashMap<SharedStr, Option<String>> = HashMap::new();
+    for result in rule_results {
+        let content = files
+            .entry(result.filename.clone())
+            .or_insert_with(|| fs::read_to_string(directory.join(&result.filename)).ok());
+        let Some(content) = content else {
+            continue;
+        };
+        for violation in &mut result.violations {
+            violation.snippet = get_snippet(content, violation, context_lines);
+        }
+    }
+}
+
+/// Returns the lines of the violation in the content, with `context_lines` lines around them.
+pub(crate) fn get_snippet(
+    content: &str,
+    violation: &Violation,
+    context_lines: u32,
+) -> Option<Snippet> {
+    if violation.file_level || violation.start.line == 0 {
+        return None;
+    }
+    let lines = content.lines().collect::<Vec<_>>();
+    let line_count = u32::try_from(lines.len()).ok()?;
+    if violation.start.line > line_count {
+        return None;
+    }
+    let start_line = violation.start.line.saturating_sub(context_lines).max(1);
+    let end_line = violation
+        .end
+        .line
+        .max(violation.start.line)
+        .saturating_add(context_lines)
+        .min(line_count);
+    let text = lines[(start_line - 1) as usize..end_line as usize]
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect();
+    Some(Snippet {
+        start_line,
+        end_line,
+        text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::model::common::Position;
+    use kernel::model::rule::{RuleCategory, RuleSeverity};
+    use kernel::model::violation::ViolationBuilder;
+
+    fn violation(start_line: u32, end_line: u32) -> Violation {
+        ViolationBuilder::default()
+            .start(Position {
+                line: start_line,
+                col: 1,
+            })
+            .end(Position {
+                line: end_line,
+                col: 2,
+            })
+            .message("message".to_string())
+            .severity(RuleSeverity::Error)
+            .category(RuleCategory::BestPractices)
+            .fixes(vec![])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn snippet_with_context() {
+        let content = "line1\nline2\nline3\nline4\nline5\n";
+        assert_eq!(
+            get_snippet(content, &violation(3, 3), 0),
+            Some(Snippet {
+                start_line: 3,
+                end_line: 3,
+                text: "line3\n".to_string(),
+            })
+        );
+        assert_eq!(
+            get_snippet(content, &violation(2, 3), 1),
+            Some(Snippet {
+                start_line: 1,
+                end_line: 4,
+                text: "line1\nline2\nline3\nline4\n".to_string(),
+            })
+        );
+        // the context is clamped to the lines of the file
+        assert_eq!(
+            get_snippet(content, &violation(5, 5), 3).map(|s| (s.start_line, s.end_line)),
+            Some((2, 5))
+        );
+        assert_eq!(get_snippet(content, &violation(8, 8), 1), None);
+    }
+}
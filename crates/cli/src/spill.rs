@@ -0,0 +1,298 @@
+//! The aggregation of the results of an analysis in a bounded amount of memory: once the buffered
+//! results hold too many violations, they are sorted and written (spilled) to a temporary file,
+//! and the files are merged back, in order, when the reports are written.
+
+use anyhow::{Context, Result};
+use kernel::model::rule::{sort_rule_results, RuleResult};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+pub struct ResultSpill {
+    /// The number of buffered violations over which the results are spilled, if any.
+    max_violations: Option<usize>,
+    buffer: Vec<RuleResult>,
+    buffered_violations: usize,
+    spilled: Option<SpilledResults>,
+}
+
+/// The results of an analysis, once all of them were aggregated.
+pub enum AggregatedResults {
+    /// The results were never spilled.
+    InMemory(Vec<RuleResult>),
+    Spilled(SpilledResults),
+}
+
+impl ResultSpill {
+    pub fn new(max_violations: Option<usize>) -> Self {
+        Self {
+            max_violations,
+            buffer: vec![],
+            buffered_violations: 0,
+            spilled: None,
+        }
+    }
+
+    /// Adds results, and spills the buffered results if they hold too many violations. `prepare`
+    /// is applied to the results before they are sorted and spilled (e.g. to merge the duplicate
+    /// violations), so it must only depend on the results of the same file.
+    pub fn extend(
+        &mut self,
+        results: impl IntoIterator<Item = RuleResult>,
+        prepare: impl FnMut(&mut [RuleResult]),
+    ) -> Result<()> {
+        for result in results {
+            self.buffered_violations += result.violations.len();
+            self.buffer.push(result);
+        }
+        if self
+            .max_violations
+            .is_some_and(|max| self.buffered_violations > max)
+        {
+            self.spill(prepare)?;
+        }
+        Ok(())
+    }
+
+    /// Returns all the results, prepared and sorted with [`sort_rule_results`].
+    pub fn finish(
+        mut self,
+        mut prepare: impl FnMut(&mut [RuleResult]),
+    ) -> Result<AggregatedResults> {
+        if self.spilled.is_none() {
+            prepare(&mut self.buffer);
+            sort_rule_results(&mut self.buffer);
+            return Ok(AggregatedResults::InMemory(self.buffer));
+        }
+        if !self.buffer.is_empty() {
+            self.spill(prepare)?;
+        }
+        Ok(AggregatedResults::Spilled(
+            self.spilled.expect("results were spilled"),
+        ))
+    }
+
+    fn spill(&mut self, mut prepare: impl FnMut(&mut [RuleResult])) -> Result<()> {
+        let mut results = std::mem::take(&mut self.buffer);
+        self.buffered_violations = 0;
+        prepare(&mut results);
+        sort_rule_results(&mut results);
+
+        if self.spilled.is_none() {
+            self.spilled = Some(SpilledResults {
+                directory: tempfile::tempdir()
+                    .context("cannot create a directory to spill the results")?,
+                files: vec![],
+                violations: 0,
+            });
+        }
+        let spilled = self.spilled.as_mut().expect("spill directory was created");
+        let path = spilled
+            .directory
+            .path()
+            .join(format!("results-{}.jsonl", spilled.files.len()));
+        let file = File::create(&path)
+            .with_context(|| format!("cannot spill the results to {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        for result in &results {
+            spilled.violations += result.violations.len();
+            serde_json::to_writer(&mut writer, result)?;
+            writer.write_all(b"\n")?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("cannot spill the results to {}", path.display()))?;
+        spilled.files.push(path);
+        Ok(())
+    }
+}
+
+/// The results of an analysis that were spilled to temporary files, each sorted. The files are
+/// removed when the results are dropped.
+pub struct SpilledResults {
+    directory: TempDir,
+    files: Vec<PathBuf>,
+    violations: usize,
+}
+
+impl SpilledResults {
+    /// Returns the number of files the results were spilled to.
+    pub fn files(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns the number of violations of the results.
+    pub fn violations(&self) -> usize {
+        self.violations
+    }
+
+    /// Returns the results in the order of [`sort_rule_results`], reading one result of each file
+    /// at a time.
+    pub fn iter(&self) -> Result<MergedResults> {
+        let mut merged = MergedResults {
+            files: vec![],
+            heap: BinaryHeap::new(),
+        };
+        for path in &self.files {
+            let file = File::open(path)
+                .with_context(|| format!("cannot read the spilled results {}", path.display()))?;
+            merged.files.push(BufReader::new(file).lines());
+            merged.read_next(merged.files.len() - 1)?;
+        }
+        Ok(merged)
+    }
+
+    /// Reads all the results back in memory.
+    pub fn load(&self) -> Result<Vec<RuleResult>> {
+        self.iter()?.collect()
+    }
+}
+
+/// The merge of the spilled files, as an iterator of results.
+pub struct MergedResults {
+    files: Vec<Lines<BufReader<File>>>,
+    /// The next result of each file that was not read entirely.
+    heap: BinaryHeap<Reverse<NextResult>>,
+}
+
+impl MergedResults {
+    fn read_next(&mut self, file: usize) -> Result<()> {
+        if let Some(line) = self.files[file].next() {
+            let line = line.context("cannot read the spilled results")?;
+            let result = serde_json::from_str(&line).context("cannot read the spilled results")?;
+            self.heap.push(Reverse(NextResult { result, file }));
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for MergedResults {
+    type Item = Result<RuleResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse(next) = self.heap.pop()?;
+        if let Err(err) = self.read_next(next.file) {
+            return Some(Err(err));
+        }
+        Some(Ok(next.result))
+    }
+}
+
+struct NextResult {
+    result: RuleResult,
+    /// The index of the file the result was read from: the results of the same file and rule
+    /// are kept in the order they were spilled.
+    file: usize,
+}
+
+impl NextResult {
+    fn key(&self) -> (&str, &str, usize) {
+        (&self.result.filename, &self.result.rule_name, self.file)
+    }
+}
+
+impl PartialEq for NextResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for NextResult {}
+
+impl PartialOrd for NextResult {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NextResult {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rule_result, violation};
+    use kernel::model::common::Position;
+    use kernel::model::rule::{RuleCategory, RuleSeverity};
+
+    fn result(filename: &str, rule_name: &str, violations: u32) -> RuleResult {
+        let violations = (1..=violations)
+            .map(|line| {
+                violation(
+                    Position { line, col: 1 },
+                    Position { line, col: 10 },
+                    RuleSeverity::Warning,
+                    RuleCategory::Security,
+                )
+            })
+            .collect();
+        rule_result(rule_name, filename, violations)
+    }
+
+    fn names(results: &[RuleResult]) -> Vec<(&str, &str)> {
+        results
+            .iter()
+            .map(|r| (r.filename.as_str(), r.rule_name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn spill_and_merge() {
+        let mut spill = ResultSpill::new(Some(2));
+        spill
+            .extend(
+                [result("b.py", "rs/b", 1), result("a.py", "rs/b", 1)],
+                |_| {},
+            )
+            .unwrap();
+        // over the threshold: the results are spilled
+        spill
+            .extend(
+                [result("c.py", "rs/a", 2), result("a.py", "rs/a", 0)],
+                |_| {},
+            )
+            .unwrap();
+        spill.extend([result("b.py", "rs/a", 1)], |_| {}).unwrap();
+
+        let AggregatedResults::Spilled(spilled) = spill.finish(|_| {}).unwrap() else {
+            panic!("the results were not spilled");
+        };
+        assert_eq!(spilled.files(), 2);
+        assert_eq!(spilled.violations(), 5);
+        assert_eq!(
+            names(&spilled.load().unwrap()),
+            vec![
+                ("a.py", "rs/a"),
+                ("a.py", "rs/b"),
+                ("b.py", "rs/a"),
+                ("b.py", "rs/b"),
+                ("c.py", "rs/a")
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_in_memory() {
+        let mut spill = ResultSpill::new(None);
+        spill
+            .extend(
+                [result("b.py", "rs/a", 10), result("a.py", "rs/a", 10)],
+                |_| {},
+            )
+            .unwrap();
+        let AggregatedResults::InMemory(results) = spill
+            .finish(|results| results.iter_mut().for_each(|r| r.violations.clear()))
+            .unwrap()
+        else {
+            panic!("the results were spilled");
+        };
+        assert_eq!(names(&results), vec![("a.py", "rs/a"), ("b.py", "rs/a")]);
+        assert!(results.iter().all(|r| r.violations.is_empty()));
+    }
+}
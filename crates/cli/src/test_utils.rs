@@ -0,0 +1,44 @@
+//! Fixtures shared by the unit tests of the crate.
+
+use kernel::model::common::Position;
+use kernel::model::rule::{RuleCategory, RuleResult, RuleSeverity};
+use kernel::model::violation::{Violation, ViolationBuilder};
+
+/// Returns the result of a rule on a file with the given violations, without error or timing.
+pub(crate) fn rule_result(
+    rule_name: &str,
+    filename: &str,
+    violations: Vec<Violation>,
+) -> RuleResult {
+    RuleResult {
+        rule_name: rule_name.into(),
+        filename: filename.into(),
+        violations,
+        errors: vec![],
+        execution_error: None,
+        output: None,
+        execution_time_ms: 0,
+        parsing_time_ms: 0,
+        query_node_time_ms: 0,
+        degraded: false,
+        profile: None,
+    }
+}
+
+/// Returns a violation without fix, whose message is `message`.
+pub(crate) fn violation(
+    start: Position,
+    end: Position,
+    severity: RuleSeverity,
+    category: RuleCategory,
+) -> Violation {
+    ViolationBuilder::default()
+        .start(start)
+        .end(end)
+        .message("message")
+        .severity(severity)
+        .category(category)
+        .fixes(vec![])
+        .build()
+        .unwrap()
+}
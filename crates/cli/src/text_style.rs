@@ -0,0 +1,216 @@
+//! The style of the text of a file (its indentation and its line endings), detected from its
+//! content, so that the code inserted by the fixes looks like the code around it.
+
+use kernel::editorconfig::EditorConfigProperties;
+use std::collections::HashMap;
+
+/// The width of the indentation when it cannot be detected.
+const DEFAULT_INDENT_WIDTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tabs,
+    Spaces,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextStyle {
+    pub indent_style: IndentStyle,
+    /// The number of columns of a level of indentation (and of a tab).
+    pub indent_width: usize,
+    pub line_ending: LineEnding,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            indent_style: IndentStyle::Spaces,
+            indent_width: DEFAULT_INDENT_WIDTH,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+impl TextStyle {
+    /// Detects the style of a file: the indentation used by most of its indented lines (with the
+    /// most frequent difference between the indentation of two consecutive lines as the width),
+    /// and the line ending of most of its lines.
+    pub fn detect(content: &str) -> Self {
+        let mut style = TextStyle::default();
+        let (mut crlf, mut lf) = (0, 0);
+        let (mut tab_lines, mut space_lines) = (0, 0);
+        let mut widths = HashMap::<usize, usize>::new();
+        let mut previous_indent = 0;
+        for line in content.split_inclusive('\n') {
+            if line.ends_with("\r\n") {
+                crlf += 1;
+            } else if line.ends_with('\n') {
+                lf += 1;
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with('\t') {
+                tab_lines += 1;
+                continue;
+            }
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            if indent > 0 {
+                space_lines += 1;
+            }
+            let delta = indent.abs_diff(previous_indent);
+            if delta > 1 {
+                *widths.entry(delta).or_default() += 1;
+            }
+            previous_indent = indent;
+        }
+        if crlf > lf {
+            style.line_ending = LineEnding::CrLf;
+        }
+        if tab_lines > space_lines {
+            style.indent_style = IndentStyle::Tabs;
+        }
+        if let Some((width, _)) =
+            widths
+                .into_iter()
+                .max_by(|(width_a, count_a), (width_b, count_b)| {
+                    count_a.cmp(count_b).then(width_b.cmp(width_a))
+                })
+        {
+            style.indent_width = width;
+        }
+        style
+    }
+
+    /// Overrides the detected style with the settings of the `.editorconfig` files of the file,
+    /// which are the conventions declared by the project.
+    pub fn with_editorconfig(mut self, properties: &EditorConfigProperties) -> Self {
+        match properties.indent_style.as_deref() {
+            Some("tab") => self.indent_style = IndentStyle::Tabs,
+            Some("space") => self.indent_style = IndentStyle::Spaces,
+            _ => {}
+        }
+        let width = match self.indent_style {
+            IndentStyle::Tabs => properties.tab_width,
+            IndentStyle::Spaces => properties.indent_size,
+        };
+        if let Some(width) = width.filter(|w| *w > 0) {
+            self.indent_width = width as usize;
+        }
+        match properties.end_of_line.as_deref() {
+            Some("lf") => self.line_ending = LineEnding::Lf,
+            Some("crlf") => self.line_ending = LineEnding::CrLf,
+            _ => {}
+        }
+        self
+    }
+
+    /// Converts text inserted in a file to its style: the line endings are replaced by the line
+    /// ending of the file, and the indentation of the lines (of the first line only when
+    /// `at_line_start` is true, as it's inserted after the code of a line otherwise) uses tabs or
+    /// spaces as the file does. The alignment within the indentation is kept.
+    pub fn adapt(&self, text: &str, at_line_start: bool) -> String {
+        let mut adapted = String::with_capacity(text.len());
+        for (idx, line) in text.split_inclusive('\n').enumerate() {
+            let (line, ending) = match line.strip_suffix('\n') {
+                Some(line) => (line.strip_suffix('\r').unwrap_or(line), true),
+                None => (line, false),
+            };
+            if idx > 0 || at_line_start {
+                let code = line.trim_start_matches([' ', '\t']);
+                adapted.push_str(&self.indentation(&line[..line.len() - code.len()]));
+                adapted.push_str(code);
+            } else {
+                adapted.push_str(line);
+            }
+            if ending {
+                adapted.push_str(self.line_ending.as_str());
+            }
+        }
+        adapted
+    }
+
+    /// Converts the leading whitespace of a line to the indentation style of the file.
+    fn indentation(&self, whitespace: &str) -> String {
+        let columns = whitespace
+            .chars()
+            .map(|c| if c == '\t' { self.indent_width } else { 1 })
+            .sum::<usize>();
+        match self.indent_style {
+            IndentStyle::Spaces => " ".repeat(columns),
+            IndentStyle::Tabs => format!(
+                "{}{}",
+                "\t".repeat(columns / self.indent_width),
+                " ".repeat(columns % self.indent_width)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_style() {
+        assert_eq!(TextStyle::detect(""), TextStyle::default());
+        assert_eq!(
+            TextStyle::detect("def foo():\r\n  if x:\r\n    bar()\r\n  baz()\r\n"),
+            TextStyle {
+                indent_style: IndentStyle::Spaces,
+                indent_width: 2,
+                line_ending: LineEnding::CrLf,
+            }
+        );
+        assert_eq!(
+            TextStyle::detect("func foo() {\n\tif x {\n\t\tbar()\n\t}\n}\n").indent_style,
+            IndentStyle::Tabs
+        );
+    }
+
+    #[test]
+    fn adapt_text() {
+        let style = TextStyle {
+            indent_style: IndentStyle::Tabs,
+            indent_width: 4,
+            line_ending: LineEnding::CrLf,
+        };
+        assert_eq!(
+            style.adapt("if x:\n    bar()\n      # aligned\n", false),
+            "if x:\r\n\tbar()\r\n\t  # aligned\r\n"
+        );
+        // the first line is only indented at the start of a line
+        assert_eq!(style.adapt("    x = 1", false), "    x = 1");
+        assert_eq!(style.adapt("    x = 1", true), "\tx = 1");
+
+        let style = TextStyle::detect("a:\n  b\n");
+        assert_eq!(style.adapt("if x:\r\n\tbar()", true), "if x:\n  bar()");
+    }
+
+    #[test]
+    fn editorconfig_overrides() {
+        let style = TextStyle::detect("a:\n  b\n").with_editorconfig(&EditorConfigProperties {
+            indent_style: Some("tab".to_string()),
+            tab_width: Some(2),
+            end_of_line: Some("crlf".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(style.adapt("if x:\n  bar()", true), "if x:\r\n\tbar()");
+    }
+}
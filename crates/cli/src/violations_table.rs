@@ -1,29 +1,42 @@
-use kernel::model::rule::RuleResult;
+use kernel::model::rule::{RuleResult, RuleSeverity};
+use kernel::model::violation::Violation;
 use prettytable::{format, row, Table};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
-pub fn print_violations_table(rule_results: &[RuleResult]) {
-    let mut table = Table::new();
-    let format = format::FormatBuilder::new()
+/// The owner of the violations of the files without owners.
+const NO_OWNER: &str = "(no owner)";
+
+pub(crate) fn table_format() -> format::TableFormat {
+    format::FormatBuilder::new()
         .separator(
             format::LinePosition::Title,
             format::LineSeparator::new('-', '-', '-', '-'),
         )
         .padding(1, 1)
-        .build();
-    table.set_format(format);
+        .build()
+}
+
+pub fn print_violations_table(rule_results: &[RuleResult]) {
+    let mut table = Table::new();
+    table.set_format(table_format());
     table.set_titles(row![
         "rule", "filename", "location", "category", "severity", "message"
     ]);
     for rule_result in rule_results {
         if !rule_result.violations.is_empty() {
             for violation in &rule_result.violations {
-                let position = format!(
-                    "{}:{}-{}:{}",
-                    violation.start.line,
-                    violation.start.col,
-                    violation.end.line,
-                    violation.end.col
-                );
+                let position = if violation.file_level {
+                    "file".to_string()
+                } else {
+                    format!(
+                        "{}:{}-{}:{}",
+                        violation.start.line,
+                        violation.start.col,
+                        violation.end.line,
+                        violation.end.col
+                    )
+                };
                 table.add_row(row![
                     rule_result.rule_name,
                     rule_result.filename,
@@ -37,3 +50,179 @@ pub fn print_violations_table(rule_results: &[RuleResult]) {
     }
     table.printstd();
 }
+
+/// Prints the number of violations of each owner, by severity. A violation is counted once for
+/// each owner of its file.
+pub fn print_owners_table<'a>(violations: impl IntoIterator<Item = &'a Violation>) {
+    let mut counts: BTreeMap<&str, [usize; 4]> = BTreeMap::new();
+    for violation in violations {
+        let severity = severity_index(violation.severity);
+        if violation.owners.is_empty() {
+            counts.entry(NO_OWNER).or_default()[severity] += 1;
+        }
+        for owner in &violation.owners {
+            counts.entry(owner).or_default()[severity] += 1;
+        }
+    }
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by_key(|(_, c)| std::cmp::Reverse(c.iter().sum::<usize>()));
+
+    let mut table = Table::new();
+    table.set_format(table_format());
+    table.set_titles(row![
+        "owner",
+        "violations",
+        "error",
+        "warning",
+        "notice",
+        "none"
+    ]);
+    for (owner, [error, warning, notice, none]) in counts {
+        table.add_row(row![
+            owner,
+            error + warning + notice + none,
+            error,
+            warning,
+            notice,
+            none
+        ]);
+    }
+    table.printstd();
+}
+
+/// The number of violations of an analysis, by severity, by rule and by directory.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ViolationsSummary<'a> {
+    pub total: usize,
+    /// The number of errors, warnings, notices and violations without severity.
+    pub by_severity: [usize; 4],
+    /// The rules with violations, from the noisiest to the quietest.
+    pub by_rule: Vec<(&'a str, usize)>,
+    /// The directories of the files with violations, from the noisiest to the quietest.
+    pub by_directory: Vec<(String, usize)>,
+}
+
+/// Counts the violations, given with the name of their rule and their file.
+pub fn summarize_violations<'a>(
+    violations: impl IntoIterator<Item = (&'a str, &'a str, &'a Violation)>,
+) -> ViolationsSummary<'a> {
+    let mut summary = ViolationsSummary::default();
+    let mut by_rule: HashMap<&str, usize> = HashMap::new();
+    let mut by_directory: HashMap<String, usize> = HashMap::new();
+    for (rule_name, filename, violation) in violations {
+        summary.total += 1;
+        summary.by_severity[severity_index(violation.severity)] += 1;
+        *by_rule.entry(rule_name).or_default() += 1;
+        let directory = match Path::new(filename).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_string_lossy().to_string(),
+            _ => ".".to_string(),
+        };
+        *by_directory.entry(directory).or_default() += 1;
+    }
+    summary.by_rule = by_rule.into_iter().collect();
+    summary
+        .by_rule
+        .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    summary.by_directory = by_directory.into_iter().collect();
+    summary
+        .by_directory
+        .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    summary
+}
+
+/// Prints the number of violations by severity, and the `top` noisiest rules and directories.
+pub fn print_summary(summary: &ViolationsSummary, top: usize) {
+    let [error, warning, notice, none] = summary.by_severity;
+    let mut table = Table::new();
+    table.set_format(table_format());
+    table.set_titles(row!["violations", "error", "warning", "notice", "none"]);
+    table.add_row(row![summary.total, error, warning, notice, none]);
+    table.printstd();
+
+    for (title, counts) in [
+        (
+            "rule",
+            summary
+                .by_rule
+                .iter()
+                .map(|(rule, count)| (rule.to_string(), *count))
+                .collect::<Vec<_>>(),
+        ),
+        ("directory", summary.by_directory.clone()),
+    ] {
+        if counts.is_empty() {
+            continue;
+        }
+        println!();
+        let mut table = Table::new();
+        table.set_format(table_format());
+        table.set_titles(row![title, "violations"]);
+        for (name, count) in counts.iter().take(top) {
+            table.add_row(row![name, count]);
+        }
+        table.printstd();
+        if counts.len() > top {
+            println!("... and {} more", counts.len() - top);
+        }
+    }
+}
+
+/// The index of a severity in the counts of the tables, from the most to the least severe.
+fn severity_index(severity: RuleSeverity) -> usize {
+    match severity {
+        RuleSeverity::Error => 0,
+        RuleSeverity::Warning => 1,
+        RuleSeverity::Notice => 2,
+        RuleSeverity::None => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::model::common::Position;
+    use kernel::model::rule::RuleCategory;
+
+    fn violation(severity: RuleSeverity) -> Violation {
+        Violation {
+            start: Position { line: 1, col: 1 },
+            end: Position { line: 1, col: 2 },
+            message: "message".into(),
+            severity,
+            category: RuleCategory::BestPractices,
+            fixes: vec![],
+            merged_rules: vec![],
+            tags: vec![],
+            cwe: vec![],
+            documentation_url: None,
+            related_locations: vec![],
+            file_level: false,
+            fingerprint: None,
+            owners: vec![],
+            snippet: None,
+            offsets: None,
+            last_commit: None,
+        }
+    }
+
+    #[test]
+    fn summarize() {
+        let error = violation(RuleSeverity::Error);
+        let warning = violation(RuleSeverity::Warning);
+        let summary = summarize_violations([
+            ("a/noisy", "src/app/main.py", &warning),
+            ("a/noisy", "src/app/util.py", &warning),
+            ("a/noisy", "setup.py", &warning),
+            ("a/quiet", "src/app/main.py", &error),
+        ]);
+        assert_eq!(
+            summary,
+            ViolationsSummary {
+                total: 4,
+                by_severity: [1, 3, 0, 0],
+                by_rule: vec![("a/noisy", 3), ("a/quiet", 1)],
+                by_directory: vec![("src/app".to_string(), 3), (".".to_string(), 1)],
+            }
+        );
+    }
+}
@@ -0,0 +1,4 @@
+//! The data model shared by the crates of the analyzer: the positions in the source code, and the
+//! violations (with their fixes) reported by the static analysis and the secrets scanner.
+
+pub mod model;
@@ -0,0 +1,4 @@
+pub mod position;
+pub mod rule;
+pub mod shared_str;
+pub mod violation;
@@ -0,0 +1,28 @@
+use derive_builder::Builder;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A 1-based line and column in the source code.
+#[derive(Deserialize, Debug, Serialize, Clone, Builder, JsonSchema)]
+pub struct Position {
+    pub line: u32,
+    pub col: u32,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "position (line: {}, col: {})", self.line, self.col)
+    }
+}
+
+/// The exact location of a range of the source code, in addition to its 1-based line and column
+/// (in bytes): the byte offsets within the file, and the 0-based columns in UTF-16 code units
+/// expected by editors (e.g. the Language Server Protocol).
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub struct Offsets {
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub start_utf16_col: u32,
+    pub end_utf16_col: u32,
+}
@@ -0,0 +1,78 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// In the RuleCategory, we keep unknown. Old rules keep putting
+/// whatever they want as category. As a matter of fact, old rules that
+/// use old values (e.g. DEPLOYMENT) will fail deserialization. We then match
+/// them on the `Unknown` value.
+///
+/// The `Unknown` value is never exposed tho because we always rewrite the
+/// category of all violations by the categories of the rules they come from.
+#[derive(Copy, Clone, Deserialize, Debug, Serialize, Eq, PartialEq, JsonSchema)]
+pub enum RuleCategory {
+    #[serde(rename = "BEST_PRACTICES")]
+    BestPractices,
+    #[serde(rename = "CODE_STYLE")]
+    CodeStyle,
+    #[serde(rename = "ERROR_PRONE")]
+    ErrorProne,
+    #[serde(rename = "PERFORMANCE")]
+    Performance,
+    #[serde(rename = "SECURITY")]
+    Security,
+    #[serde(other)]
+    #[serde(skip_serializing)]
+    #[schemars(skip)]
+    Unknown, // kept only for backward compatibility
+}
+
+impl fmt::Display for RuleCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BestPractices => write!(f, "best_practices"),
+            Self::CodeStyle => write!(f, "code_style"),
+            Self::ErrorProne => write!(f, "error_prone"),
+            Self::Performance => write!(f, "performance"),
+            Self::Security => write!(f, "security"),
+            Self::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Deserialize, Debug, Serialize, Eq, PartialEq, JsonSchema)]
+pub enum RuleSeverity {
+    #[serde(rename = "ERROR")]
+    Error,
+    #[serde(rename = "WARNING")]
+    Warning,
+    #[serde(rename = "NOTICE")]
+    Notice,
+    #[serde(rename = "NONE")]
+    None,
+}
+
+impl TryFrom<&str> for RuleSeverity {
+    type Error = &'static str;
+
+    fn try_from(s: &str) -> Result<Self, &'static str> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(RuleSeverity::Error),
+            "warning" => Ok(RuleSeverity::Warning),
+            "notice" => Ok(RuleSeverity::Notice),
+            "none" => Ok(RuleSeverity::None),
+            _ => Err("unknown severity"),
+        }
+    }
+}
+
+impl fmt::Display for RuleSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+            Self::Notice => write!(f, "notice"),
+            Self::None => write!(f, "none"),
+        }
+    }
+}
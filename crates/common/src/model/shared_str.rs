@@ -0,0 +1,229 @@
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fmt;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::Arc;
+
+/// An immutable string whose clones share the same allocation. It is used for the strings that
+/// are repeated across the results of an analysis (the rule names, the filenames and the messages),
+/// so that they are only stored once when they are interned with an [`Interner`].
+///
+/// It is serialized as a regular string.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SharedStr(Arc<str>);
+
+impl SharedStr {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for SharedStr {
+    fn default() -> Self {
+        Self(Arc::from(""))
+    }
+}
+
+impl Deref for SharedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SharedStr {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<OsStr> for SharedStr {
+    fn as_ref(&self) -> &OsStr {
+        OsStr::new(&*self.0)
+    }
+}
+
+impl AsRef<Path> for SharedStr {
+    fn as_ref(&self) -> &Path {
+        Path::new(&*self.0)
+    }
+}
+
+impl Borrow<str> for SharedStr {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Debug for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for SharedStr {
+    fn from(value: &str) -> Self {
+        Self(Arc::from(value))
+    }
+}
+
+impl From<&String> for SharedStr {
+    fn from(value: &String) -> Self {
+        Self(Arc::from(value.as_str()))
+    }
+}
+
+impl From<String> for SharedStr {
+    fn from(value: String) -> Self {
+        Self(Arc::from(value))
+    }
+}
+
+impl From<Arc<str>> for SharedStr {
+    fn from(value: Arc<str>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<SharedStr> for String {
+    fn from(value: SharedStr) -> Self {
+        value.0.to_string()
+    }
+}
+
+impl PartialEq<str> for SharedStr {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for SharedStr {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for SharedStr {
+    fn eq(&self, other: &String) -> bool {
+        &*self.0 == other.as_str()
+    }
+}
+
+impl PartialEq<SharedStr> for str {
+    fn eq(&self, other: &SharedStr) -> bool {
+        self == &*other.0
+    }
+}
+
+impl PartialEq<SharedStr> for &str {
+    fn eq(&self, other: &SharedStr) -> bool {
+        *self == &*other.0
+    }
+}
+
+impl PartialEq<SharedStr> for String {
+    fn eq(&self, other: &SharedStr) -> bool {
+        self.as_str() == &*other.0
+    }
+}
+
+impl Serialize for SharedStr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SharedStr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl JsonSchema for SharedStr {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        String::schema_name()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
+/// Deduplicates strings: the strings interned with the same content share the same allocation.
+#[derive(Default)]
+pub struct Interner {
+    strings: HashSet<SharedStr>,
+}
+
+impl Interner {
+    /// Returns the shared copy of `value`, which is created the first time it is interned.
+    pub fn intern(&mut self, value: &str) -> SharedStr {
+        if let Some(shared) = self.strings.get(value) {
+            return shared.clone();
+        }
+        let shared = SharedStr::from(value);
+        self.strings.insert(shared.clone());
+        shared
+    }
+
+    /// Replaces `value` with its shared copy, so that its own allocation is freed.
+    pub fn share(&mut self, value: &mut SharedStr) {
+        *value = self.intern(value);
+    }
+
+    /// Returns the number of distinct strings that were interned.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The strings interned with the same content share their allocation.
+    #[test]
+    fn intern_shares_allocation() {
+        let mut interner = Interner::default();
+        let mut first = SharedStr::from("python-security/no-eval".to_string());
+        let mut second = SharedStr::from("python-security/no-eval");
+        let mut other = SharedStr::from("python-security/no-exec");
+        interner.share(&mut first);
+        interner.share(&mut second);
+        interner.share(&mut other);
+        assert!(Arc::ptr_eq(&first.0, &second.0));
+        assert!(!Arc::ptr_eq(&first.0, &other.0));
+        assert_eq!(interner.len(), 2);
+        assert_eq!(first, "python-security/no-eval");
+    }
+
+    #[test]
+    fn serialize_as_string() {
+        let value = SharedStr::from("main.py");
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#""main.py""#);
+        let value: SharedStr = serde_json::from_str(&json).unwrap();
+        assert_eq!(value, "main.py");
+    }
+}
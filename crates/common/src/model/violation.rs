@@ -0,0 +1,244 @@
+use crate::model::position::{Offsets, Position};
+use crate::model::rule::{RuleCategory, RuleSeverity};
+use crate::model::shared_str::SharedStr;
+
+use derive_builder::Builder;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Deserialize, Debug, Serialize, Eq, PartialEq, JsonSchema)]
+pub enum EditType {
+    #[serde(rename = "ADD")]
+    Add,
+    #[serde(rename = "REMOVE")]
+    Remove,
+    #[serde(rename = "UPDATE")]
+    Update,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone, Builder, JsonSchema)]
+pub struct Edit {
+    pub start: Position,
+    pub end: Option<Position>,
+    #[serde(rename = "editType")]
+    pub edit_type: EditType,
+    pub content: Option<String>,
+}
+
+/// How safely a fix can be applied without being reviewed.
+#[derive(Copy, Clone, Default, Deserialize, Debug, Serialize, Eq, PartialEq, Hash, JsonSchema)]
+pub enum FixApplicability {
+    /// The fix preserves the behavior of the code, and can be applied automatically.
+    #[serde(rename = "AUTOMATIC")]
+    Automatic,
+    /// The fix is likely correct, but it may change the behavior of the code and should be reviewed.
+    #[default]
+    #[serde(rename = "SUGGESTED")]
+    Suggested,
+    /// The fix is a starting point that must be completed manually.
+    #[serde(rename = "MANUAL")]
+    Manual,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone, Builder, JsonSchema)]
+pub struct Fix {
+    pub description: String,
+    pub edits: Vec<Edit>,
+    #[builder(default)]
+    #[serde(default)]
+    pub applicability: FixApplicability,
+}
+
+/// A secondary location that helps explain a violation (e.g. "the function is declared here").
+#[derive(Deserialize, Debug, Serialize, Clone, Builder, JsonSchema)]
+pub struct RelatedLocation {
+    pub start: Position,
+    pub end: Position,
+    pub message: String,
+}
+
+/// The source code of a violation, with lines of context before and after it.
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq, JsonSchema)]
+pub struct Snippet {
+    /// The line number of the first line of the text.
+    pub start_line: u32,
+    /// The line number of the last line of the text.
+    pub end_line: u32,
+    pub text: String,
+}
+
+/// The last commit that changed the lines of a violation, from `git blame`.
+#[derive(Deserialize, Debug, Serialize, Clone, PartialEq, Eq, JsonSchema)]
+pub struct LastCommit {
+    pub sha: String,
+    /// The name of the author of the commit.
+    pub author: String,
+    pub author_email: String,
+    /// The date of the commit, in seconds since the Unix epoch.
+    pub date: i64,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone, Builder, JsonSchema)]
+pub struct Violation {
+    pub start: Position,
+    pub end: Position,
+    #[builder(setter(into))]
+    pub message: SharedStr,
+    pub severity: RuleSeverity,
+    pub category: RuleCategory,
+    pub fixes: Vec<Fix>,
+    /// The names of the other rules that reported this exact violation, and that were merged into it.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merged_rules: Vec<String>,
+    /// Free-form tags attached to this violation by the rule.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The identifiers of the CWEs (Common Weakness Enumeration) this violation maps to (e.g. `79` for CWE-79).
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cwe: Vec<u32>,
+    /// A URL to documentation specific to this violation.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documentation_url: Option<String>,
+    /// Secondary locations that help explain this violation.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related_locations: Vec<RelatedLocation>,
+    /// Whether this violation applies to the file as a whole rather than to a specific location.
+    /// The position of a file-level violation is the start of the file.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub file_level: bool,
+    /// A stable identifier of this violation, computed with `kernel::model::violation::compute_fingerprint`
+    /// when the file is analyzed.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// The owners of the file of this violation, as listed in the `CODEOWNERS` file of the repository.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    /// The source code of this violation, embedded in the reports on request.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<Snippet>,
+    /// The byte offsets and UTF-16 columns of the violation, computed when the file is analyzed.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offsets: Option<Offsets>,
+    /// The last commit that changed the lines of this violation, added to the reports on request.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_commit: Option<LastCommit>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn position(line: u32, col: u32) -> Position {
+        Position { line, col }
+    }
+
+    /// Serializes a value, deserializes it back, and checks that nothing was lost.
+    fn assert_round_trip<T>(value: &T) -> Value
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+    {
+        let json = serde_json::to_value(value).unwrap();
+        let deserialized: T = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&deserialized).unwrap(), json);
+        json
+    }
+
+    #[test]
+    fn violation_round_trip() {
+        let violation = Violation {
+            start: position(1, 2),
+            end: position(3, 4),
+            message: "do not use eval".into(),
+            severity: RuleSeverity::Error,
+            category: RuleCategory::Security,
+            fixes: vec![Fix {
+                description: "remove the call".to_string(),
+                edits: vec![Edit {
+                    start: position(1, 2),
+                    end: Some(position(3, 4)),
+                    edit_type: EditType::Remove,
+                    content: None,
+                }],
+                applicability: FixApplicability::Automatic,
+            }],
+            merged_rules: vec!["other-ruleset/rule".to_string()],
+            tags: vec!["injection".to_string()],
+            cwe: vec![95],
+            documentation_url: Some("https://example.com/rule".to_string()),
+            related_locations: vec![RelatedLocation {
+                start: position(5, 1),
+                end: position(5, 10),
+                message: "the value comes from here".to_string(),
+            }],
+            file_level: false,
+            fingerprint: Some("abcdef".to_string()),
+            owners: vec!["@team".to_string()],
+            snippet: Some(Snippet {
+                start_line: 1,
+                end_line: 3,
+                text: "eval(\n  input\n)".to_string(),
+            }),
+            offsets: Some(Offsets {
+                start_byte: 1,
+                end_byte: 15,
+                start_utf16_col: 1,
+                end_utf16_col: 1,
+            }),
+            last_commit: Some(LastCommit {
+                sha: "0123456789abcdef0123456789abcdef01234567".to_string(),
+                author: "Jane Doe".to_string(),
+                author_email: "jane@example.com".to_string(),
+                date: 1_700_000_000,
+            }),
+        };
+        let json = assert_round_trip(&violation);
+        assert_eq!(json["severity"], "ERROR");
+        assert_eq!(json["category"], "SECURITY");
+        assert_eq!(json["fixes"][0]["edits"][0]["editType"], "REMOVE");
+        assert_eq!(json["fixes"][0]["applicability"], "AUTOMATIC");
+    }
+
+    #[test]
+    fn violation_defaults() {
+        // the optional fields are omitted when they are empty, and default to empty when missing.
+        let json = json!({
+            "start": {"line": 1, "col": 1},
+            "end": {"line": 1, "col": 5},
+            "message": "message",
+            "severity": "WARNING",
+            "category": "BEST_PRACTICES",
+            "fixes": [{"description": "fix", "edits": []}],
+        });
+        let violation: Violation = serde_json::from_value(json.clone()).unwrap();
+        assert!(violation.tags.is_empty());
+        assert!(violation.fingerprint.is_none());
+        assert_eq!(
+            violation.fixes[0].applicability,
+            FixApplicability::Suggested
+        );
+
+        let mut expected = json;
+        expected["fixes"][0]["applicability"] = json!("SUGGESTED");
+        assert_eq!(assert_round_trip(&violation), expected);
+    }
+
+    #[test]
+    fn unknown_category() {
+        // categories of legacy rules are accepted, but never reported.
+        let category: RuleCategory = serde_json::from_value(json!("DEPLOYMENT")).unwrap();
+        assert_eq!(category, RuleCategory::Unknown);
+        assert!(serde_json::to_value(category).is_err());
+    }
+}
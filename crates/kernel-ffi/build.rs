@@ -0,0 +1,13 @@
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    // the header is written to the output directory of the build, never to the sources
+    cbindgen::generate(&crate_dir)
+        .expect("should be able to generate the C header")
+        .write_to_file(out_dir.join("datadog_static_analyzer.h"));
+}
@@ -0,0 +1,239 @@
+//! A C-compatible interface to the kernel, to embed the analyzer in tools written in other
+//! languages. The header `datadog_static_analyzer.h` is generated with
+//! `cbindgen --config cbindgen.toml --output include/datadog_static_analyzer.h` in the crate
+//! directory. The build script also generates it in its output directory
+//! (`target/<profile>/build/kernel-ffi-*/out`), whose path isn't stable.
+//!
+//! All the strings returned by the library are JSON documents that must be freed with
+//! [`dd_sa_string_free`].
+
+use anyhow::{anyhow, Context, Result};
+use kernel::api::{analyze_file, AnalysisRequest};
+use kernel::constants::CARGO_VERSION;
+use kernel::model::analysis::AnalysisOptions;
+use kernel::model::common::{ColumnUnit, Language};
+use kernel::model::rule::{RuleInternal, RuleResult};
+use kernel::model::ruleset::RuleSet;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// The JSON document returned by [`dd_sa_analyze`].
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum AnalysisOutput {
+    Results { results: Vec<RuleResult> },
+    Error { error: String },
+}
+
+/// Analyzes a buffer of UTF-8 code with the rules of the given rulesets.
+///
+/// - `language` is the language of the code, as serialized in the rules (e.g. `PYTHON`).
+/// - `filename` is the path of the file the code comes from.
+/// - `code` and `code_len` are the code to analyze. `code` may be null if `code_len` is 0.
+/// - `rulesets_json` is a JSON array of rulesets, in the format of the Datadog API.
+///
+/// Returns a JSON document with either the results (`{"results": [...]}`) or an error
+/// (`{"error": "..."}`). The returned string must be freed with [`dd_sa_string_free`].
+///
+/// # Safety
+/// `language`, `filename` and `rulesets_json` must be valid nul-terminated strings, and `code` must
+/// point to `code_len` readable bytes (or be null if `code_len` is 0).
+#[no_mangle]
+pub unsafe extern "C" fn dd_sa_analyze(
+    language: *const c_char,
+    filename: *const c_char,
+    code: *const u8,
+    code_len: usize,
+    rulesets_json: *const c_char,
+) -> *mut c_char {
+    let output = catch_unwind(AssertUnwindSafe(|| {
+        analyze_buffer(language, filename, code, code_len, rulesets_json)
+    }))
+    .unwrap_or_else(|_| Err(anyhow!("the analysis panicked")));
+    let output = match output {
+        Ok(results) => AnalysisOutput::Results { results },
+        Err(e) => AnalysisOutput::Error {
+            error: format!("{e:#}"),
+        },
+    };
+    into_c_string(serde_json::to_string(&output).unwrap_or_default())
+}
+
+/// Returns the version of the analyzer. The returned string must be freed with [`dd_sa_string_free`].
+#[no_mangle]
+pub extern "C" fn dd_sa_version() -> *mut c_char {
+    into_c_string(CARGO_VERSION.to_string())
+}
+
+/// Frees a string returned by the library. Passing a null pointer is a no-op.
+///
+/// # Safety
+/// `s` must have been returned by the library, and must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn dd_sa_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// # Safety
+/// See [`dd_sa_analyze`].
+unsafe fn analyze_buffer(
+    language: *const c_char,
+    filename: *const c_char,
+    code: *const u8,
+    code_len: usize,
+    rulesets_json: *const c_char,
+) -> Result<Vec<RuleResult>> {
+    let language = str_from_ptr(language, "language")?;
+    let language: Language = serde_json::from_value(serde_json::Value::from(language))
+        .with_context(|| format!("invalid language {language}"))?;
+    let filename = str_from_ptr(filename, "filename")?;
+    // an empty buffer may be passed as a null pointer, which `from_raw_parts` doesn't accept
+    let code = if code_len == 0 {
+        ""
+    } else if code.is_null() {
+        return Err(anyhow!("code is null"));
+    } else {
+        std::str::from_utf8(std::slice::from_raw_parts(code, code_len))
+            .context("code is not valid UTF-8")?
+    };
+    let rulesets: Vec<RuleSet> = serde_json::from_str(str_from_ptr(rulesets_json, "rulesets")?)
+        .context("invalid rulesets")?;
+
+    let rules = compile_rules(&rulesets, language)?;
+    let analysis_options = AnalysisOptions {
+        log_output: false,
+        use_debug: false,
+        ignore_generated_files: false,
+        report_parse_errors: false,
+        column_unit: ColumnUnit::Bytes,
+        generated_files_rules: HashSet::new(),
+        profile_rules: false,
+        nondeterministic_rules: false,
+    };
+    let request = AnalysisRequest::new(filename, code)
+        .language(language)
+        .rules(&rules)
+        .options(analysis_options);
+    Ok(analyze_file(&request)?.rule_results)
+}
+
+/// Compiles the rules of the rulesets for the given language.
+fn compile_rules(rulesets: &[RuleSet], language: Language) -> Result<Vec<RuleInternal>> {
+    rulesets
+        .iter()
+        .flat_map(|ruleset| &ruleset.rules)
+        .filter(|rule| rule.language == language)
+        .map(|rule| {
+            if !rule.verify_checksum() {
+                return Err(anyhow!("rule {} has an invalid checksum", rule.name));
+            }
+            rule.to_rule_internal()
+        })
+        .collect()
+}
+
+/// # Safety
+/// `ptr` must be null or a valid nul-terminated string.
+unsafe fn str_from_ptr<'a>(ptr: *const c_char, name: &str) -> Result<&'a str> {
+    if ptr.is_null() {
+        return Err(anyhow!("{name} is null"));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .with_context(|| format!("{name} is not valid UTF-8"))
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    // JSON documents never contain a nul byte, as it is escaped.
+    CString::new(s).unwrap_or_default().into_raw()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn call_analyze(language: &str, code: &str, rulesets: &str) -> Value {
+        let language = CString::new(language).unwrap();
+        let filename = CString::new("myfile.py").unwrap();
+        let rulesets = CString::new(rulesets).unwrap();
+        unsafe {
+            let output = dd_sa_analyze(
+                language.as_ptr(),
+                filename.as_ptr(),
+                code.as_ptr(),
+                code.len(),
+                rulesets.as_ptr(),
+            );
+            let value = serde_json::from_str(CStr::from_ptr(output).to_str().unwrap()).unwrap();
+            dd_sa_string_free(output);
+            value
+        }
+    }
+
+    #[test]
+    fn analyze_without_rules() {
+        let output = call_analyze("PYTHON", "def foo():\n    pass\n", "[]");
+        assert_eq!(output, serde_json::json!({"results": []}));
+    }
+
+    #[test]
+    fn analyze_errors() {
+        let output = call_analyze("COBOL", "", "[]");
+        assert!(output["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("invalid language COBOL"));
+        let output = call_analyze("PYTHON", "", "{");
+        assert!(output["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("invalid rulesets"));
+    }
+
+    #[test]
+    fn null_pointers() {
+        unsafe {
+            let output = dd_sa_analyze(
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+                0,
+                std::ptr::null(),
+            );
+            assert_eq!(
+                CStr::from_ptr(output).to_str().unwrap(),
+                r#"{"error":"language is null"}"#
+            );
+            dd_sa_string_free(output);
+            dd_sa_string_free(std::ptr::null_mut());
+        }
+    }
+
+    /// An empty buffer can be passed as a null pointer, but not a non-empty one.
+    #[test]
+    fn null_code() {
+        let language = CString::new("PYTHON").unwrap();
+        let filename = CString::new("myfile.py").unwrap();
+        let rulesets = CString::new("[]").unwrap();
+        let analyze = |code_len: usize| unsafe {
+            let output = dd_sa_analyze(
+                language.as_ptr(),
+                filename.as_ptr(),
+                std::ptr::null(),
+                code_len,
+                rulesets.as_ptr(),
+            );
+            let value: Value =
+                serde_json::from_str(CStr::from_ptr(output).to_str().unwrap()).unwrap();
+            dd_sa_string_free(output);
+            value
+        };
+        assert_eq!(analyze(0), serde_json::json!({"results": []}));
+        assert_eq!(analyze(1), serde_json::json!({"error": "code is null"}));
+    }
+}
@@ -39,6 +39,15 @@ impl Point {
     }
 }
 
+impl From<Point> for ::common::model::position::Position {
+    fn from(value: Point) -> Self {
+        Self {
+            line: value.line.get(),
+            col: value.col.get(),
+        }
+    }
+}
+
 impl Default for Point {
     fn default() -> Self {
         Self {
@@ -62,3 +62,14 @@ pub enum Severity {
     Notice,
     Info,
 }
+
+impl From<Severity> for ::common::model::rule::RuleSeverity {
+    fn from(value: Severity) -> Self {
+        match value {
+            Severity::Error => Self::Error,
+            Severity::Warning => Self::Warning,
+            Severity::Notice => Self::Notice,
+            Severity::Info => Self::None,
+        }
+    }
+}
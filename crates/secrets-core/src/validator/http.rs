@@ -8,10 +8,10 @@ use governor::middleware::NoOpMiddleware;
 use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::io::Read;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::ops::Add;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use url::Url;
 
 /// An error returned by an [`HttpValidator`] when performing a validation attempt.
@@ -58,6 +58,8 @@ enum ValidationError {
     UnexpectedValue(String),
     #[error("http body length of {length} bytes exceeds limit of {limit} bytes")]
     BodyTooLarge { length: usize, limit: usize },
+    #[error("host `{0}` is not in the allowed hosts")]
+    HostNotAllowed(String),
 }
 
 const DEFAULT_MAX_ATTEMPTS: usize = 4;
@@ -265,6 +267,8 @@ pub struct HttpValidator<T: Clock = MonotonicClock> {
     clock: T,
     /// The limiter that enforces a maximum outbound HTTP request rate across all threads using this validator.
     rate_limiter: Arc<RateLimiter<T>>,
+    /// The limits shared with other validators, if any.
+    shared_limits: Option<Arc<SharedLimits>>,
     /// The implementation that determines how to structure the HTTP request on a per-
     /// [`Candidate`] basis.
     request_generator: RequestGenerator,
@@ -287,6 +291,9 @@ pub enum NextAction {
     RetryAfter(Duration),
     /// The handler successfully performed a validation and categorized the candidate.
     ReturnResult(SecretCategory),
+    /// The handler accepted the response, and the validation continues with the next request of a
+    /// chain (see [`HttpValidator::send`]).
+    Continue,
     /// No registered handler could handle the HTTP response result, so a default fallback error was generated.
     Unhandled,
 }
@@ -325,6 +332,34 @@ impl<T: Clock> Validator for HttpValidator<T> {
     }
 
     fn validate(&self, candidate: Candidate) -> Result<SecretCategory, ValidatorError> {
+        let outcome = self.send_with_retries(&candidate)?;
+        match outcome.action {
+            NextAction::ReturnResult(result) => Ok(result),
+            // A single request can't continue with another one.
+            _ => Err(ValidationError::UnhandledResponse(Box::new(outcome.response)).into()),
+        }
+    }
+}
+
+/// The response that ended the attempts of [`HttpValidator::send`], and the action the response
+/// parser returned for it (either [`NextAction::ReturnResult`] or [`NextAction::Continue`]).
+#[derive(Debug)]
+pub struct SendOutcome {
+    pub action: NextAction,
+    pub response: Result<HttpResponse, ureq::Error>,
+}
+
+impl<T: Clock> HttpValidator<T> {
+    /// Sends the request for a candidate, with the same rate limiting and retries as a validation,
+    /// but returns the response instead of only the result. This allows a request to be a step of a
+    /// chain of requests, where the response parser returns [`NextAction::Continue`] to move on to the
+    /// next step.
+    pub fn send(&self, candidate: &Candidate) -> Result<SendOutcome, HttpValidatorError> {
+        self.send_with_retries(candidate)
+            .map_err(HttpValidatorError::from)
+    }
+
+    fn send_with_retries(&self, candidate: &Candidate) -> Result<SendOutcome, ValidationError> {
         #[cfg(not(test))]
         use std::time::Instant;
         #[cfg(test)]
@@ -343,31 +378,42 @@ impl<T: Clock> Validator for HttpValidator<T> {
             loop {
                 let elapsed = start_time.elapsed();
                 if elapsed > self.max_attempt_duration {
-                    return Err(ValidationError::RetryTimeExceeded { attempted, elapsed }.into());
+                    return Err(ValidationError::RetryTimeExceeded { attempted, elapsed });
                 }
-                match self.rate_limiter.check() {
-                    Ok(_) => break,
-                    Err(try_again_at) => {
-                        let next_delay = try_again_at.wait_time_from(self.clock.now());
+                let next_delay = match self.rate_limiter.check() {
+                    Ok(_) => self
+                        .shared_limits
+                        .as_ref()
+                        .and_then(|limits| limits.check_rate().err()),
+                    Err(try_again_at) => Some(try_again_at.wait_time_from(self.clock.now())),
+                };
+                match next_delay {
+                    None => break,
+                    Some(next_delay) => {
                         let elapsed = start_time.elapsed();
                         if elapsed.add(next_delay) > self.max_attempt_duration {
                             return Err(ValidationError::RetryWillExceedTime {
                                 attempted,
                                 elapsed,
                                 next_delay,
-                            }
-                            .into());
+                            });
                         }
                         thread_sleep(next_delay);
                     }
                 }
             }
 
-            let formatted_url = (self.request_generator.format_url)(&candidate)
+            let formatted_url = (self.request_generator.format_url)(candidate)
                 .map_err(|_| ValidationError::GeneratorError("format_url"))?;
 
             let url = Url::parse(&formatted_url)
                 .map_err(|parse_err| ValidationError::InvalidUrl(formatted_url, parse_err))?;
+            if let Some(limits) = &self.shared_limits {
+                let host = url.host_str().unwrap_or_default();
+                if !limits.allows_host(host) {
+                    return Err(ValidationError::HostNotAllowed(host.to_string()));
+                }
+            }
 
             let time_budget = {
                 let elapsed = start_time.elapsed();
@@ -380,9 +426,24 @@ impl<T: Clock> Validator for HttpValidator<T> {
                 .agent
                 .request(self.request_generator.method.as_ref(), url.as_str())
                 .timeout(time_budget.min(self.request_timeout));
-            request = (self.request_generator.add_headers)(&candidate, request)
+            request = (self.request_generator.add_headers)(candidate, request)
                 .map_err(|_| ValidationError::GeneratorError("add_headers"))?;
 
+            // The permit is held until the response has been read.
+            let permit = match &self.shared_limits {
+                Some(limits) => {
+                    let host = url.host_str().unwrap_or_default();
+                    let permit = limits.acquire(host, time_budget).ok_or_else(|| {
+                        ValidationError::RetryTimeExceeded {
+                            attempted,
+                            elapsed: start_time.elapsed(),
+                        }
+                    })?;
+                    Some(permit)
+                }
+                None => None,
+            };
+
             attempted += 1;
             let ureq_result = match &self.request_generator.method {
                 HttpMethod::Get => request.call(),
@@ -391,7 +452,7 @@ impl<T: Clock> Validator for HttpValidator<T> {
                         .request_generator
                         .build_post_payload
                         .as_ref()
-                        .map(|get_payload_for| get_payload_for(&candidate))
+                        .map(|get_payload_for| get_payload_for(candidate))
                         .transpose()
                         .map_err(|_| ValidationError::GeneratorError("build_post_payload"))?;
                     if let Some((_, content_type)) = &payload {
@@ -408,11 +469,12 @@ impl<T: Clock> Validator for HttpValidator<T> {
                 }
                 Err(err) => Err(err),
             };
+            drop(permit);
             let next_action = (self.response_parser)(&response);
 
             match next_action {
                 NextAction::Abort => {
-                    return Err(ValidationError::RequestedAbort(Box::new(response)).into());
+                    return Err(ValidationError::RequestedAbort(Box::new(response)));
                 }
                 NextAction::Retry => {}
                 NextAction::RetryAfter(http_retry_after) => {
@@ -423,9 +485,14 @@ impl<T: Clock> Validator for HttpValidator<T> {
                         .checked_sub(iter.peek().copied().unwrap_or_default())
                         .unwrap_or_default();
                 }
-                NextAction::ReturnResult(result) => return Ok(result),
+                NextAction::ReturnResult(_) | NextAction::Continue => {
+                    return Ok(SendOutcome {
+                        action: next_action,
+                        response,
+                    });
+                }
                 NextAction::Unhandled => {
-                    return Err(ValidationError::UnhandledResponse(Box::new(response)).into());
+                    return Err(ValidationError::UnhandledResponse(Box::new(response)));
                 }
             }
 
@@ -437,8 +504,7 @@ impl<T: Clock> Validator for HttpValidator<T> {
                         attempted,
                         elapsed,
                         next_delay: to_sleep,
-                    }
-                    .into());
+                    });
                 }
                 thread_sleep(to_sleep);
             }
@@ -448,8 +514,7 @@ impl<T: Clock> Validator for HttpValidator<T> {
         Err(ValidationError::RetryAttemptsExceeded {
             attempted,
             elapsed: start_time.elapsed(),
-        }
-        .into())
+        })
     }
 }
 
@@ -469,7 +534,8 @@ impl From<ValidationError> for HttpValidatorError {
             ValidationError::InvalidUrl(_, _)
             | ValidationError::InvalidMethod(_)
             | ValidationError::UnhandledResponse(_)
-            | ValidationError::GeneratorError(_) => Self::LocalError(value.to_string()),
+            | ValidationError::GeneratorError(_)
+            | ValidationError::HostNotAllowed(_) => Self::LocalError(value.to_string()),
             ValidationError::RetryTimeExceeded { attempted, elapsed }
             | ValidationError::RetryAttemptsExceeded { attempted, elapsed }
             | ValidationError::RetryWillExceedTime {
@@ -627,12 +693,213 @@ impl Default for RateLimitQuota {
     }
 }
 
+/// The configuration of the HTTP client used by a group of [`HttpValidator`]s (for example, all the
+/// validators of a scan).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpClientConfig {
+    /// The URL of the proxy to send the requests through. If `None`, the proxy is read from the
+    /// `ALL_PROXY`, `HTTPS_PROXY` or `HTTP_PROXY` environment variables, if set.
+    pub proxy: Option<String>,
+    /// The maximum number of requests in flight to a single host.
+    pub max_concurrency_per_host: Option<NonZeroUsize>,
+    /// The maximum number of requests per second, across all the validators.
+    pub max_requests_per_second: Option<NonZeroU32>,
+    /// The hosts the validators may send requests to. If `None`, any host is allowed.
+    pub allowed_hosts: Option<HostAllowlist>,
+}
+
+impl HttpClientConfig {
+    /// Builds an agent that sends its requests through the configured proxy. The agent can be
+    /// cloned to share its connection pool.
+    pub fn build_agent(&self) -> Result<ureq::Agent, ureq::Error> {
+        let mut builder = ureq::AgentBuilder::new().try_proxy_from_env(true);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(ureq::Proxy::new(proxy)?);
+        }
+        // A redirect would send the request to a host that was not checked against the allowlist.
+        if self.allowed_hosts.is_some() {
+            builder = builder.redirects(0);
+        }
+        Ok(builder.build())
+    }
+
+    /// Builds the limits to share between the validators, if any limit is configured.
+    pub fn build_limits(&self) -> Option<Arc<SharedLimits>> {
+        if self.max_concurrency_per_host.is_none()
+            && self.max_requests_per_second.is_none()
+            && self.allowed_hosts.is_none()
+        {
+            return None;
+        }
+        let mut limits =
+            SharedLimits::new(self.max_requests_per_second, self.max_concurrency_per_host);
+        if let Some(allowed_hosts) = &self.allowed_hosts {
+            limits = limits.allowed_hosts(allowed_hosts.clone());
+        }
+        Some(Arc::new(limits))
+    }
+}
+
+/// The hosts that [`HttpValidator`]s may send requests to. A pattern is a host name, where `*`
+/// matches any sequence of characters: `*.example.com` allows all the subdomains of `example.com`,
+/// but not `example.com` itself. Host names are not case-sensitive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostAllowlist {
+    patterns: Vec<String>,
+}
+
+impl HostAllowlist {
+    pub fn new<T: AsRef<str>>(patterns: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            patterns: patterns
+                .into_iter()
+                .map(|pattern| pattern.as_ref().to_ascii_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Returns true if the host matches one of the patterns.
+    pub fn allows(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.patterns
+            .iter()
+            .any(|pattern| wildcard_match(pattern, &host))
+    }
+}
+
+/// Returns true if the text matches the pattern, where `*` matches any sequence of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = text.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // The pattern has no wildcard.
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Limits on the outbound requests that are shared by a group of [`HttpValidator`]s, in addition to
+/// the rate limit of each validator.
+pub struct SharedLimits {
+    /// The hosts the requests may be sent to, if restricted.
+    allowed_hosts: Option<HostAllowlist>,
+    /// The limiter that enforces a maximum outbound HTTP request rate across all the validators.
+    rate_limiter: Option<governor::DefaultDirectRateLimiter>,
+    clock: DefaultClock,
+    max_concurrency_per_host: Option<NonZeroUsize>,
+    /// The number of requests in flight, by host.
+    in_flight: Mutex<HashMap<String, usize>>,
+    released: Condvar,
+}
+
+impl SharedLimits {
+    pub fn new(
+        max_requests_per_second: Option<NonZeroU32>,
+        max_concurrency_per_host: Option<NonZeroUsize>,
+    ) -> Self {
+        Self {
+            allowed_hosts: None,
+            rate_limiter: max_requests_per_second
+                .map(|rate| governor::RateLimiter::direct(governor::Quota::per_second(rate))),
+            clock: DefaultClock::default(),
+            max_concurrency_per_host,
+            in_flight: Mutex::new(HashMap::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Restricts the requests to the hosts of the allowlist.
+    pub fn allowed_hosts(mut self, allowed_hosts: HostAllowlist) -> Self {
+        self.allowed_hosts = Some(allowed_hosts);
+        self
+    }
+
+    /// Returns true if a request can be sent to the host.
+    fn allows_host(&self, host: &str) -> bool {
+        self.allowed_hosts
+            .as_ref()
+            .map_or(true, |allowed_hosts| allowed_hosts.allows(host))
+    }
+
+    /// Returns an error with the time to wait if the global rate limit doesn't allow a request now.
+    fn check_rate(&self) -> Result<(), Duration> {
+        match &self.rate_limiter {
+            Some(limiter) => limiter
+                .check()
+                .map_err(|try_again_at| try_again_at.wait_time_from(self.clock.now())),
+            None => Ok(()),
+        }
+    }
+
+    /// Waits until a request can be sent to the host, and returns the permit to hold while the
+    /// request is in flight. Returns `None` if no request could be sent within the timeout.
+    fn acquire(&self, host: &str, timeout: Duration) -> Option<HostPermit<'_>> {
+        let Some(max) = self.max_concurrency_per_host else {
+            return Some(HostPermit {
+                limits: self,
+                host: None,
+            });
+        };
+        let in_flight = self.in_flight.lock().unwrap_or_else(|err| err.into_inner());
+        let (mut in_flight, wait) = self
+            .released
+            .wait_timeout_while(in_flight, timeout, |in_flight| {
+                in_flight.get(host).copied().unwrap_or_default() >= max.get()
+            })
+            .unwrap_or_else(|err| err.into_inner());
+        if wait.timed_out() {
+            return None;
+        }
+        *in_flight.entry(host.to_string()).or_default() += 1;
+        Some(HostPermit {
+            limits: self,
+            host: Some(host.to_string()),
+        })
+    }
+}
+
+/// A request in flight to a host, counted against the [`SharedLimits`] until it's dropped.
+struct HostPermit<'a> {
+    limits: &'a SharedLimits,
+    host: Option<String>,
+}
+
+impl Drop for HostPermit<'_> {
+    fn drop(&mut self) {
+        let Some(host) = self.host.take() else {
+            return;
+        };
+        let mut in_flight = self
+            .limits
+            .in_flight
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        if let Some(count) = in_flight.get_mut(&host) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(&host);
+            }
+        }
+        self.limits.released.notify_all();
+    }
+}
+
 pub struct HttpValidatorBuilder {
     validator_id: ValidatorId,
     max_attempted_duration: Duration,
     request_generator: RequestGenerator,
     response_parser: Box<DynFnResponseParser>,
     rate_limit: RateLimitQuota,
+    shared_limits: Option<Arc<SharedLimits>>,
     retry_config: RetryConfig,
     request_timeout: Duration,
 }
@@ -651,6 +918,7 @@ impl HttpValidatorBuilder {
             request_generator,
             response_parser,
             rate_limit: RateLimitQuota::default(),
+            shared_limits: None,
             retry_config: RetryConfig::default(),
             request_timeout: Self::DEFAULT_REQ_TIMEOUT,
         }
@@ -673,6 +941,12 @@ impl HttpValidatorBuilder {
         self
     }
 
+    /// Sets the limits shared with other validators, which apply in addition to the validator's rate limit.
+    pub fn shared_limits(mut self, limits: Arc<SharedLimits>) -> Self {
+        self.shared_limits = Some(limits);
+        self
+    }
+
     /// The maximum amount of time to spend on a single validation attempt, inclusive of retries and
     /// round-trip latency.
     pub fn max_attempt_duration(mut self, max: Duration) -> Self {
@@ -706,6 +980,7 @@ impl HttpValidatorBuilder {
             max_attempt_duration: self.max_attempted_duration,
             clock,
             rate_limiter: Arc::new(rate_limiter),
+            shared_limits: self.shared_limits,
             request_generator: self.request_generator,
             response_parser: self.response_parser,
             backoff_generator: self.retry_config.to_backoff_generator(),
@@ -805,6 +1080,50 @@ impl RequestGeneratorBuilder {
 /// The default duration to wait when failing to parse an expected "Retry-After" header.
 const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(2);
 
+/// Parses the value of a `Retry-After` header, which is either a number of seconds, or an HTTP
+/// date in the IMF-fixdate format (e.g. `Wed, 21 Oct 2015 07:28:00 GMT`). A date in the past is
+/// a zero delay.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// Parses an HTTP date in the IMF-fixdate format (RFC 9110, section 5.6.7).
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let (_, date) = value.split_once(", ")?;
+    let [day, month, year, time, "GMT"] = date.split(' ').collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let day = day.parse::<i64>().ok()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let year = year.parse::<i64>().ok()?;
+    let [hours, minutes, seconds] = time
+        .split(':')
+        .map(|part| part.parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?[..]
+    else {
+        return None;
+    };
+
+    // The number of days since the Unix epoch, from Howard Hinnant's `days_from_civil`.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let timestamp = days * 86_400 + hours * 3_600 + minutes * 60 + seconds;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(timestamp).ok()?))
+}
+
 /// Builds a generalized Response parser that only looks at HTTP status codes.
 ///
 /// When a transport error occurs, the original request will be retried if it was due to a
@@ -889,14 +1208,17 @@ impl ResponseParserBuilder {
                 Err(err) => match err {
                     ureq::Error::Status(code, response) => match code {
                         429 => {
-                            let retry_after = response.header("Retry-After").map(str::parse::<u64>);
-                            if let Some(Ok(retry_after)) = retry_after {
-                                NextAction::RetryAfter(Duration::from_secs(retry_after))
-                            } else {
-                                NextAction::RetryAfter(DEFAULT_RETRY_AFTER)
-                            }
+                            let retry_after = response
+                                .header("Retry-After")
+                                .and_then(|value| parse_retry_after(value, SystemTime::now()));
+                            NextAction::RetryAfter(retry_after.unwrap_or(DEFAULT_RETRY_AFTER))
                         }
-                        500 | 502 | 503 | 504 => NextAction::Retry,
+                        // A server that is unavailable can indicate when to retry.
+                        503 => response
+                            .header("Retry-After")
+                            .and_then(|value| parse_retry_after(value, SystemTime::now()))
+                            .map_or(NextAction::Retry, NextAction::RetryAfter),
+                        500 | 502 | 504 => NextAction::Retry,
                         501 | 506 | 507 | 508 | 510 | 511 => NextAction::Abort,
                         _ => NextAction::Unhandled,
                     },
@@ -921,17 +1243,20 @@ mod tests {
     use crate::rule::{LocatedString, RuleMatch};
     use crate::validator::http::time::{Instant, MockClock};
     use crate::validator::http::{
-        DynFnResponseParser, HttpResponse, HttpValidator, HttpValidatorBuilder, HttpValidatorError,
-        NextAction, RequestGenerator, RequestGeneratorBuilder, ResponseParserBuilder, RetryConfig,
-        RetryPolicy, ValidationError,
+        parse_retry_after, DynFnResponseParser, HostAllowlist, HttpResponse, HttpValidator,
+        HttpValidatorBuilder, HttpValidatorError, NextAction, RequestGenerator,
+        RequestGeneratorBuilder, ResponseParserBuilder, RetryConfig, RetryPolicy, SharedLimits,
+        ValidationError,
     };
     use crate::validator::{Candidate, SecretCategory, Severity, ValidatorError};
     use crate::Validator;
     use httpmock::MockServer;
     use std::collections::HashMap;
+    use std::num::NonZeroUsize;
     use std::ops::Mul;
     use std::path::PathBuf;
-    use std::time::Duration;
+    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
     use ureq::Agent;
 
     const VALID: &str = "121bdc4e---------valid----------49935a92";
@@ -1039,6 +1364,30 @@ mod tests {
         assert_eq!(category, SecretCategory::Invalid(Severity::Info));
     }
 
+    /// A response parser can accept a response without a result, which `send` returns to continue a chain
+    #[test]
+    fn send_continue() {
+        let ms = MockServer::start();
+        let mock = ms.mock(|when, then| {
+            when.any_request();
+            then.status(200).body("access_token=abc");
+        });
+        let req_gen = base_request_generator(&ms);
+        let resp_parser = ResponseParserBuilder::new()
+            .on_status_code(200, NextAction::Continue)
+            .build();
+        let validator = build_validator!(req_gen, resp_parser);
+        let outcome = validator.send(&to_candidate(VALID)).unwrap();
+        assert_eq!(outcome.action, NextAction::Continue);
+        assert_eq!(outcome.response.unwrap().body(), "access_token=abc");
+        // A validation can't end without a result
+        let ValidatorError::ChildError { err, .. } =
+            validator.validate(to_candidate(VALID)).unwrap_err();
+        let err = err.downcast_ref::<HttpValidatorError>().unwrap();
+        assert!(matches!(err, HttpValidatorError::LocalError(_)));
+        mock.assert_hits(2);
+    }
+
     #[test]
     fn validation_abort() {
         let ms = MockServer::start();
@@ -1142,6 +1491,102 @@ mod tests {
         assert_eq!(pre_validation.elapsed(), Duration::from_secs(0));
     }
 
+    /// A server that is unavailable can also indicate when to retry
+    #[test]
+    fn validation_503_retry_after() {
+        let ms = MockServer::start();
+        let mock = ms.mock(|when, then| {
+            when.any_request();
+            then.header("Retry-After", "6").status(503);
+        });
+        let req_gen = base_request_generator(&ms);
+        let resp_parser = base_response_parser();
+        let validator = build_validator!(
+            req_gen,
+            resp_parser,
+            retry_config(fixed_retry(2, Duration::from_secs(1)))
+        );
+        let pre_validation = Instant::now();
+        let _ = validator.validate(to_candidate(VALID));
+        mock.assert_hits(2);
+        assert_eq!(pre_validation.elapsed(), Duration::from_secs(6));
+    }
+
+    #[test]
+    fn retry_after_values() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_445_412_400);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::from_secs(80))
+        );
+        // A date in the past doesn't delay the retry
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 UTC", now),
+            None
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    /// The number of requests in flight to a host is capped, independently for each host
+    #[test]
+    fn shared_limits_per_host() {
+        let limits = SharedLimits::new(None, NonZeroUsize::new(1));
+        let timeout = Duration::from_millis(10);
+        let permit = limits.acquire("a.example.com", timeout).unwrap();
+        assert!(limits.acquire("a.example.com", timeout).is_none());
+        assert!(limits.acquire("b.example.com", timeout).is_some());
+        drop(permit);
+        assert!(limits.acquire("a.example.com", timeout).is_some());
+    }
+
+    #[test]
+    fn host_allowlist_patterns() {
+        let allowlist = HostAllowlist::new(["api.github.com", "*.DatadogHQ.com", "10.*.*.1"]);
+        assert!(allowlist.allows("API.github.com"));
+        assert!(allowlist.allows("api.datadoghq.com"));
+        assert!(allowlist.allows("api.us5.datadoghq.com"));
+        assert!(!allowlist.allows("datadoghq.com"));
+        assert!(!allowlist.allows("api.datadoghq.com.attacker.io"));
+        assert!(!allowlist.allows("github.com"));
+        assert!(allowlist.allows("10.0.0.1"));
+        assert!(!allowlist.allows("10.0.0.12"));
+        assert!(!HostAllowlist::default().allows("api.github.com"));
+    }
+
+    /// A request to a host that isn't allowed is not sent
+    #[test]
+    fn host_not_allowed() {
+        let ms = MockServer::start();
+        let mock = ms.mock(|when, then| {
+            when.any_request();
+            then.status(200);
+        });
+        let req_gen = base_request_generator(&ms);
+        let resp_parser = base_response_parser();
+        let limits =
+            SharedLimits::new(None, None).allowed_hosts(HostAllowlist::new(["*.example.com"]));
+        let validator = build_validator!(req_gen, resp_parser, shared_limits(Arc::new(limits)));
+        let err = validator.send(&to_candidate(VALID)).unwrap_err();
+        assert!(matches!(err, HttpValidatorError::LocalError(_)));
+        mock.assert_hits(0);
+
+        let req_gen = base_request_generator(&ms);
+        let resp_parser = base_response_parser();
+        let limits =
+            SharedLimits::new(None, None).allowed_hosts(HostAllowlist::new([ms.host().as_str()]));
+        let validator = build_validator!(req_gen, resp_parser, shared_limits(Arc::new(limits)));
+        assert!(validator.send(&to_candidate(VALID)).is_ok());
+        mock.assert_hits(1);
+    }
+
     /// Tests rate limiting for unique requests.
     #[test]
     fn rate_limiter_full_attempts() {
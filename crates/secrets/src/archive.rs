@@ -0,0 +1,305 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use flate2::read::GzDecoder;
+use std::io;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+
+/// The separator between the path of an archive and the path of a file inside of it.
+///
+/// For example, `lib/bundle.jar!com/example/application.properties`.
+pub const ARCHIVE_PATH_SEPARATOR: char = '!';
+
+/// The number of leading bytes inspected to determine if a file is text.
+const TEXT_SNIFF_LEN: usize = 8192;
+
+/// Limits on how far the scanner descends into archives.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ArchiveConfig {
+    /// The maximum nesting level of archives. A depth of 1 scans the files contained in an archive,
+    /// but not the files within an archive that is itself contained in an archive.
+    pub max_depth: usize,
+    /// The maximum number of uncompressed bytes read from a single top-level archive, including
+    /// any nested archives.
+    pub max_total_size: u64,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            max_total_size: 50 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if [".zip", ".jar", ".war", ".ear", ".aar"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+        {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns true if the path has the extension of an archive format the scanner can descend into.
+pub fn is_archive(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(ArchiveKind::from_name)
+        .is_some()
+}
+
+/// A text file extracted from an archive.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ArchiveEntry {
+    /// The path of the entry relative to the top-level archive, using [`ARCHIVE_PATH_SEPARATOR`]
+    /// to separate nested archives (e.g. `inner.jar!config/app.properties`).
+    pub inner_path: String,
+    pub contents: Vec<u8>,
+}
+
+/// Reads the text files contained in the archive at `path`, descending into nested archives
+/// according to the `config`. Entries that exceed the remaining size budget are not read.
+pub fn read_archive(path: &Path, config: &ArchiveConfig) -> io::Result<Vec<ArchiveEntry>> {
+    let name = path.to_string_lossy();
+    let kind = ArchiveKind::from_name(&name)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not an archive"))?;
+    let file = std::fs::File::open(path)?;
+    let mut budget = config.max_total_size;
+    let mut entries = Vec::new();
+    visit_archive(kind, file, "", 1, config, &mut budget, &mut entries)?;
+    Ok(entries)
+}
+
+fn visit_archive<R: Read + Seek>(
+    kind: ArchiveKind,
+    reader: R,
+    prefix: &str,
+    depth: usize,
+    config: &ArchiveConfig,
+    budget: &mut u64,
+    entries: &mut Vec<ArchiveEntry>,
+) -> io::Result<()> {
+    if depth > config.max_depth {
+        return Ok(());
+    }
+    match kind {
+        ArchiveKind::Zip => {
+            let mut archive = zip::ZipArchive::new(reader)?;
+            for i in 0..archive.len() {
+                let mut file = archive.by_index(i)?;
+                if file.is_dir() {
+                    continue;
+                }
+                let name = file.name().to_string();
+                let Some(contents) = read_within_budget(&mut file, file.size(), budget)? else {
+                    break;
+                };
+                drop(file);
+                visit_entry(&name, contents, prefix, depth, config, budget, entries)?;
+            }
+        }
+        ArchiveKind::Tar => visit_tar(reader, prefix, depth, config, budget, entries)?,
+        ArchiveKind::TarGz => visit_tar(
+            GzDecoder::new(reader),
+            prefix,
+            depth,
+            config,
+            budget,
+            entries,
+        )?,
+    }
+    Ok(())
+}
+
+fn visit_tar<R: Read>(
+    reader: R,
+    prefix: &str,
+    depth: usize,
+    config: &ArchiveConfig,
+    budget: &mut u64,
+    entries: &mut Vec<ArchiveEntry>,
+) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().to_string();
+        let size = entry.size();
+        let Some(contents) = read_within_budget(&mut entry, size, budget)? else {
+            break;
+        };
+        visit_entry(&name, contents, prefix, depth, config, budget, entries)?;
+    }
+    Ok(())
+}
+
+/// Handles a file read from an archive: nested archives are descended into, text files are kept,
+/// and binary files are discarded.
+fn visit_entry(
+    name: &str,
+    contents: Vec<u8>,
+    prefix: &str,
+    depth: usize,
+    config: &ArchiveConfig,
+    budget: &mut u64,
+    entries: &mut Vec<ArchiveEntry>,
+) -> io::Result<()> {
+    let inner_path = format!("{}{}", prefix, name);
+    if let Some(kind) = ArchiveKind::from_name(name) {
+        let nested_prefix = format!("{}{}", inner_path, ARCHIVE_PATH_SEPARATOR);
+        // A corrupted nested archive shouldn't prevent scanning the rest of its parent.
+        let _ = visit_archive(
+            kind,
+            Cursor::new(contents),
+            &nested_prefix,
+            depth + 1,
+            config,
+            budget,
+            entries,
+        );
+    } else if is_text(&contents) {
+        entries.push(ArchiveEntry {
+            inner_path,
+            contents,
+        });
+    }
+    Ok(())
+}
+
+/// Reads an entry if its size fits in the remaining budget, returning `None` once the budget is exhausted.
+fn read_within_budget(
+    reader: &mut impl Read,
+    size: u64,
+    budget: &mut u64,
+) -> io::Result<Option<Vec<u8>>> {
+    if size > *budget {
+        *budget = 0;
+        return Ok(None);
+    }
+    let mut contents = Vec::with_capacity(size as usize);
+    // The declared size can't be trusted, so the read itself is bounded as well.
+    reader.take(*budget).read_to_end(&mut contents)?;
+    *budget -= contents.len() as u64;
+    Ok(Some(contents))
+}
+
+/// Returns true if the leading bytes of the contents don't contain a NUL byte.
+fn is_text(contents: &[u8]) -> bool {
+    let len = contents.len().min(TEXT_SNIFF_LEN);
+    !contents[..len].contains(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in files {
+            writer
+                .start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn tar_gz_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *contents).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn read_bytes(name: &str, bytes: Vec<u8>, config: &ArchiveConfig) -> Vec<ArchiveEntry> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, bytes).unwrap();
+        read_archive(&path, config).unwrap()
+    }
+
+    #[test]
+    fn archive_detection() {
+        assert!(is_archive(Path::new("lib/app.jar")));
+        assert!(is_archive(Path::new("dist/Bundle.ZIP")));
+        assert!(is_archive(Path::new("release.tar.gz")));
+        assert!(is_archive(Path::new("release.tgz")));
+        assert!(!is_archive(Path::new("src/main.rs")));
+        assert!(!is_archive(Path::new("archive.gz")));
+    }
+
+    /// Text files are extracted, binary files are skipped, and nested archives use the `!` separator.
+    #[test]
+    fn zip_nested_entries() {
+        let inner = zip_bytes(&[("application.properties", b"key=abc_018cf028")]);
+        let outer = zip_bytes(&[
+            ("README.md", b"hello"),
+            ("Main.class", b"\xca\xfe\xba\xbe\x00\x00"),
+            ("lib/inner.jar", &inner),
+        ]);
+        let entries = read_bytes("outer.zip", outer, &ArchiveConfig::default());
+        let paths = entries
+            .iter()
+            .map(|e| e.inner_path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec!["README.md", "lib/inner.jar!application.properties"]
+        );
+        assert_eq!(entries[1].contents, b"key=abc_018cf028");
+    }
+
+    #[test]
+    fn depth_limit() {
+        let inner = zip_bytes(&[("secret.txt", b"abc_018cf028")]);
+        let outer = zip_bytes(&[("top.txt", b"hello"), ("inner.zip", &inner)]);
+        let config = ArchiveConfig {
+            max_depth: 1,
+            ..Default::default()
+        };
+        let entries = read_bytes("outer.zip", outer, &config);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].inner_path, "top.txt");
+    }
+
+    #[test]
+    fn size_budget() {
+        let archive = tar_gz_bytes(&[("a.txt", &[b'a'; 10]), ("b.txt", &[b'b'; 10])]);
+        let config = ArchiveConfig {
+            max_total_size: 15,
+            ..Default::default()
+        };
+        let entries = read_bytes("bundle.tar.gz", archive, &config);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].inner_path, "a.txt");
+    }
+}
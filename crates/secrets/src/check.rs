@@ -3,10 +3,12 @@
 // Copyright 2024 Datadog, Inc.
 
 use crate::check::entropy::NormalizedEntropy;
-use crate::check::simple::{AnyOf, Contains, Equals};
-use crate::rule_file::check::RawCheck;
-use crate::rule_file::StringsOrInts;
+use crate::check::simple::{AnyOf, Compare, Contains, Equals, MatchesRegex};
+use crate::rule_file::check::{RawAnyOf, RawCheck};
+use crate::rule_file::{StringsOrInts, TemplateVar};
 use secrets_core::Checker;
+use std::borrow::Cow;
+use std::cmp::Ordering;
 
 pub(crate) mod entropy;
 pub(crate) mod simple;
@@ -17,6 +19,8 @@ pub(crate) enum Check {
     AnyOf(AnyOf),
     Contains(Contains),
     Entropy(NormalizedEntropy),
+    MatchesRegex(MatchesRegex),
+    Compare(Compare),
 }
 
 impl Checker for Check {
@@ -26,25 +30,143 @@ impl Checker for Check {
             Check::AnyOf(ch) => ch.check(input),
             Check::Contains(ch) => ch.check(input),
             Check::Entropy(ch) => ch.check(input),
+            Check::MatchesRegex(ch) => ch.check(input),
+            Check::Compare(ch) => ch.check(input),
         }
     }
 }
 
-impl Check {
+/// A tree of [`Check`]s, each on the value of its own input variable.
+#[derive(Debug, Clone)]
+pub(crate) enum Condition {
+    Check { input: String, check: Check },
+    Not(Box<Condition>),
+    AllOf(Vec<Condition>),
+    AnyOf(Vec<Condition>),
+}
+
+impl Condition {
     pub(crate) fn from_raw(raw: &RawCheck) -> Self {
+        let check = |input: &TemplateVar, check: Check| Self::Check {
+            input: input.name().to_string(),
+            check,
+        };
         match raw {
-            RawCheck::Equals(raw) => Equals::new(raw.value.clone()).into(),
-            RawCheck::AnyOf(raw) => {
+            RawCheck::Equals(raw) => check(&raw.input, Equals::new(raw.value.clone()).into()),
+            RawCheck::AnyOf(RawAnyOf::Values(raw)) => {
                 let kind = match raw.values.clone() {
                     StringsOrInts::Strings(strs) => AnyOf::new(strs),
                     StringsOrInts::Integers(ints) => AnyOf::new(ints),
                 };
-                kind.into()
+                check(&raw.input, kind.into())
             }
-            RawCheck::Contains(raw) => Contains::new(&raw.substring).into(),
-            RawCheck::NormalizedEntropy(raw) => {
-                NormalizedEntropy::new(raw.over_threshold, raw.base).into()
+            RawCheck::Contains(raw) => check(&raw.input, Contains::new(&raw.substring).into()),
+            RawCheck::NormalizedEntropy(raw) => check(
+                &raw.input,
+                NormalizedEntropy::new(raw.over_threshold, raw.base).into(),
+            ),
+            RawCheck::MatchesRegex(raw) => {
+                check(&raw.input, MatchesRegex::new(raw.pattern.0.clone()).into())
+            }
+            RawCheck::GreaterThan(raw) => check(
+                &raw.input,
+                Compare::new(Ordering::Greater, raw.value).into(),
+            ),
+            RawCheck::LessThan(raw) => {
+                check(&raw.input, Compare::new(Ordering::Less, raw.value).into())
+            }
+            RawCheck::Not(raw) => Self::Not(Box::new(Self::from_raw(raw))),
+            RawCheck::AllOf(raws) => Self::AllOf(raws.iter().map(Self::from_raw).collect()),
+            RawCheck::AnyOf(RawAnyOf::Checks(raws)) => {
+                Self::AnyOf(raws.iter().map(Self::from_raw).collect())
             }
         }
     }
+
+    /// Evaluates the condition with the values of the input variables returned by `resolve`.
+    /// A check of a variable without a value is false.
+    pub(crate) fn evaluate<'a>(&self, resolve: &dyn Fn(&str) -> Option<Cow<'a, str>>) -> bool {
+        match self {
+            Condition::Check { input, check } => {
+                resolve(input).is_some_and(|value| check.check(value.as_bytes()))
+            }
+            Condition::Not(condition) => !condition.evaluate(resolve),
+            Condition::AllOf(conditions) => conditions.iter().all(|c| c.evaluate(resolve)),
+            Condition::AnyOf(conditions) => conditions.iter().any(|c| c.evaluate(resolve)),
+        }
+    }
+}
+
+/// Evaluates all the checks on the same input, for a condition whose checks share their input variable.
+impl Checker for Condition {
+    fn check(&self, input: &[u8]) -> bool {
+        match self {
+            Condition::Check { check, .. } => check.check(input),
+            Condition::Not(condition) => !condition.check(input),
+            Condition::AllOf(conditions) => conditions.iter().all(|c| c.check(input)),
+            Condition::AnyOf(conditions) => conditions.iter().any(|c| c.check(input)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::check::Condition;
+    use crate::rule_file::check::RawCheck;
+    use crate::rule_file::SingletonMap;
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+
+    fn condition(yaml: &str) -> Condition {
+        let raw = serde_yaml::from_str::<SingletonMap<RawCheck>>(yaml).unwrap();
+        Condition::from_raw(&raw)
+    }
+
+    #[test]
+    fn evaluate_combinators() {
+        let condition = condition(
+            "
+all-of:
+  - greater-than:
+      input: ${{ http.response.code }}
+      value: 199
+  - less-than:
+      input: ${{ http.response.code }}
+      value: 300
+  - not:
+      matches-regex:
+        input: ${{ http.response.body }}
+        pattern: (?i)\"scope\":\\s*\"read\"
+  - any-of:
+      - equals:
+          input: ${{ http.response.header.X-Plan }}
+          value: enterprise
+      - any-of:
+          input: ${{ http.response.header.X-Plan }}
+          values: [team, pro]
+",
+        );
+        let evaluate = |code: &str, body: &str, plan: Option<&str>| {
+            let mut variables = HashMap::from([
+                ("http.response.code", code.to_string()),
+                ("http.response.body", body.to_string()),
+            ]);
+            if let Some(plan) = plan {
+                variables.insert("http.response.header.X-Plan", plan.to_string());
+            }
+            condition.evaluate(&|var: &str| variables.get(var).cloned().map(Cow::Owned))
+        };
+        assert!(evaluate("200", r#"{"scope": "write"}"#, Some("pro")));
+        assert!(evaluate("204", r#"{"scope": "admin"}"#, Some("enterprise")));
+        assert!(!evaluate("401", r#"{"scope": "write"}"#, Some("pro")));
+        assert!(!evaluate("200", r#"{"Scope": "READ"}"#, Some("pro")));
+        assert!(!evaluate("200", r#"{"scope": "write"}"#, Some("free")));
+        // a check of a missing variable is false
+        assert!(!evaluate("200", r#"{"scope": "write"}"#, None));
+        assert!(!evaluate(
+            "not a number",
+            r#"{"scope": "write"}"#,
+            Some("pro")
+        ));
+    }
 }
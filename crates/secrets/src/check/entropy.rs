@@ -25,7 +25,7 @@ impl NormalizedEntropy {
         Self { threshold, base }
     }
 
-    fn normalized_entropy(&self, data: impl IntoIterator<Item = char>) -> f32 {
+    pub fn normalized_entropy(&self, data: impl IntoIterator<Item = char>) -> f32 {
         let entropy = shannon_entropy(data);
         (entropy / (self.base as f32).log2()).clamp(0.0, 1.0)
     }
@@ -4,6 +4,7 @@
 
 use crate::check::Check;
 use secrets_core::Checker;
+use std::cmp::Ordering;
 
 /// A [`Checker`] that interprets the input as a [`String`] and checks if it matches any of the
 /// specified input strings.
@@ -82,3 +83,57 @@ impl From<Contains> for Check {
         Self::Contains(value)
     }
 }
+
+/// A [`Checker`] that interprets the input as a [`String`] and returns true if the regex matches
+/// a part of it.
+#[derive(Debug, Clone)]
+pub(crate) struct MatchesRegex(regex::Regex);
+
+impl MatchesRegex {
+    pub fn new(regex: regex::Regex) -> Self {
+        Self(regex)
+    }
+}
+
+impl Checker for MatchesRegex {
+    fn check(&self, input: &[u8]) -> bool {
+        self.0.is_match(&String::from_utf8_lossy(input))
+    }
+}
+
+impl From<MatchesRegex> for Check {
+    fn from(value: MatchesRegex) -> Self {
+        Self::MatchesRegex(value)
+    }
+}
+
+/// A [`Checker`] that parses the input as a number and compares it to a value. An input that
+/// isn't a number fails the check.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Compare {
+    /// The expected ordering of the input relative to the value.
+    ordering: Ordering,
+    value: f64,
+}
+
+impl Compare {
+    pub fn new(ordering: Ordering, value: f64) -> Self {
+        Self { ordering, value }
+    }
+}
+
+impl Checker for Compare {
+    fn check(&self, input: &[u8]) -> bool {
+        std::str::from_utf8(input)
+            .ok()
+            .and_then(|text| text.trim().parse::<f64>().ok())
+            .and_then(|number| number.partial_cmp(&self.value))
+            .is_some_and(|ordering| ordering == self.ordering)
+    }
+}
+
+impl From<Compare> for Check {
+    fn from(value: Compare) -> Self {
+        Self::Compare(value)
+    }
+}
@@ -2,11 +2,16 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
+pub mod archive;
 mod check;
+mod markdown;
+mod parts;
 mod proximity;
 pub mod rule_file;
 pub mod scanner;
 pub use scanner::{Scanner, ScannerBuilder};
+pub mod severity;
+mod structured;
 mod validator;
 
 pub use secrets_core as core;
@@ -0,0 +1,209 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use std::path::Path;
+
+/// The format of a documentation file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Markup {
+    /// Markdown and MDX
+    Markdown,
+    AsciiDoc,
+}
+
+/// Returns the format of the file if the path has the extension of a documentation file.
+pub(crate) fn markup_format(path: &Path) -> Option<Markup> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "md" | "markdown" | "mdx" => Some(Markup::Markdown),
+        "adoc" | "asciidoc" => Some(Markup::AsciiDoc),
+        _ => None,
+    }
+}
+
+/// A delimiter that opens a block of code: a Markdown fence (```` ``` ```` or `~~~`), or, in
+/// AsciiDoc, a listing (`----`) or literal (`....`) block. In Markdown, `----` is a thematic break
+/// or the underline of a heading.
+#[derive(Debug, Copy, Clone)]
+struct Fence {
+    byte: u8,
+    len: usize,
+}
+
+impl Fence {
+    fn parse(line: &[u8], format: Markup) -> Option<Self> {
+        let line = line.trim_ascii();
+        let byte = *line.first()?;
+        let len = line.iter().take_while(|b| **b == byte).count();
+        match byte {
+            // Markdown fences can be followed by an info string (e.g. ```` ```yaml ````).
+            b'`' | b'~' if len >= 3 => Some(Self { byte, len }),
+            // AsciiDoc delimiters are alone on their line.
+            b'-' | b'.' if format == Markup::AsciiDoc && len >= 4 && len == line.len() => {
+                Some(Self { byte, len })
+            }
+            _ => None,
+        }
+    }
+
+    fn is_closed_by(&self, line: &[u8]) -> bool {
+        let line = line.trim_ascii();
+        line.len() >= self.len && line.iter().all(|b| *b == self.byte)
+    }
+}
+
+/// Blanks out everything in a documentation file that isn't code, keeping the content of fenced
+/// code blocks and inline code spans. Documentation frequently contains real tokens pasted as
+/// "examples", while its prose mostly produces false positives. As a token can also be pasted in
+/// the prose, this is only done when the scanner is configured to (see
+/// [`ScannerBuilder::markup_code_only`](crate::ScannerBuilder::markup_code_only)).
+///
+/// Masked bytes are replaced by spaces (line breaks are kept), so the byte offsets, and hence the
+/// positions of the candidates, are the same as in the original file.
+pub(crate) fn mask_prose(contents: &[u8], format: Markup) -> Vec<u8> {
+    let mut masked = Vec::with_capacity(contents.len());
+    let mut fence: Option<Fence> = None;
+
+    for line in contents.split_inclusive(|b| *b == b'\n') {
+        match fence {
+            Some(open) if open.is_closed_by(line) => {
+                fence = None;
+                mask_into(line, &mut masked);
+            }
+            Some(_) => masked.extend_from_slice(line),
+            None => {
+                if let Some(open) = Fence::parse(line, format) {
+                    fence = Some(open);
+                    mask_into(line, &mut masked);
+                } else {
+                    mask_outside_inline_code(line, &mut masked);
+                }
+            }
+        }
+    }
+    masked
+}
+
+/// Keeps the content of the inline code spans of a line (e.g. `` `token` `` or ``` ``token`` ```),
+/// and masks everything else. A span is closed by a run of backticks of the same length.
+fn mask_outside_inline_code(line: &[u8], masked: &mut Vec<u8>) {
+    let mut idx = 0;
+    while idx < line.len() {
+        if line[idx] != b'`' {
+            mask_into(&line[idx..idx + 1], masked);
+            idx += 1;
+            continue;
+        }
+        let run = backtick_run(&line[idx..]);
+        let content_start = idx + run;
+        let mut closing = None;
+        let mut search = content_start;
+        while search < line.len() {
+            if line[search] == b'`' {
+                let len = backtick_run(&line[search..]);
+                if len == run {
+                    closing = Some(search);
+                    break;
+                }
+                search += len;
+            } else {
+                search += 1;
+            }
+        }
+        match closing {
+            Some(content_end) => {
+                mask_into(&line[idx..content_start], masked);
+                masked.extend_from_slice(&line[content_start..content_end]);
+                mask_into(&line[content_end..content_end + run], masked);
+                idx = content_end + run;
+            }
+            None => {
+                // An unmatched run of backticks is literal text.
+                mask_into(&line[idx..content_start], masked);
+                idx = content_start;
+            }
+        }
+    }
+}
+
+fn backtick_run(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|b| **b == b'`').count()
+}
+
+fn mask_into(bytes: &[u8], masked: &mut Vec<u8>) {
+    masked.extend(
+        bytes
+            .iter()
+            .map(|b| if *b == b'\n' || *b == b'\r' { *b } else { b' ' }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask(contents: &str) -> String {
+        mask_as(contents, Markup::Markdown)
+    }
+
+    fn mask_as(contents: &str, format: Markup) -> String {
+        String::from_utf8(mask_prose(contents.as_bytes(), format)).unwrap()
+    }
+
+    #[test]
+    fn markup_file_detection() {
+        assert_eq!(
+            markup_format(Path::new("README.md")),
+            Some(Markup::Markdown)
+        );
+        assert_eq!(
+            markup_format(Path::new("docs/setup.MDX")),
+            Some(Markup::Markdown)
+        );
+        assert_eq!(
+            markup_format(Path::new("docs/index.adoc")),
+            Some(Markup::AsciiDoc)
+        );
+        assert_eq!(markup_format(Path::new("src/main.rs")), None);
+    }
+
+    #[test]
+    fn fenced_blocks_kept() {
+        let contents = "Set the key:\n```bash\nexport KEY=abc_018cf028\n```\nDone é\n";
+        let masked = mask(contents);
+        assert_eq!(masked.len(), contents.len());
+        let lines = masked.lines().collect::<Vec<_>>();
+        assert_eq!(lines[2], "export KEY=abc_018cf028");
+        for idx in [0, 1, 3, 4] {
+            assert!(lines[idx].trim().is_empty());
+        }
+    }
+
+    #[test]
+    fn asciidoc_blocks_kept() {
+        let contents = "[source,yaml]\n----\nkey: abc_018cf028\n----\n....\nliteral\n....\ntext";
+        let masked = mask_as(contents, Markup::AsciiDoc);
+        let lines = masked.lines().collect::<Vec<_>>();
+        assert_eq!(lines[2], "key: abc_018cf028");
+        assert_eq!(lines[5], "literal");
+        assert!(lines[0].trim().is_empty());
+        assert!(lines[7].trim().is_empty());
+    }
+
+    #[test]
+    fn inline_code_kept() {
+        let masked = mask("Use `abc_018cf028` or ``a ` b`` but not abc_123 or `unclosed");
+        assert_eq!(
+            masked,
+            format!("{:5}abc_018cf028{:7}a ` b{:31}", "", "", "")
+        );
+    }
+
+    /// In Markdown, `----` is a thematic break, not the start of a code block.
+    #[test]
+    fn markdown_thematic_break_not_code() {
+        let masked = mask("Intro\n----\ntoken abc_018cf028\n----\n");
+        assert!(masked.trim().is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use crate::rule_file::matcher::RawPart;
+use secrets_core::common::ByteSpan;
+use secrets_core::location::PointLocator;
+use secrets_core::rule::{LocatedString, RuleMatch};
+
+/// The default number of bytes around a candidate where its parts are searched.
+const DEFAULT_MAX_DISTANCE: usize = 100;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PartError {
+    #[error("invalid part name `{0}`: expecting only alphanumeric characters, `-` and `_`")]
+    InvalidName(String),
+    #[error("duplicate part name `{0}`")]
+    DuplicateName(String),
+    #[error("invalid regex for part `{name}`: {message}")]
+    Regex { name: String, message: String },
+}
+
+/// An additional part of a multi-part secret (for example, the client id that accompanies a client
+/// secret), which is searched for near each candidate of a rule.
+#[derive(Debug, Clone)]
+pub(crate) struct Part {
+    name: String,
+    regex: regex::bytes::Regex,
+    max_distance: usize,
+    required: bool,
+}
+
+impl Part {
+    pub(crate) fn from_raw(raw: &RawPart) -> Result<Self, PartError> {
+        let is_valid_name = !raw.name.is_empty()
+            && raw
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !is_valid_name {
+            return Err(PartError::InvalidName(raw.name.clone()));
+        }
+        let regex = regex::bytes::Regex::new(&raw.pattern).map_err(|err| PartError::Regex {
+            name: raw.name.clone(),
+            message: err.to_string(),
+        })?;
+        Ok(Self {
+            name: raw.name.clone(),
+            regex,
+            max_distance: raw.max_distance.unwrap_or(DEFAULT_MAX_DISTANCE),
+            required: raw.required.unwrap_or(true),
+        })
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns the part closest to the candidate, among the matches of the part's pattern that are
+    /// entirely within `max_distance` bytes before or after it. The value of a part is the first
+    /// capture group of its pattern, or the entire match if it has no group.
+    fn find(&self, locator: &PointLocator, candidate: ByteSpan) -> Option<LocatedString> {
+        let data = locator.data();
+        let start = candidate.start_index as usize;
+        let end = candidate.end_index as usize;
+        let window_start = start.saturating_sub(self.max_distance);
+        let window_end = end.saturating_add(self.max_distance).min(data.len());
+
+        // (distance to the candidate, span of the value)
+        let before = self
+            .regex
+            .captures_iter(&data[window_start..start])
+            .last()
+            .and_then(|captures| {
+                let entire = captures.get(0)?;
+                let value = captures.get(1).unwrap_or(entire);
+                let span = ByteSpan::new(window_start + value.start(), window_start + value.end());
+                Some((start - (window_start + entire.end()), span))
+            });
+        let after = self
+            .regex
+            .captures(&data[end..window_end])
+            .and_then(|captures| {
+                let entire = captures.get(0)?;
+                let value = captures.get(1).unwrap_or(entire);
+                let span = ByteSpan::new(end + value.start(), end + value.end());
+                Some((entire.start(), span))
+            });
+        let (_, span) = match (before, after) {
+            (Some(before), Some(after)) => {
+                if after.0 < before.0 {
+                    after
+                } else {
+                    before
+                }
+            }
+            (Some(found), None) | (None, Some(found)) => found,
+            (None, None) => return None,
+        };
+        LocatedString::from_locator(locator, span).ok()
+    }
+}
+
+/// Adds the parts found near a candidate to the captures of its [`RuleMatch`], under their name, so
+/// that validators can use them as `candidate.captures.<NAME>`.
+///
+/// Returns false if a required part wasn't found, in which case the candidate should be dropped.
+pub(crate) fn add_parts_mut(
+    parts: &[Part],
+    locator: &PointLocator,
+    rule_match: &mut RuleMatch,
+) -> bool {
+    for part in parts {
+        match part.find(locator, rule_match.matched.byte_span) {
+            Some(located) => {
+                rule_match.captures.insert(part.name.clone(), located);
+            }
+            None if part.required => return false,
+            None => {}
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parts::{add_parts_mut, Part, PartError};
+    use crate::rule_file::matcher::RawPart;
+    use secrets_core::common::ByteSpan;
+    use secrets_core::location::PointLocator;
+    use secrets_core::rule::{LocatedString, RuleMatch};
+    use std::collections::HashMap;
+
+    fn part(name: &str, pattern: &str, max_distance: usize, required: bool) -> Part {
+        Part::from_raw(&RawPart {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            max_distance: Some(max_distance),
+            required: Some(required),
+        })
+        .unwrap()
+    }
+
+    fn rule_match(locator: &PointLocator, matched: &str) -> RuleMatch {
+        let data = std::str::from_utf8(locator.data()).unwrap();
+        let start = data.find(matched).unwrap();
+        let span = ByteSpan::new(start, start + matched.len());
+        RuleMatch {
+            rule_id: "rule-id".into(),
+            matched: LocatedString::from_locator(locator, span).unwrap(),
+            captures: HashMap::new(),
+        }
+    }
+
+    /// The closest match of a part is added to the captures, using its first capture group.
+    #[test]
+    fn closest_part_added() {
+        let data = "client_id=aaaa\nclient_id=bbbb\nclient_secret=s3cr3t\nclient_id=cccc";
+        let locator = PointLocator::new(data.as_bytes());
+        let mut rm = rule_match(&locator, "s3cr3t");
+
+        let parts = [part("client_id", r"client_id=([a-z]{4})", 40, true)];
+        assert!(add_parts_mut(&parts, &locator, &mut rm));
+        let client_id = &rm.captures["client_id"];
+        assert_eq!(client_id.as_str(), "cccc");
+        assert_eq!(client_id.point_span.start().line.get(), 4);
+    }
+
+    /// Parts are only searched within their max distance, and a missing required part rejects the candidate.
+    #[test]
+    fn part_max_distance() {
+        let data = "client_id=aaaa -------------------- client_secret=s3cr3t";
+        let locator = PointLocator::new(data.as_bytes());
+
+        let mut rm = rule_match(&locator, "s3cr3t");
+        let parts = [part("client_id", r"client_id=([a-z]{4})", 20, true)];
+        assert!(!add_parts_mut(&parts, &locator, &mut rm));
+
+        let mut rm = rule_match(&locator, "s3cr3t");
+        let parts = [part("client_id", r"client_id=([a-z]{4})", 20, false)];
+        assert!(add_parts_mut(&parts, &locator, &mut rm));
+        assert!(rm.captures.is_empty());
+
+        let mut rm = rule_match(&locator, "s3cr3t");
+        let parts = [part("client_id", r"client_id=([a-z]{4})", 60, true)];
+        assert!(add_parts_mut(&parts, &locator, &mut rm));
+        assert_eq!(rm.captures["client_id"].as_str(), "aaaa");
+    }
+
+    #[test]
+    fn invalid_parts() {
+        let raw = |name: &str, pattern: &str| RawPart {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            max_distance: None,
+            required: None,
+        };
+        assert!(matches!(
+            Part::from_raw(&raw("steps.a", "abc")),
+            Err(PartError::InvalidName(_))
+        ));
+        assert!(matches!(
+            Part::from_raw(&raw("client-id", "(abc")),
+            Err(PartError::Regex { .. })
+        ));
+    }
+}
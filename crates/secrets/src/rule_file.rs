@@ -8,6 +8,7 @@ pub(crate) use common::*;
 mod file;
 pub use file::*;
 pub mod matcher;
+pub mod severity;
 mod template;
 pub use template::*;
 pub mod validator;
@@ -3,6 +3,7 @@
 // Copyright 2024 Datadog, Inc.
 
 use crate::rule_file::{raw_item, StringOrInt, StringsOrInts, TemplateVar};
+use serde::{Deserialize, Deserializer};
 
 raw_item! {
     /// A check and its configuration.
@@ -11,6 +12,13 @@ raw_item! {
         AnyOf(RawAnyOf),
         Contains(RawContains),
         NormalizedEntropy(RawNormalizedEntropy),
+        MatchesRegex(RawMatchesRegex),
+        GreaterThan(RawCompare),
+        LessThan(RawCompare),
+        /// True if the check is false.
+        Not(Box<RawCheck>),
+        /// True if all the checks are true.
+        AllOf(Vec<RawCheck>),
     }
 
     /// The configuration for check `equals`
@@ -21,8 +29,8 @@ raw_item! {
         pub value: StringOrInt,
     }
 
-    /// The configuration for check `any_of`
-    pub struct RawAnyOf {
+    /// The configuration for check `any-of` with a list of values
+    pub struct RawAnyOfValues {
         /// The variable to check for equality
         pub input: TemplateVar,
         /// A list of either string or integer values to check against
@@ -46,16 +54,143 @@ raw_item! {
         /// The number of possible characters, used to normalize the entropy calculation.
         pub base: Option<u8>,
     }
+
+    /// The configuration for check `matches-regex`
+    pub struct RawMatchesRegex {
+        /// The string variable to check
+        pub input: TemplateVar,
+        /// The regex to search for in the input
+        pub pattern: RawRegex,
+    }
+
+    /// The configuration for checks `greater-than` and `less-than`
+    pub struct RawCompare {
+        /// The variable to parse as a number (e.g. `${{ http.response.code }}`)
+        pub input: TemplateVar,
+        /// The number to compare the input to
+        pub value: f64,
+    }
+}
+
+/// The configuration for check `any-of`: either a list of values that the input must be equal to one of,
+/// or a list of checks that must be true for one of them.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+pub enum RawAnyOf {
+    Values(RawAnyOfValues),
+    Checks(Vec<RawCheck>),
+}
+
+/// A regex, which is validated when it's deserialized.
+#[derive(Debug, Clone)]
+pub struct RawRegex(pub regex::Regex);
+
+impl<'de> Deserialize<'de> for RawRegex {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        regex::Regex::new(&pattern)
+            .map(Self)
+            .map_err(serde::de::Error::custom)
+    }
 }
 
 impl RawCheck {
-    /// Returns the name of the input variable for this check.
-    pub fn input_variable(&self) -> &str {
+    /// Returns the name of the input variable for this check, or `None` if it combines checks
+    /// with different input variables.
+    pub fn input_variable(&self) -> Option<&str> {
+        let mut variables = Vec::new();
+        self.collect_input_variables(&mut variables);
+        let first = *variables.first()?;
+        variables.iter().all(|var| *var == first).then_some(first)
+    }
+
+    fn collect_input_variables<'a>(&'a self, variables: &mut Vec<&'a str>) {
         match self {
-            RawCheck::Equals(raw) => raw.input.name(),
-            RawCheck::AnyOf(raw) => raw.input.name(),
-            RawCheck::Contains(raw) => raw.input.name(),
-            RawCheck::NormalizedEntropy(raw) => raw.input.name(),
+            RawCheck::Equals(raw) => variables.push(raw.input.name()),
+            RawCheck::AnyOf(RawAnyOf::Values(raw)) => variables.push(raw.input.name()),
+            RawCheck::Contains(raw) => variables.push(raw.input.name()),
+            RawCheck::NormalizedEntropy(raw) => variables.push(raw.input.name()),
+            RawCheck::MatchesRegex(raw) => variables.push(raw.input.name()),
+            RawCheck::GreaterThan(raw) | RawCheck::LessThan(raw) => {
+                variables.push(raw.input.name())
+            }
+            RawCheck::Not(raw) => raw.collect_input_variables(variables),
+            RawCheck::AllOf(raws) | RawCheck::AnyOf(RawAnyOf::Checks(raws)) => {
+                for raw in raws {
+                    raw.collect_input_variables(variables);
+                }
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::rule_file::check::{RawAnyOf, RawCheck};
+    use crate::rule_file::SingletonMap;
+
+    fn parse(yaml: &str) -> Result<RawCheck, serde_yaml::Error> {
+        serde_yaml::from_str::<SingletonMap<RawCheck>>(yaml).map(SingletonMap::into_inner)
+    }
+
+    #[test]
+    fn parse_any_of() {
+        let values = parse(
+            "
+any-of:
+  input: ${{ http.response.code }}
+  values: [200, 201]
+",
+        )
+        .unwrap();
+        assert!(matches!(values, RawCheck::AnyOf(RawAnyOf::Values(_))));
+        let checks = parse(
+            "
+any-of:
+  - equals:
+      input: ${{ http.response.code }}
+      value: 200
+  - not:
+      contains:
+        input: ${{ http.response.body }}
+        substring: error
+",
+        )
+        .unwrap();
+        assert!(matches!(checks, RawCheck::AnyOf(RawAnyOf::Checks(c)) if c.len() == 2));
+        assert_eq!(checks.input_variable(), None);
+    }
+
+    #[test]
+    fn input_variable() {
+        let check = parse(
+            "
+all-of:
+  - greater-than:
+      input: ${{ candidate }}
+      value: 10
+  - not:
+      matches-regex:
+        input: ${{ candidate }}
+        pattern: ^test_
+",
+        )
+        .unwrap();
+        assert_eq!(check.input_variable(), Some("candidate"));
+    }
+
+    #[test]
+    fn invalid_regex() {
+        let check = parse(
+            "
+matches-regex:
+  input: ${{ candidate }}
+  pattern: (unclosed
+",
+        );
+        assert!(check.is_err());
+    }
+}
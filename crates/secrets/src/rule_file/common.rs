@@ -130,9 +130,23 @@ pub fn parse_candidate_variable(var: &str) -> Option<CandidateVariable> {
     None
 }
 
+/// The prefix of the template variables that hold the outputs of the previous steps of a request chain.
+pub(crate) const STEP_VARIABLE_PREFIX: &str = "steps.";
+
+/// Returns the name of the template variable that holds a value extracted by a step of a request chain.
+pub(crate) fn step_variable(step_id: &str, name: &str) -> String {
+    format!("{STEP_VARIABLE_PREFIX}{step_id}.extracted.{name}")
+}
+
 /// Given a [`Candidate`], constructs a variable provider used to evaluate a [`TemplateString`](crate::rule_file::TemplateString).
+///
+/// The outputs of the previous steps of a request chain are stored in the candidate's captures,
+/// under their variable name (which can't collide with the name of a capture, as it contains a `.`).
 pub fn make_candidate_provider(candidate: &Candidate) -> Box<DynFnVariableProvider<'_>> {
     Box::new(|var: &str| {
+        if var.starts_with(STEP_VARIABLE_PREFIX) {
+            return candidate.rule_match.captures.get(var).map(|ls| ls.as_str());
+        }
         parse_candidate_variable(var).and_then(|candidate_var| match candidate_var {
             CandidateVariable::Entire => Some(candidate.rule_match.matched.as_str()),
             CandidateVariable::Capture(name) => candidate
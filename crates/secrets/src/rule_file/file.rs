@@ -3,6 +3,7 @@
 // Copyright 2024 Datadog, Inc.
 
 use crate::rule_file::matcher::RawMatcher;
+use crate::rule_file::severity::RawSeverityRule;
 use crate::rule_file::validator::RawValidator;
 use crate::rule_file::{raw_item, SingletonMap};
 use std::collections::BTreeMap;
@@ -20,9 +21,21 @@ raw_item! {
         pub id: String,
         pub description: Option<String>,
         pub short_description: Option<String>,
+        /// How to remediate a finding of the rule (e.g. how to revoke and rotate the secret).
+        pub remediation: Option<String>,
+        // File selection
+        /// If true, the rule also scans files that are excluded from analysis by default
+        /// (for example, files with an unrecognized extension like `.pem` or `.env`, or files over the size limit).
+        pub include_ineligible_files: Option<bool>,
+        /// Glob patterns of the paths where the findings of the rule are ignored. Ignored findings
+        /// are still returned, so that they can be reported as suppressed.
+        pub ignore_paths: Option<Vec<String>>,
         // Rule logic
         pub matcher: SingletonMap<RawMatcher>,
         pub validator: SingletonMap<RawValidator>,
+        /// The rules that set the severity of a finding, the first one that applies taking
+        /// precedence. Without any, the severity is the one of the validation.
+        pub severity_rules: Option<Vec<RawSeverityRule>>,
     }
 }
 
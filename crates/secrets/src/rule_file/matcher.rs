@@ -17,6 +17,8 @@ raw_item! {
         pub pattern: String,
         pub proximity: Option<RawProximity>,
         pub checks: Option<Vec<RawCheck>>,
+        /// The other parts of a multi-part secret, searched for near each candidate.
+        pub parts: Option<Vec<RawPart>>,
     }
 
     /// The proximity keywords configuration for matcher `hyperscan`
@@ -24,4 +26,15 @@ raw_item! {
         pub keywords: Vec<String>,
         pub max_distance: Option<usize>,
     }
+
+    /// An additional part of a multi-part secret for matcher `hyperscan` (e.g. the client id of a client secret)
+    pub struct RawPart {
+        /// The name of the capture the part is added to.
+        pub name: String,
+        pub pattern: String,
+        /// The part must be entirely within this number of bytes before or after the candidate.
+        pub max_distance: Option<usize>,
+        /// If true (the default), candidates without this part are dropped.
+        pub required: Option<bool>,
+    }
 }
@@ -0,0 +1,46 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use crate::rule_file::{raw_item, RawSeverity};
+
+raw_item! {
+    /// A rule that sets the severity of a finding from its validation and its context.
+    pub struct RawSeverityRule {
+        /// The conditions, which must all be true for the rule to apply.
+        pub when: RawSeverityConditions,
+        pub severity: RawSeverity,
+    }
+
+    /// The conditions of a [`RawSeverityRule`]. An absent condition is always true.
+    pub struct RawSeverityConditions {
+        /// The validation status of the finding must be one of these.
+        pub status: Option<Vec<RawFindingStatus>>,
+        /// The class of the path of the file of the finding.
+        pub path: Option<RawPathClass>,
+        /// The normalized entropy of the secret (between 0 and 1) must be at least this value.
+        pub min_entropy: Option<f32>,
+        /// Whether a proximity keyword of the rule is near the secret.
+        pub keyword: Option<bool>,
+    }
+}
+
+/// The validation status of a finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RawFindingStatus {
+    Valid,
+    Invalid,
+    Inconclusive,
+    Unvalidated,
+}
+
+/// The class of the path of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RawPathClass {
+    /// Tests, fixtures and mocks.
+    Test,
+    /// Any other file.
+    Production,
+}
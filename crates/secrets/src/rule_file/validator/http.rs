@@ -15,6 +15,8 @@ raw_item! {
 pub enum RawExtension {
     #[serde(rename = "simple-request")]
     Simple(RawCfgSimpleRequest),
+    #[serde(rename = "request-chain")]
+    Chain(RawCfgRequestChain),
 }
 
 // Simple HTTP Request
@@ -41,10 +43,21 @@ raw_item! {
     pub struct RawHeaders(pub BTreeMap<String, TemplateString>);
 
     pub struct RawResponseHandler {
+        /// The values to read from the body of the response, available to the handlers as the
+        /// template variables `extracted.<name>`.
+        pub extract: Option<BTreeMap<String, SingletonMap<RawExtractor>>>,
         pub handler_list: Vec<RawHandler>,
         pub default_result: RawActionReturn,
     }
 
+    /// A value to read from the body of a response.
+    pub enum RawExtractor {
+        /// A JSONPath that selects a single value (e.g. `$.account.scopes[0]`).
+        JsonPath(String),
+        /// A regex whose first capture group (or entire match, if it has no group) is the value.
+        Regex(String),
+    }
+
     pub struct RawHandler {
         pub on_match: SingletonMap<RawCheck>,
         pub action: SingletonMap<RawAction>,
@@ -63,6 +76,33 @@ raw_item! {
     }
 }
 
+// HTTP Request Chain
+////////////////////////////////////////
+
+raw_item! {
+    /// A sequence of HTTP requests, where the values extracted from the response of a step are
+    /// available to the following steps as the template variables `steps.<step-id>.extracted.<name>`.
+    pub struct RawCfgRequestChain {
+        pub steps: Vec<RawStep>,
+        /// The result when the last step completes without a result, or when the error response of
+        /// a step isn't handled.
+        pub default_result: RawActionReturn,
+    }
+
+    /// A request of a chain. If none of its handlers match a successful response, the chain
+    /// continues with the next step.
+    pub struct RawStep {
+        pub id: String,
+        pub request: RawRequest,
+        pub response_handler: RawStepResponseHandler,
+    }
+
+    pub struct RawStepResponseHandler {
+        pub extract: Option<BTreeMap<String, SingletonMap<RawExtractor>>>,
+        pub handler_list: Vec<RawHandler>,
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, serde::Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum RawMethod {
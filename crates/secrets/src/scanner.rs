@@ -2,7 +2,10 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
-use crate::check::Check;
+use crate::archive::{is_archive, read_archive, ArchiveConfig, ARCHIVE_PATH_SEPARATOR};
+use crate::check::Condition;
+use crate::markdown::{markup_format, mask_prose};
+use crate::parts::{add_parts_mut, Part, PartError};
 use crate::proximity::{build_proximity_pattern, restore_rule_match_mut, PROXIMITY_MAGIC};
 use crate::rule_file::matcher::RawMatcher;
 use crate::rule_file::validator::http::RawExtension;
@@ -10,24 +13,40 @@ use crate::rule_file::validator::RawValidator;
 use crate::rule_file::{
     parse_candidate_variable, CandidateVariable, RawMultiRuleFile, RawRuleFile,
 };
+use crate::severity::SeverityRules;
+use crate::structured::{parse_entries, structured_format, Entry};
+use crate::validator::extract::ExtractorError;
 use crate::validator::http;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use secrets_core::engine::{Engine, EngineBuilder, ValidationResult};
+use secrets_core::location::PointLocator;
 use secrets_core::matcher::hyperscan::HyperscanBuilder;
 use secrets_core::matcher::{MatcherId, PatternId};
 use secrets_core::rule::{RuleId, TargetedChecker};
-use secrets_core::validator::http::RetryConfig;
+use secrets_core::ureq;
+use secrets_core::validator::http::{HttpClientConfig, RetryConfig, SharedLimits};
 use secrets_core::validator::{Candidate, ValidatorId};
 use secrets_core::{Matcher, Rule, Validator};
+use std::borrow::Cow;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::{fs, io};
 
+/// The maximum number of bytes between a proximity keyword and a candidate, by default.
+const DEFAULT_MAX_DISTANCE: usize = 40;
+
 #[derive(Debug, thiserror::Error)]
 pub enum ScannerError {
     #[error("engine error: {message}")]
     Engine { message: String },
+    #[error("archive error: {message}")]
+    Archive { message: String },
+    #[error("the scan was cancelled")]
+    Cancelled,
     #[error(transparent)]
     Io(#[from] io::Error),
 }
@@ -35,6 +54,14 @@ pub enum ScannerError {
 pub struct Scanner {
     rule_map: HashMap<String, RuleInfo>,
     engine: Engine,
+    /// The structured variants of the rules, by the id of the variant.
+    structured_rules: HashMap<RuleId, StructuredRule>,
+    /// The parts of the multi-part secrets, by the id of their rule.
+    parts: HashMap<RuleId, Vec<Part>>,
+    archives: Option<ArchiveConfig>,
+    cancellation_flag: Option<Arc<AtomicUsize>>,
+    /// If true, only the code blocks and inline code of documentation files are scanned.
+    markup_code_only: bool,
 }
 
 impl Scanner {
@@ -42,18 +69,130 @@ impl Scanner {
         if self.rule_count() == 0 {
             return Ok(vec![]);
         }
+        self.check_cancellation()?;
+        if let Some(config) = &self.archives {
+            if is_archive(file_path) {
+                return self.scan_archive(file_path, config);
+            }
+        }
         let file_contents = fs::read(file_path).map_err(ScannerError::Io)?;
-        self.engine
-            .scan(file_path, &file_contents)
-            .map(|mut candidates| {
-                for candidate in candidates.iter_mut() {
-                    restore_rule_match_mut(&mut candidate.rule_match);
-                }
-                candidates
-            })
-            .map_err(|err| ScannerError::Engine {
-                message: err.to_string(),
+        self.scan_contents(file_path, &file_contents)
+    }
+
+    /// Scans a file that is excluded from analysis by default, only reporting the candidates from
+    /// rules that opted into scanning these files.
+    pub fn scan_ineligible_file(&self, file_path: &Path) -> Result<Vec<Candidate>, ScannerError> {
+        if !self
+            .rule_map
+            .values()
+            .any(|info| info.include_ineligible_files)
+        {
+            return Ok(vec![]);
+        }
+        let candidates = self.scan_file(file_path)?;
+        Ok(candidates
+            .into_iter()
+            .filter(|candidate| {
+                self.rule(candidate.rule_match.rule_id.as_str())
+                    .is_some_and(|info| info.include_ineligible_files)
             })
+            .collect())
+    }
+
+    /// Scans the text files contained in an archive. The source of each candidate is a virtual path
+    /// made of the path of the archive and the path within it (e.g. `lib/app.jar!config.properties`).
+    fn scan_archive(
+        &self,
+        file_path: &Path,
+        config: &ArchiveConfig,
+    ) -> Result<Vec<Candidate>, ScannerError> {
+        let entries = read_archive(file_path, config).map_err(|err| ScannerError::Archive {
+            message: format!("{}: {}", file_path.display(), err),
+        })?;
+        let mut candidates = Vec::new();
+        for entry in entries {
+            self.check_cancellation()?;
+            let mut virtual_path = file_path.as_os_str().to_os_string();
+            virtual_path.push(ARCHIVE_PATH_SEPARATOR.to_string());
+            virtual_path.push(&entry.inner_path);
+            candidates.extend(self.scan_contents(&PathBuf::from(virtual_path), &entry.contents)?);
+        }
+        Ok(candidates)
+    }
+
+    /// Scans the contents of a file. If the scanner only scans the code of documentation files
+    /// (e.g. Markdown), their prose is skipped.
+    pub fn scan_contents(
+        &self,
+        file_path: &Path,
+        file_contents: &[u8],
+    ) -> Result<Vec<Candidate>, ScannerError> {
+        let format = markup_format(file_path).filter(|_| self.markup_code_only);
+        let file_contents = match format {
+            Some(format) => Cow::Owned(mask_prose(file_contents, format)),
+            None => Cow::Borrowed(file_contents),
+        };
+        let mut candidates =
+            self.engine
+                .scan(file_path, &file_contents)
+                .map_err(|err| ScannerError::Engine {
+                    message: err.to_string(),
+                })?;
+        for candidate in candidates.iter_mut() {
+            restore_rule_match_mut(&mut candidate.rule_match);
+        }
+        if !self.structured_rules.is_empty() {
+            let entries = structured_format(file_path)
+                .map(|format| parse_entries(format, &file_contents))
+                .unwrap_or_default();
+            candidates = self.resolve_structured_candidates(candidates, &entries);
+        }
+        if !self.parts.is_empty() {
+            let locator = PointLocator::new(&file_contents);
+            candidates.retain_mut(|candidate| {
+                self.parts
+                    .get(&candidate.rule_match.rule_id)
+                    .map_or(true, |parts| {
+                        add_parts_mut(parts, &locator, &mut candidate.rule_match)
+                    })
+            });
+        }
+        Ok(candidates)
+    }
+
+    /// Attributes the candidates of the structured variants of the rules to their rule when they
+    /// are the value of a key that contains one of the rule's proximity keywords (for example, the
+    /// value of `aws.secret_access_key` for the keyword `aws`), and drops the others. A candidate
+    /// that was also found by the rule itself is only returned once.
+    fn resolve_structured_candidates(
+        &self,
+        candidates: Vec<Candidate>,
+        entries: &[Entry],
+    ) -> Vec<Candidate> {
+        let (structured, mut resolved): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|c| self.structured_rules.contains_key(&c.rule_match.rule_id));
+        let mut found = resolved
+            .iter()
+            .map(|c| (c.rule_match.rule_id.clone(), c.rule_match.matched.byte_span))
+            .collect::<HashSet<_>>();
+        for mut candidate in structured {
+            let rule = &self.structured_rules[&candidate.rule_match.rule_id];
+            let span = candidate.rule_match.matched.byte_span;
+            let is_keyed_value = entries.iter().any(|entry| {
+                entry.value.start <= span.start_index as usize
+                    && span.end_index as usize <= entry.value.end
+                    && rule.matches_key(&entry.key_path)
+            });
+            if !is_keyed_value {
+                continue;
+            }
+            candidate.rule_match.rule_id = rule.rule_id.clone();
+            if found.insert((rule.rule_id.clone(), span)) {
+                resolved.push(candidate);
+            }
+        }
+        resolved
     }
 
     pub fn validate_candidate(
@@ -81,6 +220,14 @@ impl Scanner {
     pub fn rule_count(&self) -> usize {
         self.rule_map.len()
     }
+
+    /// Returns an error if the scan was cancelled with the cancellation flag of the scanner.
+    fn check_cancellation(&self) -> Result<(), ScannerError> {
+        match &self.cancellation_flag {
+            Some(flag) if flag.load(Ordering::Relaxed) != 0 => Err(ScannerError::Cancelled),
+            _ => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -93,6 +240,8 @@ pub enum ScannerBuilderError {
     InvalidYamlSyntax { message: String },
     #[error("{0}")]
     CompilationError(String),
+    #[error("invalid HTTP proxy: {0}")]
+    InvalidProxy(String),
     #[error(transparent)]
     Io(#[from] io::Error),
 }
@@ -116,12 +265,24 @@ pub struct ScannerBuilder {
     // ---
     // Validator-specific configuration
     http_retry: RetryConfig,
+    http_client: HttpClientConfig,
+    // ---
+    // Scan configuration
+    archives: Option<ArchiveConfig>,
+    cancellation_flag: Option<Arc<AtomicUsize>>,
+    structured_content: bool,
+    markup_code_only: bool,
     // ---
     // Built items
     hs_builder: HyperscanBuilder,
+    /// The agent shared by all the HTTP validators (and their connection pool).
+    http_agent: Option<ureq::Agent>,
+    http_limits: Option<Arc<SharedLimits>>,
     built_validators: Vec<Box<dyn Validator + Send + Sync>>,
     built_rules: Vec<Rule>,
     rule_infos: Vec<RuleInfo>,
+    structured_rules: HashMap<RuleId, StructuredRule>,
+    parts: HashMap<RuleId, Vec<Part>>,
 }
 
 impl ScannerBuilder {
@@ -133,10 +294,19 @@ impl ScannerBuilder {
             rule_sources: Vec::new(),
             rule_mapping: HashMap::new(),
             http_retry: RetryConfig::default(),
+            http_client: HttpClientConfig::default(),
+            archives: None,
+            cancellation_flag: None,
+            structured_content: false,
+            markup_code_only: false,
             hs_builder: HyperscanBuilder::new(matcher_id),
+            http_agent: None,
+            http_limits: None,
             built_validators: Vec::new(),
             built_rules: Vec::new(),
             rule_infos: Vec::new(),
+            structured_rules: HashMap::new(),
+            parts: HashMap::new(),
         }
     }
 
@@ -167,7 +337,50 @@ impl ScannerBuilder {
         self
     }
 
+    /// Configures the HTTP client shared by all [`HttpValidator`](http::HttpValidator): its proxy, and the
+    /// limits on the requests across all the validators.
+    pub fn http_client(mut self, config: HttpClientConfig) -> Self {
+        self.http_client = config;
+        self
+    }
+
+    /// Enables scanning the text files contained in archives (zip, jar, tar, tar.gz), within the given limits.
+    pub fn archives(mut self, config: ArchiveConfig) -> Self {
+        self.archives = Some(config);
+        self
+    }
+
+    /// Sets a flag that cancels the scans of the scanner when it is set to a non-zero value (the
+    /// convention of tree-sitter's cancellation flag). A cancelled scan returns [`ScannerError::Cancelled`]
+    /// before scanning the next file or archive entry.
+    pub fn cancellation_flag(mut self, flag: Arc<AtomicUsize>) -> Self {
+        self.cancellation_flag = Some(flag);
+        self
+    }
+
+    /// Enables the structured-content mode: in JSON, YAML and `.env` files, the values of the keys
+    /// that contain a proximity keyword of a rule are matched by the rule's pattern, wherever the
+    /// keyword is in the file (e.g. in a parent key of a nested value).
+    pub fn structured_content(mut self, enabled: bool) -> Self {
+        self.structured_content = enabled;
+        self
+    }
+
+    /// Only scans the code blocks and inline code of documentation files (Markdown, MDX and AsciiDoc),
+    /// skipping their prose. By default, documentation files are scanned entirely.
+    pub fn markup_code_only(mut self, enabled: bool) -> Self {
+        self.markup_code_only = enabled;
+        self
+    }
+
     pub fn try_build(mut self) -> Result<Scanner, ScannerBuilderError> {
+        let agent = self
+            .http_client
+            .build_agent()
+            .map_err(|err| ScannerBuilderError::InvalidProxy(err.to_string()))?;
+        self.http_agent = Some(agent);
+        self.http_limits = self.http_client.build_limits();
+
         let rule_sources = std::mem::take(&mut self.rule_sources);
         for rule_source in rule_sources {
             let raw_rules = Self::extract_raw_rules(rule_source)?;
@@ -189,7 +402,15 @@ impl ScannerBuilder {
             .into_iter()
             .map(|info| (info.rule_id.clone(), info))
             .collect::<HashMap<_, _>>();
-        Ok(Scanner { engine, rule_map })
+        Ok(Scanner {
+            engine,
+            rule_map,
+            structured_rules: self.structured_rules,
+            parts: self.parts,
+            archives: self.archives,
+            cancellation_flag: self.cancellation_flag,
+            markup_code_only: self.markup_code_only,
+        })
     }
 
     fn extract_raw_rules(rule_source: RuleSource) -> Result<Vec<RawRuleFile>, ScannerBuilderError> {
@@ -247,18 +468,57 @@ impl ScannerBuilder {
             Entry::Vacant(entry) => entry,
         };
 
+        let ignore_paths = match &raw_rule.ignore_paths {
+            Some(patterns) => {
+                let mut builder = GlobSetBuilder::new();
+                for pattern in patterns {
+                    let glob = Glob::new(pattern).map_err(|err| {
+                        ScannerBuilderError::RuleCompilationError {
+                            rule: rule_id.to_string(),
+                            message: err.to_string(),
+                        }
+                    })?;
+                    builder.add(glob);
+                }
+                let globs =
+                    builder
+                        .build()
+                        .map_err(|err| ScannerBuilderError::RuleCompilationError {
+                            rule: rule_id.to_string(),
+                            message: err.to_string(),
+                        })?;
+                Some(globs)
+            }
+            None => None,
+        };
+
+        let severity_rules = {
+            let RawMatcher::Hyperscan(raw) = raw_rule.matcher.deref();
+            let proximity = raw.proximity.as_ref();
+            SeverityRules::new(
+                raw_rule.severity_rules.clone().unwrap_or_default(),
+                proximity.into_iter().flat_map(|p| &p.keywords),
+                proximity
+                    .and_then(|p| p.max_distance)
+                    .unwrap_or(DEFAULT_MAX_DISTANCE),
+            )
+        };
         self.rule_infos.push(RuleInfo {
             rule_id: rule_id.to_string(),
             description: raw_rule.description.unwrap_or_default(),
             short_description: raw_rule.short_description.unwrap_or_default(),
+            remediation: raw_rule.remediation.unwrap_or_default(),
+            include_ineligible_files: raw_rule.include_ineligible_files.unwrap_or(false),
+            ignore_paths,
+            severity_rules,
         });
 
         let mut checks = Vec::new();
+        let mut structured_variant = None;
         let pattern_id = match raw_rule.matcher.deref() {
             RawMatcher::Hyperscan(raw) => {
                 // Transform the user's regex, if needed
                 let (pattern, transformation) = if let Some(proximity) = &raw.proximity {
-                    const DEFAULT_MAX_DISTANCE: usize = 40;
                     let max_distance = proximity.max_distance.unwrap_or(DEFAULT_MAX_DISTANCE);
                     let proximity_keywords = proximity.keywords.iter().map(String::as_str);
 
@@ -281,15 +541,33 @@ impl ScannerBuilder {
                 })?;
                 entry.insert(pattern_id);
 
+                let mut parts = Vec::new();
+                for raw_part in raw.parts.iter().flatten() {
+                    let part = Part::from_raw(raw_part).map_err(|err| {
+                        ScannerBuilderError::RuleCompilationError {
+                            rule: rule_id.to_string(),
+                            message: err.to_string(),
+                        }
+                    })?;
+                    if parts.iter().any(|p: &Part| p.name() == part.name()) {
+                        return Err(ScannerBuilderError::RuleCompilationError {
+                            rule: rule_id.to_string(),
+                            message: PartError::DuplicateName(part.name().to_string()).to_string(),
+                        });
+                    }
+                    parts.push(part);
+                }
+
                 // Convert the user input into a formatted `PatternCheck`
                 if let Some(raw_checks) = &raw.checks {
                     for raw_check in raw_checks {
-                        let check = Check::from_raw(raw_check);
-                        let pattern_checker = match parse_candidate_variable(
-                            raw_check.input_variable(),
-                        ) {
+                        let check = Condition::from_raw(raw_check);
+                        let Some(input_variable) = raw_check.input_variable() else {
+                            return Err(ScannerBuilderError::RuleCompilationError { rule: rule_id.to_string(), message: "the checks combined by `not`, `all-of` and `any-of` must have the same input variable".to_string() });
+                        };
+                        let pattern_checker = match parse_candidate_variable(input_variable) {
                             None => {
-                                return Err(ScannerBuilderError::RuleCompilationError { rule: rule_id.to_string(), message: format!("`{}` is not a valid variable: expecting either \"candidate\" or a capture name prepended by \"candidate.captures.\"", raw_check.input_variable()) });
+                                return Err(ScannerBuilderError::RuleCompilationError { rule: rule_id.to_string(), message: format!("`{}` is not a valid variable: expecting either \"candidate\" or a capture name prepended by \"candidate.captures.\"", input_variable) });
                             }
                             Some(CandidateVariable::Entire) => {
                                 match transformation {
@@ -303,43 +581,170 @@ impl ScannerBuilder {
                                 }
                             }
                             Some(CandidateVariable::Capture(name)) => {
+                                // The parts are only added to the candidates after the checks are run.
+                                if parts.iter().any(|part| part.name() == name) {
+                                    return Err(ScannerBuilderError::RuleCompilationError { rule: rule_id.to_string(), message: format!("`{}` is a part of the secret, which can't be checked by the matcher", input_variable) });
+                                }
                                 TargetedChecker::named_capture(name, check)
                             }
                         };
                         checks.push(pattern_checker);
                     }
                 }
+
+                // In structured-content mode, a rule with proximity keywords also matches its
+                // unmodified pattern, which is only kept in the values of keys with a keyword.
+                let keywords = raw
+                    .proximity
+                    .iter()
+                    .filter(|_| self.structured_content)
+                    .flat_map(|proximity| &proximity.keywords)
+                    .map(|keyword| keyword.trim().to_ascii_lowercase())
+                    .filter(|keyword| !keyword.is_empty())
+                    .collect::<Vec<_>>();
+                if !keywords.is_empty() {
+                    let variant_pattern_id = self
+                        .hs_builder
+                        .add_regex(raw.pattern.clone())
+                        .map_err(|err| ScannerBuilderError::RuleCompilationError {
+                            rule: rule_id.to_string(),
+                            message: err.to_string(),
+                        })?;
+                    let mut variant_checks = Vec::new();
+                    for raw_check in raw.checks.iter().flatten() {
+                        let check = Condition::from_raw(raw_check);
+                        // (The input variables were validated when building the rule's checks)
+                        let pattern_checker = match raw_check
+                            .input_variable()
+                            .and_then(parse_candidate_variable)
+                        {
+                            Some(CandidateVariable::Capture(name)) => {
+                                TargetedChecker::named_capture(name, check)
+                            }
+                            _ => TargetedChecker::candidate(check),
+                        };
+                        variant_checks.push(pattern_checker);
+                    }
+                    structured_variant = Some((variant_pattern_id, variant_checks, keywords));
+                }
+                if !parts.is_empty() {
+                    self.parts.insert(rule_id.clone(), parts);
+                }
                 pattern_id
             }
         };
 
-        let validator = match raw_rule.validator.deref() {
-            RawValidator::Http(raw_http) => match &raw_http.0 {
-                RawExtension::Simple(raw_cfg) => {
-                    // Because it's derived from rule_id, this is a unique id.
-                    let validator_id = ValidatorId::from(format!("validator-http_{}", rule_id));
-                    http::build_simple_http(raw_cfg.clone(), validator_id, &self.http_retry)
+        let validator: Box<dyn Validator + Send + Sync> = match raw_rule.validator.deref() {
+            RawValidator::Http(raw_http) => {
+                // Because it's derived from rule_id, this is a unique id.
+                let validator_id = ValidatorId::from(format!("validator-http_{}", rule_id));
+                let agent = self.http_agent.clone().unwrap_or_else(ureq::Agent::new);
+                let limits = self.http_limits.clone();
+                let to_compilation_error =
+                    |err: ExtractorError| ScannerBuilderError::RuleCompilationError {
+                        rule: rule_id.to_string(),
+                        message: err.to_string(),
+                    };
+                match &raw_http.0 {
+                    RawExtension::Simple(raw_cfg) => Box::new(
+                        http::build_simple_http(
+                            raw_cfg.clone(),
+                            validator_id,
+                            &self.http_retry,
+                            agent,
+                            limits,
+                        )
+                        .map_err(to_compilation_error)?,
+                    ),
+                    RawExtension::Chain(raw_cfg) => Box::new(
+                        http::build_request_chain(
+                            raw_cfg.clone(),
+                            validator_id,
+                            &self.http_retry,
+                            agent,
+                            limits,
+                        )
+                        .map_err(to_compilation_error)?,
+                    ),
                 }
-            },
+            }
         };
 
         let validator_id = validator.id().clone();
+        if let Some((variant_pattern_id, variant_checks, keywords)) = structured_variant {
+            let variant_id = RuleId::from(format!("{}{}", rule_id, STRUCTURED_MAGIC));
+            let variant = Rule::new(
+                variant_id.clone(),
+                variant_pattern_id,
+                validator_id.clone(),
+                Vec::new(),
+                variant_checks,
+            );
+            self.built_rules.push(variant);
+            self.structured_rules.insert(
+                variant_id,
+                StructuredRule {
+                    rule_id: rule_id.clone(),
+                    keywords,
+                },
+            );
+        }
         let rule = Rule::new(rule_id, pattern_id, validator_id, Vec::new(), checks);
         self.built_rules.push(rule);
 
-        let boxed: Box<dyn Validator + Send + Sync> = Box::new(validator);
-        self.built_validators.push(boxed);
+        self.built_validators.push(validator);
 
         Ok(())
     }
 }
 
+/// The suffix of the id of the structured variant of a rule, named in a manner to avoid collisions
+/// with the ids of the rules.
+const STRUCTURED_MAGIC: &str = "__STRUCTURED_MAGIC_5f3759df__";
+
+/// A variant of a rule that matches its pattern without its proximity keywords. Its candidates are
+/// only kept in the values of structured files whose key contains one of the keywords.
+#[derive(Debug, Clone)]
+struct StructuredRule {
+    /// The id of the rule this is a variant of.
+    rule_id: RuleId,
+    /// The proximity keywords of the rule, in lowercase.
+    keywords: Vec<String>,
+}
+
+impl StructuredRule {
+    fn matches_key(&self, key_path: &str) -> bool {
+        let key_path = key_path.to_ascii_lowercase();
+        self.keywords
+            .iter()
+            .any(|keyword| key_path.contains(keyword.as_str()))
+    }
+}
+
 /// Metadata about a Rule that isn't related to its functionality
 #[derive(Debug, Clone)]
 pub struct RuleInfo {
     pub rule_id: String,
     pub description: String,
     pub short_description: String,
+    /// How to remediate a finding of the rule, empty if the rule doesn't say.
+    pub remediation: String,
+    /// Whether the rule scans files that are excluded from analysis by default.
+    pub include_ineligible_files: bool,
+    /// The paths where the findings of the rule are ignored.
+    pub ignore_paths: Option<GlobSet>,
+    /// The rules that set the severity of the findings of the rule from their context.
+    pub severity_rules: SeverityRules,
+}
+
+impl RuleInfo {
+    /// Returns true if the findings of the rule are ignored in the file, given its path relative
+    /// to the root of the repository.
+    pub fn ignores_path(&self, path: impl AsRef<Path>) -> bool {
+        self.ignore_paths
+            .as_ref()
+            .is_some_and(|globs| globs.is_match(path))
+    }
 }
 
 #[cfg(test)]
@@ -397,4 +802,192 @@ validator:
         let _ = scanner.engine.validate_candidate(candidates[0].clone());
         mock.assert_hits(1);
     }
+
+    /// Only rules that opted in report candidates for files excluded from analysis by default.
+    #[test]
+    fn ineligible_files_opt_in() {
+        let opted_in = RULE_FILE
+            .replace(
+                "id: rule-one",
+                "id: rule-two\ninclude-ineligible-files: true",
+            )
+            .replace("<__cfg(test)_magic_url__>", "http://localhost");
+        let default = RULE_FILE.replace("<__cfg(test)_magic_url__>", "http://localhost");
+        let scanner = ScannerBuilder::new()
+            .yaml_string(default)
+            .yaml_string(opted_in)
+            .try_build()
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.pem");
+        std::fs::write(&path, "--- abc_018cf028 ---").unwrap();
+
+        assert_eq!(scanner.scan_file(&path).unwrap().len(), 2);
+        let candidates = scanner.scan_ineligible_file(&path).unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].rule_match.rule_id.as_str(), "rule-two");
+    }
+
+    /// Documentation files are scanned entirely, unless the scanner only scans their code: the
+    /// code is then scanned at its original position.
+    #[test]
+    fn markdown_code_scanned() {
+        let yaml = RULE_FILE.replace("<__cfg(test)_magic_url__>", "http://localhost");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("README.md");
+        let contents =
+            "Not abc_018cf028.\n\n```\n--- abc_018cf028 ---\n```\nUse `--- abc_018cf028 ---`\n";
+        std::fs::write(&path, contents).unwrap();
+
+        let lines = |markup_code_only: bool| {
+            let scanner = ScannerBuilder::new()
+                .yaml_string(yaml.clone())
+                .markup_code_only(markup_code_only)
+                .try_build()
+                .unwrap();
+            scanner
+                .scan_file(&path)
+                .unwrap()
+                .iter()
+                .map(|c| c.rule_match.matched.point_span.start().line.get())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lines(false), vec![1, 4, 6]);
+        assert_eq!(lines(true), vec![4, 6]);
+    }
+
+    /// A cancelled scanner doesn't scan files.
+    #[test]
+    fn cancelled_scan() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let yaml = RULE_FILE.replace("<__cfg(test)_magic_url__>", "http://localhost");
+        let flag = Arc::new(AtomicUsize::new(0));
+        let scanner = ScannerBuilder::new()
+            .yaml_string(yaml)
+            .cancellation_flag(Arc::clone(&flag))
+            .try_build()
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.txt");
+        std::fs::write(&path, "--- abc_018cf028 ---").unwrap();
+
+        assert_eq!(scanner.scan_file(&path).unwrap().len(), 1);
+        flag.store(1, Ordering::Relaxed);
+        assert!(matches!(
+            scanner.scan_file(&path),
+            Err(super::ScannerError::Cancelled)
+        ));
+    }
+    /// In structured-content mode, the values of the keys that contain a proximity keyword are
+    /// matched, even when the keyword is too far from the value for the proximity pattern.
+    #[test]
+    fn structured_content() {
+        let yaml = RULE_FILE
+            .replace(
+                "    checks:",
+                "    proximity:\n      keywords: [provider]\n      max-distance: 5\n    checks:",
+            )
+            .replace("<__cfg(test)_magic_url__>", "http://localhost");
+        let contents = "provider:\n  credentials:\n    token: abc_018cf028\nother: abc_018cf028\n";
+        let dir = tempfile::tempdir().unwrap();
+        let yaml_path = dir.path().join("config.yml");
+        std::fs::write(&yaml_path, contents).unwrap();
+        let text_path = dir.path().join("config.txt");
+        std::fs::write(&text_path, contents).unwrap();
+
+        let scanner = ScannerBuilder::new()
+            .yaml_string(yaml.clone())
+            .try_build()
+            .unwrap();
+        assert!(scanner.scan_file(&yaml_path).unwrap().is_empty());
+
+        let scanner = ScannerBuilder::new()
+            .yaml_string(yaml)
+            .structured_content(true)
+            .try_build()
+            .unwrap();
+        let candidates = scanner.scan_file(&yaml_path).unwrap();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].rule_match.rule_id.as_str(), "rule-one");
+        assert_eq!(candidates[0].rule_match.matched.as_str(), "abc_018cf028");
+        assert_eq!(
+            candidates[0]
+                .rule_match
+                .matched
+                .point_span
+                .start()
+                .line
+                .get(),
+            3
+        );
+        assert!(scanner.scan_file(&text_path).unwrap().is_empty());
+    }
+
+    /// The parts of a multi-part secret found near a candidate are added to its captures, and
+    /// candidates without a required part are dropped.
+    #[test]
+    fn multi_part_secret() {
+        let ms = MockServer::start();
+        let mock = ms.mock(|when, then| {
+            when.method("GET")
+                .path("/")
+                .query_param("id", "abc")
+                .header("Authorization", "Basic client-42:abc_018cf028");
+            then.status(200);
+        });
+        let yaml = RULE_FILE
+            .replace(
+                "    checks:",
+                "    parts:\n      - name: client_id\n        pattern: 'client=(client-[0-9]+)'\n        max-distance: 30\n    checks:",
+            )
+            .replace(
+                "Bearer ${{ candidate }}",
+                "Basic ${{ candidate.captures.client_id }}:${{ candidate }}",
+            )
+            .replace("<__cfg(test)_magic_url__>", &ms.base_url());
+        let scanner = ScannerBuilder::new().yaml_string(yaml).try_build().unwrap();
+
+        let contents = "client=client-42\nsecret=abc_018cf028\n\n\n\n\n\n\n\nsecret=xyz_018cf028\n";
+        let candidates = scanner
+            .scan_contents(&PathBuf::from("config.txt"), contents.as_bytes())
+            .unwrap();
+        assert_eq!(candidates.len(), 1);
+        let client_id = &candidates[0].rule_match.captures["client_id"];
+        assert_eq!(client_id.as_str(), "client-42");
+        assert_eq!(client_id.point_span.start().line.get(), 1);
+
+        let _ = scanner.validate_candidate(&candidates[0]);
+        mock.assert_hits(1);
+    }
+
+    /// A rule ignores the paths that match its `ignore-paths` globs.
+    #[test]
+    fn rule_ignore_paths() {
+        let yaml = RULE_FILE
+            .replace(
+                "id: rule-one",
+                "id: rule-one\nignore-paths:\n  - 'tests/**'\n  - '**/*.example'",
+            )
+            .replace("<__cfg(test)_magic_url__>", "http://localhost");
+        let scanner = ScannerBuilder::new().yaml_string(yaml).try_build().unwrap();
+        let rule = scanner.rule("rule-one").unwrap();
+        assert!(rule.ignores_path("tests/fixtures/config.txt"));
+        assert!(rule.ignores_path("config/.env.example"));
+        assert!(!rule.ignores_path("src/config.txt"));
+
+        let invalid = RULE_FILE
+            .replace(
+                "id: rule-one",
+                "id: rule-one\nignore-paths:\n  - 'tests/[a'",
+            )
+            .replace("<__cfg(test)_magic_url__>", "http://localhost");
+        assert!(ScannerBuilder::new()
+            .yaml_string(invalid)
+            .try_build()
+            .is_err());
+    }
 }
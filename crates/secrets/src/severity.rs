@@ -0,0 +1,270 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use crate::check::entropy::NormalizedEntropy;
+use crate::rule_file::severity::{RawFindingStatus, RawPathClass, RawSeverityRule};
+use secrets_core::validator::Severity;
+use std::ops::Range;
+use std::path::Path;
+
+/// The directories that contain tests, fixtures and mocks.
+const TEST_DIRECTORIES: &[&str] = &[
+    "test",
+    "tests",
+    "__tests__",
+    "spec",
+    "specs",
+    "testdata",
+    "fixtures",
+    "__fixtures__",
+    "mocks",
+    "__mocks__",
+];
+
+/// The validation status of a finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingStatus {
+    Valid,
+    Invalid,
+    Inconclusive,
+    Unvalidated,
+}
+
+impl From<RawFindingStatus> for FindingStatus {
+    fn from(value: RawFindingStatus) -> Self {
+        match value {
+            RawFindingStatus::Valid => Self::Valid,
+            RawFindingStatus::Invalid => Self::Invalid,
+            RawFindingStatus::Inconclusive => Self::Inconclusive,
+            RawFindingStatus::Unvalidated => Self::Unvalidated,
+        }
+    }
+}
+
+/// A finding of a rule, with the context the severity rules are evaluated on.
+#[derive(Debug, Clone)]
+pub struct Finding<'a> {
+    pub status: FindingStatus,
+    /// The path of the file, relative to the root of the repository.
+    pub path: &'a Path,
+    /// The text of the secret.
+    pub secret: &'a str,
+    /// The content of the file and the byte range of the secret in it, if the file could be read.
+    pub content: Option<(&'a str, Range<usize>)>,
+}
+
+#[derive(Debug, Clone)]
+struct SeverityRule {
+    statuses: Option<Vec<FindingStatus>>,
+    /// Whether the file must be a test (`Some(true)`) or not (`Some(false)`).
+    test_path: Option<bool>,
+    min_entropy: Option<f32>,
+    keyword: Option<bool>,
+    severity: Severity,
+}
+
+/// The rules that set the severity of the findings of a rule from their validation and their context,
+/// instead of the single severity of the validation.
+#[derive(Debug, Clone, Default)]
+pub struct SeverityRules {
+    rules: Vec<SeverityRule>,
+    /// The proximity keywords of the rule, in lowercase.
+    keywords: Vec<String>,
+    /// The number of bytes before or after a secret where a keyword is near it.
+    max_distance: usize,
+}
+
+impl SeverityRules {
+    pub(crate) fn new(
+        raw_rules: Vec<RawSeverityRule>,
+        keywords: impl IntoIterator<Item = impl AsRef<str>>,
+        max_distance: usize,
+    ) -> Self {
+        let rules = raw_rules
+            .into_iter()
+            .map(|raw| SeverityRule {
+                statuses: raw
+                    .when
+                    .status
+                    .map(|statuses| statuses.into_iter().map(FindingStatus::from).collect()),
+                test_path: raw.when.path.map(|class| class == RawPathClass::Test),
+                min_entropy: raw.when.min_entropy,
+                keyword: raw.when.keyword,
+                severity: raw.severity.into(),
+            })
+            .collect();
+        Self {
+            rules,
+            keywords: keywords
+                .into_iter()
+                .map(|keyword| keyword.as_ref().trim().to_lowercase())
+                .filter(|keyword| !keyword.is_empty())
+                .collect(),
+            max_distance,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns the severity of the first rule whose conditions are all true for the finding, if any.
+    pub fn severity(&self, finding: &Finding) -> Option<Severity> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        let test_path = is_test_path(finding.path);
+        let entropy = NormalizedEntropy::new(0.0, None).normalized_entropy(finding.secret.chars());
+        let keyword = self.has_keyword_near(finding);
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.statuses
+                    .as_ref()
+                    .map_or(true, |statuses| statuses.contains(&finding.status))
+                    && rule.test_path.map_or(true, |test| test == test_path)
+                    && rule.min_entropy.map_or(true, |min| entropy >= min)
+                    && rule.keyword.map_or(true, |near| near == keyword)
+            })
+            .map(|rule| rule.severity)
+    }
+
+    /// Returns true if a proximity keyword of the rule is within `max_distance` bytes of the secret.
+    fn has_keyword_near(&self, finding: &Finding) -> bool {
+        let Some((content, range)) = &finding.content else {
+            return false;
+        };
+        let mut start = range.start.saturating_sub(self.max_distance);
+        while !content.is_char_boundary(start) {
+            start -= 1;
+        }
+        let mut end = range
+            .end
+            .saturating_add(self.max_distance)
+            .min(content.len());
+        while !content.is_char_boundary(end) {
+            end += 1;
+        }
+        let surroundings = content[start..end].to_lowercase();
+        self.keywords
+            .iter()
+            .any(|keyword| surroundings.contains(keyword.as_str()))
+    }
+}
+
+/// Returns true if the file is a test, a fixture or a mock: a file in a test directory, or a file
+/// named as a test (e.g. `test_app.py`, `app_test.go`, `app.test.js` or `AppTest.java`).
+pub fn is_test_path(path: &Path) -> bool {
+    let in_test_directory = path.parent().is_some_and(|parent| {
+        parent.components().any(|component| {
+            let name = component.as_os_str().to_string_lossy().to_lowercase();
+            TEST_DIRECTORIES.contains(&name.as_str())
+        })
+    });
+    if in_test_directory {
+        return true;
+    }
+    let Some(stem) = path.file_stem().map(|stem| stem.to_string_lossy()) else {
+        return false;
+    };
+    let lowercase = stem.to_lowercase();
+    lowercase.starts_with("test_")
+        || lowercase.ends_with("_test")
+        || lowercase.ends_with(".test")
+        || lowercase.ends_with(".spec")
+        || lowercase.ends_with("_spec")
+        || stem.ends_with("Test")
+        || stem.ends_with("Tests")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule_file::severity::RawSeverityRule;
+
+    #[test]
+    fn test_paths() {
+        assert!(is_test_path(Path::new("src/tests/config.py")));
+        assert!(is_test_path(Path::new("pkg/app_test.go")));
+        assert!(is_test_path(Path::new("web/app.spec.ts")));
+        assert!(is_test_path(Path::new("src/main/java/AppTest.java")));
+        assert!(!is_test_path(Path::new("src/config.py")));
+        assert!(!is_test_path(Path::new("src/contest.py")));
+        assert!(!is_test_path(Path::new("tests")));
+    }
+
+    #[test]
+    fn first_rule_that_applies() {
+        let raw = serde_yaml::from_str::<Vec<RawSeverityRule>>(
+            r#"
+- when:
+    status: [VALID]
+    path: production
+  severity: ERROR
+- when:
+    min-entropy: 0.5
+    keyword: true
+  severity: WARNING
+- when:
+    path: test
+  severity: INFO
+"#,
+        )
+        .unwrap();
+        let rules = SeverityRules::new(raw, ["Provider"], 20);
+        let content = "provider_key = 'x7Kq9mPz2LwR'\nother = 'aaaaaaaaaaaa'\n";
+        let finding = |status, path, secret: &'static str| {
+            let start = content.find(secret).unwrap();
+            Finding {
+                status,
+                path: Path::new(path),
+                secret,
+                content: Some((content, start..start + secret.len())),
+            }
+        };
+
+        let severity = |finding: Finding| rules.severity(&finding);
+        assert_eq!(
+            severity(finding(
+                FindingStatus::Valid,
+                "src/config.py",
+                "x7Kq9mPz2LwR"
+            )),
+            Some(Severity::Error)
+        );
+        assert_eq!(
+            severity(finding(
+                FindingStatus::Valid,
+                "tests/config.py",
+                "x7Kq9mPz2LwR"
+            )),
+            Some(Severity::Warning)
+        );
+        // the keyword is too far, and the entropy too low
+        assert_eq!(
+            severity(finding(
+                FindingStatus::Unvalidated,
+                "src/config.py",
+                "aaaaaaaaaaaa"
+            )),
+            None
+        );
+        assert_eq!(
+            severity(finding(
+                FindingStatus::Unvalidated,
+                "tests/config.py",
+                "aaaaaaaaaaaa"
+            )),
+            Some(Severity::Info)
+        );
+        assert_eq!(
+            SeverityRules::default().severity(&finding(
+                FindingStatus::Valid,
+                "src/config.py",
+                "x7Kq9mPz2LwR"
+            )),
+            None
+        );
+    }
+}
@@ -0,0 +1,384 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use std::ops::Range;
+use std::path::Path;
+
+/// The format of a structured file, whose keys are used as the context of the values.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum Format {
+    Json,
+    Yaml,
+    Env,
+}
+
+/// A scalar value of a structured file, along with the keys that lead to it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Entry {
+    /// The keys from the root of the document to the value, joined with `.`
+    /// (e.g. `aws.credentials.secret_access_key`).
+    pub key_path: String,
+    /// The byte range of the value within the file, without its quotes.
+    pub value: Range<usize>,
+}
+
+/// Returns the format of a file from its name, if it's a structured file.
+pub(crate) fn structured_format(path: &Path) -> Option<Format> {
+    let file_name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if file_name == ".env" || file_name.starts_with(".env.") {
+        return Some(Format::Env);
+    }
+    match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "json" => Some(Format::Json),
+        "yml" | "yaml" => Some(Format::Yaml),
+        "env" => Some(Format::Env),
+        _ => None,
+    }
+}
+
+/// Extracts the scalar values of a structured file. The parsing is lenient: a file with a syntax
+/// error returns the entries that could be read, as it's only used to add context to candidates.
+pub(crate) fn parse_entries(format: Format, contents: &[u8]) -> Vec<Entry> {
+    match format {
+        Format::Json => parse_json(contents),
+        Format::Yaml => parse_yaml(contents),
+        Format::Env => parse_env(contents),
+    }
+}
+
+/// An object or array of a JSON document.
+struct JsonContainer {
+    is_object: bool,
+    /// The key of the value currently being read, for an object.
+    key: Option<String>,
+    expecting_key: bool,
+}
+
+fn parse_json(contents: &[u8]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut stack: Vec<JsonContainer> = Vec::new();
+    let mut index = 0;
+    while index < contents.len() {
+        match contents[index] {
+            byte @ (b'{' | b'[') => stack.push(JsonContainer {
+                is_object: byte == b'{',
+                key: None,
+                expecting_key: byte == b'{',
+            }),
+            b'}' | b']' => {
+                stack.pop();
+            }
+            b',' => {
+                if let Some(container) = stack.last_mut().filter(|c| c.is_object) {
+                    container.key = None;
+                    container.expecting_key = true;
+                }
+            }
+            b':' => {
+                if let Some(container) = stack.last_mut() {
+                    container.expecting_key = false;
+                }
+            }
+            b'"' => {
+                let start = index + 1;
+                let end = find_string_end(contents, start);
+                match stack.last_mut() {
+                    Some(container) if container.expecting_key => {
+                        container.key =
+                            Some(String::from_utf8_lossy(&contents[start..end]).into_owned());
+                    }
+                    _ => entries.push(Entry {
+                        key_path: join_keys(stack.iter().filter_map(|c| c.key.as_deref())),
+                        value: start..end,
+                    }),
+                }
+                index = end;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    entries
+}
+
+/// Returns the index of the closing quote of a JSON string that starts at `start`, or the end of
+/// the contents if the string isn't closed.
+fn find_string_end(contents: &[u8], start: usize) -> usize {
+    let mut index = start;
+    while index < contents.len() {
+        match contents[index] {
+            b'\\' => index += 2,
+            b'"' => return index,
+            _ => index += 1,
+        }
+    }
+    contents.len()
+}
+
+/// Parses the block mappings, sequences and scalars of a YAML document, line by line. Flow
+/// collections (e.g. `{a: 1}`) are read as a single scalar.
+fn parse_yaml(contents: &[u8]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    // The keys of the mappings that contain the current line, with their indentation.
+    let mut keys: Vec<(usize, String)> = Vec::new();
+    // The indentation and key path of the block scalar (`|` or `>`) being read.
+    let mut block_scalar: Option<(usize, String)> = None;
+    let mut offset = 0;
+
+    for line in contents.split_inclusive(|b| *b == b'\n') {
+        let line_start = offset;
+        offset += line.len();
+        let line = trim_line_break(line);
+        let mut indent = line.iter().take_while(|b| **b == b' ').count();
+        if line[indent..].is_empty() {
+            continue;
+        }
+        if let Some((block_indent, key_path)) = &block_scalar {
+            if indent > *block_indent {
+                entries.push(Entry {
+                    key_path: key_path.clone(),
+                    value: line_start + indent..line_start + line.len(),
+                });
+                continue;
+            }
+            block_scalar = None;
+        }
+        if line[indent..].starts_with(b"#") {
+            continue;
+        }
+        if line == b"---" || line == b"..." {
+            keys.clear();
+            continue;
+        }
+
+        // The content of a sequence item is indented as if the dash was a space.
+        let mut is_item = false;
+        while line[indent..].starts_with(b"- ") || &line[indent..] == b"-" {
+            is_item = true;
+            indent += 1;
+            indent += line[indent..].iter().take_while(|b| **b == b' ').count();
+        }
+        while keys
+            .last()
+            .is_some_and(|(key_indent, _)| *key_indent >= indent)
+        {
+            keys.pop();
+        }
+
+        let body = &line[indent..];
+        match find_yaml_separator(body) {
+            Some(separator) => {
+                let key = String::from_utf8_lossy(trim_quotes(body[..separator].trim_ascii()));
+                let key_path =
+                    join_keys(keys.iter().map(|(_, k)| k.as_str()).chain([key.as_ref()]));
+                let value_start = indent + separator + 1;
+                let value = line[value_start..].trim_ascii_start();
+                if value.is_empty() || value.starts_with(b"#") {
+                    keys.push((indent, key.into_owned()));
+                } else if value.starts_with(b"|") || value.starts_with(b">") {
+                    block_scalar = Some((indent, key_path));
+                } else {
+                    let range = scalar_range(line, value_start);
+                    entries.push(Entry {
+                        key_path,
+                        value: line_start + range.start..line_start + range.end,
+                    });
+                }
+            }
+            None if is_item => {
+                let range = scalar_range(line, indent);
+                entries.push(Entry {
+                    key_path: join_keys(keys.iter().map(|(_, k)| k.as_str())),
+                    value: line_start + range.start..line_start + range.end,
+                });
+            }
+            None => {}
+        }
+    }
+    entries
+}
+
+/// Returns the index of the `:` that separates a key from its value in a YAML line, if any.
+fn find_yaml_separator(body: &[u8]) -> Option<usize> {
+    // A quoted key can contain the separator.
+    let search_from = match body.first() {
+        Some(quote @ (b'"' | b'\'')) => body[1..].iter().position(|b| b == quote)? + 2,
+        _ => 0,
+    };
+    (search_from..body.len()).find(|&index| {
+        body[index] == b':'
+            && body
+                .get(index + 1)
+                .map_or(true, |b| *b == b' ' || *b == b'\t')
+    })
+}
+
+/// Parses the lines of a `.env` file: `KEY=value`, optionally prefixed with `export`.
+fn parse_env(contents: &[u8]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    for line in contents.split_inclusive(|b| *b == b'\n') {
+        let line_start = offset;
+        offset += line.len();
+        let line = trim_line_break(line);
+        let indent = line.len() - line.trim_ascii_start().len();
+        let mut body = &line[indent..];
+        if body.starts_with(b"#") {
+            continue;
+        }
+        let mut key_start = indent;
+        if let Some(rest) = body.strip_prefix(b"export ") {
+            key_start += body.len() - rest.len();
+            body = rest;
+        }
+        let Some(separator) = body.iter().position(|b| *b == b'=') else {
+            continue;
+        };
+        let key = body[..separator].trim_ascii();
+        if key.is_empty() {
+            continue;
+        }
+        let range = scalar_range(line, key_start + separator + 1);
+        entries.push(Entry {
+            key_path: String::from_utf8_lossy(key).into_owned(),
+            value: line_start + range.start..line_start + range.end,
+        });
+    }
+    entries
+}
+
+/// Returns the range of the scalar that starts at `from` in a line, without the surrounding
+/// whitespace, its quotes, and the comment that follows an unquoted value.
+fn scalar_range(line: &[u8], from: usize) -> Range<usize> {
+    let start = from + (line[from..].len() - line[from..].trim_ascii_start().len());
+    let value = &line[start..];
+    match value.first() {
+        Some(quote @ (b'"' | b'\'')) => {
+            let end = value[1..]
+                .iter()
+                .position(|b| b == quote)
+                .map_or(line.len(), |position| start + 1 + position);
+            start + 1..end
+        }
+        _ => {
+            let end = value
+                .windows(2)
+                .position(|w| w[0].is_ascii_whitespace() && w[1] == b'#')
+                .map_or(line.len(), |position| start + position);
+            start..start + line[start..end].trim_ascii_end().len()
+        }
+    }
+}
+
+fn trim_line_break(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn trim_quotes(key: &[u8]) -> &[u8] {
+    match key {
+        [b'"', inner @ .., b'"'] | [b'\'', inner @ .., b'\''] => inner,
+        _ => key,
+    }
+}
+
+fn join_keys<'a>(keys: impl IntoIterator<Item = &'a str>) -> String {
+    keys.into_iter().collect::<Vec<_>>().join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the key paths and the text of the values of the entries.
+    fn parse(format: Format, contents: &str) -> Vec<(String, &str)> {
+        parse_entries(format, contents.as_bytes())
+            .into_iter()
+            .map(|entry| (entry.key_path, &contents[entry.value]))
+            .collect()
+    }
+
+    fn entries<'a>(expected: &[(&str, &'a str)]) -> Vec<(String, &'a str)> {
+        expected
+            .iter()
+            .map(|(key, value)| (key.to_string(), *value))
+            .collect()
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(structured_format(Path::new("a/b.json")), Some(Format::Json));
+        assert_eq!(structured_format(Path::new("ci.YML")), Some(Format::Yaml));
+        assert_eq!(structured_format(Path::new(".env")), Some(Format::Env));
+        assert_eq!(
+            structured_format(Path::new(".env.local")),
+            Some(Format::Env)
+        );
+        assert_eq!(structured_format(Path::new("prod.env")), Some(Format::Env));
+        assert_eq!(structured_format(Path::new("main.py")), None);
+    }
+
+    #[test]
+    fn json() {
+        let contents = r#"{
+  "aws": {"access_key_id": "AKIA", "secret_access_key": "abc\"def"},
+  "tokens": ["t1", "t2"],
+  "port": 8080,
+  "nested": [{"password": "p"}]
+}"#;
+        assert_eq!(
+            parse(Format::Json, contents),
+            entries(&[
+                ("aws.access_key_id", "AKIA"),
+                ("aws.secret_access_key", r#"abc\"def"#),
+                ("tokens", "t1"),
+                ("tokens", "t2"),
+                ("nested.password", "p"),
+            ])
+        );
+    }
+
+    #[test]
+    fn yaml() {
+        let contents = r#"# credentials
+aws:
+  access_key_id: AKIA # inline comment
+  "secret_access_key": 'abc def'
+tokens:
+  - t1
+  - name: t2
+    value: "v2"
+certificate: |
+  line1
+  line2
+url: http://example.com
+"#;
+        assert_eq!(
+            parse(Format::Yaml, contents),
+            entries(&[
+                ("aws.access_key_id", "AKIA"),
+                ("aws.secret_access_key", "abc def"),
+                ("tokens", "t1"),
+                ("tokens.name", "t2"),
+                ("tokens.value", "v2"),
+                ("certificate", "line1"),
+                ("certificate", "line2"),
+                ("url", "http://example.com"),
+            ])
+        );
+    }
+
+    #[test]
+    fn env() {
+        let contents = "# comment\nAWS_SECRET_ACCESS_KEY=abc # comment\nexport TOKEN=\"quoted value\"\r\nEMPTY=\nnot a variable\n";
+        assert_eq!(
+            parse(Format::Env, contents),
+            entries(&[
+                ("AWS_SECRET_ACCESS_KEY", "abc"),
+                ("TOKEN", "quoted value"),
+                ("EMPTY", ""),
+            ])
+        );
+    }
+}
@@ -7,6 +7,7 @@ use crate::rule_file::{RawSecretStatus, RawSeverity};
 use secrets_core::validator::http::NextAction;
 use secrets_core::validator::{SecretCategory, Severity};
 
+pub(crate) mod extract;
 pub mod http;
 
 impl From<RawAction> for NextAction {
@@ -0,0 +1,189 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use crate::rule_file::validator::http::RawExtractor;
+use std::borrow::Cow;
+
+/// The prefix of the template variables that hold the values extracted from a response.
+pub(crate) const EXTRACTED_PREFIX: &str = "extracted.";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExtractorError {
+    #[error("invalid JSONPath `{path}`: {message}")]
+    JsonPath { path: String, message: String },
+    #[error("invalid regex `{pattern}`: {message}")]
+    Regex { pattern: String, message: String },
+}
+
+/// A compiled [`RawExtractor`], which reads a value from the body of a response.
+#[derive(Debug, Clone)]
+pub(crate) enum Extractor {
+    JsonPath(Vec<JsonPathSegment>),
+    Regex(regex::Regex),
+}
+
+/// A step of a JSONPath, from a value to one of its children.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum JsonPathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl Extractor {
+    pub(crate) fn from_raw(raw: &RawExtractor) -> Result<Self, ExtractorError> {
+        match raw {
+            RawExtractor::JsonPath(path) => {
+                parse_json_path(path)
+                    .map(Self::JsonPath)
+                    .map_err(|message| ExtractorError::JsonPath {
+                        path: path.clone(),
+                        message,
+                    })
+            }
+            RawExtractor::Regex(pattern) => {
+                regex::Regex::new(pattern)
+                    .map(Self::Regex)
+                    .map_err(|err| ExtractorError::Regex {
+                        pattern: pattern.clone(),
+                        message: err.to_string(),
+                    })
+            }
+        }
+    }
+
+    /// Returns the value extracted from the body, if any.
+    ///
+    /// A JSONPath that leads to a string returns the string, and to a number, a boolean, an array or
+    /// an object returns its JSON representation. A regex returns its first capture group, or the
+    /// entire match if it has no group.
+    pub(crate) fn extract<'a>(&self, body: &'a str) -> Option<Cow<'a, str>> {
+        match self {
+            Extractor::JsonPath(segments) => {
+                let root = serde_json::from_str::<serde_json::Value>(body).ok()?;
+                let value = segments
+                    .iter()
+                    .try_fold(&root, |value, segment| match segment {
+                        JsonPathSegment::Key(key) => value.get(key),
+                        JsonPathSegment::Index(index) => value.get(index),
+                    })?;
+                match value {
+                    serde_json::Value::Null => None,
+                    serde_json::Value::String(string) => Some(Cow::Owned(string.clone())),
+                    other => Some(Cow::Owned(other.to_string())),
+                }
+            }
+            Extractor::Regex(regex) => {
+                let captures = regex.captures(body)?;
+                let matched = captures.get(1).or_else(|| captures.get(0))?;
+                Some(Cow::Borrowed(matched.as_str()))
+            }
+        }
+    }
+}
+
+/// Parses the subset of JSONPath that selects a single value: `$`, followed by keys (`.name` or
+/// `['name']`) and array indices (`[0]`).
+fn parse_json_path(path: &str) -> Result<Vec<JsonPathSegment>, String> {
+    let mut rest = path
+        .trim()
+        .strip_prefix('$')
+        .ok_or_else(|| "expected the path to start with `$`".to_string())?;
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after_dot) = rest.strip_prefix('.') {
+            let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+            let key = &after_dot[..end];
+            if key.is_empty() || key == "*" {
+                return Err(format!("unsupported key `{key}`"));
+            }
+            segments.push(JsonPathSegment::Key(key.to_string()));
+            rest = &after_dot[end..];
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            let end = after_bracket
+                .find(']')
+                .ok_or_else(|| "unclosed `[`".to_string())?;
+            let inner = after_bracket[..end].trim();
+            let quoted = ['\'', '"'].into_iter().find_map(|quote| {
+                inner
+                    .strip_prefix(quote)
+                    .and_then(|key| key.strip_suffix(quote))
+            });
+            let segment = match quoted {
+                Some(key) => JsonPathSegment::Key(key.to_string()),
+                None => JsonPathSegment::Index(
+                    inner
+                        .parse()
+                        .map_err(|_| format!("unsupported selector `[{inner}]`"))?,
+                ),
+            };
+            segments.push(segment);
+            rest = &after_bracket[end + 1..];
+        } else {
+            return Err(format!("unexpected `{rest}`"));
+        }
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json_path(path: &str) -> Extractor {
+        Extractor::from_raw(&RawExtractor::JsonPath(path.to_string())).unwrap()
+    }
+
+    #[test]
+    fn parse_json_paths() {
+        use JsonPathSegment::{Index, Key};
+        assert_eq!(parse_json_path("$"), Ok(vec![]));
+        assert_eq!(
+            parse_json_path("$.account.scopes[1]"),
+            Ok(vec![Key("account".into()), Key("scopes".into()), Index(1)])
+        );
+        assert_eq!(
+            parse_json_path("$['user.name'][\"id\"]"),
+            Ok(vec![Key("user.name".into()), Key("id".into())])
+        );
+        assert!(parse_json_path("account.type").is_err());
+        assert!(parse_json_path("$.scopes[*]").is_err());
+        assert!(parse_json_path("$.*").is_err());
+        assert!(parse_json_path("$.scopes[0").is_err());
+        assert!(parse_json_path("$..type").is_err());
+    }
+
+    #[test]
+    fn extract_json() {
+        let body =
+            r#"{"account": {"type": "admin", "id": 42, "scopes": ["read", "write"], "org": null}}"#;
+        let extract = |path: &str| json_path(path).extract(body).map(Cow::into_owned);
+        assert_eq!(extract("$.account.type").as_deref(), Some("admin"));
+        assert_eq!(extract("$.account.id").as_deref(), Some("42"));
+        assert_eq!(extract("$.account.scopes[1]").as_deref(), Some("write"));
+        assert_eq!(
+            extract("$.account.scopes").as_deref(),
+            Some(r#"["read","write"]"#)
+        );
+        assert_eq!(extract("$.account.org"), None);
+        assert_eq!(extract("$.account.missing"), None);
+        assert_eq!(json_path("$.a").extract("not json"), None);
+    }
+
+    #[test]
+    fn extract_regex() {
+        let regex =
+            |pattern: &str| Extractor::from_raw(&RawExtractor::Regex(pattern.to_string())).unwrap();
+        let body = "user=alice; scopes=repo,gist";
+        assert_eq!(
+            regex("scopes=([a-z,]+)").extract(body).as_deref(),
+            Some("repo,gist")
+        );
+        assert_eq!(
+            regex("user=[a-z]+").extract(body).as_deref(),
+            Some("user=alice")
+        );
+        assert_eq!(regex("token=(.+)").extract(body), None);
+        assert!(Extractor::from_raw(&RawExtractor::Regex("(".to_string())).is_err());
+    }
+}
@@ -2,38 +2,177 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
+use secrets_core::rule::LocatedString;
 use secrets_core::ureq;
 use secrets_core::validator::http::{
-    DynFnPostPayloadGenerator, GeneratorResult, HttpResponse, HttpValidator, HttpValidatorBuilder,
-    NextAction, RequestGeneratorBuilder, ResponseParserBuilder, RetryConfig,
+    DynFnPostPayloadGenerator, DynFnResponseParser, GeneratorResult, HttpResponse, HttpValidator,
+    HttpValidatorBuilder, NextAction, RequestGenerator, RequestGeneratorBuilder,
+    ResponseParserBuilder, RetryConfig, SharedLimits,
 };
-use secrets_core::validator::{Candidate, ValidatorId};
+use secrets_core::validator::{Candidate, SecretCategory, ValidatorError, ValidatorId};
 use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
-use crate::check::Check;
-use crate::rule_file::make_candidate_provider;
+use crate::check::Condition;
 use crate::rule_file::validator::http::{
-    RawCfgSimpleRequest, RawMethod, RawRequest, RawResponseHandler,
+    RawCfgRequestChain, RawCfgSimpleRequest, RawExtractor, RawHandler, RawMethod, RawRequest,
+    RawResponseHandler, RawStep, RawStepResponseHandler,
 };
-use secrets_core::Checker;
+use crate::rule_file::{make_candidate_provider, step_variable, SingletonMap};
+use crate::validator::extract::{Extractor, ExtractorError, EXTRACTED_PREFIX};
+use secrets_core::Validator;
 
 const USER_AGENT: &str = "Datadog/StaticAnalyzer";
 
+/// The extractors of a response, by the name of their variable.
+type Extractors = Arc<HashMap<String, Extractor>>;
+
 /// Builds an [`HttpValidator`] from the user input.
 ///
-/// This is not a fallible action, even if the user input is syntactically correct but invalid for any reason.
-/// Invalid user input will cause a runtime error during validation.
+/// Returns an error if a response extractor is invalid. Otherwise, this is not a fallible action, even if
+/// the user input is syntactically correct but invalid for any reason: invalid user input will cause
+/// a runtime error during validation.
+///
+/// The requests are sent with the `agent`, and count against the `shared_limits`, if any.
 pub fn build_simple_http(
     raw: RawCfgSimpleRequest,
     validator_id: ValidatorId,
     retry_config: &RetryConfig,
-) -> HttpValidator {
+    agent: ureq::Agent,
+    shared_limits: Option<Arc<SharedLimits>>,
+) -> Result<HttpValidator, ExtractorError> {
+    let request_generator = build_request_generator(raw.request, agent);
+
+    let RawResponseHandler {
+        extract,
+        handler_list,
+        default_result,
+    } = raw.response_handler;
+    let extractors = build_extractors(extract)?;
+    let response_handler = add_handlers(ResponseParserBuilder::new(), handler_list, &extractors)
+        .set_default(NextAction::ReturnResult(default_result.into()))
+        .build();
+
+    Ok(build_validator(
+        validator_id,
+        request_generator,
+        response_handler,
+        retry_config,
+        shared_limits,
+    ))
+}
+
+/// Builds a [`RequestChainValidator`] from the user input, with the same error handling as
+/// [`build_simple_http`]. Each step is an [`HttpValidator`] with its own rate limit and retries.
+pub fn build_request_chain(
+    raw: RawCfgRequestChain,
+    validator_id: ValidatorId,
+    retry_config: &RetryConfig,
+    agent: ureq::Agent,
+    shared_limits: Option<Arc<SharedLimits>>,
+) -> Result<RequestChainValidator, ExtractorError> {
+    let default_result: SecretCategory = raw.default_result.into();
+    let steps = raw
+        .steps
+        .into_iter()
+        .map(|step| {
+            let RawStep {
+                id,
+                request,
+                response_handler,
+            } = step;
+            let RawStepResponseHandler {
+                extract,
+                handler_list,
+            } = response_handler;
+            let extractors = build_extractors(extract)?;
+            // A successful response that no handler matches continues the chain, and an error
+            // response that neither the handlers nor the default error handler match ends it.
+            let continue_on_success = |res: &Result<HttpResponse, ureq::Error>| -> NextAction {
+                match res {
+                    Ok(_) => NextAction::Continue,
+                    Err(_) => NextAction::Unhandled,
+                }
+            };
+            let response_handler =
+                add_handlers(ResponseParserBuilder::new(), handler_list, &extractors)
+                    .add_handler(Box::new(continue_on_success))
+                    .set_default(NextAction::ReturnResult(default_result))
+                    .build();
+            let validator = build_validator(
+                ValidatorId::from(format!("{}_{}", validator_id, id)),
+                build_request_generator(request, agent.clone()),
+                response_handler,
+                retry_config,
+                shared_limits.clone(),
+            );
+            Ok(ChainStep {
+                id,
+                validator,
+                extractors,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(RequestChainValidator {
+        validator_id,
+        steps,
+        default_result,
+    })
+}
+
+/// A [`Validator`] that sends a sequence of HTTP requests, where the values extracted from the
+/// response of a step are available to the requests of the following steps.
+pub struct RequestChainValidator {
+    validator_id: ValidatorId,
+    steps: Vec<ChainStep>,
+    /// The result when the last step completes without a result.
+    default_result: SecretCategory,
+}
+
+struct ChainStep {
+    id: String,
+    validator: HttpValidator,
+    extractors: Extractors,
+}
+
+impl Validator for RequestChainValidator {
+    fn id(&self) -> &ValidatorId {
+        &self.validator_id
+    }
+
+    fn validate(&self, mut candidate: Candidate) -> Result<SecretCategory, ValidatorError> {
+        for step in &self.steps {
+            let outcome = step.validator.send(&candidate)?;
+            let (NextAction::Continue, Ok(response)) = (outcome.action, outcome.response) else {
+                return Ok(match outcome.action {
+                    NextAction::ReturnResult(result) => result,
+                    _ => self.default_result,
+                });
+            };
+            for (name, extractor) in step.extractors.iter() {
+                if let Some(value) = extractor.extract(response.body()) {
+                    let value = LocatedString {
+                        inner: value.into_owned(),
+                        byte_span: Default::default(),
+                        point_span: Default::default(),
+                    };
+                    let variable = step_variable(&step.id, name);
+                    candidate.rule_match.captures.insert(variable, value);
+                }
+            }
+        }
+        Ok(self.default_result)
+    }
+}
+
+fn build_request_generator(raw: RawRequest, agent: ureq::Agent) -> RequestGenerator {
     let RawRequest {
         url: template_url,
         headers: template_headers,
         method,
         body,
-    } = raw.request;
+    } = raw;
     let url_generator = Box::new(move |candidate: &Candidate| -> GeneratorResult<String> {
         let provider = make_candidate_provider(candidate);
         Ok(template_url.try_evaluate(&provider).map_err(Box::new)?)
@@ -49,7 +188,6 @@ pub fn build_simple_http(
         );
         boxed
     });
-    let agent = ureq::Agent::new();
     let mut request_generator = match method {
         RawMethod::Get => RequestGeneratorBuilder::http_get(agent, url_generator),
         RawMethod::Post => {
@@ -70,20 +208,33 @@ pub fn build_simple_http(
         }
     }
     request_generator = request_generator.header("User-Agent", USER_AGENT);
-    let request_generator = request_generator.build();
+    request_generator.build()
+}
 
-    let RawResponseHandler {
-        handler_list,
-        default_result,
-    } = raw.response_handler;
+fn build_extractors(
+    raw: Option<BTreeMap<String, SingletonMap<RawExtractor>>>,
+) -> Result<Extractors, ExtractorError> {
+    let extractors = raw
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, raw)| Extractor::from_raw(&raw).map(|extractor| (name, extractor)))
+        .collect::<Result<HashMap<_, _>, _>>()?;
+    Ok(Arc::new(extractors))
+}
 
-    let mut response_handler = ResponseParserBuilder::new();
+/// Adds the user's handlers to the response parser, in order.
+fn add_handlers(
+    mut response_handler: ResponseParserBuilder,
+    handler_list: Vec<RawHandler>,
+    extractors: &Extractors,
+) -> ResponseParserBuilder {
     for raw_handler in handler_list {
         let (raw_check, raw_action) = (raw_handler.on_match, raw_handler.action);
         let raw_action = raw_action.into_inner();
-        let checker = Check::from_raw(&raw_check);
+        let condition = Condition::from_raw(&raw_check);
+        let extractors = Arc::clone(extractors);
         let handler = move |req_result: &Result<HttpResponse, ureq::Error>| -> NextAction {
-            let input = match raw_check.input_variable() {
+            let resolve = |var: &str| match var {
                 "http.response.body" => req_result
                     .as_ref()
                     .map(|resp| Some(Cow::Borrowed(resp.body())))
@@ -96,8 +247,12 @@ pub fn build_simple_http(
                     };
                     response_code.map(|code| Cow::Owned(code.to_string()))
                 }
-                _ => raw_check
-                    .input_variable()
+                var if var.starts_with(EXTRACTED_PREFIX) => var
+                    .strip_prefix(EXTRACTED_PREFIX)
+                    .and_then(|name| extractors.get(name))
+                    .zip(req_result.as_ref().ok())
+                    .and_then(|(extractor, response)| extractor.extract(response.body())),
+                _ => var
                     .strip_prefix("http.response.header.")
                     .and_then(|header| {
                         req_result.as_ref().map_or(None, |response| {
@@ -105,11 +260,8 @@ pub fn build_simple_http(
                         })
                     }),
             };
-            let Some(input) = input else {
-                return NextAction::Unhandled;
-            };
 
-            if checker.check(input.as_bytes()) {
+            if condition.evaluate(&resolve) {
                 raw_action.clone().into()
             } else {
                 NextAction::Unhandled
@@ -117,21 +269,33 @@ pub fn build_simple_http(
         };
         response_handler = response_handler.add_handler(Box::new(handler));
     }
-    response_handler =
-        response_handler.set_default(NextAction::ReturnResult(default_result.into()));
-    let response_handler = response_handler.build();
+    response_handler
+}
 
-    HttpValidatorBuilder::new(validator_id, request_generator, response_handler)
-        .retry_config(retry_config.clone())
-        .build()
+fn build_validator(
+    validator_id: ValidatorId,
+    request_generator: RequestGenerator,
+    response_handler: Box<DynFnResponseParser>,
+    retry_config: &RetryConfig,
+    shared_limits: Option<Arc<SharedLimits>>,
+) -> HttpValidator {
+    let mut builder = HttpValidatorBuilder::new(validator_id, request_generator, response_handler)
+        .retry_config(retry_config.clone());
+    if let Some(limits) = shared_limits {
+        builder = builder.shared_limits(limits);
+    }
+    builder.build()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::rule_file::validator::http::RawCfgSimpleRequest;
-    use crate::validator::http::{build_simple_http, USER_AGENT};
+    use crate::rule_file::validator::http::{RawCfgRequestChain, RawCfgSimpleRequest};
+    use crate::validator::http::{
+        build_request_chain, build_simple_http, RequestChainValidator, USER_AGENT,
+    };
     use httpmock::MockServer;
     use secrets_core::rule::{LocatedString, RuleMatch};
+    use secrets_core::ureq;
     use secrets_core::validator::http::{
         HttpValidator, HttpValidatorError, RetryConfig, RetryPolicy,
     };
@@ -192,14 +356,23 @@ request:
         let cfg: RawCfgSimpleRequest =
             serde_yaml::from_str(&format!("{}\n{}", request_yaml, response_yaml)).unwrap();
         let validator_id: ValidatorId = "http-validator_test-rule".into();
-        let retry_config = RetryConfig {
+        build_simple_http(cfg, validator_id, &retry_config(), ureq::Agent::new(), None).unwrap()
+    }
+
+    fn make_chain_validator(chain_yaml: &str) -> RequestChainValidator {
+        let cfg: RawCfgRequestChain = serde_yaml::from_str(chain_yaml).unwrap();
+        let validator_id: ValidatorId = "http-validator_test-rule".into();
+        build_request_chain(cfg, validator_id, &retry_config(), ureq::Agent::new(), None).unwrap()
+    }
+
+    fn retry_config() -> RetryConfig {
+        RetryConfig {
             max_attempts: 3,
             use_jitter: false,
             policy: RetryPolicy::Fixed {
                 duration: Duration::from_millis(1),
             },
-        };
-        build_simple_http(cfg, validator_id, &retry_config)
+        }
     }
 
     /// Generates a test case that configures a [`MockServer`] to return the specified HTTP response
@@ -501,6 +674,86 @@ response-handler:
         assert!(matches!(err, HttpValidatorError::TimedOut { .. }));
     }
 
+    #[test]
+    fn parse_response_extracted() {
+        let response_yaml = r#"
+response-handler:
+  extract:
+    account-type:
+      json-path: $.account.type
+    scopes:
+      regex: '"scopes": "([a-z,]+)"'
+  handler-list:
+    - on-match:
+        equals:
+          input: ${{ extracted.account-type }}
+          value: admin
+      action:
+        return:
+          secret: VALID
+          severity: ERROR
+    - on-match:
+        contains:
+          input: ${{ extracted.scopes }}
+          substring: write
+      action:
+        return:
+          secret: VALID
+          severity: WARNING
+    - on-match:
+        equals:
+          input: ${{ extracted.undefined }}
+          value: admin
+      action:
+        return:
+          secret: INVALID
+          severity: INFO
+  default-result:
+    secret: VALID
+    severity: NOTICE
+"#;
+        let respond_with = |body: &'static str| {
+            test_response!(
+                response_yaml,
+                respond
+                    .status(200)
+                    .body(body)
+                    .header("Content-Type", "application/json")
+            )
+        };
+        let result = respond_with(r#"{"account": {"type": "admin"}, "scopes": "read"}"#);
+        assert_eq!(result.unwrap(), SecretCategory::Valid(Severity::Error));
+        let result = respond_with(r#"{"account": {"type": "user"}, "scopes": "read,write"}"#);
+        assert_eq!(result.unwrap(), SecretCategory::Valid(Severity::Warning));
+        let result = respond_with(r#"{"account": {"type": "user"}, "scopes": "read"}"#);
+        assert_eq!(result.unwrap(), SecretCategory::Valid(Severity::Notice));
+    }
+
+    /// An invalid extractor is rejected when the validator is built
+    #[test]
+    fn parse_response_invalid_extractor() {
+        let response_yaml = "\
+response-handler:
+  extract:
+    scopes:
+      json-path: scopes[0]
+  handler-list:
+  default-result:
+    secret: INCONCLUSIVE
+    severity: NOTICE
+";
+        let cfg: RawCfgSimpleRequest =
+            serde_yaml::from_str(&format!("{}\n{}", DEFAULT_REQUEST, response_yaml)).unwrap();
+        let result = build_simple_http(
+            cfg,
+            "http-validator_test-rule".into(),
+            &RetryConfig::default(),
+            ureq::Agent::new(),
+            None,
+        );
+        assert!(result.is_err());
+    }
+
     /// Tests that the first matching condition (top-to-bottom) is accepted, even if conflicting conditions follow
     #[test]
     fn parse_response_sequential_order() {
@@ -544,4 +797,87 @@ $$<__cfg(test)_injected_second__>
         let result = test_response!(&response_yaml, respond.status(200).body("forbidden"));
         assert_eq!(result.unwrap(), SecretCategory::Invalid(Severity::Info));
     }
+
+    /// A chain that exchanges a refresh token for an access token, and reads the account type with it
+    const REQUEST_CHAIN: &str = r#"
+steps:
+  - id: exchange
+    request:
+      url: <__cfg(test)_magic_url__>/token
+      method: POST
+      body:
+        data: refresh_token=${{ candidate }}
+        content-type: application/x-www-form-urlencoded
+    response-handler:
+      extract:
+        access-token:
+          json-path: $.access_token
+      handler-list:
+        - on-match:
+            equals:
+              input: ${{ http.response.code }}
+              value: 401
+          action:
+            return:
+              secret: INVALID
+              severity: INFO
+  - id: identity
+    request:
+      url: <__cfg(test)_magic_url__>/me
+      method: GET
+      headers:
+        Authorization: Bearer ${{ steps.exchange.extracted.access-token }}
+    response-handler:
+      extract:
+        account-type:
+          json-path: $.type
+      handler-list:
+        - on-match:
+            equals:
+              input: ${{ extracted.account-type }}
+              value: admin
+          action:
+            return:
+              secret: VALID
+              severity: ERROR
+default-result:
+  secret: VALID
+  severity: WARNING
+"#;
+
+    #[test]
+    fn request_chain() {
+        let run_chain = |exchange_status: u16, account_type: &str| {
+            let ms = MockServer::start();
+            let exchange = ms.mock(|when, then| {
+                when.path("/token").body(format!("refresh_token={}", VALID));
+                then.status(exchange_status)
+                    .body(r#"{"access_token": "abc123"}"#);
+            });
+            let identity = ms.mock(|when, then| {
+                when.path("/me").header("Authorization", "Bearer abc123");
+                then.status(200)
+                    .body(format!(r#"{{"type": "{}"}}"#, account_type));
+            });
+            let yaml = REQUEST_CHAIN.replace("<__cfg(test)_magic_url__>", &ms.base_url());
+            let validator = make_chain_validator(&yaml);
+            let result = validator.validate(to_candidate(VALID, HashMap::new()));
+            (result.unwrap(), exchange.hits(), identity.hits())
+        };
+        // the output of the first step is used by the second one, which returns the result
+        assert_eq!(
+            run_chain(200, "admin"),
+            (SecretCategory::Valid(Severity::Error), 1, 1)
+        );
+        // the last step completes without a result
+        assert_eq!(
+            run_chain(200, "user"),
+            (SecretCategory::Valid(Severity::Warning), 1, 1)
+        );
+        // a step returns a result before the end of the chain
+        assert_eq!(
+            run_chain(401, "admin"),
+            (SecretCategory::Invalid(Severity::Info), 1, 0)
+        );
+    }
 }
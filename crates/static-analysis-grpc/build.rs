@@ -0,0 +1,8 @@
+use std::env;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use a vendored `protoc` so that building the crate doesn't require protobuf to be installed.
+    env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    tonic_build::compile_protos("proto/static_analyzer.proto")?;
+    Ok(())
+}
@@ -0,0 +1,174 @@
+//! The gRPC interface of the analysis server: the generated client and server of the
+//! `datadog.static_analyzer.v1` protobuf package, and the conversions from the kernel model.
+
+use kernel::model::common;
+use kernel::model::rule::{RuleCategory, RuleResult as KernelRuleResult, RuleSeverity};
+use kernel::model::violation;
+
+pub mod v1 {
+    tonic::include_proto!("datadog.static_analyzer.v1");
+}
+
+impl From<common::Position> for v1::Position {
+    fn from(value: common::Position) -> Self {
+        Self {
+            line: value.line,
+            col: value.col,
+        }
+    }
+}
+
+impl From<RuleSeverity> for v1::Severity {
+    fn from(value: RuleSeverity) -> Self {
+        match value {
+            RuleSeverity::Error => Self::Error,
+            RuleSeverity::Warning => Self::Warning,
+            RuleSeverity::Notice => Self::Notice,
+            RuleSeverity::None => Self::None,
+        }
+    }
+}
+
+impl From<RuleCategory> for v1::Category {
+    fn from(value: RuleCategory) -> Self {
+        match value {
+            RuleCategory::BestPractices => Self::BestPractices,
+            RuleCategory::CodeStyle => Self::CodeStyle,
+            RuleCategory::ErrorProne => Self::ErrorProne,
+            RuleCategory::Performance => Self::Performance,
+            RuleCategory::Security => Self::Security,
+            RuleCategory::Unknown => Self::Unspecified,
+        }
+    }
+}
+
+impl From<violation::Edit> for v1::Edit {
+    fn from(value: violation::Edit) -> Self {
+        let edit_type = match value.edit_type {
+            violation::EditType::Add => v1::EditType::Add,
+            violation::EditType::Remove => v1::EditType::Remove,
+            violation::EditType::Update => v1::EditType::Update,
+        };
+        Self {
+            edit_type: edit_type.into(),
+            start: Some(value.start.into()),
+            end: value.end.map(v1::Position::from),
+            content: value.content,
+        }
+    }
+}
+
+impl From<violation::Fix> for v1::Fix {
+    fn from(value: violation::Fix) -> Self {
+        let applicability = match value.applicability {
+            violation::FixApplicability::Automatic => v1::FixApplicability::Automatic,
+            violation::FixApplicability::Suggested => v1::FixApplicability::Suggested,
+            violation::FixApplicability::Manual => v1::FixApplicability::Manual,
+        };
+        Self {
+            description: value.description,
+            edits: value.edits.into_iter().map(v1::Edit::from).collect(),
+            applicability: applicability.into(),
+        }
+    }
+}
+
+impl From<violation::RelatedLocation> for v1::RelatedLocation {
+    fn from(value: violation::RelatedLocation) -> Self {
+        Self {
+            start: Some(value.start.into()),
+            end: Some(value.end.into()),
+            message: value.message,
+        }
+    }
+}
+
+impl From<violation::Violation> for v1::Violation {
+    fn from(value: violation::Violation) -> Self {
+        Self {
+            start: Some(value.start.into()),
+            end: Some(value.end.into()),
+            message: value.message.into(),
+            severity: v1::Severity::from(value.severity).into(),
+            category: v1::Category::from(value.category).into(),
+            fixes: value.fixes.into_iter().map(v1::Fix::from).collect(),
+            tags: value.tags,
+            cwe: value.cwe,
+            documentation_url: value.documentation_url,
+            related_locations: value
+                .related_locations
+                .into_iter()
+                .map(v1::RelatedLocation::from)
+                .collect(),
+            file_level: value.file_level,
+            fingerprint: value.fingerprint,
+        }
+    }
+}
+
+impl From<KernelRuleResult> for v1::RuleResult {
+    fn from(value: KernelRuleResult) -> Self {
+        Self {
+            rule_name: value.rule_name.into(),
+            filename: value.filename.into(),
+            violations: value
+                .violations
+                .into_iter()
+                .map(v1::Violation::from)
+                .collect(),
+            errors: value.errors,
+            execution_error: value.execution_error,
+            execution_time_ms: u64::try_from(value.execution_time_ms).unwrap_or(u64::MAX),
+            degraded: value.degraded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::model::common::Position;
+
+    #[test]
+    fn convert_violation() {
+        let violation = violation::Violation {
+            start: Position { line: 1, col: 2 },
+            end: Position { line: 3, col: 4 },
+            message: "message".into(),
+            severity: RuleSeverity::Warning,
+            category: RuleCategory::Security,
+            fixes: vec![violation::Fix {
+                description: "fix".to_string(),
+                edits: vec![violation::Edit {
+                    start: Position { line: 1, col: 2 },
+                    end: None,
+                    edit_type: violation::EditType::Add,
+                    content: Some("foo".to_string()),
+                }],
+                applicability: violation::FixApplicability::Automatic,
+            }],
+            merged_rules: vec![],
+            tags: vec!["tag".to_string()],
+            cwe: vec![79],
+            documentation_url: None,
+            related_locations: vec![],
+            file_level: false,
+            fingerprint: None,
+            owners: vec![],
+            snippet: None,
+            offsets: None,
+            last_commit: None,
+        };
+        let converted = v1::Violation::from(violation);
+        assert_eq!(converted.start, Some(v1::Position { line: 1, col: 2 }));
+        assert_eq!(converted.severity(), v1::Severity::Warning);
+        assert_eq!(converted.category(), v1::Category::Security);
+        assert_eq!(
+            converted.fixes[0].applicability(),
+            v1::FixApplicability::Automatic
+        );
+        assert_eq!(converted.fixes[0].edits[0].edit_type(), v1::EditType::Add);
+        assert_eq!(converted.fixes[0].edits[0].end, None);
+        assert_eq!(converted.cwe, vec![79]);
+    }
+}
@@ -1,4 +1,5 @@
 use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -78,6 +79,24 @@ fn main() {
             files: vec!["parser.c".to_string()],
             cpp: false,
         },
+        TreeSitterProject {
+            name: "tree-sitter-graphql".to_string(),
+            compilation_unit: "tree-sitter-graphql".to_string(),
+            repository: "https://github.com/bkegley/tree-sitter-graphql.git".to_string(),
+            commit_hash: "master".to_string(),
+            build_dir: "src".into(),
+            files: vec!["parser.c".to_string()],
+            cpp: false,
+        },
+        TreeSitterProject {
+            name: "tree-sitter-groovy".to_string(),
+            compilation_unit: "tree-sitter-groovy".to_string(),
+            repository: "https://github.com/murtaza64/tree-sitter-groovy.git".to_string(),
+            commit_hash: "main".to_string(),
+            build_dir: "src".into(),
+            files: vec!["parser.c".to_string()],
+            cpp: false,
+        },
         TreeSitterProject {
             name: "tree-sitter-hcl".to_string(),
             compilation_unit: "tree-sitter-hcl".to_string(),
@@ -123,6 +142,24 @@ fn main() {
             files: vec!["parser.c".to_string()],
             cpp: false,
         },
+        TreeSitterProject {
+            name: "tree-sitter-objc".to_string(),
+            compilation_unit: "tree-sitter-objc".to_string(),
+            repository: "https://github.com/tree-sitter-grammars/tree-sitter-objc.git".to_string(),
+            commit_hash: "master".to_string(),
+            build_dir: "src".into(),
+            files: vec!["parser.c".to_string()],
+            cpp: false,
+        },
+        TreeSitterProject {
+            name: "tree-sitter-proto".to_string(),
+            compilation_unit: "tree-sitter-proto".to_string(),
+            repository: "https://github.com/treywood/tree-sitter-proto.git".to_string(),
+            commit_hash: "main".to_string(),
+            build_dir: "src".into(),
+            files: vec!["parser.c".to_string()],
+            cpp: false,
+        },
         TreeSitterProject {
             name: "tree-sitter-python".to_string(),
             compilation_unit: "tree-sitter-python".to_string(),
@@ -150,6 +187,15 @@ fn main() {
             files: vec!["parser.c".to_string(), "scanner.c".to_string()],
             cpp: false,
         },
+        TreeSitterProject {
+            name: "tree-sitter-xml".to_string(),
+            compilation_unit: "tree-sitter-xml".to_string(),
+            repository: "https://github.com/tree-sitter-grammars/tree-sitter-xml.git".to_string(),
+            build_dir: "xml/src".into(),
+            commit_hash: "master".to_string(),
+            files: vec!["parser.c".to_string(), "scanner.c".to_string()],
+            cpp: false,
+        },
         TreeSitterProject {
             name: "tree-sitter-yaml".to_string(),
             compilation_unit: "tree-sitter-yaml-parser".to_string(),
@@ -205,4 +251,18 @@ fn main() {
         compile_project(proj);
         env::set_current_dir(&base_dir).unwrap();
     }
+
+    // Embed the revisions of the grammars, to report the grammar a query is incompatible with.
+    let grammars = tree_sitter_projects
+        .iter()
+        .map(|proj| {
+            format!(
+                "    ({:?}, {:?}, {:?}),\n",
+                proj.name, proj.repository, proj.commit_hash
+            )
+        })
+        .collect::<String>();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out_dir.join("grammars.rs"), format!("&[\n{grammars}]\n"))
+        .expect("failed to write the grammar versions");
 }
@@ -1,6 +1,15 @@
 pub mod analyze;
+pub mod cancellation;
+#[cfg(feature = "js-runtime")]
 pub mod ddsa_lib;
 pub mod file_context;
 pub mod generated_content;
+#[cfg(feature = "js-runtime")]
 pub mod javascript;
+pub mod normalization;
+pub mod parser_pool;
+pub mod rule_runtime;
+pub mod sfc;
 pub mod tree_sitter;
+#[cfg(feature = "wasm-runtime")]
+pub mod wasm_runtime;
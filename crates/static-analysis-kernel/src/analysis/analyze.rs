@@ -1,90 +1,155 @@
+use crate::analysis::cancellation::CancellationToken;
 use crate::analysis::file_context::common::get_file_context;
 use crate::analysis::generated_content::is_generated_file;
-use crate::analysis::javascript::execute_rule;
-use crate::analysis::tree_sitter::{get_query_nodes, get_tree};
+use crate::analysis::normalization::NormalizedCode;
+use crate::analysis::rule_runtime::{rule_runtime, RuleRuntime};
+use crate::analysis::tree_sitter::{get_query_nodes_interned, get_tree, get_tree_with, TSQuery};
 use crate::arguments::ArgumentProvider;
-use crate::model::analysis::{AnalysisOptions, FileIgnoreBehavior, LinesToIgnore};
-use crate::model::common::Language;
+use crate::model::analysis::{
+    AnalysisOptions, FileIgnoreBehavior, LinesToIgnore, MatchNode, MatchNodeContext,
+    TreeSitterNode, RULE_PARSE_ERROR,
+};
+use crate::model::common::{ColumnUnit, Language, LineIndex, Position};
 use crate::model::config_file::split_path;
-use crate::model::rule::{RuleInternal, RuleResult};
+use crate::model::rule::{RuleCategory, RuleInternal, RuleResult, RuleSeverity};
+use crate::model::violation::{compute_fingerprint, Violation, ViolationBuilder};
 use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
-/// Split the code and extract all the logic that reports to lines to ignore.
-/// If a no-dd-sa statement occurs on the first line, it applies to the whole file.
-/// Otherwise, it only applies to the line below.
-fn get_lines_to_ignore(code: &str, language: &Language) -> LinesToIgnore {
-    let mut lines_to_ignore_for_all_rules = vec![];
-    let mut lines_to_ignore_per_rules: HashMap<u32, Vec<String>> = HashMap::new();
+/// The markers of the comments that disable rules.
+const SUPPRESSION_MARKERS: &[&str] = &["no-dd-sa", "datadog-disable"];
 
-    let mut line_number = 1u32;
-    let disabling_patterns = match language {
+/// The marker of the comments that disable rules for the whole file, on any line.
+const FILE_SUPPRESSION_MARKER: &str = "no-dd-sa-file";
+
+/// Returns the tokens that start the comments that can disable rules in a language.
+fn comment_openers(language: &Language) -> &'static [&'static str] {
+    match language {
         Language::Python
         | Language::Dockerfile
         | Language::Ruby
         | Language::Terraform
-        | Language::Yaml => {
-            vec!["#no-dd-sa", "#datadog-disable"]
-        }
-        Language::JavaScript | Language::TypeScript => {
-            vec![
-                "//no-dd-sa",
-                "/*no-dd-sa",
-                "//datadog-disable",
-                "/*datadog-disable",
-            ]
-        }
+        | Language::GraphQL
+        | Language::Yaml => &["#"],
+        Language::JavaScript | Language::TypeScript | Language::Groovy => &["//", "/*"],
         Language::Go
         | Language::Rust
         | Language::Csharp
         | Language::Java
         | Language::Kotlin
-        | Language::Swift => {
-            vec!["//no-dd-sa", "//datadog-disable"]
-        }
-        Language::Json => {
-            vec!["impossiblestringtoreach"]
-        }
+        | Language::ObjectiveC
+        | Language::Proto
+        | Language::Swift => &["//"],
+        Language::Xml => &["<!--"],
+        // JSON has no comments
+        Language::Json => &[],
+    }
+}
+
+/// Returns the lines where rules are disabled by a comment that starts with a marker (`no-dd-sa`
+/// or `datadog-disable`), optionally followed by the rules to disable (e.g. `# no-dd-sa ruleset/rule`).
+/// If the comment is on the first line, it applies to the whole file. Otherwise, it applies to the
+/// line below, and also to its own line if it trails the code of the line.
+/// A `no-dd-sa-file` comment applies to the whole file, on any line.
+///
+/// The file is only parsed if it contains a marker. Use [`get_lines_to_ignore_with_tree`] if the
+/// file is already parsed.
+pub fn get_lines_to_ignore(code: &str, language: &Language) -> LinesToIgnore {
+    let has_markers = SUPPRESSION_MARKERS
+        .iter()
+        .any(|marker| code.contains(marker));
+    let tree = if has_markers {
+        get_tree(code, language)
+    } else {
+        None
     };
+    get_lines_to_ignore_with_tree(code, language, tree.as_ref())
+}
+
+/// Same as [`get_lines_to_ignore`], with the tree of the file: a marker that is not in a comment
+/// of the tree (e.g. a marker in a string literal) is ignored. Without a tree, all the markers
+/// that follow a comment opener are used.
+pub fn get_lines_to_ignore_with_tree(
+    code: &str,
+    language: &Language,
+    tree: Option<&tree_sitter::Tree>,
+) -> LinesToIgnore {
+    let mut lines_to_ignore_for_all_rules = vec![];
+    let mut lines_to_ignore_per_rules: HashMap<u32, Vec<String>> = HashMap::new();
     let mut ignore_file_all_rules: bool = false;
     let mut rules_to_ignore: Vec<String> = vec![];
-    for line in code.lines() {
-        let line_without_whitespaces: String =
-            line.chars().filter(|c| !c.is_whitespace()).collect();
-        for p in &disabling_patterns {
-            if line_without_whitespaces.contains(p) {
-                // get the rulesets/rules being referenced on the line
-                let parts: Vec<String> = line
-                    .to_string()
-                    .replace("//", "")
-                    .replace("/*", "")
-                    .replace("*/", "")
-                    .replace('#', "")
-                    .replace("no-dd-sa", "")
-                    .replace("datadog-disable", "")
-                    .replace(':', "")
-                    .replace(',', " ")
-                    .split_whitespace()
-                    .filter(|e| e.contains('/'))
-                    .map(|e| e.to_string())
-                    .collect();
 
-                // no ruleset/rules specified, we just ignore everything
-                if parts.is_empty() {
-                    if line_number == 1 {
-                        ignore_file_all_rules = true;
-                    } else {
-                        lines_to_ignore_for_all_rules.push(line_number + 1);
-                    }
-                } else if line_number == 1 {
-                    rules_to_ignore.extend(parts.clone());
-                } else {
-                    lines_to_ignore_per_rules.insert(line_number + 1, parts.clone());
-                }
+    // only the lines with a marker are read, in the order of the file
+    let openers = comment_openers(language);
+    let mut markers = SUPPRESSION_MARKERS
+        .iter()
+        .flat_map(|marker| code.match_indices(marker))
+        .collect::<Vec<_>>();
+    markers.sort_unstable();
+
+    let mut line_number = 1u32;
+    let mut line_start = 0;
+    let mut last_suppressed_line = 0;
+    for (offset, marker) in markers {
+        line_number += code[line_start..offset]
+            .bytes()
+            .filter(|b| *b == b'\n')
+            .count() as u32;
+        line_start = code[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+        if line_number == last_suppressed_line {
+            continue;
+        }
+        // the marker must start the comment
+        let before_marker = code[line_start..offset].trim_end();
+        let Some(opener) = openers.iter().find(|o| before_marker.ends_with(*o)) else {
+            continue;
+        };
+        let opener_offset = line_start + before_marker.len() - opener.len();
+        if tree.is_some_and(|tree| !is_in_comment(tree, opener_offset)) {
+            continue;
+        }
+        last_suppressed_line = line_number;
+
+        let line_end = code[offset..]
+            .find('\n')
+            .map_or(code.len(), |idx| offset + idx);
+        let is_file_marker = code[offset..line_end]
+            .strip_prefix(FILE_SUPPRESSION_MARKER)
+            .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '-'));
+        let marker_len = if is_file_marker {
+            FILE_SUPPRESSION_MARKER.len()
+        } else {
+            marker.len()
+        };
+        let rules = get_suppressed_rules(&code[offset + marker_len..line_end]);
+        let is_file_level = is_file_marker || line_number == 1;
+        // a comment after the code of the line (like `foo() # no-dd-sa`) also applies to the line
+        let is_trailing = !code[line_start..opener_offset].trim().is_empty();
+        let lines = if is_trailing {
+            line_number..=line_number + 1
+        } else {
+            line_number + 1..=line_number + 1
+        };
+        // no ruleset/rules specified, we just ignore everything
+        if rules.is_empty() {
+            if is_file_level {
+                ignore_file_all_rules = true;
+            } else {
+                lines_to_ignore_for_all_rules.extend(lines);
+            }
+        } else if is_file_level {
+            rules_to_ignore.extend(rules);
+        } else {
+            // the lines may also be suppressed by the comments of the other lines
+            for line in lines {
+                lines_to_ignore_per_rules
+                    .entry(line)
+                    .or_default()
+                    .extend(rules.iter().cloned());
             }
         }
-        line_number += 1;
     }
 
     let ignore_file = if ignore_file_all_rules {
@@ -100,6 +165,256 @@ fn get_lines_to_ignore(code: &str, language: &Language) -> LinesToIgnore {
     }
 }
 
+/// Returns the rules listed after a marker, separated by spaces or commas, and optionally after a
+/// colon (e.g. `no-dd-sa:ruleset/rule1, ruleset/rule2`). The end of a block comment is not part
+/// of the rules.
+fn get_suppressed_rules(text: &str) -> Vec<String> {
+    let text = ["*/", "-->"]
+        .iter()
+        .filter_map(|end| text.find(end))
+        .min()
+        .map_or(text, |end| &text[..end]);
+    text.split(|c: char| c.is_whitespace() || c == ',' || c == ':')
+        .filter(|part| part.contains('/'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns whether the token at the offset is in a comment of the tree, and not in a string. A
+/// token that is in neither (e.g. for a grammar without comment nodes) is considered a comment.
+fn is_in_comment(tree: &tree_sitter::Tree, offset: usize) -> bool {
+    let mut node = tree
+        .root_node()
+        .descendant_for_byte_range(offset, offset + 1);
+    while let Some(current) = node {
+        let kind = current.kind();
+        if kind.contains("comment") || kind.contains("Comment") {
+            return true;
+        }
+        let is_string = ["string", "String", "heredoc", "template", "scalar"]
+            .iter()
+            .any(|string_kind| kind.contains(string_kind));
+        if is_string {
+            return false;
+        }
+        node = current.parent();
+    }
+    true
+}
+
+/// Returns the nodes matched by the queries of a rule (the main query and its named queries), which
+/// are passed to the `visit` function of the rule. The nodes share the code and the filename.
+pub fn get_rule_matches(
+    tree: &tree_sitter::Tree,
+    rule: &RuleInternal,
+    filename: impl Into<Arc<str>>,
+    code: impl Into<Arc<str>>,
+    arguments: &HashMap<String, String>,
+) -> Vec<MatchNode> {
+    QueryMatches::new(tree, filename.into(), code.into()).rule_matches(rule, arguments)
+}
+
+/// The matches of the queries of the rules on a file. A query is executed once per file, and its
+/// matches are given to all the rules with the same query (by the digest of its source), e.g. the
+/// rules of a ruleset that check the same nodes. The nodes captured by several matches or queries
+/// are only mapped once.
+struct QueryMatches<'a> {
+    tree: &'a tree_sitter::Tree,
+    filename: Arc<str>,
+    code: Arc<str>,
+    /// The matches of each query, by digest, without the arguments of the rules.
+    matches: HashMap<Arc<str>, Vec<MatchNode>>,
+    nodes: HashMap<usize, Option<TreeSitterNode>>,
+    /// The number of queries whose matches were reused instead of executed.
+    reused: usize,
+}
+
+impl<'a> QueryMatches<'a> {
+    fn new(tree: &'a tree_sitter::Tree, filename: Arc<str>, code: Arc<str>) -> Self {
+        Self {
+            tree,
+            filename,
+            code,
+            matches: HashMap::new(),
+            nodes: HashMap::new(),
+            reused: 0,
+        }
+    }
+
+    /// Returns the nodes matched by the queries of a rule, as [`get_rule_matches`].
+    fn rule_matches(
+        &mut self,
+        rule: &RuleInternal,
+        arguments: &HashMap<String, String>,
+    ) -> Vec<MatchNode> {
+        let mut nodes = self.query_matches(&rule.tree_sitter_query, arguments);
+        for (query_name, query) in &rule.named_queries {
+            nodes.extend(
+                self.query_matches(query, arguments)
+                    .into_iter()
+                    .map(|node| MatchNode {
+                        query_name: Some(query_name.clone()),
+                        ..node
+                    }),
+            );
+        }
+        nodes
+    }
+
+    fn query_matches(
+        &mut self,
+        query: &TSQuery,
+        arguments: &HashMap<String, String>,
+    ) -> Vec<MatchNode> {
+        let Self {
+            tree,
+            filename,
+            code,
+            matches,
+            nodes,
+            reused,
+        } = self;
+        let execute = |arguments: &HashMap<String, String>,
+                       nodes: &mut HashMap<usize, Option<TreeSitterNode>>| {
+            get_query_nodes_interned(
+                tree,
+                query,
+                Arc::clone(filename),
+                Arc::clone(code),
+                arguments,
+                nodes,
+            )
+        };
+        // a query compiled without its source can't be compared with the other queries
+        let Some(digest) = query.digest() else {
+            return execute(arguments, nodes);
+        };
+        match matches.get(digest) {
+            Some(query_matches) => {
+                *reused += 1;
+                query_matches
+                    .iter()
+                    .map(|node| with_arguments(node, arguments))
+                    .collect()
+            }
+            None => {
+                let query_matches = execute(&HashMap::new(), nodes);
+                let rule_matches = query_matches
+                    .iter()
+                    .map(|node| with_arguments(node, arguments))
+                    .collect();
+                matches.insert(Arc::from(digest), query_matches);
+                rule_matches
+            }
+        }
+    }
+}
+
+/// Returns a copy of a match with the arguments of a rule.
+fn with_arguments(node: &MatchNode, arguments: &HashMap<String, String>) -> MatchNode {
+    MatchNode {
+        captures: node.captures.clone(),
+        captures_list: node.captures_list.clone(),
+        context: MatchNodeContext {
+            code: node.context.code.clone(),
+            filename: Arc::clone(&node.context.filename),
+            arguments: arguments.clone(),
+            editorconfig: node.context.editorconfig.clone(),
+        },
+        query_name: node.query_name.clone(),
+    }
+}
+
+/// Returns the synthetic [`RULE_PARSE_ERROR`] result of a file: a violation for each `ERROR` and
+/// `MISSING` node of its tree, or a file-level violation if it could not be parsed at all.
+/// Returns `None` if the file has no syntax error.
+pub fn get_parse_error_result(
+    tree: Option<&tree_sitter::Tree>,
+    filename: &str,
+    parsing_time_ms: u128,
+) -> Option<RuleResult> {
+    let violations = match tree {
+        None => vec![parse_error_violation(
+            "the file could not be parsed".to_string(),
+            Position { line: 1, col: 1 },
+            Position { line: 1, col: 1 },
+            true,
+        )],
+        Some(tree) => {
+            let mut violations = vec![];
+            collect_syntax_errors(tree.root_node(), &mut violations);
+            if violations.is_empty() {
+                return None;
+            }
+            violations
+        }
+    };
+    Some(RuleResult {
+        rule_name: RULE_PARSE_ERROR.into(),
+        filename: filename.into(),
+        violations,
+        errors: vec![],
+        execution_error: None,
+        execution_time_ms: 0,
+        output: None,
+        parsing_time_ms,
+        query_node_time_ms: 0,
+        degraded: false,
+        profile: None,
+    })
+}
+
+/// Adds a violation for each `ERROR` and `MISSING` node of the subtree. The children of an error,
+/// and the subtrees without errors, are not visited.
+fn collect_syntax_errors(node: tree_sitter::Node, violations: &mut Vec<Violation>) {
+    if node.is_error() || node.is_missing() {
+        let message = if node.is_missing() {
+            format!("syntax error: missing `{}`", node.kind())
+        } else {
+            "syntax error".to_string()
+        };
+        violations.push(parse_error_violation(
+            message,
+            as_position(node.start_position()),
+            as_position(node.end_position()),
+            false,
+        ));
+        return;
+    }
+    if !node.has_error() {
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_syntax_errors(child, violations);
+    }
+}
+
+fn as_position(point: tree_sitter::Point) -> Position {
+    Position {
+        line: point.row as u32 + 1,
+        col: point.column as u32 + 1,
+    }
+}
+
+fn parse_error_violation(
+    message: String,
+    start: Position,
+    end: Position,
+    file_level: bool,
+) -> Violation {
+    ViolationBuilder::default()
+        .start(start)
+        .end(end)
+        .message(message)
+        .severity(RuleSeverity::Warning)
+        .category(RuleCategory::ErrorProne)
+        .fixes(vec![])
+        .file_level(file_level)
+        .build()
+        .expect("all the fields of the violation are set")
+}
+
 // main function
 // 1. Build the context (tree-sitter tree, etc)
 // 2. Run the tree-sitter query and build the object that hold the match
@@ -117,96 +432,299 @@ where
     I: IntoIterator,
     I::Item: Borrow<RuleInternal>,
 {
-    // check if we should ignore the file before doing any more expensive work.
-    if analysis_option.ignore_generated_files && is_generated_file(code, language) {
+    analyze_with(
+        language,
+        rules,
+        filename,
+        code,
+        argument_provider,
+        analysis_option,
+        &CancellationToken::new(),
+    )
+}
+
+/// Same as [`analyze`], but the analysis stops as soon as possible once the token is cancelled.
+/// The rules that were not executed are then reported with [`RuleResult::cancelled`].
+///
+/// The code is shared by the matches passed to the rules: pass an `Arc<str>` to share it with
+/// the caller instead of copying it.
+pub fn analyze_with<I>(
+    language: &Language,
+    rules: I,
+    filename: &str,
+    code: impl Into<Arc<str>>,
+    argument_provider: &ArgumentProvider,
+    analysis_option: &AnalysisOptions,
+    cancellation: &CancellationToken,
+) -> Vec<RuleResult>
+where
+    I: IntoIterator,
+    I::Item: Borrow<RuleInternal>,
+{
+    analyze_with_runtime(
+        language,
+        rules,
+        filename,
+        code,
+        argument_provider,
+        analysis_option,
+        cancellation,
+        rule_runtime(),
+    )
+}
+
+/// Same as [`analyze_with`], with the rules executed by the given runtime instead of the runtime
+/// of the process.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn analyze_with_runtime<I>(
+    language: &Language,
+    rules: I,
+    filename: &str,
+    code: impl Into<Arc<str>>,
+    argument_provider: &ArgumentProvider,
+    analysis_option: &AnalysisOptions,
+    cancellation: &CancellationToken,
+    runtime: &dyn RuleRuntime,
+) -> Vec<RuleResult>
+where
+    I: IntoIterator,
+    I::Item: Borrow<RuleInternal>,
+{
+    let code: Arc<str> = code.into();
+    // check if we should ignore the file before doing any more expensive work: a generated file
+    // is only analyzed by the rules that opt into generated files.
+    let skip_generated =
+        analysis_option.ignore_generated_files && is_generated_file(&code, language);
+    let rules = rules
+        .into_iter()
+        .filter(|rule| {
+            !skip_generated
+                || analysis_option
+                    .generated_files_rules
+                    .contains(&rule.borrow().name)
+        })
+        .collect::<Vec<_>>();
+    if skip_generated && rules.is_empty() {
         if analysis_option.use_debug {
             eprintln!("Skipping generated file {}", filename);
         }
         return vec![];
     }
+    // the rules whose prerequisites are absent from the file are not executed (and have no
+    // result), and the file is not parsed when no rule remains.
+    let rule_count = rules.len();
+    let rules = rules
+        .into_iter()
+        .filter(|rule| rule.borrow().prerequisites.matches(&code))
+        .collect::<Vec<_>>();
+    if analysis_option.use_debug && rules.len() < rule_count {
+        eprintln!(
+            "Skipping {} rule(s) without their prerequisites on file {}",
+            rule_count - rules.len(),
+            filename
+        );
+    }
+    if rules.is_empty() && !analysis_option.report_parse_errors {
+        return vec![];
+    }
 
-    let lines_to_ignore = get_lines_to_ignore(code, language);
+    // The file is analyzed without its byte order mark and with LF line endings, and the positions
+    // of the results are converted back to the positions in the file.
+    let normalized = NormalizedCode::new(&code);
+    let original_code = code;
+    let code = Arc::clone(normalized.code());
+    let to_original = |rule_result: &mut RuleResult| {
+        if !normalized.is_unchanged() {
+            for_each_position(rule_result, |position| {
+                *position = normalized.original_position(position)
+            });
+        }
+    };
 
     let parsing_time = Instant::now();
 
-    let tree = get_tree(code, language);
+    let tree = get_tree_with(&code, language, cancellation);
 
     let parsing_time_ms = parsing_time.elapsed().as_millis();
 
     let split_filename = split_path(filename);
-
-    tree.map_or_else(
-        || {
+    let shared_filename: Arc<str> = Arc::from(filename);
+    let editorconfig = argument_provider.get_editorconfig(filename);
+
+    match tree {
+        None => {
+            if cancellation.is_cancelled() {
+                return rules
+                    .into_iter()
+                    .map(|rule| RuleResult::cancelled(&rule.borrow().name, filename))
+                    .collect();
+            }
             if analysis_option.use_debug {
                 eprintln!("error when parsing source file {filename}");
             }
+            if analysis_option.report_parse_errors {
+                return get_parse_error_result(None, filename, parsing_time_ms)
+                    .into_iter()
+                    .collect();
+            }
             vec![]
-        },
-        |tree| {
-            let file_context = get_file_context(&tree, language, &code.to_string());
-            rules
-                .into_iter()
-                .map(|rule| {
+        }
+        Some(tree) => {
+            // A file with syntax errors is still analyzed, with the tree recovered by tree-sitter
+            // (with `ERROR` and `MISSING` nodes), so that files can be analyzed while they're edited.
+            let degraded = tree.root_node().has_error();
+            if degraded && analysis_option.use_debug {
+                eprintln!("syntax errors in source file {filename}, the results may be incomplete");
+            }
+            let file_context = get_file_context(&tree, language, &code, filename);
+            let lines_to_ignore = get_lines_to_ignore_with_tree(&code, language, Some(&tree));
+
+            // The matches are computed on this thread, and the rules with matches are then
+            // executed by the JavaScript runtimes.
+            let mut query_matches =
+                QueryMatches::new(&tree, Arc::clone(&shared_filename), Arc::clone(&code));
+            let mut rules_to_execute = vec![];
+            let mut rule_results = rules
+                .iter()
+                .enumerate()
+                .map(|(idx, rule)| {
                     let rule = rule.borrow();
+                    if cancellation.is_cancelled() {
+                        return RuleResult::cancelled(&rule.name, filename);
+                    }
                     if analysis_option.use_debug {
                         eprintln!("Apply rule {} file {}", rule.name, filename);
                     }
 
                     let query_node_time = Instant::now();
 
-                    let nodes = get_query_nodes(
-                        &tree,
-                        &rule.tree_sitter_query,
-                        filename,
-                        code,
-                        &argument_provider.get_arguments(&split_filename, &rule.name),
-                    );
+                    let arguments = argument_provider.get_arguments(&split_filename, &rule.name);
+                    let mut nodes = query_matches.rule_matches(rule, &arguments);
+                    if editorconfig.is_some() {
+                        for node in &mut nodes {
+                            node.context.editorconfig = editorconfig.clone();
+                        }
+                    }
 
                     let query_node_time_ms = query_node_time.elapsed().as_millis();
 
-                    if nodes.is_empty() {
-                        RuleResult {
-                            rule_name: rule.name.clone(),
-                            filename: filename.to_string(),
-                            violations: vec![],
-                            errors: vec![],
-                            execution_error: None,
-                            execution_time_ms: 0,
-                            output: None,
-                            parsing_time_ms,
-                            query_node_time_ms,
-                        }
-                    } else {
-                        let mut rule_result = execute_rule(
-                            rule,
-                            nodes,
-                            filename.to_string(),
-                            analysis_option.clone(),
-                            &file_context,
-                        );
-
-                        // filter violations that have been ignored
-                        rule_result.violations.retain(|v| {
-                            !lines_to_ignore.should_filter_rule(rule.name.as_str(), v.start.line)
-                        });
-                        rule_result.query_node_time_ms = query_node_time_ms;
-                        rule_result.parsing_time_ms = parsing_time_ms;
-
-                        rule_result
+                    if !nodes.is_empty() {
+                        rules_to_execute.push((idx, (rule, nodes)));
+                    }
+                    RuleResult {
+                        rule_name: rule.name.as_str().into(),
+                        filename: Arc::clone(&shared_filename).into(),
+                        violations: vec![],
+                        errors: vec![],
+                        execution_error: None,
+                        execution_time_ms: 0,
+                        output: None,
+                        parsing_time_ms,
+                        query_node_time_ms,
+                        degraded,
+                        profile: None,
                     }
                 })
-                .collect()
-        },
-    )
+                .collect::<Vec<_>>();
+            if analysis_option.use_debug && query_matches.reused > 0 {
+                eprintln!(
+                    "Reused the matches of {} query(ies) shared by several rules on file {}",
+                    query_matches.reused, filename
+                );
+            }
+
+            let (indices, rules_to_execute): (Vec<_>, Vec<_>) =
+                rules_to_execute.into_iter().unzip();
+            let executed = runtime.execute_rules(
+                rules_to_execute,
+                filename,
+                analysis_option,
+                &file_context,
+                cancellation,
+            );
+            let lines = code.lines().collect::<Vec<_>>();
+            let line_index = LineIndex::new(&original_code);
+            for (idx, mut rule_result) in indices.into_iter().zip(executed) {
+                // filter violations that have been ignored
+                rule_result.violations.retain(|v| {
+                    !lines_to_ignore
+                        .should_filter_rule(rule_result.rule_name.as_str(), v.start.line)
+                });
+                to_original(&mut rule_result);
+                for violation in &mut rule_result.violations {
+                    violation.fingerprint = (violation.start.line as usize)
+                        .checked_sub(1)
+                        .and_then(|idx| lines.get(idx))
+                        .map(|line| compute_fingerprint(&rule_result.rule_name, filename, line));
+                    violation.offsets = line_index.offsets(&violation.start, &violation.end);
+                }
+                rule_result.query_node_time_ms = rule_results[idx].query_node_time_ms;
+                rule_result.parsing_time_ms = parsing_time_ms;
+                rule_result.degraded = degraded;
+                rule_results[idx] = rule_result;
+            }
+            if analysis_option.report_parse_errors {
+                rule_results.extend(
+                    get_parse_error_result(Some(&tree), filename, parsing_time_ms).map(
+                        |mut result| {
+                            to_original(&mut result);
+                            result
+                        },
+                    ),
+                );
+            }
+            if analysis_option.column_unit != ColumnUnit::Bytes {
+                for rule_result in &mut rule_results {
+                    convert_columns(rule_result, &line_index, analysis_option.column_unit);
+                }
+            }
+            rule_results
+        }
+    }
+}
+
+/// Converts the columns of the positions of a result, computed in bytes by tree-sitter, to `unit`.
+/// A position that doesn't exist in the file is left unchanged.
+pub(crate) fn convert_columns(
+    rule_result: &mut RuleResult,
+    line_index: &LineIndex,
+    unit: ColumnUnit,
+) {
+    for_each_position(rule_result, |position| {
+        if let Some(col) = line_index.col_in(position, unit) {
+            position.col = col;
+        }
+    });
+}
+
+/// Calls `f` on the positions of the violations of a result, and of their related locations and
+/// fixes.
+pub(crate) fn for_each_position(rule_result: &mut RuleResult, mut f: impl FnMut(&mut Position)) {
+    for violation in &mut rule_result.violations {
+        f(&mut violation.start);
+        f(&mut violation.end);
+        for location in &mut violation.related_locations {
+            f(&mut location.start);
+            f(&mut location.end);
+        }
+        for edit in violation.fixes.iter_mut().flat_map(|fix| &mut fix.edits) {
+            f(&mut edit.start);
+            if let Some(end) = &mut edit.end {
+                f(end);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
-    use crate::analysis::tree_sitter::get_query;
+    use crate::analysis::tree_sitter::{get_query, get_tree};
+    use crate::editorconfig::EditorConfig;
     use crate::model::common::Language;
-    use crate::model::rule::{RuleCategory, RuleSeverity};
+    use crate::model::rule::{RuleCategory, RuleInternalBuilder, RuleSeverity};
+    use std::collections::HashSet;
 
     const QUERY_CODE: &str = r#"
 (function_definition
@@ -220,6 +738,17 @@ def foo(arg1):
     pass
         "#;
 
+    /// Returns a builder of a Python rule with its name, code and query.
+    fn rule_builder(name: &str, code: &str, query: &str) -> RuleInternalBuilder {
+        RuleInternalBuilder::default()
+            .name(name.to_string())
+            .category(RuleCategory::CodeStyle)
+            .severity(RuleSeverity::Notice)
+            .language(Language::Python)
+            .code(code.to_string())
+            .tree_sitter_query(get_query(query, &Language::Python).unwrap())
+    }
+
     // execution time must be more than 0
     #[test]
     fn test_execution_time() {
@@ -250,12 +779,20 @@ function visit(node, filename, code) {
             language: Language::Python,
             code: rule_code.to_string(),
             tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let analysis_options = AnalysisOptions {
             log_output: true,
             use_debug: false,
             ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
         };
         let results = analyze(
             &Language::Python,
@@ -316,6 +853,9 @@ function visit(node, filename, code) {
             language: Language::Python,
             code: rule_code1.to_string(),
             tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
         let rule2 = RuleInternal {
             name: "myrule".to_string(),
@@ -326,12 +866,20 @@ function visit(node, filename, code) {
             language: Language::Python,
             code: rule_code2.to_string(),
             tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let analysis_options = AnalysisOptions {
             log_output: true,
             use_debug: false,
             ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
         };
         let results = analyze(
             &Language::Python,
@@ -427,12 +975,20 @@ for(var i = 0; i <= 10; i--){}
             language: Language::JavaScript,
             code: rule_code1.to_string(),
             tree_sitter_query: get_query(tree_sitter_query, &Language::JavaScript).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let analysis_options = AnalysisOptions {
             log_output: true,
             use_debug: false,
             ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
         };
         let results = analyze(
             &Language::JavaScript,
@@ -482,12 +1038,20 @@ def foo():
             language: Language::Python,
             code: rule_code1.to_string(),
             tree_sitter_query: get_query(tree_sitter_query, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let analysis_options = AnalysisOptions {
             log_output: true,
             use_debug: false,
             ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
         };
         let results = analyze(
             &Language::Python,
@@ -537,12 +1101,20 @@ def foo(arg1):
             language: Language::Python,
             code: rule_code.to_string(),
             tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let analysis_options = AnalysisOptions {
             log_output: true,
             use_debug: false,
             ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
         };
         let results = analyze(
             &Language::Python,
@@ -584,6 +1156,20 @@ def foo(arg1):
         // python
         let code = format!("\n\t#no-dd-sa:{rule}");
         assert_lines_to_ignore(code, Language::Python, rule);
+        // objective-c
+        let code = format!("\n\t//no-dd-sa:{rule}");
+        assert_lines_to_ignore(code, Language::ObjectiveC, rule);
+        // proto
+        let code = format!("\n\t//no-dd-sa:{rule}");
+        assert_lines_to_ignore(code, Language::Proto, rule);
+        // graphql
+        let code = format!("\n\t#no-dd-sa:{rule}");
+        assert_lines_to_ignore(code, Language::GraphQL, rule);
+        // xml
+        let code = format!("\n\t<!--no-dd-sa:{rule}-->");
+        assert_lines_to_ignore(code, Language::Xml, rule);
+        let code = format!("\n\t<!-- no-dd-sa {rule} -->");
+        assert_lines_to_ignore(code, Language::Xml, rule);
     }
 
     #[test]
@@ -712,12 +1298,20 @@ function visit(node, filename, code) {
             language: Language::Go,
             code: rule_code.to_string(),
             tree_sitter_query: get_query(query, &Language::Go).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let analysis_options = AnalysisOptions {
             log_output: true,
             use_debug: false,
             ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
         };
         let results = analyze(
             &Language::Go,
@@ -730,12 +1324,580 @@ function visit(node, filename, code) {
 
         assert_eq!(1, results.len());
         let result = results.get(0).unwrap();
-        let output = result.output.clone().unwrap();
+        let output = result.output_text().unwrap();
         assert_eq!(result.violations.len(), 1);
         assert!(output.contains("\"math/rand\""));
         assert!(output.contains("\"crypto/rand\""));
     }
 
+    #[test]
+    fn test_hcl_file_context() {
+        let code = r#"
+source "amazon-ebs" "ubuntu" {
+  ami_name = "packer-example"
+}
+        "#;
+
+        let query = r#"(block) @block"#;
+
+        let rule_code = r#"
+function visit(node, filename, code) {
+    const n = node.captures["block"];
+    if(node.context.file_kind === "packer") {
+        const error = buildError(n.start.line, n.start.col, n.end.line, n.end.col, "packer block", "CRITICAL", "security");
+        addError(error);
+    }
+}
+        "#;
+
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::Security,
+            severity: RuleSeverity::Notice,
+            language: Language::Terraform,
+            code: rule_code.to_string(),
+            tree_sitter_query: get_query(query, &Language::Terraform).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+
+        let analysis_options = AnalysisOptions {
+            log_output: true,
+            use_debug: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        for (filename, expected_violations) in [("ami.pkr.hcl", 1), ("main.tf", 0)] {
+            let results = analyze(
+                &Language::Terraform,
+                [&rule],
+                filename,
+                code,
+                &ArgumentProvider::new(),
+                &analysis_options,
+            );
+            assert_eq!(results[0].violations.len(), expected_violations);
+        }
+    }
+
+    #[test]
+    fn test_named_queries() {
+        let code = r#"
+import os
+os.system("ls")
+eval("1 + 1")
+        "#;
+
+        let rule_code = r#"
+function visit(node, filename, code, queryName) {
+    const n = node.captures["call"];
+    addError(buildError(n.start.line, n.start.col, n.end.line, n.end.col, `${queryName}`, "CRITICAL", "security"));
+}
+        "#;
+
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::Security,
+            severity: RuleSeverity::Error,
+            language: Language::Python,
+            code: rule_code.to_string(),
+            tree_sitter_query: get_query("", &Language::Python).unwrap(),
+            named_queries: vec![
+                (
+                    "system".to_string(),
+                    get_query(
+                        r#"(call function: (attribute attribute: (identifier) @name (#eq? @name "system"))) @call"#,
+                        &Language::Python,
+                    )
+                    .unwrap(),
+                ),
+                (
+                    "eval".to_string(),
+                    get_query(
+                        r#"(call function: (identifier) @name (#eq? @name "eval")) @call"#,
+                        &Language::Python,
+                    )
+                    .unwrap(),
+                ),
+            ],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+
+        let analysis_options = AnalysisOptions {
+            log_output: true,
+            use_debug: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let results = analyze(
+            &Language::Python,
+            [&rule],
+            "myfile.py",
+            code,
+            &ArgumentProvider::new(),
+            &analysis_options,
+        );
+        assert_eq!(results.len(), 1);
+        let violations = &results[0].violations;
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].message, "system");
+        assert_eq!(violations[0].start.line, 3);
+        assert_eq!(violations[1].message, "eval");
+        assert_eq!(violations[1].start.line, 4);
+    }
+
+    // the matches of a rule can be computed without executing it
+    #[test]
+    fn test_get_rule_matches() {
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: String::new(),
+            tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![(
+                "pass".to_string(),
+                get_query("(pass_statement) @pass", &Language::Python).unwrap(),
+            )],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let tree = get_tree(PYTHON_CODE, &Language::Python).unwrap();
+        let nodes = get_rule_matches(&tree, &rule, "myfile.py", PYTHON_CODE, &HashMap::new());
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes[0].captures.contains_key("name"));
+        assert_eq!(nodes[0].query_name, None);
+        assert_eq!(nodes[1].query_name.as_deref(), Some("pass"));
+
+        // the queries shared by the rules are executed once, with the arguments of each rule
+        let other_rule = rule_builder("otherrule", "", "(pass_statement) @pass")
+            .build()
+            .unwrap();
+        let mut query_matches =
+            QueryMatches::new(&tree, Arc::from("myfile.py"), Arc::from(PYTHON_CODE));
+        assert_eq!(query_matches.rule_matches(&rule, &HashMap::new()).len(), 2);
+        let arguments = HashMap::from([("max".to_string(), "2".to_string())]);
+        let nodes = query_matches.rule_matches(&other_rule, &arguments);
+        assert_eq!(query_matches.reused, 1);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].query_name, None);
+        assert_eq!(nodes[0].context.arguments, arguments);
+        assert_eq!(query_matches.rule_matches(&rule, &HashMap::new()).len(), 2);
+        assert_eq!(query_matches.reused, 3);
+    }
+
+    // the fingerprint of a violation doesn't change when lines are added above it
+    #[test]
+    fn test_violation_fingerprint() {
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: r#"
+function visit(node, filename, code) {
+    const functionName = node.captures["name"];
+    addError(buildError(functionName.start.line, functionName.start.col, functionName.end.line, functionName.end.col, "invalid name"));
+}"#
+            .to_string(),
+            tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let analysis_options = AnalysisOptions {
+            log_output: false,
+            use_debug: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let fingerprint = |code: &str| {
+            let results = analyze(
+                &Language::Python,
+                [&rule],
+                "myfile.py",
+                code,
+                &ArgumentProvider::new(),
+                &analysis_options,
+            );
+            results[0].violations[0].fingerprint.clone().unwrap()
+        };
+        let original = fingerprint("def foo(arg1):\n    pass\n");
+        assert_eq!(
+            original,
+            compute_fingerprint("myrule", "myfile.py", "def foo(arg1):")
+        );
+        assert_eq!(
+            original,
+            fingerprint("import os\n\ndef foo(arg1):\n    pass\n")
+        );
+    }
+
+    // the columns of the violations are reported in the unit of the options
+    #[test]
+    fn test_column_unit() {
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: r#"
+function visit(node, filename, code) {
+    const name = node.captures["name"];
+    addError(buildError(name.start.line, name.start.col, name.end.line, name.end.col, "call"));
+}"#
+            .to_string(),
+            tree_sitter_query: get_query("(call function: (identifier) @name)", &Language::Python)
+                .unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let code = "x = \"é😀\" + foo(1)\n";
+        let columns = |column_unit: ColumnUnit| {
+            let analysis_options = AnalysisOptions {
+                log_output: false,
+                use_debug: false,
+                ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
+            };
+            let results = analyze(
+                &Language::Python,
+                [&rule],
+                "myfile.py",
+                code,
+                &ArgumentProvider::new(),
+                &analysis_options,
+            );
+            let violation = &results[0].violations[0];
+            (violation.start.col, violation.end.col)
+        };
+        assert_eq!(columns(ColumnUnit::Bytes), (16, 19));
+        assert_eq!(columns(ColumnUnit::Chars), (12, 15));
+        assert_eq!(columns(ColumnUnit::Utf16), (13, 16));
+    }
+
+    // the files with a byte order mark and CRLF line endings are analyzed without them, and the
+    // positions are the positions in the file
+    #[test]
+    fn test_normalized_code() {
+        let rule = rule_builder(
+            "myrule",
+            r#"
+function visit(node, filename, code) {
+    const name = node.captures["name"];
+    if (code.includes("\r")) {
+        return;
+    }
+    const error = buildError(name.start.line, name.start.col, name.end.line, name.end.col, "call");
+    const edit = buildEdit(name.start.line, name.start.col, name.end.line, name.end.col, "update", "bar");
+    addError(error.addFix(buildFix("use bar", [edit])));
+}"#,
+            "(call function: (identifier) @name)",
+        )
+        .build()
+        .unwrap();
+        let code = "\u{feff}foo(1)\r\nfoo(2)\r\n";
+        let results = analyze(
+            &Language::Python,
+            [&rule],
+            "myfile.py",
+            code,
+            &ArgumentProvider::new(),
+            &AnalysisOptions::default(),
+        );
+        let violations = &results[0].violations;
+        assert_eq!(violations.len(), 2);
+        let positions = violations
+            .iter()
+            .map(|v| (v.start.line, v.start.col, v.end.col))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(1, 4, 7), (2, 1, 4)]);
+        let edit = &violations[0].fixes[0].edits[0];
+        assert_eq!((edit.start.col, edit.end.as_ref().unwrap().col), (4, 7));
+        let offsets = violations[1].offsets.as_ref().unwrap();
+        assert_eq!((offsets.start_byte, offsets.end_byte), (11, 14));
+        assert_eq!(&code[offsets.start_byte..offsets.end_byte], "foo");
+
+        // the byte order mark isn't a column in UTF-16 code units
+        let results = analyze(
+            &Language::Python,
+            [&rule],
+            "myfile.py",
+            code,
+            &ArgumentProvider::new(),
+            &AnalysisOptions {
+                column_unit: ColumnUnit::Utf16,
+                ..Default::default()
+            },
+        );
+        let violation = &results[0].violations[0];
+        assert_eq!((violation.start.col, violation.end.col), (1, 4));
+        let offsets = violation.offsets.as_ref().unwrap();
+        assert_eq!((offsets.start_byte, offsets.start_utf16_col), (3, 0));
+    }
+
+    // a cancelled analysis doesn't execute the rules, and reports them as cancelled
+    #[test]
+    fn test_analyze_cancelled() {
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: "function visit(node, filename, code) { addError(buildError(1, 1, 1, 2, \"error\")); }"
+                .to_string(),
+            tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let analysis_options = AnalysisOptions {
+            log_output: false,
+            use_debug: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let results = analyze_with(
+            &Language::Python,
+            &vec![rule],
+            "myfile.py",
+            PYTHON_CODE,
+            &ArgumentProvider::new(),
+            &analysis_options,
+            &cancellation,
+        );
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_cancelled());
+        assert!(results[0].violations.is_empty());
+    }
+
+    // generated files are only analyzed by the rules that opt into them
+    #[test]
+    fn test_generated_files_rules() {
+        let rule = |name: &str| {
+            rule_builder(
+                name,
+                "function visit(node, filename, code) { addError(buildError(1, 1, 1, 2, \"error\")); }",
+                QUERY_CODE,
+            )
+            .build()
+            .unwrap()
+        };
+        let rules = vec![rule("ruleset/generated"), rule("ruleset/other")];
+        let mut analysis_options = AnalysisOptions {
+            ignore_generated_files: true,
+            ..Default::default()
+        };
+        let code = format!("# Generated protocol buffer code{PYTHON_CODE}");
+        let analyze_code = |options: &AnalysisOptions| {
+            analyze(
+                &Language::Python,
+                &rules,
+                "myfile_pb2.py",
+                &code,
+                &ArgumentProvider::new(),
+                options,
+            )
+        };
+
+        assert!(analyze_code(&analysis_options).is_empty());
+
+        analysis_options
+            .generated_files_rules
+            .insert("ruleset/generated".to_string());
+        let results = analyze_code(&analysis_options);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].rule_name, "ruleset/generated");
+        assert_eq!(results[0].violations.len(), 1);
+    }
+
+    // the rules are only executed on the files that contain one of their prerequisites
+    #[test]
+    fn test_rule_prerequisites() {
+        use crate::model::rule::{Prerequisite, RulePrerequisites};
+
+        let rule = |name: &str, prerequisites: &[Prerequisite]| {
+            rule_builder(
+                name,
+                "function visit(node, filename, code) { addError(buildError(1, 1, 1, 2, \"error\")); }",
+                QUERY_CODE,
+            )
+            .prerequisites(RulePrerequisites::new(prerequisites).unwrap())
+            .build()
+            .unwrap()
+        };
+        let rules = vec![
+            rule("ruleset/any", &[]),
+            rule(
+                "ruleset/requests",
+                &[Prerequisite::Substring("requests".to_string())],
+            ),
+            rule(
+                "ruleset/urllib",
+                &[
+                    Prerequisite::Substring("httpx".to_string()),
+                    Prerequisite::Regex(r"import\s+urllib".to_string()),
+                ],
+            ),
+        ];
+        let analysis_options = AnalysisOptions::default();
+        let rule_names = |code: &str| {
+            analyze(
+                &Language::Python,
+                &rules,
+                "myfile.py",
+                code,
+                &ArgumentProvider::new(),
+                &analysis_options,
+            )
+            .into_iter()
+            .map(|r| {
+                assert_eq!(r.violations.len(), 1);
+                r.rule_name
+            })
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(rule_names(PYTHON_CODE), vec!["ruleset/any"]);
+        assert_eq!(
+            rule_names(&format!("import  urllib\nimport requests{PYTHON_CODE}")),
+            vec!["ruleset/any", "ruleset/requests", "ruleset/urllib"]
+        );
+        // no rule applies to the file
+        assert!(analyze(
+            &Language::Python,
+            &rules[1..],
+            "myfile.py",
+            PYTHON_CODE,
+            &ArgumentProvider::new(),
+            &analysis_options,
+        )
+        .is_empty());
+    }
+
+    // the syntax errors of a file are reported as a synthetic result, when enabled
+    #[test]
+    fn test_report_parse_errors() {
+        let mut analysis_options = AnalysisOptions {
+            log_output: false,
+            use_debug: false,
+            ignore_generated_files: false,
+            report_parse_errors: true,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let analyze_code = |code: &str, options: &AnalysisOptions| {
+            analyze(
+                &Language::Python,
+                &Vec::<RuleInternal>::new(),
+                "myfile.py",
+                code,
+                &ArgumentProvider::new(),
+                options,
+            )
+        };
+        let invalid_code = "def foo(arg1):\n    pass\n\nx = (1 +\n";
+
+        let results = analyze_code(invalid_code, &analysis_options);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].rule_name, RULE_PARSE_ERROR);
+        assert!(!results[0].violations.is_empty());
+        assert!(results[0].violations.iter().all(|v| v.start.line >= 4));
+
+        assert!(analyze_code(PYTHON_CODE, &analysis_options).is_empty());
+
+        analysis_options.report_parse_errors = false;
+        assert!(analyze_code(invalid_code, &analysis_options).is_empty());
+    }
+
+    // the rules are executed on the tree recovered from a file with syntax errors, and their
+    // results are marked as degraded
+    #[test]
+    fn test_degraded_analysis() {
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: "function visit(node, filename, code) { addError(buildError(1, 1, 1, 2, \"error\")); }"
+                .to_string(),
+            tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let analysis_options = AnalysisOptions {
+            log_output: false,
+            use_debug: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let analyze_code = |code: &str| {
+            analyze(
+                &Language::Python,
+                std::slice::from_ref(&rule),
+                "myfile.py",
+                code,
+                &ArgumentProvider::new(),
+                &analysis_options,
+            )
+        };
+
+        let results = analyze_code(PYTHON_CODE);
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].degraded);
+
+        let results = analyze_code("def foo(arg1):\n    pass\n\nx = (1 +\n");
+        assert_eq!(results.len(), 1);
+        assert!(results[0].degraded);
+        assert_eq!(results[0].violations.len(), 1);
+    }
+
     #[test]
     fn test_get_lines_to_ignore_javascript() {
         // no-dd-sa ruleset1/rule1 on line 3 so we ignore line 4 for ruleset1/rule1
@@ -844,6 +2006,137 @@ line20("foo")
         );
     }
 
+    #[test]
+    fn test_get_lines_to_ignore_trailing_comments() {
+        let code = "\
+import os
+# no-dd-sa ruleset/rule1
+os.system(cmd)  # no-dd-sa:ruleset/rule2
+eval(x) # datadog-disable
+foo()
+bar()
+";
+        let lines_to_ignore = get_lines_to_ignore(code, &Language::Python);
+        assert_eq!(
+            lines_to_ignore.ignore_file,
+            FileIgnoreBehavior::SomeRules(vec![])
+        );
+        // a trailing comment applies to its line and to the line below
+        assert_eq!(lines_to_ignore.lines_to_ignore, vec![4, 5]);
+        assert_eq!(
+            lines_to_ignore.lines_to_ignore_per_rule,
+            HashMap::from([
+                (
+                    3,
+                    vec!["ruleset/rule1".to_string(), "ruleset/rule2".to_string()]
+                ),
+                (4, vec!["ruleset/rule2".to_string()])
+            ])
+        );
+        assert!(lines_to_ignore.should_filter_rule("ruleset/rule2", 3));
+        assert!(lines_to_ignore.should_filter_rule("ruleset/rule2", 4));
+        assert!(!lines_to_ignore.should_filter_rule("ruleset/rule2", 6));
+
+        let code = "let a = 1;\nfoo(); /* no-dd-sa */\nbar(); // no-dd-sa ruleset/rule\nbaz();\n";
+        let lines_to_ignore = get_lines_to_ignore(code, &Language::JavaScript);
+        assert_eq!(lines_to_ignore.lines_to_ignore, vec![2, 3]);
+        assert_eq!(
+            lines_to_ignore.lines_to_ignore_per_rule,
+            HashMap::from([
+                (3, vec!["ruleset/rule".to_string()]),
+                (4, vec!["ruleset/rule".to_string()])
+            ])
+        );
+    }
+
+    /// A trailing comment still suppresses the line below, and a comment on the first line still
+    /// applies to the whole file, as before the comments applied to their own line.
+    #[test]
+    fn test_get_lines_to_ignore_trailing_comments_previous_behavior() {
+        let code = "import os # no-dd-sa\nfoo()\n";
+        let lines_to_ignore = get_lines_to_ignore(code, &Language::Python);
+        assert_eq!(lines_to_ignore.ignore_file, FileIgnoreBehavior::AllRules);
+
+        let code = "import os\nfoo()  # no-dd-sa ruleset/rule\nbar()\nbaz()\n";
+        let lines_to_ignore = get_lines_to_ignore(code, &Language::Python);
+        assert!(lines_to_ignore.should_filter_rule("ruleset/rule", 3));
+        assert!(!lines_to_ignore.should_filter_rule("ruleset/rule", 4));
+        assert!(!lines_to_ignore.should_filter_rule("ruleset/other", 3));
+    }
+
+    #[test]
+    fn test_get_lines_to_ignore_file_marker() {
+        let code = "\
+#!/usr/bin/env python
+# Copyright 2024 Datadog, Inc.
+# no-dd-sa-file ruleset/rule1, ruleset/rule2
+
+import os # no-dd-sa-file ruleset/rule3
+# no-dd-sa-filename
+foo()
+";
+        let lines_to_ignore = get_lines_to_ignore(code, &Language::Python);
+        assert_eq!(
+            lines_to_ignore.ignore_file,
+            FileIgnoreBehavior::SomeRules(vec![
+                "ruleset/rule1".to_string(),
+                "ruleset/rule2".to_string(),
+                "ruleset/rule3".to_string()
+            ])
+        );
+        // `no-dd-sa-filename` is not the file marker
+        assert_eq!(lines_to_ignore.lines_to_ignore, vec![7]);
+        assert!(lines_to_ignore.lines_to_ignore_per_rule.is_empty());
+
+        let code = "package main\n\n// no-dd-sa-file\nfunc main() {}\n";
+        let lines_to_ignore = get_lines_to_ignore(code, &Language::Go);
+        assert_eq!(lines_to_ignore.ignore_file, FileIgnoreBehavior::AllRules);
+        assert!(lines_to_ignore.lines_to_ignore.is_empty());
+    }
+
+    #[test]
+    fn test_get_lines_to_ignore_not_in_comments() {
+        let code = r##"
+x = "# no-dd-sa"
+y = 1
+z = """
+# no-dd-sa ruleset/rule
+"""
+foo() # no-dd-sa ruleset/rule
+bar()
+"##;
+        // the markers in strings are ignored
+        let lines_to_ignore = get_lines_to_ignore(code, &Language::Python);
+        assert!(lines_to_ignore.lines_to_ignore.is_empty());
+        assert_eq!(
+            lines_to_ignore.lines_to_ignore_per_rule,
+            HashMap::from([
+                (7, vec!["ruleset/rule".to_string()]),
+                (8, vec!["ruleset/rule".to_string()])
+            ])
+        );
+
+        // without the tree, the strings cannot be told apart from the comments
+        let lines_to_ignore = get_lines_to_ignore_with_tree(code, &Language::Python, None);
+        assert_eq!(lines_to_ignore.lines_to_ignore, vec![2, 3]);
+        assert_eq!(lines_to_ignore.lines_to_ignore_per_rule.len(), 3);
+
+        // a marker must start the comment
+        let code = "foo()\n// see no-dd-sa\nbar()\n/* no-dd-sa:ruleset/rule */\nbaz()\n";
+        let lines_to_ignore = get_lines_to_ignore(code, &Language::JavaScript);
+        assert!(lines_to_ignore.lines_to_ignore.is_empty());
+        assert_eq!(
+            lines_to_ignore.lines_to_ignore_per_rule,
+            HashMap::from([(5, vec!["ruleset/rule".to_string()])])
+        );
+
+        assert!(
+            get_lines_to_ignore("{\"a\": \"// no-dd-sa\"}", &Language::Json)
+                .lines_to_ignore
+                .is_empty()
+        );
+    }
+
     #[test]
     fn test_argument_values() {
         let rule_code = r#"
@@ -869,6 +2162,9 @@ function visit(node, filename, code) {
             language: Language::Python,
             code: rule_code.to_string(),
             tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
         let rule2 = RuleInternal {
             name: "rule2".to_string(),
@@ -879,12 +2175,20 @@ function visit(node, filename, code) {
             language: Language::Python,
             code: rule_code.to_string(),
             tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let analysis_options = AnalysisOptions {
             log_output: true,
             use_debug: false,
             ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
         };
         let mut argument_provider = ArgumentProvider::new();
         argument_provider.add_argument("rule1", &split_path("myfile.py"), "my-argument", "101");
@@ -906,4 +2210,61 @@ function visit(node, filename, code) {
         assert!(result1.violations[0].message.contains("argument = 101"));
         assert_eq!(result2.violations.len(), 0);
     }
+
+    /// The `.editorconfig` settings of the file are in the context of the nodes.
+    #[test]
+    fn test_editorconfig_values() {
+        let rule_code = r#"
+function visit(node, filename, code) {
+    const functionName = node.captures["name"];
+    const editorconfig = node.context.editorconfig;
+    if (editorconfig !== undefined) {
+        const error = buildError(
+            functionName.start.line, functionName.start.col,
+            functionName.end.line, functionName.end.col,
+            `max = ${editorconfig.max_line_length}, style = ${editorconfig.indent_style}`);
+        addError(error);
+    }
+}
+        "#;
+
+        let rule = RuleInternal {
+            name: "rule1".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: rule_code.to_string(),
+            tree_sitter_query: get_query(QUERY_CODE, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let mut editorconfig = EditorConfig::new();
+        editorconfig.add_file("", "[*.py]\nmax_line_length = 88\nindent_style = space\n");
+        let mut argument_provider = ArgumentProvider::new();
+        argument_provider.set_editorconfig(editorconfig);
+
+        let results = analyze(
+            &Language::Python,
+            [&rule],
+            "myfile.py",
+            PYTHON_CODE,
+            &argument_provider,
+            &AnalysisOptions::default(),
+        );
+        assert_eq!(results[0].violations[0].message, "max = 88, style = space");
+
+        // a file without settings has no `editorconfig` in its context
+        let results = analyze(
+            &Language::Python,
+            [&rule],
+            "myfile.py",
+            PYTHON_CODE,
+            &ArgumentProvider::new(),
+            &AnalysisOptions::default(),
+        );
+        assert!(results[0].violations.is_empty());
+    }
 }
@@ -0,0 +1,49 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A token used to cancel an in-flight analysis. Clones of a token share the same state, so a
+/// token can be cancelled from another thread (e.g. a signal handler) than the one analyzing.
+///
+/// The cancellation is cooperative: the analysis checks the token while parsing, between rules,
+/// and between batches of rules, and the rules that were not executed are reported with the
+/// [`ERROR_ANALYSIS_CANCELLED`](crate::model::analysis::ERROR_ANALYSIS_CANCELLED) error.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    /// A non-zero value means that the analysis is cancelled. This is the convention of the
+    /// cancellation flag of tree-sitter, so that the flag can be shared with the parser.
+    flag: Arc<AtomicUsize>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the analyses that use this token.
+    pub fn cancel(&self) {
+        self.flag.store(1, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed) != 0
+    }
+
+    /// Returns the flag of the token. A non-zero value means that the analysis is cancelled.
+    pub fn flag(&self) -> &Arc<AtomicUsize> {
+        &self.flag
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_shared_token() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+}
@@ -12,6 +12,9 @@ pub(crate) mod js;
 pub(crate) mod ops;
 pub(crate) mod runtime;
 pub(crate) use runtime::JsRuntime;
+pub mod runtime_pool;
+pub use runtime_pool::JsRuntimePool;
+pub(crate) mod string_value;
 #[allow(dead_code)]
 mod test_utils;
 pub mod v8_ds;
@@ -80,6 +80,7 @@ impl ContextBridge {
         // we never mutate trees (or nodes).
         if self.root.ddsa.get_tree().map(|ex| ex.root_node().id()) != Some(tree.root_node().id()) {
             self.root.ddsa.set_tree(tree.clone());
+            self.root.js.clear_scopes_cache(scope);
             // Update file contexts
             self.file
                 .ddsa
@@ -91,6 +92,7 @@ impl ContextBridge {
             self.root.ddsa.set_text(Arc::clone(file_contents));
             // The cache is populated lazily, so a change in value means we need to clear the cache.
             self.root.js.set_file_contents_cache(scope, None);
+            self.root.js.clear_scopes_cache(scope);
         }
         if self.root.ddsa.get_filename() != Some(filename.as_ref()) {
             self.root.ddsa.set_filename(Arc::clone(filename));
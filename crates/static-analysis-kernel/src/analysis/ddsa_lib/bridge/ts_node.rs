@@ -99,6 +99,8 @@ impl TsNodeBridge {
             start_col: start_col as u32 + 1,
             end_line: end_line as u32 + 1,
             end_col: end_col as u32 + 1,
+            start_byte: node.start_byte() as u32,
+            end_byte: node.end_byte() as u32,
             node_type_id: node.grammar_id(),
             _pd: PhantomData,
         };
@@ -152,8 +154,7 @@ impl TsNodeBridge {
     }
 
     /// Looks up a value in the v8_map, returning the value as a v8 object.
-    #[cfg(test)]
-    fn v8_get<'s>(
+    pub fn v8_get<'s>(
         &self,
         scope: &mut HandleScope<'s>,
         id: NodeId,
@@ -173,6 +174,7 @@ mod tests {
     use crate::analysis::ddsa_lib::common::{attach_as_global, get_field, v8_interned};
     use crate::analysis::ddsa_lib::test_utils::{cfg_test_runtime, try_execute};
     use crate::analysis::ddsa_lib::RawTSNode;
+    use crate::analysis::parser_pool::with_parser;
     use crate::analysis::tree_sitter::get_tree_sitter_language;
     use crate::model::common::Language;
     use deno_core::v8;
@@ -208,20 +210,18 @@ mod tests {
         }
     }
 
-    /// A newtype [`tree_sitter::Parser`] that constructs a [`Tree`].
-    struct TsParser(tree_sitter::Parser);
+    /// A parser of a [`Language`] that constructs a [`Tree`] with a parser of the pool.
+    struct TsParser(Language);
     impl TsParser {
         fn new(language: Language) -> Self {
-            let lang = get_tree_sitter_language(&language);
-            let mut parser = tree_sitter::Parser::new();
-            parser.set_language(&lang).unwrap();
-            Self(parser)
+            Self(language)
         }
         fn parse(&mut self, text: &'static str) -> Tree {
-            Tree(self.0.parse(text, None).unwrap(), text)
+            let tree = with_parser(&self.0, |parser| parser.parse(text, None)).flatten();
+            Tree(tree.unwrap(), text)
         }
         fn language(&self) -> tree_sitter::Language {
-            self.0.language().unwrap()
+            get_tree_sitter_language(&self.0)
         }
     }
 
@@ -10,3 +10,5 @@ mod root;
 pub use root::RootContext;
 mod rule;
 pub use rule::RuleContext;
+pub(crate) mod scopes;
+pub use scopes::ScopeTable;
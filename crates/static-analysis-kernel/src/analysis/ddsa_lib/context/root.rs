@@ -2,6 +2,9 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
+use crate::analysis::ddsa_lib::context::ScopeTable;
+use crate::model::common::Position;
+use std::cell::OnceCell;
 use std::sync::Arc;
 
 /// A stateful struct containing metadata about a ddsa rule execution.
@@ -13,6 +16,10 @@ pub struct RootContext {
     tree: Option<tree_sitter::Tree>,
     /// The source string that was parsed by tree-sitter to generate `tree`.
     tree_text: Option<Arc<str>>,
+    /// The byte offsets of the start of each line of `tree_text`, computed the first time they are needed.
+    line_starts: OnceCell<Vec<usize>>,
+    /// The symbol table of `tree`, computed the first time it is needed.
+    scopes: OnceCell<ScopeTable>,
     /// A filename associated with a rule execution.
     filename: Option<Arc<str>>,
 }
@@ -26,9 +33,81 @@ impl RootContext {
     /// Assigns the provided text string to the context. If an existing text string was assigned, it
     /// will be returned as `Some(text)`.
     pub fn set_text(&mut self, text: Arc<str>) -> Option<Arc<str>> {
+        self.line_starts = OnceCell::new();
+        self.scopes = OnceCell::new();
         Option::replace(&mut self.tree_text, text)
     }
 
+    /// Returns the text between two positions (1-based line and 1-based byte column, like the
+    /// positions of tree-sitter nodes). The `end` position is exclusive.
+    ///
+    /// Returns `None` if there is no text, if a position is outside the text, or if the positions
+    /// are not ordered.
+    pub fn get_text_between(&self, start: &Position, end: &Position) -> Option<&str> {
+        let start = self.byte_offset(start)?;
+        let end = self.byte_offset(end)?;
+        self.get_text()?.get(start..end)
+    }
+
+    /// Returns the text of the lines from `start_line` to `end_line` (1-based, inclusive), without
+    /// the line break of the last line.
+    ///
+    /// Returns `None` if there is no text, if a line doesn't exist, or if the lines are not ordered.
+    pub fn get_lines(&self, start_line: u32, end_line: u32) -> Option<&str> {
+        let text = self.get_text()?;
+        let start = *self
+            .line_starts()?
+            .get(start_line.checked_sub(1)? as usize)?;
+        let end = self.line_end(end_line)?;
+        text.get(start..end)
+    }
+
+    /// Returns the byte offset of a position in the text.
+    fn byte_offset(&self, position: &Position) -> Option<usize> {
+        let text = self.get_text()?;
+        let line_starts = self.line_starts()?;
+        let idx = position.line.checked_sub(1)? as usize;
+        let offset = *line_starts.get(idx)? + position.col.checked_sub(1)? as usize;
+        // A column can point right after the last character of a line, but not past its `\n`.
+        let max_offset = line_starts
+            .get(idx + 1)
+            .map(|next_start| next_start - 1)
+            .unwrap_or(text.len());
+        (offset <= max_offset).then_some(offset)
+    }
+
+    /// Returns the byte offset of the end of a 1-based line, excluding its line break.
+    fn line_end(&self, line: u32) -> Option<usize> {
+        let text = self.get_text()?;
+        let line_starts = self.line_starts()?;
+        let idx = line.checked_sub(1)? as usize;
+        line_starts.get(idx)?;
+        let end = match line_starts.get(idx + 1) {
+            // Exclude the `\n` (and a preceding `\r`).
+            Some(&next_start) => {
+                let end = next_start - 1;
+                if text[..end].ends_with('\r') {
+                    end - 1
+                } else {
+                    end
+                }
+            }
+            None => text.len(),
+        };
+        Some(end)
+    }
+
+    /// Returns the byte offsets of the start of each line of the text.
+    fn line_starts(&self) -> Option<&[usize]> {
+        let text = self.get_text()?;
+        let line_starts = self.line_starts.get_or_init(|| {
+            std::iter::once(0)
+                .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+                .collect()
+        });
+        Some(line_starts.as_slice())
+    }
+
     /// Returns a reference to the underlying [`tree_sitter::Tree`], if it exists.
     pub fn get_tree(&self) -> Option<&tree_sitter::Tree> {
         self.tree.as_ref()
@@ -37,9 +116,18 @@ impl RootContext {
     /// Assigns the provided `tree_sitter::Tree` to the context. If an existing tree was assigned, it
     /// will be returned as `Some(tree)`.
     pub fn set_tree(&mut self, tree: tree_sitter::Tree) -> Option<tree_sitter::Tree> {
+        self.scopes = OnceCell::new();
         Option::replace(&mut self.tree, tree)
     }
 
+    /// Returns the symbol table of the tree, computing it if this is the first time it was requested.
+    /// Returns `None` if either the tree or its text has not been set.
+    pub fn get_scopes(&self) -> Option<&ScopeTable> {
+        let tree = self.tree.as_ref()?;
+        let text = self.get_text()?;
+        Some(self.scopes.get_or_init(|| ScopeTable::new(tree, text)))
+    }
+
     /// Returns a reference to the filename assigned to the context.
     pub fn get_filename(&self) -> Option<&str> {
         self.filename.as_ref().map(AsRef::as_ref)
@@ -51,3 +139,60 @@ impl RootContext {
         Option::replace(&mut self.filename, filename)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::ddsa_lib::context::RootContext;
+    use crate::analysis::ddsa_lib::context::ScopeTable;
+    use crate::model::common::Position;
+    use std::sync::Arc;
+
+    fn pos(line: u32, col: u32) -> Position {
+        Position { line, col }
+    }
+
+    #[test]
+    fn get_text_between() {
+        let mut ctx = RootContext::default();
+        assert!(ctx.get_text_between(&pos(1, 1), &pos(1, 2)).is_none());
+        ctx.set_text(Arc::from("const é = 1;\r\nfoo();\nbar();"));
+
+        assert_eq!(ctx.get_text_between(&pos(1, 7), &pos(1, 9)), Some("é"));
+        assert_eq!(
+            ctx.get_text_between(&pos(1, 7), &pos(3, 4)),
+            Some("é = 1;\r\nfoo();\nbar")
+        );
+        // The end of a line
+        assert_eq!(ctx.get_text_between(&pos(2, 1), &pos(2, 7)), Some("foo();"));
+        assert_eq!(ctx.get_text_between(&pos(3, 1), &pos(3, 7)), Some("bar();"));
+        // Past the end of a line
+        assert!(ctx.get_text_between(&pos(2, 1), &pos(2, 9)).is_none());
+        assert!(ctx.get_text_between(&pos(3, 1), &pos(3, 8)).is_none());
+        // Not a char boundary
+        assert!(ctx.get_text_between(&pos(1, 7), &pos(1, 8)).is_none());
+        // Not ordered, or not 1-based
+        assert!(ctx.get_text_between(&pos(2, 1), &pos(1, 1)).is_none());
+        assert!(ctx.get_text_between(&pos(0, 1), &pos(1, 1)).is_none());
+        assert!(ctx.get_text_between(&pos(1, 0), &pos(1, 1)).is_none());
+    }
+
+    #[test]
+    fn get_lines() {
+        let mut ctx = RootContext::default();
+        ctx.set_text(Arc::from("a = 1\r\nb = 2\n\nc = 3\n"));
+
+        assert_eq!(ctx.get_lines(1, 1), Some("a = 1"));
+        assert_eq!(ctx.get_lines(2, 2), Some("b = 2"));
+        assert_eq!(ctx.get_lines(3, 3), Some(""));
+        assert_eq!(ctx.get_lines(1, 4), Some("a = 1\r\nb = 2\n\nc = 3"));
+        assert_eq!(ctx.get_lines(5, 5), Some(""));
+        assert!(ctx.get_lines(6, 6).is_none());
+        assert!(ctx.get_lines(2, 1).is_none());
+        assert!(ctx.get_lines(0, 1).is_none());
+
+        // The line index is recomputed when the text changes.
+        ctx.set_text(Arc::from("x\ny"));
+        assert_eq!(ctx.get_lines(2, 2), Some("y"));
+        assert!(ctx.get_lines(3, 3).is_none());
+    }
+}
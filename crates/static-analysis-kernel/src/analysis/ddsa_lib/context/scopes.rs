@@ -0,0 +1,785 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use crate::analysis::tree_sitter::get_tree_sitter_language;
+use crate::model::common::{Language, Position};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// The index of a [`Scope`] within a [`ScopeTable`].
+pub type ScopeId = usize;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScopeKind {
+    /// The top-level scope of a file.
+    Module,
+    /// The scope of a function, method or lambda.
+    Function,
+    /// The body of a class. Names defined in a class body are not visible from its methods.
+    Class,
+    /// A block (JavaScript only).
+    Block,
+    /// A list, set or dictionary comprehension, or a generator expression (Python only).
+    Comprehension,
+}
+
+/// The range spanned by a node, using 1-based lines and 1-based byte columns.
+#[derive(Debug, Clone, Serialize)]
+pub struct Location {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Location {
+    fn from_node(node: tree_sitter::Node) -> Self {
+        let (start, end) = (node.start_position(), node.end_position());
+        Self {
+            start: Position {
+                line: start.row as u32 + 1,
+                col: start.column as u32 + 1,
+            },
+            end: Position {
+                line: end.row as u32 + 1,
+                col: end.column as u32 + 1,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Scope {
+    pub id: ScopeId,
+    pub kind: ScopeKind,
+    /// The enclosing scope. This is `None` only for the module scope.
+    pub parent: Option<ScopeId>,
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A name bound in a scope, along with the locations where it is defined and referenced.
+#[derive(Debug, Clone, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    pub scope: ScopeId,
+    pub definitions: Vec<Location>,
+    pub references: Vec<Location>,
+}
+
+/// A reference to a name that isn't defined in the file (e.g. a builtin or a global).
+#[derive(Debug, Clone, Serialize)]
+pub struct UnresolvedReference {
+    pub name: String,
+    pub scope: ScopeId,
+    pub location: Location,
+}
+
+/// A basic symbol table for a file: its scopes, the symbols defined in each scope, and the references to them.
+///
+/// This is a syntactic approximation: names are resolved lexically without following imports, and
+/// dynamic constructs (e.g. `eval` or `with`) are ignored. Python and JavaScript (including TypeScript)
+/// are supported. For other languages, the table only contains the module scope.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScopeTable {
+    /// The scopes, in the order that they appear in the file. The module scope is the first.
+    pub scopes: Vec<Scope>,
+    pub symbols: Vec<Symbol>,
+    pub unresolved: Vec<UnresolvedReference>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Syntax {
+    Python,
+    JavaScript,
+    Other,
+}
+
+impl ScopeTable {
+    /// Computes the symbol table of a tree-sitter tree.
+    pub fn new(tree: &tree_sitter::Tree, code: &str) -> Self {
+        let ts_language = tree.language();
+        let syntax = if ts_language == get_tree_sitter_language(&Language::Python) {
+            Syntax::Python
+        } else if [Language::JavaScript, Language::TypeScript]
+            .iter()
+            .any(|lang| ts_language == get_tree_sitter_language(lang))
+        {
+            Syntax::JavaScript
+        } else {
+            Syntax::Other
+        };
+        let mut builder = Builder::new(syntax, code, tree.root_node());
+        if syntax != Syntax::Other {
+            builder.visit_all(tree.root_node());
+        }
+        builder.finish()
+    }
+
+    /// Returns the innermost scope containing the position.
+    pub fn scope_at(&self, position: &Position) -> &Scope {
+        // Scopes are ordered by start position, so the last one containing the position is the innermost.
+        let key = (position.line, position.col);
+        self.scopes
+            .iter()
+            .rev()
+            .find(|scope| {
+                (scope.start.line, scope.start.col) <= key && key < (scope.end.line, scope.end.col)
+            })
+            .unwrap_or(&self.scopes[0])
+    }
+}
+
+struct Builder<'a> {
+    syntax: Syntax,
+    code: &'a str,
+    scopes: Vec<Scope>,
+    symbols: Vec<Symbol>,
+    bindings: HashMap<(ScopeId, &'a str), usize>,
+    /// The ids of the identifiers that are neither references nor definitions
+    /// (e.g. the module in `from os import path`), or that were already handled as definitions.
+    handled: HashSet<usize>,
+    /// The names declared `global` in a (Python) scope.
+    globals: HashSet<(ScopeId, &'a str)>,
+    /// The names declared `nonlocal` in a (Python) scope.
+    nonlocals: HashSet<(ScopeId, &'a str)>,
+    references: Vec<(ScopeId, &'a str, Location)>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(syntax: Syntax, code: &'a str, root: tree_sitter::Node) -> Self {
+        let location = Location::from_node(root);
+        let module = Scope {
+            id: 0,
+            kind: ScopeKind::Module,
+            parent: None,
+            start: location.start,
+            end: location.end,
+        };
+        Self {
+            syntax,
+            code,
+            scopes: vec![module],
+            symbols: vec![],
+            bindings: HashMap::new(),
+            handled: HashSet::new(),
+            globals: HashSet::new(),
+            nonlocals: HashSet::new(),
+            references: vec![],
+        }
+    }
+
+    /// Visits the tree in pre-order, so that definitions are recorded before their names are visited.
+    fn visit_all(&mut self, root: tree_sitter::Node<'a>) {
+        let mut stack = vec![(root, 0)];
+        while let Some((node, scope)) = stack.pop() {
+            let child_scope = match self.syntax {
+                Syntax::Python => self.visit_python(node, scope),
+                Syntax::JavaScript => self.visit_javascript(node, scope),
+                Syntax::Other => scope,
+            };
+            let is_reference = match self.syntax {
+                Syntax::Python => node.kind() == "identifier",
+                _ => matches!(node.kind(), "identifier" | "shorthand_property_identifier"),
+            };
+            if is_reference && !self.handled.contains(&node.id()) {
+                self.references
+                    .push((scope, self.text(node), Location::from_node(node)));
+            }
+            let mut cursor = node.walk();
+            let children = node.named_children(&mut cursor).collect::<Vec<_>>();
+            stack.extend(children.into_iter().rev().map(|child| (child, child_scope)));
+        }
+    }
+
+    /// Records the definitions made by a Python node, returning the scope of its children.
+    fn visit_python(&mut self, node: tree_sitter::Node<'a>, scope: ScopeId) -> ScopeId {
+        match node.kind() {
+            "function_definition" | "class_definition" => {
+                if let Some(name) = node.child_by_field_name("name") {
+                    self.define(scope, name);
+                }
+                let kind = if node.kind() == "class_definition" {
+                    ScopeKind::Class
+                } else {
+                    ScopeKind::Function
+                };
+                return self.push_scope(node, kind, scope);
+            }
+            "lambda" => return self.push_scope(node, ScopeKind::Function, scope),
+            "list_comprehension"
+            | "set_comprehension"
+            | "dictionary_comprehension"
+            | "generator_expression" => {
+                return self.push_scope(node, ScopeKind::Comprehension, scope)
+            }
+            "parameters" | "lambda_parameters" => {
+                let mut cursor = node.walk();
+                for param in node.named_children(&mut cursor) {
+                    let name = match param.kind() {
+                        "default_parameter" | "typed_default_parameter" => {
+                            param.child_by_field_name("name")
+                        }
+                        "typed_parameter" => param.named_child(0),
+                        _ => Some(param),
+                    };
+                    for ident in name.map(python_pattern_names).unwrap_or_default() {
+                        self.define(scope, ident);
+                    }
+                }
+            }
+            "assignment" | "for_statement" | "for_in_clause" => {
+                let targets = node.child_by_field_name("left");
+                for ident in targets.map(python_pattern_names).unwrap_or_default() {
+                    self.define(scope, ident);
+                }
+            }
+            "named_expression" => {
+                if let Some(name) = node.child_by_field_name("name") {
+                    self.define(scope, name);
+                }
+            }
+            "as_pattern" => {
+                let alias = node.child_by_field_name("alias");
+                for ident in alias.map(python_pattern_names).unwrap_or_default() {
+                    self.define(scope, ident);
+                }
+            }
+            "except_clause" => {
+                // Older grammars: `except Exception as e` has the identifier as a direct child.
+                let mut cursor = node.walk();
+                let children = node.children(&mut cursor).collect::<Vec<_>>();
+                for pair in children.windows(2) {
+                    if pair[0].kind() == "as" && pair[1].kind() == "identifier" {
+                        self.define(scope, pair[1]);
+                    }
+                }
+            }
+            "import_statement" | "import_from_statement" => {
+                if let Some(module) = node.child_by_field_name("module_name") {
+                    self.skip_all(module);
+                }
+                let mut cursor = node.walk();
+                for name in node.children_by_field_name("name", &mut cursor) {
+                    let (defined, skipped) = match name.kind() {
+                        "aliased_import" => (
+                            name.child_by_field_name("alias"),
+                            name.child_by_field_name("name"),
+                        ),
+                        // `import a.b.c` binds `a`, `from a import b` binds `b`.
+                        "dotted_name" => (name.named_child(0), Some(name)),
+                        _ => (None, None),
+                    };
+                    if let Some(skipped) = skipped {
+                        self.skip_all(skipped);
+                    }
+                    if let Some(defined) = defined {
+                        self.define(scope, defined);
+                    }
+                }
+            }
+            "global_statement" | "nonlocal_statement" => {
+                let mut cursor = node.walk();
+                let names = node.named_children(&mut cursor).collect::<Vec<_>>();
+                for name in names {
+                    let text = self.text(name);
+                    if node.kind() == "global_statement" {
+                        self.globals.insert((scope, text));
+                    } else {
+                        self.nonlocals.insert((scope, text));
+                    }
+                    self.handled.insert(name.id());
+                }
+            }
+            "attribute" => {
+                if let Some(attribute) = node.child_by_field_name("attribute") {
+                    self.handled.insert(attribute.id());
+                }
+            }
+            "keyword_argument" => {
+                if let Some(name) = node.child_by_field_name("name") {
+                    self.handled.insert(name.id());
+                }
+            }
+            _ => {}
+        }
+        scope
+    }
+
+    /// Records the definitions made by a JavaScript (or TypeScript) node, returning the scope of its children.
+    fn visit_javascript(&mut self, node: tree_sitter::Node<'a>, scope: ScopeId) -> ScopeId {
+        match node.kind() {
+            "function_declaration" | "generator_function_declaration" | "class_declaration" => {
+                if let Some(name) = node.child_by_field_name("name") {
+                    self.define(scope, name);
+                }
+                let kind = if node.kind() == "class_declaration" {
+                    ScopeKind::Class
+                } else {
+                    ScopeKind::Function
+                };
+                return self.push_scope(node, kind, scope);
+            }
+            "function_expression" | "function" | "generator_function" | "class" => {
+                let kind = if node.kind() == "class" {
+                    ScopeKind::Class
+                } else {
+                    ScopeKind::Function
+                };
+                let inner = self.push_scope(node, kind, scope);
+                // The name of a function (or class) expression is only visible within itself.
+                if let Some(name) = node.child_by_field_name("name") {
+                    self.define(inner, name);
+                }
+                return inner;
+            }
+            "arrow_function" => {
+                let inner = self.push_scope(node, ScopeKind::Function, scope);
+                if let Some(param) = node.child_by_field_name("parameter") {
+                    self.define(inner, param);
+                }
+                return inner;
+            }
+            "method_definition" => return self.push_scope(node, ScopeKind::Function, scope),
+            "statement_block" => {
+                // The body of a function shares the scope of its parameters.
+                let is_function_body = node.parent().is_some_and(|parent| {
+                    matches!(
+                        parent.kind(),
+                        "function_declaration"
+                            | "generator_function_declaration"
+                            | "function_expression"
+                            | "function"
+                            | "generator_function"
+                            | "arrow_function"
+                            | "method_definition"
+                    )
+                });
+                if !is_function_body {
+                    return self.push_scope(node, ScopeKind::Block, scope);
+                }
+            }
+            "for_statement" | "switch_body" => {
+                return self.push_scope(node, ScopeKind::Block, scope)
+            }
+            "for_in_statement" => {
+                let inner = self.push_scope(node, ScopeKind::Block, scope);
+                // Only `for (const x of ...)` defines `x`: `for (x of ...)` assigns to an existing variable.
+                let left = node.child_by_field_name("left");
+                let declaration_kind = node.child_by_field_name("kind").or_else(|| {
+                    left.and_then(|left| left.prev_sibling())
+                        .filter(|prev| matches!(prev.kind(), "var" | "let" | "const"))
+                });
+                if let Some(declaration_kind) = declaration_kind {
+                    let target = if declaration_kind.kind() == "var" {
+                        self.function_scope(scope)
+                    } else {
+                        inner
+                    };
+                    for ident in left.map(javascript_pattern_names).unwrap_or_default() {
+                        self.define(target, ident);
+                    }
+                }
+                return inner;
+            }
+            "catch_clause" => {
+                let inner = self.push_scope(node, ScopeKind::Block, scope);
+                let param = node.child_by_field_name("parameter");
+                for ident in param.map(javascript_pattern_names).unwrap_or_default() {
+                    self.define(inner, ident);
+                }
+                return inner;
+            }
+            "variable_declarator" => {
+                // `var` is scoped to the function, `let` and `const` to the block.
+                let is_var = node
+                    .parent()
+                    .is_some_and(|parent| parent.kind() == "variable_declaration");
+                let target = if is_var {
+                    self.function_scope(scope)
+                } else {
+                    scope
+                };
+                let name = node.child_by_field_name("name");
+                for ident in name.map(javascript_pattern_names).unwrap_or_default() {
+                    self.define(target, ident);
+                }
+            }
+            "formal_parameters" => {
+                let mut cursor = node.walk();
+                let params = node.named_children(&mut cursor).collect::<Vec<_>>();
+                for param in params {
+                    // TypeScript wraps parameters to hold their type annotation.
+                    let pattern = match param.kind() {
+                        "required_parameter" | "optional_parameter" => {
+                            param.child_by_field_name("pattern")
+                        }
+                        _ => Some(param),
+                    };
+                    for ident in pattern.map(javascript_pattern_names).unwrap_or_default() {
+                        self.define(scope, ident);
+                    }
+                }
+            }
+            "import_specifier" => {
+                match node.child_by_field_name("alias") {
+                    Some(alias) => {
+                        self.skip_all(node.child_by_field_name("name").unwrap_or(alias));
+                        self.define(scope, alias);
+                    }
+                    None => {
+                        if let Some(name) = node.child_by_field_name("name") {
+                            self.define(scope, name);
+                        }
+                    }
+                };
+            }
+            "import_clause" | "namespace_import" => {
+                let mut cursor = node.walk();
+                let names = node
+                    .named_children(&mut cursor)
+                    .filter(|child| child.kind() == "identifier")
+                    .collect::<Vec<_>>();
+                for name in names {
+                    self.define(scope, name);
+                }
+            }
+            "export_specifier" => {
+                if let Some(alias) = node.child_by_field_name("alias") {
+                    self.skip_all(alias);
+                }
+            }
+            _ => {}
+        }
+        scope
+    }
+
+    /// Creates a scope spanning the node, returning its id.
+    fn push_scope(&mut self, node: tree_sitter::Node, kind: ScopeKind, parent: ScopeId) -> ScopeId {
+        let id = self.scopes.len();
+        let location = Location::from_node(node);
+        self.scopes.push(Scope {
+            id,
+            kind,
+            parent: Some(parent),
+            start: location.start,
+            end: location.end,
+        });
+        id
+    }
+
+    /// Returns the closest function (or module) scope enclosing `scope`.
+    fn function_scope(&self, mut scope: ScopeId) -> ScopeId {
+        while !matches!(
+            self.scopes[scope].kind,
+            ScopeKind::Function | ScopeKind::Module
+        ) {
+            scope = self.scopes[scope].parent.unwrap_or(0);
+        }
+        scope
+    }
+
+    /// Records the definition of the name held by an identifier node.
+    fn define(&mut self, scope: ScopeId, name_node: tree_sitter::Node<'a>) {
+        let name = self.text(name_node);
+        let location = Location::from_node(name_node);
+        if self.nonlocals.contains(&(scope, name)) {
+            // An assignment to a `nonlocal` name refers to the binding in an enclosing function.
+            self.references.push((scope, name, location));
+            self.handled.insert(name_node.id());
+            return;
+        }
+        let scope = if self.globals.contains(&(scope, name)) {
+            0
+        } else {
+            scope
+        };
+        let symbol_idx = *self.bindings.entry((scope, name)).or_insert_with(|| {
+            self.symbols.push(Symbol {
+                name: name.to_string(),
+                scope,
+                definitions: vec![],
+                references: vec![],
+            });
+            self.symbols.len() - 1
+        });
+        self.symbols[symbol_idx].definitions.push(location);
+        self.handled.insert(name_node.id());
+    }
+
+    /// Marks all the identifiers within a node as neither references nor definitions.
+    fn skip_all(&mut self, node: tree_sitter::Node) {
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            self.handled.insert(node.id());
+            let mut cursor = node.walk();
+            stack.extend(node.named_children(&mut cursor));
+        }
+    }
+
+    fn text(&self, node: tree_sitter::Node) -> &'a str {
+        self.code.get(node.byte_range()).unwrap_or_default()
+    }
+
+    /// Resolves the references to the innermost enclosing scope that defines their name.
+    fn finish(mut self) -> ScopeTable {
+        let mut unresolved = vec![];
+        for (scope, name, location) in std::mem::take(&mut self.references) {
+            let mut current = Some(scope);
+            let mut found = None;
+            while let Some(scope_id) = current {
+                let candidate = &self.scopes[scope_id];
+                // The names defined in a class body are only visible from the body itself.
+                let visible = candidate.kind != ScopeKind::Class || scope_id == scope;
+                if visible {
+                    if let Some(&symbol_idx) = self.bindings.get(&(scope_id, name)) {
+                        found = Some(symbol_idx);
+                        break;
+                    }
+                }
+                current = candidate.parent;
+            }
+            match found {
+                Some(symbol_idx) => self.symbols[symbol_idx].references.push(location),
+                None => unresolved.push(UnresolvedReference {
+                    name: name.to_string(),
+                    scope,
+                    location,
+                }),
+            }
+        }
+        ScopeTable {
+            scopes: self.scopes,
+            symbols: self.symbols,
+            unresolved,
+        }
+    }
+}
+
+/// Returns the identifiers bound by a Python assignment target (e.g. `a` and `b` in `a, (b, c.d) = ...`).
+fn python_pattern_names(node: tree_sitter::Node) -> Vec<tree_sitter::Node> {
+    match node.kind() {
+        "identifier" => vec![node],
+        "pattern_list"
+        | "tuple_pattern"
+        | "list_pattern"
+        | "list_splat_pattern"
+        | "dictionary_splat_pattern"
+        | "tuple"
+        | "list"
+        | "parenthesized_expression"
+        | "as_pattern_target" => {
+            let mut cursor = node.walk();
+            let children = node.named_children(&mut cursor).collect::<Vec<_>>();
+            children
+                .into_iter()
+                .flat_map(python_pattern_names)
+                .collect()
+        }
+        // Attributes and subscripts don't bind a name.
+        _ => vec![],
+    }
+}
+
+/// Returns the identifiers bound by a JavaScript binding pattern (e.g. `a` and `b` in `{ a, c: [b] }`).
+fn javascript_pattern_names(node: tree_sitter::Node) -> Vec<tree_sitter::Node> {
+    match node.kind() {
+        "identifier" | "shorthand_property_identifier_pattern" => vec![node],
+        "object_pattern" | "array_pattern" | "rest_pattern" => {
+            let mut cursor = node.walk();
+            let children = node.named_children(&mut cursor).collect::<Vec<_>>();
+            children
+                .into_iter()
+                .flat_map(javascript_pattern_names)
+                .collect()
+        }
+        "pair_pattern" => node
+            .child_by_field_name("value")
+            .map(javascript_pattern_names)
+            .unwrap_or_default(),
+        "assignment_pattern" | "object_assignment_pattern" => node
+            .child_by_field_name("left")
+            .map(javascript_pattern_names)
+            .unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tree_sitter::get_tree;
+
+    fn table(code: &str, language: Language) -> ScopeTable {
+        let tree = get_tree(code, &language).unwrap();
+        ScopeTable::new(&tree, code)
+    }
+
+    /// Returns the (scope kind, definition lines, reference lines) of the symbols with the given name.
+    fn symbols(table: &ScopeTable, name: &str) -> Vec<(ScopeKind, Vec<u32>, Vec<u32>)> {
+        table
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.name == name)
+            .map(|symbol| {
+                (
+                    table.scopes[symbol.scope].kind,
+                    symbol.definitions.iter().map(|l| l.start.line).collect(),
+                    symbol.references.iter().map(|l| l.start.line).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn unresolved(table: &ScopeTable) -> Vec<&str> {
+        table.unresolved.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    #[test]
+    fn python_scopes() {
+        let code = "\
+import os.path
+from collections import OrderedDict as OD
+x = 1
+def foo(a, b=2, *args, c: int = 3, **kwargs):
+    y = [x for x in args]
+    global z
+    z = a + b
+    return os.path.join(y, OD(c=c), kwargs)
+
+class Bar:
+    x = 2
+    def method(self):
+        return x, len(self)
+";
+        let table = table(code, Language::Python);
+        let kinds = table.scopes.iter().map(|s| s.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ScopeKind::Module,
+                ScopeKind::Function,
+                ScopeKind::Comprehension,
+                ScopeKind::Class,
+                ScopeKind::Function
+            ]
+        );
+        assert_eq!(
+            symbols(&table, "os"),
+            vec![(ScopeKind::Module, vec![1], vec![8])]
+        );
+        assert_eq!(
+            symbols(&table, "OD"),
+            vec![(ScopeKind::Module, vec![2], vec![8])]
+        );
+        // The module-level `x` is referenced from the method, because the class body isn't visible from it.
+        assert_eq!(
+            symbols(&table, "x"),
+            vec![
+                (ScopeKind::Module, vec![3], vec![13]),
+                (ScopeKind::Comprehension, vec![5], vec![5]),
+                (ScopeKind::Class, vec![11], vec![]),
+            ]
+        );
+        assert_eq!(
+            symbols(&table, "c"),
+            vec![(ScopeKind::Function, vec![4], vec![8])]
+        );
+        assert_eq!(
+            symbols(&table, "kwargs"),
+            vec![(ScopeKind::Function, vec![4], vec![8])]
+        );
+        assert_eq!(
+            symbols(&table, "z"),
+            vec![(ScopeKind::Module, vec![7], vec![])]
+        );
+        assert_eq!(
+            symbols(&table, "self"),
+            vec![(ScopeKind::Function, vec![12], vec![13])]
+        );
+        assert_eq!(unresolved(&table), vec!["int", "len"]);
+    }
+
+    #[test]
+    fn javascript_scopes() {
+        let code = "\
+import React, { useState as useS } from 'react';
+var a = 1;
+function foo(b, { c, d: [e] }, ...f) {
+    if (b) {
+        var g = c;
+        let a = e;
+    }
+    return a + g + f + useS;
+}
+const bar = (x) => { try { x() } catch (err) { console.log(err, React) } };
+for (const item of foo()) { bar(item); }
+";
+        let table = table(code, Language::JavaScript);
+        assert_eq!(
+            symbols(&table, "a"),
+            vec![
+                (ScopeKind::Module, vec![2], vec![8]),
+                (ScopeKind::Block, vec![6], vec![]),
+            ]
+        );
+        // `var` is hoisted to the function.
+        assert_eq!(
+            symbols(&table, "g"),
+            vec![(ScopeKind::Function, vec![5], vec![8])]
+        );
+        for name in ["c", "e", "f"] {
+            assert_eq!(symbols(&table, name).len(), 1, "{}", name);
+            assert_eq!(symbols(&table, name)[0].0, ScopeKind::Function);
+        }
+        assert!(symbols(&table, "d").is_empty());
+        assert_eq!(
+            symbols(&table, "useS"),
+            vec![(ScopeKind::Module, vec![1], vec![8])]
+        );
+        assert!(symbols(&table, "useState").is_empty());
+        assert_eq!(
+            symbols(&table, "React"),
+            vec![(ScopeKind::Module, vec![1], vec![10])]
+        );
+        assert_eq!(
+            symbols(&table, "err"),
+            vec![(ScopeKind::Block, vec![10], vec![10])]
+        );
+        assert_eq!(
+            symbols(&table, "x"),
+            vec![(ScopeKind::Function, vec![10], vec![10])]
+        );
+        assert_eq!(
+            symbols(&table, "item"),
+            vec![(ScopeKind::Block, vec![11], vec![11])]
+        );
+        assert_eq!(
+            symbols(&table, "foo"),
+            vec![(ScopeKind::Module, vec![3], vec![11])]
+        );
+        assert_eq!(unresolved(&table), vec!["console"]);
+    }
+
+    #[test]
+    fn scope_at() {
+        let code = "\
+def foo():
+    return [x for x in range(3)]
+";
+        let table = table(code, Language::Python);
+        let scope_at = |line, col| table.scope_at(&Position { line, col }).kind;
+        assert_eq!(scope_at(1, 1), ScopeKind::Function);
+        assert_eq!(scope_at(2, 5), ScopeKind::Function);
+        assert_eq!(scope_at(2, 13), ScopeKind::Comprehension);
+        assert_eq!(scope_at(3, 1), ScopeKind::Module);
+    }
+
+    #[test]
+    fn unsupported_language() {
+        let table = table("package main\nfunc main() {}", Language::Go);
+        assert_eq!(table.scopes.len(), 1);
+        assert!(table.symbols.is_empty());
+        assert!(table.unresolved.is_empty());
+    }
+}
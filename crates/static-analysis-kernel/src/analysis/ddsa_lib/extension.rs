@@ -8,8 +8,15 @@ deno_core::extension!(
     ddsa_lib,
     ops = [
         ops::op_current_filename,
+        ops::op_current_scopes,
         ops::op_console_push,
         ops::op_ts_node_text,
+        ops::op_ts_node_named_children,
+        ops::op_ts_node_parent,
+        ops::op_ts_node_string_value,
+        ops::op_ts_query,
+        ops::op_text_between,
+        ops::op_text_lines,
         ops::op_current_ts_tree_text,
     ],
     esm_entry_point = "ext:ddsa_lib/__bootstrap.js",
@@ -20,9 +27,12 @@ deno_core::extension!(
         ("ext:ddsa_lib/context_file_go", "context_file_go.js"),
         ("ext:ddsa_lib/context_root", "context_root.js"),
         ("ext:ddsa_lib/context_rule", "context_rule.js"),
+        ("ext:ddsa_lib/ddsa", "ddsa.js"),
         ("ext:ddsa_lib/edit", "edit.js"),
         ("ext:ddsa_lib/fix", "fix.js"),
         ("ext:ddsa_lib/query_match", "query_match.js"),
+        ("ext:ddsa_lib/sandbox", "sandbox.js"),
+        ("ext:ddsa_lib/scopes", "scopes.js"),
         ("ext:ddsa_lib/stella_compat", "stella_compat.js"),
         ("ext:ddsa_lib/utility", "utility.js"),
         ("ext:ddsa_lib/ts_node", "ts_node.js"),
@@ -15,12 +15,16 @@ mod context_root;
 pub(crate) use context_root::RootContext;
 mod context_rule;
 pub(crate) use context_rule::RuleContext;
+mod ddsa;
+pub(crate) use ddsa::*;
 mod edit;
 pub(crate) use edit::*;
 mod fix;
 pub(crate) use fix::*;
 mod query_match;
 pub(crate) use query_match::*;
+mod scopes;
+pub(crate) use scopes::*;
 mod ts_node;
 pub(crate) use ts_node::*;
 mod violation;
@@ -25,9 +25,23 @@ globalThis.TreeSitterNode = TreeSitterNode;
 // NOTE: This is temporary scaffolding used during the transition to `ddsa_lib::JsRuntime`.
 import * as stellaCompat from "ext:ddsa_lib/stella_compat";
 for (const [name, obj] of Object.entries(stellaCompat)) {
-    globalThis[name] = obj;
+    // `_cleanExecute` controls the sandbox of the rules, so it must not be reachable by them.
+    if (name !== "_cleanExecute") {
+        globalThis[name] = obj;
+    }
 }
 ///////////
 
 import {DDSA_Console} from "ext:ddsa_lib/utility";
 globalThis.console = new DDSA_Console();
+
+import {DDSA} from "ext:ddsa_lib/ddsa";
+globalThis.ddsa = new DDSA();
+
+// The globals with side effects are restricted once the runtime is set up.
+import {sandbox} from "ext:ddsa_lib/sandbox";
+sandbox.install();
+
+// The Rust runtime captures a reference to `_cleanExecute` and then deletes this property before
+// executing any rule (see `JsRuntime::try_new`).
+globalThis.__ddsaCleanExecute = stellaCompat._cleanExecute;
@@ -2,9 +2,12 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
+import {ScopeTable} from "ext:ddsa_lib/scopes";
+
 const {
     op_current_ts_tree_text,
     op_current_filename,
+    op_current_scopes,
 } = Deno.core.ops;
 
 /**
@@ -30,6 +33,13 @@ export class RootContext {
          * @private
          */
         this.__js_cachedFileContents = undefined;
+        /**
+         * The symbol table of the file. This is intended to be an internal field. External callers
+         * should use the {@link RootContext.scopes} getter.
+         * @type {ScopeTable | undefined}
+         * @private
+         */
+        this.__js_cachedScopes = undefined;
         /**
          * An object that provides extra metadata for a specific filetypes.
          * @type {FileContext}
@@ -70,4 +80,21 @@ export class RootContext {
         }
         return this.__js_cachedFilename;
     }
+
+    /**
+     * A getter for the symbol table of the file (its scopes, and the definitions and references of each symbol).
+     *
+     * @remarks
+     * The symbol table is computed by Rust the first time it is requested for a file. Subsequent calls to this
+     * getter will return the cached value.
+     *
+     * @returns {ScopeTable}
+     */
+    get scopes() {
+        if (this.__js_cachedScopes === undefined) {
+            const {scopes, symbols, unresolved} = op_current_scopes();
+            this.__js_cachedScopes = new ScopeTable(scopes, symbols, unresolved);
+        }
+        return this.__js_cachedScopes;
+    }
 }
@@ -20,6 +20,7 @@ pub struct RootContext<T> {
     s_rule_ctx: v8::Global<v8::String>,
     s_filename: v8::Global<v8::String>,
     s_file_contents: v8::Global<v8::String>,
+    s_scopes: v8::Global<v8::String>,
     _pd: PhantomData<T>,
 }
 
@@ -45,12 +46,15 @@ impl RootContext<Instance> {
         let s_filename = v8::Global::new(scope, s_filename);
         let s_file_contents = v8_interned(scope, "__js_cachedFileContents");
         let s_file_contents = v8::Global::new(scope, s_file_contents);
+        let s_scopes = v8_interned(scope, "__js_cachedScopes");
+        let s_scopes = v8::Global::new(scope, s_scopes);
         Ok(Self {
             v8_object,
             s_file_ctx,
             s_rule_ctx,
             s_filename,
             s_file_contents,
+            s_scopes,
             _pd: PhantomData,
         })
     }
@@ -124,6 +128,11 @@ impl RootContext<Instance> {
         }
     }
 
+    /// Clears the symbol table cache in the context.
+    pub fn clear_scopes_cache(&self, scope: &mut HandleScope) {
+        set_undefined(&self.v8_object, scope, &self.s_scopes);
+    }
+
     #[cfg(test)]
     fn get_cache(&self, scope: &mut HandleScope, key: &v8::Global<v8::String>) -> Option<String> {
         let v8_key = v8::Local::new(scope, key);
@@ -168,11 +177,13 @@ mod tests {
             // Variables
             "__js_cachedFilename",
             "__js_cachedFileContents",
+            "__js_cachedScopes",
             "fileCtx",
             "ruleCtx",
             // Methods
             "fileContents",
             "filename",
+            "scopes",
         ];
         assert!(js_instance_eq(RootContext::CLASS_NAME, instance_expected));
         let class_expected = &[];
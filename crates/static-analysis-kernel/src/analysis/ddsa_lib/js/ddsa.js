@@ -0,0 +1,127 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+const {
+    op_text_between,
+    op_text_lines,
+    op_ts_node_string_value,
+    op_ts_query,
+} = Deno.core.ops;
+
+/**
+ * The entry point to the functions provided by the static analysis kernel to rules. An instance of
+ * this class is available to rules as the global `ddsa`.
+ */
+export class DDSA {
+    /**
+     * Executes a tree-sitter query, returning an array of its matches. Each match is an object that maps the
+     * names of the captures to the captured node (or to an array of nodes, for quantified captures, e.g. `(comment)+ @cap`).
+     * @param {string} pattern The tree-sitter query.
+     * @param {TreeSitterNode} [scopeNode] The node to execute the query on. If not provided, the query is executed on the entire tree.
+     * @returns {Array<Object<string, TreeSitterNode | Array<TreeSitterNode>>>}
+     * @throws {Error} If the query is invalid.
+     *
+     * @remarks
+     * A query is only compiled the first time it is used, so it is not costly to call this repeatedly with the same pattern.
+     *
+     * @example
+     * ```javascript
+     * // Find the calls to a function whose name is only known at runtime.
+     * for (const match of ddsa.query(`(call_expression function: (identifier) @fn (#eq? @fn "${name}")) @call`, node)) {
+     *     const call = match.call;
+     *     // ...
+     * }
+     * ```
+     */
+    query(pattern, scopeNode) {
+        if (scopeNode === undefined) {
+            return op_ts_query(pattern, 0, false);
+        }
+        return op_ts_query(pattern, scopeNode.id, true);
+    }
+
+    /**
+     * Returns the value that a string literal evaluates to at runtime, with its escape sequences decoded.
+     * Adjacent literals (Python) and literals concatenated with `+` are joined. For interpolated strings
+     * (Python f-strings and JavaScript template strings), only the static parts are kept.
+     * @param {TreeSitterNode} node
+     * @returns {string | undefined} The value, or `undefined` if the node isn't a string literal.
+     *
+     * @remarks
+     * This is supported for Python, JavaScript, TypeScript and Go.
+     *
+     * @example
+     * ```javascript
+     * // For the Python string `"\x41" "BC"`:
+     * ddsa.getStringValue(node); // "ABC"
+     * // While its source text is:
+     * node.text; // "\x41" "BC"
+     * ```
+     */
+    getStringValue(node) {
+        return op_ts_node_string_value(node.id);
+    }
+
+    /**
+     * Returns the text of the file between two positions. The `end` position is exclusive, so the text of a node
+     * is `ddsa.getTextBetween(node.start, node.end)`.
+     * @param {Position} start
+     * @param {Position} end
+     * @returns {string | undefined} The text, or `undefined` if the positions are not valid.
+     *
+     * @remarks
+     * Like the positions of nodes, columns are 1-based byte offsets within a line.
+     */
+    getTextBetween(start, end) {
+        return op_text_between(start.line, start.col, end.line, end.col);
+    }
+
+    /**
+     * Returns the text of a line of the file, without its line break.
+     * @param {number} line The 1-based line number.
+     * @returns {string | undefined} The text, or `undefined` if the line does not exist.
+     */
+    getLine(line) {
+        return op_text_lines(line, line);
+    }
+
+    /**
+     * Returns the text of a range of lines of the file, without the line break of the last line.
+     * @param {number} startLine The 1-based number of the first line.
+     * @param {number} endLine The 1-based number of the last line (inclusive).
+     * @returns {string | undefined} The text, or `undefined` if the lines do not exist.
+     */
+    getLines(startLine, endLine) {
+        return op_text_lines(startLine, endLine);
+    }
+
+    /**
+     * Returns an iterator over a node and all of its named descendants, in depth-first pre-order.
+     * @param {TreeSitterNode} node
+     * @returns {Generator<TreeSitterNode, void, void>}
+     *
+     * @remarks
+     * The tree is traversed lazily: the children of a node are only retrieved when the iterator reaches it.
+     *
+     * @example
+     * ```javascript
+     * for (const descendant of ddsa.walk(node)) {
+     *     if (descendant.type === "call_expression") {
+     *         // ...
+     *     }
+     * }
+     * ```
+     */
+    *walk(node) {
+        const stack = [node];
+        while (stack.length > 0) {
+            const current = stack.pop();
+            yield current;
+            const children = current.children();
+            for (let i = children.length - 1; i >= 0; i--) {
+                stack.push(children[i]);
+            }
+        }
+    }
+}
@@ -0,0 +1,217 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use crate::analysis::ddsa_lib::common::Class;
+use std::marker::PhantomData;
+
+/// The ES6 class `DDSA`. An instance of it is exposed to rules as the global `ddsa`.
+#[derive(Debug)]
+pub struct DDSA<T>(PhantomData<T>);
+
+impl DDSA<Class> {
+    /// The name of the JavaScript class.
+    pub const CLASS_NAME: &'static str = "DDSA";
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::ddsa_lib::bridge::{ContextBridge, TsNodeBridge};
+    use crate::analysis::ddsa_lib::common::attach_as_global;
+    use crate::analysis::ddsa_lib::js::DDSA;
+    use crate::analysis::ddsa_lib::runtime::QueryCache;
+    use crate::analysis::ddsa_lib::test_utils::{
+        cfg_test_runtime, js_class_eq, js_instance_eq, parse_js, try_execute,
+    };
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[test]
+    fn js_properties_canary() {
+        let instance_expected = &[
+            // Methods
+            "getLine",
+            "getLines",
+            "getStringValue",
+            "getTextBetween",
+            "query",
+            "walk",
+        ];
+        assert!(js_instance_eq(DDSA::CLASS_NAME, instance_expected));
+        let class_expected = &[];
+        assert!(js_class_eq(DDSA::CLASS_NAME, class_expected));
+    }
+
+    /// Sets up a runtime with the bridges required to traverse the tree, attaching the root node
+    /// of the `file_contents` as the global `ROOT`.
+    fn setup_root(file_contents: &str) -> (deno_core::JsRuntime, tree_sitter::Tree) {
+        let mut runtime = cfg_test_runtime();
+        let query_cache = Rc::new(RefCell::new(QueryCache::default()));
+        runtime.op_state().borrow_mut().put(query_cache);
+        let tsn_bridge = TsNodeBridge::try_new(&mut runtime.handle_scope()).unwrap();
+        let tsn_bridge = Rc::new(RefCell::new(tsn_bridge));
+        runtime.op_state().borrow_mut().put(Rc::clone(&tsn_bridge));
+        let ctx_bridge = ContextBridge::try_new(&mut runtime.handle_scope()).unwrap();
+        let ctx_bridge = Rc::new(RefCell::new(ctx_bridge));
+        runtime.op_state().borrow_mut().put(Rc::clone(&ctx_bridge));
+
+        let tree = parse_js(file_contents);
+        let scope = &mut runtime.handle_scope();
+        ctx_bridge.borrow_mut().set_root_context(
+            scope,
+            &tree,
+            &Arc::from(file_contents),
+            &Arc::from("file.js"),
+        );
+        let root_id = tsn_bridge.borrow_mut().insert(scope, tree.root_node());
+        let v8_root = tsn_bridge.borrow().v8_get(scope, root_id).unwrap();
+        attach_as_global(scope, v8_root, "ROOT");
+        (runtime, tree)
+    }
+
+    /// `walk` visits a node and all of its named descendants in depth-first pre-order.
+    #[test]
+    fn walk_named_descendants() {
+        let (mut runtime, _tree) = setup_root("const abc = foo(bar, 1);");
+        let scope = &mut runtime.handle_scope();
+        let code = "[...new DDSA().walk(ROOT.children()[0])].map((n) => n.text).join('|');";
+        let res = try_execute(scope, code).unwrap();
+        assert_eq!(
+            res.to_rust_string_lossy(scope),
+            "const abc = foo(bar, 1);|abc = foo(bar, 1)|abc|foo(bar, 1)|foo|(bar, 1)|bar|1"
+        );
+    }
+
+    /// Children and parents are retrieved from the tree, and the same node is always the same object.
+    #[test]
+    fn children_and_parent() {
+        let (mut runtime, _tree) = setup_root("foo(); bar();");
+        let scope = &mut runtime.handle_scope();
+        for (code, expected) in [
+            ("ROOT.children().length;", "2"),
+            ("ROOT.children()[1].text;", "bar();"),
+            ("ROOT.children()[1].parent() === ROOT;", "true"),
+            ("ROOT.children()[0] === ROOT.children()[0];", "true"),
+            ("ROOT.parent() === undefined;", "true"),
+            (
+                "ROOT.children()[0].children()[0].children()[0].children().length;",
+                "0",
+            ),
+        ] {
+            let res = try_execute(scope, code).unwrap();
+            assert_eq!(res.to_rust_string_lossy(scope), expected, "{}", code);
+        }
+    }
+
+    /// `query` returns the captures of each match, either from the entire tree or from a node.
+    #[test]
+    fn query_captures() {
+        let (mut runtime, _tree) = setup_root("const abc = foo(bar, 1);\nconst def = foo(baz);");
+        let scope = &mut runtime.handle_scope();
+        let setup = r#"
+const QUERY = "(call_expression function: (identifier) @fn arguments: (arguments (identifier)+ @args)) @call";
+const DDSA_INSTANCE = new DDSA();
+"#;
+        try_execute(scope, setup).unwrap();
+        for (code, expected) in [
+            ("DDSA_INSTANCE.query(QUERY).length;", "2"),
+            ("DDSA_INSTANCE.query(QUERY)[0].call.text;", "foo(bar, 1)"),
+            (
+                "DDSA_INSTANCE.query(QUERY)[1].args.map((n) => n.text).join();",
+                "baz",
+            ),
+            (
+                "DDSA_INSTANCE.query(QUERY, ROOT.children()[1]).length;",
+                "1",
+            ),
+            (
+                "DDSA_INSTANCE.query(QUERY, ROOT.children()[1])[0].call.text;",
+                "foo(baz)",
+            ),
+            (
+                "DDSA_INSTANCE.query(QUERY)[1].fn === DDSA_INSTANCE.query(QUERY)[1].fn;",
+                "true",
+            ),
+            ("DDSA_INSTANCE.query('(number) @num')[0].num.text;", "1"),
+        ] {
+            let res = try_execute(scope, code).unwrap();
+            assert_eq!(res.to_rust_string_lossy(scope), expected, "{}", code);
+        }
+    }
+
+    /// Queries are only compiled once, and invalid queries throw an error.
+    #[test]
+    fn query_cache_and_errors() {
+        let (mut runtime, _tree) = setup_root("foo(bar);");
+        let query_cache = Rc::clone(
+            runtime
+                .op_state()
+                .borrow()
+                .borrow::<Rc<RefCell<QueryCache>>>(),
+        );
+        let scope = &mut runtime.handle_scope();
+        let code = "new DDSA().query('(identifier) @id').length;";
+        for _ in 0..3 {
+            let res = try_execute(scope, code).unwrap();
+            assert_eq!(res.to_rust_string_lossy(scope), "2");
+        }
+        assert_eq!(query_cache.borrow().len(), 1);
+
+        let res = try_execute(scope, "new DDSA().query('(identifier @id');");
+        assert!(res.is_err());
+        assert_eq!(query_cache.borrow().len(), 1);
+    }
+
+    /// Text can be retrieved by position or by line, without slicing the file's text in JavaScript.
+    #[test]
+    fn text_slicing() {
+        let (mut runtime, _tree) = setup_root("const abc = 'é';\nfoo(abc);\n");
+        let scope = &mut runtime.handle_scope();
+        try_execute(scope, "const DDSA_INSTANCE = new DDSA();").unwrap();
+        for (code, expected) in [
+            ("DDSA_INSTANCE.getLine(1);", "const abc = 'é';"),
+            ("DDSA_INSTANCE.getLine(2);", "foo(abc);"),
+            ("DDSA_INSTANCE.getLine(4);", "undefined"),
+            (
+                "DDSA_INSTANCE.getLines(1, 2);",
+                "const abc = 'é';\nfoo(abc);",
+            ),
+            (
+                "DDSA_INSTANCE.getTextBetween({line: 1, col: 13}, {line: 2, col: 4});",
+                "'é';\nfoo",
+            ),
+            (
+                "DDSA_INSTANCE.getTextBetween({line: 2, col: 4}, {line: 1, col: 1});",
+                "undefined",
+            ),
+        ] {
+            let res = try_execute(scope, code).unwrap();
+            assert_eq!(res.to_rust_string_lossy(scope), expected, "{}", code);
+        }
+        // The text between the positions of a node is the text of the node.
+        let code = "[...DDSA_INSTANCE.walk(ROOT)].every((n) => DDSA_INSTANCE.getTextBetween(n.start, n.end) === n.text);";
+        let res = try_execute(scope, code).unwrap();
+        assert!(res.is_true());
+    }
+
+    /// The value of string literals is decoded by Rust.
+    #[test]
+    fn string_value() {
+        let (mut runtime, _tree) = setup_root("const a = 'it\\'s' + \"\\x41\";\nconst b = c;");
+        let scope = &mut runtime.handle_scope();
+        let code = "\
+const DDSA_INSTANCE = new DDSA();
+const VALUES = ROOT.children().map((n) => n.children()[0].children()[1]);
+";
+        try_execute(scope, code).unwrap();
+        for (code, expected) in [
+            ("VALUES[0].text;", r#"'it\'s' + "\x41""#),
+            ("DDSA_INSTANCE.getStringValue(VALUES[0]);", "it'sA"),
+            ("DDSA_INSTANCE.getStringValue(VALUES[1]);", "undefined"),
+        ] {
+            let res = try_execute(scope, code).unwrap();
+            assert_eq!(res.to_rust_string_lossy(scope), expected, "{}", code);
+        }
+    }
+}
@@ -2,6 +2,14 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
+/**
+ * How safely a fix can be applied without being reviewed:
+ * * "AUTOMATIC": the fix preserves the behavior of the code, and can be applied automatically.
+ * * "SUGGESTED": the fix is likely correct, but it may change the behavior of the code and should be reviewed.
+ * * "MANUAL": the fix is a starting point that must be completed manually.
+ * @typedef { "AUTOMATIC" | "SUGGESTED" | "MANUAL" } FixApplicability
+ */
+
 /**
  * An object containing a collection of {@link Edit}s that will fix a static analysis {@link Violation}.
  */
@@ -9,8 +17,9 @@ export class Fix {
     /**
      * @param {string} message
      * @param {Array<Edit>} edits
+     * @param {FixApplicability | undefined} applicability
      */
-    constructor(message, edits) {
+    constructor(message, edits, applicability) {
         /**
          * A human-friendly message describing what the fix does.
          * @type {string}
@@ -27,6 +36,13 @@ export class Fix {
          * @private
          */
         this.edits = edits;
+        /**
+         * How safely this fix can be applied. If `undefined`, the fix is considered "SUGGESTED".
+         * @type {FixApplicability | undefined}
+         * @readonly
+         * @private
+         */
+        this.applicability = applicability;
     }
 
     /**
@@ -34,13 +50,14 @@ export class Fix {
      *
      * @param {string} message
      * @param {Array<Edit>} edits
+     * @param {FixApplicability | undefined} applicability
      * @returns {Fix}
      *
      * @remarks
      * This is a convenience function to allow creation of a `Fix` without using the class constructor.
      * It is functionally equivalent to calling `new Fix(...)`.
      */
-    static new(message, edits) {
-        return new Fix(message, edits);
+    static new(message, edits, applicability) {
+        return new Fix(message, edits, applicability);
     }
 }
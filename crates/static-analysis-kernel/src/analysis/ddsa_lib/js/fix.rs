@@ -3,7 +3,7 @@
 // Copyright 2024 Datadog, Inc.
 
 use crate::analysis::ddsa_lib::common::{
-    get_field, iter_v8_array, v8_type_from, DDSAJsRuntimeError, Instance,
+    get_field, get_optional_field, iter_v8_array, v8_type_from, DDSAJsRuntimeError, Instance,
 };
 use crate::analysis::ddsa_lib::js::edit::EditConverter;
 use crate::analysis::ddsa_lib::js::Edit;
@@ -17,6 +17,7 @@ use deno_core::v8::HandleScope;
 pub struct Fix<T> {
     pub message: String,
     pub edits: Vec<Edit<T>>,
+    pub applicability: Option<violation::FixApplicability>,
 }
 
 impl Fix<Instance> {
@@ -31,7 +32,11 @@ impl<T> From<Fix<T>> for violation::Fix {
             .into_iter()
             .map(violation::Edit::from)
             .collect::<Vec<_>>();
-        violation::Fix { description, edits }
+        violation::Fix {
+            description,
+            edits,
+            applicability: value.applicability.unwrap_or_default(),
+        }
     }
 }
 
@@ -63,7 +68,25 @@ impl V8Converter for FixConverter {
         let edits = iter_v8_array(edits, scope)
             .map(|value| self.edit_converter.try_convert_from(scope, value))
             .collect::<Result<Vec<_>, _>>()?;
-        Ok(Fix { message, edits })
+        let applicability =
+            get_optional_field::<v8::String>(v8_obj, "applicability", scope, "string | undefined")?
+                .map(
+                    |applicability| match applicability.to_rust_string_lossy(scope).as_str() {
+                        "AUTOMATIC" => Ok(violation::FixApplicability::Automatic),
+                        "SUGGESTED" => Ok(violation::FixApplicability::Suggested),
+                        "MANUAL" => Ok(violation::FixApplicability::Manual),
+                        _ => Err(DDSAJsRuntimeError::InvalidValue {
+                            identifier: "applicability",
+                            expected: "one of `AUTOMATIC`, `SUGGESTED`, `MANUAL`",
+                        }),
+                    },
+                )
+                .transpose()?;
+        Ok(Fix {
+            message,
+            edits,
+            applicability,
+        })
     }
 }
 
@@ -76,7 +99,9 @@ mod tests {
     fn js_properties_canary() {
         let instance_expected = &[
             // Variables
-            "message", "edits",
+            "message",
+            "edits",
+            "applicability",
         ];
         assert!(js_instance_eq(Fix::CLASS_NAME, instance_expected));
         let class_expected = &["new"];
@@ -2,6 +2,7 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
+import {DDSA} from "ext:ddsa_lib/ddsa";
 import {DDSA_Console} from "ext:ddsa_lib/utility";
 
 /**
@@ -14,3 +15,9 @@ import {DDSA_Console} from "ext:ddsa_lib/utility";
  * @type {DDSA_Console}
  * @global
  */
+
+/**
+ * @name ddsa
+ * @type {DDSA}
+ * @global
+ */
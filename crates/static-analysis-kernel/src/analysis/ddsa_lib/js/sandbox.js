@@ -0,0 +1,189 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+/**
+ * The globals that have side effects outside the analysis of a file: network access, timers,
+ * scheduling of jobs, and direct access to the ops of the runtime.
+ * @type {Array<string>}
+ */
+const RESTRICTED_GLOBALS = [
+    "Deno",
+    "__bootstrap",
+    "fetch",
+    "XMLHttpRequest",
+    "WebSocket",
+    "setTimeout",
+    "setInterval",
+    "setImmediate",
+    "queueMicrotask",
+    "WebAssembly",
+    "Atomics",
+];
+
+/**
+ * The time seen by the rules that are executed deterministically: 2024-01-01T00:00:00Z.
+ */
+const FROZEN_TIME_MS = 1704067200000;
+
+/**
+ * Returns a pseudo-random generator (mulberry32) of numbers in [0, 1), seeded with a 32-bit integer.
+ * @param {number} seed
+ * @returns {function(): number}
+ */
+export function seededRandom(seed) {
+    let state = seed >>> 0;
+    return () => {
+        state = (state + 0x6D2B79F5) >>> 0;
+        let t = state;
+        t = Math.imul(t ^ (t >>> 15), t | 1);
+        t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+        return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+    };
+}
+
+/**
+ * The error thrown when a rule accesses a restricted global without being allowed to.
+ */
+export class SandboxViolation extends Error {
+    /**
+     * @param {string} name The name of the restricted global.
+     */
+    constructor(name) {
+        super(`\`${name}\` is not permitted in rules`);
+        this.name = "SandboxViolation";
+    }
+}
+
+/**
+ * Restricts the access of the rules to the globals with side effects. Once installed, the
+ * restricted globals can only be accessed within {@link Sandbox.run} by a rule allowed to use them.
+ */
+export class Sandbox {
+    /**
+     * @param {Array<string>} names The names of the restricted globals.
+     */
+    constructor(names) {
+        /** @type {Array<string>} */
+        this.names = names;
+        /** @type {boolean} */
+        this.allowed = false;
+        /**
+         * The restricted globals accessed by the rule that is executing.
+         * @type {Array<string>}
+         */
+        this.violations = [];
+        /**
+         * The generator of `Math.random` for the rule that is executing, or `null` if the rule uses
+         * the real `Math.random` and `Date`.
+         * @type {(function(): number) | null}
+         */
+        this.random = null;
+        /**
+         * Whether a closure is executing within {@link Sandbox.run}.
+         * @type {boolean}
+         */
+        this.running = false;
+    }
+
+    /**
+     * Replaces the restricted globals of `globalThis` with accessors that check the permission
+     * of the executing rule. The globals that aren't provided by the runtime are `undefined` for
+     * the allowed rules.
+     */
+    install() {
+        for (const name of this.names) {
+            let value = globalThis[name];
+            Object.defineProperty(globalThis, name, {
+                get: () => {
+                    this.check(name);
+                    return value;
+                },
+                set: (newValue) => {
+                    this.check(name);
+                    value = newValue;
+                },
+                enumerable: false,
+                configurable: false,
+            });
+        }
+        this.installDeterminism();
+    }
+
+    /**
+     * Replaces `Math.random` and `Date` so that a rule executed deterministically gets the numbers
+     * of its seeded generator, and the frozen time {@link FROZEN_TIME_MS}: the output of a rule
+     * then only depends on the file it analyzes.
+     */
+    installDeterminism() {
+        const sandbox = this;
+        const random = Math.random;
+        Math.random = () => (sandbox.random === null ? random() : sandbox.random());
+
+        const RealDate = Date;
+        function DeterministicDate(...args) {
+            const frozen = sandbox.random !== null;
+            if (!new.target) {
+                return frozen ? new RealDate(FROZEN_TIME_MS).toString() : RealDate();
+            }
+            if (args.length === 0 && frozen) {
+                return Reflect.construct(RealDate, [FROZEN_TIME_MS], new.target);
+            }
+            return Reflect.construct(RealDate, args, new.target);
+        }
+        // `Date.UTC` and `Date.parse` are inherited, and the dates are still `instanceof Date`.
+        Object.setPrototypeOf(DeterministicDate, RealDate);
+        DeterministicDate.prototype = RealDate.prototype;
+        DeterministicDate.now = () => (sandbox.random === null ? RealDate.now() : FROZEN_TIME_MS);
+        globalThis.Date = DeterministicDate;
+    }
+
+    /**
+     * Throws a {@link SandboxViolation} if the executing rule isn't allowed to access a restricted global.
+     * @param {string} name
+     */
+    check(name) {
+        if (!this.allowed) {
+            this.violations.push(name);
+            throw new SandboxViolation(name);
+        }
+    }
+
+    /**
+     * Executes the closure of a rule. The violation is re-thrown after the execution if the rule
+     * caught it, so that the rule can't silently ignore it.
+     *
+     * The sandbox can't be entered re-entrantly: a closure can't change the permissions or the
+     * determinism of the execution it's part of.
+     * @param {function(): T} closure
+     * @param {boolean} allowSideEffects Whether the rule can access the restricted globals.
+     * @param {number | null} seed The seed of `Math.random` for a rule executed deterministically,
+     * or `null` to use the real `Math.random` and `Date`.
+     * @returns {T}
+     * @template T
+     */
+    run(closure, allowSideEffects, seed = null) {
+        if (this.running) {
+            this.violations.push("Sandbox.run");
+            throw new SandboxViolation("Sandbox.run");
+        }
+        this.running = true;
+        this.violations.length = 0;
+        this.allowed = allowSideEffects;
+        this.random = seed === null ? null : seededRandom(seed);
+        let result;
+        try {
+            result = closure();
+        } finally {
+            this.allowed = false;
+            this.random = null;
+            this.running = false;
+        }
+        if (this.violations.length > 0) {
+            throw new SandboxViolation(this.violations[0]);
+        }
+        return result;
+    }
+}
+
+export const sandbox = new Sandbox(RESTRICTED_GLOBALS);
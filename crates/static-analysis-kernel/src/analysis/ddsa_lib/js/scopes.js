@@ -0,0 +1,172 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+/**
+ * The index of a {@link Scope} within {@link ScopeTable.scopes}.
+ * @typedef {number} ScopeId
+ */
+
+/**
+ * @typedef {"module" | "function" | "class" | "block" | "comprehension"} ScopeKind
+ */
+
+/**
+ * A region of the file that can bind names (e.g. a module, a function or a block).
+ * @typedef {Object} Scope
+ * @property {ScopeId} id
+ * @property {ScopeKind} kind
+ * @property {ScopeId | null} parent The enclosing scope. This is `null` only for the module scope.
+ * @property {Position} start
+ * @property {Position} end
+ */
+
+/**
+ * The range spanned by a definition or a reference.
+ * @typedef {Object} Location
+ * @property {Position} start
+ * @property {Position} end
+ */
+
+/**
+ * A name bound in a scope.
+ * @typedef {Object} Symbol
+ * @property {string} name
+ * @property {ScopeId} scope
+ * @property {Array<Location>} definitions
+ * @property {Array<Location>} references
+ */
+
+/**
+ * A reference to a name that isn't defined in the file (e.g. a builtin or a global).
+ * @typedef {Object} UnresolvedReference
+ * @property {string} name
+ * @property {ScopeId} scope
+ * @property {Location} location
+ */
+
+/**
+ * The symbol table of a file: its scopes, the symbols defined in each scope, and the references to them.
+ * This is computed by the Rust static-analysis-kernel for Python and JavaScript (including TypeScript).
+ * For other languages, only the module scope is present.
+ *
+ * @example
+ * ```javascript
+ * // Report the variables that are never used.
+ * for (const symbol of context.scopes.symbols) {
+ *     if (symbol.references.length === 0) {
+ *         // ...
+ *     }
+ * }
+ * ```
+ */
+export class ScopeTable {
+    /**
+     * @param {Array<Scope>} scopes
+     * @param {Array<Symbol>} symbols
+     * @param {Array<UnresolvedReference>} unresolved
+     */
+    constructor(scopes, symbols, unresolved) {
+        /**
+         * The scopes, in the order that they appear in the file. The module scope is the first.
+         * @type {Array<Scope>}
+         * @readonly
+         */
+        this.scopes = scopes;
+        /**
+         * @type {Array<Symbol>}
+         * @readonly
+         */
+        this.symbols = symbols;
+        /**
+         * @type {Array<UnresolvedReference>}
+         * @readonly
+         */
+        this.unresolved = unresolved;
+        /**
+         * A lazily-built index of the symbols of each scope.
+         * @type {Map<ScopeId, Map<string, Symbol>> | undefined}
+         * @private
+         */
+        this._symbolsByScope = undefined;
+    }
+
+    /**
+     * Returns the innermost scope containing the position.
+     * @param {Position} position
+     * @returns {Scope}
+     */
+    getScopeAt(position) {
+        // Scopes are ordered by start position, so the last one containing the position is the innermost.
+        for (let i = this.scopes.length - 1; i > 0; i--) {
+            const scope = this.scopes[i];
+            if (comparePositions(scope.start, position) <= 0 && comparePositions(position, scope.end) < 0) {
+                return scope;
+            }
+        }
+        return this.scopes[0];
+    }
+
+    /**
+     * Returns the symbols defined in a scope.
+     * @param {ScopeId} scopeId
+     * @returns {Array<Symbol>}
+     */
+    getSymbols(scopeId) {
+        return Array.from(this._index().get(scopeId)?.values() ?? []);
+    }
+
+    /**
+     * Returns the symbol that a name refers to at the given position, or `undefined` if the name isn't defined
+     * in the file. As in Python, the names defined in a class body are not visible from its methods.
+     * @param {string} name
+     * @param {Position} position
+     * @returns {Symbol | undefined}
+     */
+    lookup(name, position) {
+        const start = this.getScopeAt(position).id;
+        let current = start;
+        while (current !== null && current !== undefined) {
+            const scope = this.scopes[current];
+            if (scope.kind !== "class" || current === start) {
+                const symbol = this._index().get(current)?.get(name);
+                if (symbol !== undefined) {
+                    return symbol;
+                }
+            }
+            current = scope.parent;
+        }
+        return undefined;
+    }
+
+    /**
+     * Returns the index of the symbols of each scope, building it if needed.
+     * @returns {Map<ScopeId, Map<string, Symbol>>}
+     * @private
+     */
+    _index() {
+        if (this._symbolsByScope === undefined) {
+            this._symbolsByScope = new Map();
+            for (const symbol of this.symbols ?? []) {
+                let scopeSymbols = this._symbolsByScope.get(symbol.scope);
+                if (scopeSymbols === undefined) {
+                    scopeSymbols = new Map();
+                    this._symbolsByScope.set(symbol.scope, scopeSymbols);
+                }
+                scopeSymbols.set(symbol.name, symbol);
+            }
+        }
+        return this._symbolsByScope;
+    }
+}
+
+/**
+ * Compares two positions, returning a negative number if `a` is before `b`, zero if they are equal,
+ * and a positive number otherwise.
+ * @param {Position} a
+ * @param {Position} b
+ * @returns {number}
+ */
+function comparePositions(a, b) {
+    return a.line === b.line ? a.col - b.col : a.line - b.line;
+}
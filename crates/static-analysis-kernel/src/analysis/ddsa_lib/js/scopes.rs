@@ -0,0 +1,121 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use crate::analysis::ddsa_lib::common::Class;
+use std::marker::PhantomData;
+
+/// The ES6 class `ScopeTable`, which wraps the symbol table computed by [`ddsa_lib::ScopeTable`](crate::analysis::ddsa_lib::ScopeTable).
+#[derive(Debug)]
+pub struct ScopeTable<T>(PhantomData<T>);
+
+impl ScopeTable<Class> {
+    /// The name of the JavaScript class.
+    pub const CLASS_NAME: &'static str = "ScopeTable";
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::ddsa_lib::bridge::ContextBridge;
+    use crate::analysis::ddsa_lib::common::{attach_as_global, v8_string};
+    use crate::analysis::ddsa_lib::js::ScopeTable;
+    use crate::analysis::ddsa_lib::test_utils::{
+        cfg_test_runtime, js_class_eq, js_instance_eq, try_execute,
+    };
+    use crate::analysis::tree_sitter::get_tree;
+    use crate::model::common::Language;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[test]
+    fn js_properties_canary() {
+        let instance_expected = &[
+            // Variables
+            "scopes",
+            "symbols",
+            "unresolved",
+            "_symbolsByScope",
+            // Methods
+            "getScopeAt",
+            "getSymbols",
+            "lookup",
+            "_index",
+        ];
+        assert!(js_instance_eq(ScopeTable::CLASS_NAME, instance_expected));
+        let class_expected = &[];
+        assert!(js_class_eq(ScopeTable::CLASS_NAME, class_expected));
+    }
+
+    /// The symbol table is computed by Rust, exposed via `context.scopes` and cached until the tree changes.
+    #[test]
+    fn root_context_scopes() {
+        let mut runtime = cfg_test_runtime();
+        let bridge = ContextBridge::try_new(&mut runtime.handle_scope()).unwrap();
+        let bridge = Rc::new(RefCell::new(bridge));
+        runtime.op_state().borrow_mut().put(Rc::clone(&bridge));
+        let scope = &mut runtime.handle_scope();
+        let v8_root_ctx = bridge.borrow().as_local(scope);
+        attach_as_global(scope, v8_root_ctx, "ROOT");
+
+        let code_1 = "\
+x = 1
+def foo(y):
+    x = y
+    return len(x)
+";
+        let code_1 = Arc::<str>::from(code_1);
+        let filename = Arc::<str>::from("file.py");
+        let tree_1 = get_tree(&code_1, &Language::Python).unwrap();
+        bridge
+            .borrow_mut()
+            .set_root_context(scope, &tree_1, &code_1, &filename);
+        for (code, expected) in [
+            (
+                "ROOT.scopes.scopes.map((s) => s.kind).join();",
+                "module,function",
+            ),
+            (
+                "ROOT.scopes.getScopeAt({line: 3, col: 5}).kind;",
+                "function",
+            ),
+            (
+                "ROOT.scopes.lookup('x', {line: 1, col: 1}).definitions[0].start.line;",
+                "1",
+            ),
+            (
+                "ROOT.scopes.lookup('x', {line: 4, col: 16}).definitions[0].start.line;",
+                "3",
+            ),
+            ("ROOT.scopes.lookup('y', {line: 1, col: 1});", "undefined"),
+            (
+                "ROOT.scopes.lookup('y', {line: 3, col: 9}).references.length;",
+                "1",
+            ),
+            (
+                "ROOT.scopes.getSymbols(0).map((s) => s.name).join();",
+                "x,foo",
+            ),
+            ("ROOT.scopes.unresolved.map((r) => r.name).join();", "len"),
+            ("ROOT.scopes === ROOT.scopes;", "true"),
+        ] {
+            let res = try_execute(scope, code).unwrap();
+            assert_eq!(res.to_rust_string_lossy(scope), expected, "{}", code);
+        }
+
+        // A new file clears the cache.
+        let code_2 = Arc::<str>::from("import os\n");
+        let tree_2 = get_tree(&code_2, &Language::Python).unwrap();
+        bridge
+            .borrow_mut()
+            .set_root_context(scope, &tree_2, &code_2, &filename);
+        let cache_key = v8_string(scope, "__js_cachedScopes");
+        let v8_root_ctx = bridge.borrow().as_local(scope);
+        assert!(v8_root_ctx
+            .get(scope, cache_key.into())
+            .unwrap()
+            .is_undefined());
+        let res = try_execute(scope, "ROOT.scopes.symbols.map((s) => s.name).join();").unwrap();
+        assert_eq!(res.to_rust_string_lossy(scope), "os");
+    }
+}
@@ -1,5 +1,6 @@
 import {Edit} from "ext:ddsa_lib/edit";
 import {Fix} from "ext:ddsa_lib/fix";
+import {sandbox} from "ext:ddsa_lib/sandbox";
 import {Violation} from "ext:ddsa_lib/violation";
 
 globalThis.stellaAllErrors = [];
@@ -27,8 +28,12 @@ export function buildError(startLine, startCol, endLine, endCol, message, severi
   return Violation.new(startLine, startCol, endLine, endCol, message);
 }
 
-export function buildFix(message, list) {
-  return Fix.new(message, list);
+export function buildFileError(message, severity, category) {
+  return Violation.newFileLevel(message);
+}
+
+export function buildFix(message, list, applicability) {
+  return Fix.new(message, list, applicability?.toUpperCase());
 }
 
 export function buildEditUpdate(startLine, startCol, endLine, endCol, content) {
@@ -82,7 +87,13 @@ export function getCodeForNode(node, code) {
 
 // We re-use the same v8 isolate across multiple rule executions. Because the user's JavaScript can mutate variables
 // external to its scope, this function allows us to ensure that a closure is executed in a "clean", non-mutated context.
-export function _cleanExecute(closure) {
-  stellaAllErrors.length = 0;
-  return closure();
-}
\ No newline at end of file
+// The closure is executed in the sandbox: it can only access the globals with side effects if `allowSideEffects` is true,
+// and it's executed deterministically (seeded `Math.random`, frozen `Date`) unless `seed` is null.
+//
+// NOTE: This function isn't defined in the global scope: it's only called by the Rust runtime.
+export function _cleanExecute(closure, allowSideEffects = false, seed = null) {
+  return sandbox.run(() => {
+    stellaAllErrors.length = 0;
+    return closure();
+  }, allowSideEffects, seed);
+}
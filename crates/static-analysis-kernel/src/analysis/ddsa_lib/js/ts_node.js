@@ -2,7 +2,11 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
-const { op_ts_node_text } = Deno.core.ops;
+const {
+    op_ts_node_named_children,
+    op_ts_node_parent,
+    op_ts_node_text,
+} = Deno.core.ops;
 
 /**
  * A non-zero integer assigned by the Rust static-analysis-kernel.
@@ -34,9 +38,11 @@ export class TreeSitterNode {
      * @param {number} startCol
      * @param {number} endLine
      * @param {number} endCol
+     * @param {number} startByte
+     * @param {number} endByte
      * @param {NodeTypeId} nodeTypeId
      */
-    constructor(id, startLine, startCol, endLine, endCol, nodeTypeId) {
+    constructor(id, startLine, startCol, endLine, endCol, startByte, endByte, nodeTypeId) {
         /**
          * The id of this node, assigned by the Rust static analysis kernel.
          * @type {NodeId}
@@ -63,6 +69,16 @@ export class TreeSitterNode {
          * @private
          */
         this._endCol = endCol;
+        /**
+         * @type {number}
+         * @private
+         */
+        this._startByte = startByte;
+        /**
+         * @type {number}
+         * @private
+         */
+        this._endByte = endByte;
         /** @type {NodeTypeId} */
         this._typeId = nodeTypeId;
         /**
@@ -131,6 +147,42 @@ export class TreeSitterNode {
         return this._cachedEnd;
     }
 
+    /**
+     * A getter to return the byte offset of the start of this node within the file.
+     * @returns {number}
+     */
+    get startByte() {
+        return this._startByte;
+    }
+
+    /**
+     * A getter to return the byte offset of the end of this node within the file.
+     * @returns {number}
+     */
+    get endByte() {
+        return this._endByte;
+    }
+
+    /**
+     * Returns the named children of this node, in order.
+     * @returns {Array<TreeSitterNode>}
+     *
+     * @remarks
+     * The children are retrieved from the tree-sitter tree retained by Rust, so only the nodes that
+     * are visited are sent to JavaScript.
+     */
+    children() {
+        return op_ts_node_named_children(this.id);
+    }
+
+    /**
+     * Returns the parent of this node, or `undefined` if this is the root node.
+     * @returns {TreeSitterNode | undefined}
+     */
+    parent() {
+        return op_ts_node_parent(this.id);
+    }
+
     /**
      * A getter to return the string version of this node's type.
      * @returns {string}
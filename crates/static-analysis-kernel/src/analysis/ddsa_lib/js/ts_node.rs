@@ -17,6 +17,10 @@ pub struct TreeSitterNode<T> {
     pub start_col: u32,
     pub end_line: u32,
     pub end_col: u32,
+    /// The byte offset of the start of the node within the file.
+    pub start_byte: u32,
+    /// The byte offset of the end of the node within the file.
+    pub end_byte: u32,
     pub node_type_id: u16,
     pub _pd: PhantomData<T>,
 }
@@ -44,8 +48,19 @@ impl TreeSitterNodeFn<Class> {
         let start_col = v8_uint(scope, ts_node.start_col).into();
         let end_line = v8_uint(scope, ts_node.end_line).into();
         let end_col = v8_uint(scope, ts_node.end_col).into();
+        let start_byte = v8_uint(scope, ts_node.start_byte).into();
+        let end_byte = v8_uint(scope, ts_node.end_byte).into();
         let node_type_id = v8_uint(scope, ts_node.node_type_id as u32).into();
-        let args = [id, start_line, start_col, end_line, end_col, node_type_id];
+        let args = [
+            id,
+            start_line,
+            start_col,
+            end_line,
+            end_col,
+            start_byte,
+            end_byte,
+            node_type_id,
+        ];
         self.0
             .open(scope)
             .new_instance(scope, &args[..])
@@ -72,6 +87,8 @@ mod tests {
             "_startCol",
             "_endLine",
             "_endCol",
+            "_startByte",
+            "_endByte",
             "_typeId",
             "_cachedStart",
             "_cachedEnd",
@@ -81,6 +98,10 @@ mod tests {
             "type",
             "start",
             "end",
+            "startByte",
+            "endByte",
+            "children",
+            "parent",
         ];
         assert!(js_instance_eq(TreeSitterNodeFn::CLASS_NAME, expected));
         let expected = &[];
@@ -100,6 +121,8 @@ mod tests {
             start_col: 1,
             end_line: 456,
             end_col: 32,
+            start_byte: 2000,
+            end_byte: 9000,
             node_type_id: 8,
             _pd: PhantomData,
         };
@@ -142,4 +165,30 @@ mod tests {
             assert_eq!(v8_cached_pos.get_hash(), v8_returned_pos.get_hash())
         }
     }
+
+    #[test]
+    fn byte_offset_getters() {
+        let mut runtime = cfg_test_runtime();
+        let scope = &mut runtime.handle_scope();
+        let js_class = TreeSitterNodeFn::<Class>::try_new(scope).unwrap();
+
+        let ts_node = TreeSitterNode::<Instance> {
+            id: 3,
+            start_line: 2,
+            start_col: 5,
+            end_line: 2,
+            end_col: 9,
+            start_byte: 17,
+            end_byte: 21,
+            node_type_id: 8,
+            _pd: PhantomData,
+        };
+        let v8_ts_node = js_class.new_instance(scope, ts_node);
+        attach_as_global(scope, v8_ts_node, "TS_NODE");
+
+        let res = try_execute(scope, "TS_NODE.startByte;").unwrap();
+        assert_eq!(res.uint32_value(scope).unwrap(), 17);
+        let res = try_execute(scope, "TS_NODE.endByte;").unwrap();
+        assert_eq!(res.uint32_value(scope).unwrap(), 21);
+    }
 }
@@ -11,7 +11,39 @@ export class DDSA_Console {
      * @returns {void}
      */
     log(...args) {
-        op_console_push(`${this.constructor.stringifyAll(...args)}`);
+        op_console_push("log", `${this.constructor.stringifyAll(...args)}`);
+    }
+
+    /**
+     * @param {...*} args
+     * @returns {void}
+     */
+    debug(...args) {
+        op_console_push("debug", `${this.constructor.stringifyAll(...args)}`);
+    }
+
+    /**
+     * @param {...*} args
+     * @returns {void}
+     */
+    info(...args) {
+        op_console_push("info", `${this.constructor.stringifyAll(...args)}`);
+    }
+
+    /**
+     * @param {...*} args
+     * @returns {void}
+     */
+    warn(...args) {
+        op_console_push("warn", `${this.constructor.stringifyAll(...args)}`);
+    }
+
+    /**
+     * @param {...*} args
+     * @returns {void}
+     */
+    error(...args) {
+        op_console_push("error", `${this.constructor.stringifyAll(...args)}`);
     }
 
     /**
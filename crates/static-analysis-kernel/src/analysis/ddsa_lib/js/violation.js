@@ -2,6 +2,15 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
+/**
+ * @typedef RelatedLocation
+ * @property {number} startLine
+ * @property {number} startCol
+ * @property {number} endLine
+ * @property {number} endCol
+ * @property {string} message
+ */
+
 /**
  * A static analysis violation to be reported to the Rust static analysis kernel.
  */
@@ -49,6 +58,32 @@ export class Violation {
          * @type {Array<Fix> | undefined}
          * */
         this.fixes = undefined;
+        /**
+         * Free-form tags associated with this Violation.
+         * @type {Array<string> | undefined}
+         */
+        this.tags = undefined;
+        /**
+         * The identifiers of the CWEs this Violation maps to (e.g. `79` for CWE-79).
+         * @type {Array<number> | undefined}
+         */
+        this.cwe = undefined;
+        /**
+         * A URL to documentation specific to this Violation.
+         * @type {string | undefined}
+         */
+        this.documentationUrl = undefined;
+        /**
+         * Secondary locations that help explain this Violation.
+         * @type {Array<RelatedLocation> | undefined}
+         */
+        this.relatedLocations = undefined;
+        /**
+         * Whether this Violation applies to the file as a whole rather than to a specific location.
+         * @type {boolean}
+         * @readonly
+         */
+        this.fileLevel = false;
     }
 
     /**
@@ -64,6 +99,63 @@ export class Violation {
         return this;
     }
 
+    /**
+     * @param {string} tag
+     * @returns {Violation}
+     * Adds a tag to this violation and returns `this`.
+     */
+    addTag(tag) {
+        if (this.tags === undefined) {
+            this.tags = [];
+        }
+        this.tags.push(String(tag));
+        return this;
+    }
+
+    /**
+     * @param {number | string} cwe Either the numeric identifier (`79`) or the full name (`"CWE-79"`) of the CWE.
+     * @returns {Violation}
+     * Adds a CWE to this violation and returns `this`.
+     */
+    addCwe(cwe) {
+        const id = typeof cwe === "string" ? Number(cwe.trim().replace(/^CWE-/i, "")) : cwe;
+        if (!Number.isInteger(id) || id <= 0) {
+            throw new TypeError(`invalid CWE: ${cwe}`);
+        }
+        if (this.cwe === undefined) {
+            this.cwe = [];
+        }
+        this.cwe.push(id);
+        return this;
+    }
+
+    /**
+     * @param {string} url
+     * @returns {Violation}
+     * Sets the documentation URL of this violation and returns `this`.
+     */
+    setDocumentationUrl(url) {
+        this.documentationUrl = url;
+        return this;
+    }
+
+    /**
+     * @param {number} startLine
+     * @param {number} startCol
+     * @param {number} endLine
+     * @param {number} endCol
+     * @param {string} message A message describing the location (e.g. "the function is declared here").
+     * @returns {Violation}
+     * Adds a secondary location to this violation and returns `this`.
+     */
+    addRelatedLocation(startLine, startCol, endLine, endCol, message) {
+        if (this.relatedLocations === undefined) {
+            this.relatedLocations = [];
+        }
+        this.relatedLocations.push({startLine, startCol, endLine, endCol, message});
+        return this;
+    }
+
     /**
      * Creates a new `Violation`.
      *
@@ -81,4 +173,17 @@ export class Violation {
     static new(startLine, startCol, endLine, endCol, message) {
         return new Violation(startLine, startCol, endLine, endCol, message);
     }
+
+    /**
+     * Creates a new `Violation` that applies to the file as a whole (e.g. "the file is missing a license header").
+     * Its position is the start of the file.
+     *
+     * @param {string} message
+     * @returns {Violation}
+     */
+    static newFileLevel(message) {
+        const violation = new Violation(1, 1, 1, 1, message);
+        violation.fileLevel = true;
+        return violation;
+    }
 }
@@ -23,9 +23,40 @@ pub struct Violation<T> {
     pub end_col: u32,
     pub message: String,
     pub fixes: Option<Vec<Fix<T>>>,
+    pub tags: Option<Vec<String>>,
+    pub cwe: Option<Vec<u32>>,
+    pub documentation_url: Option<String>,
+    pub related_locations: Option<Vec<RelatedLocation>>,
+    pub file_level: bool,
     pub _pd: PhantomData<T>,
 }
 
+/// A representation of a JavaScript `RelatedLocation` object.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct RelatedLocation {
+    pub start_line: u32,
+    pub start_col: u32,
+    pub end_line: u32,
+    pub end_col: u32,
+    pub message: String,
+}
+
+impl From<RelatedLocation> for violation::RelatedLocation {
+    fn from(value: RelatedLocation) -> Self {
+        violation::RelatedLocation {
+            start: Position {
+                line: value.start_line,
+                col: value.start_col,
+            },
+            end: Position {
+                line: value.end_line,
+                col: value.end_col,
+            },
+            message: value.message,
+        }
+    }
+}
+
 impl Violation<Instance> {
     pub const CLASS_NAME: &'static str = "Violation";
 
@@ -48,10 +79,29 @@ impl Violation<Instance> {
                 line: self.end_line,
                 col: self.end_col,
             },
-            message: self.message,
+            message: self.message.into(),
             severity,
             category,
             fixes,
+            merged_rules: vec![],
+            tags: self.tags.unwrap_or_default(),
+            cwe: self.cwe.unwrap_or_default(),
+            documentation_url: self.documentation_url,
+            related_locations: self
+                .related_locations
+                .map(|locations| {
+                    locations
+                        .into_iter()
+                        .map(violation::RelatedLocation::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            file_level: self.file_level,
+            fingerprint: None,
+            owners: vec![],
+            snippet: None,
+            offsets: None,
+            last_commit: None,
         }
     }
 }
@@ -93,6 +143,50 @@ impl V8Converter for ViolationConverter {
                     .collect::<Result<Vec<_>, _>>()
             })
             .transpose()?;
+        let tags = get_optional_field::<v8::Array>(v8_obj, "tags", scope, "array | undefined")?;
+        let tags = tags
+            .map(|array| {
+                iter_v8_array(array, scope)
+                    .map(|value| {
+                        v8_type_from::<v8::String>(value, "string")
+                            .map(|tag| tag.to_rust_string_lossy(scope))
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+        let cwe = get_optional_field::<v8::Array>(v8_obj, "cwe", scope, "array | undefined")?;
+        let cwe = cwe
+            .map(|array| {
+                iter_v8_array(array, scope)
+                    .map(|value| {
+                        v8_type_from::<v8::Integer>(value, "number").map(|id| id.value() as u32)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+        let documentation_url = get_optional_field::<v8::String>(
+            v8_obj,
+            "documentationUrl",
+            scope,
+            "string | undefined",
+        )?
+        .map(|url| url.to_rust_string_lossy(scope));
+        let related_locations = get_optional_field::<v8::Array>(
+            v8_obj,
+            "relatedLocations",
+            scope,
+            "array | undefined",
+        )?;
+        let related_locations = related_locations
+            .map(|array| {
+                iter_v8_array(array, scope)
+                    .map(|value| related_location_from(scope, value))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+        let file_level =
+            get_optional_field::<v8::Boolean>(v8_obj, "fileLevel", scope, "boolean | undefined")?
+                .is_some_and(|file_level| file_level.is_true());
         Ok(Violation {
             start_line,
             start_col,
@@ -100,11 +194,37 @@ impl V8Converter for ViolationConverter {
             end_col,
             message,
             fixes,
+            tags,
+            cwe,
+            documentation_url,
+            related_locations,
+            file_level,
             _pd: PhantomData,
         })
     }
 }
 
+/// Converts a JavaScript `RelatedLocation` object into a [`RelatedLocation`].
+fn related_location_from<'s>(
+    scope: &mut HandleScope<'s>,
+    value: v8::Local<'s, v8::Value>,
+) -> Result<RelatedLocation, DDSAJsRuntimeError> {
+    let v8_obj = v8_type_from::<v8::Object>(value, "RelatedLocation")?;
+    let start_line = get_field::<v8::Integer>(v8_obj, "startLine", scope, "number")?.value() as u32;
+    let start_col = get_field::<v8::Integer>(v8_obj, "startCol", scope, "number")?.value() as u32;
+    let end_line = get_field::<v8::Integer>(v8_obj, "endLine", scope, "number")?.value() as u32;
+    let end_col = get_field::<v8::Integer>(v8_obj, "endCol", scope, "number")?.value() as u32;
+    let message =
+        get_field::<v8::String>(v8_obj, "message", scope, "string")?.to_rust_string_lossy(scope);
+    Ok(RelatedLocation {
+        start_line,
+        start_col,
+        end_line,
+        end_col,
+        message,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::analysis::ddsa_lib::js::Violation;
@@ -120,11 +240,20 @@ mod tests {
             "endCol",
             "message",
             "fixes",
+            "tags",
+            "cwe",
+            "documentationUrl",
+            "relatedLocations",
+            "fileLevel",
             // Methods
             "addFix",
+            "addTag",
+            "addCwe",
+            "setDocumentationUrl",
+            "addRelatedLocation",
         ];
         assert!(js_instance_eq(Violation::CLASS_NAME, instance_exp));
-        let class_expected = &["new"];
+        let class_expected = &["new", "newFileLevel"];
         assert!(js_class_eq(Violation::CLASS_NAME, class_expected));
     }
 }
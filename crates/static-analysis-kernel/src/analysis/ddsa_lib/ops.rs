@@ -2,19 +2,32 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
-use crate::analysis::ddsa_lib::common::NodeId;
-use crate::analysis::ddsa_lib::{bridge, runtime};
-use deno_core::{op2, OpState};
+use crate::analysis::ddsa_lib::common::{v8_interned, NodeId};
+use crate::analysis::ddsa_lib::string_value::string_value;
+use crate::analysis::ddsa_lib::{bridge, runtime, RawTSNode, ScopeTable};
+use crate::analysis::tree_sitter::TSCaptureContent;
+use crate::model::common::Position;
+use crate::model::rule::ConsoleLevel;
+use deno_core::error::AnyError;
+use deno_core::{op2, v8, OpState};
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Appends a line to the console, with the name of the `console` method that wrote it.
 #[op2(fast)]
-pub fn op_console_push(state: &mut OpState, #[string] line: &str) {
+pub fn op_console_push(state: &mut OpState, #[string] level: &str, #[string] line: &str) {
+    let level = match level {
+        "debug" => ConsoleLevel::Debug,
+        "info" => ConsoleLevel::Info,
+        "warn" => ConsoleLevel::Warn,
+        "error" => ConsoleLevel::Error,
+        _ => ConsoleLevel::Log,
+    };
     let console = state.borrow::<Rc<RefCell<runtime::JsConsole>>>();
     let mut console = console
         .try_borrow_mut()
         .expect("console should only be accessed via sequential executions");
-    console.push(line);
+    console.push(level, line);
 }
 
 /// Returns a string containing the text used to generate the tree-sitter tree.
@@ -49,6 +62,22 @@ pub fn op_current_filename(state: &OpState) -> String {
         .to_string()
 }
 
+/// Returns the symbol table of the file being scanned.
+///
+/// # Panics
+/// Panics if the [`ddsa_lib::RootContext`](crate::analysis::ddsa_lib::RootContext) has not set the tree or its text.
+#[op2]
+#[serde]
+pub fn op_current_scopes(state: &OpState) -> ScopeTable {
+    let ctx_bridge = state.borrow::<Rc<RefCell<bridge::ContextBridge>>>();
+    ctx_bridge
+        .borrow()
+        .ddsa_root_context()
+        .get_scopes()
+        .expect("tree and its text should always be `Some` during rule execution")
+        .clone()
+}
+
 /// Returns a string containing the text that spans a tree-sitter node.
 ///
 /// # Panics
@@ -70,6 +99,227 @@ pub fn op_ts_node_text(state: &OpState, #[smi] node_id: u32) -> Option<String> {
         .map(ToString::to_string)
 }
 
+/// Returns a string containing the text between two positions of the tree's text (the `end` position is exclusive).
+/// Positions use 1-based lines and 1-based byte columns, like the positions of tree-sitter nodes.
+///
+/// If the positions are not valid, `None` is returned.
+#[op2]
+#[string]
+pub fn op_text_between(
+    state: &OpState,
+    #[smi] start_line: u32,
+    #[smi] start_col: u32,
+    #[smi] end_line: u32,
+    #[smi] end_col: u32,
+) -> Option<String> {
+    let ctx_bridge = state
+        .borrow::<Rc<RefCell<bridge::ContextBridge>>>()
+        .borrow();
+    let start = Position {
+        line: start_line,
+        col: start_col,
+    };
+    let end = Position {
+        line: end_line,
+        col: end_col,
+    };
+    ctx_bridge
+        .ddsa_root_context()
+        .get_text_between(&start, &end)
+        .map(ToString::to_string)
+}
+
+/// Returns a string containing the lines of the tree's text from `start_line` to `end_line` (1-based, inclusive),
+/// without the line break of the last line.
+///
+/// If the lines are not valid, `None` is returned.
+#[op2]
+#[string]
+pub fn op_text_lines(
+    state: &OpState,
+    #[smi] start_line: u32,
+    #[smi] end_line: u32,
+) -> Option<String> {
+    let ctx_bridge = state
+        .borrow::<Rc<RefCell<bridge::ContextBridge>>>()
+        .borrow();
+    ctx_bridge
+        .ddsa_root_context()
+        .get_lines(start_line, end_line)
+        .map(ToString::to_string)
+}
+
+/// Returns the runtime value of a string literal node, with its escape sequences decoded.
+/// See [`string_value`](crate::analysis::ddsa_lib::string_value::string_value) for the supported constructs.
+///
+/// If the node doesn't exist or isn't a string literal, `None` is returned.
+///
+/// # Panics
+/// Panics if the [`ddsa_lib::RootContext`](crate::analysis::ddsa_lib::RootContext) has not set the tree or its text.
+#[op2]
+#[string]
+pub fn op_ts_node_string_value(state: &OpState, #[smi] node_id: u32) -> Option<String> {
+    let ctx_bridge = state
+        .borrow::<Rc<RefCell<bridge::ContextBridge>>>()
+        .borrow();
+    let root_ctx = ctx_bridge.ddsa_root_context();
+    let tree = root_ctx
+        .get_tree()
+        .expect("tree should always be `Some` during rule execution");
+    let tree_text = root_ctx
+        .get_text()
+        .expect("tree text should always be `Some` during rule execution");
+    let node_bridge = state.borrow::<Rc<RefCell<bridge::TsNodeBridge>>>().borrow();
+    let ts_node = restore_ts_node_for_op(&node_bridge, node_id)?;
+    string_value(&tree.language(), ts_node, tree_text)
+}
+
+/// Returns an array containing the named children of a tree-sitter node, in order. Each child is
+/// inserted into the [`TsNodeBridge`](bridge::TsNodeBridge) if it wasn't already present.
+///
+/// If the node doesn't exist, an empty array is returned.
+#[op2]
+pub fn op_ts_node_named_children<'s>(
+    state: &OpState,
+    scope: &mut v8::HandleScope<'s>,
+    #[smi] node_id: u32,
+) -> v8::Local<'s, v8::Array> {
+    let tsn_bridge = state.borrow::<Rc<RefCell<bridge::TsNodeBridge>>>();
+    let Some(raw_node) = tsn_bridge.borrow().get_raw(node_id).cloned() else {
+        return v8::Array::new(scope, 0);
+    };
+    // Safety: see `restore_ts_node_for_op`.
+    let ts_node = unsafe { raw_node.to_node() };
+    let mut tsn_bridge = tsn_bridge.borrow_mut();
+    let mut cursor = ts_node.walk();
+    let children = ts_node
+        .named_children(&mut cursor)
+        .map(|child| {
+            let child_id = tsn_bridge.insert(scope, child);
+            tsn_bridge
+                .v8_get(scope, child_id)
+                .expect("node should have just been inserted")
+                .into()
+        })
+        .collect::<Vec<v8::Local<v8::Value>>>();
+    v8::Array::new_with_elements(scope, &children)
+}
+
+/// Returns the parent of a tree-sitter node, inserting it into the [`TsNodeBridge`](bridge::TsNodeBridge)
+/// if it wasn't already present.
+///
+/// If the node doesn't exist, or if it is the root node, `undefined` is returned.
+#[op2]
+pub fn op_ts_node_parent<'s>(
+    state: &OpState,
+    scope: &mut v8::HandleScope<'s>,
+    #[smi] node_id: u32,
+) -> v8::Local<'s, v8::Value> {
+    let tsn_bridge = state.borrow::<Rc<RefCell<bridge::TsNodeBridge>>>();
+    let Some(raw_node) = tsn_bridge.borrow().get_raw(node_id).cloned() else {
+        return v8::undefined(scope).into();
+    };
+    // Safety: see `restore_ts_node_for_op`.
+    let ts_node = unsafe { raw_node.to_node() };
+    let Some(parent) = ts_node.parent() else {
+        return v8::undefined(scope).into();
+    };
+    let mut tsn_bridge = tsn_bridge.borrow_mut();
+    let parent_id = tsn_bridge.insert(scope, parent);
+    tsn_bridge
+        .v8_get(scope, parent_id)
+        .expect("node should have just been inserted")
+        .into()
+}
+
+/// Executes a tree-sitter query on the current tree, returning an array containing an object for each match.
+/// The keys of the object are the capture names, and the values are either a node, or an array of nodes
+/// if the capture is quantified (e.g. `(comment)+ @cap`).
+///
+/// If `scoped` is true, the query is only executed on the node with the given `node_id` (and its descendants).
+/// Otherwise, it is executed on the entire tree.
+///
+/// Queries are compiled once per language and cached in the [`QueryCache`](runtime::QueryCache), which
+/// only keeps the most recently used queries.
+///
+/// # Errors
+/// Returns an error if the query is not valid for the language of the tree.
+///
+/// # Panics
+/// Panics if the [`ddsa_lib::RootContext`](crate::analysis::ddsa_lib::RootContext) has not set the tree or its text.
+#[op2]
+pub fn op_ts_query<'s>(
+    state: &OpState,
+    scope: &mut v8::HandleScope<'s>,
+    #[string] pattern: &str,
+    #[smi] node_id: u32,
+    scoped: bool,
+) -> Result<v8::Local<'s, v8::Array>, AnyError> {
+    let ctx_bridge = state
+        .borrow::<Rc<RefCell<bridge::ContextBridge>>>()
+        .borrow();
+    let root_ctx = ctx_bridge.ddsa_root_context();
+    let tree = root_ctx
+        .get_tree()
+        .expect("tree should always be `Some` during rule execution");
+    let tree_text = root_ctx
+        .get_text()
+        .expect("tree text should always be `Some` during rule execution");
+    let query = state
+        .borrow::<Rc<RefCell<runtime::QueryCache>>>()
+        .borrow_mut()
+        .get_or_compile(&tree.language(), pattern)?;
+
+    let tsn_bridge = state.borrow::<Rc<RefCell<bridge::TsNodeBridge>>>();
+    let raw_node = if scoped {
+        let Some(raw_node) = tsn_bridge.borrow().get_raw(node_id).cloned() else {
+            return Ok(v8::Array::new(scope, 0));
+        };
+        raw_node
+    } else {
+        RawTSNode::new(tree.root_node())
+    };
+    // Safety:
+    // 1. An op will only be called during a JavaScript rule execution, where it's guaranteed that
+    //    the `tree_sitter::Tree` exists (and is owned by the `ddsa_lib::RootContext` on the `bridge::ContextBridge`).
+    // 2. The node is either the root node of that tree, or a node that was inserted into the bridge
+    //    by Rust: the `node_id` passed by the rule is only used to look it up (see `restore_ts_node_for_op`).
+    // 3. We never mutate the `tree_sitter::Tree` or any related nodes.
+    let ts_node = unsafe { raw_node.to_node() };
+
+    let mut tsn_bridge = tsn_bridge.borrow_mut();
+    let mut cursor = query.cursor();
+    let mut v8_matches = Vec::<v8::Local<v8::Value>>::new();
+    for query_match in cursor.matches(ts_node, tree_text) {
+        let v8_match = v8::Object::new(scope);
+        for capture in query_match {
+            let capture = tsn_bridge.insert_capture(scope, capture);
+            let v8_key = v8_interned(scope, &capture.name);
+            let v8_value: v8::Local<v8::Value> = match capture.contents {
+                TSCaptureContent::Single(node_id) => tsn_bridge
+                    .v8_get(scope, node_id)
+                    .expect("node should have just been inserted")
+                    .into(),
+                TSCaptureContent::Multi(node_ids) => {
+                    let v8_nodes = node_ids
+                        .into_iter()
+                        .map(|node_id| {
+                            tsn_bridge
+                                .v8_get(scope, node_id)
+                                .expect("node should have just been inserted")
+                                .into()
+                        })
+                        .collect::<Vec<v8::Local<v8::Value>>>();
+                    v8::Array::new_with_elements(scope, &v8_nodes).into()
+                }
+            };
+            v8_match.set(scope, v8_key.into(), v8_value);
+        }
+        v8_matches.push(v8_match.into());
+    }
+    Ok(v8::Array::new_with_elements(scope, &v8_matches))
+}
+
 /// A function that restores a `tree_sitter::Node` given its `NodeId`.
 fn restore_ts_node_for_op(
     bridge: &bridge::TsNodeBridge,
@@ -2,24 +2,58 @@
 // This product includes software developed at Datadog (https://www.datadoghq.com/).
 // Copyright 2024 Datadog, Inc.
 
-use crate::analysis::ddsa_lib::common::DDSAJsRuntimeError;
+use crate::analysis::ddsa_lib::common::{load_function, v8_interned, DDSAJsRuntimeError};
 use crate::analysis::ddsa_lib::extension::ddsa_lib;
+use crate::analysis::tree_sitter::TSQuery;
+use crate::model::rule::{ConsoleLevel, ConsoleRecord};
+use deno_core::v8;
 use std::cell::{RefCell, RefMut};
+use std::collections::HashMap;
 use std::rc::Rc;
 
 /// The Datadog Static Analyzer JavaScript runtime
 pub struct JsRuntime {
     runtime: deno_core::JsRuntime,
     console: Rc<RefCell<JsConsole>>,
+    /// The `_cleanExecute` function of `stella_compat.js`, which isn't reachable from `globalThis`.
+    clean_execute: v8::Global<v8::Function>,
 }
 
+/// The property of `globalThis` that holds `_cleanExecute` until the runtime captures it.
+const CLEAN_EXECUTE: &str = "__ddsaCleanExecute";
+
 impl JsRuntime {
     pub fn try_new() -> Result<Self, DDSAJsRuntimeError> {
         let mut runtime = base_js_runtime();
         let console = Rc::new(RefCell::new(JsConsole::new()));
         runtime.op_state().borrow_mut().put(Rc::clone(&console));
+        let query_cache = Rc::new(RefCell::new(QueryCache::default()));
+        runtime.op_state().borrow_mut().put(query_cache);
+
+        // `_cleanExecute` controls the sandbox of the rules: it's removed from the global scope
+        // before any rule is executed, so that a rule can't call it to change its own permissions.
+        let clean_execute = {
+            let scope = &mut runtime.handle_scope();
+            let clean_execute = load_function(scope, CLEAN_EXECUTE)?;
+            let global = scope.get_current_context().global(scope);
+            let key = v8_interned(scope, CLEAN_EXECUTE);
+            global.delete(scope, key.into());
+            clean_execute
+        };
+
+        Ok(Self {
+            runtime,
+            console,
+            clean_execute,
+        })
+    }
 
-        Ok(Self { runtime, console })
+    /// Returns the function that executes the closure of a rule in the sandbox, with the
+    /// arguments `(closure, allowSideEffects, seed)`.
+    ///
+    /// NOTE: This is temporary scaffolding used during the transition to `ddsa_lib::JsRuntime`.
+    pub(crate) fn clean_execute_compat(&self) -> v8::Global<v8::Function> {
+        self.clean_execute.clone()
     }
 
     /// Provides a mutable reference to the underlying [`deno_core::JsRuntime`].
@@ -48,22 +82,193 @@ pub(crate) fn base_js_runtime() -> deno_core::JsRuntime {
     })
 }
 
-/// A mutable scratch space that collects the output of the `console.log` function invoked by JavaScript code.
-pub(crate) struct JsConsole(Vec<String>);
+/// The maximum number of lines kept by the console for an execution.
+const MAX_CONSOLE_LINES: usize = 500;
+
+/// The maximum number of bytes of the messages kept by the console for an execution.
+const MAX_CONSOLE_BYTES: usize = 64 * 1024;
+
+/// The marker appended to a message that was cut at [`MAX_CONSOLE_BYTES`].
+const TRUNCATED_MARKER: &str = "... [truncated]";
+
+/// A mutable scratch space that collects the output of the `console` functions invoked by JavaScript code.
+///
+/// The output of an execution is bounded by [`MAX_CONSOLE_LINES`] and [`MAX_CONSOLE_BYTES`], so
+/// that a rule logging in a loop can't bloat the memory of the runtime or the reports: the lines
+/// past the limits are dropped, and counted in a final line.
+pub(crate) struct JsConsole {
+    records: Vec<ConsoleRecord>,
+    bytes: usize,
+    dropped: usize,
+}
 
 impl JsConsole {
     /// Creates a new, empty `Console`.
     pub fn new() -> Self {
-        Self(Vec::new())
+        Self {
+            records: Vec::new(),
+            bytes: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Appends a line to the console, unless the limits of the console are reached.
+    pub fn push(&mut self, level: ConsoleLevel, value: impl Into<String>) {
+        if self.records.len() >= MAX_CONSOLE_LINES || self.bytes >= MAX_CONSOLE_BYTES {
+            self.dropped += 1;
+            return;
+        }
+        let mut message = value.into();
+        let remaining = MAX_CONSOLE_BYTES - self.bytes;
+        if message.len() > remaining {
+            let mut end = remaining;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+            message.push_str(TRUNCATED_MARKER);
+            self.bytes = MAX_CONSOLE_BYTES;
+        } else {
+            self.bytes += message.len();
+        }
+        self.records.push(ConsoleRecord::new(level, message));
+    }
+
+    /// Removes all lines from the `Console`, and returns them. If lines were dropped, a last
+    /// line tells how many.
+    pub fn drain(&mut self) -> Vec<ConsoleRecord> {
+        let mut records = std::mem::take(&mut self.records);
+        if self.dropped > 0 {
+            records.push(ConsoleRecord::new(
+                ConsoleLevel::Warn,
+                format!("[{} more console lines truncated]", self.dropped),
+            ));
+        }
+        self.bytes = 0;
+        self.dropped = 0;
+        records
+    }
+}
+
+/// The maximum number of queries kept by a [`QueryCache`]. The cache lives as long as the runtime,
+/// so this bounds its memory even if rules build the source of their queries from the analyzed code.
+pub(crate) const MAX_CACHED_QUERIES: usize = 256;
+
+/// A cache of the tree-sitter queries that rules compile at runtime via `ddsa.query`, keyed by
+/// the language of the tree and the source of the query. Once it holds [`MAX_CACHED_QUERIES`]
+/// queries, the least recently used query is evicted to cache a new one.
+#[derive(Debug, Default)]
+pub(crate) struct QueryCache {
+    queries: HashMap<(tree_sitter::Language, String), CachedQuery>,
+    /// Incremented on each lookup, to order the queries by their last use.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct CachedQuery {
+    query: Rc<TSQuery>,
+    last_used: u64,
+}
+
+impl QueryCache {
+    /// Returns the compiled query for the given language and source, compiling and caching it
+    /// if it isn't cached.
+    pub fn get_or_compile(
+        &mut self,
+        language: &tree_sitter::Language,
+        source: &str,
+    ) -> Result<Rc<TSQuery>, tree_sitter::QueryError> {
+        self.clock += 1;
+        let key = (language.clone(), source.to_string());
+        if let Some(cached) = self.queries.get_mut(&key) {
+            cached.last_used = self.clock;
+            return Ok(Rc::clone(&cached.query));
+        }
+        let query = Rc::new(TSQuery::try_new(language, source)?);
+        if self.queries.len() >= MAX_CACHED_QUERIES {
+            let least_recently_used = self
+                .queries
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = least_recently_used {
+                self.queries.remove(&key);
+            }
+        }
+        self.queries.insert(
+            key,
+            CachedQuery {
+                query: Rc::clone(&query),
+                last_used: self.clock,
+            },
+        );
+        Ok(query)
     }
 
-    /// Appends a string to the console.
-    pub fn push(&mut self, value: impl Into<String>) {
-        self.0.push(value.into())
+    /// Returns the number of cached queries.
+    pub fn len(&self) -> usize {
+        self.queries.len()
     }
 
-    /// Removes all lines from the `Console`, returning them as an iterator.
-    pub fn drain(&mut self) -> impl Iterator<Item = String> + '_ {
-        self.0.drain(..)
+    /// Returns true if no queries are cached.
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The lines past the limits of the console are dropped, and counted in a final line.
+    #[test]
+    fn console_limits() {
+        let mut console = JsConsole::new();
+        console.push(ConsoleLevel::Warn, "first");
+        for _ in 0..MAX_CONSOLE_LINES + 9 {
+            console.push(ConsoleLevel::Log, "line");
+        }
+        let records = console.drain();
+        assert_eq!(records.len(), MAX_CONSOLE_LINES + 1);
+        assert_eq!(records[0], ConsoleRecord::new(ConsoleLevel::Warn, "first"));
+        assert_eq!(
+            records.last().unwrap(),
+            &ConsoleRecord::new(ConsoleLevel::Warn, "[10 more console lines truncated]")
+        );
+
+        // the message that reaches the limit of bytes is cut
+        console.push(ConsoleLevel::Log, "é".repeat(MAX_CONSOLE_BYTES));
+        console.push(ConsoleLevel::Log, "dropped");
+        let records = console.drain();
+        assert_eq!(records.len(), 2);
+        assert!(records[0].message.ends_with(TRUNCATED_MARKER));
+        assert!(records[0].message.len() <= MAX_CONSOLE_BYTES + TRUNCATED_MARKER.len());
+        assert!(console.drain().is_empty());
+    }
+
+    /// The cache keeps the most recently used queries, up to [`MAX_CACHED_QUERIES`].
+    #[test]
+    fn query_cache_evicts_least_recently_used() {
+        let language = crate::analysis::tree_sitter::get_tree_sitter_language(
+            &crate::model::common::Language::Python,
+        );
+        let source = |i: usize| format!("(identifier) @id{i}");
+        let mut cache = QueryCache::default();
+        for i in 0..MAX_CACHED_QUERIES {
+            cache.get_or_compile(&language, &source(i)).unwrap();
+        }
+        let first = cache.get_or_compile(&language, &source(0)).unwrap();
+        assert_eq!(cache.len(), MAX_CACHED_QUERIES);
+
+        // the second query is now the least recently used
+        cache
+            .get_or_compile(&language, &source(MAX_CACHED_QUERIES))
+            .unwrap();
+        assert_eq!(cache.len(), MAX_CACHED_QUERIES);
+        assert!(Rc::ptr_eq(
+            &first,
+            &cache.get_or_compile(&language, &source(0)).unwrap()
+        ));
+        assert!(!cache.queries.contains_key(&(language.clone(), source(1))));
     }
 }
@@ -0,0 +1,164 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use crate::analysis::ddsa_lib::common::DDSAJsRuntimeError;
+use crate::analysis::ddsa_lib::JsRuntime;
+use std::cell::RefCell;
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::thread;
+
+thread_local! {
+    /// The runtime of a thread of a [`JsRuntimePool`], created when the thread starts.
+    static POOL_RUNTIME: RefCell<Option<JsRuntime>> = const { RefCell::new(None) };
+}
+
+/// A pool of [`JsRuntime`]s, each owned by a dedicated worker thread.
+///
+/// Because a v8 isolate can't be moved between threads, jobs are sent to the runtimes instead.
+/// The workers are the threads of a rayon pool: an idle worker steals jobs from the queues of the
+/// other workers, so that a few slow rules don't leave the other isolates idle.
+pub struct JsRuntimePool {
+    threads: rayon::ThreadPool,
+}
+
+impl JsRuntimePool {
+    /// Creates a pool of `size` runtimes (at least one). Each runtime is created and warmed up
+    /// on its worker thread before this function returns.
+    pub fn try_new(size: usize) -> Result<Self, DDSAJsRuntimeError> {
+        let size = size.max(1);
+        let (init_tx, init_rx) = mpsc::channel();
+        let init_tx = Mutex::new(init_tx);
+        let threads = rayon::ThreadPoolBuilder::new()
+            .num_threads(size)
+            .thread_name(|idx| format!("ddsa-js-runtime-{idx}"))
+            .start_handler(move |_| {
+                let result = JsRuntime::try_new().map(|runtime| {
+                    POOL_RUNTIME.with(|pool_runtime| *pool_runtime.borrow_mut() = Some(runtime));
+                });
+                let _ = init_tx
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .send(result);
+            })
+            .build()
+            .expect("should be able to spawn the runtime threads");
+
+        for result in init_rx.iter().take(size) {
+            result?;
+        }
+        Ok(Self { threads })
+    }
+
+    /// The number of runtimes of the pool.
+    pub fn size(&self) -> usize {
+        self.threads.current_num_threads()
+    }
+
+    /// Executes the jobs on the runtimes of the pool, and returns their results in the order of the jobs.
+    /// This blocks until all the jobs have been executed.
+    ///
+    /// # Panics
+    /// Panics if a job panicked, once all the jobs have been executed. This must not be called from
+    /// a job, as the runtime of its thread is already in use.
+    pub(crate) fn execute_all<'a, T, F, I>(&self, jobs: I) -> Vec<T>
+    where
+        I: IntoIterator<Item = F>,
+        F: FnOnce(&mut JsRuntime) -> T + Send + 'a,
+        T: Send + 'a,
+    {
+        let jobs = jobs.into_iter().collect::<Vec<_>>();
+        let mut results = (0..jobs.len()).map(|_| None).collect::<Vec<_>>();
+        self.threads.scope(|scope| {
+            for (job, result) in jobs.into_iter().zip(results.iter_mut()) {
+                scope.spawn(move |_| *result = Some(with_pool_runtime(job)));
+            }
+        });
+        results
+            .into_iter()
+            .map(|result| result.expect("there should be one result per job"))
+            .collect()
+    }
+
+    /// Executes a job on a runtime of the pool, and returns its result.
+    ///
+    /// # Panics
+    /// See [`JsRuntimePool::execute_all`].
+    pub(crate) fn execute<'a, T, F>(&self, job: F) -> T
+    where
+        F: FnOnce(&mut JsRuntime) -> T + Send + 'a,
+        T: Send + 'a,
+    {
+        self.execute_all([job])
+            .pop()
+            .expect("there should be one result per job")
+    }
+
+    /// Initializes the global pool with `size` runtimes. This has no effect if the global pool was already
+    /// initialized, as it can only be initialized once.
+    pub fn init_global(size: usize) -> Result<&'static JsRuntimePool, DDSAJsRuntimeError> {
+        if let Some(pool) = GLOBAL_POOL.get() {
+            return Ok(pool);
+        }
+        let pool = Self::try_new(size)?;
+        Ok(GLOBAL_POOL.get_or_init(|| pool))
+    }
+
+    /// Returns the global pool, initializing it with one runtime per available CPU if it wasn't
+    /// initialized with [`JsRuntimePool::init_global`].
+    pub fn global() -> &'static JsRuntimePool {
+        GLOBAL_POOL.get_or_init(|| {
+            let size = thread::available_parallelism().map_or(1, |n| n.get());
+            Self::try_new(size).expect("runtime should have all data required to init")
+        })
+    }
+}
+
+static GLOBAL_POOL: OnceLock<JsRuntimePool> = OnceLock::new();
+
+/// Calls `job` with the runtime of the current thread of the pool.
+fn with_pool_runtime<T>(job: impl FnOnce(&mut JsRuntime) -> T) -> T {
+    POOL_RUNTIME.with(|pool_runtime| {
+        let mut pool_runtime = pool_runtime.borrow_mut();
+        let runtime = pool_runtime
+            .as_mut()
+            .expect("the threads of the pool should have a runtime");
+        job(runtime)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsRuntimePool;
+    use deno_core::v8;
+
+    /// Evaluates a JavaScript expression that returns an integer.
+    fn eval_integer(runtime: &mut super::JsRuntime, code: &str) -> i64 {
+        let scope = &mut runtime.inner_compat().handle_scope();
+        let code = v8::String::new(scope, code).unwrap();
+        let script = v8::Script::compile(scope, code, None).unwrap();
+        script.run(scope).unwrap().integer_value(scope).unwrap()
+    }
+
+    #[test]
+    fn execute_all_in_order() {
+        let pool = JsRuntimePool::try_new(4).unwrap();
+        assert_eq!(pool.size(), 4);
+        let inputs = (0..64).collect::<Vec<i64>>();
+        let results = pool.execute_all(inputs.iter().map(|input| {
+            move |runtime: &mut super::JsRuntime| eval_integer(runtime, &format!("{input} * 2"))
+        }));
+        assert_eq!(results, inputs.iter().map(|i| i * 2).collect::<Vec<_>>());
+    }
+
+    /// A job that panics doesn't take down its worker.
+    #[test]
+    fn panicking_job() {
+        let pool = JsRuntimePool::try_new(1).unwrap();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pool.execute(|_| -> i64 { panic!("job panicked") })
+        }));
+        assert!(result.is_err());
+        assert_eq!(pool.execute(|runtime| eval_integer(runtime, "1 + 1")), 2);
+    }
+}
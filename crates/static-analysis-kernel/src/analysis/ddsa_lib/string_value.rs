@@ -0,0 +1,352 @@
+// Unless explicitly stated otherwise all files in this repository are licensed under the Apache License, Version 2.0.
+// This product includes software developed at Datadog (https://www.datadoghq.com/).
+// Copyright 2024 Datadog, Inc.
+
+use crate::analysis::tree_sitter::get_tree_sitter_language;
+use crate::model::common::Language;
+
+/// The languages whose string literals can be decoded.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Syntax {
+    Python,
+    JavaScript,
+    Go,
+}
+
+impl Syntax {
+    fn from_ts_language(ts_language: &tree_sitter::Language) -> Option<Self> {
+        [
+            (Language::Python, Syntax::Python),
+            (Language::JavaScript, Syntax::JavaScript),
+            (Language::TypeScript, Syntax::JavaScript),
+            (Language::Go, Syntax::Go),
+        ]
+        .into_iter()
+        .find(|(language, _)| *ts_language == get_tree_sitter_language(language))
+        .map(|(_, syntax)| syntax)
+    }
+}
+
+/// Returns the value that a string literal evaluates to at runtime, decoding its escape sequences.
+///
+/// The following are also supported:
+/// * The implicit concatenation of adjacent literals (Python), and the concatenation of literals with `+`.
+/// * Interpolated strings (Python f-strings and JavaScript template strings): only the static parts are
+///   kept, and the interpolated expressions are omitted.
+///
+/// Returns `None` if the node isn't a string literal (or a concatenation of string literals), or if
+/// the language of the tree isn't supported (only Python, JavaScript, TypeScript and Go are).
+pub fn string_value(
+    ts_language: &tree_sitter::Language,
+    node: tree_sitter::Node,
+    code: &str,
+) -> Option<String> {
+    let syntax = Syntax::from_ts_language(ts_language)?;
+    literal_value(syntax, node, code)
+}
+
+fn literal_value(syntax: Syntax, node: tree_sitter::Node, code: &str) -> Option<String> {
+    let text = code.get(node.byte_range())?;
+    match (syntax, node.kind()) {
+        (Syntax::Python, "string") => python_string(node, code),
+        (Syntax::Python, "concatenated_string") => {
+            let mut cursor = node.walk();
+            let strings = node.named_children(&mut cursor).collect::<Vec<_>>();
+            strings
+                .into_iter()
+                .map(|string| python_string(string, code))
+                .collect()
+        }
+        (Syntax::JavaScript, "string") => {
+            let content = strip_quotes(text, &["\"", "'"])?;
+            Some(decode_escapes(syntax, content))
+        }
+        (Syntax::JavaScript, "template_string") => {
+            static_parts(node, code, "template_substitution", 1, 1, |part| {
+                Some(decode_escapes(syntax, part))
+            })
+        }
+        (Syntax::Go, "interpreted_string_literal") => {
+            let content = strip_quotes(text, &["\""])?;
+            Some(decode_escapes(syntax, content))
+        }
+        // Carriage returns are discarded from raw strings.
+        (Syntax::Go, "raw_string_literal") => Some(strip_quotes(text, &["`"])?.replace('\r', "")),
+        (_, "binary_expression" | "binary_operator") => {
+            let operator = node.child_by_field_name("operator")?;
+            if operator.kind() != "+" {
+                return None;
+            }
+            let left = literal_value(syntax, node.child_by_field_name("left")?, code)?;
+            let right = literal_value(syntax, node.child_by_field_name("right")?, code)?;
+            Some(left + &right)
+        }
+        (_, "parenthesized_expression") if node.named_child_count() == 1 => {
+            literal_value(syntax, node.named_child(0)?, code)
+        }
+        _ => None,
+    }
+}
+
+/// Decodes a single Python string (e.g. `"abc"`, `r'\d'`, `b"\x00"` or `f"{name}: {{value}}"`).
+fn python_string(node: tree_sitter::Node, code: &str) -> Option<String> {
+    if node.kind() != "string" {
+        return None;
+    }
+    let text = code.get(node.byte_range())?;
+    let prefix_len = text.find(['"', '\''])?;
+    let prefix = text[..prefix_len].to_ascii_lowercase();
+    let quotes = &text[prefix_len..];
+    let quote_len = if quotes.starts_with("\"\"\"") || quotes.starts_with("'''") {
+        3
+    } else {
+        1
+    };
+    let is_raw = prefix.contains('r');
+    let is_fstring = prefix.contains('f');
+    let decode = |part: &str| {
+        let part = if is_raw {
+            part.to_string()
+        } else {
+            decode_escapes(Syntax::Python, part)
+        };
+        if is_fstring {
+            Some(part.replace("{{", "{").replace("}}", "}"))
+        } else {
+            Some(part)
+        }
+    };
+    static_parts(
+        node,
+        code,
+        "interpolation",
+        prefix_len + quote_len,
+        quote_len,
+        decode,
+    )
+}
+
+/// Decodes the static parts of a string literal (i.e. the content between the `open_len`-byte opening
+/// delimiter and the `close_len`-byte closing delimiter, excluding the children of kind `interpolation_kind`).
+fn static_parts(
+    node: tree_sitter::Node,
+    code: &str,
+    interpolation_kind: &str,
+    open_len: usize,
+    close_len: usize,
+    decode: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let content_start = node.start_byte() + open_len;
+    let content_end = node.end_byte().checked_sub(close_len)?;
+    if content_start > content_end {
+        return None;
+    }
+    let mut value = String::new();
+    let mut part_start = content_start;
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() == interpolation_kind {
+            value.push_str(&decode(code.get(part_start..child.start_byte())?)?);
+            part_start = child.end_byte();
+        }
+    }
+    value.push_str(&decode(code.get(part_start..content_end)?)?);
+    Some(value)
+}
+
+/// Returns the text between the (identical) opening and closing quotes.
+fn strip_quotes<'a>(text: &'a str, quotes: &[&str]) -> Option<&'a str> {
+    quotes.iter().find_map(|quote| {
+        text.strip_prefix(quote)
+            .and_then(|text| text.strip_suffix(quote))
+    })
+}
+
+/// Decodes the escape sequences of the content of a string literal. Invalid escape sequences are kept as-is.
+fn decode_escapes(syntax: Syntax, content: &str) -> String {
+    let mut decoded = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            decoded.push(ch);
+            continue;
+        }
+        let Some(escaped) = chars.next() else {
+            decoded.push('\\');
+            break;
+        };
+        let unicode_digits = match escaped {
+            'x' => Some(2),
+            'u' if syntax == Syntax::JavaScript && chars.peek() == Some(&'{') => None,
+            'u' => Some(4),
+            'U' if syntax != Syntax::JavaScript => Some(8),
+            _ => None,
+        };
+        if let Some(len) = unicode_digits {
+            let digits = chars.clone().take(len).collect::<String>();
+            match u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+            {
+                Some(value) if digits.len() == len => {
+                    decoded.push(value);
+                    chars.nth(len - 1);
+                }
+                _ => {
+                    decoded.push('\\');
+                    decoded.push(escaped);
+                }
+            }
+            continue;
+        }
+        match escaped {
+            'n' => decoded.push('\n'),
+            't' => decoded.push('\t'),
+            'r' => decoded.push('\r'),
+            'b' => decoded.push('\u{8}'),
+            'f' => decoded.push('\u{c}'),
+            'v' => decoded.push('\u{b}'),
+            'a' if syntax != Syntax::JavaScript => decoded.push('\u{7}'),
+            '\\' | '\'' | '"' => decoded.push(escaped),
+            // Line continuations
+            '\n' if syntax != Syntax::Go => {}
+            '\r' if syntax != Syntax::Go => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+            }
+            // `\u{1F600}`
+            'u' => {
+                let digits = chars
+                    .clone()
+                    .skip(1)
+                    .take_while(|ch| *ch != '}')
+                    .collect::<String>();
+                match u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    Some(value) => {
+                        decoded.push(value);
+                        // Skip the braces and the digits.
+                        chars.nth(digits.len() + 1);
+                    }
+                    None => decoded.push_str("\\u"),
+                }
+            }
+            // JavaScript only supports `\0` (not followed by a digit), as octal escapes are legacy.
+            '0' if syntax == Syntax::JavaScript => decoded.push('\0'),
+            '0'..='7' if syntax != Syntax::JavaScript => {
+                let mut digits = escaped.to_string();
+                while digits.len() < 3 && chars.peek().is_some_and(|ch| ('0'..='7').contains(ch)) {
+                    digits.push(chars.next().unwrap_or_default());
+                }
+                match u32::from_str_radix(&digits, 8)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    Some(value) => decoded.push(value),
+                    None => {
+                        decoded.push('\\');
+                        decoded.push_str(&digits);
+                    }
+                }
+            }
+            // Python keeps unknown escape sequences, while JavaScript drops the backslash.
+            _ if syntax == Syntax::JavaScript => decoded.push(escaped),
+            _ => {
+                decoded.push('\\');
+                decoded.push(escaped);
+            }
+        }
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tree_sitter::get_tree;
+
+    /// Returns the string value of the expression after the first ` = ` in `code`.
+    fn value_of(code: &str, language: Language) -> Option<String> {
+        let tree = get_tree(code, &language).unwrap();
+        let start = code.find(" = ").unwrap() + 3;
+        let end = code.trim_end().trim_end_matches(';').len();
+        let node = tree
+            .root_node()
+            .named_descendant_for_byte_range(start, end)
+            .unwrap();
+        string_value(&tree.language(), node, code)
+    }
+
+    #[test]
+    fn python_strings() {
+        for (code, expected) in [
+            (
+                r#"x = "a\tb\x41\101é\N{DASH}\q""#,
+                Some("a\tbAAé\\N{DASH}\\q"),
+            ),
+            (r#"x = r"\d+\n""#, Some("\\d+\\n")),
+            (r#"x = b'\x00\''"#, Some("\0'")),
+            ("x = '''line\\\ncontinued'''", Some("linecontinued")),
+            (r#"x = "abc" 'def' "\n""#, Some("abcdef\n")),
+            (
+                r#"x = f"{name}: {{value}} \t{other!r}""#,
+                Some(": {value} \t"),
+            ),
+            (r#"x = rf"\d{n}""#, Some("\\d")),
+            (r#"x = "abc" + ("def" + 'g')"#, Some("abcdefg")),
+            (r#"x = "abc" + name"#, None),
+            ("x = 123", None),
+        ] {
+            assert_eq!(
+                value_of(code, Language::Python).as_deref(),
+                expected,
+                "{}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn javascript_strings() {
+        for (code, expected) in [
+            (
+                r#"const x = "a\tb\x41é\u{1F600}\q\0";"#,
+                Some("a\tbAé😀q\0"),
+            ),
+            (r#"const x = 'it\'s';"#, Some("it's")),
+            (
+                "const x = `template ${name} \\n end`;",
+                Some("template  \n end"),
+            ),
+            (r#"const x = "abc" + 'def';"#, Some("abcdef")),
+            (r#"const x = "abc" - 'def';"#, None),
+            ("const x = name;", None),
+        ] {
+            for language in [Language::JavaScript, Language::TypeScript] {
+                assert_eq!(value_of(code, language).as_deref(), expected, "{}", code);
+            }
+        }
+    }
+
+    #[test]
+    fn go_strings() {
+        let code = "package main\nvar x = \"a\\tb\\x41\\101\\u00e9\"";
+        assert_eq!(value_of(code, Language::Go).as_deref(), Some("a\tbAAé"));
+        let code = "package main\nvar x = `raw\\n\r\nstring`";
+        assert_eq!(
+            value_of(code, Language::Go).as_deref(),
+            Some("raw\\n\nstring")
+        );
+    }
+
+    #[test]
+    fn unsupported_language() {
+        let code = "x = \"abc\"";
+        let tree = get_tree(code, &Language::Ruby).unwrap();
+        let string = tree.root_node().descendant_for_byte_range(4, 9).unwrap();
+        assert!(string_value(&tree.language(), string, code).is_none());
+    }
+}
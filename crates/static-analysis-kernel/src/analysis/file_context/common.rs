@@ -1,4 +1,5 @@
 use crate::analysis::file_context::go::{get_file_context_go, FileContextGo};
+use crate::analysis::file_context::hcl::{get_file_context_hcl, FileContextHcl};
 use crate::model::common::Language;
 use serde::Serialize;
 use tree_sitter::Tree;
@@ -9,6 +10,7 @@ use tree_sitter::Tree;
 #[serde(untagged)]
 pub enum FileContext {
     Go(FileContextGo),
+    Hcl(FileContextHcl),
     None {},
 }
 
@@ -16,9 +18,15 @@ pub fn get_empty_file_context() -> FileContext {
     FileContext::None {}
 }
 
-pub fn get_file_context(tree: &Tree, language: &Language, code: &String) -> FileContext {
-    if *language == Language::Go {
-        return FileContext::Go(get_file_context_go(tree, code));
+pub fn get_file_context(
+    tree: &Tree,
+    language: &Language,
+    code: &str,
+    filename: &str,
+) -> FileContext {
+    match language {
+        Language::Go => FileContext::Go(get_file_context_go(tree, code)),
+        Language::Terraform => FileContext::Hcl(get_file_context_hcl(filename)),
+        _ => get_empty_file_context(),
     }
-    get_empty_file_context()
 }
@@ -11,7 +11,7 @@ pub struct FileContextGo {
 }
 
 /// Get the context for Go. It takes the tree sitter tree, and get all the necessary artifacts to build the context.
-pub fn get_file_context_go(tree: &Tree, code: &String) -> FileContextGo {
+pub fn get_file_context_go(tree: &Tree, code: &str) -> FileContextGo {
     let mut packages_list: HashSet<String> = HashSet::new();
     let mut packages_aliased: HashMap<String, String> = HashMap::new();
 
@@ -95,10 +95,11 @@ import (
 
         let tree = get_tree(code, &Language::Go).unwrap();
 
-        let file_context_go = match get_file_context(&tree, &Language::Go, &code.to_string()) {
-            FileContext::Go(go) => go,
-            _ => panic!("unreachable"),
-        };
+        let file_context_go =
+            match get_file_context(&tree, &Language::Go, &code.to_string(), "main.go") {
+                FileContext::Go(go) => go,
+                _ => panic!("unreachable"),
+            };
 
         assert_eq!((&(file_context_go).packages).len(), 3);
         assert!(
@@ -0,0 +1,62 @@
+use serde::Serialize;
+
+/// The kind of HCL file being analyzed. HCL is used by several tools that do not share
+/// the same semantics, so rules can use this to only apply to some of them.
+#[derive(Debug, Clone, Copy, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HclFileKind {
+    /// A Terraform configuration (`.tf`)
+    Terraform,
+    /// A Terraform variable definitions file (`.tfvars`)
+    Tfvars,
+    /// A Packer template (`.pkr.hcl`)
+    Packer,
+    /// A Nomad job specification (`.nomad`, `.nomad.hcl`)
+    Nomad,
+    /// Any other HCL file
+    Hcl,
+}
+
+/// Structure for the file context that is specific to HCL files.
+#[derive(Serialize, Debug, Clone)]
+pub struct FileContextHcl {
+    pub file_kind: HclFileKind,
+}
+
+/// Get the kind of HCL file based on its filename.
+pub fn get_hcl_file_kind(filename: &str) -> HclFileKind {
+    let filename = filename.to_lowercase();
+    if filename.ends_with(".tf") {
+        HclFileKind::Terraform
+    } else if filename.ends_with(".tfvars") {
+        HclFileKind::Tfvars
+    } else if filename.ends_with(".pkr.hcl") {
+        HclFileKind::Packer
+    } else if filename.ends_with(".nomad") || filename.ends_with(".nomad.hcl") {
+        HclFileKind::Nomad
+    } else {
+        HclFileKind::Hcl
+    }
+}
+
+/// Get the context for HCL files.
+pub fn get_file_context_hcl(filename: &str) -> FileContextHcl {
+    FileContextHcl {
+        file_kind: get_hcl_file_kind(filename),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_hcl_file_kind() {
+        assert_eq!(get_hcl_file_kind("main.tf"), HclFileKind::Terraform);
+        assert_eq!(get_hcl_file_kind("env/prod.tfvars"), HclFileKind::Tfvars);
+        assert_eq!(get_hcl_file_kind("images/ami.pkr.hcl"), HclFileKind::Packer);
+        assert_eq!(get_hcl_file_kind("jobs/web.nomad"), HclFileKind::Nomad);
+        assert_eq!(get_hcl_file_kind("jobs/web.nomad.hcl"), HclFileKind::Nomad);
+        assert_eq!(get_hcl_file_kind("config.hcl"), HclFileKind::Hcl);
+    }
+}
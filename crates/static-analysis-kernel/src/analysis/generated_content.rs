@@ -6,12 +6,16 @@ pub const THRIFT_HEADER: &str = "Autogenerated by Thrift Compiler";
 /// Max number of characters we use at the file header to detect if this is a generated file.
 pub const MAX_HEADER_SIZE: usize = 400;
 
+/// The average length of the lines above which a JavaScript or TypeScript file is minified.
+pub const MINIFIED_LINE_LENGTH: usize = 110;
+
 /// Returns if a file is generated or not based on a few heuristics.
 /// Some heuristics are based on these sources
 ///  - https://github.com/github-linguist/linguist/blob/master/lib/linguist/generated.rb
 ///
 /// We only look at the first few bytes of the code that are generally comments generated by
-/// code generation tools. We look at most at [MAX_HEADER_SIZE] characters.
+/// code generation tools. We look at most at [MAX_HEADER_SIZE] characters. The minified
+/// JavaScript and TypeScript files are also generated (see [is_minified]).
 pub fn is_generated_file(full_content: &str, language: &Language) -> bool {
     let size_to_analyze = MAX_HEADER_SIZE.min(full_content.len());
 
@@ -31,6 +35,7 @@ pub fn is_generated_file(full_content: &str, language: &Language) -> bool {
             content.contains("Generated by PEG.js")
                 | content.contains("GENERATED CODE -- DO NOT EDIT!")
                 | content.contains(THRIFT_HEADER)
+                || is_minified(full_content)
         }
         Language::Python => {
             content.contains("Generated protocol buffer code")
@@ -44,14 +49,27 @@ pub fn is_generated_file(full_content: &str, language: &Language) -> bool {
             content.contains("Generated by PEG.js")
                 | content.contains("GENERATED CODE -- DO NOT EDIT!")
                 | content.contains(THRIFT_HEADER)
+                || is_minified(full_content)
         }
+        // the definitions are the source the code is generated from, so they are never generated
+        // themselves, even if they mention the protocol buffer compiler.
+        Language::Proto => false,
         _ => false,
     }
 }
 
+/// Returns if the code is minified: its lines are longer than [MINIFIED_LINE_LENGTH] bytes on
+/// average, like in linguist.
+pub fn is_minified(content: &str) -> bool {
+    let lines = content.lines().count();
+    lines > 0 && content.len() / lines > MINIFIED_LINE_LENGTH
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::analysis::generated_content::{is_generated_file, PROTOBUF_HEADER, THRIFT_HEADER};
+    use crate::analysis::generated_content::{
+        is_generated_file, MINIFIED_LINE_LENGTH, PROTOBUF_HEADER, THRIFT_HEADER,
+    };
     use crate::model::common::Language;
 
     #[test]
@@ -140,6 +158,22 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_is_generated_file_minified() {
+        let line = "var a=1;".repeat(MINIFIED_LINE_LENGTH / 8 + 1);
+        assert!(is_generated_file(&line, &Language::JavaScript));
+        assert!(is_generated_file(
+            &format!("/*! lib v1 */\n{line}{line}"),
+            &Language::TypeScript
+        ));
+        assert!(!is_generated_file(
+            &format!("// a long comment\n{line}\nfoo();\nbar();\n"),
+            &Language::JavaScript
+        ));
+        // only the JavaScript and TypeScript files are minified
+        assert!(!is_generated_file(&line, &Language::Python));
+    }
+
     #[test]
     fn test_is_generated_file_typescript() {
         assert!(!is_generated_file(
@@ -159,4 +193,12 @@ mod tests {
             &Language::TypeScript
         ));
     }
+
+    #[test]
+    fn test_is_generated_file_proto() {
+        assert!(!is_generated_file(
+            &format!("// {}\nsyntax = \"proto3\";", PROTOBUF_HEADER),
+            &Language::Proto
+        ));
+    }
 }
@@ -1,29 +1,72 @@
 use crate::model::analysis::{
-    AnalysisOptions, MatchNode, ERROR_RULE_EXECUTION, ERROR_RULE_TIMEOUT,
+    AnalysisOptions, MatchNode, ERROR_RULE_EXECUTION, ERROR_RULE_SANDBOX, ERROR_RULE_TIMEOUT,
 };
-use crate::model::rule::{RuleInternal, RuleResult};
+use crate::model::rule::{ConsoleRecord, RuleInternal, RuleProfile, RuleResult};
 use crate::model::violation::Violation;
+use cpu_time::ThreadTime;
 use deno_core::serde_v8;
 use deno_core::v8;
 use deno_core::v8::NewStringType::Internalized;
-use std::cell::RefCell;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use crate::analysis::cancellation::CancellationToken;
 use crate::analysis::ddsa_lib::common::iter_v8_array;
-use crate::analysis::ddsa_lib::JsRuntime;
+use crate::analysis::ddsa_lib::{JsRuntime, JsRuntimePool};
 use crate::analysis::file_context::common::FileContext;
 use serde::{Deserialize, Serialize};
 
 /// The duration an individual execution of `v8` may run before it will be forcefully halted.
 const JAVASCRIPT_EXECUTION_TIMEOUT: Duration = Duration::from_millis(5000);
 
-thread_local! {
-    static JS_RUNTIME: RefCell<JsRuntime> = {
-        let runtime = JsRuntime::try_new().expect("runtime should have all data required to init");
-        RefCell::new(runtime)
+/// The number of rules executed on a file by a single job of the [`JsRuntimePool`].
+const RULE_BATCH_SIZE: usize = 8;
+
+/// The name of the JavaScript error thrown when a rule accesses an API with side effects
+/// without being allowed to (see `sandbox.js`).
+const SANDBOX_VIOLATION: &str = "SandboxViolation";
+
+/// Returns the seed of `Math.random` for the execution of a rule on a file: the same rule gets
+/// the same random numbers on the same file, so that its output is reproducible. Returns `None`
+/// when the rules are not executed deterministically.
+fn rule_seed(
+    rule: &RuleInternal,
+    filename: &str,
+    analysis_options: &AnalysisOptions,
+) -> Option<u32> {
+    if analysis_options.nondeterministic_rules {
+        return None;
+    }
+    // FNV-1a, which is stable across the versions of the analyzer (unlike the `DefaultHasher`)
+    let seed = rule
+        .name
+        .bytes()
+        .chain([0])
+        .chain(filename.bytes())
+        .fold(0x811c9dc5u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x01000193)
+        });
+    Some(seed)
+}
+
+/// Returns the arguments of `_cleanExecute` (see `stella_compat.js`) to execute the closure of a
+/// rule in the sandbox, with the permissions of the rule and its seed.
+fn sandbox_args<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    closure: v8::Local<'s, v8::Value>,
+    allow_side_effects: bool,
+    seed: Option<u32>,
+) -> [v8::Local<'s, v8::Value>; 3] {
+    let seed = match seed {
+        Some(seed) => v8::Integer::new_from_unsigned(scope, seed).into(),
+        None => v8::null(scope).into(),
     };
+    [
+        closure,
+        v8::Boolean::new(scope, allow_side_effects).into(),
+        seed,
+    ]
 }
 
 use crate::analysis::ddsa_lib::js::ViolationConverter;
@@ -42,20 +85,33 @@ pub enum ExecutionError {
     Execution { reason: String },
     #[error("execution timed out at {:.2}s", .0.as_secs_f32())]
     ExecutionTimeout(Duration),
+    #[error("sandbox violation: {reason}")]
+    SandboxViolation { reason: String },
     #[error("unable to interpret JavaScript: `{reason}`")]
     Interpreter { reason: String },
     #[error("expected value returned from JavaScript execution: `{reason}`")]
     UnexpectedReturnValue { reason: String },
 }
 
+impl ExecutionError {
+    /// Creates the error for an exception thrown by a rule.
+    fn from_exception(reason: String) -> Self {
+        if reason.starts_with(&format!("{SANDBOX_VIOLATION}:")) {
+            Self::SandboxViolation { reason }
+        } else {
+            Self::Execution { reason }
+        }
+    }
+}
+
 // This structure is what is returned by the JavaScript code
 #[derive(Deserialize, Debug, Serialize, Clone)]
 struct StellaExecution {
     violations: Vec<Violation>, // the list of violations returned by the rule
 }
 
-// execute a rule. It is the exposed function to execute a rule and start the underlying
-// JS runtime.
+// execute a rule. It is the exposed function to execute a rule on a runtime of the
+// global `JsRuntimePool`.
 pub fn execute_rule(
     rule: &RuleInternal,
     match_nodes: Vec<MatchNode>,
@@ -63,21 +119,206 @@ pub fn execute_rule(
     analysis_options: AnalysisOptions,
     file_context: &FileContext,
 ) -> RuleResult {
-    let execution_start = Instant::now();
+    JsRuntimePool::global().execute(|runtime| {
+        execute_rule_with_runtime(
+            runtime,
+            rule,
+            &match_nodes,
+            &filename,
+            &analysis_options,
+            file_context,
+        )
+    })
+}
 
-    let (res, console_output) = JS_RUNTIME.with_borrow_mut(|runtime| {
-        let res = execute_rule_internal(runtime, rule, &match_nodes, &filename, file_context);
-        let console_output = runtime.console_compat().drain().collect::<Vec<_>>();
-        (res, console_output)
+/// Executes rules on a file, with the nodes matched by each rule. The rules are split in batches
+/// that are executed in parallel by the runtimes of the global [`JsRuntimePool`].
+///
+/// The results are returned in the order of the rules. The batches that were not started when the
+/// token is cancelled are reported with [`RuleResult::cancelled`].
+pub fn execute_rules(
+    rules: Vec<(&RuleInternal, Vec<MatchNode>)>,
+    filename: &str,
+    analysis_options: &AnalysisOptions,
+    file_context: &FileContext,
+    cancellation: &CancellationToken,
+) -> Vec<RuleResult> {
+    let jobs = rules.chunks(RULE_BATCH_SIZE).map(|batch| {
+        move |runtime: &mut JsRuntime| {
+            if cancellation.is_cancelled() {
+                return batch
+                    .iter()
+                    .map(|(rule, _)| RuleResult::cancelled(&rule.name, filename))
+                    .collect();
+            }
+            execute_rule_batch_with_runtime(
+                runtime,
+                batch,
+                filename,
+                analysis_options,
+                file_context,
+            )
+        }
     });
+    JsRuntimePool::global()
+        .execute_all(jobs)
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Executes a batch of rules with the given runtime, in a single script execution: entering `v8`
+/// and setting up the globals of the file is done once for the batch instead of once per rule.
+///
+/// The console output and the resources used by the rules can't be told apart in a batch, so the
+/// rules are executed individually when the output is logged or the rules are profiled.
+fn execute_rule_batch_with_runtime(
+    runtime: &mut JsRuntime,
+    batch: &[(&RuleInternal, Vec<MatchNode>)],
+    filename: &str,
+    analysis_options: &AnalysisOptions,
+    file_context: &FileContext,
+) -> Vec<RuleResult> {
+    let execute_individually = |runtime: &mut JsRuntime| {
+        batch
+            .iter()
+            .map(|(rule, match_nodes)| {
+                execute_rule_with_runtime(
+                    runtime,
+                    rule,
+                    match_nodes,
+                    filename,
+                    analysis_options,
+                    file_context,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    if batch.len() < 2 || analysis_options.log_output || analysis_options.profile_rules {
+        return execute_individually(runtime);
+    }
+
+    let outcomes =
+        execute_rule_batch_internal(runtime, batch, filename, analysis_options, file_context);
+    // The console output is only kept when it's logged.
+    runtime.console_compat().drain();
+    match outcomes {
+        Ok(outcomes) => batch
+            .iter()
+            .zip(outcomes)
+            .map(|((rule, _), (res, elapsed))| {
+                into_rule_result(
+                    rule,
+                    filename,
+                    analysis_options,
+                    res,
+                    vec![],
+                    elapsed.as_millis(),
+                )
+            })
+            .collect(),
+        // A rule of the batch can't be compiled. The rules are executed individually so that
+        // the error is only reported for that rule.
+        Err(_) => execute_individually(runtime),
+    }
+}
+
+/// Executes a rule with the given runtime.
+fn execute_rule_with_runtime(
+    runtime: &mut JsRuntime,
+    rule: &RuleInternal,
+    match_nodes: &[MatchNode],
+    filename: &str,
+    analysis_options: &AnalysisOptions,
+    file_context: &FileContext,
+) -> RuleResult {
+    let profiler = analysis_options
+        .profile_rules
+        .then(|| RuleProfiler::start(runtime, match_nodes));
+    let execution_start = Instant::now();
+
+    let res = execute_rule_internal(
+        runtime,
+        rule,
+        match_nodes,
+        filename,
+        analysis_options,
+        file_context,
+    );
+    let console_output = runtime.console_compat().drain();
     let execution_time_ms = execution_start.elapsed().as_millis();
+    let mut rule_result = into_rule_result(
+        rule,
+        filename,
+        analysis_options,
+        res,
+        console_output,
+        execution_time_ms,
+    );
+    rule_result.profile = profiler.map(|profiler| profiler.finish(runtime));
+    rule_result
+}
+
+/// Measures the resources used by an execution of a rule.
+struct RuleProfiler {
+    used_heap_size: usize,
+    cpu_start: ThreadTime,
+    wall_start: Instant,
+    captures: usize,
+}
+
+impl RuleProfiler {
+    fn start(runtime: &mut JsRuntime, match_nodes: &[MatchNode]) -> Self {
+        let captures = match_nodes
+            .iter()
+            .map(|node| {
+                node.captures.len() + node.captures_list.values().map(Vec::len).sum::<usize>()
+            })
+            .sum();
+        Self {
+            used_heap_size: used_heap_size(runtime),
+            cpu_start: ThreadTime::now(),
+            wall_start: Instant::now(),
+            captures,
+        }
+    }
 
+    fn finish(self, runtime: &mut JsRuntime) -> RuleProfile {
+        RuleProfile {
+            heap_allocated_bytes: used_heap_size(runtime).saturating_sub(self.used_heap_size)
+                as u64,
+            cpu_time_ms: self.cpu_start.elapsed().as_millis(),
+            wall_time_ms: self.wall_start.elapsed().as_millis(),
+            captures: self.captures,
+        }
+    }
+}
+
+/// Returns the size of the used `v8` heap of the runtime, in bytes.
+fn used_heap_size(runtime: &mut JsRuntime) -> usize {
+    let mut statistics = v8::HeapStatistics::default();
+    runtime
+        .inner_compat()
+        .v8_isolate()
+        .get_heap_statistics(&mut statistics);
+    statistics.used_heap_size()
+}
+
+/// Converts the result of the execution of a rule to a [`RuleResult`].
+fn into_rule_result(
+    rule: &RuleInternal,
+    filename: &str,
+    analysis_options: &AnalysisOptions,
+    res: Result<Vec<Violation>, ExecutionError>,
+    console_output: Vec<ConsoleRecord>,
+    execution_time_ms: u128,
+) -> RuleResult {
     // NOTE: This is a translation layer to map Result<T, E> to a `RuleResult` struct.
     // Eventually, `execute_rule` should be refactored to also use a `Result`, and then this will no longer be required.
     let (violations, errors, execution_error, output) = match res {
         Ok(violations) => {
             let output = (!console_output.is_empty() && analysis_options.log_output)
-                .then_some(console_output.join("\n"));
+                .then_some(console_output);
             (violations, vec![], None, output)
         }
         Err(err) => {
@@ -95,6 +336,12 @@ pub fn execute_rule(
                     }
                     (ERROR_RULE_EXECUTION, Some(reason))
                 }
+                ExecutionError::SandboxViolation { reason } => {
+                    if analysis_options.use_debug {
+                        eprintln!("rule:file {} sandbox violation, message: {}", r_f, reason);
+                    }
+                    (ERROR_RULE_SANDBOX, Some(reason))
+                }
                 ExecutionError::UnexpectedReturnValue { reason } => {
                     (ERROR_RULE_EXECUTION, Some(reason))
                 }
@@ -104,8 +351,8 @@ pub fn execute_rule(
         }
     };
     RuleResult {
-        rule_name: rule.name.clone(),
-        filename,
+        rule_name: rule.name.as_str().into(),
+        filename: filename.into(),
         violations,
         errors,
         execution_error,
@@ -113,6 +360,8 @@ pub fn execute_rule(
         execution_time_ms,
         parsing_time_ms: 0,    // filled later in the execute step
         query_node_time_ms: 0, // filled later in the execute step
+        degraded: false,       // filled later in the execute step
+        profile: None,
     }
 }
 
@@ -127,13 +376,15 @@ fn execute_rule_internal(
     rule: &RuleInternal,
     match_nodes: &[MatchNode],
     filename: &str,
+    analysis_options: &AnalysisOptions,
     file_context: &FileContext,
 ) -> Result<Vec<Violation>, ExecutionError> {
     // NOTE: We merge the existing node context with the file context and resolve key collisions
     // by using the file context's value.
+    // The script only evaluates to the closure of the rule, which is then executed in the sandbox.
     let js_code = format!(
         r#"
-_cleanExecute(() => {{
+(() => {{
 // Note: variables prefixed with "GLOBAL_" are defined by the static analysis kernel directly via the v8 API.
 
 // The rule's JavaScript code
@@ -145,16 +396,18 @@ for (const n of GLOBAL_nodes) {{
     if (Object.keys(GLOBAL_fileContext).length > 0) {{
         n.context = {{...n.context, ...GLOBAL_fileContext}};
     }}
-    visit(n, GLOBAL_filename, n.context.code);
+    visit(n, GLOBAL_filename, n.context.code, n.queryName);
 }}
 
 return stellaAllErrors;
 }});
 "#,
-        rule.code
+        rule.code,
     );
+    let seed = rule_seed(rule, filename, analysis_options);
 
     let iso_handle = runtime.inner_compat().v8_isolate().thread_safe_handle();
+    let clean_execute = runtime.clean_execute_compat();
 
     let handle_scope = &mut runtime.inner_compat().handle_scope();
     let ctx = handle_scope.get_current_context();
@@ -201,6 +454,241 @@ return stellaAllErrors;
         ExecutionError::Interpreter { reason }
     })?;
 
+    let execution_start = Instant::now();
+    let clean_execute = v8::Local::new(tc_scope, &clean_execute);
+    let (execution_result, timed_out) = run_with_timeout(&iso_handle, || {
+        compiled_script.run(tc_scope).and_then(|closure| {
+            let receiver = v8::undefined(tc_scope).into();
+            let args = sandbox_args(tc_scope, closure, rule.allow_side_effects, seed);
+            clean_execute.call(tc_scope, receiver, &args)
+        })
+    });
+    if timed_out {
+        return Err(ExecutionError::ExecutionTimeout(execution_start.elapsed()));
+    }
+
+    let execution_result = execution_result.ok_or_else(|| {
+        let exception = tc_scope
+            .exception()
+            .expect("return value should only be `None` if an error was caught");
+        let reason = exception.to_rust_string_lossy(tc_scope);
+        tc_scope.reset();
+        ExecutionError::from_exception(reason)
+    })?;
+    let violations = into_violations(tc_scope, execution_result, rule)?;
+
+    // Drop the objects we created. Because we are re-using the context, it won't happen automatically.
+    global.delete(tc_scope, key_nodes.into());
+    global.delete(tc_scope, key_file_context.into());
+    global.delete(tc_scope, key_filename.into());
+
+    Ok(violations)
+}
+
+/// Converts the errors reported by a rule (the `stellaAllErrors` array) to violations.
+fn into_violations<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    value: v8::Local<'s, v8::Value>,
+    rule: &RuleInternal,
+) -> Result<Vec<Violation>, ExecutionError> {
+    use crate::analysis::ddsa_lib::v8_ds::V8Converter;
+
+    let unexpected = |reason: String| ExecutionError::UnexpectedReturnValue { reason };
+    let v8_array: v8::Local<v8::Array> = value
+        .try_into()
+        .map_err(|err: v8::DataError| unexpected(err.to_string()))?;
+    iter_v8_array(v8_array, scope)
+        .map(|value| {
+            violation_converter()
+                .try_convert_from(scope, value)
+                .map(|v| v.into_violation(rule.severity, rule.category))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| unexpected(err.to_string()))
+}
+
+/// The result of the execution of a rule of a batch, and its execution time.
+type BatchOutcome = (Result<Vec<Violation>, ExecutionError>, Duration);
+
+/// Executes a batch of rules with a single script compilation, and returns the outcome of each rule.
+/// Each rule is executed in its own closure, so that its declarations are isolated from the other rules.
+///
+/// The closures are called one after the other, each with its own [`JAVASCRIPT_EXECUTION_TIMEOUT`]:
+/// a slow rule only times out itself, and the rules after it are still executed. An error is only
+/// returned if the script can't be compiled.
+fn execute_rule_batch_internal(
+    runtime: &mut JsRuntime,
+    batch: &[(&RuleInternal, Vec<MatchNode>)],
+    filename: &str,
+    analysis_options: &AnalysisOptions,
+    file_context: &FileContext,
+) -> Result<Vec<BatchOutcome>, ExecutionError> {
+    // The rules with the same queries and arguments have the same matches: their nodes are only
+    // serialized once, and the rules are given the same objects.
+    let digests = batch
+        .iter()
+        .map(|(rule, _)| rule.queries_digest())
+        .collect::<Vec<_>>();
+    // the index in the batch of the first rule of each set of nodes
+    let mut node_sets: Vec<usize> = vec![];
+    let node_set_indices = (0..batch.len())
+        .map(|idx| {
+            let shared = node_sets.iter().position(|&first| {
+                digests[idx].is_some()
+                    && digests[first] == digests[idx]
+                    && same_arguments(&batch[first].1, &batch[idx].1)
+            });
+            shared.unwrap_or_else(|| {
+                node_sets.push(idx);
+                node_sets.len() - 1
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // NOTE: We merge the existing node context with the file context and resolve key collisions
+    // by using the file context's value.
+    let closures = batch
+        .iter()
+        .zip(&node_set_indices)
+        .map(|((rule, _), node_set)| {
+            format!(
+                r#"
+() => {{
+// The rule's JavaScript code
+//////////////////////////////
+{}
+//////////////////////////////
+
+for (const n of GLOBAL_batchNodes[{node_set}]) {{
+    if (Object.keys(GLOBAL_fileContext).length > 0) {{
+        n.context = {{...n.context, ...GLOBAL_fileContext}};
+    }}
+    visit(n, GLOBAL_filename, n.context.code, n.queryName);
+}}
+
+return stellaAllErrors;
+}}"#,
+                rule.code,
+            )
+        })
+        .collect::<Vec<_>>();
+    // Note: variables prefixed with "GLOBAL_" are defined by the static analysis kernel directly via the v8 API.
+    // The script only evaluates to the closures of the rules, which are then executed one by one
+    // in the sandbox.
+    let js_code = format!("[{}];", closures.join(","));
+
+    let iso_handle = runtime.inner_compat().v8_isolate().thread_safe_handle();
+    let clean_execute = runtime.clean_execute_compat();
+
+    let handle_scope = &mut runtime.inner_compat().handle_scope();
+    let ctx = handle_scope.get_current_context();
+    let scope = &mut v8::ContextScope::new(handle_scope, ctx);
+    let global = ctx.global(scope);
+
+    let key_nodes =
+        v8::String::new_from_utf8(scope, "GLOBAL_batchNodes".as_bytes(), Internalized).unwrap();
+    let batch_nodes = node_sets
+        .iter()
+        .map(|&idx| &batch[idx].1)
+        .collect::<Vec<_>>();
+    let v8_nodes = serde_v8::to_v8(scope, batch_nodes).expect("MatchNode should be serializable");
+    global.set(scope, key_nodes.into(), v8_nodes);
+
+    let key_file_context =
+        v8::String::new_from_utf8(scope, "GLOBAL_fileContext".as_bytes(), Internalized).unwrap();
+    let v8_file_context =
+        serde_v8::to_v8(scope, file_context).expect("FileContext should be serializable");
+    global.set(scope, key_file_context.into(), v8_file_context);
+
+    let key_filename =
+        v8::String::new_from_utf8(scope, "GLOBAL_filename".as_bytes(), Internalized).unwrap();
+    let v8_filename = serde_v8::to_v8(scope, filename).expect("filename should be valid v8 string");
+    global.set(scope, key_filename.into(), v8_filename);
+
+    let keys = [key_nodes, key_file_context, key_filename];
+
+    let code = v8::String::new(scope, &js_code)
+        .expect("dynamically generated JavaScript code should be valid v8 string");
+    let functions = {
+        let tc_scope = &mut v8::TryCatch::new(scope);
+        let functions = v8::Script::compile(tc_scope, code, None)
+            .and_then(|script| script.run(tc_scope))
+            .and_then(|value| v8::Local::<v8::Array>::try_from(value).ok());
+        functions.ok_or_else(|| {
+            let reason = tc_scope
+                .exception()
+                .map(|exception| exception.to_rust_string_lossy(tc_scope))
+                .unwrap_or_default();
+            tc_scope.reset();
+            ExecutionError::Interpreter { reason }
+        })
+    };
+    let functions = match functions {
+        Ok(functions) => functions,
+        Err(err) => {
+            for key in keys {
+                global.delete(scope, key.into());
+            }
+            return Err(err);
+        }
+    };
+
+    let outcomes = batch
+        .iter()
+        .enumerate()
+        .map(|(idx, (rule, _))| {
+            let tc_scope = &mut v8::TryCatch::new(scope);
+            let closure = functions
+                .get_index(tc_scope, idx as u32)
+                .filter(|value| value.is_function())
+                .expect("the script should evaluate to a closure per rule");
+            let receiver = v8::undefined(tc_scope).into();
+            let seed = rule_seed(rule, filename, analysis_options);
+            let args = sandbox_args(tc_scope, closure, rule.allow_side_effects, seed);
+            let clean_execute = v8::Local::new(tc_scope, &clean_execute);
+
+            let execution_start = Instant::now();
+            let (execution_result, timed_out) = run_with_timeout(&iso_handle, || {
+                clean_execute.call(tc_scope, receiver, &args)
+            });
+            let elapsed = execution_start.elapsed();
+            let res = match execution_result {
+                _ if timed_out => {
+                    tc_scope.reset();
+                    Err(ExecutionError::ExecutionTimeout(elapsed))
+                }
+                Some(value) => into_violations(tc_scope, value, rule),
+                None => {
+                    let reason = tc_scope
+                        .exception()
+                        .map(|exception| exception.to_rust_string_lossy(tc_scope))
+                        .unwrap_or_default();
+                    tc_scope.reset();
+                    Err(ExecutionError::from_exception(reason))
+                }
+            };
+            (res, elapsed)
+        })
+        .collect::<Vec<_>>();
+
+    // Drop the objects we created. Because we are re-using the context, it won't happen automatically.
+    for key in keys {
+        global.delete(scope, key.into());
+    }
+
+    Ok(outcomes)
+}
+
+/// Returns true if the matches of two rules with the same queries have the same arguments.
+fn same_arguments(nodes: &[MatchNode], other_nodes: &[MatchNode]) -> bool {
+    nodes.len() == other_nodes.len()
+        && nodes.first().map(|n| &n.context.arguments)
+            == other_nodes.first().map(|n| &n.context.arguments)
+}
+
+/// Runs JavaScript with `run`, terminating its execution if it exceeds [`JAVASCRIPT_EXECUTION_TIMEOUT`].
+/// Returns the result of `run`, and whether it timed out.
+fn run_with_timeout<T>(iso_handle: &v8::IsolateHandle, run: impl FnOnce() -> T) -> (T, bool) {
     let done_flag = Arc::new(AtomicBool::new(false));
     let done_flag_clone = Arc::clone(&done_flag);
     let iso_handle_clone = iso_handle.clone();
@@ -224,8 +712,7 @@ return stellaAllErrors;
         }
     });
 
-    let execution_start = Instant::now();
-    let execution_result = compiled_script.run(tc_scope);
+    let execution_result = run();
     done_flag.store(true, Ordering::Relaxed);
     // This can't deadlock because even if a race causes the atomic bool to be set after the `unpark`,
     // the watchdog thread's call to `park_timeout` will return immediately.
@@ -234,42 +721,8 @@ return stellaAllErrors;
     let timed_out = timed_out.join().expect("thread should not panic");
     if timed_out {
         iso_handle.cancel_terminate_execution();
-        return Err(ExecutionError::ExecutionTimeout(execution_start.elapsed()));
     }
-
-    let execution_result = execution_result.ok_or_else(|| {
-        let exception = tc_scope
-            .exception()
-            .expect("return value should only be `None` if an error was caught");
-        let reason = exception.to_rust_string_lossy(tc_scope);
-        tc_scope.reset();
-        ExecutionError::Execution { reason }
-    })?;
-
-    let v8_array: v8::Local<v8::Array> =
-        execution_result.try_into().map_err(|err: v8::DataError| {
-            let reason = err.to_string();
-            ExecutionError::UnexpectedReturnValue { reason }
-        })?;
-    let violations = iter_v8_array(v8_array, tc_scope)
-        .map(|value| {
-            use crate::analysis::ddsa_lib::v8_ds::V8Converter;
-            violation_converter()
-                .try_convert_from(tc_scope, value)
-                .map(|v| v.into_violation(rule.severity, rule.category))
-        })
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|err| {
-            let reason = err.to_string();
-            ExecutionError::UnexpectedReturnValue { reason }
-        })?;
-
-    // Drop the objects we created. Because we are re-using the context, it won't happen automatically.
-    global.delete(tc_scope, key_nodes.into());
-    global.delete(tc_scope, key_file_context.into());
-    global.delete(tc_scope, key_filename.into());
-
-    Ok(violations)
+    (execution_result, timed_out)
 }
 
 #[cfg(test)]
@@ -277,9 +730,9 @@ mod tests {
     use super::*;
     use crate::analysis::file_context::common::get_empty_file_context;
     use crate::analysis::tree_sitter::{get_query, get_query_nodes, get_tree};
-    use crate::model::common::Language;
-    use crate::model::rule::{RuleCategory, RuleSeverity};
-    use std::collections::HashMap;
+    use crate::model::common::{ColumnUnit, Language};
+    use crate::model::rule::{ConsoleLevel, RuleCategory, RuleSeverity};
+    use std::collections::{HashMap, HashSet};
 
     #[test]
     fn test_execute_rule() {
@@ -313,6 +766,9 @@ mod tests {
             language: Language::Python,
             code: rule_code.to_string(),
             tree_sitter_query: query,
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let nodes = get_query_nodes(
@@ -331,6 +787,11 @@ mod tests {
                 use_debug: true,
                 log_output: true,
                 ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
             },
             &get_empty_file_context(),
         );
@@ -374,6 +835,9 @@ def foo(arg1):
             language: Language::Python,
             code: rule_code.to_string(),
             tree_sitter_query: query,
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let nodes = get_query_nodes(
@@ -392,6 +856,11 @@ def foo(arg1):
                 use_debug: true,
                 log_output: true,
                 ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
             },
             &get_empty_file_context(),
         );
@@ -444,6 +913,9 @@ def foo(arg1):
             language: Language::Python,
             code: rule_code.to_string(),
             tree_sitter_query: query,
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
         let nodes = get_query_nodes(&tree, &rule.tree_sitter_query, "plop", c, &HashMap::new());
 
@@ -455,6 +927,11 @@ def foo(arg1):
                 use_debug: true,
                 log_output: true,
                 ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
             },
             &get_empty_file_context(),
         );
@@ -475,6 +952,90 @@ def foo(arg1):
         );
     }
 
+    #[test]
+    fn test_execute_with_violation_metadata() {
+        let q = r#"
+(function_definition
+    name: (identifier) @name
+)
+        "#;
+
+        let rule_code = r#"
+function visit(node, filename, code) {
+    const functionName = node.captures["name"];
+    const error = buildError(functionName.start.line, functionName.start.col, functionName.end.line, functionName.end.col, "invalid name")
+        .addTag("injection")
+        .addCwe(89)
+        .addCwe("CWE-79")
+        .setDocumentationUrl("https://example.com/docs")
+        .addRelatedLocation(1, 1, 1, 4, "declared here");
+    addError(error);
+    addError(buildError(functionName.start.line, functionName.start.col, functionName.end.line, functionName.end.col, "no metadata"));
+    addError(buildFileError("missing license header", "WARNING", "best_practices"));
+}
+        "#;
+
+        let c = "def foo(arg1):\n    pass";
+        let tree = get_tree(c, &Language::Python).unwrap();
+        let query = get_query(q, &Language::Python).unwrap();
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::Security,
+            severity: RuleSeverity::Error,
+            language: Language::Python,
+            code: rule_code.to_string(),
+            tree_sitter_query: query,
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let nodes = get_query_nodes(&tree, &rule.tree_sitter_query, "plop", c, &HashMap::new());
+
+        let rule_execution = execute_rule(
+            &rule,
+            nodes,
+            "foo.py".to_string(),
+            AnalysisOptions {
+                use_debug: false,
+                log_output: false,
+                ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
+            },
+            &get_empty_file_context(),
+        );
+        assert!(rule_execution.execution_error.is_none());
+        assert_eq!(3, rule_execution.violations.len());
+        let violation = &rule_execution.violations[0];
+        assert!(!violation.file_level);
+        assert_eq!(violation.tags, vec!["injection".to_string()]);
+        assert_eq!(violation.cwe, vec![89, 79]);
+        assert_eq!(
+            violation.documentation_url.as_deref(),
+            Some("https://example.com/docs")
+        );
+        assert_eq!(violation.related_locations.len(), 1);
+        let related = &violation.related_locations[0];
+        assert_eq!((related.start.line, related.start.col), (1, 1));
+        assert_eq!((related.end.line, related.end.col), (1, 4));
+        assert_eq!(related.message, "declared here");
+        let violation = &rule_execution.violations[1];
+        assert!(violation.related_locations.is_empty());
+        assert!(violation.tags.is_empty());
+        assert!(violation.cwe.is_empty());
+        assert!(violation.documentation_url.is_none());
+        let violation = &rule_execution.violations[2];
+        assert!(violation.file_level);
+        assert_eq!(violation.message, "missing license header");
+        assert_eq!((violation.start.line, violation.start.col), (1, 1));
+        assert_eq!((violation.end.line, violation.end.col), (1, 1));
+    }
+
     #[test]
     fn test_execute_with_console() {
         // Test for a string
@@ -518,7 +1079,7 @@ function visit(node, filename, code) {
         let rule_code_number = r#"
 function visit(node, filename, code) {
     foo = 42;
-    console.log(foo);
+    console.warn(foo);
 }
         "#;
 
@@ -537,6 +1098,9 @@ def foo(arg1):
             language: Language::Python,
             code: rule_code_string.to_string(),
             tree_sitter_query: query,
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let nodes = get_query_nodes(
@@ -555,13 +1119,18 @@ def foo(arg1):
                 use_debug: true,
                 log_output: true,
                 ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
             },
             &get_empty_file_context(),
         );
 
         // execute for string
         assert!(rule_execution.execution_error.is_none());
-        assert_eq!(rule_execution.output.unwrap(), "bla");
+        assert_eq!(rule_execution.output_text().unwrap(), "bla");
 
         // execute with array
         rule.code = rule_code_array.to_string();
@@ -573,12 +1142,17 @@ def foo(arg1):
                 use_debug: true,
                 log_output: true,
                 ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
             },
             &get_empty_file_context(),
         );
 
         assert!(rule_execution.execution_error.is_none());
-        assert_eq!(rule_execution.output.unwrap(), "[1,2,3]");
+        assert_eq!(rule_execution.output_text().unwrap(), "[1,2,3]");
 
         // execute with object
         rule.code = rule_code_object.to_string();
@@ -590,12 +1164,17 @@ def foo(arg1):
                 use_debug: true,
                 log_output: true,
                 ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
             },
             &get_empty_file_context(),
         );
 
         assert!(rule_execution.execution_error.is_none());
-        assert_eq!(rule_execution.output.unwrap(), "{\"astType\":\"identifier\",\"start\":{\"line\":2,\"col\":5},\"end\":{\"line\":2,\"col\":8},\"fieldName\":null,\"children\":[]}");
+        assert_eq!(rule_execution.output_text().unwrap(), "{\"astType\":\"identifier\",\"start\":{\"line\":2,\"col\":5},\"end\":{\"line\":2,\"col\":8},\"fieldName\":null,\"children\":[]}");
 
         // execute with null
         rule.code = rule_code_null.to_string();
@@ -607,12 +1186,17 @@ def foo(arg1):
                 use_debug: true,
                 log_output: true,
                 ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
             },
             &get_empty_file_context(),
         );
 
         assert!(rule_execution.execution_error.is_none());
-        assert_eq!(rule_execution.output.unwrap(), "null\nundefined");
+        assert_eq!(rule_execution.output_text().unwrap(), "null\nundefined");
 
         // execute with a number
         rule.code = rule_code_number.to_string();
@@ -624,12 +1208,18 @@ def foo(arg1):
                 use_debug: true,
                 log_output: true,
                 ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
             },
             &get_empty_file_context(),
         );
 
         assert!(rule_execution.execution_error.is_none());
-        assert_eq!(rule_execution.output.unwrap(), "42");
+        assert_eq!(rule_execution.output_text().unwrap(), "42");
+        assert_eq!(rule_execution.output.unwrap()[0].level, ConsoleLevel::Warn);
     }
 
     // change the type of the edit, which should trigger a serialization issue
@@ -672,6 +1262,9 @@ def foo(arg1):
             language: Language::Python,
             code: rule_code.to_string(),
             tree_sitter_query: query,
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let nodes = get_query_nodes(
@@ -690,6 +1283,11 @@ def foo(arg1):
                 use_debug: true,
                 log_output: true,
                 ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
             },
             &get_empty_file_context(),
         );
@@ -732,6 +1330,9 @@ def foo(arg1):
             language: Language::Python,
             code: rule_code.to_string(),
             tree_sitter_query: query,
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
         };
 
         let nodes = get_query_nodes(
@@ -750,6 +1351,11 @@ def foo(arg1):
                 use_debug: true,
                 log_output: true,
                 ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit: ColumnUnit::Bytes,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
             },
             &get_empty_file_context(),
         );
@@ -769,4 +1375,513 @@ def foo(arg1):
             rule_execution.errors.get(0).unwrap()
         )
     }
+
+    /// Rules executed in a batch are isolated from each other, and their results are kept in order.
+    #[test]
+    fn test_execute_rules_batch() {
+        let q = r#"
+(function_definition
+    name: (identifier) @name
+)
+        "#;
+        let reporting_rule = r#"
+const message = "invalid name";
+function visit(node, filename, code) {
+    const functionName = node.captures["name"];
+    addError(buildError(functionName.start.line, functionName.start.col, functionName.end.line, functionName.end.col, message));
+}
+        "#;
+        let throwing_rule = r#"
+const message = "should not leak";
+function visit(node, filename, code) {
+    addError(buildError(1, 1, 1, 2, message));
+    throw new Error("rule failed");
+}
+        "#;
+        let invalid_rule = r#"
+function visit(node, filename, code) {
+}}
+        "#;
+
+        let c = "def foo(arg1):\n    pass\ndef bar():\n    pass";
+        let tree = get_tree(c, &Language::Python).unwrap();
+        let rule = |name: &str, code: &str| RuleInternal {
+            name: name.to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: code.to_string(),
+            tree_sitter_query: get_query(q, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let rules = [
+            rule("reporting-1", reporting_rule),
+            rule("throwing", throwing_rule),
+            rule("reporting-2", reporting_rule),
+            rule("invalid", invalid_rule),
+        ];
+        let analysis_options = AnalysisOptions {
+            use_debug: false,
+            log_output: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let with_nodes = |rules: &[RuleInternal]| {
+            rules
+                .iter()
+                .map(|rule| {
+                    let nodes =
+                        get_query_nodes(&tree, &rule.tree_sitter_query, "plop", c, &HashMap::new());
+                    (rule, nodes)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let results = execute_rules(
+            with_nodes(&rules[..3]),
+            "foo.py",
+            &analysis_options,
+            &get_empty_file_context(),
+            &CancellationToken::new(),
+        );
+        let names = results
+            .iter()
+            .map(|r| r.rule_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["reporting-1", "throwing", "reporting-2"]);
+        for result in [&results[0], &results[2]] {
+            assert!(result.execution_error.is_none());
+            assert_eq!(result.violations.len(), 2);
+            assert_eq!(result.violations[0].message, "invalid name");
+        }
+        assert!(results[1].violations.is_empty());
+        assert_eq!(
+            results[1].execution_error.as_deref(),
+            Some("Error: rule failed")
+        );
+        assert_eq!(results[1].errors, vec![ERROR_RULE_EXECUTION.to_string()]);
+
+        // A rule that can't be compiled only fails itself.
+        let results = execute_rules(
+            with_nodes(&rules),
+            "foo.py",
+            &analysis_options,
+            &get_empty_file_context(),
+            &CancellationToken::new(),
+        );
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].violations.len(), 2);
+        assert_eq!(results[2].violations.len(), 2);
+        assert_eq!(
+            results[3].execution_error.as_deref(),
+            Some("SyntaxError: Unexpected token '}'")
+        );
+    }
+
+    /// The timeout of a batch applies to each of its rules: rules that are slow, but within the
+    /// timeout, don't make the rules after them time out.
+    #[test]
+    fn test_execute_rules_batch_timeout_per_rule() {
+        let q = r#"
+(function_definition
+    name: (identifier) @name
+)
+        "#;
+        let slow_rule = r#"
+function visit(node, filename, code) {
+    const end = Date.now() + 3000;
+    while (Date.now() < end) {}
+}
+        "#;
+        let reporting_rule = r#"
+function visit(node, filename, code) {
+    addError(buildError(1, 1, 1, 2, "done"));
+}
+        "#;
+
+        let c = "def foo(arg1):\n    pass";
+        let tree = get_tree(c, &Language::Python).unwrap();
+        let rule = |name: &str, code: &str| RuleInternal {
+            name: name.to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: code.to_string(),
+            tree_sitter_query: get_query(q, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let rules = [
+            rule("slow-1", slow_rule),
+            rule("slow-2", slow_rule),
+            rule("reporting", reporting_rule),
+        ];
+        // `Date` is frozen when the rules are executed deterministically
+        let analysis_options = AnalysisOptions {
+            nondeterministic_rules: true,
+            ..Default::default()
+        };
+        let with_nodes = rules
+            .iter()
+            .map(|rule| {
+                let nodes =
+                    get_query_nodes(&tree, &rule.tree_sitter_query, "plop", c, &HashMap::new());
+                (rule, nodes)
+            })
+            .collect::<Vec<_>>();
+
+        let results = execute_rules(
+            with_nodes,
+            "foo.py",
+            &analysis_options,
+            &get_empty_file_context(),
+            &CancellationToken::new(),
+        );
+        for result in &results {
+            assert!(result.errors.is_empty(), "{}", result.rule_name);
+        }
+        assert_eq!(results[2].violations.len(), 1);
+    }
+
+    /// The APIs with side effects can only be accessed by the rules allowed to use them, and a
+    /// violation of the sandbox is reported even if the rule catches it.
+    #[test]
+    fn test_sandbox() {
+        let q = r#"
+(function_definition
+    name: (identifier) @name
+)
+        "#;
+        let fetching_rule = r#"
+function visit(node, filename, code) {
+    fetch("https://example.com");
+}
+        "#;
+        let catching_rule = r#"
+function visit(node, filename, code) {
+    try {
+        queueMicrotask(() => {});
+    } catch (e) {}
+    addError(buildError(1, 1, 1, 2, "done"));
+}
+        "#;
+        let ops_rule = r#"
+function visit(node, filename, code) {
+    if (typeof Deno.core.ops === "object") {
+        addError(buildError(1, 1, 1, 2, "done"));
+    }
+}
+        "#;
+
+        let c = "def foo(arg1):\n    pass";
+        let tree = get_tree(c, &Language::Python).unwrap();
+        let rule = |name: &str, code: &str, allow_side_effects: bool| RuleInternal {
+            name: name.to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: code.to_string(),
+            tree_sitter_query: get_query(q, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects,
+            prerequisites: Default::default(),
+        };
+        let rules = [
+            rule("fetching", fetching_rule, false),
+            rule("catching", catching_rule, false),
+            rule("ops", ops_rule, false),
+            rule("allowed-ops", ops_rule, true),
+        ];
+        let analysis_options = AnalysisOptions {
+            use_debug: false,
+            log_output: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let with_nodes = rules
+            .iter()
+            .map(|rule| {
+                let nodes =
+                    get_query_nodes(&tree, &rule.tree_sitter_query, "plop", c, &HashMap::new());
+                (rule, nodes)
+            })
+            .collect::<Vec<_>>();
+
+        let batch_results = execute_rules(
+            with_nodes.clone(),
+            "foo.py",
+            &analysis_options,
+            &get_empty_file_context(),
+            &CancellationToken::new(),
+        );
+        let individual_results = with_nodes
+            .into_iter()
+            .map(|(rule, nodes)| {
+                execute_rule(
+                    rule,
+                    nodes,
+                    "foo.py".to_string(),
+                    analysis_options.clone(),
+                    &get_empty_file_context(),
+                )
+            })
+            .collect::<Vec<_>>();
+        for results in [batch_results, individual_results] {
+            for (result, name) in results[..3].iter().zip(["fetch", "queueMicrotask", "Deno"]) {
+                assert!(result.violations.is_empty());
+                assert_eq!(result.errors, vec![ERROR_RULE_SANDBOX.to_string()]);
+                assert_eq!(
+                    result.execution_error.as_deref(),
+                    Some(format!("SandboxViolation: `{name}` is not permitted in rules").as_str())
+                );
+            }
+            assert!(results[3].errors.is_empty());
+            assert_eq!(results[3].violations.len(), 1);
+        }
+    }
+
+    /// A rule can't reach the function that executes it in the sandbox, and so it can't change its
+    /// own permissions, its determinism, or the violations already reported.
+    #[test]
+    fn test_sandbox_escape() {
+        let q = r#"
+(function_definition
+    name: (identifier) @name
+)
+        "#;
+        let escaping_rule = r#"
+function visit(node, filename, code) {
+    _cleanExecute(() => fetch("https://example.com"), true, null);
+}
+        "#;
+        let probing_rule = r#"
+function visit(node, filename, code) {
+    addError(buildError(1, 1, 1, 2, "before"));
+    try {
+        _cleanExecute(() => fetch("https://example.com"), true, null);
+    } catch (e) {}
+    if (typeof _cleanExecute === "undefined" && typeof __ddsaCleanExecute === "undefined") {
+        addError(buildError(1, 1, 1, 2, "hidden"));
+    }
+}
+        "#;
+
+        let c = "def foo(arg1):\n    pass";
+        let tree = get_tree(c, &Language::Python).unwrap();
+        let rule = |name: &str, code: &str| RuleInternal {
+            name: name.to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: code.to_string(),
+            tree_sitter_query: get_query(q, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let rules = [
+            rule("escaping", escaping_rule),
+            rule("probing", probing_rule),
+        ];
+        let analysis_options = AnalysisOptions {
+            use_debug: false,
+            log_output: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let with_nodes = rules
+            .iter()
+            .map(|rule| {
+                let nodes =
+                    get_query_nodes(&tree, &rule.tree_sitter_query, "plop", c, &HashMap::new());
+                (rule, nodes)
+            })
+            .collect::<Vec<_>>();
+
+        let batch_results = execute_rules(
+            with_nodes.clone(),
+            "foo.py",
+            &analysis_options,
+            &get_empty_file_context(),
+            &CancellationToken::new(),
+        );
+        let individual_results = with_nodes
+            .into_iter()
+            .map(|(rule, nodes)| {
+                execute_rule(
+                    rule,
+                    nodes,
+                    "foo.py".to_string(),
+                    analysis_options.clone(),
+                    &get_empty_file_context(),
+                )
+            })
+            .collect::<Vec<_>>();
+        for results in [batch_results, individual_results] {
+            assert!(results[0].violations.is_empty());
+            assert_eq!(
+                results[0].execution_error.as_deref(),
+                Some("ReferenceError: _cleanExecute is not defined")
+            );
+            assert!(results[1].errors.is_empty());
+            let messages = results[1]
+                .violations
+                .iter()
+                .map(|v| v.message.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(messages, vec!["before", "hidden"]);
+        }
+    }
+
+    /// The resources used by a rule are only reported when the rules are profiled.
+    #[test]
+    fn test_profile_rules() {
+        let q = r#"
+(function_definition
+    name: (identifier) @name
+    parameters: (parameters) @params
+)
+        "#;
+        let rule_code = r#"
+function visit(node, filename, code) {
+    const names = [];
+    for (let i = 0; i < 1000; i++) {
+        names.push(`${node.captures["name"].astType}-${i}`);
+    }
+}
+        "#;
+
+        let c = "def foo(arg1):\n    pass\ndef bar():\n    pass";
+        let tree = get_tree(c, &Language::Python).unwrap();
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: rule_code.to_string(),
+            tree_sitter_query: get_query(q, &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let nodes = get_query_nodes(&tree, &rule.tree_sitter_query, "plop", c, &HashMap::new());
+        let mut analysis_options = AnalysisOptions {
+            use_debug: false,
+            log_output: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+
+        let result = execute_rule(
+            &rule,
+            nodes.clone(),
+            "foo.py".to_string(),
+            analysis_options.clone(),
+            &get_empty_file_context(),
+        );
+        assert!(result.profile.is_none());
+
+        analysis_options.profile_rules = true;
+        let result = execute_rule(
+            &rule,
+            nodes,
+            "foo.py".to_string(),
+            analysis_options,
+            &get_empty_file_context(),
+        );
+        assert!(result.execution_error.is_none());
+        let profile = result.profile.unwrap();
+        // two matches, with two captures each
+        assert_eq!(profile.captures, 4);
+        assert!(profile.cpu_time_ms <= profile.wall_time_ms + 1);
+    }
+
+    /// The rules see the random numbers of a generator seeded with the rule and the file, and a
+    /// frozen time, unless they are executed nondeterministically.
+    #[test]
+    fn test_deterministic_rules() {
+        let rule_code = r#"
+function visit(node, filename, code) {
+    const message = `${Math.random()} ${Math.random()} ${Date.now()} ${new Date().getTime()}`;
+    addError(buildError(1, 1, 1, 2, message));
+}
+        "#;
+        let c = "def foo():\n    pass";
+        let tree = get_tree(c, &Language::Python).unwrap();
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::CodeStyle,
+            severity: RuleSeverity::Notice,
+            language: Language::Python,
+            code: rule_code.to_string(),
+            tree_sitter_query: get_query("(function_definition) @fn", &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let nodes = get_query_nodes(&tree, &rule.tree_sitter_query, "plop", c, &HashMap::new());
+        let mut analysis_options = AnalysisOptions {
+            use_debug: false,
+            log_output: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let message = |filename: &str, analysis_options: &AnalysisOptions| {
+            let result = execute_rule(
+                &rule,
+                nodes.clone(),
+                filename.to_string(),
+                analysis_options.clone(),
+                &get_empty_file_context(),
+            );
+            result.violations[0].message.clone()
+        };
+
+        let first = message("foo.py", &analysis_options);
+        let parts = first.split(' ').collect::<Vec<_>>();
+        assert_ne!(parts[0], parts[1]);
+        assert_eq!(parts[2], "1704067200000");
+        assert_eq!(parts[3], "1704067200000");
+        assert_eq!(message("foo.py", &analysis_options), first);
+        assert_ne!(message("bar.py", &analysis_options), first);
+
+        analysis_options.nondeterministic_rules = true;
+        let real = message("foo.py", &analysis_options);
+        assert_ne!(real.split(' ').nth(2), Some("1704067200000"));
+    }
 }
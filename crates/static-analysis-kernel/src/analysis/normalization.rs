@@ -0,0 +1,98 @@
+use crate::model::common::{Position, BOM};
+use std::sync::Arc;
+
+/// The content of a file normalized before it's parsed: without its byte order mark, and with its
+/// CRLF line endings replaced by LF, so that the trees and the code given to the rules don't
+/// depend on the platform the file was written on. The tabs are kept, as the columns count bytes.
+///
+/// The lines and the columns of the normalized code are the lines and the columns of the file,
+/// except on the first line of a file with a byte order mark: the positions found in the
+/// normalized code are converted back with [`NormalizedCode::original_position`], so that the
+/// positions reported and the fixes applied are the ones of the file on disk.
+#[derive(Debug, Clone)]
+pub struct NormalizedCode {
+    code: Arc<str>,
+    /// The length in bytes of the byte order mark of the file (0 without one).
+    bom_len: usize,
+    /// The number of carriage returns removed.
+    removed_crs: usize,
+}
+
+impl NormalizedCode {
+    /// Normalizes the content of a file. The content is shared, not copied, if it's already
+    /// normalized.
+    pub fn new(code: &Arc<str>) -> Self {
+        let bom_len = if code.starts_with(BOM) {
+            BOM.len_utf8()
+        } else {
+            0
+        };
+        let content = &code[bom_len..];
+        if bom_len == 0 && !content.contains("\r\n") {
+            return Self {
+                code: Arc::clone(code),
+                bom_len,
+                removed_crs: 0,
+            };
+        }
+        let mut normalized = String::with_capacity(content.len());
+        let mut removed_crs = 0;
+        let mut rest = content;
+        while let Some(idx) = rest.find("\r\n") {
+            normalized.push_str(&rest[..idx]);
+            removed_crs += 1;
+            normalized.push('\n');
+            rest = &rest[idx + 2..];
+        }
+        normalized.push_str(rest);
+        Self {
+            code: Arc::from(normalized),
+            bom_len,
+            removed_crs,
+        }
+    }
+
+    /// Returns the normalized code.
+    pub fn code(&self) -> &Arc<str> {
+        &self.code
+    }
+
+    /// Returns true if the code was already normalized.
+    pub fn is_unchanged(&self) -> bool {
+        self.bom_len == 0 && self.removed_crs == 0
+    }
+
+    /// Converts a position (with its column in bytes) in the normalized code to the position in
+    /// the file.
+    pub fn original_position(&self, position: &Position) -> Position {
+        let col = match position.line {
+            1 => position.col + self.bom_len as u32,
+            _ => position.col,
+        };
+        Position {
+            line: position.line,
+            col,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_code() {
+        let code: Arc<str> = Arc::from("x = 1\ny = 2\n");
+        let normalized = NormalizedCode::new(&code);
+        assert!(normalized.is_unchanged());
+        assert!(Arc::ptr_eq(normalized.code(), &code));
+
+        let normalized = NormalizedCode::new(&Arc::from("\u{feff}x = 1\r\ny = 2\r\nz\r"));
+        assert!(!normalized.is_unchanged());
+        assert_eq!(normalized.code().as_ref(), "x = 1\ny = 2\nz\r");
+        let position = normalized.original_position(&Position { line: 1, col: 5 });
+        assert_eq!((position.line, position.col), (1, 8));
+        let position = normalized.original_position(&Position { line: 2, col: 5 });
+        assert_eq!((position.line, position.col), (2, 5));
+    }
+}
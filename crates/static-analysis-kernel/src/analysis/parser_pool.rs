@@ -0,0 +1,87 @@
+use crate::analysis::tree_sitter::get_tree_sitter_language;
+use crate::model::common::Language;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+thread_local! {
+    static PARSERS: RefCell<HashMap<Language, tree_sitter::Parser>> = RefCell::new(HashMap::new());
+}
+
+static PARSERS_CREATED: AtomicU64 = AtomicU64::new(0);
+static PARSERS_REUSED: AtomicU64 = AtomicU64::new(0);
+
+/// The number of parsers created and reused by the analysis, across all the threads.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParserPoolStats {
+    /// The parsers created, with their language set.
+    pub created: u64,
+    /// The parses done with a parser of the pool instead of a new parser.
+    pub reused: u64,
+}
+
+/// Returns the number of parsers created and reused since the start of the process.
+pub fn parser_pool_stats() -> ParserPoolStats {
+    ParserPoolStats {
+        created: PARSERS_CREATED.load(Ordering::Relaxed),
+        reused: PARSERS_REUSED.load(Ordering::Relaxed),
+    }
+}
+
+/// Calls `f` with a parser of the language: each thread keeps a parser per language, which is
+/// reset before it's reused instead of being created for each file (creating a parser and
+/// setting its language is measurable on the repositories with many small files).
+///
+/// The parser is taken out of the pool while `f` uses it: if `f` parses another file of the
+/// language (e.g. the scripts of a single-file component), it's parsed with a new parser. Returns
+/// `None` if the language can't be set (the grammar is incompatible with tree-sitter).
+pub fn with_parser<R>(
+    language: &Language,
+    f: impl FnOnce(&mut tree_sitter::Parser) -> R,
+) -> Option<R> {
+    let pooled = PARSERS.with(|parsers| parsers.borrow_mut().remove(language));
+    let mut parser = match pooled {
+        Some(mut parser) => {
+            PARSERS_REUSED.fetch_add(1, Ordering::Relaxed);
+            // the previous parse may have been cancelled halfway
+            parser.reset();
+            parser
+        }
+        None => new_parser(language)?,
+    };
+    let result = f(&mut parser);
+    PARSERS.with(|parsers| parsers.borrow_mut().insert(*language, parser));
+    Some(result)
+}
+
+fn new_parser(language: &Language) -> Option<tree_sitter::Parser> {
+    let mut parser = tree_sitter::Parser::new();
+    parser
+        .set_language(&get_tree_sitter_language(language))
+        .ok()?;
+    PARSERS_CREATED.fetch_add(1, Ordering::Relaxed);
+    Some(parser)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse_parsers() {
+        let parse = |code: &str| {
+            with_parser(&Language::Python, |parser| {
+                parser.parse(code, None).unwrap().root_node().child_count()
+            })
+        };
+        assert_eq!(parse("x = 1"), Some(1));
+        let stats = parser_pool_stats();
+        assert_eq!(parse("x = 1\ny = 2"), Some(2));
+        // the stats are shared by the tests executed in parallel
+        assert!(parser_pool_stats().reused > stats.reused);
+
+        // a file parsed while another one is being parsed uses another parser
+        let nested = with_parser(&Language::Python, |_| parse("x = 1"));
+        assert_eq!(nested, Some(Some(1)));
+    }
+}
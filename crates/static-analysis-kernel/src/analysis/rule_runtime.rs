@@ -0,0 +1,123 @@
+//! The runtime that executes the rules on the nodes they matched. The rules are executed by `v8`
+//! (see [`crate::analysis::javascript`]) unless another runtime is selected for the process with
+//! [`set_rule_runtime`] (e.g. the WebAssembly runtime, to compare their startup latency and
+//! sandboxing).
+
+use crate::analysis::cancellation::CancellationToken;
+use crate::analysis::file_context::common::FileContext;
+#[cfg(not(feature = "js-runtime"))]
+use crate::model::analysis::ERROR_RULE_EXECUTION;
+use crate::model::analysis::{AnalysisOptions, MatchNode};
+use crate::model::rule::{RuleInternal, RuleResult};
+use std::sync::OnceLock;
+
+/// Executes the rules of a file.
+pub trait RuleRuntime: Send + Sync {
+    /// The name of the runtime, shown to the user.
+    fn name(&self) -> &'static str;
+
+    /// Executes the rules on the nodes they matched in a file. The results are returned in the
+    /// order of the rules. The rules that were not executed once the token is cancelled are
+    /// reported with [`RuleResult::cancelled`].
+    fn execute_rules(
+        &self,
+        rules: Vec<(&RuleInternal, Vec<MatchNode>)>,
+        filename: &str,
+        analysis_options: &AnalysisOptions,
+        file_context: &FileContext,
+        cancellation: &CancellationToken,
+    ) -> Vec<RuleResult>;
+}
+
+static RULE_RUNTIME: OnceLock<Box<dyn RuleRuntime>> = OnceLock::new();
+
+/// Selects the runtime of the rules of the process. It must be called before the first analysis:
+/// the runtime cannot be changed once it's used, and the runtime is returned as an error then.
+pub fn set_rule_runtime(runtime: Box<dyn RuleRuntime>) -> Result<(), Box<dyn RuleRuntime>> {
+    RULE_RUNTIME.set(runtime)
+}
+
+/// Returns the runtime of the rules of the process: the runtime selected with
+/// [`set_rule_runtime`], or `v8` by default.
+pub fn rule_runtime() -> &'static dyn RuleRuntime {
+    RULE_RUNTIME.get_or_init(default_rule_runtime).as_ref()
+}
+
+#[cfg(feature = "js-runtime")]
+fn default_rule_runtime() -> Box<dyn RuleRuntime> {
+    Box::new(V8RuleRuntime)
+}
+
+#[cfg(not(feature = "js-runtime"))]
+fn default_rule_runtime() -> Box<dyn RuleRuntime> {
+    Box::new(UnavailableRuleRuntime)
+}
+
+/// Executes the rules with the runtimes of the global
+/// [`JsRuntimePool`](crate::analysis::ddsa_lib::JsRuntimePool).
+#[cfg(feature = "js-runtime")]
+pub struct V8RuleRuntime;
+
+#[cfg(feature = "js-runtime")]
+impl RuleRuntime for V8RuleRuntime {
+    fn name(&self) -> &'static str {
+        "v8"
+    }
+
+    fn execute_rules(
+        &self,
+        rules: Vec<(&RuleInternal, Vec<MatchNode>)>,
+        filename: &str,
+        analysis_options: &AnalysisOptions,
+        file_context: &FileContext,
+        cancellation: &CancellationToken,
+    ) -> Vec<RuleResult> {
+        crate::analysis::javascript::execute_rules(
+            rules,
+            filename,
+            analysis_options,
+            file_context,
+            cancellation,
+        )
+    }
+}
+
+/// Without the JavaScript runtime (e.g. when the kernel is compiled to WebAssembly), rules cannot
+/// be executed by the kernel. The embedder is expected to execute them with the matches returned by
+/// [`get_rule_matches`](crate::analysis::analyze::get_rule_matches), and to filter the violations
+/// with [`get_lines_to_ignore`](crate::analysis::analyze::get_lines_to_ignore).
+#[cfg(not(feature = "js-runtime"))]
+pub struct UnavailableRuleRuntime;
+
+#[cfg(not(feature = "js-runtime"))]
+impl RuleRuntime for UnavailableRuleRuntime {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn execute_rules(
+        &self,
+        rules: Vec<(&RuleInternal, Vec<MatchNode>)>,
+        filename: &str,
+        _analysis_options: &AnalysisOptions,
+        _file_context: &FileContext,
+        _cancellation: &CancellationToken,
+    ) -> Vec<RuleResult> {
+        rules
+            .into_iter()
+            .map(|(rule, _)| RuleResult {
+                rule_name: rule.name.as_str().into(),
+                filename: filename.into(),
+                violations: vec![],
+                errors: vec![ERROR_RULE_EXECUTION.to_string()],
+                execution_error: Some("the JavaScript runtime is not available".to_string()),
+                execution_time_ms: 0,
+                output: None,
+                parsing_time_ms: 0,
+                query_node_time_ms: 0,
+                degraded: false,
+                profile: None,
+            })
+            .collect()
+    }
+}
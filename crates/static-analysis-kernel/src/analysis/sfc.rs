@@ -0,0 +1,505 @@
+use crate::analysis::analyze::{analyze_with_runtime, convert_columns, for_each_position};
+use crate::analysis::cancellation::CancellationToken;
+use crate::analysis::rule_runtime::{rule_runtime, RuleRuntime};
+use crate::arguments::ArgumentProvider;
+use crate::model::analysis::AnalysisOptions;
+use crate::model::common::{ColumnUnit, Language, LineIndex, Position};
+use crate::model::rule::{RuleInternal, RuleProfile, RuleResult};
+use std::borrow::Borrow;
+
+/// The extensions of single-file components (SFC): files that embed scripts in an HTML-like document.
+pub const SFC_EXTENSIONS: &[&str] = &["vue", "svelte"];
+
+/// A `<script>` block of a single-file component.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SfcScript<'a> {
+    /// The language of the script, from its `lang` attribute.
+    pub language: Language,
+    /// The content between the opening and the closing tag.
+    pub content: &'a str,
+    /// The position (in the component) of the first character of the content.
+    pub start: Position,
+}
+
+/// Returns true if the file is a single-file component, based on its extension.
+pub fn is_sfc_file(filename: &str) -> bool {
+    filename
+        .rsplit_once('.')
+        .is_some_and(|(_, ext)| SFC_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Extract the `<script>` blocks of a single-file component (Vue or Svelte). A component can have
+/// several scripts (e.g. `<script setup>` in Vue or `<script context="module">` in Svelte).
+///
+/// The `<template>` is not extracted: its markup is not analyzed, and the expressions it embeds
+/// are not valid scripts on their own.
+pub fn extract_sfc_scripts(code: &str) -> Vec<SfcScript> {
+    let lowercase = code.to_ascii_lowercase();
+    let mut scripts = vec![];
+
+    let mut offset = 0;
+    while let Some((attributes, content_start, content_end, next)) =
+        find_element(&lowercase, "script", offset)
+    {
+        let language = if is_typescript(attributes) {
+            Language::TypeScript
+        } else {
+            Language::JavaScript
+        };
+        scripts.push(SfcScript {
+            language,
+            content: &code[content_start..content_end],
+            start: position_at(code, content_start),
+        });
+        offset = next;
+    }
+    scripts
+}
+
+/// Find an element with the given tag name starting at `offset`. The `code` must be lowercase.
+/// Returns the attributes, the start and end of the content and the offset right after the element.
+fn find_element<'a>(
+    code: &'a str,
+    tag: &str,
+    offset: usize,
+) -> Option<(&'a str, usize, usize, usize)> {
+    let opening = format!("<{}", tag);
+    let closing = format!("</{}>", tag);
+
+    let mut search = offset;
+    let tag_start = loop {
+        let idx = search + code.get(search..)?.find(&opening)?;
+        // Make sure that we don't match a tag with the same prefix (e.g. `<scripts>`)
+        let after = code[idx + opening.len()..].chars().next()?;
+        if after == '>' || after == '/' || after.is_whitespace() {
+            break idx;
+        }
+        search = idx + opening.len();
+    };
+    let attributes_start = tag_start + opening.len();
+    let attributes_end = attributes_start + code[attributes_start..].find('>')?;
+    let attributes = &code[attributes_start..attributes_end];
+    // Self-closing elements (e.g. `<script src="..." />`) have no content.
+    if attributes.trim_end().ends_with('/') {
+        return Some((
+            attributes,
+            attributes_end + 1,
+            attributes_end + 1,
+            attributes_end + 1,
+        ));
+    }
+    let content_start = attributes_end + 1;
+    let content_end = content_start + code[content_start..].find(&closing)?;
+    Some((
+        attributes,
+        content_start,
+        content_end,
+        content_end + closing.len(),
+    ))
+}
+
+fn is_typescript(attributes: &str) -> bool {
+    [
+        "lang=\"ts\"",
+        "lang='ts'",
+        "lang=\"tsx\"",
+        "lang='tsx'",
+        "lang=\"typescript\"",
+        "lang='typescript'",
+    ]
+    .iter()
+    .any(|lang| attributes.contains(lang))
+}
+
+/// Get the 1-based line and column of a byte offset.
+fn position_at(code: &str, byte_offset: usize) -> Position {
+    let before = &code[..byte_offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    let col = byte_offset - line_start + 1;
+    Position {
+        line: line as u32,
+        col: col as u32,
+    }
+}
+
+/// Translate a position in a block to a position in the component. Only the first line of the
+/// block is shifted horizontally, since the following lines start at the beginning of a line.
+fn remap_position(position: &mut Position, block_start: &Position) {
+    if position.line == 1 {
+        position.col += block_start.col - 1;
+    }
+    position.line += block_start.line - 1;
+}
+
+/// Translate the positions (in bytes) of the violations found in a block, of their related
+/// locations and of their fixes to positions in the component, and compute their offsets in the
+/// component.
+fn remap_rule_result(rule_result: &mut RuleResult, block_start: &Position, line_index: &LineIndex) {
+    for_each_position(rule_result, |position| {
+        remap_position(position, block_start)
+    });
+    for violation in &mut rule_result.violations {
+        violation.offsets = line_index.offsets(&violation.start, &violation.end);
+    }
+}
+
+/// Analyze the scripts of a single-file component written in `language` (JavaScript or TypeScript).
+/// Each script is analyzed separately and the positions of the violations are relative to the component.
+/// The results of all the scripts are merged so that there is one result per rule.
+pub fn analyze_sfc<I>(
+    language: &Language,
+    rules: I,
+    filename: &str,
+    code: &str,
+    argument_provider: &ArgumentProvider,
+    analysis_option: &AnalysisOptions,
+) -> Vec<RuleResult>
+where
+    I: IntoIterator,
+    I::Item: Borrow<RuleInternal>,
+{
+    analyze_sfc_with(
+        language,
+        rules,
+        filename,
+        code,
+        argument_provider,
+        analysis_option,
+        &CancellationToken::new(),
+    )
+}
+
+/// Same as [`analyze_sfc`], but the analysis stops as soon as possible once the token is cancelled.
+pub fn analyze_sfc_with<I>(
+    language: &Language,
+    rules: I,
+    filename: &str,
+    code: &str,
+    argument_provider: &ArgumentProvider,
+    analysis_option: &AnalysisOptions,
+    cancellation: &CancellationToken,
+) -> Vec<RuleResult>
+where
+    I: IntoIterator,
+    I::Item: Borrow<RuleInternal>,
+{
+    analyze_sfc_with_runtime(
+        language,
+        rules,
+        filename,
+        code,
+        argument_provider,
+        analysis_option,
+        cancellation,
+        rule_runtime(),
+    )
+}
+
+/// Same as [`analyze_sfc_with`], with the rules executed by the given runtime instead of the
+/// runtime of the process.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn analyze_sfc_with_runtime<I>(
+    language: &Language,
+    rules: I,
+    filename: &str,
+    code: &str,
+    argument_provider: &ArgumentProvider,
+    analysis_option: &AnalysisOptions,
+    cancellation: &CancellationToken,
+    runtime: &dyn RuleRuntime,
+) -> Vec<RuleResult>
+where
+    I: IntoIterator,
+    I::Item: Borrow<RuleInternal>,
+{
+    let rules = rules.into_iter().collect::<Vec<_>>();
+    let mut merged: Vec<RuleResult> = vec![];
+    // The blocks are analyzed with columns in bytes, which are shifted to the component and then
+    // converted to the unit of the analysis, as the first line of a block doesn't start its line.
+    let block_option = AnalysisOptions {
+        column_unit: ColumnUnit::Bytes,
+        ..analysis_option.clone()
+    };
+    let line_index = LineIndex::new(code);
+
+    let scripts = extract_sfc_scripts(code)
+        .into_iter()
+        .filter(|script| script.language == *language);
+    for script in scripts {
+        let results = analyze_with_runtime(
+            language,
+            rules.iter().map(|rule| rule.borrow()),
+            filename,
+            script.content,
+            argument_provider,
+            &block_option,
+            cancellation,
+            runtime,
+        );
+        for mut result in results {
+            remap_rule_result(&mut result, &script.start, &line_index);
+            match merged.iter_mut().find(|r| r.rule_name == result.rule_name) {
+                Some(existing) => {
+                    existing.violations.extend(result.violations);
+                    existing.errors.extend(result.errors);
+                    existing.execution_error =
+                        existing.execution_error.take().or(result.execution_error);
+                    existing.output = match (existing.output.take(), result.output) {
+                        (Some(mut a), Some(b)) => {
+                            a.extend(b);
+                            Some(a)
+                        }
+                        (a, b) => a.or(b),
+                    };
+                    existing.execution_time_ms += result.execution_time_ms;
+                    existing.parsing_time_ms += result.parsing_time_ms;
+                    existing.query_node_time_ms += result.query_node_time_ms;
+                    existing.degraded |= result.degraded;
+                    existing.profile = match (existing.profile.take(), result.profile) {
+                        (Some(a), Some(b)) => Some(RuleProfile {
+                            heap_allocated_bytes: a.heap_allocated_bytes + b.heap_allocated_bytes,
+                            cpu_time_ms: a.cpu_time_ms + b.cpu_time_ms,
+                            wall_time_ms: a.wall_time_ms + b.wall_time_ms,
+                            captures: a.captures + b.captures,
+                        }),
+                        (a, b) => a.or(b),
+                    };
+                }
+                None => merged.push(result),
+            }
+        }
+    }
+    if analysis_option.column_unit != ColumnUnit::Bytes {
+        for rule_result in &mut merged {
+            convert_columns(rule_result, &line_index, analysis_option.column_unit);
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::tree_sitter::get_query;
+    use crate::model::common::ColumnUnit;
+    use crate::model::rule::{RuleCategory, RuleSeverity};
+    use crate::model::violation::Violation;
+    use std::collections::HashSet;
+
+    const VUE_COMPONENT: &str = r#"<template>
+  <div>
+    <template v-if="ok">{{ message }}</template>
+  </div>
+</template>
+
+<script setup lang="ts">
+const message: string = "hello";
+eval(message);
+</script>
+<script>export default { name: "Foo" }; eval("x");</script>
+"#;
+
+    #[test]
+    fn test_extract_sfc_scripts() {
+        let scripts = extract_sfc_scripts(VUE_COMPONENT);
+        assert_eq!(scripts.len(), 2);
+
+        assert_eq!(scripts[0].language, Language::TypeScript);
+        assert_eq!(scripts[0].start.line, 7);
+        assert!(scripts[0].content.contains("eval(message)"));
+
+        assert_eq!(scripts[1].language, Language::JavaScript);
+        assert_eq!(scripts[1].start.line, 11);
+        assert_eq!(scripts[1].start.col, 9);
+
+        // the columns count bytes, like the columns of tree-sitter
+        let scripts = extract_sfc_scripts("<!-- é --><script>eval(x);</script>");
+        assert_eq!(scripts[0].start.col, 20);
+    }
+
+    #[test]
+    fn test_is_sfc_file() {
+        assert!(is_sfc_file("src/App.vue"));
+        assert!(is_sfc_file("src/routes/+page.svelte"));
+        assert!(!is_sfc_file("src/main.ts"));
+    }
+
+    #[test]
+    fn test_analyze_sfc_remaps_positions() {
+        let rule_code = r#"
+function visit(node, filename, code) {
+    const n = node.captures["call"];
+    addError(buildError(n.start.line, n.start.col, n.end.line, n.end.col, "eval", "CRITICAL", "security"));
+}
+        "#;
+        let query = r#"(call_expression function: (identifier) @name (#eq? @name "eval")) @call"#;
+        let analysis_options = AnalysisOptions {
+            log_output: true,
+            use_debug: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        for (language, expected) in [
+            (Language::TypeScript, Position { line: 9, col: 1 }),
+            (Language::JavaScript, Position { line: 11, col: 41 }),
+        ] {
+            let rule = RuleInternal {
+                name: "myrule".to_string(),
+                short_description: None,
+                description: None,
+                category: RuleCategory::Security,
+                severity: RuleSeverity::Error,
+                language,
+                code: rule_code.to_string(),
+                tree_sitter_query: get_query(query, &language).unwrap(),
+                named_queries: vec![],
+                allow_side_effects: false,
+                prerequisites: Default::default(),
+            };
+            let results = analyze_sfc(
+                &language,
+                [&rule],
+                "App.vue",
+                VUE_COMPONENT,
+                &ArgumentProvider::new(),
+                &analysis_options,
+            );
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].violations.len(), 1);
+            let start = &results[0].violations[0].start;
+            assert_eq!((start.line, start.col), (expected.line, expected.col));
+        }
+    }
+
+    /// The related locations and the offsets are remapped to the component, and the columns are
+    /// converted to the unit of the analysis after they're shifted to the component.
+    #[test]
+    fn test_analyze_sfc_remaps_all_positions() {
+        let rule_code = r#"
+function visit(node, filename, code) {
+    const n = node.captures["call"];
+    const name = node.captures["name"];
+    addError(
+        buildError(n.start.line, n.start.col, n.end.line, n.end.col, "eval", "CRITICAL", "security")
+            .addRelatedLocation(name.start.line, name.start.col, name.end.line, name.end.col, "name")
+    );
+}
+        "#;
+        let query = r#"(call_expression function: (identifier) @name (#eq? @name "eval")) @call"#;
+        let component = "<template>é</template>\n<!-- é --><script>eval(x);\neval(y);</script>\n";
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::Security,
+            severity: RuleSeverity::Error,
+            language: Language::JavaScript,
+            code: rule_code.to_string(),
+            tree_sitter_query: get_query(query, &Language::JavaScript).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let analyze = |column_unit: ColumnUnit| {
+            let analysis_options = AnalysisOptions {
+                log_output: true,
+                use_debug: false,
+                ignore_generated_files: false,
+                report_parse_errors: false,
+                column_unit,
+                generated_files_rules: HashSet::new(),
+                profile_rules: false,
+                nondeterministic_rules: false,
+            };
+            let mut results = analyze_sfc(
+                &Language::JavaScript,
+                [&rule],
+                "App.vue",
+                component,
+                &ArgumentProvider::new(),
+                &analysis_options,
+            );
+            assert_eq!(results.len(), 1);
+            results.remove(0).violations
+        };
+        let columns = |violation: &Violation| {
+            let related = &violation.related_locations[0];
+            (
+                (violation.start.line, violation.start.col),
+                (violation.end.col, related.start.col, related.end.col),
+            )
+        };
+
+        let violations = analyze(ColumnUnit::Bytes);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(columns(&violations[0]), ((2, 20), (27, 20, 24)));
+        assert_eq!(columns(&violations[1]), ((3, 1), (8, 1, 5)));
+        for (violation, text) in violations.iter().zip(["eval(x)", "eval(y)"]) {
+            let offsets = violation.offsets.as_ref().unwrap();
+            assert_eq!(&component[offsets.start_byte..offsets.end_byte], text);
+        }
+        assert_eq!(violations[0].offsets.as_ref().unwrap().start_utf16_col, 18);
+
+        // "é" is 2 bytes and 1 UTF-16 code unit
+        let violations = analyze(ColumnUnit::Utf16);
+        assert_eq!(columns(&violations[0]), ((2, 19), (26, 19, 23)));
+        assert_eq!(columns(&violations[1]), ((3, 1), (8, 1, 5)));
+        let offsets = violations[0].offsets.as_ref().unwrap();
+        assert_eq!(&component[offsets.start_byte..offsets.end_byte], "eval(x)");
+    }
+
+    /// The result of a rule is degraded if one of the scripts has syntax errors, and the profiles
+    /// of the executions of the rule on the scripts are added up.
+    #[test]
+    fn test_analyze_sfc_merges_degraded_and_profile() {
+        let rule_code = r#"
+function visit(node, filename, code) {
+    const n = node.captures["call"];
+    addError(buildError(n.start.line, n.start.col, n.end.line, n.end.col, "eval", "CRITICAL", "security"));
+}
+        "#;
+        let query = r#"(call_expression function: (identifier) @name (#eq? @name "eval")) @call"#;
+        let component = "<script>eval(x);</script>\n<script>eval(y);\nfoo(;\n</script>\n";
+        let rule = RuleInternal {
+            name: "myrule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::Security,
+            severity: RuleSeverity::Error,
+            language: Language::JavaScript,
+            code: rule_code.to_string(),
+            tree_sitter_query: get_query(query, &Language::JavaScript).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        };
+        let analysis_options = AnalysisOptions {
+            log_output: false,
+            use_debug: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: ColumnUnit::Bytes,
+            generated_files_rules: HashSet::new(),
+            profile_rules: true,
+            nondeterministic_rules: false,
+        };
+        let results = analyze_sfc(
+            &Language::JavaScript,
+            [&rule],
+            "App.vue",
+            component,
+            &ArgumentProvider::new(),
+            &analysis_options,
+        );
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].violations.len(), 2);
+        assert!(results[0].degraded);
+        // each match captures the call and the name, in `captures` and in `captures_list`
+        assert_eq!(results[0].profile.unwrap().captures, 8);
+    }
+}
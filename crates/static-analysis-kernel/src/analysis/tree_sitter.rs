@@ -1,26 +1,37 @@
+use crate::analysis::cancellation::CancellationToken;
+use crate::analysis::parser_pool::with_parser;
 use crate::model::analysis::{MatchNode, MatchNodeContext, TreeSitterNode};
 use crate::model::common::{Language, Position};
 use anyhow::Result;
 use indexmap::IndexMap;
+use serde::Serialize;
+use sha2::Digest;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
-use tree_sitter::CaptureQuantifier;
+use tree_sitter::{CaptureQuantifier, QueryErrorKind};
 
 pub fn get_tree_sitter_language(language: &Language) -> tree_sitter::Language {
     extern "C" {
         fn tree_sitter_c_sharp() -> tree_sitter::Language;
         fn tree_sitter_dockerfile() -> tree_sitter::Language;
         fn tree_sitter_go() -> tree_sitter::Language;
+        fn tree_sitter_graphql() -> tree_sitter::Language;
+        fn tree_sitter_groovy() -> tree_sitter::Language;
         fn tree_sitter_java() -> tree_sitter::Language;
         fn tree_sitter_javascript() -> tree_sitter::Language;
         fn tree_sitter_json() -> tree_sitter::Language;
         fn tree_sitter_kotlin() -> tree_sitter::Language;
+        fn tree_sitter_objc() -> tree_sitter::Language;
+        fn tree_sitter_proto() -> tree_sitter::Language;
         fn tree_sitter_python() -> tree_sitter::Language;
         fn tree_sitter_ruby() -> tree_sitter::Language;
         fn tree_sitter_rust() -> tree_sitter::Language;
         fn tree_sitter_swift() -> tree_sitter::Language;
         fn tree_sitter_tsx() -> tree_sitter::Language;
         fn tree_sitter_hcl() -> tree_sitter::Language;
+        fn tree_sitter_xml() -> tree_sitter::Language;
         fn tree_sitter_yaml() -> tree_sitter::Language;
 
     }
@@ -29,9 +40,13 @@ pub fn get_tree_sitter_language(language: &Language) -> tree_sitter::Language {
         Language::Csharp => unsafe { tree_sitter_c_sharp() },
         Language::Dockerfile => unsafe { tree_sitter_dockerfile() },
         Language::Go => unsafe { tree_sitter_go() },
+        Language::GraphQL => unsafe { tree_sitter_graphql() },
+        Language::Groovy => unsafe { tree_sitter_groovy() },
         Language::Java => unsafe { tree_sitter_java() },
         Language::JavaScript => unsafe { tree_sitter_javascript() },
         Language::Kotlin => unsafe { tree_sitter_kotlin() },
+        Language::ObjectiveC => unsafe { tree_sitter_objc() },
+        Language::Proto => unsafe { tree_sitter_proto() },
         Language::Json => unsafe { tree_sitter_json() },
         Language::Python => unsafe { tree_sitter_python() },
         Language::Ruby => unsafe { tree_sitter_ruby() },
@@ -39,24 +54,171 @@ pub fn get_tree_sitter_language(language: &Language) -> tree_sitter::Language {
         Language::Swift => unsafe { tree_sitter_swift() },
         Language::Terraform => unsafe { tree_sitter_hcl() },
         Language::TypeScript => unsafe { tree_sitter_tsx() },
+        Language::Xml => unsafe { tree_sitter_xml() },
         Language::Yaml => unsafe { tree_sitter_yaml() },
     }
 }
 
+/// The tree-sitter grammars compiled in the kernel, as (name, repository, revision).
+const GRAMMARS: &[(&str, &str, &str)] = include!(concat!(env!("OUT_DIR"), "/grammars.rs"));
+
+/// The version of the tree-sitter grammar of a language, as compiled in the kernel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct GrammarVersion {
+    pub name: &'static str,
+    pub repository: &'static str,
+    /// The commit the grammar was built from (or the branch, for the grammars that are not pinned).
+    pub revision: &'static str,
+    /// The ABI version of the generated parser.
+    pub abi_version: usize,
+}
+
+impl fmt::Display for GrammarVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // commits are shortened, like git does
+        let is_commit =
+            self.revision.len() == 40 && self.revision.chars().all(|c| c.is_ascii_hexdigit());
+        if is_commit {
+            write!(f, "{}@{}", self.name, &self.revision[..12])
+        } else {
+            write!(f, "{}@{}", self.name, self.revision)
+        }
+    }
+}
+
+pub fn get_grammar_version(language: &Language) -> GrammarVersion {
+    let name = match language {
+        Language::Csharp => "tree-sitter-c-sharp",
+        Language::Dockerfile => "tree-sitter-dockerfile",
+        Language::Go => "tree-sitter-go",
+        Language::GraphQL => "tree-sitter-graphql",
+        Language::Groovy => "tree-sitter-groovy",
+        Language::Java => "tree-sitter-java",
+        Language::JavaScript => "tree-sitter-javascript",
+        Language::Kotlin => "tree-sitter-kotlin",
+        Language::ObjectiveC => "tree-sitter-objc",
+        Language::Proto => "tree-sitter-proto",
+        Language::Json => "tree-sitter-json",
+        Language::Python => "tree-sitter-python",
+        Language::Ruby => "tree-sitter-ruby",
+        Language::Rust => "tree-sitter-rust",
+        Language::Swift => "tree-sitter-swift",
+        Language::Terraform => "tree-sitter-hcl",
+        Language::TypeScript => "tree-sitter-typescript",
+        Language::Xml => "tree-sitter-xml",
+        Language::Yaml => "tree-sitter-yaml",
+    };
+    let (name, repository, revision) = GRAMMARS
+        .iter()
+        .find(|(grammar, _, _)| *grammar == name)
+        .copied()
+        .expect("grammar should be compiled in the kernel");
+    GrammarVersion {
+        name,
+        repository,
+        revision,
+        abi_version: get_tree_sitter_language(language).version(),
+    }
+}
+
+/// The error of a query that does not compile with the grammar of its language because it uses
+/// a node kind, a field or a structure the grammar doesn't have: the query was written for
+/// another version of the grammar.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct IncompatibleQueryError {
+    /// The name of the query, for the named queries of a rule.
+    pub query_name: Option<String>,
+    pub language: Language,
+    pub grammar: GrammarVersion,
+    /// The node kind that is not in the grammar, if the query failed on a node kind.
+    pub node_kind: Option<String>,
+    /// The field that is not in the grammar, if the query failed on a field.
+    pub field: Option<String>,
+    /// The position of the error in the query (1-based).
+    pub line: usize,
+    pub col: usize,
+}
+
+impl IncompatibleQueryError {
+    /// Returns the error if the query error comes from the grammar, and `None` if the query is
+    /// invalid regardless of the grammar (e.g. a syntax error).
+    pub fn from_query_error(
+        language: &Language,
+        error: &tree_sitter::QueryError,
+    ) -> Option<IncompatibleQueryError> {
+        let (node_kind, field) = match error.kind {
+            QueryErrorKind::NodeType => (Some(error.message.clone()), None),
+            QueryErrorKind::Field => (None, Some(error.message.clone())),
+            QueryErrorKind::Structure => (None, None),
+            _ => return None,
+        };
+        Some(IncompatibleQueryError {
+            query_name: None,
+            language: *language,
+            grammar: get_grammar_version(language),
+            node_kind,
+            field,
+            line: error.row + 1,
+            col: error.column + 1,
+        })
+    }
+}
+
+impl fmt::Display for IncompatibleQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.query_name {
+            Some(name) => write!(f, "the query `{name}`")?,
+            None => write!(f, "the query")?,
+        }
+        write!(
+            f,
+            " is incompatible with the {} grammar ({}): ",
+            self.language, self.grammar
+        )?;
+        match (&self.node_kind, &self.field) {
+            (Some(node_kind), _) => write!(f, "unknown node kind `{node_kind}`")?,
+            (None, Some(field)) => write!(f, "unknown field `{field}`")?,
+            (None, None) => write!(f, "impossible pattern")?,
+        }
+        write!(f, " at {}:{}", self.line, self.col)
+    }
+}
+
+impl std::error::Error for IncompatibleQueryError {}
+
 // get the tree-sitter tree
 pub fn get_tree(code: &str, language: &Language) -> Option<tree_sitter::Tree> {
-    let mut tree_sitter_parser = tree_sitter::Parser::new();
-    let tree_sitter_language = get_tree_sitter_language(language);
-    tree_sitter_parser
-        .set_language(&tree_sitter_language)
-        .ok()?;
-    tree_sitter_parser.parse(code, None)
+    get_tree_with(code, language, &CancellationToken::new())
+}
+
+/// Gets the tree-sitter tree, stopping the parsing if the token is cancelled. Returns `None`
+/// if the code could not be parsed, or if the parsing was cancelled.
+pub fn get_tree_with(
+    code: &str,
+    language: &Language,
+    cancellation: &CancellationToken,
+) -> Option<tree_sitter::Tree> {
+    with_parser(language, |parser| {
+        // SAFETY: The flag outlives its use by the parser: it's removed before the parser is
+        // returned to the pool.
+        unsafe { parser.set_cancellation_flag(Some(cancellation.flag().as_ref())) };
+        let tree = parser.parse(code, None);
+        unsafe { parser.set_cancellation_flag(None) };
+        tree
+    })
+    .flatten()
 }
 
-// build the query from tree-sitter
+// build the query from tree-sitter. The errors that come from the grammar are returned as an
+// `IncompatibleQueryError`.
 pub fn get_query(query_code: &str, language: &Language) -> Result<TSQuery> {
     let tree_sitter_language = get_tree_sitter_language(language);
-    TSQuery::try_new(&tree_sitter_language, query_code).map_err(anyhow::Error::new)
+    TSQuery::try_new(&tree_sitter_language, query_code).map_err(|err| {
+        match IncompatibleQueryError::from_query_error(language, &err) {
+            Some(incompatible) => anyhow::Error::new(incompatible),
+            None => anyhow::Error::new(err),
+        }
+    })
 }
 
 /// A wrapper around a [`tree_sitter::Query`].
@@ -64,6 +226,7 @@ pub fn get_query(query_code: &str, language: &Language) -> Result<TSQuery> {
 pub struct TSQuery {
     query: tree_sitter::Query,
     capture_names: Vec<Arc<str>>,
+    digest: Option<Arc<str>>,
 }
 
 impl TSQuery {
@@ -73,12 +236,20 @@ impl TSQuery {
     ) -> std::result::Result<Self, tree_sitter::QueryError> {
         let query = tree_sitter::Query::new(language, source)?;
         let capture_names = Self::build_cache(&query);
+        let digest = format!("{:x}", sha2::Sha256::digest(source.as_bytes()));
         Ok(Self {
             query,
             capture_names,
+            digest: Some(Arc::from(digest)),
         })
     }
 
+    /// Returns the SHA-256 of the source of the query, if it was compiled from its source: two
+    /// queries of a language with the same digest have the same matches.
+    pub fn digest(&self) -> Option<&str> {
+        self.digest.as_deref()
+    }
+
     /// Returns a [`TSQueryCursor`] bound to the provided cursor.
     pub fn with_cursor<'a>(&'a self, cursor: &'a mut tree_sitter::QueryCursor) -> TSQueryCursor {
         TSQueryCursor {
@@ -103,6 +274,11 @@ impl TSQuery {
         }
     }
 
+    /// Returns the names of the captures of the query.
+    pub fn capture_names(&self) -> &[Arc<str>] {
+        &self.capture_names
+    }
+
     /// Generates a cache of the capture names as an [`Arc<str>`].
     fn build_cache(query: &tree_sitter::Query) -> Vec<Arc<str>> {
         query
@@ -119,6 +295,7 @@ impl From<tree_sitter::Query> for TSQuery {
         Self {
             query: value,
             capture_names,
+            digest: None,
         }
     }
 }
@@ -251,45 +428,76 @@ pub enum TSCaptureContent<T> {
 // the values of the captures for the match.
 //
 // Note that we also add the context to the node that consists of the code and variables.
+// The code and the filename are shared by all the matches: pass an `Arc<str>` to share them with
+// the caller as well.
 pub fn get_query_nodes(
     tree: &tree_sitter::Tree,
     query: &TSQuery,
-    filename: &str,
-    code: &str,
+    filename: impl Into<Arc<str>>,
+    code: impl Into<Arc<str>>,
+    arguments: &HashMap<String, String>,
+) -> Vec<MatchNode> {
+    get_query_nodes_interned(
+        tree,
+        query,
+        filename.into(),
+        code.into(),
+        arguments,
+        &mut HashMap::new(),
+    )
+}
+
+/// Same as [`get_query_nodes`], with the nodes already mapped (by their id in the tree) reused
+/// instead of mapped again: a node captured by several matches or queries is only mapped once.
+pub(crate) fn get_query_nodes_interned(
+    tree: &tree_sitter::Tree,
+    query: &TSQuery,
+    filename: Arc<str>,
+    code: Arc<str>,
     arguments: &HashMap<String, String>,
+    interned: &mut HashMap<usize, Option<TreeSitterNode>>,
 ) -> Vec<MatchNode> {
+    let mut map_node = |node: tree_sitter::Node| {
+        interned
+            .entry(node.id())
+            .or_insert_with(|| map_node(node))
+            .clone()
+    };
     let mut match_nodes: Vec<MatchNode> = vec![];
 
-    for query_match in query.cursor().matches(tree.root_node(), code) {
-        let mut captures: HashMap<String, TreeSitterNode> = HashMap::new();
-        let mut captures_list: HashMap<String, Vec<TreeSitterNode>> = HashMap::new();
+    for query_match in query.cursor().matches(tree.root_node(), code.as_ref()) {
+        let mut captures: HashMap<Arc<str>, TreeSitterNode> = HashMap::new();
+        let mut captures_list: HashMap<Arc<str>, Vec<TreeSitterNode>> = HashMap::new();
         for capture in query_match {
             let list = match capture.contents {
                 TSCaptureContent::Single(node) => {
                     map_node(node).map(|n| vec![n]).unwrap_or_default()
                 }
-                TSCaptureContent::Multi(nodes) => {
-                    nodes.into_iter().filter_map(map_node).collect::<Vec<_>>()
-                }
+                TSCaptureContent::Multi(nodes) => nodes
+                    .into_iter()
+                    .filter_map(&mut map_node)
+                    .collect::<Vec<_>>(),
             };
             // All captures are inserted into `captures_list`. However, the prior implementation continually
             // called `insert` on the `captures` map, which ended up re-writing the value every time.
             // Thus, to match this behavior, we take the `last` element of the list to insert into `captures`.
             if let Some(last) = list.last() {
-                captures.insert(capture.name.to_string(), last.clone());
-                captures_list.insert(capture.name.to_string(), list);
+                captures.insert(Arc::clone(&capture.name), last.clone());
+                captures_list.insert(Arc::clone(&capture.name), list);
             }
         }
 
         if !captures.is_empty() {
             match_nodes.push(MatchNode {
-                captures: captures.clone(),
-                captures_list: captures_list.clone(),
+                captures,
+                captures_list,
                 context: MatchNodeContext {
-                    code: Some(code.to_string()),
-                    filename: filename.to_string(),
+                    code: Some(Arc::clone(&code)),
+                    filename: Arc::clone(&filename),
                     arguments: arguments.clone(),
+                    editorconfig: None,
                 },
+                query_name: None,
             });
         }
     }
@@ -328,7 +536,7 @@ pub fn map_node(node: tree_sitter::Node) -> Option<TreeSitterNode> {
 
         // finally, build the return value.
         let ts_node = TreeSitterNode {
-            ast_type: cursor.node().kind().to_string(),
+            ast_type: Cow::Borrowed(cursor.node().kind()),
             start: Position {
                 line: u32::try_from(cursor.node().range().start_point.row + 1).unwrap(),
                 col: u32::try_from(cursor.node().range().start_point.column + 1).unwrap(),
@@ -337,7 +545,7 @@ pub fn map_node(node: tree_sitter::Node) -> Option<TreeSitterNode> {
                 line: u32::try_from(cursor.node().range().end_point.row + 1).unwrap(),
                 col: u32::try_from(cursor.node().range().end_point.column + 1).unwrap(),
             },
-            field_name: cursor.field_name().map(ToString::to_string),
+            field_name: cursor.field_name().map(Cow::Borrowed),
             children,
         };
 
@@ -513,6 +721,90 @@ fun main() {
         assert_eq!("source_file", t.unwrap().root_node().kind());
     }
 
+    #[test]
+    fn test_graphql_get_tree() {
+        let source_code = r#"
+type Query {
+  users(first: Int, after: String): UserConnection!
+  user(id: ID!): User @deprecated(reason: "Use `node` instead.")
+}
+
+query GetUsers {
+  users(first: 10) {
+    edges {
+      node {
+        id
+      }
+    }
+  }
+}
+"#;
+        let t = get_tree(source_code, &Language::GraphQL);
+        assert!(t.is_some());
+        assert!(!t.unwrap().root_node().has_error());
+    }
+
+    #[test]
+    fn test_groovy_get_tree() {
+        let source_code = r#"
+plugins {
+    id 'java'
+}
+
+dependencies {
+    implementation 'com.google.guava:guava:+'
+}
+"#;
+        let t = get_tree(source_code, &Language::Groovy);
+        assert!(t.is_some());
+        assert!(!t.unwrap().root_node().has_error());
+    }
+
+    #[test]
+    fn test_objective_c_get_tree() {
+        let source_code = r#"
+@interface Greeter : NSObject
+- (void)greet:(NSString *)name;
+@end
+
+@implementation Greeter
+- (void)greet:(NSString *)name {
+    NSLog(@"Hello, %@", name);
+}
+@end
+"#;
+        let t = get_tree(source_code, &Language::ObjectiveC);
+        assert!(t.is_some());
+        assert_eq!("translation_unit", t.unwrap().root_node().kind());
+    }
+
+    #[test]
+    fn test_proto_get_tree() {
+        let source_code = r#"
+syntax = "proto3";
+
+package greeter.v1;
+
+service Greeter {
+  rpc SayHello (HelloRequest) returns (HelloReply);
+}
+
+message HelloRequest {
+  reserved 2, 4 to 6;
+  string name = 1;
+}
+
+message HelloReply {
+  string message = 1;
+}
+"#;
+        let t = get_tree(source_code, &Language::Proto);
+        assert!(t.is_some());
+        let t = t.unwrap();
+        assert_eq!("source_file", t.root_node().kind());
+        assert!(!t.root_node().has_error());
+    }
+
     #[test]
     fn test_swift_get_tree() {
         let source_code = r#"
@@ -536,6 +828,23 @@ let myAdd = function (x: number, y: number): number {
         assert_eq!("program", t.unwrap().root_node().kind());
     }
 
+    #[test]
+    fn test_xml_get_tree() {
+        let source_code = r#"<?xml version="1.0" encoding="utf-8"?>
+<manifest xmlns:android="http://schemas.android.com/apk/res/android">
+    <application>
+        <!-- the main activity -->
+        <activity android:name=".MainActivity" android:exported="true" />
+    </application>
+</manifest>
+"#;
+        let t = get_tree(source_code, &Language::Xml);
+        assert!(t.is_some());
+        let t = t.unwrap();
+        assert_eq!("document", t.root_node().kind());
+        assert!(!t.root_node().has_error());
+    }
+
     #[test]
     fn test_yaml_get_tree() {
         let source_code = r#"
@@ -547,6 +856,26 @@ rulesets:
         assert_eq!("stream", t.unwrap().root_node().kind());
     }
 
+    #[test]
+    fn test_query_digest() {
+        let query = get_query("(identifier) @id", &Language::Python).unwrap();
+        assert_eq!(
+            query.digest(),
+            get_query("(identifier) @id", &Language::Python)
+                .unwrap()
+                .digest()
+        );
+        assert_ne!(
+            query.digest(),
+            get_query("(identifier) @name", &Language::Python)
+                .unwrap()
+                .digest()
+        );
+        let language = get_tree_sitter_language(&Language::Python);
+        let query = tree_sitter::Query::new(&language, "(identifier) @id").unwrap();
+        assert_eq!(TSQuery::from(query).digest(), None);
+    }
+
     // test the number of node we should retrieve when executing a rule
     #[test]
     fn test_get_query_nodes() {
@@ -587,4 +916,27 @@ rulesets:
         assert_eq!(None, superclasses.field_name);
         assert!(query_node.captures.contains_key("classname"));
     }
+
+    #[test]
+    fn test_incompatible_query() {
+        let grammar = get_grammar_version(&Language::Python);
+        assert_eq!(grammar.name, "tree-sitter-python");
+        assert!(!grammar.revision.is_empty());
+
+        let err = get_query("(call)\n(print_statement) @print", &Language::Python).unwrap_err();
+        let err = err.downcast::<IncompatibleQueryError>().unwrap();
+        assert_eq!(err.node_kind.as_deref(), Some("print_statement"));
+        assert_eq!((err.line, err.col), (2, 2));
+        assert!(err
+            .to_string()
+            .starts_with("the query is incompatible with the python grammar (tree-sitter-python@"));
+
+        let err = get_query("(call nope: (identifier)) @call", &Language::Python).unwrap_err();
+        let err = err.downcast::<IncompatibleQueryError>().unwrap();
+        assert_eq!(err.field.as_deref(), Some("nope"));
+
+        // a syntax error is not an incompatibility with the grammar
+        let err = get_query("(call", &Language::Python).unwrap_err();
+        assert!(err.downcast::<IncompatibleQueryError>().is_err());
+    }
 }
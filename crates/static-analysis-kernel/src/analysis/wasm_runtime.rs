@@ -0,0 +1,343 @@
+//! An experimental [`RuleRuntime`] that executes the rules in WebAssembly with `wasmtime` instead of
+//! `v8`, to compare the startup latency and the sandboxing of the two runtimes.
+//!
+//! The rules are executed by an engine module (e.g. a JavaScript interpreter compiled to
+//! WebAssembly, with the `ddsa` API of the rules), given when the runtime is created. The module
+//! has no imports: a rule cannot access the file system, the network or the clock, and each rule
+//! is executed in a new instance with its own memory, bounded in size and in instructions (fuel).
+//!
+//! The engine module exports:
+//! - `memory`: its memory.
+//! - `alloc(len: i32) -> i32`: allocates `len` bytes, and returns their address.
+//! - `execute(ptr: i32, len: i32) -> i64`: executes the rule with the JSON input of `len` bytes at
+//!   `ptr`, and returns the address (high 32 bits) and the length (low 32 bits) of its JSON output.
+//!
+//! The input is `{"rule": {"name", "code"}, "filename", "matches", "fileContext"}`, where the
+//! matches and the file context are serialized as they are given to `v8`. The output is
+//! `{"violations": [...], "console": [...]}`, or `{"error": "..."}` when the rule fails.
+
+use crate::analysis::cancellation::CancellationToken;
+use crate::analysis::file_context::common::FileContext;
+use crate::analysis::rule_runtime::RuleRuntime;
+use crate::model::analysis::{
+    AnalysisOptions, MatchNode, ERROR_RULE_EXECUTION, ERROR_RULE_TIMEOUT,
+};
+use crate::model::rule::{ConsoleLevel, ConsoleRecord, RuleInternal, RuleResult};
+use crate::model::violation::Violation;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+
+/// The default number of instructions a rule may execute on a file before it's halted, which is
+/// the equivalent of the timeout of the rules in `v8`: unlike a timeout, it doesn't depend on the
+/// load of the machine.
+const DEFAULT_RULE_FUEL: u64 = 5_000_000_000;
+
+/// The maximum size of the memory of an instance.
+const MAX_MEMORY_BYTES: usize = 256 * 1024 * 1024;
+
+#[derive(Serialize)]
+struct WasmRule<'a> {
+    name: &'a str,
+    code: &'a str,
+}
+
+#[derive(Serialize)]
+struct WasmInput<'a> {
+    rule: WasmRule<'a>,
+    filename: &'a str,
+    matches: &'a [MatchNode],
+    #[serde(rename = "fileContext")]
+    file_context: &'a FileContext,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WasmOutput {
+    Violations {
+        violations: Vec<Violation>,
+        #[serde(default)]
+        console: Vec<String>,
+    },
+    Error {
+        error: String,
+    },
+}
+
+/// Why the execution of a rule failed.
+enum WasmError {
+    /// The rule ran out of fuel.
+    Timeout,
+    Execution(String),
+}
+
+pub struct WasmRuleRuntime {
+    engine: Engine,
+    module: Module,
+    compile_time: Duration,
+    rule_fuel: u64,
+}
+
+impl WasmRuleRuntime {
+    /// Compiles the engine module at `path`.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        Self::new(&std::fs::read(path)?)
+    }
+
+    /// Compiles the engine module, in the binary or the text format.
+    pub fn new(module: &[u8]) -> anyhow::Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let compile_time = Instant::now();
+        let module = Module::new(&engine, module)?;
+        Ok(Self {
+            engine,
+            module,
+            compile_time: compile_time.elapsed(),
+            rule_fuel: DEFAULT_RULE_FUEL,
+        })
+    }
+
+    /// Sets the number of instructions a rule may execute on a file.
+    pub fn with_rule_fuel(mut self, rule_fuel: u64) -> Self {
+        self.rule_fuel = rule_fuel;
+        self
+    }
+
+    /// Returns the time spent compiling the engine module, which is the startup latency of the
+    /// runtime (the equivalent of the creation of the `v8` isolates).
+    pub fn compile_time(&self) -> Duration {
+        self.compile_time
+    }
+
+    /// Executes a rule in a new instance of the engine module.
+    fn execute_rule(&self, input: &[u8]) -> Result<WasmOutput, WasmError> {
+        let execution = || -> anyhow::Result<Vec<u8>> {
+            let limits = StoreLimitsBuilder::new()
+                .memory_size(MAX_MEMORY_BYTES)
+                .build();
+            let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+            store.limiter(|limits| limits);
+            store.set_fuel(self.rule_fuel)?;
+            // the module has no imports: it can only compute
+            let instance = Instance::new(&mut store, &self.module, &[])?;
+            let memory = instance
+                .get_memory(&mut store, "memory")
+                .ok_or_else(|| anyhow::anyhow!("the engine module exports no memory"))?;
+            let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+            let execute = instance.get_typed_func::<(i32, i32), i64>(&mut store, "execute")?;
+
+            let len = i32::try_from(input.len())?;
+            let ptr = alloc.call(&mut store, len)?;
+            memory.write(&mut store, ptr as u32 as usize, input)?;
+            let output = execute.call(&mut store, (ptr, len))? as u64;
+            let (output_ptr, output_len) = ((output >> 32) as usize, output as u32 as usize);
+            // the output is checked to be in the memory of the instance before it's allocated
+            let in_memory = output_ptr
+                .checked_add(output_len)
+                .is_some_and(|end| end <= memory.data_size(&store) && end <= MAX_MEMORY_BYTES);
+            if !in_memory {
+                anyhow::bail!("the output of the rule ({output_len} bytes) is out of its memory");
+            }
+            let mut bytes = vec![0; output_len];
+            memory.read(&store, output_ptr, &mut bytes)?;
+            Ok(bytes)
+        };
+        let bytes = execution().map_err(|err| match err.downcast_ref::<Trap>() {
+            Some(Trap::OutOfFuel) => WasmError::Timeout,
+            _ => WasmError::Execution(format!("{err:#}")),
+        })?;
+        serde_json::from_slice(&bytes)
+            .map_err(|err| WasmError::Execution(format!("invalid output of the rule: {err}")))
+    }
+}
+
+impl RuleRuntime for WasmRuleRuntime {
+    fn name(&self) -> &'static str {
+        "wasm"
+    }
+
+    fn execute_rules(
+        &self,
+        rules: Vec<(&RuleInternal, Vec<MatchNode>)>,
+        filename: &str,
+        analysis_options: &AnalysisOptions,
+        file_context: &FileContext,
+        cancellation: &CancellationToken,
+    ) -> Vec<RuleResult> {
+        rules
+            .into_iter()
+            .map(|(rule, matches)| {
+                if cancellation.is_cancelled() {
+                    return RuleResult::cancelled(&rule.name, filename);
+                }
+                let execution_time = Instant::now();
+                let input = serde_json::to_vec(&WasmInput {
+                    rule: WasmRule {
+                        name: &rule.name,
+                        code: &rule.code,
+                    },
+                    filename,
+                    matches: &matches,
+                    file_context,
+                })
+                .expect("the input of the rule is serializable");
+                let (violations, errors, execution_error, output) = match self.execute_rule(&input)
+                {
+                    Ok(WasmOutput::Violations {
+                        violations,
+                        console,
+                    }) => {
+                        let output =
+                            (!console.is_empty() && analysis_options.log_output).then(|| {
+                                console
+                                    .into_iter()
+                                    .map(|line| ConsoleRecord::new(ConsoleLevel::Log, line))
+                                    .collect()
+                            });
+                        (violations, vec![], None, output)
+                    }
+                    Ok(WasmOutput::Error { error }) | Err(WasmError::Execution(error)) => {
+                        if analysis_options.use_debug {
+                            eprintln!(
+                                "rule:file {}:{} execution error, message: {}",
+                                rule.name, filename, error
+                            );
+                        }
+                        (
+                            vec![],
+                            vec![ERROR_RULE_EXECUTION.to_string()],
+                            Some(error),
+                            None,
+                        )
+                    }
+                    Err(WasmError::Timeout) => {
+                        if analysis_options.use_debug {
+                            eprintln!("rule:file {}:{} TIMED OUT", rule.name, filename);
+                        }
+                        (vec![], vec![ERROR_RULE_TIMEOUT.to_string()], None, None)
+                    }
+                };
+                RuleResult {
+                    rule_name: rule.name.as_str().into(),
+                    filename: filename.into(),
+                    violations,
+                    errors,
+                    execution_error,
+                    output,
+                    execution_time_ms: execution_time.elapsed().as_millis(),
+                    parsing_time_ms: 0,
+                    query_node_time_ms: 0,
+                    degraded: false,
+                    profile: None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::file_context::common::get_empty_file_context;
+    use crate::analysis::tree_sitter::get_query;
+    use crate::model::common::Language;
+    use crate::model::rule::{RuleCategory, RuleSeverity};
+
+    /// An engine that reports no violation, or loops forever when the input is longer than 1000
+    /// bytes.
+    const ENGINE: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+  (data (i32.const 0) "{\"violations\": []}")
+  (func (export "execute") (param i32 i32) (result i64)
+    (if (i32.gt_u (local.get 1) (i32.const 1000))
+      (then (loop $forever (br $forever))))
+    (i64.const 18)))
+"#;
+
+    fn rule(code: &str) -> RuleInternal {
+        RuleInternal {
+            name: "ruleset/rule".to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::BestPractices,
+            severity: RuleSeverity::Warning,
+            language: Language::Python,
+            code: code.to_string(),
+            tree_sitter_query: get_query("(identifier) @id", &Language::Python).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        }
+    }
+
+    #[test]
+    fn execute_rules() {
+        let runtime = WasmRuleRuntime::new(ENGINE.as_bytes())
+            .unwrap()
+            .with_rule_fuel(1_000_000);
+        let options = AnalysisOptions {
+            log_output: false,
+            use_debug: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: Default::default(),
+            generated_files_rules: Default::default(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let (short, long) = (rule("function visit() {}"), rule(&"x".repeat(2000)));
+        let results = runtime.execute_rules(
+            vec![(&short, vec![]), (&long, vec![])],
+            "a.py",
+            &options,
+            &get_empty_file_context(),
+            &CancellationToken::new(),
+        );
+        assert!(results[0].errors.is_empty());
+        assert!(results[0].violations.is_empty());
+        // the rule that never returns runs out of fuel
+        assert_eq!(results[1].errors, vec![ERROR_RULE_TIMEOUT.to_string()]);
+    }
+
+    /// An output that is not in the memory of the instance is an execution error, and is not
+    /// allocated.
+    #[test]
+    fn output_out_of_memory() {
+        let engine = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+  (func (export "execute") (param i32 i32) (result i64)
+    (i64.const 0xffffffff)))
+"#;
+        let runtime = WasmRuleRuntime::new(engine.as_bytes()).unwrap();
+        let options = AnalysisOptions {
+            log_output: false,
+            use_debug: false,
+            ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: Default::default(),
+            generated_files_rules: Default::default(),
+            profile_rules: false,
+            nondeterministic_rules: false,
+        };
+        let rule = rule("function visit() {}");
+        let results = runtime.execute_rules(
+            vec![(&rule, vec![])],
+            "a.py",
+            &options,
+            &get_empty_file_context(),
+            &CancellationToken::new(),
+        );
+        assert_eq!(results[0].errors, vec![ERROR_RULE_EXECUTION.to_string()]);
+        assert!(results[0]
+            .execution_error
+            .as_ref()
+            .is_some_and(|error| error.contains("out of its memory")));
+    }
+}
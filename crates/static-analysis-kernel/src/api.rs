@@ -0,0 +1,277 @@
+//! The supported API to embed the kernel: an [`AnalysisRequest`] describes the analysis of a file
+//! (its content, the rules and the options), and [`analyze_file`] executes it and returns an
+//! [`AnalysisReport`].
+//!
+//! The functions of [`crate::analysis`] are the internals of the analyzer, and change with it.
+//! This API is the one kept stable for the embedders (the server of the IDEs, the C bindings,
+//! ...):
+//!
+//! ```ignore
+//! let report = analyze_file(
+//!     &AnalysisRequest::new("src/main.py", code)
+//!         .rules(&rules)
+//!         .options(AnalysisOptions {
+//!             report_parse_errors: true,
+//!             ..Default::default()
+//!         }),
+//! )?;
+//! for violation in report.violations() {
+//!     println!("{}:{}", violation.start.line, violation.message);
+//! }
+//! ```
+
+use crate::analysis::analyze::analyze_with_runtime;
+use crate::analysis::cancellation::CancellationToken;
+use crate::analysis::rule_runtime::{rule_runtime, RuleRuntime};
+use crate::analysis::sfc::{analyze_sfc_with_runtime, is_sfc_file};
+use crate::arguments::ArgumentProvider;
+use crate::model::analysis::AnalysisOptions;
+use crate::model::common::Language;
+use crate::model::rule::{RuleInternal, RuleResult};
+use crate::model::violation::Violation;
+use std::sync::Arc;
+
+/// An error that prevents the analysis of a file.
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum AnalysisError {
+    #[error("the language of {0} is unknown: set the language of the request")]
+    UnknownLanguage(String),
+}
+
+/// The analysis of a file, built from its path and content with [`AnalysisRequest::new`].
+pub struct AnalysisRequest<'a> {
+    filename: &'a str,
+    code: Arc<str>,
+    language: Option<Language>,
+    rules: Vec<&'a RuleInternal>,
+    options: AnalysisOptions,
+    argument_provider: Option<&'a ArgumentProvider>,
+    cancellation: Option<&'a CancellationToken>,
+    runtime: Option<&'a dyn RuleRuntime>,
+}
+
+impl<'a> AnalysisRequest<'a> {
+    /// Creates the analysis of a file, with its path relative to the root of the repository. The
+    /// code is shared with the matches passed to the rules: pass an `Arc<str>` to share it with
+    /// the caller instead of copying it.
+    pub fn new(filename: &'a str, code: impl Into<Arc<str>>) -> Self {
+        Self {
+            filename,
+            code: code.into(),
+            language: None,
+            rules: vec![],
+            options: AnalysisOptions::default(),
+            argument_provider: None,
+            cancellation: None,
+            runtime: None,
+        }
+    }
+
+    /// Sets the language of the file. By default, it's the language of the rules.
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Adds rules to the analysis. The rules of another language than the file's are ignored.
+    pub fn rules(mut self, rules: impl IntoIterator<Item = &'a RuleInternal>) -> Self {
+        self.rules.extend(rules);
+        self
+    }
+
+    /// Sets the options of the analysis. By default, all the options are disabled.
+    pub fn options(mut self, options: AnalysisOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the arguments of the rules. By default, the rules have no arguments.
+    pub fn argument_provider(mut self, argument_provider: &'a ArgumentProvider) -> Self {
+        self.argument_provider = Some(argument_provider);
+        self
+    }
+
+    /// Sets the token that stops the analysis. The rules that were not executed once it's
+    /// cancelled are reported with [`RuleResult::cancelled`].
+    pub fn cancellation(mut self, cancellation: &'a CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Sets the runtime that executes the rules. By default, it's the runtime of the process (see
+    /// [`set_rule_runtime`](crate::analysis::rule_runtime::set_rule_runtime)).
+    pub fn runtime(mut self, runtime: &'a dyn RuleRuntime) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// Returns the language of the file: the language of the request, or the language shared
+    /// by all its rules.
+    fn resolve_language(&self) -> Result<Language, AnalysisError> {
+        if let Some(language) = self.language {
+            return Ok(language);
+        }
+        let mut languages = self.rules.iter().map(|rule| rule.language);
+        match languages.next() {
+            Some(language) if languages.all(|l| l == language) => Ok(language),
+            _ => Err(AnalysisError::UnknownLanguage(self.filename.to_string())),
+        }
+    }
+}
+
+/// The results of the analysis of a file.
+#[derive(Debug, Clone)]
+pub struct AnalysisReport {
+    /// The language the file was analyzed as.
+    pub language: Language,
+    /// The result of each rule, in the order of the rules of the request.
+    pub rule_results: Vec<RuleResult>,
+}
+
+impl AnalysisReport {
+    /// Returns the violations of all the rules.
+    pub fn violations(&self) -> impl Iterator<Item = &Violation> {
+        self.rule_results.iter().flat_map(|r| &r.violations)
+    }
+
+    /// Returns true if the analysis was cancelled before all the rules were executed.
+    pub fn is_cancelled(&self) -> bool {
+        self.rule_results.iter().any(RuleResult::is_cancelled)
+    }
+
+    /// Returns true if the file has syntax errors: the rules were executed on the tree recovered by
+    /// the parser, so the violations may be incomplete.
+    pub fn is_degraded(&self) -> bool {
+        self.rule_results.iter().any(|r| r.degraded)
+    }
+}
+
+/// Analyzes a file. The single-file components (Vue, Svelte) are analyzed script by script, with
+/// the positions of the violations relative to the component.
+pub fn analyze_file(request: &AnalysisRequest) -> Result<AnalysisReport, AnalysisError> {
+    let language = request.resolve_language()?;
+    let rules = request
+        .rules
+        .iter()
+        .copied()
+        .filter(|rule| rule.language == language);
+    let default_arguments = ArgumentProvider::new();
+    let argument_provider = request.argument_provider.unwrap_or(&default_arguments);
+    let default_cancellation = CancellationToken::new();
+    let cancellation = request.cancellation.unwrap_or(&default_cancellation);
+    // the runtime of the process is only selected when the request has no runtime
+    let runtime = match request.runtime {
+        Some(runtime) => runtime,
+        None => rule_runtime(),
+    };
+
+    let rule_results = if is_sfc_file(request.filename) {
+        analyze_sfc_with_runtime(
+            &language,
+            rules,
+            request.filename,
+            &request.code,
+            argument_provider,
+            &request.options,
+            cancellation,
+            runtime,
+        )
+    } else {
+        analyze_with_runtime(
+            &language,
+            rules,
+            request.filename,
+            Arc::clone(&request.code),
+            argument_provider,
+            &request.options,
+            cancellation,
+            runtime,
+        )
+    };
+    Ok(AnalysisReport {
+        language,
+        rule_results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::file_context::common::FileContext;
+    use crate::analysis::tree_sitter::get_query;
+    use crate::model::analysis::MatchNode;
+    use crate::model::rule::{RuleCategory, RuleSeverity};
+
+    /// A runtime that reports the number of matches of each rule as its execution error.
+    struct CountingRuntime;
+
+    impl RuleRuntime for CountingRuntime {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn execute_rules(
+            &self,
+            rules: Vec<(&RuleInternal, Vec<MatchNode>)>,
+            filename: &str,
+            _analysis_options: &AnalysisOptions,
+            _file_context: &FileContext,
+            _cancellation: &CancellationToken,
+        ) -> Vec<RuleResult> {
+            rules
+                .into_iter()
+                .map(|(rule, matches)| RuleResult {
+                    rule_name: rule.name.as_str().into(),
+                    filename: filename.into(),
+                    violations: vec![],
+                    errors: vec![],
+                    execution_error: Some(matches.len().to_string()),
+                    output: None,
+                    execution_time_ms: 0,
+                    parsing_time_ms: 0,
+                    query_node_time_ms: 0,
+                    degraded: false,
+                    profile: None,
+                })
+                .collect()
+        }
+    }
+
+    fn rule(name: &str, language: Language, query: &str) -> RuleInternal {
+        RuleInternal {
+            name: name.to_string(),
+            short_description: None,
+            description: None,
+            category: RuleCategory::BestPractices,
+            severity: RuleSeverity::Warning,
+            language,
+            code: "function visit() {}".to_string(),
+            tree_sitter_query: get_query(query, &language).unwrap(),
+            named_queries: vec![],
+            allow_side_effects: false,
+            prerequisites: Default::default(),
+        }
+    }
+
+    #[test]
+    fn analyze_file_with_runtime() {
+        let python = rule("ruleset/python", Language::Python, "(identifier) @id");
+        let go = rule("ruleset/go", Language::Go, "(identifier) @id");
+        let request = AnalysisRequest::new("a.py", "x = y")
+            .rules([&python, &go])
+            .runtime(&CountingRuntime);
+        assert_eq!(
+            analyze_file(&request).unwrap_err(),
+            AnalysisError::UnknownLanguage("a.py".to_string())
+        );
+
+        // the rules of another language are ignored
+        let report = analyze_file(&request.language(Language::Python)).unwrap();
+        assert_eq!(report.language, Language::Python);
+        assert_eq!(report.rule_results.len(), 1);
+        assert_eq!(report.rule_results[0].rule_name, "ruleset/python");
+        assert_eq!(report.rule_results[0].execution_error.as_deref(), Some("2"));
+        assert!(!report.is_cancelled());
+        assert_eq!(report.violations().count(), 0);
+    }
+}
@@ -1,43 +1,77 @@
-use crate::model::config_file::{BySubtree, ConfigFile, SplitPath};
+use crate::editorconfig::{EditorConfig, EditorConfigProperties};
+use crate::model::config_file::{
+    join_path, ArgumentValues, BySubtree, ConfigFile, PathPattern, SplitPath,
+};
+use indexmap::IndexMap;
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
 type Argument = (String, String);
 
 #[derive(Clone)]
 // Used to extract rule arguments in the analyzer.
 pub struct ArgumentProvider {
-    by_rule: HashMap<String, BySubtree<Vec<Argument>>>,
+    // The arguments by scope: a rule name, a ruleset name, or "" for all the rules.
+    by_scope: HashMap<String, ScopedArguments>,
+    // The settings of the `.editorconfig` files, given to the rules with their arguments.
+    editorconfig: EditorConfig,
+}
+
+// The arguments of a scope, by path prefix and by glob pattern.
+#[derive(Clone, Default)]
+struct ScopedArguments {
+    by_subtree: BySubtree<Vec<Argument>>,
+    by_glob: Vec<(PathPattern, Argument)>,
 }
 
 impl ArgumentProvider {
     pub fn new() -> ArgumentProvider {
         ArgumentProvider {
-            by_rule: HashMap::new(),
+            by_scope: HashMap::new(),
+            editorconfig: EditorConfig::new(),
         }
     }
 
     pub fn from(config: &ConfigFile) -> Self {
         let mut provider = ArgumentProvider::new();
+        provider.add_arguments("", &config.arguments);
         for (ruleset_name, ruleset_cfg) in &config.rulesets {
+            provider.add_arguments(ruleset_name, &ruleset_cfg.arguments);
             for (rule_shortname, rule_cfg) in &ruleset_cfg.rules {
-                for (arg_name, arg_values) in &rule_cfg.arguments {
-                    let rule_name = format!("{}/{}", ruleset_name, rule_shortname);
-                    for (prefix, value) in arg_values.iter() {
-                        provider.add_argument(
-                            &rule_name,
-                            &prefix.into_iter().cloned().collect(),
-                            arg_name,
-                            value,
-                        );
-                    }
-                }
+                let rule_name = format!("{}/{}", ruleset_name, rule_shortname);
+                provider.add_arguments(&rule_name, &rule_cfg.arguments);
             }
         }
         provider
     }
 
-    pub fn add_argument(&mut self, rule_name: &str, path: &SplitPath, argument: &str, value: &str) {
-        let by_subtree = self.by_rule.entry(rule_name.to_string()).or_default();
+    fn add_arguments(&mut self, scope: &str, arguments: &IndexMap<String, ArgumentValues>) {
+        for (arg_name, arg_values) in arguments {
+            for (prefix, value) in arg_values.by_subtree.iter() {
+                self.add_argument(
+                    scope,
+                    &prefix.into_iter().cloned().collect(),
+                    arg_name,
+                    value,
+                );
+            }
+            for (pattern, value) in &arg_values.by_glob {
+                self.add_argument_for_pattern(scope, pattern.clone(), arg_name, value);
+            }
+        }
+    }
+
+    /// Adds an argument for the files in a directory. The scope is the name of a rule, the name
+    /// of a ruleset for all its rules, or an empty string for all the rules.
+    pub fn add_argument(&mut self, scope: &str, path: &SplitPath, argument: &str, value: &str) {
+        let by_subtree = &mut self
+            .by_scope
+            .entry(scope.to_string())
+            .or_default()
+            .by_subtree;
         match by_subtree.get_mut(path) {
             None => {
                 by_subtree.insert(path, vec![(argument.to_string(), value.to_string())]);
@@ -48,21 +82,108 @@ impl ArgumentProvider {
         };
     }
 
+    /// Adds an argument for the files that match a glob pattern, in the same scopes as
+    /// [`ArgumentProvider::add_argument`].
+    pub fn add_argument_for_pattern(
+        &mut self,
+        scope: &str,
+        pattern: PathPattern,
+        argument: &str,
+        value: &str,
+    ) {
+        self.by_scope
+            .entry(scope.to_string())
+            .or_default()
+            .by_glob
+            .push((pattern, (argument.to_string(), value.to_string())));
+    }
+
+    /// Sets the `.editorconfig` files of the repository.
+    pub fn set_editorconfig(&mut self, editorconfig: EditorConfig) {
+        self.editorconfig = editorconfig;
+    }
+
+    pub fn editorconfig(&self) -> &EditorConfig {
+        &self.editorconfig
+    }
+
+    /// Returns the `.editorconfig` settings of a file, if it has any.
+    pub fn get_editorconfig(&self, filename: &str) -> Option<Arc<EditorConfigProperties>> {
+        if self.editorconfig.is_empty() {
+            return None;
+        }
+        let properties = self.editorconfig.properties(filename);
+        (!properties.is_empty()).then(|| Arc::new(properties))
+    }
+
+    /// Returns a digest of the arguments, which change the results of the rules that use them.
+    /// The settings of the `.editorconfig` files have their own digest ([`EditorConfig::digest`]).
+    pub fn digest(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        let mut scopes = self.by_scope.iter().collect::<Vec<_>>();
+        scopes.sort_unstable_by_key(|(scope, _)| *scope);
+        for (scope, arguments) in scopes {
+            // the order of the arguments of a path and of the patterns matters, not the order of
+            // the paths in the trie
+            let mut by_subtree = arguments
+                .by_subtree
+                .iter()
+                .map(|(prefix, values)| (join_path(&prefix.into_iter().cloned().collect()), values))
+                .collect::<Vec<_>>();
+            by_subtree.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            let by_glob = arguments
+                .by_glob
+                .iter()
+                .map(|(pattern, argument)| (Borrow::<str>::borrow(pattern), argument))
+                .collect::<Vec<_>>();
+            (scope, by_subtree, by_glob).hash(&mut hasher);
+        }
+        format!("{:016x}", hasher.finish())
+    }
+
     /// Returns the arguments that apply to the given file and the given rule.
+    ///
+    /// The arguments of a rule override the arguments of its ruleset, which override the
+    /// arguments of all the rules. In a scope, glob patterns override path prefixes.
     pub fn get_arguments(&self, filename: &SplitPath, rulename: &str) -> HashMap<String, String> {
         let mut out = HashMap::new();
-        if let Some(by_prefix) = self.by_rule.get(rulename) {
-            for args in by_prefix.prefix_iter(filename) {
-                // Longer prefixes appear last, so they'll override arguments from shorter prefixes.
-                if let Some(value) = args.value() {
-                    out.extend(value.iter().cloned());
-                }
+        let path = join_path(filename);
+        let ruleset = rulename.split_once('/').map(|(ruleset, _)| ruleset);
+        for scope in [Some(""), ruleset, Some(rulename)].into_iter().flatten() {
+            if let Some(arguments) = self.by_scope.get(scope) {
+                arguments.extend_arguments(filename, &path, &mut out);
             }
         }
         out
     }
 }
 
+impl ScopedArguments {
+    fn extend_arguments(
+        &self,
+        filename: &SplitPath,
+        path: &str,
+        out: &mut HashMap<String, String>,
+    ) {
+        for args in self.by_subtree.prefix_iter(filename) {
+            // Longer prefixes appear last, so they'll override arguments from shorter prefixes.
+            if let Some(value) = args.value() {
+                out.extend(value.iter().cloned());
+            }
+        }
+        // Later patterns override earlier ones.
+        for (pattern, argument) in &self.by_glob {
+            if pattern
+                .glob
+                .as_ref()
+                .is_some_and(|glob| glob.is_match(path))
+            {
+                out.insert(argument.0.clone(), argument.1.clone());
+            }
+        }
+    }
+}
+
 impl Default for ArgumentProvider {
     fn default() -> Self {
         ArgumentProvider::new()
@@ -72,6 +193,7 @@ impl Default for ArgumentProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config_file::parse_config_file;
     use crate::model::config_file::split_path;
     use std::collections::HashMap;
 
@@ -203,4 +325,112 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn test_argument_provider_returns_arg_for_glob_pattern() {
+        let mut argument_provider = ArgumentProvider::new();
+        argument_provider.add_argument("rule", &split_path("a"), "arg", "prefix");
+        argument_provider.add_argument_for_pattern(
+            "rule",
+            "**/tests/**".to_string().into(),
+            "arg",
+            "glob",
+        );
+
+        assert_eq!(
+            argument_provider.get_arguments(&split_path("a/b.py"), "rule"),
+            HashMap::from([("arg".to_string(), "prefix".to_string())])
+        );
+        // patterns override prefixes
+        assert_eq!(
+            argument_provider.get_arguments(&split_path("a/tests/b.py"), "rule"),
+            HashMap::from([("arg".to_string(), "glob".to_string())])
+        );
+        assert_eq!(
+            argument_provider.get_arguments(&split_path("c/d/tests/e.py"), "rule"),
+            HashMap::from([("arg".to_string(), "glob".to_string())])
+        );
+    }
+
+    /// The digest changes with the arguments, and not with the order of the paths.
+    #[test]
+    fn test_argument_provider_digest() {
+        let provider = |paths: &[&str], glob_value: &str| {
+            let mut argument_provider = ArgumentProvider::new();
+            for path in paths {
+                argument_provider.add_argument("rule", &split_path(*path), "arg", path);
+            }
+            argument_provider.add_argument_for_pattern(
+                "rule",
+                "**/tests/**".to_string().into(),
+                "arg",
+                glob_value,
+            );
+            argument_provider
+        };
+        let digest = provider(&["a", "b/c"], "glob").digest();
+        assert_eq!(provider(&["b/c", "a"], "glob").digest(), digest);
+        assert_ne!(provider(&["a", "b/c"], "other").digest(), digest);
+        assert_ne!(provider(&["a"], "glob").digest(), digest);
+    }
+
+    #[test]
+    fn test_argument_provider_from_config_scopes() {
+        let config = parse_config_file(
+            r#"
+schema-version: v1
+arguments:
+  max-params: 4
+  max-depth:
+    /: 2
+    "**/tests/**": 5
+rulesets:
+  - python-best-practices:
+    arguments:
+      max-params: 6
+    rules:
+      too-many-params:
+        arguments:
+          max-params:
+            legacy: 10
+  - python-code-style
+"#,
+        )
+        .unwrap();
+        let argument_provider = ArgumentProvider::from(&config);
+
+        assert_eq!(
+            argument_provider.get_arguments(&split_path("a.py"), "python-code-style/rule"),
+            HashMap::from([
+                ("max-params".to_string(), "4".to_string()),
+                ("max-depth".to_string(), "2".to_string())
+            ])
+        );
+        assert_eq!(
+            argument_provider
+                .get_arguments(&split_path("src/tests/a.py"), "python-best-practices/rule"),
+            HashMap::from([
+                ("max-params".to_string(), "6".to_string()),
+                ("max-depth".to_string(), "5".to_string())
+            ])
+        );
+        assert_eq!(
+            argument_provider.get_arguments(
+                &split_path("legacy/a.py"),
+                "python-best-practices/too-many-params"
+            ),
+            HashMap::from([
+                ("max-params".to_string(), "10".to_string()),
+                ("max-depth".to_string(), "2".to_string())
+            ])
+        );
+        assert_eq!(
+            argument_provider
+                .get_arguments(&split_path("a.py"), "python-best-practices/too-many-params"),
+            HashMap::from([
+                ("max-params".to_string(), "6".to_string()),
+                ("max-depth".to_string(), "2".to_string())
+            ])
+        );
+    }
 }
@@ -9,9 +9,11 @@ use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::marker::PhantomData;
 
+use crate::model::common::Language;
 use crate::model::config_file::{
-    join_path, split_path, BySubtree, ConfigFile, PathConfig, PathPattern, RuleConfig,
-    RulesetConfig,
+    argument_values, ArgumentValues, ConfigFile, FileLanguagesConfig, PathConfig, PathPattern,
+    PolicyConfig, RuleConfig, RulesetConfig, SecretsAllowlistConfig, SecretsValidationConfig,
+    SeverityRemap, SeverityRemapConfig,
 };
 use crate::model::rule::{RuleCategory, RuleSeverity};
 
@@ -34,6 +36,8 @@ struct YamlConfigFile {
     rulesets: YamlRulesetList,
     #[serde(flatten)]
     paths: YamlPathConfig,
+    #[serde(default, skip_serializing_if = "UniqueKeyMap::is_empty")]
+    arguments: UniqueKeyMap<YamlArgumentValues>,
     #[serde(skip_serializing_if = "Option::is_none")]
     ignore_paths: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -42,6 +46,18 @@ struct YamlConfigFile {
     max_file_size_kb: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     ignore_generated_files: Option<bool>,
+    #[serde(default, skip_serializing_if = "YamlPolicyList::is_empty")]
+    policies: YamlPolicyList,
+    #[serde(default, skip_serializing_if = "YamlSeverityRemapList::is_empty")]
+    severity_remaps: YamlSeverityRemapList,
+    #[serde(default, skip_serializing_if = "YamlSecretsAllowlist::is_empty")]
+    secrets_allowlist: YamlSecretsAllowlist,
+    #[serde(default, skip_serializing_if = "YamlSecretsValidation::is_empty")]
+    secrets_validation: YamlSecretsValidation,
+    #[serde(default, skip_serializing_if = "YamlFileLanguages::is_empty")]
+    file_languages: YamlFileLanguages,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    plugins: Vec<String>,
 }
 
 impl From<YamlConfigFile> for ConfigFile {
@@ -57,9 +73,26 @@ impl From<YamlConfigFile> for ConfigFile {
                 }
                 paths
             },
+            arguments: value
+                .arguments
+                .0
+                .into_iter()
+                .map(|(k, v)| (k, v.into()))
+                .collect(),
             ignore_gitignore: value.ignore_gitignore,
             max_file_size_kb: value.max_file_size_kb,
             ignore_generated_files: value.ignore_generated_files,
+            policies: value.policies.0.into_iter().map(|p| p.into()).collect(),
+            severity_remaps: value
+                .severity_remaps
+                .0
+                .into_iter()
+                .map(|r| r.into())
+                .collect(),
+            secrets_allowlist: value.secrets_allowlist.into(),
+            secrets_validation: value.secrets_validation.into(),
+            file_languages: value.file_languages.into(),
+            plugins: value.plugins,
         }
     }
 }
@@ -70,10 +103,29 @@ impl From<ConfigFile> for YamlConfigFile {
             schema_version: YamlSchemaVersion::V1,
             rulesets: value.rulesets.into(),
             paths: value.paths.into(),
+            arguments: UniqueKeyMap(
+                value
+                    .arguments
+                    .into_iter()
+                    .map(|(k, v)| (k, v.into()))
+                    .collect(),
+            ),
             ignore_paths: None,
             ignore_gitignore: value.ignore_gitignore,
             max_file_size_kb: value.max_file_size_kb,
             ignore_generated_files: value.ignore_generated_files,
+            policies: YamlPolicyList(value.policies.into_iter().map(|p| p.into()).collect()),
+            severity_remaps: YamlSeverityRemapList(
+                value
+                    .severity_remaps
+                    .into_iter()
+                    .map(|r| r.into())
+                    .collect(),
+            ),
+            secrets_allowlist: value.secrets_allowlist.into(),
+            secrets_validation: value.secrets_validation.into(),
+            file_languages: value.file_languages.into(),
+            plugins: value.plugins,
         }
     }
 }
@@ -220,6 +272,10 @@ struct YamlRulesetConfig {
     paths: YamlPathConfig,
     #[serde(default, skip_serializing_if = "UniqueKeyMap::is_empty")]
     rules: UniqueKeyMap<YamlRuleConfig>,
+    #[serde(default, skip_serializing_if = "UniqueKeyMap::is_empty")]
+    arguments: UniqueKeyMap<YamlArgumentValues>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    analyze_generated_files: Option<bool>,
 }
 
 impl From<YamlRulesetConfig> for RulesetConfig {
@@ -232,6 +288,13 @@ impl From<YamlRulesetConfig> for RulesetConfig {
                 .into_iter()
                 .map(|(k, v)| (k, v.into()))
                 .collect(),
+            arguments: value
+                .arguments
+                .0
+                .into_iter()
+                .map(|(k, v)| (k, v.into()))
+                .collect(),
+            analyze_generated_files: value.analyze_generated_files,
         }
     }
 }
@@ -247,6 +310,14 @@ impl From<RulesetConfig> for YamlRulesetConfig {
                     .map(|(k, v)| (k, v.into()))
                     .collect(),
             ),
+            arguments: UniqueKeyMap(
+                value
+                    .arguments
+                    .into_iter()
+                    .map(|(k, v)| (k, v.into()))
+                    .collect(),
+            ),
+            analyze_generated_files: value.analyze_generated_files,
         }
     }
 }
@@ -293,6 +364,8 @@ struct YamlRuleConfig {
     severity: Option<RuleSeverity>,
     #[serde(skip_serializing_if = "Option::is_none")]
     category: Option<YamlRuleCategory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    analyze_generated_files: Option<bool>,
 }
 
 impl From<YamlRuleConfig> for RuleConfig {
@@ -307,6 +380,7 @@ impl From<YamlRuleConfig> for RuleConfig {
                 .collect(),
             severity: value.severity,
             category: value.category.map(|c| c.0),
+            analyze_generated_files: value.analyze_generated_files,
         }
     }
 }
@@ -324,13 +398,14 @@ impl From<RuleConfig> for YamlRuleConfig {
             ),
             severity: value.severity,
             category: value.category.map(YamlRuleCategory),
+            analyze_generated_files: value.analyze_generated_files,
         }
     }
 }
 
 // YAML-serializable argument value map.
 // If it only contains one value for the root directory, it serializes and deserializes as
-// a string; otherwise, as a map from path prefix to value.
+// a string; otherwise, as a map from path prefix or glob pattern to value.
 #[derive(Default, PartialEq)]
 struct YamlArgumentValues(IndexMap<String, String>);
 
@@ -386,24 +461,310 @@ impl Serialize for YamlArgumentValues {
     }
 }
 
-impl From<YamlArgumentValues> for BySubtree<String> {
+impl From<YamlArgumentValues> for ArgumentValues {
     fn from(value: YamlArgumentValues) -> Self {
-        let mut out = BySubtree::new();
-        for (k, v) in value.0 {
-            out.insert(&split_path(k), v);
+        argument_values(value.0)
+    }
+}
+
+impl From<ArgumentValues> for YamlArgumentValues {
+    fn from(value: ArgumentValues) -> Self {
+        YamlArgumentValues(value.iter().map(|(k, v)| (k, v.clone())).collect())
+    }
+}
+
+// YAML-serializable policy list.
+// When deserializing, disallows two policies with the same name.
+#[derive(Serialize, Default)]
+#[serde(transparent)]
+struct YamlPolicyList(Vec<YamlPolicyConfig>);
+
+impl YamlPolicyList {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'de> Deserialize<'de> for YamlPolicyList {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let list = Vec::<YamlPolicyConfig>::deserialize(deserializer)?;
+        let mut names = HashSet::new();
+        for item in &list {
+            if !names.insert(&item.name) {
+                return Err(Error::custom(format!("duplicate policy: {}", item.name)));
+            }
         }
-        out
+        Ok(YamlPolicyList(list))
     }
 }
 
-impl From<BySubtree<String>> for YamlArgumentValues {
-    fn from(value: BySubtree<String>) -> Self {
-        YamlArgumentValues(
-            value
-                .iter()
-                .map(|(k, v)| (join_path(&k.into_iter().cloned().collect()), v.clone()))
-                .collect(),
-        )
+// YAML-serializable policy configuration.
+#[derive(Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+struct YamlPolicyConfig {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<YamlRuleCategory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<RuleSeverity>,
+    #[serde(flatten)]
+    paths: YamlPathConfig,
+    max_violations: u64,
+}
+
+impl From<YamlPolicyConfig> for PolicyConfig {
+    fn from(value: YamlPolicyConfig) -> Self {
+        PolicyConfig {
+            name: value.name,
+            category: value.category.map(|c| c.0),
+            severity: value.severity,
+            paths: value.paths.into(),
+            max_violations: value.max_violations,
+        }
+    }
+}
+
+impl From<PolicyConfig> for YamlPolicyConfig {
+    fn from(value: PolicyConfig) -> Self {
+        YamlPolicyConfig {
+            name: value.name,
+            category: value.category.map(YamlRuleCategory),
+            severity: value.severity,
+            paths: value.paths.into(),
+            max_violations: value.max_violations,
+        }
+    }
+}
+
+// YAML-serializable list of severity remaps.
+// When deserializing, requires each remap to have exactly one of `severity`, `downgrade` or
+// `upgrade`.
+#[derive(Serialize, Default)]
+#[serde(transparent)]
+struct YamlSeverityRemapList(Vec<YamlSeverityRemapConfig>);
+
+impl YamlSeverityRemapList {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'de> Deserialize<'de> for YamlSeverityRemapList {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let list = Vec::<YamlSeverityRemapConfig>::deserialize(deserializer)?;
+        for item in &list {
+            let remaps = [
+                item.severity.is_some(),
+                item.downgrade.is_some(),
+                item.upgrade.is_some(),
+            ];
+            if remaps.iter().filter(|r| **r).count() != 1 {
+                return Err(Error::custom(
+                    "a severity remap needs exactly one of severity, downgrade or upgrade",
+                ));
+            }
+        }
+        Ok(YamlSeverityRemapList(list))
+    }
+}
+
+// YAML-serializable severity remap.
+#[derive(Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+struct YamlSeverityRemapConfig {
+    #[serde(flatten)]
+    paths: YamlPathConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<RuleSeverity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    downgrade: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upgrade: Option<u8>,
+}
+
+impl From<YamlSeverityRemapConfig> for SeverityRemapConfig {
+    fn from(value: YamlSeverityRemapConfig) -> Self {
+        let remap = match (value.severity, value.downgrade, value.upgrade) {
+            (Some(severity), _, _) => SeverityRemap::Set(severity),
+            (None, Some(levels), _) => SeverityRemap::Downgrade(levels),
+            (None, None, levels) => SeverityRemap::Upgrade(levels.unwrap_or(0)),
+        };
+        SeverityRemapConfig {
+            paths: value.paths.into(),
+            remap,
+        }
+    }
+}
+
+impl From<SeverityRemapConfig> for YamlSeverityRemapConfig {
+    fn from(value: SeverityRemapConfig) -> Self {
+        let (severity, downgrade, upgrade) = match value.remap {
+            SeverityRemap::Set(severity) => (Some(severity), None, None),
+            SeverityRemap::Downgrade(levels) => (None, Some(levels), None),
+            SeverityRemap::Upgrade(levels) => (None, None, Some(levels)),
+        };
+        YamlSeverityRemapConfig {
+            paths: value.paths.into(),
+            severity,
+            downgrade,
+            upgrade,
+        }
+    }
+}
+
+// YAML-serializable secrets allowlist.
+#[derive(Deserialize, Serialize, Default, PartialEq)]
+struct YamlSecretsAllowlist {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fingerprints: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    patterns: Vec<String>,
+}
+
+impl YamlSecretsAllowlist {
+    fn is_empty(&self) -> bool {
+        self == &YamlSecretsAllowlist::default()
+    }
+}
+
+impl From<YamlSecretsAllowlist> for SecretsAllowlistConfig {
+    fn from(value: YamlSecretsAllowlist) -> Self {
+        SecretsAllowlistConfig {
+            fingerprints: value.fingerprints,
+            paths: value.paths.into_iter().map(PathPattern::from).collect(),
+            patterns: value.patterns,
+        }
+    }
+}
+
+impl From<SecretsAllowlistConfig> for YamlSecretsAllowlist {
+    fn from(value: SecretsAllowlistConfig) -> Self {
+        YamlSecretsAllowlist {
+            fingerprints: value.fingerprints,
+            paths: value.paths.into_iter().map(String::from).collect(),
+            patterns: value.patterns,
+        }
+    }
+}
+
+// YAML-serializable restrictions of the validation of the secrets.
+#[derive(Deserialize, Serialize, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+struct YamlSecretsValidation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allowed_hosts: Option<Vec<String>>,
+}
+
+impl YamlSecretsValidation {
+    fn is_empty(&self) -> bool {
+        self == &YamlSecretsValidation::default()
+    }
+}
+
+impl From<YamlSecretsValidation> for SecretsValidationConfig {
+    fn from(value: YamlSecretsValidation) -> Self {
+        SecretsValidationConfig {
+            allowed_hosts: value.allowed_hosts,
+        }
+    }
+}
+
+impl From<SecretsValidationConfig> for YamlSecretsValidation {
+    fn from(value: SecretsValidationConfig) -> Self {
+        YamlSecretsValidation {
+            allowed_hosts: value.allowed_hosts,
+        }
+    }
+}
+
+// YAML-serializable mapping of file names and extensions to languages. The keys are either
+// `*.<extension>` or an exact file name.
+#[derive(Serialize, Default, PartialEq)]
+#[serde(transparent)]
+struct YamlFileLanguages(UniqueKeyMap<YamlLanguage>);
+
+impl YamlFileLanguages {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'de> Deserialize<'de> for YamlFileLanguages {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let map = UniqueKeyMap::<YamlLanguage>::deserialize(deserializer)?;
+        for key in map.0.keys() {
+            let name = key.strip_prefix("*.").unwrap_or(key);
+            if name.is_empty() || name.contains(['*', '/']) || (name != key && name.contains('.')) {
+                return Err(Error::invalid_value(
+                    Unexpected::Str(key),
+                    &"`*.<extension>` or a file name",
+                ));
+            }
+        }
+        Ok(YamlFileLanguages(map))
+    }
+}
+
+impl From<YamlFileLanguages> for FileLanguagesConfig {
+    fn from(value: YamlFileLanguages) -> Self {
+        let mut config = FileLanguagesConfig::default();
+        for (key, language) in value.0 .0 {
+            match key.strip_prefix("*.") {
+                Some(extension) => config.extensions.insert(extension.to_string(), language.0),
+                None => config.filenames.insert(key, language.0),
+            };
+        }
+        config
+    }
+}
+
+impl From<FileLanguagesConfig> for YamlFileLanguages {
+    fn from(value: FileLanguagesConfig) -> Self {
+        let extensions = value
+            .extensions
+            .into_iter()
+            .map(|(extension, language)| (format!("*.{extension}"), YamlLanguage(language)));
+        let filenames = value
+            .filenames
+            .into_iter()
+            .map(|(filename, language)| (filename, YamlLanguage(language)));
+        YamlFileLanguages(UniqueKeyMap(extensions.chain(filenames).collect()))
+    }
+}
+
+// YAML-serializable language, written in lowercase (`python`, `csharp`, ...).
+#[derive(PartialEq)]
+struct YamlLanguage(Language);
+
+impl Serialize for YamlLanguage {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("{:?}", self.0).to_lowercase())
+    }
+}
+
+impl<'de> Deserialize<'de> for YamlLanguage {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        Language::from_name(&name)
+            .map(YamlLanguage)
+            .ok_or_else(|| Error::invalid_value(Unexpected::Str(&name), &"a language"))
     }
 }
 
@@ -510,7 +871,7 @@ impl Display for AnyAsString {
 mod tests {
     use super::*;
     use crate::model::config_file::{
-        values_by_subtree, ConfigFile, PathConfig, PathPattern, RuleConfig, RulesetConfig,
+        argument_values, ConfigFile, PathConfig, PathPattern, RuleConfig, RulesetConfig,
     };
     use std::fs;
     use std::path::{Path, PathBuf};
@@ -719,8 +1080,11 @@ rulesets:
                             arguments: IndexMap::new(),
                             severity: None,
                             category: None,
+                            analyze_generated_files: None,
                         },
                     )]),
+                    arguments: IndexMap::new(),
+                    analyze_generated_files: None,
                 },
             )]),
             ..ConfigFile::default()
@@ -730,6 +1094,89 @@ rulesets:
         assert_eq!(expected, res.unwrap());
     }
 
+    // Rulesets and rules can opt into analyzing generated files.
+    #[test]
+    fn test_parse_analyze_generated_files() {
+        let data = r#"
+rulesets:
+  - license-headers:
+    analyze-generated-files: true
+  - python-security:
+    rules:
+      no-eval:
+        analyze-generated-files: true
+    "#;
+        let config = parse_config_file(data).unwrap();
+        assert_eq!(
+            config.rulesets["license-headers"].analyze_generated_files,
+            Some(true)
+        );
+        let python_security = &config.rulesets["python-security"];
+        assert_eq!(python_security.analyze_generated_files, None);
+        assert_eq!(
+            python_security.rules["no-eval"].analyze_generated_files,
+            Some(true)
+        );
+
+        let yaml = config_file_to_yaml(&config).unwrap();
+        assert_eq!(parse_config_file(&yaml).unwrap(), config);
+    }
+
+    #[test]
+    fn test_parse_file_languages() {
+        let data = r#"
+rulesets:
+  - python-best-practices
+file-languages:
+  "*.bzl": python
+  Jenkinsfile.release: groovy
+  "*.cs.txt": csharp
+    "#;
+        assert!(parse_config_file(data).is_err());
+
+        let data = r#"
+rulesets:
+  - python-best-practices
+file-languages:
+  "*.bzl": python
+  Jenkinsfile.release: Groovy
+  "*.csx": c#
+    "#;
+        let config = parse_config_file(data).unwrap();
+        let file_languages = &config.file_languages;
+        assert_eq!(
+            file_languages.extensions,
+            IndexMap::from([
+                ("bzl".to_string(), Language::Python),
+                ("csx".to_string(), Language::Csharp)
+            ])
+        );
+        assert_eq!(
+            file_languages.filenames,
+            IndexMap::from([("Jenkinsfile.release".to_string(), Language::Groovy)])
+        );
+        assert_eq!(
+            file_languages.language_for(Path::new("tools/defs.BZL")),
+            Some(Language::Python)
+        );
+        assert_eq!(
+            file_languages.language_for(Path::new("ci/Jenkinsfile.release")),
+            Some(Language::Groovy)
+        );
+        assert_eq!(file_languages.language_for(Path::new("main.py")), None);
+
+        let yaml = config_file_to_yaml(&config).unwrap();
+        assert_eq!(parse_config_file(&yaml).unwrap(), config);
+
+        let data = r#"
+rulesets:
+  - python-best-practices
+file-languages:
+  "*.bzl": starlark
+    "#;
+        assert!(parse_config_file(data).is_err());
+    }
+
     // Rules cannot be specified as lists of strings or maps.
     #[test]
     fn test_cannot_parse_rules_as_list() {
@@ -812,11 +1259,11 @@ rulesets:
                                 arguments: IndexMap::from([
                                     (
                                         "arg1".to_string(),
-                                        values_by_subtree([("", "100".to_string())]),
+                                        argument_values([("", "100".to_string())]),
                                     ),
                                     (
                                         "arg2".to_string(),
-                                        values_by_subtree([
+                                        argument_values([
                                             ("", "200".to_string()),
                                             ("uno", "201".to_string()),
                                             ("uno/dos", "202".to_string()),
@@ -826,6 +1273,7 @@ rulesets:
                                 ]),
                                 severity: None,
                                 category: None,
+                                analyze_generated_files: None,
                             },
                         ),
                         (
@@ -835,18 +1283,21 @@ rulesets:
                                 arguments: IndexMap::from([
                                     (
                                         "arg3".to_string(),
-                                        values_by_subtree([("", "300".to_string())]),
+                                        argument_values([("", "300".to_string())]),
                                     ),
                                     (
                                         "arg4".to_string(),
-                                        values_by_subtree([("cuatro", "400".to_string())]),
+                                        argument_values([("cuatro", "400".to_string())]),
                                     ),
                                 ]),
                                 severity: None,
                                 category: None,
+                                analyze_generated_files: None,
                             },
                         ),
                     ]),
+                    arguments: IndexMap::new(),
+                    analyze_generated_files: None,
                 },
             )]),
             ..ConfigFile::default()
@@ -855,6 +1306,51 @@ rulesets:
         assert_eq!(expected, res.unwrap());
     }
 
+    // Argument values for all the rules, for the rules of a ruleset, and by glob pattern
+    #[test]
+    fn test_parse_argument_values_scopes_and_globs() {
+        let data = r#"
+schema-version: v1
+rulesets:
+  - python-security:
+    arguments:
+      arg1:
+        /: 100
+        "**/tests/**": 101
+arguments:
+  arg2: 200
+        "#;
+
+        let expected = ConfigFile {
+            rulesets: IndexMap::from([(
+                "python-security".to_string(),
+                RulesetConfig {
+                    arguments: IndexMap::from([(
+                        "arg1".to_string(),
+                        argument_values([
+                            ("", "100".to_string()),
+                            ("**/tests/**", "101".to_string()),
+                        ]),
+                    )]),
+                    ..RulesetConfig::default()
+                },
+            )]),
+            arguments: IndexMap::from([(
+                "arg2".to_string(),
+                argument_values([("", "200".to_string())]),
+            )]),
+            ..ConfigFile::default()
+        };
+        let config = parse_config_file(data).unwrap();
+        assert_eq!(expected, config);
+        let arg1 = &config.rulesets["python-security"].arguments["arg1"];
+        assert_eq!(arg1.by_glob.len(), 1);
+        assert!(arg1.by_glob[0].0.matches("src/tests/test_main.py"));
+
+        let yaml = config_file_to_yaml(&config).unwrap();
+        assert_eq!(parse_config_file(&yaml).unwrap(), config);
+    }
+
     // test with everything
     #[test]
     fn test_parse_all_other_options() {
@@ -882,15 +1378,231 @@ max-file-size-kb: 512
                     "path1".to_string().into(),
                 ],
             },
+            arguments: IndexMap::new(),
             ignore_gitignore: Some(false),
             max_file_size_kb: Some(512),
             ignore_generated_files: None,
+            policies: vec![],
+            severity_remaps: vec![],
+            secrets_allowlist: SecretsAllowlistConfig::default(),
+            secrets_validation: SecretsValidationConfig::default(),
+            file_languages: FileLanguagesConfig::default(),
+            plugins: vec![],
         };
 
         let res = parse_config_file(data);
         assert_eq!(expected, res.unwrap());
     }
 
+    #[test]
+    fn test_parse_policies() {
+        let data = r#"
+rulesets:
+  - python-security
+policies:
+  - name: no-security-errors
+    category: SECURITY
+    severity: ERROR
+    max-violations: 0
+  - name: few-legacy-warnings
+    severity: WARNING
+    only:
+      - "src/legacy"
+    max-violations: 10
+    "#;
+
+        let expected = ConfigFile {
+            rulesets: IndexMap::from([("python-security".to_string(), RulesetConfig::default())]),
+            policies: vec![
+                PolicyConfig {
+                    name: "no-security-errors".to_string(),
+                    category: Some(RuleCategory::Security),
+                    severity: Some(RuleSeverity::Error),
+                    paths: PathConfig::default(),
+                    max_violations: 0,
+                },
+                PolicyConfig {
+                    name: "few-legacy-warnings".to_string(),
+                    category: None,
+                    severity: Some(RuleSeverity::Warning),
+                    paths: PathConfig {
+                        only: Some(vec!["src/legacy".to_string().into()]),
+                        ignore: vec![],
+                    },
+                    max_violations: 10,
+                },
+            ],
+            ..ConfigFile::default()
+        };
+
+        let res = parse_config_file(data);
+        assert_eq!(expected, res.unwrap());
+    }
+
+    #[test]
+    fn test_cannot_parse_repeated_policies() {
+        let data = r#"
+rulesets:
+  - python-security
+policies:
+  - name: no-errors
+    severity: ERROR
+    max-violations: 0
+  - name: no-errors
+    max-violations: 10
+    "#;
+
+        let res = parse_config_file(data);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_parse_plugins() {
+        let data = r#"
+rulesets:
+  - python-security
+plugins:
+  - tools/analyzer-plugins/libtypes.so
+    "#;
+
+        let expected = ConfigFile {
+            rulesets: IndexMap::from([("python-security".to_string(), RulesetConfig::default())]),
+            plugins: vec!["tools/analyzer-plugins/libtypes.so".to_string()],
+            ..ConfigFile::default()
+        };
+
+        let res = parse_config_file(data).unwrap();
+        assert_eq!(expected, res);
+        assert_eq!(
+            parse_config_file(&config_file_to_yaml(&res).unwrap()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_severity_remaps() {
+        let data = r#"
+rulesets:
+  - python-security
+severity-remaps:
+  - only:
+      - "experimental/**"
+    downgrade: 1
+  - only:
+      - "src/payments"
+    ignore:
+      - "src/payments/tests"
+    severity: ERROR
+    "#;
+
+        let expected = ConfigFile {
+            rulesets: IndexMap::from([("python-security".to_string(), RulesetConfig::default())]),
+            severity_remaps: vec![
+                SeverityRemapConfig {
+                    paths: PathConfig {
+                        only: Some(vec!["experimental/**".to_string().into()]),
+                        ignore: vec![],
+                    },
+                    remap: SeverityRemap::Downgrade(1),
+                },
+                SeverityRemapConfig {
+                    paths: PathConfig {
+                        only: Some(vec!["src/payments".to_string().into()]),
+                        ignore: vec!["src/payments/tests".to_string().into()],
+                    },
+                    remap: SeverityRemap::Set(RuleSeverity::Error),
+                },
+            ],
+            ..ConfigFile::default()
+        };
+
+        let res = parse_config_file(data).unwrap();
+        assert_eq!(expected, res);
+        // the remaps are serialized back
+        assert_eq!(
+            parse_config_file(&config_file_to_yaml(&res).unwrap()).unwrap(),
+            expected
+        );
+
+        let data = r#"
+rulesets:
+  - python-security
+severity-remaps:
+  - only:
+      - "experimental/**"
+    downgrade: 1
+    severity: ERROR
+    "#;
+        assert!(parse_config_file(data).is_err());
+    }
+
+    #[test]
+    fn test_parse_secrets_allowlist() {
+        let data = r#"
+rulesets:
+  - python-security
+secrets-allowlist:
+  fingerprints:
+    - "0123456789abcdef"
+  paths:
+    - "tests/fixtures"
+  patterns:
+    - "EXAMPLE"
+    "#;
+
+        let expected = ConfigFile {
+            rulesets: IndexMap::from([("python-security".to_string(), RulesetConfig::default())]),
+            secrets_allowlist: SecretsAllowlistConfig {
+                fingerprints: vec!["0123456789abcdef".to_string()],
+                paths: vec!["tests/fixtures".to_string().into()],
+                patterns: vec!["EXAMPLE".to_string()],
+            },
+            ..ConfigFile::default()
+        };
+
+        let res = parse_config_file(data);
+        assert_eq!(expected, res.unwrap());
+    }
+
+    #[test]
+    fn test_parse_secrets_validation() {
+        let data = r#"
+rulesets:
+  - python-security
+secrets-validation:
+  allowed-hosts:
+    - "api.github.com"
+    - "*.datadoghq.com"
+    "#;
+
+        let expected = ConfigFile {
+            rulesets: IndexMap::from([("python-security".to_string(), RulesetConfig::default())]),
+            secrets_validation: SecretsValidationConfig {
+                allowed_hosts: Some(vec![
+                    "api.github.com".to_string(),
+                    "*.datadoghq.com".to_string(),
+                ]),
+            },
+            ..ConfigFile::default()
+        };
+        assert_eq!(expected, parse_config_file(data).unwrap());
+
+        // an empty list allows no host
+        let data = r#"
+rulesets:
+  - python-security
+secrets-validation:
+  allowed-hosts: []
+    "#;
+        assert_eq!(
+            parse_config_file(data)
+                .unwrap()
+                .secrets_validation
+                .allowed_hosts,
+            Some(vec![])
+        );
+    }
+
     // No ruleset available in the data means that we have no configuration file
     // whatsoever and we should return Err
     #[test]
@@ -967,6 +1679,8 @@ rulesets:
                         prefix: "my-path/to/heaven".into(),
                     }]),
                 },
+                arguments: IndexMap::new(),
+                analyze_generated_files: None,
             },
         );
 
@@ -1026,6 +1740,8 @@ rulesets:
                         prefix: "my-path/to/heaven".into(),
                     }]),
                 },
+                arguments: IndexMap::new(),
+                analyze_generated_files: None,
             },
         );
 
@@ -1061,7 +1777,7 @@ rulesets:
         let mut arguments = IndexMap::new();
         arguments.insert(
             "max-params".to_string(),
-            values_by_subtree([("", "3".to_string())]),
+            argument_values([("", "3".to_string())]),
         );
 
         rules.insert(
@@ -1109,7 +1825,7 @@ rulesets:
         let mut arguments = IndexMap::new();
         arguments.insert(
             "max-params".to_string(),
-            values_by_subtree([("", "3".to_string()), ("my-path/to-file", "4".to_string())]),
+            argument_values([("", "3".to_string()), ("my-path/to-file", "4".to_string())]),
         );
 
         rules.insert(
@@ -0,0 +1,267 @@
+//! The settings of the `.editorconfig` files of a repository (see <https://editorconfig.org>), so
+//! that the rules and the fixes follow the conventions declared by the project.
+
+use globset::{GlobBuilder, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// The settings of a file, given to the rules in `node.context.editorconfig`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorConfigProperties {
+    /// `tab` or `space`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indent_style: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indent_size: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab_width: Option<u32>,
+    /// `lf`, `crlf` or `cr`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_of_line: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_line_length: Option<u32>,
+    /// `latin1`, `utf-8`, `utf-8-bom`, `utf-16be` or `utf-16le`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub charset: Option<String>,
+}
+
+impl EditorConfigProperties {
+    pub fn is_empty(&self) -> bool {
+        *self == EditorConfigProperties::default()
+    }
+}
+
+/// A section of an `.editorconfig` file: the files it applies to, and its properties.
+#[derive(Debug, Clone)]
+struct Section {
+    glob: GlobMatcher,
+    properties: Vec<(String, String)>,
+}
+
+/// An `.editorconfig` file.
+#[derive(Debug, Clone)]
+struct EditorConfigFile {
+    /// The directory of the file, relative to the root of the repository ("" for the root).
+    directory: String,
+    /// Whether the file is the top-most of its files (`root = true`).
+    root: bool,
+    sections: Vec<Section>,
+}
+
+/// The `.editorconfig` files of a repository.
+#[derive(Debug, Default, Clone)]
+pub struct EditorConfig {
+    /// The files, the top-most directories first.
+    files: Vec<EditorConfigFile>,
+}
+
+impl EditorConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns a digest of the settings, which change the results of the rules that use them.
+    pub fn digest(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        for file in &self.files {
+            (&file.directory, file.root).hash(&mut hasher);
+            for section in &file.sections {
+                (section.glob.glob().glob(), &section.properties).hash(&mut hasher);
+            }
+        }
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// Adds the `.editorconfig` file of a directory (relative to the root of the repository). The
+    /// lines that cannot be parsed and the sections with an invalid glob are ignored.
+    pub fn add_file(&mut self, directory: &str, content: &str) {
+        let directory = directory.trim_matches('/').to_string();
+        let mut file = EditorConfigFile {
+            directory,
+            root: false,
+            sections: vec![],
+        };
+        // the properties of a section with an invalid glob are dropped with it
+        let mut current: Option<Option<Section>> = None;
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if let Some(Some(section)) = current.take() {
+                    file.sections.push(section);
+                }
+                current = Some(section_glob(header).map(|glob| Section {
+                    glob,
+                    properties: vec![],
+                }));
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim().to_lowercase(), value.trim().to_string());
+            match &mut current {
+                // the properties before the first section are the preamble
+                None => {
+                    if key == "root" {
+                        file.root = value.eq_ignore_ascii_case("true");
+                    }
+                }
+                Some(Some(section)) => section.properties.push((key, value)),
+                Some(None) => {}
+            }
+        }
+        if let Some(Some(section)) = current {
+            file.sections.push(section);
+        }
+        let depth = |f: &EditorConfigFile| {
+            if f.directory.is_empty() {
+                0
+            } else {
+                f.directory.split('/').count()
+            }
+        };
+        let position = self
+            .files
+            .iter()
+            .position(|f| depth(f) > depth(&file))
+            .unwrap_or(self.files.len());
+        self.files.insert(position, file);
+    }
+
+    /// Returns the settings of a file (relative to the root of the repository): the properties of
+    /// the sections that match it, the sections of the deepest `.editorconfig` files and the last
+    /// sections of a file taking precedence. The files above a `root = true` file are ignored.
+    pub fn properties(&self, filename: &str) -> EditorConfigProperties {
+        let files = self
+            .files
+            .iter()
+            .filter_map(|file| relative_to(filename, &file.directory).map(|path| (file, path)))
+            .collect::<Vec<_>>();
+        let first = files.iter().rposition(|(f, _)| f.root).unwrap_or(0);
+
+        let mut values: Vec<(&str, &str)> = vec![];
+        for (file, path) in &files[first..] {
+            for section in file.sections.iter().filter(|s| s.glob.is_match(path)) {
+                for (key, value) in &section.properties {
+                    values.retain(|(k, _)| *k != key.as_str());
+                    values.push((key.as_str(), value.as_str()));
+                }
+            }
+        }
+
+        let value = |key: &str| {
+            values
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_lowercase())
+                .filter(|v| v != "unset")
+        };
+        let number = |key: &str| value(key).and_then(|v| v.parse::<u32>().ok());
+        let indent_style = value("indent_style").filter(|v| v == "tab" || v == "space");
+        let tab_width = number("tab_width");
+        // `indent_size = tab` is the width of a tab
+        let indent_size = match value("indent_size").as_deref() {
+            Some("tab") => tab_width,
+            _ => number("indent_size"),
+        };
+        EditorConfigProperties {
+            tab_width: tab_width.or(indent_size),
+            indent_size: indent_size.or(if indent_style.as_deref() == Some("tab") {
+                tab_width
+            } else {
+                None
+            }),
+            indent_style,
+            end_of_line: value("end_of_line")
+                .filter(|v| ["lf", "crlf", "cr"].contains(&v.as_str())),
+            max_line_length: number("max_line_length"),
+            charset: value("charset"),
+        }
+    }
+}
+
+/// Returns the glob of the header of a section: a glob with a `/` is relative to the directory of
+/// the file, and a glob without `/` matches the files of any subdirectory.
+fn section_glob(header: &str) -> Option<GlobMatcher> {
+    let pattern = match header.strip_prefix('/') {
+        Some(pattern) => pattern.to_string(),
+        None if header.contains('/') => header.to_string(),
+        None => format!("**/{header}"),
+    };
+    GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()
+        .ok()
+        .map(|glob| glob.compile_matcher())
+}
+
+/// Returns the path of a file relative to a directory, if the file is in the directory.
+fn relative_to<'a>(filename: &'a str, directory: &str) -> Option<&'a str> {
+    if directory.is_empty() {
+        return Some(filename);
+    }
+    filename.strip_prefix(directory)?.strip_prefix('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_properties() {
+        let mut editorconfig = EditorConfig::new();
+        editorconfig.add_file(
+            "src/legacy",
+            "root = true\n[*]\nindent_style = tab\ntab_width = 8\n",
+        );
+        editorconfig.add_file(
+            "",
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 4\nend_of_line = lf\ncharset = utf-8\n\n# Python\n[*.py]\nmax_line_length = 100\n\n[{Makefile,*.mk}]\nindent_style = tab\n\n[docs/*.md]\nmax_line_length = off\n[{unclosed]\nindent_size = 1\n",
+        );
+        editorconfig.add_file("src", "[*.py]\nindent_size = 2\nend_of_line = unset\n");
+
+        assert_eq!(
+            editorconfig.properties("src/main.py"),
+            EditorConfigProperties {
+                indent_style: Some("space".to_string()),
+                indent_size: Some(2),
+                tab_width: Some(2),
+                end_of_line: None,
+                max_line_length: Some(100),
+                charset: Some("utf-8".to_string()),
+            }
+        );
+        assert_eq!(
+            editorconfig.properties("tools/Makefile").indent_style,
+            Some("tab".to_string())
+        );
+        // the globs with a `/` are relative to the directory of the file
+        assert_eq!(
+            editorconfig.properties("docs/index.md").max_line_length,
+            None
+        );
+        assert_eq!(
+            editorconfig.properties("a/docs/index.md").max_line_length,
+            None
+        );
+        // the files above a root file are ignored
+        assert_eq!(
+            editorconfig.properties("src/legacy/old.py"),
+            EditorConfigProperties {
+                indent_style: Some("tab".to_string()),
+                indent_size: Some(8),
+                tab_width: Some(8),
+                ..Default::default()
+            }
+        );
+        assert!(EditorConfig::new().properties("main.py").is_empty());
+    }
+}
@@ -1,8 +1,11 @@
 pub mod analysis;
+pub mod api;
 pub mod arguments;
 pub mod config_file;
 pub mod constants;
+pub mod editorconfig;
 pub mod model;
 pub mod path_restrictions;
+pub mod plugin;
 pub mod rule_overrides;
 pub mod utils;
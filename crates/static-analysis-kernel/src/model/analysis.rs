@@ -1,20 +1,53 @@
-use crate::model::common::Position;
+use crate::editorconfig::EditorConfigProperties;
+use crate::model::common::{ColumnUnit, Position};
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
 use crate::model::analysis::FileIgnoreBehavior::AllRules;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 pub const ERROR_RULE_TIMEOUT: &str = "rule-timeout";
 pub const ERROR_RULE_EXECUTION: &str = "error-execution";
+/// The rule accessed a JavaScript API with side effects (network, timers, ...) without being allowed to.
+pub const ERROR_RULE_SANDBOX: &str = "rule-sandbox-violation";
 pub const ERROR_INVALID_QUERY: &str = "error-invalid-query";
+pub const ERROR_ANALYSIS_CANCELLED: &str = "analysis-cancelled";
+
+/// The name of the synthetic result that reports the syntax errors of a file.
+pub const RULE_PARSE_ERROR: &str = "parse-error";
 
 // Used internally to pass options to the analysis
-#[derive(Clone, Deserialize, Debug, Serialize, Builder)]
+#[derive(Clone, Default, Deserialize, Debug, Serialize, Builder)]
 pub struct AnalysisOptions {
     pub log_output: bool,
     pub use_debug: bool,
     pub ignore_generated_files: bool,
+    /// If true, the syntax errors of a file are reported with a synthetic [`RULE_PARSE_ERROR`] result.
+    #[serde(default)]
+    #[builder(default)]
+    pub report_parse_errors: bool,
+    /// The unit of the columns of the reported positions.
+    #[serde(default)]
+    #[builder(default)]
+    pub column_unit: ColumnUnit,
+    /// The rules that analyze generated files, even if `ignore_generated_files` is true.
+    #[serde(default)]
+    #[builder(default)]
+    pub generated_files_rules: HashSet<String>,
+    /// If true, the resources used by each execution of a rule are reported in [`RuleResult::profile`].
+    ///
+    /// [`RuleResult::profile`]: crate::model::rule::RuleResult::profile
+    #[serde(default)]
+    #[builder(default)]
+    pub profile_rules: bool,
+    /// If true, the rules use the real `Math.random` and `Date` instead of a random generator
+    /// seeded with the rule and the file, and a frozen time. The violations of a rule may then
+    /// differ between two analyses of the same file.
+    #[serde(default)]
+    #[builder(default)]
+    pub nondeterministic_rules: bool,
 }
 
 #[derive(PartialEq, Debug)]
@@ -66,32 +99,44 @@ pub struct AnalysisContext {
 
 // Used for the node and this is externally visible.
 // This is what you see when you do a .context on a node.
+// The code and the filename are shared by all the matches of a file instead of being copied.
 #[derive(Clone, Deserialize, Debug, Serialize, Builder)]
 pub struct MatchNodeContext {
-    pub code: Option<String>,
-    pub filename: String,
+    pub code: Option<Arc<str>>,
+    pub filename: Arc<str>,
     pub arguments: HashMap<String, String>,
+    /// The `.editorconfig` settings of the file, shared by all its matches.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editorconfig: Option<Arc<EditorConfigProperties>>,
 }
 
 // The node used to capture data in tree-sitter
+// The type and the field name of a node are the static strings of the grammar, so they are not
+// allocated for each node.
 #[derive(Clone, Deserialize, Debug, Serialize, Builder)]
 pub struct TreeSitterNode {
     #[serde(rename = "astType")]
-    pub ast_type: String,
+    pub ast_type: Cow<'static, str>,
     pub start: Position,
     pub end: Position,
     #[serde(rename = "fieldName")]
-    pub field_name: Option<String>,
+    pub field_name: Option<Cow<'static, str>>,
     pub children: Vec<TreeSitterNode>,
 }
 
 // The node that is then passed to the visit function.
+// The names of the captures are shared with the query.
 #[derive(Clone, Debug, Serialize, Builder)]
 pub struct MatchNode {
-    pub captures: HashMap<String, TreeSitterNode>,
+    pub captures: HashMap<Arc<str>, TreeSitterNode>,
     #[serde(rename = "capturesList")]
-    pub captures_list: HashMap<String, Vec<TreeSitterNode>>,
+    pub captures_list: HashMap<Arc<str>, Vec<TreeSitterNode>>,
     pub context: MatchNodeContext,
+    // the name of the query that matched, if the node comes from one of the named queries of the rule.
+    #[serde(rename = "queryName")]
+    #[builder(default)]
+    pub query_name: Option<String>,
 }
 
 #[cfg(test)]
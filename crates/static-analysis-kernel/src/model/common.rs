@@ -1,12 +1,18 @@
-use derive_builder::Builder;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+pub use ::common::model::position::{Offsets, Position, PositionBuilder};
+pub use ::common::model::shared_str::{Interner, SharedStr};
+
 #[derive(Clone, Deserialize, Debug, Serialize, Eq, PartialEq)]
 pub enum OutputFormat {
     Csv,
     Json,
     Sarif,
+    Html,
+    /// A report meant to be read in a terminal.
+    Pretty,
 }
 
 impl fmt::Display for OutputFormat {
@@ -15,6 +21,8 @@ impl fmt::Display for OutputFormat {
             Self::Csv => "CSV",
             Self::Json => "JSON",
             Self::Sarif => "SARIF",
+            Self::Html => "HTML",
+            Self::Pretty => "PRETTY",
         };
         write!(f, "{s}")
     }
@@ -28,6 +36,10 @@ pub enum Language {
     Dockerfile,
     #[serde(rename = "GO")]
     Go,
+    #[serde(rename = "GRAPHQL")]
+    GraphQL,
+    #[serde(rename = "GROOVY")]
+    Groovy,
     #[serde(rename = "JAVA")]
     Java,
     #[serde(rename = "JAVASCRIPT")]
@@ -36,6 +48,11 @@ pub enum Language {
     Json,
     #[serde(rename = "KOTLIN")]
     Kotlin,
+    #[serde(rename = "OBJECTIVEC")]
+    ObjectiveC,
+    /// Protocol buffer definitions, including gRPC services.
+    #[serde(rename = "PROTO")]
+    Proto,
     #[serde(rename = "PYTHON")]
     Python,
     #[serde(rename = "RUBY")]
@@ -44,10 +61,13 @@ pub enum Language {
     Rust,
     #[serde(rename = "SWIFT")]
     Swift,
-    #[serde(rename = "TERRAFORM")]
+    /// HCL files: Terraform, as well as Packer, Nomad and other tools using HCL.
+    #[serde(rename = "TERRAFORM", alias = "HCL")]
     Terraform,
     #[serde(rename = "TYPESCRIPT")]
     TypeScript,
+    #[serde(rename = "XML")]
+    Xml,
     #[serde(rename = "YAML")]
     Yaml,
 }
@@ -57,49 +77,268 @@ pub static ALL_LANGUAGES: &[Language] = &[
     Language::Csharp,
     Language::Dockerfile,
     Language::Go,
+    Language::GraphQL,
+    Language::Groovy,
     Language::Java,
     Language::JavaScript,
     Language::Json,
     Language::Kotlin,
+    Language::ObjectiveC,
+    Language::Proto,
     Language::Python,
     Language::Ruby,
     Language::Rust,
     Language::Swift,
     Language::TypeScript,
     Language::Terraform,
+    Language::Xml,
     Language::Yaml,
 ];
 
+impl Language {
+    /// Returns the language with a name, ignoring the case: either the name of its variant (e.g.
+    /// `csharp`) or its display name (e.g. `c#`).
+    pub fn from_name(name: &str) -> Option<Language> {
+        ALL_LANGUAGES.iter().copied().find(|language| {
+            format!("{:?}", language).eq_ignore_ascii_case(name)
+                || language.to_string().eq_ignore_ascii_case(name)
+        })
+    }
+}
+
 impl fmt::Display for Language {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
             Self::Csharp => "c#",
             Self::Dockerfile => "dockerfile",
             Self::Go => "go",
+            Self::GraphQL => "graphql",
+            Self::Groovy => "groovy",
             Self::Java => "java",
             Self::JavaScript => "javascript",
             Self::Json => "json",
             Self::Kotlin => "kotlin",
+            Self::ObjectiveC => "objective-c",
+            Self::Proto => "proto",
             Self::Python => "python",
             Self::Ruby => "ruby",
             Self::Rust => "rust",
             Self::Swift => "swift",
             Self::Terraform => "terraform",
             Self::TypeScript => "typescript",
+            Self::Xml => "xml",
             Self::Yaml => "yaml",
         };
         write!(f, "{s}")
     }
 }
 
-#[derive(Deserialize, Debug, Serialize, Clone, Builder)]
-pub struct Position {
-    pub line: u32,
-    pub col: u32,
+/// The unit of the columns of the positions reported by the analysis.
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColumnUnit {
+    /// Columns count the bytes of the UTF-8 encoded line (the tree-sitter columns).
+    #[default]
+    Bytes,
+    /// Columns count the characters (Unicode code points) of the line.
+    Chars,
+    /// Columns count the UTF-16 code units of the line, as expected by most editors.
+    Utf16,
 }
 
-impl fmt::Display for Position {
+impl ColumnUnit {
+    /// The length of a character in this unit.
+    fn len(self, ch: char) -> usize {
+        match self {
+            Self::Bytes => ch.len_utf8(),
+            Self::Chars => 1,
+            Self::Utf16 => ch.len_utf16(),
+        }
+    }
+}
+
+impl fmt::Display for ColumnUnit {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "position (line: {}, col: {})", self.line, self.col)
+        let s = match self {
+            Self::Bytes => "bytes",
+            Self::Chars => "chars",
+            Self::Utf16 => "utf16",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// The byte order mark that starts some UTF-8 files.
+pub(crate) const BOM: char = '\u{feff}';
+
+/// An index of the lines of a file, to convert positions into byte offsets and UTF-16 columns.
+///
+/// The byte order mark of a file isn't a column in characters or UTF-16 code units, as the editors
+/// don't show it: it's only counted by the columns in bytes.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    code: &'a str,
+    /// The byte offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(code: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(code.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self { code, line_starts }
+    }
+
+    /// Converts a 1-based line and column (in bytes) into a byte offset within the file. Returns
+    /// `None` if the position is outside the file or within a multibyte character.
+    pub fn byte_offset(&self, position: &Position) -> Option<usize> {
+        let line_start = *self
+            .line_starts
+            .get((position.line as usize).checked_sub(1)?)?;
+        let offset = line_start + (position.col as usize).checked_sub(1)?;
+        self.code.is_char_boundary(offset).then_some(offset)
+    }
+
+    /// Converts a 1-based line and column (in `unit`) into a byte offset within the file. Returns
+    /// `None` if the position is outside the file or within a character.
+    pub fn byte_offset_in(&self, position: &Position, unit: ColumnUnit) -> Option<usize> {
+        if unit == ColumnUnit::Bytes {
+            return self.byte_offset(position);
+        }
+        let line_start = self.text_start(position)?;
+        let col = (position.col as usize).checked_sub(1)?;
+        let mut units = 0;
+        for (idx, ch) in self.code[line_start..].char_indices() {
+            if units >= col {
+                return (units == col).then_some(line_start + idx);
+            }
+            units += unit.len(ch);
+        }
+        (units == col).then_some(self.code.len())
+    }
+
+    /// Returns the 1-based column of a position (in bytes) in `unit`.
+    pub fn col_in(&self, position: &Position, unit: ColumnUnit) -> Option<u32> {
+        let offset = self.byte_offset(position)?;
+        let line_start = self.text_start(position)?.min(offset);
+        let col = self.code[line_start..offset]
+            .chars()
+            .map(|ch| unit.len(ch))
+            .sum::<usize>();
+        u32::try_from(col + 1).ok()
+    }
+
+    /// Returns the byte offset of the start of the text of the line of a position: the start of
+    /// the line, after the byte order mark on the first line.
+    fn text_start(&self, position: &Position) -> Option<usize> {
+        let line = (position.line as usize).checked_sub(1)?;
+        let line_start = *self.line_starts.get(line)?;
+        match line {
+            0 if self.code.starts_with(BOM) => Some(BOM.len_utf8()),
+            _ => Some(line_start),
+        }
+    }
+
+    /// Returns the 0-based column of a position in UTF-16 code units.
+    pub fn utf16_col(&self, position: &Position) -> Option<u32> {
+        self.col_in(position, ColumnUnit::Utf16).map(|col| col - 1)
+    }
+
+    /// Returns the offsets of the range between two positions.
+    pub fn offsets(&self, start: &Position, end: &Position) -> Option<Offsets> {
+        Some(Offsets {
+            start_byte: self.byte_offset(start)?,
+            end_byte: self.byte_offset(end)?,
+            start_utf16_col: self.utf16_col(start)?,
+            end_utf16_col: self.utf16_col(end)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(line: u32, col: u32) -> Position {
+        Position { line, col }
+    }
+
+    #[test]
+    fn line_index_ascii() {
+        let index = LineIndex::new("abc\ndef\n");
+        assert_eq!(index.byte_offset(&position(1, 1)), Some(0));
+        assert_eq!(index.byte_offset(&position(2, 3)), Some(6));
+        assert_eq!(index.utf16_col(&position(2, 3)), Some(2));
+        assert_eq!(index.byte_offset(&position(0, 1)), None);
+        assert_eq!(index.byte_offset(&position(5, 1)), None);
+    }
+
+    /// Byte columns and UTF-16 columns differ after multibyte characters.
+    #[test]
+    fn line_index_multibyte() {
+        // "é" is 2 bytes and 1 UTF-16 code unit, "😀" is 4 bytes and 2 UTF-16 code units.
+        let code = "x\né😀 = 1\n";
+        let index = LineIndex::new(code);
+        let start = position(2, 8);
+        let end = position(2, 11);
+        assert_eq!(
+            index.offsets(&start, &end),
+            Some(Offsets {
+                start_byte: 9,
+                end_byte: 12,
+                start_utf16_col: 4,
+                end_utf16_col: 7,
+            })
+        );
+        assert_eq!(&code[9..12], "= 1");
+        // within "é"
+        assert_eq!(index.byte_offset(&position(2, 2)), None);
+    }
+
+    /// Columns are converted between units in both directions.
+    #[test]
+    fn line_index_column_units() {
+        let code = "x\né😀 = 1\n";
+        let index = LineIndex::new(code);
+        // the "=" is at byte column 8
+        let equal = position(2, 8);
+        assert_eq!(index.col_in(&equal, ColumnUnit::Bytes), Some(8));
+        assert_eq!(index.col_in(&equal, ColumnUnit::Chars), Some(4));
+        assert_eq!(index.col_in(&equal, ColumnUnit::Utf16), Some(5));
+
+        for (col, unit) in [
+            (8, ColumnUnit::Bytes),
+            (4, ColumnUnit::Chars),
+            (5, ColumnUnit::Utf16),
+        ] {
+            assert_eq!(index.byte_offset_in(&position(2, col), unit), Some(9));
+        }
+        // within "😀"
+        assert_eq!(
+            index.byte_offset_in(&position(2, 3), ColumnUnit::Utf16),
+            None
+        );
+        // the end of the file
+        assert_eq!(
+            index.byte_offset_in(&position(3, 1), ColumnUnit::Chars),
+            Some(code.len())
+        );
+    }
+
+    /// The byte order mark is only a column in bytes.
+    #[test]
+    fn line_index_byte_order_mark() {
+        let index = LineIndex::new("\u{feff}x = 1\ny = 2");
+        let x = position(1, 4);
+        assert_eq!(index.col_in(&x, ColumnUnit::Bytes), Some(4));
+        assert_eq!(index.col_in(&x, ColumnUnit::Chars), Some(1));
+        assert_eq!(index.col_in(&x, ColumnUnit::Utf16), Some(1));
+        assert_eq!(index.col_in(&position(1, 1), ColumnUnit::Chars), Some(1));
+        assert_eq!(
+            index.byte_offset_in(&position(1, 1), ColumnUnit::Utf16),
+            Some(3)
+        );
+        assert_eq!(index.utf16_col(&position(2, 1)), Some(0));
     }
 }
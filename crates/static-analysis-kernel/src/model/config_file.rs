@@ -5,6 +5,7 @@ use std::borrow::Borrow;
 use std::fmt;
 use std::path::{Path, PathBuf};
 
+use crate::model::common::Language;
 use crate::model::rule::{RuleCategory, RuleSeverity};
 
 // A pattern for an 'only' or 'ignore' field. The 'glob' field contains a precompiled glob pattern,
@@ -27,17 +28,29 @@ pub struct PathConfig {
 // A type that stores values that depend on the position in the repository tree.
 pub type BySubtree<T> = SequenceTrie<PathComponent, T>;
 
+// The values of an argument, by path prefix or glob pattern.
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct ArgumentValues {
+    // Values for the files in a directory. Longer prefixes take precedence over shorter ones.
+    pub by_subtree: BySubtree<String>,
+    // Values for the files that match a glob pattern. They take precedence over the prefixes, and
+    // later patterns over earlier ones.
+    pub by_glob: Vec<(PathPattern, String)>,
+}
+
 // Configuration for a single rule.
 #[derive(Debug, PartialEq, Default, Clone)]
 pub struct RuleConfig {
     // Paths to include/exclude for this rule.
     pub paths: PathConfig,
     // Arguments to pass to this rule.
-    pub arguments: IndexMap<String, BySubtree<String>>,
+    pub arguments: IndexMap<String, ArgumentValues>,
     // Override this rule's severity.
     pub severity: Option<RuleSeverity>,
     // Override this rule's category.
     pub category: Option<RuleCategory>,
+    // Analyze generated files with this rule, even if generated files are ignored.
+    pub analyze_generated_files: Option<bool>,
 }
 
 // Configuration for a ruleset.
@@ -47,6 +60,118 @@ pub struct RulesetConfig {
     pub paths: PathConfig,
     // Rule-specific configurations.
     pub rules: IndexMap<String, RuleConfig>,
+    // Arguments to pass to all the rules in this ruleset, overridden by the arguments of the rules.
+    pub arguments: IndexMap<String, ArgumentValues>,
+    // Analyze generated files with the rules of this ruleset, even if generated files are ignored.
+    pub analyze_generated_files: Option<bool>,
+}
+
+// A budget of violations, evaluated after the analysis.
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct PolicyConfig {
+    // The name of the policy, used in the policy report.
+    pub name: String,
+    // Only count the violations of this category.
+    pub category: Option<RuleCategory>,
+    // Only count the violations of this severity.
+    pub severity: Option<RuleSeverity>,
+    // Only count the violations in these paths.
+    pub paths: PathConfig,
+    // The policy fails when more violations are found.
+    pub max_violations: u64,
+}
+
+// A change of the severity of the violations in some paths, applied after the analysis.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SeverityRemapConfig {
+    // Only change the severity of the violations in these paths.
+    pub paths: PathConfig,
+    // The change of the severity.
+    pub remap: SeverityRemap,
+}
+
+// A change of the severity of a violation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SeverityRemap {
+    // Replace the severity.
+    Set(RuleSeverity),
+    // Lower the severity by this number of levels, down to NOTICE.
+    Downgrade(u8),
+    // Raise the severity by this number of levels, up to ERROR.
+    Upgrade(u8),
+}
+
+impl SeverityRemap {
+    // Returns the remapped severity. A violation without severity (NONE) is only remapped by `Set`.
+    pub fn apply(&self, severity: RuleSeverity) -> RuleSeverity {
+        const LEVELS: [RuleSeverity; 3] = [
+            RuleSeverity::Notice,
+            RuleSeverity::Warning,
+            RuleSeverity::Error,
+        ];
+        let Some(level) = LEVELS.iter().position(|s| *s == severity) else {
+            return match self {
+                SeverityRemap::Set(new_severity) => *new_severity,
+                _ => severity,
+            };
+        };
+        match self {
+            SeverityRemap::Set(new_severity) => *new_severity,
+            SeverityRemap::Downgrade(levels) => LEVELS[level.saturating_sub(*levels as usize)],
+            SeverityRemap::Upgrade(levels) => {
+                LEVELS[(level + *levels as usize).min(LEVELS.len() - 1)]
+            }
+        }
+    }
+}
+
+// Known false positives of the secrets scanner, which are reported as suppressed.
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct SecretsAllowlistConfig {
+    // Fingerprints of the secrets to ignore.
+    pub fingerprints: Vec<String>,
+    // Ignore the secrets in these directories and patterns.
+    pub paths: Vec<PathPattern>,
+    // Regular expressions matched against the text of the secrets.
+    pub patterns: Vec<String>,
+}
+
+// The restrictions of the validation of the secrets.
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct SecretsValidationConfig {
+    // The hosts the validators may send requests to, where `*` matches any sequence of
+    // characters. If `None`, any host is allowed; if empty, no request is sent.
+    pub allowed_hosts: Option<Vec<String>>,
+}
+
+// File names and extensions analyzed as a language. They take precedence over the built-in
+// extensions and file names of the languages.
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct FileLanguagesConfig {
+    // Extensions, without the leading dot, and the language of their files.
+    pub extensions: IndexMap<String, Language>,
+    // Exact file names, and the language of the files.
+    pub filenames: IndexMap<String, Language>,
+}
+
+impl FileLanguagesConfig {
+    // Returns the language a file is mapped to, if any. A file name takes precedence over an
+    // extension, and extensions are not case-sensitive.
+    pub fn language_for(&self, path: &Path) -> Option<Language> {
+        let file_name = path.file_name()?.to_str()?;
+        if let Some(language) = self.filenames.get(file_name) {
+            return Some(*language);
+        }
+        let (_, extension) = file_name.rsplit_once('.')?;
+        self.extensions
+            .iter()
+            .find(|(e, _)| e.eq_ignore_ascii_case(extension))
+            .map(|(_, language)| *language)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty() && self.filenames.is_empty()
+    }
 }
 
 // The parsed configuration file without any legacy fields.
@@ -56,12 +181,27 @@ pub struct ConfigFile {
     pub rulesets: IndexMap<String, RulesetConfig>,
     // Paths to include/exclude from analysis.
     pub paths: PathConfig,
+    // Arguments to pass to all the rules, overridden by the arguments of the rulesets and rules.
+    pub arguments: IndexMap<String, ArgumentValues>,
     // Ignore all the paths in the .gitignore file.
     pub ignore_gitignore: Option<bool>,
     // Analyze only files up to this size.
     pub max_file_size_kb: Option<u64>,
     // Do not analyze generated files.
     pub ignore_generated_files: Option<bool>,
+    // Policies that fail the analysis.
+    pub policies: Vec<PolicyConfig>,
+    // Changes of the severity of the violations per path, applied in order: the first one that
+    // matches the file of a violation applies.
+    pub severity_remaps: Vec<SeverityRemapConfig>,
+    // Secrets to ignore.
+    pub secrets_allowlist: SecretsAllowlistConfig,
+    // Restrictions of the validation of the secrets.
+    pub secrets_validation: SecretsValidationConfig,
+    // Additional file names and extensions of the languages.
+    pub file_languages: FileLanguagesConfig,
+    // Native plugins to load, relative to the root of the repository.
+    pub plugins: Vec<String>,
 }
 
 impl fmt::Display for ConfigFile {
@@ -113,6 +253,36 @@ impl PartialEq for PathPattern {
     }
 }
 
+impl ArgumentValues {
+    // Sets the value for a path prefix or, if the path contains glob metacharacters, a pattern.
+    pub fn insert(&mut self, path: &str, value: String) {
+        if is_glob(path) {
+            let pattern = PathPattern::from(path.to_string());
+            self.by_glob.retain(|(p, _)| *p != pattern);
+            self.by_glob.push((pattern, value));
+        } else {
+            self.by_subtree.insert(&split_path(path), value);
+        }
+    }
+
+    // Returns the paths and their values: the prefixes, then the patterns.
+    pub fn iter(&self) -> impl Iterator<Item = (String, &String)> {
+        self.by_subtree
+            .iter()
+            .map(|(k, v)| (join_path(&k.into_iter().cloned().collect()), v))
+            .chain(
+                self.by_glob
+                    .iter()
+                    .map(|(pattern, v)| (String::from(pattern.clone()), v)),
+            )
+    }
+}
+
+// Returns whether a path of the configuration file is a glob pattern rather than a prefix.
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '[', '{'])
+}
+
 impl PathConfig {
     pub fn allows_file(&self, file_name: &str) -> bool {
         !self.ignore.iter().any(|pattern| pattern.matches(file_name))
@@ -162,3 +332,16 @@ where
     }
     out
 }
+
+// Generates an ArgumentValues from an iterable of tuples of path prefix or glob pattern and value.
+pub fn argument_values<S, I>(src: I) -> ArgumentValues
+where
+    S: Borrow<str>,
+    I: IntoIterator<Item = (S, String)>,
+{
+    let mut out = ArgumentValues::default();
+    for (k, v) in src {
+        out.insert(k.borrow(), v);
+    }
+    out
+}
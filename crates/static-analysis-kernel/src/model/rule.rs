@@ -1,90 +1,21 @@
-use crate::model::common::Language;
+use crate::model::common::{Interner, Language, SharedStr};
 use base64::engine::general_purpose;
 use base64::Engine;
 
-use crate::analysis::tree_sitter::{get_query, TSQuery};
+use crate::analysis::tree_sitter::{get_query, IncompatibleQueryError, TSQuery};
+use crate::model::analysis::ERROR_ANALYSIS_CANCELLED;
 use crate::model::rule_test::RuleTest;
 use crate::model::violation::Violation;
 use anyhow::anyhow;
 use derive_builder::Builder;
+use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use std::fmt;
 use std::path::Path;
 
-/// In the RuleCategory, we keep unknown. Old rules keep putting
-/// whatever they want as category. As a matter of fact, old rules that
-/// use old values (e.g. DEPLOYMENT) will fail deserialization. We then match
-/// them on the `Unknown` value.
-///
-/// The `Unknown` value is never exposed tho because we always rewrite the
-/// category of all violations by the categories of the rules they come from.
-#[derive(Copy, Clone, Deserialize, Debug, Serialize, Eq, PartialEq)]
-pub enum RuleCategory {
-    #[serde(rename = "BEST_PRACTICES")]
-    BestPractices,
-    #[serde(rename = "CODE_STYLE")]
-    CodeStyle,
-    #[serde(rename = "ERROR_PRONE")]
-    ErrorProne,
-    #[serde(rename = "PERFORMANCE")]
-    Performance,
-    #[serde(rename = "SECURITY")]
-    Security,
-    #[serde(other)]
-    #[serde(skip_serializing)]
-    Unknown, // kept only for backward compatibility
-}
-
-impl fmt::Display for RuleCategory {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Self::BestPractices => write!(f, "best_practices"),
-            Self::CodeStyle => write!(f, "code_style"),
-            Self::ErrorProne => write!(f, "error_prone"),
-            Self::Performance => write!(f, "performance"),
-            Self::Security => write!(f, "security"),
-            Self::Unknown => write!(f, "unknown"),
-        }
-    }
-}
-
-#[derive(Copy, Clone, Deserialize, Debug, Serialize, Eq, PartialEq)]
-pub enum RuleSeverity {
-    #[serde(rename = "ERROR")]
-    Error,
-    #[serde(rename = "WARNING")]
-    Warning,
-    #[serde(rename = "NOTICE")]
-    Notice,
-    #[serde(rename = "NONE")]
-    None,
-}
-
-impl TryFrom<&str> for RuleSeverity {
-    type Error = &'static str;
-
-    fn try_from(s: &str) -> Result<Self, &'static str> {
-        match s.to_lowercase().as_str() {
-            "error" => Ok(RuleSeverity::Error),
-            "warning" => Ok(RuleSeverity::Warning),
-            "notice" => Ok(RuleSeverity::Notice),
-            "none" => Ok(RuleSeverity::None),
-            _ => Err("unknown severity"),
-        }
-    }
-}
-
-impl fmt::Display for RuleSeverity {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Self::Error => write!(f, "error"),
-            Self::Warning => write!(f, "warning"),
-            Self::Notice => write!(f, "notice"),
-            Self::None => write!(f, "none"),
-        }
-    }
-}
+pub use ::common::model::rule::{RuleCategory, RuleSeverity};
 
 #[derive(Copy, Clone, Deserialize, Debug, Serialize, Eq, PartialEq)]
 pub enum RuleType {
@@ -151,10 +82,30 @@ pub struct Rule {
     #[serde(rename = "tree_sitter_query")]
     pub tree_sitter_query_base64: Option<String>,
     #[serde(default)]
+    #[builder(default)]
+    pub tree_sitter_queries: Vec<NamedQuery>,
+    #[serde(default)]
     pub arguments: Vec<Argument>,
     pub tests: Vec<RuleTest>,
     #[serde(default)]
     pub is_testing: bool,
+    /// Whether the rule is deprecated: it's still executed, but should be replaced by [`Rule::replaced_by`].
+    #[serde(default)]
+    #[builder(default)]
+    pub deprecated: bool,
+    /// The name of the rule that replaces this deprecated rule, if any.
+    #[serde(default)]
+    #[builder(default)]
+    pub replaced_by: Option<String>,
+    /// Whether the rule may use the JavaScript APIs with side effects (network, timers, ...). They
+    /// are not available to the rules by default.
+    #[serde(default)]
+    #[builder(default)]
+    pub allow_side_effects: bool,
+    /// The text the files must contain for the rule to be executed on them (see [`Prerequisite`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub prerequisites: Vec<Prerequisite>,
 }
 
 #[derive(Clone, Deserialize, Debug, Serialize, Builder)]
@@ -165,19 +116,104 @@ pub struct Argument {
     pub description_base64: String,
 }
 
+/// An additional tree-sitter query of a rule. The matches of the query are passed to
+/// the `visit` function of the rule along with the name of the query.
+#[derive(Clone, Deserialize, Debug, Serialize, Builder)]
+pub struct NamedQuery {
+    pub name: String,
+    #[serde(rename = "query")]
+    pub query_base64: String,
+}
+
+/// A check of the content of a file, done before the file is parsed: a rule with prerequisites is
+/// only executed on the files that contain one of them (e.g. the name of the module whose calls
+/// the rule checks), which skips the query and the JavaScript of the rule on the other files.
+///
+/// It's serialized as `{"substring": "requests"}` or `{"regex": "import\\s+requests"}`.
+#[derive(Clone, Deserialize, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Prerequisite {
+    /// The file contains the text.
+    Substring(String),
+    /// The file contains a match of the regular expression.
+    Regex(String),
+}
+
+/// The compiled prerequisites of a rule. A rule without prerequisites is executed on every file.
+#[derive(Debug, Default)]
+pub struct RulePrerequisites {
+    substrings: Vec<String>,
+    regexes: Vec<Regex>,
+}
+
+impl RulePrerequisites {
+    pub fn new(prerequisites: &[Prerequisite]) -> anyhow::Result<Self> {
+        let mut compiled = Self::default();
+        for prerequisite in prerequisites {
+            match prerequisite {
+                Prerequisite::Substring(text) => compiled.substrings.push(text.clone()),
+                Prerequisite::Regex(regex) => compiled.regexes.push(
+                    Regex::new(regex)
+                        .map_err(|err| anyhow!("invalid prerequisite regex {regex}: {err}"))?,
+                ),
+            }
+        }
+        Ok(compiled)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.substrings.is_empty() && self.regexes.is_empty()
+    }
+
+    /// Returns true if the rule must be executed on the code: it has no prerequisites, or the code
+    /// contains one of them. The substrings are checked first, as they're cheaper.
+    pub fn matches(&self, code: &str) -> bool {
+        self.is_empty()
+            || self
+                .substrings
+                .iter()
+                .any(|text| code.contains(text.as_str()))
+            || self.regexes.iter().any(|regex| regex.is_match(code))
+    }
+}
+
 // This structure is used internally to handle rules.
 // Since we do not support AST of Pattern rules anymore, we
 // only have the tree-sitter query that is already pre-compiled.
-#[derive(Debug)]
+#[derive(Debug, Builder)]
+#[builder(pattern = "owned")]
 pub struct RuleInternal {
     pub name: String,
+    #[builder(default)]
     pub short_description: Option<String>,
+    #[builder(default)]
     pub description: Option<String>,
     pub category: RuleCategory,
     pub severity: RuleSeverity,
     pub language: Language,
     pub code: String,
     pub tree_sitter_query: TSQuery,
+    // the named queries of the rule, with their name.
+    #[builder(default)]
+    pub named_queries: Vec<(String, TSQuery)>,
+    // whether the rule may use the JavaScript APIs with side effects.
+    #[builder(default)]
+    pub allow_side_effects: bool,
+    // the text the files must contain for the rule to be executed on them.
+    #[builder(default)]
+    pub prerequisites: RulePrerequisites,
+}
+
+impl RuleInternal {
+    /// Returns the digest of the queries of the rule, if they were all compiled from their source:
+    /// the rules of a language with the same digest have the same matches on a file.
+    pub fn queries_digest(&self) -> Option<String> {
+        let mut digest = self.tree_sitter_query.digest()?.to_string();
+        for (name, query) in &self.named_queries {
+            digest.push_str(&format!(",{name}:{}", query.digest()?));
+        }
+        Some(digest)
+    }
 }
 
 impl Rule {
@@ -229,14 +265,23 @@ impl Rule {
             .map(|s| anyhow::Ok(String::from_utf8(general_purpose::STANDARD.decode(s)?)?))
             .transpose()?;
 
-        let tree_sitter_query = String::from_utf8(
-            general_purpose::STANDARD.decode(
-                self.tree_sitter_query_base64
-                    .as_ref()
-                    .ok_or_else(|| anyhow!("tree sitter query is empty"))?,
-            )?,
-        )?;
+        let tree_sitter_query = match &self.tree_sitter_query_base64 {
+            Some(query) => String::from_utf8(general_purpose::STANDARD.decode(query)?)?,
+            // A rule that only uses named queries does not need a main query.
+            None if !self.tree_sitter_queries.is_empty() => String::new(),
+            None => return Err(anyhow!("tree sitter query is empty")),
+        };
         let tree_sitter_query = get_query(&tree_sitter_query, &self.language)?;
+        let named_queries = self
+            .tree_sitter_queries
+            .iter()
+            .map(|q| {
+                let query = String::from_utf8(general_purpose::STANDARD.decode(&q.query_base64)?)?;
+                let query = get_query(&query, &self.language)
+                    .map_err(|err| with_query_name(err, &q.name))?;
+                Ok((q.name.clone(), query))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
         Ok(RuleInternal {
             name: self.name.clone(),
@@ -247,9 +292,36 @@ impl Rule {
             language: self.language,
             code,
             tree_sitter_query,
+            named_queries,
+            allow_side_effects: self.allow_side_effects,
+            prerequisites: RulePrerequisites::new(&self.prerequisites)?,
         })
     }
 
+    /// Returns the errors of the queries of the rule that don't compile with the grammar of its
+    /// language, so that the rules written for another version of a grammar are reported when
+    /// they are loaded.
+    pub fn get_incompatible_queries(&self) -> Vec<IncompatibleQueryError> {
+        let main_query = self.tree_sitter_query_base64.iter().map(|q| (None, q));
+        let named_queries = self
+            .tree_sitter_queries
+            .iter()
+            .map(|q| (Some(&q.name), &q.query_base64));
+        main_query
+            .chain(named_queries)
+            .filter_map(|(name, query)| {
+                let query =
+                    String::from_utf8(general_purpose::STANDARD.decode(query).ok()?).ok()?;
+                let err = get_query(&query, &self.language).err()?;
+                let err = err.downcast::<IncompatibleQueryError>().ok()?;
+                Some(IncompatibleQueryError {
+                    query_name: name.cloned(),
+                    ..err
+                })
+            })
+            .collect()
+    }
+
     /// Check the checksum of the rule is correct. The checksum of a rule is calculated
     /// by calculating the SHA256 of the base64 of the rule code.
     pub fn verify_checksum(&self) -> bool {
@@ -267,7 +339,40 @@ impl Rule {
     /// if we think that the rules change and may trigger new results.
     pub fn get_config_hash_string(&self) -> String {
         let pattern_string = self.pattern.clone().unwrap_or("no pattern".to_string());
-        format!("{}:{}:{}", self.name, pattern_string, self.code_base64).to_string()
+        let mut hash_string = format!("{}:{}:{}", self.name, pattern_string, self.code_base64);
+        // the named queries change the nodes the rule visits
+        if !self.tree_sitter_queries.is_empty() {
+            let queries = self
+                .tree_sitter_queries
+                .iter()
+                .map(|q| format!("{}={}", q.name, q.query_base64))
+                .collect::<Vec<_>>();
+            hash_string = format!("{}:{}", hash_string, queries.join(","));
+        }
+        // the prerequisites change the files a rule is executed on
+        if !self.prerequisites.is_empty() {
+            hash_string = format!(
+                "{}:{}",
+                hash_string,
+                serde_json::to_string(&self.prerequisites).expect("prerequisites are serializable")
+            );
+        }
+        // the side effects change the APIs available to the rule
+        if self.allow_side_effects {
+            hash_string.push_str(":side-effects");
+        }
+        hash_string
+    }
+}
+
+/// Sets the name of the query of an [`IncompatibleQueryError`], for the named queries of a rule.
+fn with_query_name(err: anyhow::Error, name: &str) -> anyhow::Error {
+    match err.downcast::<IncompatibleQueryError>() {
+        Ok(err) => anyhow::Error::new(IncompatibleQueryError {
+            query_name: Some(name.to_string()),
+            ..err
+        }),
+        Err(err) => err,
     }
 }
 
@@ -277,18 +382,138 @@ impl fmt::Display for Rule {
     }
 }
 
-#[derive(Clone, Builder, Serialize, Debug)]
+#[derive(Clone, Builder, Deserialize, Serialize, Debug, JsonSchema)]
 #[builder(build_fn(validate = "Self::validate"))]
 pub struct RuleResult {
-    pub rule_name: String,
-    pub filename: String,
+    #[builder(setter(into))]
+    pub rule_name: SharedStr,
+    #[builder(setter(into))]
+    pub filename: SharedStr,
     pub violations: Vec<Violation>,
     pub errors: Vec<String>,
     pub execution_error: Option<String>,
-    pub output: Option<String>,
+    /// The lines written by the rule to the console, if the output is logged
+    /// (see [`AnalysisOptions::log_output`](crate::model::analysis::AnalysisOptions::log_output)).
+    pub output: Option<Vec<ConsoleRecord>>,
     pub execution_time_ms: u128,
     pub parsing_time_ms: u128,
     pub query_node_time_ms: u128,
+    /// True if the file has syntax errors: the rule was executed on the tree recovered by the
+    /// parser, so its violations may be incomplete.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
+    /// The resources used by the execution of the rule, if the rules are profiled
+    /// (see [`AnalysisOptions::profile_rules`](crate::model::analysis::AnalysisOptions::profile_rules)).
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<RuleProfile>,
+}
+
+/// The level of a line written by a rule to the console: the method of `console` that wrote it.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsoleLevel {
+    Debug,
+    Log,
+    Info,
+    Warn,
+    Error,
+}
+
+/// A line written by a rule to the console.
+#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ConsoleRecord {
+    pub level: ConsoleLevel,
+    pub message: String,
+}
+
+impl ConsoleRecord {
+    pub fn new(level: ConsoleLevel, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            message: message.into(),
+        }
+    }
+}
+
+/// The resources used by an execution of a rule on a file.
+#[derive(Clone, Copy, Default, Deserialize, Serialize, Debug, PartialEq, Eq, JsonSchema)]
+pub struct RuleProfile {
+    /// The growth of the used `v8` heap during the execution, in bytes. The memory reclaimed by
+    /// the garbage collector during the execution is not counted.
+    pub heap_allocated_bytes: u64,
+    /// The CPU time of the thread executing the rule.
+    pub cpu_time_ms: u128,
+    /// The wall time of the execution. A wall time much greater than the CPU time means the
+    /// thread was waiting (for instance, on the other threads of the analysis).
+    pub wall_time_ms: u128,
+    /// The number of captures of the matches passed to the rule.
+    pub captures: usize,
+}
+
+impl RuleResult {
+    /// Returns the result of a rule that was not executed because the analysis was cancelled.
+    pub fn cancelled(rule_name: &str, filename: &str) -> Self {
+        Self {
+            rule_name: rule_name.into(),
+            filename: filename.into(),
+            violations: vec![],
+            errors: vec![ERROR_ANALYSIS_CANCELLED.to_string()],
+            execution_error: None,
+            output: None,
+            execution_time_ms: 0,
+            parsing_time_ms: 0,
+            query_node_time_ms: 0,
+            degraded: false,
+            profile: None,
+        }
+    }
+
+    /// Returns true if the rule was not executed because the analysis was cancelled. The result
+    /// is then incomplete.
+    pub fn is_cancelled(&self) -> bool {
+        self.errors.iter().any(|e| e == ERROR_ANALYSIS_CANCELLED)
+    }
+
+    /// Replaces the rule name, the filename and the messages of the violations with their copies
+    /// in `interner`, so that they are shared with the other results.
+    pub fn intern(&mut self, interner: &mut Interner) {
+        interner.share(&mut self.rule_name);
+        interner.share(&mut self.filename);
+        for violation in &mut self.violations {
+            interner.share(&mut violation.message);
+        }
+    }
+
+    /// Returns the messages written by the rule to the console, one per line.
+    pub fn output_text(&self) -> Option<String> {
+        self.output.as_ref().map(|records| {
+            records
+                .iter()
+                .map(|r| r.message.as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    }
+}
+
+/// Sorts rule results in a canonical order, so that the outputs of two analyses of the same code can be
+/// compared: the results are sorted by file and then by rule, and the violations of each result by
+/// position (line, then column) and then by message.
+pub fn sort_rule_results(results: &mut [RuleResult]) {
+    for result in results.iter_mut() {
+        result.violations.sort_by(|a, b| {
+            (a.start.line, a.start.col, a.end.line, a.end.col, &a.message).cmp(&(
+                b.start.line,
+                b.start.col,
+                b.end.line,
+                b.end.col,
+                &b.message,
+            ))
+        });
+    }
+    results.sort_by(|a, b| (&a.filename, &a.rule_name).cmp(&(&b.filename, &b.rule_name)));
 }
 
 impl RuleResultBuilder {
@@ -323,9 +548,14 @@ mod tests {
             pattern: None,
             cwe: None,
             tree_sitter_query_base64: None,
+            tree_sitter_queries: vec![],
             arguments: vec![],
             tests: vec![],
             is_testing: false,
+            deprecated: false,
+            replaced_by: None,
+            allow_side_effects: false,
+            prerequisites: vec![],
         };
         let rule_valid_checksum = Rule {
             name: "myrule".to_string(),
@@ -342,9 +572,14 @@ mod tests {
             pattern: None,
             cwe: None,
             tree_sitter_query_base64: None,
+            tree_sitter_queries: vec![],
             arguments: vec![],
             tests: vec![],
             is_testing: false,
+            deprecated: false,
+            replaced_by: None,
+            allow_side_effects: false,
+            prerequisites: vec![],
         };
         assert!(!rule_invalid_checksum.verify_checksum());
         assert!(rule_valid_checksum.verify_checksum());
@@ -366,9 +601,14 @@ mod tests {
             pattern: None,
             cwe: None,
             tree_sitter_query_base64: None,
+            tree_sitter_queries: vec![],
             arguments: vec![],
             tests: vec![],
             is_testing: false,
+            deprecated: false,
+            replaced_by: None,
+            allow_side_effects: false,
+            prerequisites: vec![],
         };
         let fixed_ruled = rule.fix_cwe();
         assert!(fixed_ruled.cwe.is_none());
@@ -390,9 +630,14 @@ mod tests {
             pattern: None,
             cwe: Some("".to_string()),
             tree_sitter_query_base64: None,
+            tree_sitter_queries: vec![],
             arguments: vec![],
             tests: vec![],
             is_testing: false,
+            deprecated: false,
+            replaced_by: None,
+            allow_side_effects: false,
+            prerequisites: vec![],
         };
         let fixed_ruled = rule.fix_cwe();
         assert!(fixed_ruled.cwe.is_none());
@@ -414,14 +659,141 @@ mod tests {
             pattern: None,
             cwe: Some("1234".to_string()),
             tree_sitter_query_base64: None,
+            tree_sitter_queries: vec![],
             arguments: vec![],
             tests: vec![],
             is_testing: false,
+            deprecated: false,
+            replaced_by: None,
+            allow_side_effects: false,
+            prerequisites: vec![],
         };
         let fixed_ruled = rule.fix_cwe();
         assert!(fixed_ruled.cwe.is_some());
     }
 
+    #[test]
+    fn config_hash_string_prerequisites() {
+        let mut rule = RuleBuilder::default()
+            .name("myrule".to_string())
+            .short_description_base64(None)
+            .description_base64(None)
+            .category(RuleCategory::BestPractices)
+            .severity(RuleSeverity::Warning)
+            .language(Language::Python)
+            .rule_type(RuleType::TreeSitterQuery)
+            .entity_checked(None)
+            .code_base64("mycode".to_string())
+            .checksum("foobar".to_string())
+            .pattern(None)
+            .cwe(None)
+            .tree_sitter_query_base64(None)
+            .arguments(vec![])
+            .tests(vec![])
+            .is_testing(false)
+            .build()
+            .unwrap();
+        let hash_string = rule.get_config_hash_string();
+        assert_eq!(hash_string, "myrule:no pattern:mycode");
+
+        rule.prerequisites = vec![Prerequisite::Substring("requests".to_string())];
+        assert_ne!(rule.get_config_hash_string(), hash_string);
+        let hash_string = rule.get_config_hash_string();
+
+        rule.allow_side_effects = true;
+        assert_ne!(rule.get_config_hash_string(), hash_string);
+    }
+
+    #[test]
+    fn config_hash_string_named_queries() {
+        let mut rule = RuleBuilder::default()
+            .name("myrule".to_string())
+            .short_description_base64(None)
+            .description_base64(None)
+            .category(RuleCategory::BestPractices)
+            .severity(RuleSeverity::Warning)
+            .language(Language::Python)
+            .rule_type(RuleType::TreeSitterQuery)
+            .entity_checked(None)
+            .code_base64("mycode".to_string())
+            .checksum("foobar".to_string())
+            .pattern(None)
+            .cwe(None)
+            .tree_sitter_query_base64(None)
+            .arguments(vec![])
+            .tests(vec![])
+            .is_testing(false)
+            .build()
+            .unwrap();
+        let query = |query: &str| NamedQuery {
+            name: "calls".to_string(),
+            query_base64: encode_base64_string(query.to_string()),
+        };
+        rule.tree_sitter_queries = vec![query("(call) @call")];
+        let hash_string = rule.get_config_hash_string();
+        assert_ne!(hash_string, "myrule:no pattern:mycode");
+
+        rule.tree_sitter_queries = vec![query("(call function: (identifier)) @call")];
+        assert_ne!(rule.get_config_hash_string(), hash_string);
+    }
+
+    #[test]
+    fn test_to_rule_internal_named_queries() {
+        let mut rule = Rule {
+            name: "myrule".to_string(),
+            short_description_base64: None,
+            description_base64: None,
+            category: RuleCategory::BestPractices,
+            severity: RuleSeverity::Warning,
+            language: Language::Python,
+            rule_type: RuleType::TreeSitterQuery,
+            entity_checked: None,
+            code_base64: encode_base64_string("function visit() {}".to_string()),
+            checksum: "foobar".to_string(),
+            pattern: None,
+            cwe: None,
+            tree_sitter_query_base64: None,
+            tree_sitter_queries: vec![],
+            arguments: vec![],
+            tests: vec![],
+            is_testing: false,
+            deprecated: false,
+            replaced_by: None,
+            allow_side_effects: false,
+            prerequisites: vec![],
+        };
+        // a rule needs at least one query
+        assert!(rule.to_rule_internal().is_err());
+
+        rule.tree_sitter_queries = vec![NamedQuery {
+            name: "calls".to_string(),
+            query_base64: encode_base64_string("(call) @call".to_string()),
+        }];
+        let rule_internal = rule.to_rule_internal().unwrap();
+        assert_eq!(rule_internal.named_queries.len(), 1);
+        assert_eq!(rule_internal.named_queries[0].0, "calls");
+
+        rule.tree_sitter_queries[0].query_base64 = encode_base64_string("(call".to_string());
+        assert!(rule.to_rule_internal().is_err());
+        // a syntax error is not an incompatibility with the grammar
+        assert!(rule.get_incompatible_queries().is_empty());
+
+        rule.tree_sitter_queries[0].query_base64 =
+            encode_base64_string("(print_statement) @print".to_string());
+        let incompatible = rule.get_incompatible_queries();
+        assert_eq!(incompatible.len(), 1);
+        assert_eq!(incompatible[0].query_name.as_deref(), Some("calls"));
+        assert_eq!(
+            incompatible[0].node_kind.as_deref(),
+            Some("print_statement")
+        );
+        let err = rule.to_rule_internal().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<IncompatibleQueryError>(),
+            Some(&incompatible[0])
+        );
+    }
+
     #[test]
     fn absolute_path_err() {
         fn builder_with(filename: &str) -> RuleResultBuilder {
@@ -451,4 +823,64 @@ mod tests {
             .contains("expected relative path"));
         assert!(builder_with(rel_path).build().is_ok());
     }
+
+    #[test]
+    fn sort_results_canonical_order() {
+        use crate::model::common::Position;
+        use crate::model::violation::ViolationBuilder;
+
+        let violation = |line: u32, col: u32| {
+            ViolationBuilder::default()
+                .start(Position { line, col })
+                .end(Position { line, col: col + 1 })
+                .message("message".to_string())
+                .severity(RuleSeverity::Warning)
+                .category(RuleCategory::BestPractices)
+                .fixes(vec![])
+                .build()
+                .unwrap()
+        };
+        let result = |rule_name: &str, filename: &str, violations: Vec<Violation>| RuleResult {
+            violations,
+            ..RuleResult::cancelled(rule_name, filename)
+        };
+        let mut results = vec![
+            result("rule-b", "b.py", vec![]),
+            result("rule-b", "a.py", vec![violation(3, 1), violation(1, 5)]),
+            result("rule-a", "a.py", vec![violation(2, 1), violation(1, 2)]),
+        ];
+        sort_rule_results(&mut results);
+        let order = results
+            .iter()
+            .map(|r| (r.filename.as_str(), r.rule_name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec![("a.py", "rule-a"), ("a.py", "rule-b"), ("b.py", "rule-b")]
+        );
+        let positions = results[1]
+            .violations
+            .iter()
+            .map(|v| (v.start.line, v.start.col))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(1, 5), (3, 1)]);
+    }
+
+    #[test]
+    fn parse_prerequisites() {
+        let prerequisites: Vec<Prerequisite> =
+            serde_json::from_str(r#"[{"substring": "requests"}, {"regex": "^import\\s+os"}]"#)
+                .unwrap();
+        assert_eq!(
+            prerequisites,
+            vec![
+                Prerequisite::Substring("requests".to_string()),
+                Prerequisite::Regex(r"^import\s+os".to_string())
+            ]
+        );
+        let prerequisites = RulePrerequisites::new(&prerequisites).unwrap();
+        assert!(prerequisites.matches("import requests"));
+        assert!(!prerequisites.matches("from os import path"));
+        assert!(RulePrerequisites::default().matches(""));
+    }
 }
@@ -1,40 +1,30 @@
-use crate::model::common::Position;
-use crate::model::rule::{RuleCategory, RuleSeverity};
+pub use ::common::model::violation::{
+    Edit, EditBuilder, EditType, Fix, FixApplicability, FixBuilder, LastCommit, RelatedLocation,
+    RelatedLocationBuilder, Snippet, Violation, ViolationBuilder,
+};
 
-use derive_builder::Builder;
-use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-#[derive(Copy, Clone, Deserialize, Debug, Serialize, Eq, PartialEq)]
-pub enum EditType {
-    #[serde(rename = "ADD")]
-    Add,
-    #[serde(rename = "REMOVE")]
-    Remove,
-    #[serde(rename = "UPDATE")]
-    Update,
-}
-
-#[derive(Deserialize, Debug, Serialize, Clone, Builder)]
-pub struct Edit {
-    pub start: Position,
-    pub end: Option<Position>,
-    #[serde(rename = "editType")]
-    pub edit_type: EditType,
-    pub content: Option<String>,
-}
-
-#[derive(Deserialize, Debug, Serialize, Clone, Builder)]
-pub struct Fix {
-    pub description: String,
-    pub edits: Vec<Edit>,
-}
-
-#[derive(Deserialize, Debug, Serialize, Clone, Builder)]
-pub struct Violation {
-    pub start: Position,
-    pub end: Position,
-    pub message: String,
-    pub severity: RuleSeverity,
-    pub category: RuleCategory,
-    pub fixes: Vec<Fix>,
+/// Computes the fingerprint of a violation reported by a rule, where `line_content` is the content of
+/// the first line of the violation.
+///
+/// The fingerprint is the hex-encoded SHA-256 of `<rule>|<filename>|<len(filename)>|<line>|<len(line)>`,
+/// where `<line>` is `line_content` without any whitespace, and lengths are in bytes. It doesn't depend
+/// on the position of the violation, so it remains the same when lines are added above the violation
+/// or when the indentation of its line changes. Violations of a rule on identical lines of a file
+/// have the same fingerprint.
+pub fn compute_fingerprint(rule_name: &str, filename: &str, line_content: &str) -> String {
+    let line_content = line_content
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .collect::<String>();
+    let hash_content = format!(
+        "{}|{}|{}|{}|{}",
+        rule_name,
+        filename,
+        filename.len(),
+        line_content,
+        line_content.len()
+    );
+    format!("{:x}", Sha256::digest(hash_content.as_bytes()))
 }
@@ -1,7 +1,10 @@
 use indexmap::IndexMap;
 
-use crate::model::config_file::{PathConfig, RulesetConfig};
+use crate::model::config_file::{PathConfig, PathPattern, RulesetConfig};
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 
 /// An object that provides operations to filter rules by the path of the file to check.
 #[derive(Default, Clone)]
@@ -51,6 +54,31 @@ impl PathRestrictions {
             }
         }
     }
+
+    /// Returns a digest of the restrictions, which change the files analyzed by the rules.
+    pub fn digest(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        let mut rulesets = self.rulesets.iter().collect::<Vec<_>>();
+        rulesets.sort_unstable_by_key(|(name, _)| *name);
+        for (name, restrictions) in rulesets {
+            let mut rules = restrictions
+                .rules
+                .iter()
+                .map(|(name, paths)| (name, path_config_key(paths)))
+                .collect::<Vec<_>>();
+            rules.sort_unstable_by_key(|(name, _)| *name);
+            (name, path_config_key(&restrictions.paths), rules).hash(&mut hasher);
+        }
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+/// Returns the patterns of a path configuration, to hash it.
+fn path_config_key(paths: &PathConfig) -> (Option<Vec<&str>>, Vec<&str>) {
+    fn patterns(patterns: &[PathPattern]) -> Vec<&str> {
+        patterns.iter().map(Borrow::<str>::borrow).collect()
+    }
+    (paths.only.as_deref().map(patterns), patterns(&paths.ignore))
 }
 
 fn split_rule_name(name: &str) -> (&str, &str) {
@@ -87,6 +115,8 @@ mod tests {
                         only: None,
                     },
                     rules: indexmap::IndexMap::new(),
+                    arguments: indexmap::IndexMap::new(),
+                    analyze_generated_files: None,
                 },
             ),
             (
@@ -97,6 +127,8 @@ mod tests {
                         only: Some(vec!["*/code/**".to_string().into()]),
                     },
                     rules: indexmap::IndexMap::new(),
+                    arguments: indexmap::IndexMap::new(),
+                    analyze_generated_files: None,
                 },
             ),
             (
@@ -107,6 +139,8 @@ mod tests {
                         only: Some(vec!["test/**".to_string().into()]),
                     },
                     rules: indexmap::IndexMap::new(),
+                    arguments: indexmap::IndexMap::new(),
+                    analyze_generated_files: None,
                 },
             ),
         ]);
@@ -143,6 +177,7 @@ mod tests {
                             arguments: Default::default(),
                             severity: None,
                             category: None,
+                            analyze_generated_files: None,
                         },
                     ),
                     (
@@ -155,6 +190,7 @@ mod tests {
                             arguments: Default::default(),
                             severity: None,
                             category: None,
+                            analyze_generated_files: None,
                         },
                     ),
                     (
@@ -167,9 +203,12 @@ mod tests {
                             arguments: Default::default(),
                             severity: None,
                             category: None,
+                            analyze_generated_files: None,
                         },
                     ),
                 ]),
+                arguments: Default::default(),
+                analyze_generated_files: None,
             },
         )]);
         let restrictions = PathRestrictions::from_ruleset_configs(&config);
@@ -209,8 +248,11 @@ mod tests {
                         arguments: Default::default(),
                         severity: None,
                         category: None,
+                        analyze_generated_files: None,
                     },
                 )]),
+                arguments: Default::default(),
+                analyze_generated_files: None,
             },
         )]);
         let restrictions = PathRestrictions::from_ruleset_configs(&config);
@@ -263,4 +305,37 @@ mod tests {
         assert!(!restrictions.rule_applies("only-test-starstar-foo-glob/rule", "uno/proto.go"));
         assert!(restrictions.rule_applies("ignore-uno-code-prefix/rule", "uno/proto.go"));
     }
+
+    // The digest changes with the restrictions of the rulesets and of their rules.
+    #[test]
+    fn digest() {
+        let config = |ignore: &str| {
+            indexmap::IndexMap::from([(
+                "ruleset".to_string(),
+                RulesetConfig {
+                    rules: indexmap::IndexMap::from([(
+                        "rule".to_string(),
+                        RuleConfig {
+                            paths: PathConfig {
+                                only: None,
+                                ignore: vec![ignore.to_string().into()],
+                            },
+                            ..Default::default()
+                        },
+                    )]),
+                    ..Default::default()
+                },
+            )])
+        };
+        let digest = PathRestrictions::from_ruleset_configs(&config("test/**")).digest();
+        assert_eq!(
+            PathRestrictions::from_ruleset_configs(&config("test/**")).digest(),
+            digest
+        );
+        assert_ne!(
+            PathRestrictions::from_ruleset_configs(&config("src/**")).digest(),
+            digest
+        );
+        assert_ne!(PathRestrictions::default().digest(), digest);
+    }
 }
@@ -0,0 +1,214 @@
+//! The native extensions of the analyzer: checkers that report violations like rules, for the
+//! analyses that cannot be written as JavaScript rules (e.g. with the types of an external
+//! language server). The checkers are registered in a [`PluginRegistry`], and executed on each
+//! file after the rules.
+
+use crate::analysis::analyze::get_lines_to_ignore;
+use crate::model::common::Language;
+use crate::model::rule::RuleResult;
+use crate::model::violation::{compute_fingerprint, Violation};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A native checker. A checker is executed on several files at the same time, from different
+/// threads.
+pub trait Checker: Send + Sync {
+    /// The name of the checker, used as the name of the rule of its violations
+    /// (`<plugin>/<checker>`).
+    fn name(&self) -> &str;
+
+    /// The languages of the files analyzed by the checker.
+    fn languages(&self) -> &[Language];
+
+    /// A digest of the implementation of the checker (e.g. the hash of its library): the results
+    /// of a checker are only reused with the same digest.
+    fn digest(&self) -> &str;
+
+    /// Returns the violations of the file, or the error of the checker.
+    fn check(
+        &self,
+        language: Language,
+        filename: &str,
+        code: &str,
+    ) -> Result<Vec<Violation>, String>;
+}
+
+/// The checkers of the loaded plugins.
+#[derive(Clone, Default)]
+pub struct PluginRegistry {
+    checkers: Vec<Arc<dyn Checker>>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a checker. A checker with the name of a checker already registered replaces it.
+    pub fn register_checker(&mut self, checker: Arc<dyn Checker>) {
+        self.checkers.retain(|c| c.name() != checker.name());
+        self.checkers.push(checker);
+    }
+
+    /// Returns the names of the registered checkers.
+    pub fn checker_names(&self) -> impl Iterator<Item = &str> {
+        self.checkers.iter().map(|c| c.name())
+    }
+
+    /// Returns the names of the registered checkers with their digest.
+    pub fn checker_digests(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.checkers.iter().map(|c| (c.name(), c.digest()))
+    }
+
+    /// Returns whether a checker analyzes the files of the language.
+    pub fn has_checkers(&self, language: Language) -> bool {
+        self.checkers
+            .iter()
+            .any(|c| c.languages().contains(&language))
+    }
+
+    /// Returns the languages analyzed by at least one checker.
+    pub fn languages(&self) -> Vec<Language> {
+        let mut languages = vec![];
+        for language in self.checkers.iter().flat_map(|c| c.languages()) {
+            if !languages.contains(language) {
+                languages.push(*language);
+            }
+        }
+        languages
+    }
+
+    /// Executes the checkers of the language that apply to the file (`applies` is called with the
+    /// name of each checker). As for the rules, the violations disabled by a `no-dd-sa` comment
+    /// are removed, and the violations get a fingerprint.
+    pub fn check_file(
+        &self,
+        language: Language,
+        filename: &str,
+        code: &str,
+        applies: impl Fn(&str) -> bool,
+    ) -> Vec<RuleResult> {
+        let checkers = self
+            .checkers
+            .iter()
+            .filter(|c| c.languages().contains(&language) && applies(c.name()))
+            .collect::<Vec<_>>();
+        if checkers.is_empty() {
+            return vec![];
+        }
+        let lines_to_ignore = get_lines_to_ignore(code, &language);
+        let lines = code.lines().collect::<Vec<_>>();
+        checkers
+            .into_iter()
+            .map(|checker| {
+                let execution_time = Instant::now();
+                let (mut violations, execution_error) =
+                    match checker.check(language, filename, code) {
+                        Ok(violations) => (violations, None),
+                        Err(err) => (vec![], Some(err)),
+                    };
+                violations
+                    .retain(|v| !lines_to_ignore.should_filter_rule(checker.name(), v.start.line));
+                for violation in &mut violations {
+                    violation.fingerprint = (violation.start.line as usize)
+                        .checked_sub(1)
+                        .and_then(|idx| lines.get(idx))
+                        .map(|line| compute_fingerprint(checker.name(), filename, line));
+                }
+                RuleResult {
+                    rule_name: checker.name().into(),
+                    filename: filename.into(),
+                    violations,
+                    errors: vec![],
+                    execution_error,
+                    output: None,
+                    execution_time_ms: execution_time.elapsed().as_millis(),
+                    parsing_time_ms: 0,
+                    query_node_time_ms: 0,
+                    degraded: false,
+                    profile: None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::common::Position;
+    use crate::model::rule::{RuleCategory, RuleSeverity};
+    use crate::model::violation::ViolationBuilder;
+
+    /// Reports the lines that contain `TODO`.
+    struct TodoChecker;
+
+    impl Checker for TodoChecker {
+        fn name(&self) -> &str {
+            "plugin/todo"
+        }
+
+        fn languages(&self) -> &[Language] {
+            &[Language::Python]
+        }
+
+        fn digest(&self) -> &str {
+            "todo-v1"
+        }
+
+        fn check(&self, _: Language, _: &str, code: &str) -> Result<Vec<Violation>, String> {
+            if code.contains("FAIL") {
+                return Err("cannot check the file".to_string());
+            }
+            Ok(code
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.contains("TODO"))
+                .map(|(idx, line)| {
+                    ViolationBuilder::default()
+                        .start(Position {
+                            line: idx as u32 + 1,
+                            col: 1,
+                        })
+                        .end(Position {
+                            line: idx as u32 + 1,
+                            col: line.len() as u32 + 1,
+                        })
+                        .message("TODO".to_string())
+                        .severity(RuleSeverity::Notice)
+                        .category(RuleCategory::BestPractices)
+                        .fixes(vec![])
+                        .build()
+                        .unwrap()
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn check_files() {
+        let mut registry = PluginRegistry::new();
+        registry.register_checker(Arc::new(TodoChecker));
+        assert!(registry.has_checkers(Language::Python));
+        assert!(!registry.has_checkers(Language::Go));
+
+        let code = "# TODO\nx = 1\ny = \"TODO\"  # no-dd-sa\n";
+        let results = registry.check_file(Language::Python, "a.py", code, |_| true);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].rule_name, "plugin/todo");
+        assert_eq!(results[0].violations.len(), 1);
+        assert_eq!(results[0].violations[0].start.line, 1);
+        assert!(results[0].violations[0].fingerprint.is_some());
+
+        // the checker doesn't apply to the file
+        assert!(registry
+            .check_file(Language::Python, "a.py", code, |_| false)
+            .is_empty());
+        // the error of the checker is the execution error of the rule
+        let results = registry.check_file(Language::Python, "a.py", "FAIL", |_| true);
+        assert_eq!(
+            results[0].execution_error.as_deref(),
+            Some("cannot check the file")
+        );
+    }
+}
@@ -7,6 +7,9 @@ use std::collections::HashMap;
 pub struct RuleOverrides {
     severities: HashMap<String, RuleSeverity>,
     categories: HashMap<String, RuleCategory>,
+    // Whether generated files are analyzed, per rule and per ruleset.
+    rule_generated_files: HashMap<String, bool>,
+    ruleset_generated_files: HashMap<String, bool>,
 }
 
 impl RuleOverrides {
@@ -34,9 +37,29 @@ impl RuleOverrides {
                 })
             })
             .collect();
+        let rule_generated_files: HashMap<String, bool> = cfg
+            .rulesets
+            .iter()
+            .flat_map(|(rs_name, cfg)| {
+                cfg.rules.iter().filter_map(move |(rule_name, rule)| {
+                    rule.analyze_generated_files
+                        .map(|analyze| (format!("{}/{}", rs_name, rule_name), analyze))
+                })
+            })
+            .collect();
+        let ruleset_generated_files: HashMap<String, bool> = cfg
+            .rulesets
+            .iter()
+            .filter_map(|(rs_name, cfg)| {
+                cfg.analyze_generated_files
+                    .map(|analyze| (rs_name.clone(), analyze))
+            })
+            .collect();
         RuleOverrides {
             severities,
             categories,
+            rule_generated_files,
+            ruleset_generated_files,
         }
     }
 
@@ -49,4 +72,18 @@ impl RuleOverrides {
     pub fn category(&self, rule_name: &str, original: RuleCategory) -> RuleCategory {
         *self.categories.get(rule_name).unwrap_or(&original)
     }
+
+    // Returns whether the given rule analyzes generated files. The configuration of the rule takes
+    // precedence over the configuration of its ruleset.
+    pub fn analyzes_generated_files(&self, rule_name: &str) -> bool {
+        self.rule_generated_files
+            .get(rule_name)
+            .or_else(|| {
+                rule_name
+                    .split_once('/')
+                    .and_then(|(ruleset, _)| self.ruleset_generated_files.get(ruleset))
+            })
+            .copied()
+            .unwrap_or(false)
+    }
 }
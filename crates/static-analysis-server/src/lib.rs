@@ -1,4 +1,5 @@
 pub mod constants;
+pub mod metrics;
 pub mod model;
 pub mod request;
 pub mod tree_sitter_tree;
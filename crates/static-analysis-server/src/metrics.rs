@@ -0,0 +1,268 @@
+use crate::model::analysis_response::AnalysisResponse;
+use kernel::model::analysis::ERROR_RULE_TIMEOUT;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// The upper bounds (in seconds) of the buckets of the latency histograms.
+const LATENCY_BUCKETS_SECS: [f64; 12] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// A monotonically increasing counter.
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub fn inc_by(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn render(&self, name: &str, help: &str, out: &mut String) {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} counter");
+        let _ = writeln!(out, "{name} {}", self.get());
+    }
+}
+
+/// A histogram of durations, with the buckets of [`LATENCY_BUCKETS_SECS`].
+#[derive(Debug)]
+pub struct Histogram {
+    /// The number of observations in each bucket (not cumulative), the last one being `+Inf`.
+    buckets: [AtomicU64; LATENCY_BUCKETS_SECS.len() + 1],
+    sum_micros: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Histogram {
+    pub fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        let idx = LATENCY_BUCKETS_SECS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_SECS.len());
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(
+            u64::try_from(duration.as_micros()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// The total number of observations.
+    pub fn count(&self) -> u64 {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    fn render(&self, name: &str, help: &str, out: &mut String) {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        let mut cumulative = 0;
+        for (idx, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            match LATENCY_BUCKETS_SECS.get(idx) {
+                Some(bound) => {
+                    let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+                }
+                None => {
+                    let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {cumulative}");
+                }
+            }
+        }
+        let sum_secs = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{name}_sum {sum_secs}");
+        let _ = writeln!(out, "{name}_count {cumulative}");
+    }
+}
+
+/// The metrics of the analysis server, exposed in the Prometheus text format.
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    pub analysis_requests: Counter,
+    pub analysis_request_errors: Counter,
+    pub files_analyzed: Counter,
+    pub rule_timeouts: Counter,
+    pub rule_execution_errors: Counter,
+    pub rule_cache_hits: Counter,
+    pub rule_cache_misses: Counter,
+    pub analysis_duration: Histogram,
+    pub rule_execution_duration: Histogram,
+}
+
+impl ServerMetrics {
+    /// Records the outcome of an analysis request that took `duration` to process.
+    pub fn record_analysis(&self, response: &AnalysisResponse, duration: Duration) {
+        self.analysis_requests.inc();
+        if !response.errors.is_empty() {
+            self.analysis_request_errors.inc();
+        }
+        if !response.rule_responses.is_empty() {
+            self.files_analyzed.inc();
+        }
+        for rule_response in &response.rule_responses {
+            self.record_rule_execution(
+                rule_response.execution_time_ms,
+                &rule_response.errors,
+                rule_response.execution_error.is_some(),
+            );
+        }
+        self.analysis_duration.observe(duration);
+    }
+
+    /// Records the execution of a rule on a file, with the errors it reported and whether it failed.
+    pub fn record_rule_execution(&self, execution_time_ms: u128, errors: &[String], failed: bool) {
+        let execution_time_ms = u64::try_from(execution_time_ms).unwrap_or(u64::MAX);
+        self.rule_execution_duration
+            .observe(Duration::from_millis(execution_time_ms));
+        if errors.iter().any(|e| e == ERROR_RULE_TIMEOUT) {
+            self.rule_timeouts.inc();
+        } else if failed {
+            self.rule_execution_errors.inc();
+        }
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.analysis_requests.render(
+            "static_analyzer_analysis_requests_total",
+            "The number of analysis requests received.",
+            &mut out,
+        );
+        self.analysis_request_errors.render(
+            "static_analyzer_analysis_request_errors_total",
+            "The number of analysis requests that were rejected with an error.",
+            &mut out,
+        );
+        self.files_analyzed.render(
+            "static_analyzer_files_analyzed_total",
+            "The number of files analyzed with at least one rule.",
+            &mut out,
+        );
+        self.rule_timeouts.render(
+            "static_analyzer_rule_timeouts_total",
+            "The number of rule executions that timed out.",
+            &mut out,
+        );
+        self.rule_execution_errors.render(
+            "static_analyzer_rule_execution_errors_total",
+            "The number of rule executions that failed with an error.",
+            &mut out,
+        );
+        self.rule_cache_hits.render(
+            "static_analyzer_rule_cache_hits_total",
+            "The number of rulesets that were found in the cache of compiled rules.",
+            &mut out,
+        );
+        self.rule_cache_misses.render(
+            "static_analyzer_rule_cache_misses_total",
+            "The number of rulesets that had to be fetched and compiled.",
+            &mut out,
+        );
+        self.analysis_duration.render(
+            "static_analyzer_analysis_duration_seconds",
+            "The time taken to process an analysis request.",
+            &mut out,
+        );
+        self.rule_execution_duration.render(
+            "static_analyzer_rule_execution_duration_seconds",
+            "The time taken to execute a rule on a file.",
+            &mut out,
+        );
+        out
+    }
+}
+
+/// Returns the metrics of the server.
+pub fn metrics() -> &'static ServerMetrics {
+    static METRICS: OnceLock<ServerMetrics> = OnceLock::new();
+    METRICS.get_or_init(ServerMetrics::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::analysis_response::RuleResponse;
+
+    fn rule_response(execution_time_ms: u128, errors: Vec<String>) -> RuleResponse {
+        RuleResponse {
+            identifier: "myrule".to_string(),
+            violations: vec![],
+            errors,
+            execution_error: None,
+            output: None,
+            execution_time_ms,
+            degraded: false,
+        }
+    }
+
+    #[test]
+    fn histogram_buckets() {
+        let histogram = Histogram::default();
+        histogram.observe(Duration::from_millis(3));
+        histogram.observe(Duration::from_millis(5));
+        histogram.observe(Duration::from_secs(60));
+        assert_eq!(histogram.count(), 3);
+
+        let mut out = String::new();
+        histogram.render("latency", "help", &mut out);
+        assert!(out.contains("latency_bucket{le=\"0.001\"} 0\n"));
+        assert!(out.contains("latency_bucket{le=\"0.005\"} 2\n"));
+        assert!(out.contains("latency_bucket{le=\"10\"} 2\n"));
+        assert!(out.contains("latency_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("latency_sum 60.008\n"));
+        assert!(out.contains("latency_count 3\n"));
+    }
+
+    #[test]
+    fn record_analysis() {
+        let metrics = ServerMetrics::default();
+        let response = AnalysisResponse {
+            rule_responses: vec![
+                rule_response(10, vec![]),
+                rule_response(5000, vec![ERROR_RULE_TIMEOUT.to_string()]),
+            ],
+            errors: vec![],
+        };
+        metrics.record_analysis(&response, Duration::from_millis(20));
+        let response = AnalysisResponse {
+            rule_responses: vec![],
+            errors: vec!["code-not-base64".to_string()],
+        };
+        metrics.record_analysis(&response, Duration::from_millis(1));
+
+        assert_eq!(metrics.analysis_requests.get(), 2);
+        assert_eq!(metrics.analysis_request_errors.get(), 1);
+        assert_eq!(metrics.files_analyzed.get(), 1);
+        assert_eq!(metrics.rule_timeouts.get(), 1);
+        assert_eq!(metrics.rule_execution_errors.get(), 0);
+        assert_eq!(metrics.rule_execution_duration.count(), 2);
+        assert_eq!(metrics.analysis_duration.count(), 2);
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("# TYPE static_analyzer_analysis_requests_total counter\n"));
+        assert!(rendered.contains("static_analyzer_analysis_requests_total 2\n"));
+        assert!(
+            rendered.contains("# TYPE static_analyzer_rule_execution_duration_seconds histogram\n")
+        );
+    }
+}
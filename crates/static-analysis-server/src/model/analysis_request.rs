@@ -1,5 +1,7 @@
-use kernel::model::common::Language;
-use kernel::model::rule::{Argument, EntityChecked, RuleCategory, RuleSeverity, RuleType};
+use kernel::model::common::{ColumnUnit, Language};
+use kernel::model::rule::{
+    Argument, EntityChecked, NamedQuery, Prerequisite, RuleCategory, RuleSeverity, RuleType,
+};
 use serde::{Deserialize, Serialize};
 
 // This is a copy of the rule. We are just renaming the attribute to be
@@ -26,13 +28,20 @@ pub struct ServerRule {
     #[serde(rename = "tree_sitter_query")]
     pub tree_sitter_query_base64: Option<String>,
     #[serde(default)]
+    pub tree_sitter_queries: Vec<NamedQuery>,
+    #[serde(default)]
     pub arguments: Vec<Argument>,
+    #[serde(default)]
+    pub prerequisites: Vec<Prerequisite>,
 }
 
 #[derive(Clone, Deserialize, Debug, Serialize)]
 pub struct AnalysisRequestOptions {
     pub use_tree_sitter: Option<bool>,
     pub log_output: Option<bool>,
+    /// The unit of the columns of the positions in the response (default: bytes).
+    #[serde(default)]
+    pub column_unit: Option<ColumnUnit>,
 }
 
 #[derive(Clone, Deserialize, Debug, Serialize)]
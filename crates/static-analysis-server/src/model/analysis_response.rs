@@ -9,6 +9,9 @@ pub struct RuleResponse {
     pub execution_error: Option<String>,
     pub output: Option<String>,
     pub execution_time_ms: u128,
+    /// True if the file has syntax errors, so the violations may be incomplete.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
 }
 
 #[derive(Clone, Deserialize, Debug, Serialize)]
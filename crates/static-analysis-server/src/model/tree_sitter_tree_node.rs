@@ -17,10 +17,10 @@ pub struct ServerTreeSitterNode {
 impl From<TreeSitterNode> for ServerTreeSitterNode {
     fn from(value: TreeSitterNode) -> Self {
         ServerTreeSitterNode {
-            ast_type: value.ast_type,
+            ast_type: value.ast_type.into_owned(),
             start: value.start,
             end: value.end,
-            field_name: value.field_name,
+            field_name: value.field_name.map(|name| name.into_owned()),
             children: value
                 .children
                 .into_iter()
@@ -1,8 +1,8 @@
-use kernel::model::common::Position;
+use kernel::model::common::{Offsets, Position};
 use kernel::model::rule::{RuleCategory, RuleSeverity};
 
 use derive_builder::Builder;
-use kernel::model::violation::{Edit, EditType, Fix, Violation};
+use kernel::model::violation::{Edit, EditType, Fix, FixApplicability, RelatedLocation, Violation};
 use serde::{Deserialize, Serialize};
 
 /// because of our naming conventions that mix camelCase in the JS code
@@ -24,6 +24,9 @@ pub struct ServerEdit {
 pub struct ServerFix {
     pub description: String,
     pub edits: Vec<ServerEdit>,
+    #[builder(default)]
+    #[serde(default)]
+    pub applicability: FixApplicability,
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone, Builder)]
@@ -34,6 +37,27 @@ pub struct ServerViolation {
     pub severity: RuleSeverity,
     pub category: RuleCategory,
     pub fixes: Vec<ServerFix>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cwe: Vec<u32>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documentation_url: Option<String>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related_locations: Vec<RelatedLocation>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub file_level: bool,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offsets: Option<Offsets>,
 }
 
 /// Transform an edit from the kernel into an edit that is surfaced by the server.
@@ -51,6 +75,7 @@ pub fn fix_to_server(fix: &Fix) -> ServerFix {
     ServerFix {
         description: fix.description.clone(),
         edits: fix.edits.iter().map(edit_to_server).collect(),
+        applicability: fix.applicability,
     }
 }
 
@@ -59,9 +84,16 @@ pub fn violation_to_server(violation: &Violation) -> ServerViolation {
     ServerViolation {
         start: violation.start.clone(),
         end: violation.end.clone(),
-        message: violation.message.clone(),
+        message: violation.message.to_string(),
         severity: violation.severity,
         category: violation.category,
         fixes: violation.fixes.iter().map(fix_to_server).collect(),
+        tags: violation.tags.clone(),
+        cwe: violation.cwe.clone(),
+        documentation_url: violation.documentation_url.clone(),
+        related_locations: violation.related_locations.clone(),
+        file_level: violation.file_level,
+        fingerprint: violation.fingerprint.clone(),
+        offsets: violation.offsets,
     }
 }
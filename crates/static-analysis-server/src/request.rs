@@ -2,6 +2,7 @@ use crate::constants::{
     ERROR_CHECKSUM_MISMATCH, ERROR_CODE_LANGUAGE_MISMATCH, ERROR_CODE_NOT_BASE64,
     ERROR_CONFIGURATION_NOT_BASE64, ERROR_COULD_NOT_PARSE_CONFIGURATION, ERROR_DECODING_BASE64,
 };
+use crate::metrics::metrics;
 use crate::model::analysis_request::{AnalysisRequest, ServerRule};
 use crate::model::analysis_response::{AnalysisResponse, RuleResponse};
 use crate::model::violation::violation_to_server;
@@ -13,9 +14,18 @@ use kernel::model::rule::{Rule, RuleCategory, RuleInternal, RuleSeverity};
 use kernel::path_restrictions::PathRestrictions;
 use kernel::rule_overrides::RuleOverrides;
 use kernel::utils::decode_base64_string;
+use std::collections::HashSet;
+use std::time::Instant;
 
 #[tracing::instrument(skip_all)]
 pub fn process_analysis_request(request: AnalysisRequest) -> AnalysisResponse {
+    let start = Instant::now();
+    let response = analyze_request(request);
+    metrics().record_analysis(&response, start.elapsed());
+    response
+}
+
+fn analyze_request(request: AnalysisRequest) -> AnalysisResponse {
     tracing::debug!("Processing analysis request");
 
     // Decode the configuration, if present.
@@ -111,9 +121,14 @@ pub fn process_analysis_request(request: AnalysisRequest) -> AnalysisResponse {
             checksum: r.checksum.clone().unwrap_or("".to_string()),
             pattern: r.pattern.clone(),
             tree_sitter_query_base64: r.tree_sitter_query_base64.clone(),
+            tree_sitter_queries: r.tree_sitter_queries.clone(),
             arguments: r.arguments.clone(),
             tests: vec![],
             is_testing: false,
+            deprecated: false,
+            replaced_by: None,
+            allow_side_effects: false,
+            prerequisites: r.prerequisites.clone(),
         })
         .collect();
 
@@ -189,21 +204,32 @@ pub fn process_analysis_request(request: AnalysisRequest) -> AnalysisResponse {
             use_debug: false,
             log_output: request
                 .options
+                .as_ref()
                 .map(|o| o.log_output.unwrap_or(false))
                 .unwrap_or(false),
             ignore_generated_files: false,
+            report_parse_errors: false,
+            column_unit: request
+                .options
+                .as_ref()
+                .and_then(|o| o.column_unit)
+                .unwrap_or_default(),
+            generated_files_rules: HashSet::new(),
+            profile_rules: false,
+            nondeterministic_rules: false,
         },
     );
 
     let rule_responses = rule_results
         .iter()
         .map(|rr| RuleResponse {
-            identifier: rr.rule_name.clone(),
+            identifier: rr.rule_name.to_string(),
             violations: rr.violations.iter().map(violation_to_server).collect(),
             errors: rr.errors.clone(),
             execution_error: rr.execution_error.clone(),
-            output: rr.output.clone(),
+            output: rr.output_text(),
             execution_time_ms: rr.execution_time_ms,
+            degraded: rr.degraded,
         })
         .collect();
 
@@ -253,7 +279,9 @@ mod tests {
                     checksum: Some("f546e49732dc071fd5da82e1a2d9bcf5cf9a824c3679d8b59237c4ba23340057".to_string()),
                     pattern: None,
                     tree_sitter_query_base64: Some("KGZ1bmN0aW9uX2RlZmluaXRpb24KICAgIG5hbWU6IChpZGVudGlmaWVyKSBAbmFtZQogIHBhcmFtZXRlcnM6IChwYXJhbWV0ZXJzKSBAcGFyYW1zCik=".to_string()),
+                    tree_sitter_queries: vec![],
                     arguments: vec![],
+                    prerequisites: vec![],
                 }
             ]
         };
@@ -286,7 +314,9 @@ mod tests {
                     checksum: Some("f546e49732dc071fd5da82e1a2d9bcf5cf9a824c36d8b59237c4ba23340057".to_string()),
                     pattern: None,
                     tree_sitter_query_base64: Some("KGZ1bmN0aW9uX2RlZmluaXRpb24KICAgIG5hbWU6IChpZGVudGlmaWVyKSBAbmFtZQogIHBhcmFtZXRlcnM6IChwYXJhbWV0ZXJzKSBAcGFyYW1zCik=".to_string()),
+                    tree_sitter_queries: vec![],
                     arguments: vec![],
+                    prerequisites: vec![],
                 }
             ]
         };
@@ -321,7 +351,9 @@ mod tests {
                     checksum: None,
                     pattern: None,
                     tree_sitter_query_base64: Some("KGZ1bmN0aW9uX2RlZmluaXRpb24KICAgIG5hbWU6IChpZGVudGlmaWVyKSBAbmFtZQogIHBhcmFtZXRlcnM6IChwYXJhbWV0ZXJzKSBAcGFyYW1zCik=".to_string()),
+                    tree_sitter_queries: vec![],
                     arguments: vec![],
+                    prerequisites: vec![],
                 }
             ]
         };
@@ -356,7 +388,9 @@ mod tests {
                     checksum: Some("1a1dd51c47738a19b073a20ffc16c1eb816a4a6ed05ffaa53c19db0caf036c0c".to_string()),
                     pattern: None,
                     tree_sitter_query_base64: Some("KGZ1bmN0aW9uX2RlZmluaXRpb24KICAgIG5hbWU6IChpZGVudGlmaWVyKSBAbmFtZQogIHBhcmFtZXRlcnM6IChwYXJhbWV0ZXJzKSBAcGFyYW1zCik=".to_string()),
+                    tree_sitter_queries: vec![],
                     arguments: vec![],
+                    prerequisites: vec![],
                 }
             ]
         };
@@ -391,7 +425,9 @@ mod tests {
                     checksum: None,
                     pattern: None,
                     tree_sitter_query_base64: Some("KGZ1bmN0aW9uX2RlZmluaXRpb24KICAgIG5hbWU6IChpZGVudGlmaWVyKSBAbmFtZQogIHBhcmFtZXRlcnM6IChwYXJhbWV0ZXJzKSBAcGFyYW1zCik=".to_string()),
+                    tree_sitter_queries: vec![],
                     arguments: vec![],
+                    prerequisites: vec![],
                 }
             ]
         };
@@ -418,7 +454,9 @@ mod tests {
             checksum: Some("f546e49732dc071fd5da82e1a2d9bcf5cf9a824c3679d8b59237c4ba23340057".to_string()),
             pattern: None,
             tree_sitter_query_base64: Some("KGZ1bmN0aW9uX2RlZmluaXRpb24KICAgIG5hbWU6IChpZGVudGlmaWVyKSBAbmFtZQogIHBhcmFtZXRlcnM6IChwYXJhbWV0ZXJzKSBAcGFyYW1zCik=".to_string()),
+            tree_sitter_queries: vec![],
             arguments: vec![],
+            prerequisites: vec![],
         };
         let mut request = AnalysisRequest {
             filename: "path/to/myfile.py".to_string(),
@@ -589,7 +627,9 @@ rulesets:
                 checksum: Some("f546e49732dc071fd5da82e1a2d9bcf5cf9a824c3679d8b59237c4ba23340057".to_string()),
                 pattern: None,
                 tree_sitter_query_base64: Some("KGZ1bmN0aW9uX2RlZmluaXRpb24KICAgIG5hbWU6IChpZGVudGlmaWVyKSBAbmFtZQogIHBhcmFtZXRlcnM6IChwYXJhbWV0ZXJzKSBAcGFyYW1zCik=".to_string()),
+                tree_sitter_queries: vec![],
                 arguments: vec![],
+                prerequisites: vec![],
             }],
         };
         let response = process_analysis_request(request.clone());
@@ -645,7 +685,9 @@ function visit(node, filename, code) {
                     checksum: Some("984ba37fbfdfa4245ed7922efd224365ec216e540647989ac5e8559624ba9be4".to_string()),
                     pattern: None,
                     tree_sitter_query_base64: Some("KGZ1bmN0aW9uX2RlZmluaXRpb24KICAgIG5hbWU6IChpZGVudGlmaWVyKSBAbmFtZQogIHBhcmFtZXRlcnM6IChwYXJhbWV0ZXJzKSBAcGFyYW1zCik=".to_string()),
+                    tree_sitter_queries: vec![],
                     arguments: vec![],
+                    prerequisites: vec![],
                 }
             ]
         };
@@ -686,7 +728,9 @@ function visit(node, filename, code) {
                     checksum: Some("984ba37fbfdfa4245ed7922efd224365ec216e540647989ac5e8559624ba9be4".to_string()),
                     pattern: None,
                     tree_sitter_query_base64: Some("KGZ1bmN0aW9uX2RlZmluaXRpb24KICAgIG5hbWU6IChpZGVudGlmaWVyKSBAbmFtZQogIHBhcmFtZXRlcnM6IChwYXJhbWV0ZXJzKSBAcGFyYW1zCik=".to_string()),
+                    tree_sitter_queries: vec![],
                     arguments: vec![],
+                    prerequisites: vec![],
                 }
             ]
         };